@@ -0,0 +1,489 @@
+//! Incremental backups built on checkpoints.
+//!
+//! Layout of a backup directory:
+//!
+//! ```text
+//! meta/<id>                       file list of backup <id>
+//! shared/<number>_<size>_<crc>.sst SSTs, stored once and shared by backups
+//! shared/<number>_<size>_<crc>.blob blob files, likewise
+//! private/<id>/...                MANIFEST, CURRENT and WALs of backup <id>
+//! ```
+//!
+//! A backup is taken by checkpointing the DB into a scratch directory inside
+//! the backup directory and moving its files into place, so SSTs travel by
+//! hard link whenever the DB and the backups share a filesystem. SSTs and
+//! blob files are immutable; one already stored under the same number, size
+//! and checksum is reused instead of being stored again. A backup exists
+//! once its `meta` file has been renamed into place; anything else left by
+//! an interrupted backup is garbage collected by the next
+//! [`BackupEngine::open`] or [`BackupEngine::purge_old_backups`].
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::db::DB;
+use crate::error::{Error, Result, ResultExt};
+
+const META_DIR: &str = "meta";
+const SHARED_DIR: &str = "shared";
+const PRIVATE_DIR: &str = "private";
+const SCRATCH_PREFIX: &str = "tmp-";
+const META_HEADER: &str = "mmdb-backup 1";
+
+/// Summary of one backup, as returned by [`BackupEngine::get_backup_info`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackupInfo {
+    pub backup_id: u32,
+    /// Creation time in seconds since the Unix epoch.
+    pub timestamp: u64,
+    /// Total size of the backup's files, shared ones included.
+    pub size: u64,
+    pub num_files: usize,
+}
+
+/// One file of a backup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct BackupFile {
+    /// Path relative to the DB directory, e.g. `cf-1/000012.sst`.
+    db_path: String,
+    /// Path relative to the backup directory.
+    stored: String,
+    size: u64,
+    crc: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct BackupMeta {
+    timestamp: u64,
+    files: Vec<BackupFile>,
+}
+
+impl BackupMeta {
+    fn encode(&self) -> String {
+        let mut out = format!("{}\ntimestamp {}\n", META_HEADER, self.timestamp);
+        for f in &self.files {
+            out.push_str(&format!(
+                "file {:08x} {} {} {}\n",
+                f.crc, f.size, f.stored, f.db_path
+            ));
+        }
+        out
+    }
+
+    fn decode(text: &str) -> Result<Self> {
+        let bad = |line: &str| Error::corruption(format!("malformed backup meta line {:?}", line));
+        let mut lines = text.lines();
+        if lines.next() != Some(META_HEADER) {
+            return Err(Error::corruption("missing backup meta header"));
+        }
+        let timestamp_line = lines.next().unwrap_or_default();
+        let timestamp = timestamp_line
+            .strip_prefix("timestamp ")
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(|| bad(timestamp_line))?;
+        let mut files = Vec::new();
+        for line in lines {
+            let fields: Vec<&str> = line.split(' ').collect();
+            let [tag, crc, size, stored, db_path] = fields[..] else {
+                return Err(bad(line));
+            };
+            if tag != "file" {
+                return Err(bad(line));
+            }
+            files.push(BackupFile {
+                crc: u32::from_str_radix(crc, 16).map_err(|_| bad(line))?,
+                size: size.parse().map_err(|_| bad(line))?,
+                stored: stored.to_string(),
+                db_path: db_path.to_string(),
+            });
+        }
+        Ok(Self { timestamp, files })
+    }
+}
+
+/// Writes numbered, incremental backups of a [`DB`] into one directory and
+/// restores them.
+///
+/// ```no_run
+/// use mmdb::{BackupEngine, DB, DbOptions};
+///
+/// # fn main() -> mmdb::Result<()> {
+/// let db = DB::open(DbOptions::default(), "db")?;
+/// let mut backups = BackupEngine::open("backups")?;
+/// let id = backups.create_new_backup(&db)?;
+/// backups.purge_old_backups(3)?;
+/// backups.verify_backup(id)?;
+/// backups.restore_db_from_backup(id, "restored")?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct BackupEngine {
+    dir: PathBuf,
+    backups: BTreeMap<u32, BackupMeta>,
+}
+
+impl BackupEngine {
+    /// Open (creating if needed) the backup directory `dir` and collect
+    /// leftovers of interrupted backups.
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        for sub in [META_DIR, SHARED_DIR, PRIVATE_DIR] {
+            fs::create_dir_all(dir.join(sub)).ctx()?;
+        }
+        let mut backups = BTreeMap::new();
+        for entry in fs::read_dir(dir.join(META_DIR)).ctx()? {
+            let entry = entry.ctx()?;
+            let Some(id) = entry.file_name().to_str().and_then(|s| s.parse().ok()) else {
+                continue;
+            };
+            let text = fs::read_to_string(entry.path()).ctx()?;
+            let meta =
+                BackupMeta::decode(&text).with_ctx(|| format!("failed to load backup {}", id))?;
+            backups.insert(id, meta);
+        }
+        let engine = Self { dir, backups };
+        engine.garbage_collect().ctx()?;
+        Ok(engine)
+    }
+
+    /// Back up `db` and return the new backup's id. Only SSTs not already
+    /// stored by an earlier backup take up new space.
+    pub fn create_new_backup(&mut self, db: &DB) -> Result<u32> {
+        let id = self
+            .backups
+            .keys()
+            .next_back()
+            .map_or(Ok(1), |last| {
+                last.checked_add(1)
+                    .ok_or_else(|| Error::invalid_argument("backup ids exhausted"))
+            })
+            .ctx()?;
+        let scratch = self.dir.join(format!("{}{}", SCRATCH_PREFIX, id));
+        if scratch.exists() {
+            fs::remove_dir_all(&scratch).ctx()?;
+        }
+        let result = self.store_backup(db, id, &scratch);
+        let _ = fs::remove_dir_all(&scratch);
+        match result {
+            Ok(meta) => {
+                self.backups.insert(id, meta);
+                Ok(id)
+            }
+            Err(e) => {
+                let _ = fs::remove_dir_all(self.private_dir(id));
+                Err(e)
+            }
+        }
+    }
+
+    /// Every backup, oldest first.
+    pub fn get_backup_info(&self) -> Vec<BackupInfo> {
+        self.backups
+            .iter()
+            .map(|(&backup_id, meta)| BackupInfo {
+                backup_id,
+                timestamp: meta.timestamp,
+                size: meta.files.iter().map(|f| f.size).sum(),
+                num_files: meta.files.len(),
+            })
+            .collect()
+    }
+
+    /// Delete all but the `keep` newest backups, along with every shared
+    /// SST no remaining backup refers to.
+    pub fn purge_old_backups(&mut self, keep: usize) -> Result<()> {
+        let excess = self.backups.len().saturating_sub(keep);
+        let doomed: Vec<u32> = self.backups.keys().take(excess).copied().collect();
+        for id in doomed {
+            // The meta file goes first: without it the backup no longer
+            // exists and its files are garbage, even if removal stops here.
+            match fs::remove_file(self.meta_path(id)) {
+                Ok(()) => {}
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e).ctx(),
+            }
+            self.backups.remove(&id);
+        }
+        fsync_dir(&self.dir.join(META_DIR)).ctx()?;
+        self.garbage_collect().ctx()
+    }
+
+    /// Check that every file of backup `id` is present with the recorded
+    /// size and checksum.
+    pub fn verify_backup(&self, id: u32) -> Result<()> {
+        for file in &self.backup(id)?.files {
+            let (size, crc) = checksum_file(&self.dir.join(&file.stored), None)
+                .with_ctx(|| format!("backup {}: cannot read {}", id, file.stored))?;
+            check_file(id, file, size, crc).ctx()?;
+        }
+        Ok(())
+    }
+
+    /// Restore backup `id` into `target_dir`, which must not exist or be
+    /// empty. The result can be opened with [`DB::open`]. Checksums are
+    /// verified while copying.
+    pub fn restore_db_from_backup(&self, id: u32, target_dir: impl AsRef<Path>) -> Result<()> {
+        let meta = self.backup(id)?;
+        let target = target_dir.as_ref();
+        if target.exists() && fs::read_dir(target).ctx()?.next().is_some() {
+            return Err(Error::invalid_argument(format!(
+                "restore target {} is not empty",
+                target.display()
+            )));
+        }
+        fs::create_dir_all(target).ctx()?;
+        let mut dirs = HashSet::new();
+        dirs.insert(target.to_path_buf());
+        for file in &meta.files {
+            let dst = target.join(&file.db_path);
+            if let Some(parent) = dst.parent()
+                && dirs.insert(parent.to_path_buf())
+            {
+                fs::create_dir_all(parent).ctx()?;
+            }
+            let (size, crc) = checksum_file(&self.dir.join(&file.stored), Some(&dst))
+                .with_ctx(|| format!("backup {}: cannot restore {}", id, file.db_path))?;
+            check_file(id, file, size, crc).ctx()?;
+        }
+        for dir in &dirs {
+            fsync_dir(dir).ctx()?;
+        }
+        Ok(())
+    }
+
+    fn backup(&self, id: u32) -> Result<&BackupMeta> {
+        self.backups
+            .get(&id)
+            .ok_or_else(|| Error::invalid_argument(format!("no backup with id {}", id)))
+    }
+
+    fn meta_path(&self, id: u32) -> PathBuf {
+        self.dir.join(META_DIR).join(id.to_string())
+    }
+
+    fn private_dir(&self, id: u32) -> PathBuf {
+        self.dir.join(PRIVATE_DIR).join(id.to_string())
+    }
+
+    /// Checkpoint `db` into `scratch`, move the files into the shared and
+    /// private stores, then publish the meta file.
+    fn store_backup(&self, db: &DB, id: u32, scratch: &Path) -> Result<BackupMeta> {
+        db.create_checkpoint(scratch).ctx()?;
+        let private = self.private_dir(id);
+        if private.exists() {
+            fs::remove_dir_all(&private).ctx()?;
+        }
+        let mut files = Vec::new();
+        let mut dirs = vec![self.dir.join(SHARED_DIR), self.dir.join(PRIVATE_DIR)];
+        for db_path in list_files(scratch).ctx()? {
+            let src = scratch.join(&db_path);
+            let (size, crc) = checksum_file(&src, None).ctx()?;
+            let file_name = db_path.rsplit('/').next().unwrap_or(&db_path);
+            let shared = [".sst", ".blob"]
+                .into_iter()
+                .find_map(|ext| Some((file_name.strip_suffix(ext)?, ext)));
+            let stored = match shared {
+                Some((number, ext)) => {
+                    format!("{}/{}_{}_{:08x}{}", SHARED_DIR, number, size, crc, ext)
+                }
+                None => format!("{}/{}/{}", PRIVATE_DIR, id, db_path),
+            };
+            let dst = self.dir.join(&stored);
+            if !dst.exists() {
+                if let Some(parent) = dst.parent()
+                    && !dirs.iter().any(|d| d == parent)
+                {
+                    fs::create_dir_all(parent).ctx()?;
+                    dirs.push(parent.to_path_buf());
+                }
+                fs::rename(&src, &dst).ctx()?;
+            }
+            files.push(BackupFile {
+                db_path,
+                stored,
+                size,
+                crc,
+            });
+        }
+        for dir in &dirs {
+            fsync_dir(dir).ctx()?;
+        }
+
+        let meta = BackupMeta {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            files,
+        };
+        let meta_path = self.meta_path(id);
+        let tmp_path = meta_path.with_extension("tmp");
+        {
+            let mut writer = BufWriter::new(fs::File::create(&tmp_path).ctx()?);
+            writer.write_all(meta.encode().as_bytes()).ctx()?;
+            writer.flush().ctx()?;
+            writer.get_ref().sync_all().ctx()?;
+        }
+        fs::rename(&tmp_path, &meta_path).ctx()?;
+        fsync_dir(&self.dir.join(META_DIR)).ctx()?;
+        Ok(meta)
+    }
+
+    /// Remove scratch directories, stray meta temp files, and private
+    /// directories and shared SSTs that no backup refers to.
+    fn garbage_collect(&self) -> Result<()> {
+        let referenced: HashSet<&str> = self
+            .backups
+            .values()
+            .flat_map(|meta| meta.files.iter().map(|f| f.stored.as_str()))
+            .collect();
+        for entry in fs::read_dir(&self.dir).ctx()? {
+            let entry = entry.ctx()?;
+            if entry
+                .file_name()
+                .to_string_lossy()
+                .starts_with(SCRATCH_PREFIX)
+            {
+                remove_logged(&entry.path());
+            }
+        }
+        for entry in fs::read_dir(self.dir.join(META_DIR)).ctx()? {
+            let entry = entry.ctx()?;
+            if entry.file_name().to_string_lossy().ends_with(".tmp") {
+                remove_logged(&entry.path());
+            }
+        }
+        for entry in fs::read_dir(self.dir.join(PRIVATE_DIR)).ctx()? {
+            let entry = entry.ctx()?;
+            let live = entry
+                .file_name()
+                .to_str()
+                .and_then(|s| s.parse().ok())
+                .is_some_and(|id| self.backups.contains_key(&id));
+            if !live {
+                remove_logged(&entry.path());
+            }
+        }
+        for entry in fs::read_dir(self.dir.join(SHARED_DIR)).ctx()? {
+            let entry = entry.ctx()?;
+            let stored = format!("{}/{}", SHARED_DIR, entry.file_name().to_string_lossy());
+            if !referenced.contains(stored.as_str()) {
+                remove_logged(&entry.path());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Paths of all files under `root`, relative to it and `/`-separated.
+fn list_files(root: &Path) -> Result<Vec<String>> {
+    let mut files = Vec::new();
+    let mut pending = vec![String::new()];
+    while let Some(rel) = pending.pop() {
+        for entry in fs::read_dir(root.join(&rel)).ctx()? {
+            let entry = entry.ctx()?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let path = if rel.is_empty() {
+                name
+            } else {
+                format!("{}/{}", rel, name)
+            };
+            if entry.file_type().ctx()?.is_dir() {
+                pending.push(path);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Size and CRC32 of `path`, optionally copying it to `copy_to` (synced)
+/// along the way.
+fn checksum_file(path: &Path, copy_to: Option<&Path>) -> Result<(u64, u32)> {
+    let mut src = fs::File::open(path).ctx()?;
+    let mut dst = match copy_to {
+        Some(p) => Some(fs::File::create(p).ctx()?),
+        None => None,
+    };
+    let mut hasher = crc32fast::Hasher::new();
+    let mut size = 0u64;
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = src.read(&mut buf).ctx()?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        size += n as u64;
+        if let Some(dst) = dst.as_mut() {
+            dst.write_all(&buf[..n]).ctx()?;
+        }
+    }
+    if let Some(dst) = dst {
+        dst.sync_all().ctx()?;
+    }
+    Ok((size, hasher.finalize()))
+}
+
+fn check_file(id: u32, file: &BackupFile, size: u64, crc: u32) -> Result<()> {
+    if size != file.size || crc != file.crc {
+        return Err(Error::corruption(format!(
+            "backup {}: {} has size {} crc {:08x}, expected size {} crc {:08x}",
+            id, file.stored, size, crc, file.size, file.crc
+        )));
+    }
+    Ok(())
+}
+
+fn fsync_dir(dir: &Path) -> Result<()> {
+    fs::File::open(dir).ctx()?.sync_all().ctx()
+}
+
+fn remove_logged(path: &Path) {
+    let result = if path.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    };
+    if let Err(e) = result {
+        tracing::warn!("backup cleanup: failed to remove {}: {}", path.display(), e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backup_meta_roundtrip_and_rejects_garbage() {
+        let meta = BackupMeta {
+            timestamp: 1_700_000_000,
+            files: vec![
+                BackupFile {
+                    db_path: "cf-1/000012.sst".to_string(),
+                    stored: "shared/000012_4096_0badf00d.sst".to_string(),
+                    size: 4096,
+                    crc: 0x0bad_f00d,
+                },
+                BackupFile {
+                    db_path: "CURRENT".to_string(),
+                    stored: "private/3/CURRENT".to_string(),
+                    size: 16,
+                    crc: 7,
+                },
+            ],
+        };
+        assert_eq!(BackupMeta::decode(&meta.encode()).unwrap(), meta);
+
+        let truncated = meta.encode().replace("file 00000007 16", "file 00000007");
+        assert_eq!(
+            BackupMeta::decode(&truncated).unwrap_err().kind(),
+            crate::ErrorKind::Corruption
+        );
+        assert!(BackupMeta::decode("timestamp 1\n").is_err());
+    }
+}
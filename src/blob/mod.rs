@@ -0,0 +1,157 @@
+//! Blob files for key-value separation.
+//!
+//! With [`DbOptions::min_blob_size`](crate::DbOptions::min_blob_size) set,
+//! flush writes values at least that large to an append-only blob file and
+//! stores a [`BlobHandle`] in the SST entry instead (type
+//! [`ValueType::BlobIndex`](crate::types::ValueType::BlobIndex)). Compaction
+//! then moves only the small handle, not the value.
+//!
+//! File layout: an 8-byte magic header followed by back-to-back records of
+//! `value ++ crc32(value)` (LE). A record is addressed by its offset and
+//! value size; nothing else in the file is needed to read it.
+//!
+//! Blob files are tracked in the MANIFEST alongside SSTs. Each file's
+//! garbage (records no longer referenced by any SST) is accumulated through
+//! `VersionEdit`s; a file whose records are all garbage is deleted, and
+//! compaction relocates the live records of files past
+//! [`DbOptions::blob_garbage_collection_threshold`](crate::DbOptions::blob_garbage_collection_threshold).
+
+pub mod reader;
+pub mod writer;
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+
+use crate::error::{Error, Result};
+
+pub use reader::BlobFileReader;
+pub use writer::BlobFileWriter;
+
+/// Magic number at the start of every blob file ("MMDBBLOB").
+pub const BLOB_MAGIC: u64 = 0x4D4D_4442_424C_4F42;
+
+/// Size of the file header (the magic number).
+pub const BLOB_HEADER_SIZE: u64 = 8;
+
+/// Size of the per-record checksum trailer.
+pub const BLOB_RECORD_TRAILER_SIZE: u64 = 4;
+
+/// Path of blob file `number` inside `db_path`.
+pub fn blob_file_path(db_path: &Path, number: u64) -> PathBuf {
+    db_path.join(format!("{:06}.blob", number))
+}
+
+/// Location of one value inside a blob file: the payload stored in the SST
+/// in place of the value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlobHandle {
+    pub file_number: u64,
+    /// Offset of the record (value start) within the file.
+    pub offset: u64,
+    /// Value size in bytes, excluding the checksum trailer.
+    pub size: u64,
+}
+
+impl BlobHandle {
+    pub const ENCODED_LEN: usize = 24;
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(Self::ENCODED_LEN);
+        buf.extend_from_slice(&self.file_number.to_le_bytes());
+        buf.extend_from_slice(&self.offset.to_le_bytes());
+        buf.extend_from_slice(&self.size.to_le_bytes());
+        buf
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        if data.len() != Self::ENCODED_LEN {
+            return Err(Error::corruption(format!(
+                "blob handle: expected {} bytes, got {}",
+                Self::ENCODED_LEN,
+                data.len()
+            )));
+        }
+        let u64_at = |i: usize| u64::from_le_bytes(data[i..i + 8].try_into().unwrap());
+        Ok(Self {
+            file_number: u64_at(0),
+            offset: u64_at(8),
+            size: u64_at(16),
+        })
+    }
+
+    /// Bytes this record occupies in its blob file.
+    pub fn record_size(&self) -> u64 {
+        self.size + BLOB_RECORD_TRAILER_SIZE
+    }
+}
+
+/// A value stored in a blob file, read on first access and then cached.
+///
+/// Held by [`LazyValue::Blob`](crate::types::LazyValue::Blob); the `Arc` on
+/// the reader keeps the file open for as long as the value is reachable.
+pub struct BlobValue {
+    reader: Arc<BlobFileReader>,
+    handle: BlobHandle,
+    loaded: OnceLock<Result<Vec<u8>>>,
+}
+
+impl BlobValue {
+    pub fn new(reader: Arc<BlobFileReader>, handle: BlobHandle) -> Self {
+        Self {
+            reader,
+            handle,
+            loaded: OnceLock::new(),
+        }
+    }
+
+    pub fn handle(&self) -> &BlobHandle {
+        &self.handle
+    }
+
+    /// Read the value, hitting the file only on the first call.
+    pub fn load(&self) -> Result<&[u8]> {
+        match self.loaded.get_or_init(|| self.reader.read(&self.handle)) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_read_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut writer = BlobFileWriter::new(dir.path(), 7).unwrap();
+        let a = writer.add(b"hello").unwrap();
+        let b = writer.add(&[0xAB; 10_000]).unwrap();
+        let meta = writer.finish().unwrap();
+        assert_eq!(meta.number, 7);
+        assert_eq!(meta.blob_count, 2);
+        assert_eq!(meta.blob_bytes, a.record_size() + b.record_size());
+        assert_eq!(BlobHandle::decode(&b.encode()).unwrap(), b);
+
+        let reader = Arc::new(BlobFileReader::open(dir.path(), 7).unwrap());
+        assert_eq!(reader.read(&a).unwrap(), b"hello");
+        let value = BlobValue::new(reader.clone(), b);
+        assert_eq!(value.load().unwrap(), &[0xAB; 10_000][..]);
+
+        // A handle pointing into the middle of a record fails its checksum.
+        let bad = BlobHandle {
+            offset: a.offset + 1,
+            ..a
+        };
+        assert!(reader.read(&bad).is_err());
+    }
+
+    #[test]
+    fn test_unfinished_writer_removes_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut writer = BlobFileWriter::new(dir.path(), 3).unwrap();
+        writer.add(b"v").unwrap();
+        drop(writer);
+        assert!(!blob_file_path(dir.path(), 3).exists());
+    }
+}
@@ -0,0 +1,76 @@
+//! Blob file reader: random-access reads of single records.
+
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use parking_lot::Mutex;
+
+use crate::blob::{
+    BLOB_HEADER_SIZE, BLOB_MAGIC, BLOB_RECORD_TRAILER_SIZE, BlobHandle, blob_file_path,
+};
+use crate::error::{Error, Result, ResultExt};
+
+/// An open blob file.
+pub struct BlobFileReader {
+    number: u64,
+    file_size: u64,
+    file: Mutex<File>,
+}
+
+impl BlobFileReader {
+    /// Open blob file `number` in `db_path` and check its header.
+    pub fn open(db_path: &Path, number: u64) -> Result<Self> {
+        let path = blob_file_path(db_path, number);
+        let mut file = File::open(&path).ctx()?;
+        let file_size = file.metadata().ctx()?.len();
+        let mut magic = [0u8; BLOB_HEADER_SIZE as usize];
+        if file_size < BLOB_HEADER_SIZE || file.read_exact(&mut magic).is_err() {
+            return Err(Error::corruption(format!(
+                "blob file {}: truncated header",
+                path.display()
+            )));
+        }
+        if u64::from_le_bytes(magic) != BLOB_MAGIC {
+            return Err(Error::corruption(format!(
+                "blob file {}: bad magic number",
+                path.display()
+            )));
+        }
+        Ok(Self {
+            number,
+            file_size,
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Read and verify the record at `handle`.
+    pub fn read(&self, handle: &BlobHandle) -> Result<Vec<u8>> {
+        let end = handle
+            .offset
+            .checked_add(handle.record_size())
+            .filter(|&end| handle.offset >= BLOB_HEADER_SIZE && end <= self.file_size);
+        if handle.file_number != self.number || end.is_none() {
+            return Err(Error::corruption(format!(
+                "blob file {}: handle {:?} out of range (file size {})",
+                self.number, handle, self.file_size
+            )));
+        }
+        let mut buf = vec![0u8; handle.record_size() as usize];
+        {
+            let mut file = self.file.lock();
+            file.seek(SeekFrom::Start(handle.offset)).ctx()?;
+            file.read_exact(&mut buf).ctx()?;
+        }
+        let split = buf.len() - BLOB_RECORD_TRAILER_SIZE as usize;
+        let stored = u32::from_le_bytes(buf[split..].try_into().unwrap());
+        buf.truncate(split);
+        if crc32fast::hash(&buf) != stored {
+            return Err(Error::corruption(format!(
+                "blob file {}: checksum mismatch at offset {}",
+                self.number, handle.offset
+            )));
+        }
+        Ok(buf)
+    }
+}
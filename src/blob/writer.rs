@@ -0,0 +1,81 @@
+//! Blob file writer: appends values and hands back their handles.
+
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::blob::{BLOB_HEADER_SIZE, BLOB_MAGIC, BlobHandle, blob_file_path};
+use crate::error::{Result, ResultExt};
+use crate::manifest::version_edit::BlobFileMetaData;
+
+/// Writes one blob file. The file is removed on drop unless
+/// [`finish`](Self::finish) succeeded, so an aborted flush or compaction
+/// leaves nothing behind.
+pub struct BlobFileWriter {
+    path: PathBuf,
+    number: u64,
+    writer: BufWriter<File>,
+    offset: u64,
+    blob_count: u64,
+    finished: bool,
+}
+
+impl BlobFileWriter {
+    pub fn new(db_path: &Path, number: u64) -> Result<Self> {
+        let path = blob_file_path(db_path, number);
+        let file = OpenOptions::new()
+            .create_new(true)
+            .write(true)
+            .open(&path)
+            .ctx()?;
+        let mut writer = Self {
+            path,
+            number,
+            writer: BufWriter::new(file),
+            offset: 0,
+            blob_count: 0,
+            finished: false,
+        };
+        writer.writer.write_all(&BLOB_MAGIC.to_le_bytes()).ctx()?;
+        writer.offset = BLOB_HEADER_SIZE;
+        Ok(writer)
+    }
+
+    /// Append `value` and return the handle that locates it.
+    pub fn add(&mut self, value: &[u8]) -> Result<BlobHandle> {
+        let handle = BlobHandle {
+            file_number: self.number,
+            offset: self.offset,
+            size: value.len() as u64,
+        };
+        self.writer.write_all(value).ctx()?;
+        self.writer
+            .write_all(&crc32fast::hash(value).to_le_bytes())
+            .ctx()?;
+        self.offset += handle.record_size();
+        self.blob_count += 1;
+        Ok(handle)
+    }
+
+    /// Flush and fsync the file. The caller syncs the directory (through the
+    /// MANIFEST commit) before the file is referenced.
+    pub fn finish(mut self) -> Result<BlobFileMetaData> {
+        self.writer.flush().ctx()?;
+        self.writer.get_ref().sync_all().ctx()?;
+        self.finished = true;
+        Ok(BlobFileMetaData {
+            number: self.number,
+            file_size: self.offset,
+            blob_count: self.blob_count,
+            blob_bytes: self.offset - BLOB_HEADER_SIZE,
+        })
+    }
+}
+
+impl Drop for BlobFileWriter {
+    fn drop(&mut self) {
+        if !self.finished {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
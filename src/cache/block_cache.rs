@@ -157,6 +157,8 @@ pub struct BlockCachePool {
     /// When true (capacity 0), caching is disabled: inserts are no-ops and
     /// lookups always miss. Honors the documented "0 disables caching" option.
     disabled: bool,
+    /// Capacity the pool was created with.
+    capacity_bytes: u64,
 }
 
 impl BlockCachePool {
@@ -183,6 +185,7 @@ impl BlockCachePool {
             index,
             next_member: AtomicU64::new(0),
             disabled: capacity_bytes == 0,
+            capacity_bytes,
         }
     }
 
@@ -205,6 +208,16 @@ impl BlockCachePool {
     pub fn entry_count(&self) -> u64 {
         self.inner.entry_count()
     }
+
+    /// Approximate bytes held by the whole pool's LRU store.
+    pub fn usage_bytes(&self) -> u64 {
+        self.inner.weighted_size()
+    }
+
+    /// Capacity of the pool in bytes, shared by all members.
+    pub fn capacity_bytes(&self) -> u64 {
+        self.capacity_bytes
+    }
 }
 
 /// One DB's view of a [`BlockCachePool`]: the same five-method surface
@@ -441,6 +454,11 @@ impl BlockCache {
         }
     }
 
+    /// The pool this view belongs to.
+    pub(crate) fn pool(&self) -> &Arc<BlockCachePool> {
+        &self.pool
+    }
+
     /// Bytes currently pinned by this member.
     pub fn pinned_bytes(&self) -> u64 {
         self.pinned_bytes.load(Ordering::Relaxed)
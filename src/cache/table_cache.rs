@@ -9,6 +9,7 @@ use crate::cache::block_cache::BlockCache;
 use crate::error::{Result, ResultExt};
 use crate::sst::table_reader::TableReader;
 use crate::stats::DbStats;
+use crate::types::InternalKeyComparator;
 
 /// Cache for open TableReader instances.
 pub struct TableCache {
@@ -16,6 +17,7 @@ pub struct TableCache {
     inner: moka::sync::Cache<u64, Arc<TableReader>>,
     block_cache: Option<Arc<BlockCache>>,
     stats: Option<Arc<DbStats>>,
+    icmp: InternalKeyComparator,
 }
 
 impl TableCache {
@@ -24,15 +26,23 @@ impl TableCache {
     /// this cache; live Versions and iterators can pin additional readers.
     #[cfg(test)]
     pub fn new(db_path: &Path, max_open_files: u64, block_cache: Option<Arc<BlockCache>>) -> Self {
-        Self::new_with_stats(db_path, max_open_files, block_cache, None)
+        Self::new_with_stats(
+            db_path,
+            max_open_files,
+            block_cache,
+            None,
+            InternalKeyComparator::default(),
+        )
     }
 
-    /// Create a new table cache with optional stats.
+    /// Create a new table cache with optional stats. Every reader it opens
+    /// orders keys with `icmp`.
     pub fn new_with_stats(
         db_path: &Path,
         max_open_files: u64,
         block_cache: Option<Arc<BlockCache>>,
         stats: Option<Arc<DbStats>>,
+        icmp: InternalKeyComparator,
     ) -> Self {
         Self {
             db_path: db_path.to_path_buf(),
@@ -41,6 +51,7 @@ impl TableCache {
                 .build(),
             block_cache,
             stats,
+            icmp,
         }
     }
 
@@ -52,10 +63,12 @@ impl TableCache {
         let db_path = self.db_path.clone();
         let block_cache = self.block_cache.clone();
         let stats = self.stats.clone();
+        let icmp = self.icmp.clone();
         self.inner
             .try_get_with(file_number, || {
                 let path = db_path.join(format!("{:06}.sst", file_number));
-                TableReader::open_with_all(&path, file_number, block_cache, stats).map(Arc::new)
+                TableReader::open_with_all(&path, file_number, block_cache, stats, icmp)
+                    .map(Arc::new)
             })
             .map_err(|e: Arc<crate::error::Error>| (*e).clone())
             .with_ctx(|| format!("table cache load failed for file {:06}", file_number))
@@ -81,6 +94,11 @@ impl TableCache {
         }
     }
 
+    /// The key ordering shared by every reader this cache opens.
+    pub fn comparator(&self) -> &InternalKeyComparator {
+        &self.icmp
+    }
+
     /// Evict a file from the cache.
     pub fn evict(&self, file_number: u64) {
         self.inner.invalidate(&file_number);
@@ -0,0 +1,46 @@
+//! Column family handles.
+//!
+//! A column family is an independent keyspace with its own memtable, levels,
+//! and [`DbOptions`](crate::DbOptions). All families of a [`DB`](crate::DB)
+//! share one WAL and one sequence space, so a single
+//! [`WriteBatch`](crate::WriteBatch) can update several of them atomically.
+
+use std::sync::Arc;
+
+/// Name of the implicit column family that plain `DB` methods operate on.
+pub const DEFAULT_COLUMN_FAMILY_NAME: &str = "default";
+
+/// Identifies a column family of an open [`DB`](crate::DB).
+///
+/// Handles are cheap to clone. A handle to a dropped family stays valid as
+/// a value, but every operation through it fails with
+/// [`ErrorKind::InvalidArgument`](crate::ErrorKind::InvalidArgument).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ColumnFamilyHandle {
+    pub(crate) id: u32,
+    pub(crate) name: Arc<str>,
+}
+
+impl ColumnFamilyHandle {
+    pub(crate) fn new(id: u32, name: &str) -> Self {
+        Self {
+            id,
+            name: Arc::from(name),
+        }
+    }
+
+    /// Handle of the default column family.
+    pub fn default_family() -> Self {
+        Self::new(0, DEFAULT_COLUMN_FAMILY_NAME)
+    }
+
+    /// Persistent id of the family. Ids are never reused after a drop.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Name the family was created with.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
@@ -0,0 +1,224 @@
+//! FIFO compaction strategy.
+//!
+//! Every file stays in L0, newest first. Compaction deletes the oldest files
+//! whole — once the files exceed `max_table_files_size`, or once their data
+//! is older than `ttl` — without reading them. With `allow_compaction`, the
+//! newest small L0 files are also merged into L0 to bound the file count.
+
+use std::collections::HashSet;
+
+use crate::compaction::leveled::{CompactionHint, CompactionTask};
+use crate::listener::CompactionReason;
+use crate::manifest::version::{TableFile, Version};
+use crate::options::DbOptions;
+
+pub struct FifoCompaction;
+
+impl FifoCompaction {
+    /// Pick a deletion if the budget or TTL is exceeded, otherwise an
+    /// intra-L0 merge if enabled and due. `in_flight` has the same meaning
+    /// as for `LeveledCompaction::pick_compaction`.
+    pub fn pick_compaction(
+        version: &Version,
+        options: &DbOptions,
+        in_flight: &HashSet<u64>,
+    ) -> Option<CompactionTask> {
+        Self::pick_deletion(version, options, in_flight)
+            .or_else(|| Self::pick_intra_l0(version, options, in_flight))
+    }
+
+    /// Read-triggered compaction does not apply: files never move.
+    pub fn pick_compaction_for_hint(
+        _version: &Version,
+        _hint: &CompactionHint,
+        _in_flight: &HashSet<u64>,
+    ) -> Option<CompactionTask> {
+        None
+    }
+
+    fn pick_deletion(
+        version: &Version,
+        options: &DbOptions,
+        in_flight: &HashSet<u64>,
+    ) -> Option<CompactionTask> {
+        let fifo = &options.fifo_compaction;
+        let files = version.level_files(0);
+
+        // Newest-first index of the first file to drop; every older file
+        // goes with it.
+        let mut keep = files.len();
+        let mut total: u64 = files.iter().map(|tf| tf.meta.file_size).sum();
+        while keep > 0 && total > fifo.max_table_files_size {
+            keep -= 1;
+            total -= files[keep].meta.file_size;
+        }
+        let mut reason = CompactionReason::FifoMaxSize;
+        if let Some(ttl) = fifo.ttl {
+            let now = options.clock.now_millis();
+            let ttl = u64::try_from(ttl.as_millis()).unwrap_or(u64::MAX);
+            // Files recorded without a creation time are never expired by
+            // age on their own, but go with any newer expired file.
+            if let Some(expired) = files.iter().position(|tf| {
+                tf.meta.creation_time != 0 && tf.meta.creation_time.saturating_add(ttl) <= now
+            }) && expired < keep
+            {
+                keep = expired;
+                reason = CompactionReason::FifoTtl;
+            }
+        }
+
+        let dropped = &files[keep..];
+        if dropped.is_empty() || dropped.iter().any(|tf| in_flight.contains(&tf.meta.number)) {
+            return None;
+        }
+        Some(Self::task(version, dropped.to_vec(), true, reason))
+    }
+
+    fn pick_intra_l0(
+        version: &Version,
+        options: &DbOptions,
+        in_flight: &HashSet<u64>,
+    ) -> Option<CompactionTask> {
+        if !options.fifo_compaction.allow_compaction {
+            return None;
+        }
+        // Only the newest files: merged output is installed as the newest
+        // L0 file, so it must not jump ahead of any file it leaves out.
+        let inputs: Vec<TableFile> = version
+            .level_files(0)
+            .iter()
+            .take_while(|tf| tf.meta.file_size < options.target_file_size_base)
+            .cloned()
+            .collect();
+        if inputs.len() < options.l0_compaction_trigger.max(2)
+            || inputs.iter().any(|tf| in_flight.contains(&tf.meta.number))
+        {
+            return None;
+        }
+        Some(Self::task(
+            version,
+            inputs,
+            false,
+            CompactionReason::FifoIntraL0,
+        ))
+    }
+
+    fn task(
+        version: &Version,
+        files: Vec<TableFile>,
+        delete_only: bool,
+        reason: CompactionReason,
+    ) -> CompactionTask {
+        CompactionTask {
+            level: 0,
+            input_files_level: files,
+            input_files_middle: Vec::new(),
+            input_files_next: Vec::new(),
+            output_level: 0,
+            delete_only,
+            blob_files: version.blob_files.clone(),
+            reason,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::time::Duration;
+
+    use super::FifoCompaction;
+    use crate::manifest::version_edit::{FileMetaData, VersionEdit};
+    use crate::manifest::version_set::VersionSet;
+    use crate::options::{Clock, DbOptions, FifoCompactionOptions};
+    use crate::sst::table_builder::{TableBuildOptions, TableBuilder};
+    use crate::types::{InternalKey, ValueType};
+
+    struct ManualClock(AtomicU64);
+
+    impl Clock for ManualClock {
+        fn now_millis(&self) -> u64 {
+            self.0.load(Ordering::Relaxed)
+        }
+    }
+
+    fn build_sst(dir: &std::path::Path, number: u64, creation_time: u64) -> FileMetaData {
+        let path = dir.join(format!("{number:06}.sst"));
+        let mut builder = TableBuilder::new(
+            &path,
+            TableBuildOptions {
+                internal_keys: true,
+                bloom_bits_per_key: 0,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let key = InternalKey::new(b"k", number, ValueType::Value);
+        builder.add(key.as_bytes(), &[7u8; 1024]).unwrap();
+        let result = builder.finish().unwrap();
+        FileMetaData {
+            number,
+            file_size: result.file_size,
+            smallest_key: result.smallest_key.unwrap(),
+            largest_key: result.largest_key.unwrap(),
+            has_range_deletions: result.has_range_deletions,
+            creation_time,
+        }
+    }
+
+    fn picked(task: &crate::compaction::leveled::CompactionTask) -> Vec<u64> {
+        let mut numbers: Vec<u64> = task
+            .input_files_level
+            .iter()
+            .map(|tf| tf.meta.number)
+            .collect();
+        numbers.sort();
+        numbers
+    }
+
+    #[test]
+    fn test_oldest_files_dropped_over_budget_and_ttl() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut versions = VersionSet::create(dir.path(), 7).unwrap();
+        let mut edit = VersionEdit::new();
+        for number in 1..=5 {
+            edit.add_file(0, build_sst(dir.path(), number, number * 1000));
+        }
+        versions.log_and_apply(edit).unwrap();
+        let version = versions.current();
+        let file_size = version.level_files(0)[0].meta.file_size;
+
+        let clock = Arc::new(ManualClock(AtomicU64::new(5000)));
+        let mut options = DbOptions {
+            clock: clock.clone(),
+            fifo_compaction: FifoCompactionOptions {
+                max_table_files_size: file_size * 3,
+                ttl: Some(Duration::from_secs(10)),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let task = FifoCompaction::pick_compaction(&version, &options, &HashSet::new()).unwrap();
+        assert!(task.delete_only);
+        assert_eq!(picked(&task), vec![1, 2]);
+        assert!(FifoCompaction::pick_compaction(&version, &options, &HashSet::from([1])).is_none());
+
+        // File 3 expires: it and everything older go, even within budget.
+        options.fifo_compaction.max_table_files_size = u64::MAX;
+        clock.0.store(13_000, Ordering::Relaxed);
+        let task = FifoCompaction::pick_compaction(&version, &options, &HashSet::new()).unwrap();
+        assert_eq!(picked(&task), vec![1, 2, 3]);
+
+        clock.0.store(5000, Ordering::Relaxed);
+        assert!(FifoCompaction::pick_compaction(&version, &options, &HashSet::new()).is_none());
+
+        // Intra-L0: the newest small files merge back into L0.
+        options.fifo_compaction.allow_compaction = true;
+        let task = FifoCompaction::pick_compaction(&version, &options, &HashSet::new()).unwrap();
+        assert!(!task.delete_only);
+        assert_eq!(task.output_level, 0);
+        assert_eq!(picked(&task), vec![1, 2, 3, 4, 5]);
+    }
+}
@@ -6,7 +6,7 @@
 //! After compaction, the old files are deleted and new files are installed.
 
 use std::cmp::Ordering as CmpOrdering;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::fs::remove_file;
 use std::path::Path;
 use std::sync::{
@@ -14,26 +14,31 @@ use std::sync::{
     atomic::{AtomicU64, Ordering},
 };
 use std::thread::scope;
+use std::time::{Duration, Instant};
 
 #[cfg(test)]
 use std::sync::atomic::AtomicUsize;
 
+use parking_lot::RwLock;
+
+use crate::blob::{BlobFileWriter, BlobHandle, blob_file_path};
 use crate::cache::block_cache::BlockCache;
 use crate::cache::table_cache::TableCache;
 use crate::error::{Error, Result, ResultExt};
 use crate::iterator::merge::{IterSource, MergingIterator};
 use crate::iterator::range_del::RangeTombstoneTracker;
-use crate::manifest::version::{TableFile, Version};
-use crate::manifest::version_edit::{FileMetaData, VersionEdit};
+use crate::listener::{CompactionJobInfo, CompactionReason, EventListener, TableFileDeletionInfo};
+use crate::manifest::version::{BlobFile, TableFile, Version};
+use crate::manifest::version_edit::{BlobFileMetaData, FileMetaData, VersionEdit};
 use crate::manifest::version_set::VersionSet;
-use crate::options::{CompactionFilterDecision, DbOptions};
+use crate::options::{CompactionFilterDecision, DbOptions, MergeOperator};
 use crate::rate_limiter::RateLimiter;
 use crate::sst::table_builder::{META_BLOCK_SPLIT_THRESHOLD, TableBuildOptions, TableBuilder};
 use crate::sst::table_reader::{MAX_DECOMPRESSED_BLOCK_SIZE, TableIterator};
-use crate::stats::DbStats;
+use crate::stats::{DbStats, LevelIo};
 use crate::types::{
-    InternalKey, LazyValue, MAX_SEQUENCE_NUMBER, SequenceNumber, ValueType, compare_internal_key,
-    decode_internal_key, tombstone_overlaps_bounds, user_key,
+    InternalKey, InternalKeyComparator, LazyValue, MAX_SEQUENCE_NUMBER, SequenceNumber, ValueType,
+    decode_internal_key, expiring_value_len, tombstone_overlaps_bounds, user_key,
 };
 
 /// Test-only instrumentation: incremented each time `execute_compaction_io`
@@ -58,6 +63,9 @@ pub(crate) struct CompactionContext<'a> {
     pub rate_limiter: Option<&'a Arc<RateLimiter>>,
     pub stats: Option<&'a Arc<DbStats>>,
     pub active_snapshots: &'a [SequenceNumber],
+    /// With user-defined timestamps, versions older than this timestamp are
+    /// no longer readable and may be collapsed.
+    pub full_history_ts_low: Option<&'a [u8]>,
 }
 
 /// Description of a compaction to perform.
@@ -66,8 +74,19 @@ pub struct CompactionTask {
     pub level: usize,
     /// Files from the source level.
     pub input_files_level: Vec<TableFile>,
-    /// Files from the target level (level + 1).
+    /// Files from levels strictly between `level` and `output_level`, with
+    /// their levels. Only universal compaction spans more than two levels.
+    pub input_files_middle: Vec<(usize, TableFile)>,
+    /// Files from the target level (`output_level`).
     pub input_files_next: Vec<TableFile>,
+    /// Level the output is written to: `level + 1` for leveled compaction.
+    pub output_level: usize,
+    /// Drop the input files instead of merging them (FIFO compaction).
+    pub delete_only: bool,
+    /// Blob files of the version the inputs were picked from.
+    pub blob_files: BTreeMap<u64, BlobFile>,
+    /// Why the task was picked, as reported to event listeners.
+    pub reason: CompactionReason,
 }
 
 /// Range tombstone user-key extents `[begin, end)` written to each output
@@ -76,6 +95,9 @@ pub struct CompactionTask {
 /// open or read an SST while the caller holds the DB lock.
 type OutputTombstones = HashMap<u64, Vec<(Vec<u8>, Vec<u8>)>>;
 
+/// Blob records dropped by a compaction, per blob file: (count, bytes).
+type BlobGarbage = BTreeMap<u64, (u64, u64)>;
+
 /// Result of the I/O phase of compaction (no lock needed to produce this).
 pub struct CompactionOutput {
     /// The version edit with new files added and old files deleted.
@@ -90,24 +112,104 @@ pub struct CompactionOutput {
     pub next_file_number_hint: u64,
     /// Range tombstone extents per output file, for the install precheck.
     output_tombstones: OutputTombstones,
+    /// Listener info for the completed job, carried through the install.
+    job: Option<CompactionJobInfo>,
+    /// I/O into the job's output level, recorded once installed.
+    level_io: LevelIo,
 }
 
 /// Deferred cleanup actions that must be performed AFTER the manifest is
 /// synced to disk. Executing these before sync risks data loss on crash.
+#[derive(Default)]
 pub struct PostCompactionCleanup {
     /// Old SST file numbers to delete from disk.
     pub files_to_delete: HashSet<u64>,
+    /// Blob files that became all garbage.
+    pub blob_files_to_delete: Vec<u64>,
+    /// The installed job, reported to listeners once the files are gone.
+    /// `None` when the output was discarded.
+    pub job: Option<CompactionJobInfo>,
 }
 
-impl CompactionTask {}
+impl CompactionTask {
+    /// Listener info for this task before it runs.
+    pub(crate) fn job_info(&self) -> CompactionJobInfo {
+        CompactionJobInfo {
+            reason: self.reason,
+            level: self.level,
+            output_level: self.output_level,
+            input_files: self.inputs().map(|(_, tf)| tf.meta.number).collect(),
+            output_files: Vec::new(),
+            bytes_read: self.inputs().map(|(_, tf)| tf.meta.file_size).sum(),
+            bytes_written: 0,
+            duration: Duration::ZERO,
+        }
+    }
+
+    /// I/O into the output level once the task wrote `written` bytes and
+    /// moved `moved` bytes there. Moved and deleted inputs are not read.
+    fn level_io(&self, written: u64, moved: u64) -> LevelIo {
+        let mut io = LevelIo {
+            compactions: 1,
+            written,
+            moved,
+            ..Default::default()
+        };
+        if self.delete_only || moved > 0 {
+            return io;
+        }
+        for (level, tf) in self.inputs() {
+            if level == self.output_level {
+                io.read_output += tf.meta.file_size;
+            } else {
+                io.read_upper += tf.meta.file_size;
+            }
+        }
+        io
+    }
+
+    /// Every input file with its level, source level first.
+    pub(crate) fn inputs(&self) -> impl Iterator<Item = (usize, &TableFile)> {
+        self.input_files_level
+            .iter()
+            .map(|tf| (self.level, tf))
+            .chain(
+                self.input_files_middle
+                    .iter()
+                    .map(|(level, tf)| (*level, tf)),
+            )
+            .chain(
+                self.input_files_next
+                    .iter()
+                    .map(|tf| (self.output_level, tf)),
+            )
+    }
+
+    /// Every input file, source level first.
+    pub(crate) fn all_input_files(&self) -> Vec<TableFile> {
+        self.inputs().map(|(_, tf)| tf.clone()).collect()
+    }
+}
 
 pub struct LeveledCompaction;
 
+/// Creation time for the output of merging `files`: the newest input's.
+fn newest_creation_time(files: &[TableFile]) -> u64 {
+    files
+        .iter()
+        .map(|tf| tf.meta.creation_time)
+        .max()
+        .unwrap_or(0)
+}
+
 /// Collect range tombstones from input files and return them as sorted
 /// internal-key entries suitable for injection into the merge iterator.
 /// This ensures tombstones from new-format SSTs (which store range
 /// deletions in a separate block) participate in the merge.
-fn collect_range_del_entries(files: &[TableFile]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+fn collect_range_del_entries(
+    files: &[TableFile],
+    icmp: &InternalKeyComparator,
+) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
     let mut entries = Vec::new();
     for tf in files {
         if tf.meta.has_range_deletions {
@@ -118,7 +220,7 @@ fn collect_range_del_entries(files: &[TableFile]) -> Result<Vec<(Vec<u8>, Vec<u8
             }
         }
     }
-    entries.sort_by(|a, b| compare_internal_key(&a.0, &b.0));
+    entries.sort_by(|a, b| icmp.compare(&a.0, &b.0));
     Ok(entries)
 }
 
@@ -134,14 +236,15 @@ fn file_metadata_overlaps_bounds(
     lower: Option<&[u8]>,
     upper: Option<&[u8]>,
 ) -> bool {
+    let icmp = tf.reader.comparator();
     let file_smallest = user_key(&tf.meta.smallest_key);
     let file_largest = user_key(&tf.meta.largest_key);
-    let above_lower = lower.is_none_or(|lo| file_largest >= lo);
-    let below_upper = upper.is_none_or(|hi| file_smallest < hi);
+    let above_lower = lower.is_none_or(|lo| icmp.user_le(lo, file_largest));
+    let below_upper = upper.is_none_or(|hi| icmp.user_lt(file_smallest, hi));
     above_lower && below_upper
 }
 
-fn file_overlaps_compact_bounds(
+pub(crate) fn file_overlaps_compact_bounds(
     tf: &TableFile,
     lower: Option<&[u8]>,
     upper: Option<&[u8]>,
@@ -153,9 +256,9 @@ fn file_overlaps_compact_bounds(
         return false;
     }
     match tf.reader.get_range_tombstones() {
-        Ok(tombstones) => tombstones
-            .iter()
-            .any(|(begin, end, _)| tombstone_overlaps_bounds(begin, end, lower, upper)),
+        Ok(tombstones) => tombstones.iter().any(|(begin, end, _)| {
+            tombstone_overlaps_bounds(tf.reader.comparator(), begin, end, lower, upper)
+        }),
         Err(e) => {
             tracing::warn!(
                 "failed to read range tombstones from SST {} while picking compact_range: {}",
@@ -197,27 +300,33 @@ fn add_file_extents(tf: &TableFile, extents: &mut Vec<UserKeyRange>) -> bool {
 }
 
 fn file_metadata_overlaps_extent(tf: &TableFile, extent: &UserKeyRange) -> bool {
+    let icmp = tf.reader.comparator();
     let file_smallest = user_key(&tf.meta.smallest_key);
     let file_largest = user_key(&tf.meta.largest_key);
     match extent {
         UserKeyRange::File { smallest, largest } => {
-            file_largest >= smallest.as_slice() && file_smallest <= largest.as_slice()
+            icmp.user_le(smallest, file_largest) && icmp.user_le(file_smallest, largest)
         }
         UserKeyRange::Tombstone { begin, end } => {
-            file_largest >= begin.as_slice() && file_smallest < end.as_slice()
+            icmp.user_le(begin, file_largest) && icmp.user_lt(file_smallest, end)
         }
     }
 }
 
-fn tombstone_overlaps_extent(begin: &[u8], end: &[u8], extent: &UserKeyRange) -> bool {
+fn tombstone_overlaps_extent(
+    icmp: &InternalKeyComparator,
+    begin: &[u8],
+    end: &[u8],
+    extent: &UserKeyRange,
+) -> bool {
     match extent {
         UserKeyRange::File { smallest, largest } => {
-            end > smallest.as_slice() && begin <= largest.as_slice()
+            icmp.user_lt(smallest, end) && icmp.user_le(begin, largest)
         }
         UserKeyRange::Tombstone {
             begin: other_begin,
             end: other_end,
-        } => end > other_begin.as_slice() && begin < other_end.as_slice(),
+        } => icmp.user_lt(other_begin, end) && icmp.user_lt(begin, other_end),
     }
 }
 
@@ -229,9 +338,9 @@ fn file_overlaps_extent(tf: &TableFile, extent: &UserKeyRange) -> bool {
         return false;
     }
     match tf.reader.get_range_tombstones() {
-        Ok(tombstones) => tombstones
-            .iter()
-            .any(|(begin, end, _)| tombstone_overlaps_extent(begin, end, extent)),
+        Ok(tombstones) => tombstones.iter().any(|(begin, end, _)| {
+            tombstone_overlaps_extent(tf.reader.comparator(), begin, end, extent)
+        }),
         Err(e) => {
             tracing::warn!(
                 "failed to read range tombstones from SST {} while checking overlap: {}",
@@ -243,6 +352,20 @@ fn file_overlaps_extent(tf: &TableFile, extent: &UserKeyRange) -> bool {
     }
 }
 
+/// Whether any of `files` (range tombstones included) touches a user key in
+/// `[smallest, largest]`.
+pub(crate) fn files_overlap_user_range(
+    files: &[TableFile],
+    smallest: &[u8],
+    largest: &[u8],
+) -> bool {
+    let extent = UserKeyRange::File {
+        smallest: smallest.to_vec(),
+        largest: largest.to_vec(),
+    };
+    files.iter().any(|tf| file_overlaps_extent(tf, &extent))
+}
+
 fn overlapping_files_for_inputs(files: &[TableFile], inputs: &[TableFile]) -> Vec<TableFile> {
     let mut extents = Vec::new();
     for tf in inputs {
@@ -266,12 +389,13 @@ fn overlapping_files_for_inputs(files: &[TableFile], inputs: &[TableFile]) -> Ve
             if tf.meta.smallest_key.is_empty() || tf.meta.largest_key.is_empty() {
                 continue;
             }
+            let icmp = tf.reader.comparator();
             let s = user_key(&tf.meta.smallest_key);
             let l = user_key(&tf.meta.largest_key);
-            if agg_smallest.is_none_or(|cur| s < cur) {
+            if agg_smallest.is_none_or(|cur| icmp.user_lt(s, cur)) {
                 agg_smallest = Some(s);
             }
-            if agg_largest.is_none_or(|cur| l > cur) {
+            if agg_largest.is_none_or(|cur| icmp.user_lt(cur, l)) {
                 agg_largest = Some(l);
             }
         }
@@ -357,6 +481,10 @@ struct SubCompactionParams<'a> {
     file_number_limit: u64,
     all_range_del_entries: &'a [(Vec<u8>, Vec<u8>)],
     all_raw_tombstones: &'a [(Vec<u8>, Vec<u8>, SequenceNumber)],
+    icmp: &'a InternalKeyComparator,
+    blob_files: &'a BTreeMap<u64, BlobFile>,
+    /// Creation time recorded on every output file.
+    creation_time: u64,
 }
 
 /// Output of a single sub-compaction (new files only; deletions handled by orchestrator).
@@ -364,6 +492,9 @@ struct SubCompactionOutput {
     new_files: Vec<(u32, FileMetaData)>,
     /// Range tombstone extents per output file (see `OutputTombstones`).
     output_tombstones: OutputTombstones,
+    /// Blob file holding relocated values, if any.
+    blob_file: Option<BlobFileMetaData>,
+    blob_garbage: BlobGarbage,
 }
 
 /// Compute split points from target-level file boundaries.
@@ -424,45 +555,29 @@ fn build_sub_tasks(task: &CompactionTask, split_points: &[Vec<u8>]) -> Vec<SubCo
         // Filter target-level files to those overlapping [lower, upper)
         let sub_next: Vec<TableFile> = next_files
             .iter()
-            .filter(|tf| {
-                let file_smallest = user_key(&tf.meta.smallest_key);
-                let file_largest = user_key(&tf.meta.largest_key);
-                let above_lower = match &lower {
-                    Some(lo) => file_largest >= lo.as_slice(),
-                    None => true,
-                };
-                let below_upper = match &upper {
-                    Some(hi) => file_smallest < hi.as_slice(),
-                    None => true,
-                };
-                above_lower && below_upper
-            })
+            .filter(|tf| file_metadata_overlaps_bounds(tf, lower.as_deref(), upper.as_deref()))
             .cloned()
             .collect();
 
         // For L0: all source files (they overlap arbitrarily).
-        // For Ln: only files overlapping this sub-range.
-        let sub_level: Vec<TableFile> = if task.level == 0 {
+        // For Ln: only files overlapping this sub-range. Files from
+        // intermediate levels are merged alongside the source files.
+        let mut sub_level: Vec<TableFile> = if task.level == 0 {
             task.input_files_level.clone()
         } else {
             task.input_files_level
                 .iter()
-                .filter(|tf| {
-                    let file_smallest = user_key(&tf.meta.smallest_key);
-                    let file_largest = user_key(&tf.meta.largest_key);
-                    let above_lower = match &lower {
-                        Some(lo) => file_largest >= lo.as_slice(),
-                        None => true,
-                    };
-                    let below_upper = match &upper {
-                        Some(hi) => file_smallest < hi.as_slice(),
-                        None => true,
-                    };
-                    above_lower && below_upper
-                })
+                .filter(|tf| file_metadata_overlaps_bounds(tf, lower.as_deref(), upper.as_deref()))
                 .cloned()
                 .collect()
         };
+        sub_level.extend(
+            task.input_files_middle
+                .iter()
+                .map(|(_, tf)| tf)
+                .filter(|tf| file_metadata_overlaps_bounds(tf, lower.as_deref(), upper.as_deref()))
+                .cloned(),
+        );
 
         sub_tasks.push(SubCompactionTask {
             lower_bound: lower,
@@ -536,6 +651,237 @@ fn collect_raw_tombstones(files: &[TableFile]) -> Result<Vec<RawTombstone>> {
     Ok(tombstones)
 }
 
+/// Result of folding a run of merge operands during compaction.
+struct MergeRun {
+    /// `Value` when the run resolved against a base (or the bottom of the
+    /// key's history), `Merge` when operands remain.
+    vt: ValueType,
+    /// Entry to write at the run's newest sequence number.
+    value: Vec<u8>,
+    /// Older operands that could not be partially merged into `value`,
+    /// newest first. Written verbatim as `Merge` entries after it.
+    older: Vec<(SequenceNumber, Vec<u8>)>,
+    /// Oldest sequence number consumed from the merge stream.
+    oldest_seq: SequenceNumber,
+}
+
+/// Rewrite an expired `ExpiringValue` as a `Deletion` at the same sequence
+/// number — which is how every reader already sees it — so the usual
+/// tombstone rules decide when it can be dropped. Other entries are left
+/// untouched.
+fn expire_entry(ikey: &mut Vec<u8>, value: &mut LazyValue, now_millis: u64) -> Result<()> {
+    let (user_key, seq, vt) = decode_internal_key(ikey).ctx()?;
+    if vt == ValueType::ExpiringValue && expiring_value_len(value.as_slice(), now_millis)?.is_none()
+    {
+        *ikey = InternalKey::new(user_key, seq, ValueType::Deletion).into_bytes();
+        *value = LazyValue::empty();
+    }
+    Ok(())
+}
+
+/// Blob bookkeeping for one compaction pass.
+///
+/// Every `BlobIndex` entry read from the inputs is counted as garbage of its
+/// blob file when consumed; [`keep`](Self::keep) takes the count back for an
+/// entry written out with the same handle. Entries that point into a file
+/// past `blob_garbage_collection_threshold` are instead relocated — inline
+/// when separation is off or the value is below `min_blob_size`, otherwise
+/// into a new blob file — so the old file eventually becomes all garbage and
+/// is deleted.
+struct BlobCompaction<'a> {
+    db_path: &'a Path,
+    files: &'a BTreeMap<u64, BlobFile>,
+    threshold: f64,
+    min_blob_size: Option<usize>,
+    garbage: BlobGarbage,
+    writer: Option<BlobFileWriter>,
+}
+
+impl<'a> BlobCompaction<'a> {
+    fn new(ctx: &CompactionContext<'a>, files: &'a BTreeMap<u64, BlobFile>) -> Self {
+        Self {
+            db_path: ctx.db_path,
+            files,
+            threshold: ctx.options.blob_garbage_collection_threshold,
+            min_blob_size: ctx.options.min_blob_size,
+            garbage: BTreeMap::new(),
+            writer: None,
+        }
+    }
+
+    /// Decode an input `BlobIndex` value and count its record as garbage.
+    fn consume(&mut self, value: &[u8]) -> Result<BlobHandle> {
+        let handle = BlobHandle::decode(value)?;
+        let entry = self.garbage.entry(handle.file_number).or_default();
+        entry.0 += 1;
+        entry.1 += handle.record_size();
+        Ok(handle)
+    }
+
+    fn file(&self, handle: &BlobHandle) -> Result<&'a BlobFile> {
+        self.files.get(&handle.file_number).ok_or_else(|| {
+            Error::corruption(format!(
+                "blob handle references missing blob file {}",
+                handle.file_number
+            ))
+        })
+    }
+
+    fn read(&self, handle: &BlobHandle) -> Result<Vec<u8>> {
+        self.file(handle)?.reader.read(handle)
+    }
+
+    /// Write out a consumed entry. Returns `None` to keep the entry as-is,
+    /// or the relocated `(type, value)` to write in its place.
+    fn keep(
+        &mut self,
+        handle: BlobHandle,
+        allocate_number: impl FnOnce() -> Result<u64>,
+    ) -> Result<Option<(ValueType, Vec<u8>)>> {
+        if self.file(&handle)?.garbage_ratio() <= self.threshold {
+            let entry = self.garbage.get_mut(&handle.file_number).unwrap();
+            entry.0 -= 1;
+            entry.1 -= handle.record_size();
+            return Ok(None);
+        }
+        let value = self.read(&handle)?;
+        match self.min_blob_size {
+            Some(min) if value.len() >= min => {
+                if self.writer.is_none() {
+                    self.writer = Some(BlobFileWriter::new(self.db_path, allocate_number()?)?);
+                }
+                let new_handle = self.writer.as_mut().unwrap().add(&value)?;
+                Ok(Some((ValueType::BlobIndex, new_handle.encode())))
+            }
+            _ => Ok(Some((ValueType::Value, value))),
+        }
+    }
+
+    /// Sync the relocation file (if any) and return it with the garbage
+    /// counted against the input blob files.
+    fn finish(self) -> Result<(Option<BlobFileMetaData>, BlobGarbage)> {
+        let blob_file = self.writer.map(BlobFileWriter::finish).transpose()?;
+        let garbage = self
+            .garbage
+            .into_iter()
+            .filter(|(_, (count, _))| *count > 0)
+            .collect();
+        Ok((blob_file, garbage))
+    }
+}
+
+fn remove_blob_files(db_path: &Path, files: &[BlobFileMetaData]) {
+    for meta in files {
+        let _ = remove_file(blob_file_path(db_path, meta.number));
+    }
+}
+
+/// Fold the `Merge` entry `(user_key, seq)` just taken from `merger` with the
+/// older versions of the same key that share its snapshot stripe (no active
+/// snapshot lies between them, so no reader can observe the intermediate
+/// states).
+///
+/// The run ends at the first base `Value`/`Deletion` (consumed, and folded
+/// in with `full_merge`), at an entry hidden by a range tombstone newer than
+/// it but not newer than `seq` (left in the stream; the run folds with no
+/// base), or where the stripe or key changes. An unresolved run at the
+/// bottommost level with no older versions left also folds with no base;
+/// otherwise the operands are combined with `partial_merge` where possible.
+#[allow(clippy::too_many_arguments)]
+fn collapse_merge_run<F: Fn(&[u8], &[u8]) -> CmpOrdering>(
+    merger: &mut MergingIterator<F>,
+    icmp: &InternalKeyComparator,
+    op: &dyn MergeOperator,
+    range_tombstones: &mut RangeTombstoneTracker,
+    active_snapshots: &[SequenceNumber],
+    is_bottommost: bool,
+    user_key: &[u8],
+    seq: SequenceNumber,
+    operand: Vec<u8>,
+) -> Result<MergeRun> {
+    let stripe = |s: SequenceNumber| active_snapshots.partition_point(|&snap| snap < s);
+    let run_stripe = stripe(seq);
+    let mut operands = vec![(seq, operand)];
+    let mut oldest_seq = seq;
+    // `Some(base)` once the run resolved; `base` is `None` for a deletion.
+    let mut resolved: Option<Option<Vec<u8>>> = None;
+    let mut older_versions = false;
+    while let Some((next_ikey, next_value)) = merger.peek_entry() {
+        let (next_uk, next_seq, next_vt) = decode_internal_key(next_ikey).ctx()?;
+        if next_uk != user_key {
+            // An older timestamp of the same key is an older version too.
+            older_versions = icmp.timestamp_size() > 0
+                && icmp.strip_timestamp(next_uk) == icmp.strip_timestamp(user_key);
+            break;
+        }
+        // An expiring base stays a separate entry: folding the run into it
+        // would either drop its expiry or make the operands expire with it.
+        // A blob base stays too, so its record is not read here.
+        if next_vt == ValueType::RangeDeletion
+            || next_vt == ValueType::ExpiringValue
+            || next_vt == ValueType::BlobIndex
+            || stripe(next_seq) != run_stripe
+        {
+            older_versions = true;
+            break;
+        }
+        if !range_tombstones.is_empty() && range_tombstones.is_deleted(user_key, next_seq, seq) {
+            resolved = Some(None);
+            break;
+        }
+        let next_value = next_value.to_vec();
+        oldest_seq = next_seq;
+        merger.advance_entry();
+        match next_vt {
+            ValueType::Value => {
+                resolved = Some(Some(next_value));
+                break;
+            }
+            ValueType::Deletion => {
+                resolved = Some(None);
+                break;
+            }
+            _ => operands.push((next_seq, next_value)),
+        }
+    }
+
+    if resolved.is_none() && is_bottommost && !older_versions {
+        resolved = Some(None);
+    }
+    if let Some(base) = resolved {
+        let values: Vec<&[u8]> = operands.iter().map(|(_, v)| v.as_slice()).collect();
+        let value = op
+            .full_merge_newest_first(user_key, base.as_deref(), &values)
+            .ctx()?;
+        return Ok(MergeRun {
+            vt: ValueType::Value,
+            value,
+            older: Vec::new(),
+            oldest_seq,
+        });
+    }
+
+    let mut rest = operands.split_off(1);
+    let (_, mut acc) = operands.pop().unwrap();
+    let mut combined = 0;
+    for (_, older) in &rest {
+        match op.partial_merge(user_key, older, &acc) {
+            Some(v) => {
+                acc = v;
+                combined += 1;
+            }
+            None => break,
+        }
+    }
+    rest.drain(..combined);
+    Ok(MergeRun {
+        vt: ValueType::Merge,
+        value: acc,
+        older: rest,
+        oldest_seq,
+    })
+}
+
 /// Execute a single sub-compaction covering [lower_bound, upper_bound).
 /// File numbers are allocated from a shared atomic counter to avoid collisions.
 /// `all_range_del_entries` contains range tombstone merge entries from ALL
@@ -580,11 +926,11 @@ fn execute_sub_compaction_io(
                 let start = user_key(ikey);
                 sub.lower_bound
                     .as_ref()
-                    .is_none_or(|lo| start >= lo.as_slice())
+                    .is_none_or(|lo| params.icmp.user_le(lo, start))
                     && sub
                         .upper_bound
                         .as_ref()
-                        .is_none_or(|hi| start < hi.as_slice())
+                        .is_none_or(|hi| params.icmp.user_lt(start, hi))
             })
             .cloned()
             .collect();
@@ -593,7 +939,7 @@ fn execute_sub_compaction_io(
         }
     }
 
-    let mut merger = MergingIterator::new(sources, compare_internal_key);
+    let mut merger = MergingIterator::new(sources, params.icmp.as_fn());
 
     // If lower_bound is set, seek past it
     if let Some(ref lo) = sub.lower_bound {
@@ -614,7 +960,7 @@ fn execute_sub_compaction_io(
     let mut last_range_del_key: Option<Vec<u8>> = None;
     let mut last_written_seq: SequenceNumber = 0;
     let mut snapshot_idx: usize = ctx.active_snapshots.len();
-    let mut range_tombstones = RangeTombstoneTracker::new();
+    let mut range_tombstones = RangeTombstoneTracker::with_comparator(params.icmp.clone());
     // Pre-populate the tracker with every tombstone that can cover a key in
     // this sub-task's range — in particular straddlers whose start key is
     // before lower_bound must still take effect. Tombstones entirely outside
@@ -623,6 +969,7 @@ fn execute_sub_compaction_io(
     // are skipped instead of cloned.
     for (begin, end, seq) in params.all_raw_tombstones {
         if tombstone_overlaps_bounds(
+            params.icmp,
             begin,
             end,
             sub.lower_bound.as_deref(),
@@ -632,9 +979,39 @@ fn execute_sub_compaction_io(
         }
     }
     range_tombstones.reset();
+    // Set while the newest kept version of the current key is a merge operand
+    // with no base folded in: older versions must then be retained even
+    // within the same snapshot stripe, because that operand needs them.
+    let mut merge_unresolved = false;
+    let now_millis = ctx.options.clock.now_millis();
+    let mut blobs = BlobCompaction::new(ctx, params.blob_files);
+    // Older operands of a partially merged run, written verbatim next.
+    let mut pending_operands: VecDeque<(Vec<u8>, LazyValue)> = VecDeque::new();
+    // With user-defined timestamps, the versions of one key are separate
+    // user keys. Once a base version older than `full_history_ts_low` is
+    // visible to every snapshot, no permitted read can see past it, so the
+    // key's remaining (older-timestamp) versions are dropped.
+    let mut collapsed_key: Option<Vec<u8>> = None;
+    let collapsible = |user_key: &[u8], seq: SequenceNumber| {
+        ctx.full_history_ts_low.is_some_and(|low| {
+            seq < params.oldest_snapshot_seq && params.icmp.split_timestamp(user_key).1 < low
+        })
+    };
 
-    while let Some((ikey, value)) = merger.next_entry() {
-        let (user_key, entry_seq, vt) = match decode_internal_key(&ikey) {
+    loop {
+        let (mut ikey, mut value, verbatim) = if let Some((k, v)) = pending_operands.pop_front() {
+            (k, v, true)
+        } else if let Some((k, v)) = merger.next_entry() {
+            (k, v, false)
+        } else {
+            break;
+        };
+        let expired = if verbatim {
+            Ok(())
+        } else {
+            expire_entry(&mut ikey, &mut value, now_millis)
+        };
+        let (user_key, entry_seq, vt) = match expired.and_then(|()| decode_internal_key(&ikey)) {
             Ok(decoded) => decoded,
             Err(e) => {
                 cleanup_output_files(
@@ -648,11 +1025,29 @@ fn execute_sub_compaction_io(
 
         // Check upper bound: stop if user_key >= upper_bound
         if let Some(ref hi) = sub.upper_bound
-            && user_key >= hi.as_slice()
+            && !params.icmp.user_lt(user_key, hi)
         {
             break;
         }
 
+        // Counted only past the upper-bound check: the entry that ends this
+        // sub-task is consumed again by the next one.
+        let blob_handle = if vt == ValueType::BlobIndex && !verbatim {
+            match blobs.consume(value.as_slice()) {
+                Ok(handle) => Some(handle),
+                Err(e) => {
+                    cleanup_output_files(
+                        ctx.db_path,
+                        &new_files,
+                        builder.as_ref().map(|_| current_file_number),
+                    );
+                    return Err(e);
+                }
+            }
+        } else {
+            None
+        };
+
         // Only cut output files at a user-key boundary: all versions of one user
         // key must stay in the same file, otherwise L1+ files would have
         // overlapping key ranges and a point read could pick the wrong file and
@@ -680,13 +1075,25 @@ fn execute_sub_compaction_io(
                     smallest_key: result.smallest_key.unwrap_or_default(),
                     largest_key: result.largest_key.unwrap_or_default(),
                     has_range_deletions: result.has_range_deletions,
+                    creation_time: params.creation_time,
                 },
             ));
             current_size = 0;
             pending_cut = false;
         }
 
-        if vt == ValueType::RangeDeletion {
+        if !verbatim
+            && vt != ValueType::RangeDeletion
+            && collapsed_key
+                .as_deref()
+                .is_some_and(|key| key == params.icmp.strip_timestamp(user_key))
+        {
+            continue;
+        }
+
+        if verbatim {
+            // Older operands of a run decided below; keep as-is.
+        } else if vt == ValueType::RangeDeletion {
             // Not re-added to `range_tombstones` here: `all_raw_tombstones`
             // already pre-populated the tracker with every tombstone in the
             // input set (including this one) before the loop started, so
@@ -707,7 +1114,9 @@ fn execute_sub_compaction_io(
             while snapshot_idx > 0 && ctx.active_snapshots[snapshot_idx - 1] >= last_written_seq {
                 snapshot_idx -= 1;
             }
-            if snapshot_idx > 0 && ctx.active_snapshots[snapshot_idx - 1] >= entry_seq {
+            if merge_unresolved
+                || (snapshot_idx > 0 && ctx.active_snapshots[snapshot_idx - 1] >= entry_seq)
+            {
                 last_written_seq = entry_seq;
                 // A retained older version that is shadowed by a range tombstone
                 // below the oldest snapshot must still be dropped. Otherwise, if
@@ -715,10 +1124,16 @@ fn execute_sub_compaction_io(
                 // value would resurrect for the snapshot that retained it. (The
                 // newest-version branch below already applies this check; retained
                 // versions need it too.)
-                if vt == ValueType::Value
-                    && !range_tombstones.is_empty()
+                if matches!(
+                    vt,
+                    ValueType::Value
+                        | ValueType::Merge
+                        | ValueType::ExpiringValue
+                        | ValueType::BlobIndex
+                ) && !range_tombstones.is_empty()
                     && range_tombstones.is_deleted(user_key, entry_seq, params.oldest_snapshot_seq)
                 {
+                    merge_unresolved = false;
                     continue;
                 }
             } else {
@@ -728,37 +1143,135 @@ fn execute_sub_compaction_io(
             last_point_key = Some(user_key.to_vec());
             last_written_seq = entry_seq;
             snapshot_idx = ctx.active_snapshots.len();
+            merge_unresolved = false;
 
             // Only a genuine Deletion may be dropped here, and only at the
             // bottommost level below the oldest snapshot. Type was validated
-            // by `decode_internal_key` above.
+            // by `decode_internal_key` above. With timestamps it also hides
+            // the key's older-timestamp versions, so it can only go together
+            // with them.
             if params.is_bottommost
                 && entry_seq < params.oldest_snapshot_seq
                 && vt == ValueType::Deletion
             {
-                continue;
+                if params.icmp.timestamp_size() == 0 {
+                    continue;
+                }
+                if collapsible(user_key, entry_seq) {
+                    collapsed_key = Some(params.icmp.strip_timestamp(user_key).to_vec());
+                    continue;
+                }
             }
 
-            if vt == ValueType::Value
-                && !range_tombstones.is_empty()
+            if matches!(
+                vt,
+                ValueType::Value
+                    | ValueType::Merge
+                    | ValueType::ExpiringValue
+                    | ValueType::BlobIndex
+            ) && !range_tombstones.is_empty()
                 && range_tombstones.is_deleted(user_key, entry_seq, params.oldest_snapshot_seq)
             {
                 continue;
             }
         }
 
-        // Apply compaction filter
         let mut final_value = value;
+        let mut out_vt = vt;
+        let mut out_ikey: Option<Vec<u8>> = None;
+        if vt == ValueType::Merge
+            && !verbatim
+            && let Some(op) = ctx.options.merge_operator.as_deref()
+        {
+            let run = match collapse_merge_run(
+                &mut merger,
+                params.icmp,
+                op,
+                &mut range_tombstones,
+                ctx.active_snapshots,
+                params.is_bottommost,
+                user_key,
+                entry_seq,
+                final_value.into_vec(),
+            ) {
+                Ok(run) => run,
+                Err(e) => {
+                    cleanup_output_files(
+                        ctx.db_path,
+                        &new_files,
+                        builder.as_ref().map(|_| current_file_number),
+                    );
+                    return Err(e);
+                }
+            };
+            last_written_seq = run.oldest_seq;
+            merge_unresolved = run.vt == ValueType::Merge;
+            if run.vt == ValueType::Value {
+                out_ikey = Some(InternalKey::new(user_key, entry_seq, run.vt).into_bytes());
+            }
+            out_vt = run.vt;
+            final_value = LazyValue::Inline(run.value);
+            for (seq, operand) in run.older {
+                pending_operands.push_back((
+                    InternalKey::new(user_key, seq, ValueType::Merge).into_bytes(),
+                    LazyValue::Inline(operand),
+                ));
+            }
+        } else if vt == ValueType::Merge {
+            merge_unresolved = true;
+        } else if vt != ValueType::RangeDeletion {
+            merge_unresolved = false;
+        }
+
+        // Apply compaction filter
         if params.is_bottommost
             && ctx.active_snapshots.is_empty()
             && let Some(ref filter) = ctx.options.compaction_filter
-            && vt == ValueType::Value
+            && matches!(out_vt, ValueType::Value | ValueType::BlobIndex)
         {
-            match filter.filter(params.target_level, user_key, final_value.as_slice()) {
+            // A separated value is filtered on its contents, not its handle.
+            let blob_value = match blob_handle.map(|h| blobs.read(&h)).transpose() {
+                Ok(v) => v,
+                Err(e) => {
+                    cleanup_output_files(
+                        ctx.db_path,
+                        &new_files,
+                        builder.as_ref().map(|_| current_file_number),
+                    );
+                    return Err(e);
+                }
+            };
+            let current = blob_value.as_deref().unwrap_or(final_value.as_slice());
+            match filter.filter(params.target_level, user_key, current) {
                 CompactionFilterDecision::Keep => {}
                 CompactionFilterDecision::Remove => continue,
                 CompactionFilterDecision::ChangeValue(new_val) => {
                     final_value = LazyValue::Inline(new_val);
+                    if out_vt == ValueType::BlobIndex {
+                        out_vt = ValueType::Value;
+                        out_ikey = Some(InternalKey::new(user_key, entry_seq, out_vt).into_bytes());
+                    }
+                }
+            }
+        }
+
+        if out_vt == ValueType::BlobIndex
+            && let Some(handle) = blob_handle
+        {
+            match blobs.keep(handle, || allocate_output_file_number(params)) {
+                Ok(None) => {}
+                Ok(Some((vt, relocated))) => {
+                    out_vt = vt;
+                    final_value = LazyValue::Inline(relocated);
+                    out_ikey = Some(InternalKey::new(user_key, entry_seq, out_vt).into_bytes());
+                }
+                Err(e) => {
+                    cleanup_output_files(
+                        ctx.db_path,
+                        &new_files,
+                        builder.as_ref().map(|_| current_file_number),
+                    );
+                    return Err(e);
                 }
             }
         }
@@ -794,12 +1307,12 @@ fn execute_sub_compaction_io(
         let ikey_ref = if params.is_bottommost
             && entry_seq > 0
             && entry_seq < params.oldest_snapshot_seq
-            && vt == ValueType::Value
+            && matches!(out_vt, ValueType::Value | ValueType::BlobIndex)
         {
-            final_ikey = InternalKey::new(user_key, 0, vt).as_bytes().to_vec();
+            final_ikey = InternalKey::new(user_key, 0, out_vt).as_bytes().to_vec();
             &final_ikey
         } else {
-            &ikey
+            out_ikey.as_ref().unwrap_or(&ikey)
         };
 
         let entry_bytes = ikey_ref.len() + final_value.len();
@@ -813,6 +1326,16 @@ fn execute_sub_compaction_io(
             return Err(e);
         }
         current_size += entry_bytes;
+        if matches!(
+            out_vt,
+            ValueType::Value
+                | ValueType::Deletion
+                | ValueType::ExpiringValue
+                | ValueType::BlobIndex
+        ) && collapsible(user_key, entry_seq)
+        {
+            collapsed_key = Some(params.icmp.strip_timestamp(user_key).to_vec());
+        }
         if current_file_user_key != user_key {
             current_file_user_key.clear();
             current_file_user_key.extend_from_slice(user_key);
@@ -857,6 +1380,7 @@ fn execute_sub_compaction_io(
                 smallest_key: result.smallest_key.unwrap_or_default(),
                 largest_key: result.largest_key.unwrap_or_default(),
                 has_range_deletions: result.has_range_deletions,
+                creation_time: params.creation_time,
             },
         ));
     }
@@ -870,9 +1394,19 @@ fn execute_sub_compaction_io(
         )));
     }
 
+    let (blob_file, blob_garbage) = match blobs.finish() {
+        Ok(finished) => finished,
+        Err(e) => {
+            cleanup_output_files(ctx.db_path, &new_files, None);
+            return Err(e);
+        }
+    };
+
     Ok(SubCompactionOutput {
         new_files,
         output_tombstones,
+        blob_file,
+        blob_garbage,
     })
 }
 
@@ -885,21 +1419,21 @@ impl LeveledCompaction {
             .target_file_size_base
             .max(1)
             .min((META_BLOCK_SPLIT_THRESHOLD / 2) as u64);
-        let estimated_input_size = task
-            .input_files_level
-            .iter()
-            .chain(task.input_files_next.iter())
-            .fold(0u64, |acc, tf| {
-                acc.saturating_add(estimated_uncompressed_file_size(tf))
-            });
+        let estimated_input_size = task.inputs().fold(0u64, |acc, (_, tf)| {
+            acc.saturating_add(estimated_uncompressed_file_size(tf))
+        });
         let size_outputs = estimated_input_size.saturating_add(target_size - 1) / target_size;
-        let input_count = task
-            .input_files_level
-            .len()
-            .saturating_add(task.input_files_next.len()) as u64;
+        let input_count = task.inputs().count() as u64;
+        // One blob file per sub-compaction for relocated values.
+        let blob_outputs = if task.blob_files.is_empty() {
+            0
+        } else {
+            options.max_subcompactions.max(1) as u64
+        };
         size_outputs
             .saturating_add(input_count)
             .saturating_add(options.max_subcompactions.max(1) as u64)
+            .saturating_add(blob_outputs)
             .saturating_add(16)
             .max(1)
     }
@@ -984,7 +1518,12 @@ impl LeveledCompaction {
         Some(CompactionTask {
             level: 0,
             input_files_level: input_l0,
+            input_files_middle: Vec::new(),
             input_files_next: input_l1,
+            output_level: 1,
+            delete_only: false,
+            blob_files: version.blob_files.clone(),
+            reason: CompactionReason::L0FileCount,
         })
     }
 
@@ -1034,7 +1573,12 @@ impl LeveledCompaction {
         Some(CompactionTask {
             level,
             input_files_level: input_level,
+            input_files_middle: Vec::new(),
             input_files_next: input_next,
+            output_level: level + 1,
+            delete_only: false,
+            blob_files: version.blob_files.clone(),
+            reason: CompactionReason::LevelMaxBytes,
         })
     }
 
@@ -1071,7 +1615,12 @@ impl LeveledCompaction {
             return Some(CompactionTask {
                 level: 0,
                 input_files_level: input_l0,
+                input_files_middle: Vec::new(),
                 input_files_next: input_l1,
+                output_level: 1,
+                delete_only: false,
+                blob_files: version.blob_files.clone(),
+                reason: CompactionReason::Manual,
             });
         }
 
@@ -1099,7 +1648,12 @@ impl LeveledCompaction {
                 return Some(CompactionTask {
                     level,
                     input_files_level: input_level,
+                    input_files_middle: Vec::new(),
                     input_files_next: input_next,
+                    output_level: level + 1,
+                    delete_only: false,
+                    blob_files: version.blob_files.clone(),
+                    reason: CompactionReason::Manual,
                 });
             }
         }
@@ -1115,6 +1669,8 @@ impl LeveledCompaction {
     /// When `options.max_subcompactions > 1` and the target level has enough
     /// files to split on, the work is divided into parallel sub-compactions
     /// using `std::thread::scope`.
+    ///
+    /// Reports the job to `options.listeners` as it begins.
     pub(crate) fn execute_compaction_io(
         ctx: &CompactionContext<'_>,
         task: &CompactionTask,
@@ -1122,7 +1678,77 @@ impl LeveledCompaction {
         file_number_limit: u64,
         is_bottommost: bool,
     ) -> Result<CompactionOutput> {
-        let target_level = task.level + 1;
+        let started = Instant::now();
+        let mut job = task.job_info();
+        for listener in &ctx.options.listeners {
+            listener.on_compaction_begin(&job);
+        }
+        let mut output = Self::write_compaction_outputs(
+            ctx,
+            task,
+            file_number_start,
+            file_number_limit,
+            is_bottommost,
+        )?;
+        job.output_files = output
+            .edit
+            .new_files
+            .iter()
+            .map(|(_, meta)| meta.number)
+            .collect();
+        // A trivial move's output is its input, relabeled: nothing written.
+        job.bytes_written = output
+            .edit
+            .new_files
+            .iter()
+            .filter(|(_, meta)| !job.input_files.contains(&meta.number))
+            .map(|(_, meta)| meta.file_size)
+            .sum();
+        let moved = output
+            .edit
+            .new_files
+            .iter()
+            .filter(|(_, meta)| job.input_files.contains(&meta.number))
+            .map(|(_, meta)| meta.file_size)
+            .sum();
+        output.level_io = task.level_io(job.bytes_written, moved);
+        job.duration = started.elapsed();
+        output.job = Some(job);
+        Ok(output)
+    }
+
+    fn write_compaction_outputs(
+        ctx: &CompactionContext<'_>,
+        task: &CompactionTask,
+        file_number_start: u64,
+        file_number_limit: u64,
+        is_bottommost: bool,
+    ) -> Result<CompactionOutput> {
+        let target_level = task.output_level;
+
+        // Deletion: the inputs leave the version unread. Like a trivial
+        // move, this still goes through `install_compaction`'s stale-input
+        // check.
+        if task.delete_only {
+            let mut edit = VersionEdit::new();
+            let input_files: Vec<(u32, FileMetaData)> = task
+                .inputs()
+                .map(|(level, f)| (level as u32, f.meta.clone()))
+                .collect();
+            for (level, meta) in &input_files {
+                edit.delete_file(*level, meta.number);
+            }
+            let input_file_numbers = input_files.iter().map(|(_, meta)| meta.number).collect();
+            return Ok(CompactionOutput {
+                edit,
+                input_files,
+                input_file_numbers,
+                next_file_number_hint: file_number_start,
+                output_tombstones: OutputTombstones::new(),
+                job: None,
+                level_io: LevelIo::default(),
+            });
+        }
 
         // Trivial move optimization: if there's exactly one input file and no
         // overlap with the next level, just move the metadata without
@@ -1136,6 +1762,7 @@ impl LeveledCompaction {
         // this function (including concurrent background workers) can reach
         // this path, not just the single-threaded `do_compaction`.
         if task.input_files_level.len() == 1
+            && task.input_files_middle.is_empty()
             && task.input_files_next.is_empty()
             && ctx
                 .options
@@ -1161,6 +1788,8 @@ impl LeveledCompaction {
                 // No new file numbers are consumed by a metadata-only move.
                 next_file_number_hint: file_number_start,
                 output_tombstones: OutputTombstones::new(),
+                job: None,
+                level_io: LevelIo::default(),
             });
         }
 
@@ -1178,6 +1807,7 @@ impl LeveledCompaction {
         } else {
             ctx.options.compression
         };
+        let icmp = InternalKeyComparator::new(&ctx.options.comparator);
         let build_opts = TableBuildOptions {
             block_size: ctx.options.block_size,
             block_restart_interval: ctx.options.block_restart_interval,
@@ -1186,6 +1816,7 @@ impl LeveledCompaction {
             compression: target_compression,
             prefix_len: ctx.options.prefix_len,
             block_property_collectors: Vec::new(),
+            comparator: icmp.clone(),
         };
 
         // Compute split points from target-level file boundaries.
@@ -1201,7 +1832,7 @@ impl LeveledCompaction {
         // included), so tombstone visibility stays complete for every key it
         // processes — the cross-sub-task sharing this data structure exists
         // for (INV-C2a) works correctly with `actual_subs > 1`.
-        let max_subs = if task.level == 0 {
+        let max_subs = if task.level == 0 || !task.input_files_middle.is_empty() {
             1
         } else {
             ctx.options.max_subcompactions.max(1)
@@ -1215,13 +1846,8 @@ impl LeveledCompaction {
         // Collect ALL range tombstones once from ALL input files.
         // These must be shared across all sub-tasks because a single range
         // tombstone can span multiple sub-compaction key ranges.
-        let all_input_files: Vec<TableFile> = task
-            .input_files_level
-            .iter()
-            .chain(task.input_files_next.iter())
-            .cloned()
-            .collect();
-        let all_range_del_entries = collect_range_del_entries(&all_input_files).ctx()?;
+        let all_input_files = task.all_input_files();
+        let all_range_del_entries = collect_range_del_entries(&all_input_files, &icmp).ctx()?;
         let all_raw_tombstones = collect_raw_tombstones(&all_input_files).ctx()?;
 
         // Shared atomic counter for thread-safe file number allocation
@@ -1236,6 +1862,9 @@ impl LeveledCompaction {
             file_number_limit,
             all_range_del_entries: &all_range_del_entries,
             all_raw_tombstones: &all_raw_tombstones,
+            icmp: &icmp,
+            blob_files: &task.blob_files,
+            creation_time: newest_creation_time(&all_input_files),
         };
 
         let sub_outputs = if actual_subs <= 1 {
@@ -1276,22 +1905,25 @@ impl LeveledCompaction {
                     .iter()
                     .flat_map(|o| o.new_files.iter().map(|(_, m)| m.number))
                     .collect();
-                // Clean up SST files from successful sub-compactions.
+                // Clean up SST and blob files from successful sub-compactions.
                 for sub_out in &outputs {
                     for (_, meta) in &sub_out.new_files {
                         let orphan = ctx.db_path.join(format!("{:06}.sst", meta.number));
                         let _ = remove_file(&orphan);
                     }
+                    remove_blob_files(ctx.db_path, sub_out.blob_file.as_slice());
                 }
                 // Clean up any orphaned SSTs from the panicked thread(s):
                 // file numbers were consumed from the shared counter but never
-                // reported, so delete any .sst file in the allocated range that
-                // is not accounted for in the successful outputs.
+                // reported, so delete any .sst (or relocation .blob) file in
+                // the allocated range that is not accounted for in the
+                // successful outputs.
                 let end = file_counter.load(Ordering::Acquire);
                 for num in file_number_start..end {
                     if !known.contains(&num) {
                         let orphan = ctx.db_path.join(format!("{:06}.sst", num));
                         let _ = remove_file(&orphan);
+                        let _ = remove_file(blob_file_path(ctx.db_path, num));
                     }
                 }
                 return Err(e);
@@ -1302,6 +1934,7 @@ impl LeveledCompaction {
         // Merge sub-compaction outputs
         let mut edit = VersionEdit::new();
         let mut output_tombstones = OutputTombstones::new();
+        let mut blob_garbage = BlobGarbage::new();
         for sub_out in sub_outputs {
             for file_entry in sub_out.new_files {
                 edit.new_files.push(file_entry);
@@ -1309,27 +1942,29 @@ impl LeveledCompaction {
             // File numbers come from a shared atomic counter, so per-sub maps
             // are disjoint and extend cannot collide.
             output_tombstones.extend(sub_out.output_tombstones);
+            if let Some(meta) = sub_out.blob_file {
+                edit.add_blob_file(meta);
+            }
+            for (number, (count, bytes)) in sub_out.blob_garbage {
+                let entry = blob_garbage.entry(number).or_default();
+                entry.0 += count;
+                entry.1 += bytes;
+            }
+        }
+        for (number, (count, bytes)) in blob_garbage {
+            edit.add_blob_garbage(number, count, bytes);
         }
 
         // Record deletions (orchestrator responsibility)
         let input_files: Vec<(u32, FileMetaData)> = task
-            .input_files_level
-            .iter()
-            .map(|f| (task.level as u32, f.meta.clone()))
-            .chain(
-                task.input_files_next
-                    .iter()
-                    .map(|f| (target_level as u32, f.meta.clone())),
-            )
+            .inputs()
+            .map(|(level, f)| (level as u32, f.meta.clone()))
             .collect();
         let input_file_numbers: HashSet<u64> =
             input_files.iter().map(|(_, meta)| meta.number).collect();
 
-        for tf in &task.input_files_level {
-            edit.delete_file(task.level as u32, tf.meta.number);
-        }
-        for tf in &task.input_files_next {
-            edit.delete_file(target_level as u32, tf.meta.number);
+        for (level, meta) in &input_files {
+            edit.delete_file(*level, meta.number);
         }
 
         Ok(CompactionOutput {
@@ -1338,6 +1973,8 @@ impl LeveledCompaction {
             input_file_numbers,
             next_file_number_hint: file_counter.load(Ordering::Relaxed),
             output_tombstones,
+            job: None,
+            level_io: LevelIo::default(),
         })
     }
 
@@ -1386,14 +2023,15 @@ impl LeveledCompaction {
                         .iter()
                         .any(|tf| tf.meta == *expected)
             });
-            stale || Self::outputs_overlap_unexpected_current_files(&output, &version)
+            stale
+                || Self::outputs_overlap_unexpected_current_files(&output, &version)
+                || Self::l0_outputs_not_newest(&output, &version)
         };
         if discard {
             cleanup_output_files(db_path, &created_files, None);
             evict_table_cache_files(table_cache, &created_files);
-            return Ok(PostCompactionCleanup {
-                files_to_delete: HashSet::new(),
-            });
+            remove_blob_files(db_path, &output.edit.new_blob_files);
+            return Ok(PostCompactionCleanup::default());
         }
 
         // Belt-and-braces: `allocate_output_file_number` enforces
@@ -1407,12 +2045,14 @@ impl LeveledCompaction {
         output
             .edit
             .set_next_file_number(versions.next_file_number());
+        let created_blob_files = output.edit.new_blob_files.clone();
         if let Err(e) = versions.log_and_apply(output.edit) {
             // Delete only the freshly-written SSTs (otherwise they would be
             // orphaned on disk) — `created_files` excludes a trivial move's
             // still-live input file.
             cleanup_output_files(db_path, &created_files, None);
             evict_table_cache_files(table_cache, &created_files);
+            remove_blob_files(db_path, &created_blob_files);
             return Err(e).ctx();
         }
 
@@ -1427,12 +2067,17 @@ impl LeveledCompaction {
         }
 
         if let Some(s) = stats {
-            s.record_compaction_completed();
+            s.record_compaction_completed(output.job.as_ref().map(|job| job.duration));
+            if let Some(job) = &output.job {
+                s.record_level_io(job.output_level, &output.level_io);
+            }
         }
 
         // Return the file numbers that need deletion AFTER manifest sync
         Ok(PostCompactionCleanup {
             files_to_delete: output.input_file_numbers,
+            blob_files_to_delete: versions.take_obsolete_blob_files(),
+            job: output.job,
         })
     }
 
@@ -1446,6 +2091,28 @@ impl LeveledCompaction {
     /// opened or read here. Current-version files are checked through their
     /// long-lived readers, whose range tombstones are cached after first
     /// access (same pattern the pick phase uses).
+    /// Outputs written back to L0 (FIFO intra-L0 compaction) are installed
+    /// as the newest L0 files, which is only right if no L0 file newer than
+    /// the inputs was added while they were being merged.
+    fn l0_outputs_not_newest(output: &CompactionOutput, version: &Version) -> bool {
+        if !output.edit.new_files.iter().any(|(level, _)| *level == 0) {
+            return false;
+        }
+        let inputs: HashSet<u64> = output
+            .input_files
+            .iter()
+            .filter(|(level, _)| *level == 0)
+            .map(|(_, meta)| meta.number)
+            .collect();
+        let Some(&oldest_input) = inputs.iter().min() else {
+            return false;
+        };
+        version
+            .level_files(0)
+            .iter()
+            .any(|tf| tf.meta.number > oldest_input && !inputs.contains(&tf.meta.number))
+    }
+
     fn outputs_overlap_unexpected_current_files(
         output: &CompactionOutput,
         version: &Version,
@@ -1499,25 +2166,67 @@ impl LeveledCompaction {
         false
     }
 
-    /// Delete old SST files after manifest has been synced.
-    pub fn run_post_compaction_cleanup(cleanup: &PostCompactionCleanup, db_path: &Path) {
-        for num in &cleanup.files_to_delete {
-            let old_path = db_path.join(format!("{:06}.sst", num));
-            if let Err(e) = remove_file(&old_path) {
-                tracing::warn!("failed to remove old SST {}: {}", old_path.display(), e);
+    /// Delete old SST and blob files after manifest has been synced. Deletion waits
+    /// while `deletion_gate` is held exclusively (by a checkpoint linking
+    /// the files it captured). Then reports the deletions and the completed
+    /// job to `listeners`, so callers must not hold the DB lock.
+    pub fn run_post_compaction_cleanup(
+        cleanup: &PostCompactionCleanup,
+        db_path: &Path,
+        deletion_gate: &RwLock<()>,
+        listeners: &[Arc<dyn EventListener>],
+    ) {
+        let mut deletions = Vec::new();
+        {
+            let _gate = deletion_gate.read();
+            for num in &cleanup.files_to_delete {
+                let old_path = db_path.join(format!("{:06}.sst", num));
+                let result = remove_file(&old_path);
+                if let Err(ref e) = result {
+                    tracing::warn!("failed to remove old SST {}: {}", old_path.display(), e);
+                }
+                if !listeners.is_empty() {
+                    deletions.push(TableFileDeletionInfo {
+                        file_number: *num,
+                        path: old_path,
+                        result: result.map_err(Error::from),
+                    });
+                }
+            }
+            for num in &cleanup.blob_files_to_delete {
+                let old_path = blob_file_path(db_path, *num);
+                if let Err(e) = remove_file(&old_path) {
+                    tracing::warn!(
+                        "failed to remove old blob file {}: {}",
+                        old_path.display(),
+                        e
+                    );
+                }
+            }
+        }
+        for listener in listeners {
+            for info in &deletions {
+                listener.on_table_file_deleted(info);
+            }
+            if let Some(ref job) = cleanup.job {
+                listener.on_compaction_completed(job);
             }
         }
     }
 
     /// Force-merge all files at a given level into one output at the same level.
-    /// Drops tombstones if this is the bottommost level.
+    /// Drops tombstones if this is the bottommost level. The manifest is
+    /// synced before returning; the caller deletes the inputs with
+    /// `run_post_compaction_cleanup` once it has released the DB lock.
     pub(crate) fn force_merge_level(
         ctx: &CompactionContext<'_>,
         level: usize,
+        reason: CompactionReason,
         versions: &mut VersionSet,
         table_cache: Option<&Arc<TableCache>>,
         block_cache: Option<&Arc<BlockCache>>,
-    ) -> Result<()> {
+    ) -> Result<PostCompactionCleanup> {
+        let started = Instant::now();
         let oldest_snapshot_seq = ctx
             .active_snapshots
             .iter()
@@ -1527,10 +2236,11 @@ impl LeveledCompaction {
         let version = versions.current();
         let files = version.level_files(level);
         if files.is_empty() {
-            return Ok(());
+            return Ok(PostCompactionCleanup::default());
         }
         let is_bottommost =
             Self::is_bottommost_level(&version, level, ctx.options.num_levels, files);
+        let creation_time = newest_creation_time(files);
         // A single file with a no-op filter is already in its final form —
         // rewriting would waste I/O for no benefit. The same holds when a
         // non-noop filter cannot fire anyway (the merge loop only applies
@@ -1541,9 +2251,17 @@ impl LeveledCompaction {
         // next compaction that touches the file. When the filter can
         // actually remove or change entries (e.g. lazy-delete dead keys),
         // even a single file must be reprocessed so every key-value pair
-        // passes through the filter.
+        // passes through the filter. Likewise a `full_history_ts_low`
+        // watermark may collapse versions inside a single file, and a blob
+        // file past the GC threshold may have records to relocate.
         let filter_can_apply = is_bottommost && ctx.active_snapshots.is_empty();
+        let blob_gc_due = version
+            .blob_files
+            .values()
+            .any(|bf| bf.garbage_ratio() > ctx.options.blob_garbage_collection_threshold);
         if files.len() == 1
+            && ctx.full_history_ts_low.is_none()
+            && !blob_gc_due
             && (!filter_can_apply
                 || ctx
                     .options
@@ -1551,7 +2269,7 @@ impl LeveledCompaction {
                     .as_ref()
                     .is_none_or(|f| f.is_noop()))
         {
-            return Ok(());
+            return Ok(PostCompactionCleanup::default());
         }
 
         let mut sources: Vec<IterSource> = Vec::new();
@@ -1565,12 +2283,13 @@ impl LeveledCompaction {
         }
 
         // Inject range tombstones from new-format SSTs into the merge stream
-        let range_del_entries = collect_range_del_entries(files).ctx()?;
+        let icmp = InternalKeyComparator::new(&ctx.options.comparator);
+        let range_del_entries = collect_range_del_entries(files, &icmp).ctx()?;
         if !range_del_entries.is_empty() {
             sources.push(IterSource::new(range_del_entries));
         }
 
-        let mut merger = MergingIterator::new(sources, compare_internal_key);
+        let mut merger = MergingIterator::new(sources, icmp.as_fn());
 
         let compression = if !ctx.options.compression_per_level.is_empty()
             && level < ctx.options.compression_per_level.len()
@@ -1589,6 +2308,7 @@ impl LeveledCompaction {
             compression,
             prefix_len: ctx.options.prefix_len,
             block_property_collectors: Vec::new(),
+            comparator: icmp.clone(),
         };
 
         let mut edit = VersionEdit::new();
@@ -1601,10 +2321,35 @@ impl LeveledCompaction {
         let mut last_range_del_key: Option<Vec<u8>> = None;
         let mut last_written_seq: SequenceNumber = 0;
         let mut snapshot_idx: usize = ctx.active_snapshots.len();
-        let mut range_tombstones = RangeTombstoneTracker::new();
+        let mut range_tombstones = RangeTombstoneTracker::with_comparator(icmp.clone());
+        // See execute_sub_compaction_io.
+        let mut merge_unresolved = false;
+        let now_millis = ctx.options.clock.now_millis();
+        let mut blobs = BlobCompaction::new(ctx, &version.blob_files);
+        let mut pending_operands: VecDeque<(Vec<u8>, LazyValue)> = VecDeque::new();
+        let mut collapsed_key: Option<Vec<u8>> = None;
+        let collapsible = |user_key: &[u8], seq: SequenceNumber| {
+            ctx.full_history_ts_low.is_some_and(|low| {
+                seq < oldest_snapshot_seq && icmp.split_timestamp(user_key).1 < low
+            })
+        };
 
-        while let Some((ikey, value)) = merger.next_entry() {
-            let (user_key, entry_seq, vt) = match decode_internal_key(&ikey) {
+        loop {
+            let (mut ikey, mut value, verbatim) = if let Some((k, v)) = pending_operands.pop_front()
+            {
+                (k, v, true)
+            } else if let Some((k, v)) = merger.next_entry() {
+                (k, v, false)
+            } else {
+                break;
+            };
+            let expired = if verbatim {
+                Ok(())
+            } else {
+                expire_entry(&mut ikey, &mut value, now_millis)
+            };
+            let (user_key, entry_seq, vt) = match expired.and_then(|()| decode_internal_key(&ikey))
+            {
                 Ok(decoded) => decoded,
                 Err(e) => {
                     cleanup_output_files(
@@ -1616,6 +2361,22 @@ impl LeveledCompaction {
                 }
             };
 
+            let blob_handle = if vt == ValueType::BlobIndex && !verbatim {
+                match blobs.consume(value.as_slice()) {
+                    Ok(handle) => Some(handle),
+                    Err(e) => {
+                        cleanup_output_files(
+                            ctx.db_path,
+                            &edit.new_files,
+                            builder.as_ref().map(|_| current_file_number),
+                        );
+                        return Err(e);
+                    }
+                }
+            } else {
+                None
+            };
+
             // Defer size-triggered file cuts to user-key boundaries so a key's
             // versions are never split across files (which would create overlapping
             // same-level key ranges and possibly miss visible versions on reads).
@@ -1642,13 +2403,25 @@ impl LeveledCompaction {
                         smallest_key: result.smallest_key.unwrap_or_default(),
                         largest_key: result.largest_key.unwrap_or_default(),
                         has_range_deletions: result.has_range_deletions,
+                        creation_time,
                     },
                 );
                 current_size = 0;
                 pending_cut = false;
             }
 
-            if vt == ValueType::RangeDeletion {
+            if !verbatim
+                && vt != ValueType::RangeDeletion
+                && collapsed_key
+                    .as_deref()
+                    .is_some_and(|key| key == icmp.strip_timestamp(user_key))
+            {
+                continue;
+            }
+
+            if verbatim {
+                // Older operands of a run decided below; keep as-is.
+            } else if vt == ValueType::RangeDeletion {
                 range_tombstones.add(user_key.to_vec(), value.as_slice().to_vec(), entry_seq);
                 range_tombstones.reset();
                 if let Some(ref last) = last_range_del_key
@@ -1668,15 +2441,23 @@ impl LeveledCompaction {
                 {
                     snapshot_idx -= 1;
                 }
-                if snapshot_idx > 0 && ctx.active_snapshots[snapshot_idx - 1] >= entry_seq {
+                if merge_unresolved
+                    || (snapshot_idx > 0 && ctx.active_snapshots[snapshot_idx - 1] >= entry_seq)
+                {
                     last_written_seq = entry_seq;
                     // A retained older version shadowed by a range tombstone below
                     // the oldest snapshot must still be dropped, or it would
                     // resurrect if that tombstone is dropped at the bottommost level.
-                    if vt == ValueType::Value
-                        && !range_tombstones.is_empty()
+                    if matches!(
+                        vt,
+                        ValueType::Value
+                            | ValueType::Merge
+                            | ValueType::ExpiringValue
+                            | ValueType::BlobIndex
+                    ) && !range_tombstones.is_empty()
                         && range_tombstones.is_deleted(user_key, entry_seq, oldest_snapshot_seq)
                     {
+                        merge_unresolved = false;
                         continue;
                     }
                 } else {
@@ -1686,32 +2467,128 @@ impl LeveledCompaction {
                 last_point_key = Some(user_key.to_vec());
                 last_written_seq = entry_seq;
                 snapshot_idx = ctx.active_snapshots.len();
+                merge_unresolved = false;
 
                 // Type was validated by `decode_internal_key` above.
                 if is_bottommost && entry_seq < oldest_snapshot_seq && vt == ValueType::Deletion {
-                    continue;
+                    if icmp.timestamp_size() == 0 {
+                        continue;
+                    }
+                    if collapsible(user_key, entry_seq) {
+                        collapsed_key = Some(icmp.strip_timestamp(user_key).to_vec());
+                        continue;
+                    }
                 }
 
-                if vt == ValueType::Value
-                    && !range_tombstones.is_empty()
+                if matches!(
+                    vt,
+                    ValueType::Value
+                        | ValueType::Merge
+                        | ValueType::ExpiringValue
+                        | ValueType::BlobIndex
+                ) && !range_tombstones.is_empty()
                     && range_tombstones.is_deleted(user_key, entry_seq, oldest_snapshot_seq)
                 {
                     continue;
                 }
             }
 
-            // Apply compaction filter
             let mut final_value = value;
+            let mut out_vt = vt;
+            let mut out_ikey: Option<Vec<u8>> = None;
+            if vt == ValueType::Merge
+                && !verbatim
+                && let Some(op) = ctx.options.merge_operator.as_deref()
+            {
+                let run = match collapse_merge_run(
+                    &mut merger,
+                    &icmp,
+                    op,
+                    &mut range_tombstones,
+                    ctx.active_snapshots,
+                    is_bottommost,
+                    user_key,
+                    entry_seq,
+                    final_value.into_vec(),
+                ) {
+                    Ok(run) => run,
+                    Err(e) => {
+                        cleanup_output_files(
+                            ctx.db_path,
+                            &edit.new_files,
+                            builder.as_ref().map(|_| current_file_number),
+                        );
+                        return Err(e);
+                    }
+                };
+                last_written_seq = run.oldest_seq;
+                merge_unresolved = run.vt == ValueType::Merge;
+                if run.vt == ValueType::Value {
+                    out_ikey = Some(InternalKey::new(user_key, entry_seq, run.vt).into_bytes());
+                }
+                out_vt = run.vt;
+                final_value = LazyValue::Inline(run.value);
+                for (seq, operand) in run.older {
+                    pending_operands.push_back((
+                        InternalKey::new(user_key, seq, ValueType::Merge).into_bytes(),
+                        LazyValue::Inline(operand),
+                    ));
+                }
+            } else if vt == ValueType::Merge {
+                merge_unresolved = true;
+            } else if vt != ValueType::RangeDeletion {
+                merge_unresolved = false;
+            }
+
+            // Apply compaction filter
             if is_bottommost
                 && ctx.active_snapshots.is_empty()
                 && let Some(ref filter) = ctx.options.compaction_filter
-                && vt == ValueType::Value
+                && matches!(out_vt, ValueType::Value | ValueType::BlobIndex)
             {
-                match filter.filter(level, user_key, final_value.as_slice()) {
+                let blob_value = match blob_handle.map(|h| blobs.read(&h)).transpose() {
+                    Ok(v) => v,
+                    Err(e) => {
+                        cleanup_output_files(
+                            ctx.db_path,
+                            &edit.new_files,
+                            builder.as_ref().map(|_| current_file_number),
+                        );
+                        return Err(e);
+                    }
+                };
+                let current = blob_value.as_deref().unwrap_or(final_value.as_slice());
+                match filter.filter(level, user_key, current) {
                     CompactionFilterDecision::Keep => {}
                     CompactionFilterDecision::Remove => continue,
                     CompactionFilterDecision::ChangeValue(new_val) => {
                         final_value = LazyValue::Inline(new_val);
+                        if out_vt == ValueType::BlobIndex {
+                            out_vt = ValueType::Value;
+                            out_ikey =
+                                Some(InternalKey::new(user_key, entry_seq, out_vt).into_bytes());
+                        }
+                    }
+                }
+            }
+
+            if out_vt == ValueType::BlobIndex
+                && let Some(handle) = blob_handle
+            {
+                match blobs.keep(handle, || Ok(versions.new_file_number())) {
+                    Ok(None) => {}
+                    Ok(Some((vt, relocated))) => {
+                        out_vt = vt;
+                        final_value = LazyValue::Inline(relocated);
+                        out_ikey = Some(InternalKey::new(user_key, entry_seq, out_vt).into_bytes());
+                    }
+                    Err(e) => {
+                        cleanup_output_files(
+                            ctx.db_path,
+                            &edit.new_files,
+                            builder.as_ref().map(|_| current_file_number),
+                        );
+                        return Err(e);
                     }
                 }
             }
@@ -1746,12 +2623,12 @@ impl LeveledCompaction {
             let ikey_ref = if is_bottommost
                 && entry_seq > 0
                 && entry_seq < oldest_snapshot_seq
-                && vt == ValueType::Value
+                && matches!(out_vt, ValueType::Value | ValueType::BlobIndex)
             {
-                final_ikey = InternalKey::new(user_key, 0, vt).as_bytes().to_vec();
+                final_ikey = InternalKey::new(user_key, 0, out_vt).as_bytes().to_vec();
                 &final_ikey
             } else {
-                &ikey
+                out_ikey.as_ref().unwrap_or(&ikey)
             };
 
             if let Err(e) = builder
@@ -1765,6 +2642,16 @@ impl LeveledCompaction {
             }
             let entry_bytes = ikey_ref.len() + final_value.len();
             current_size += entry_bytes;
+            if matches!(
+                out_vt,
+                ValueType::Value
+                    | ValueType::Deletion
+                    | ValueType::ExpiringValue
+                    | ValueType::BlobIndex
+            ) && collapsible(user_key, entry_seq)
+            {
+                collapsed_key = Some(icmp.strip_timestamp(user_key).to_vec());
+            }
             if current_file_user_key != user_key {
                 current_file_user_key.clear();
                 current_file_user_key.extend_from_slice(user_key);
@@ -1805,6 +2692,7 @@ impl LeveledCompaction {
                     smallest_key: result.smallest_key.unwrap_or_default(),
                     largest_key: result.largest_key.unwrap_or_default(),
                     has_range_deletions: result.has_range_deletions,
+                    creation_time,
                 },
             );
         }
@@ -1826,6 +2714,20 @@ impl LeveledCompaction {
             )));
         }
 
+        let (blob_file, blob_garbage) = match blobs.finish() {
+            Ok(finished) => finished,
+            Err(e) => {
+                cleanup_output_files(ctx.db_path, &edit.new_files, None);
+                return Err(e);
+            }
+        };
+        if let Some(meta) = blob_file {
+            edit.add_blob_file(meta);
+        }
+        for (number, (count, bytes)) in blob_garbage {
+            edit.add_blob_garbage(number, count, bytes);
+        }
+
         let input_file_numbers: HashSet<u64> = files.iter().map(|f| f.meta.number).collect();
         for tf in files {
             edit.delete_file(level as u32, tf.meta.number);
@@ -1835,8 +2737,10 @@ impl LeveledCompaction {
         // Capture output files so they can be deleted if the install fails,
         // otherwise the freshly-written SSTs would be orphaned on disk.
         let output_files = edit.new_files.clone();
+        let output_blob_files = edit.new_blob_files.clone();
         if let Err(e) = versions.log_and_apply(edit) {
             cleanup_output_files(ctx.db_path, &output_files, None);
+            remove_blob_files(ctx.db_path, &output_blob_files);
             return Err(e).ctx();
         }
         // force_merge_level holds &mut VersionSet for the duration, sync here.
@@ -1853,19 +2757,33 @@ impl LeveledCompaction {
             }
         }
 
-        // Safe to delete old SSTs after manifest is synced
-        for num in &input_file_numbers {
-            let old_path = ctx.db_path.join(format!("{:06}.sst", num));
-            if let Err(e) = remove_file(&old_path) {
-                tracing::warn!("failed to remove old SST {}: {}", old_path.display(), e);
-            }
-        }
-
+        let job = CompactionJobInfo {
+            reason,
+            level,
+            output_level: level,
+            input_files: files.iter().map(|f| f.meta.number).collect(),
+            output_files: output_files.iter().map(|(_, meta)| meta.number).collect(),
+            bytes_read: files.iter().map(|f| f.meta.file_size).sum(),
+            bytes_written: output_files.iter().map(|(_, meta)| meta.file_size).sum(),
+            duration: started.elapsed(),
+        };
         if let Some(s) = ctx.stats {
-            s.record_compaction_completed();
+            s.record_compaction_completed(Some(job.duration));
+            s.record_level_io(
+                level,
+                &LevelIo {
+                    compactions: 1,
+                    read_output: job.bytes_read,
+                    written: job.bytes_written,
+                    ..Default::default()
+                },
+            );
         }
-
-        Ok(())
+        Ok(PostCompactionCleanup {
+            files_to_delete: input_file_numbers,
+            blob_files_to_delete: versions.take_obsolete_blob_files(),
+            job: Some(job),
+        })
     }
 
     /// Pick a compaction based on a read-triggered hint. If the hinted level
@@ -1885,7 +2803,64 @@ impl LeveledCompaction {
             return None;
         }
         // Pick the largest file at the hinted level.
-        Self::pick_level_compaction(version, level, in_flight)
+        Self::pick_level_compaction(version, level, in_flight).map(|task| CompactionTask {
+            reason: CompactionReason::ReadTriggered,
+            ..task
+        })
+    }
+
+    /// Bytes compaction must rewrite to bring every level within its
+    /// target, as RocksDB estimates it: all of L0 once it reaches
+    /// `l0_compaction_trigger`, then each level's excess over its target
+    /// (plus what it already carried down) times the fan-out into the next
+    /// level, plus one for rewriting the excess itself.
+    pub fn estimate_pending_compaction_bytes(version: &Version, options: &DbOptions) -> u64 {
+        let level_size = |level: usize| -> u64 {
+            version
+                .level_files(level)
+                .iter()
+                .map(|f| f.meta.file_size)
+                .sum()
+        };
+        let mut pending = 0u64;
+        let mut carried = 0u64;
+        if version.l0_file_count() >= options.l0_compaction_trigger {
+            carried = level_size(0);
+            pending = carried;
+        }
+        for level in 1..version.num_levels.saturating_sub(1) {
+            let size = level_size(level).saturating_add(carried);
+            let target = Self::max_bytes_for_level(options, level);
+            if size <= target {
+                carried = 0;
+                continue;
+            }
+            let excess = size - target;
+            let next = level_size(level + 1);
+            let fan_out = next as f64 / size as f64 + 1.0;
+            pending = pending.saturating_add((excess as f64 * fan_out) as u64);
+            carried = excess;
+        }
+        pending
+    }
+
+    /// How far `level` is past its compaction trigger: L0's file count over
+    /// `l0_compaction_trigger`, other levels' size over their target. A
+    /// score of 1.0 or more makes the level eligible for compaction. The
+    /// last level has no target and scores 0.
+    pub fn level_score(version: &Version, options: &DbOptions, level: usize) -> f64 {
+        if level == 0 {
+            return version.l0_file_count() as f64 / options.l0_compaction_trigger.max(1) as f64;
+        }
+        if level + 1 >= version.num_levels {
+            return 0.0;
+        }
+        let size: u64 = version
+            .level_files(level)
+            .iter()
+            .map(|f| f.meta.file_size)
+            .sum();
+        size as f64 / Self::max_bytes_for_level(options, level).max(1) as f64
     }
 
     /// Maximum bytes for a given level.
@@ -1898,19 +2873,21 @@ impl LeveledCompaction {
     }
 
     /// Compute the total key range of a set of files.
-    /// Uses `compare_internal_key` for correct variable-length user key ordering.
+    /// Uses the tables' internal key comparator, so variable-length and
+    /// custom-ordered user keys compare correctly.
     fn total_key_range(files: &[TableFile]) -> (Vec<u8>, Vec<u8>) {
         let mut smallest = Vec::new();
         let mut largest = Vec::new();
 
         for f in files {
+            let icmp = f.reader.comparator();
             if smallest.is_empty()
-                || compare_internal_key(&f.meta.smallest_key, &smallest) == CmpOrdering::Less
+                || icmp.compare(&f.meta.smallest_key, &smallest) == CmpOrdering::Less
             {
                 smallest = f.meta.smallest_key.clone();
             }
             if largest.is_empty()
-                || compare_internal_key(&f.meta.largest_key, &largest) == CmpOrdering::Greater
+                || icmp.compare(&f.meta.largest_key, &largest) == CmpOrdering::Greater
             {
                 largest = f.meta.largest_key.clone();
             }
@@ -1974,7 +2951,9 @@ impl LeveledCompaction {
                 let file_smallest_uk = user_key(&f.meta.smallest_key);
                 // File overlaps if: file.largest_uk >= smallest_uk AND file.smallest_uk <= largest_uk.
                 // This is metadata-only; range-aware callers use `overlapping_files_for_inputs`.
-                file_largest_uk >= smallest_uk && file_smallest_uk <= largest_uk
+                let icmp = f.reader.comparator();
+                icmp.user_le(smallest_uk, file_largest_uk)
+                    && icmp.user_le(file_smallest_uk, largest_uk)
             })
             .cloned()
             .collect()
@@ -1990,7 +2969,7 @@ mod tests {
     fn test_discarded_trivial_move_preserves_live_input_file() {
         use std::collections::HashSet;
 
-        use super::{CompactionOutput, LeveledCompaction, OutputTombstones};
+        use super::{CompactionOutput, LevelIo, LeveledCompaction, OutputTombstones};
         use crate::manifest::version_edit::{FileMetaData, VersionEdit};
         use crate::manifest::version_set::VersionSet;
         use crate::sst::table_builder::{TableBuildOptions, TableBuilder};
@@ -2023,6 +3002,7 @@ mod tests {
                 smallest_key: result.smallest_key.unwrap(),
                 largest_key: result.largest_key.unwrap(),
                 has_range_deletions: false,
+                creation_time: 0,
             }
         }
 
@@ -2058,6 +3038,8 @@ mod tests {
             input_file_numbers: HashSet::new(),
             next_file_number_hint: versions.next_file_number(),
             output_tombstones: OutputTombstones::new(),
+            job: None,
+            level_io: LevelIo::default(),
         };
 
         // Install must DISCARD the move (file #1's range now overlaps the
@@ -2133,6 +3115,7 @@ mod tests {
                 smallest_key: result.smallest_key.unwrap(),
                 largest_key: result.largest_key.unwrap(),
                 has_range_deletions: result.has_range_deletions,
+                creation_time: 0,
             }
         }
 
@@ -2208,6 +3191,7 @@ mod tests {
                 smallest_key: result.smallest_key.unwrap(),
                 largest_key: result.largest_key.unwrap(),
                 has_range_deletions: result.has_range_deletions,
+                creation_time: 0,
             }
         }
 
@@ -2767,6 +3751,7 @@ mod tests {
         use super::{
             CompactionContext, CompactionTask, LeveledCompaction, PARALLEL_SUB_COMPACTIONS_TAKEN,
         };
+        use crate::listener::CompactionReason;
         use crate::manifest::version::TableFile;
         use crate::manifest::version_edit::FileMetaData;
         use crate::sst::table_builder::{TableBuildOptions, TableBuilder};
@@ -2802,6 +3787,7 @@ mod tests {
                     smallest_key: result.smallest_key.unwrap_or(smallest),
                     largest_key: result.largest_key.unwrap_or(largest),
                     has_range_deletions: false,
+                    creation_time: 0,
                 },
                 reader: Arc::new(crate::sst::table_reader::TableReader::open(&path).unwrap()),
             }
@@ -2826,7 +3812,12 @@ mod tests {
         let task = CompactionTask {
             level: 1,
             input_files_level: vec![source_file],
+            input_files_middle: Vec::new(),
             input_files_next: target_files,
+            output_level: 2,
+            delete_only: false,
+            blob_files: std::collections::BTreeMap::new(),
+            reason: CompactionReason::LevelMaxBytes,
         };
 
         let options = DbOptions {
@@ -2839,6 +3830,7 @@ mod tests {
             rate_limiter: None,
             stats: None,
             active_snapshots: &[],
+            full_history_ts_low: None,
         };
 
         let before = PARALLEL_SUB_COMPACTIONS_TAKEN.load(AtomicOrdering::Relaxed);
@@ -2888,6 +3880,7 @@ mod tests {
             CompactionContext, CompactionOutput, CompactionTask, LeveledCompaction,
             PARALLEL_SUB_COMPACTIONS_TAKEN,
         };
+        use crate::listener::CompactionReason;
         use crate::manifest::version::TableFile;
         use crate::manifest::version_edit::FileMetaData;
         use crate::sst::table_builder::{TableBuildOptions, TableBuilder};
@@ -2940,6 +3933,7 @@ mod tests {
                     smallest_key: result.smallest_key.unwrap_or_default(),
                     largest_key: result.largest_key.unwrap_or_default(),
                     has_range_deletions: result.has_range_deletions,
+                    creation_time: 0,
                 },
                 reader: Arc::new(crate::sst::table_reader::TableReader::open(&path).unwrap()),
             }
@@ -2977,7 +3971,12 @@ mod tests {
             CompactionTask {
                 level: 1,
                 input_files_level: vec![source_file],
+                input_files_middle: Vec::new(),
                 input_files_next: target_files,
+                output_level: 2,
+                delete_only: false,
+                blob_files: std::collections::BTreeMap::new(),
+                reason: CompactionReason::LevelMaxBytes,
             }
         }
 
@@ -3024,6 +4023,7 @@ mod tests {
                 rate_limiter: None,
                 stats: None,
                 active_snapshots,
+                full_history_ts_low: None,
             };
 
             let before = PARALLEL_SUB_COMPACTIONS_TAKEN.load(AtomicOrdering::Relaxed);
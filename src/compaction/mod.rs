@@ -1,5 +1,234 @@
 //! Compaction: background process to merge SST files and reduce read amplification.
 
+use std::collections::HashSet;
+
+use crate::manifest::version::Version;
+use crate::options::{CompactionStyle, DbOptions};
+
+pub mod fifo;
 pub mod leveled;
+pub mod universal;
 
+pub use fifo::FifoCompaction;
 pub use leveled::LeveledCompaction;
+pub use universal::UniversalCompaction;
+
+use leveled::{CompactionHint, CompactionTask};
+
+/// Input selection for one [`CompactionStyle`]. Picked tasks are executed
+/// and installed by `LeveledCompaction` regardless of style.
+pub(crate) trait CompactionPicker: Send + Sync {
+    /// The next compaction the layout calls for, if any.
+    fn pick_compaction(
+        &self,
+        version: &Version,
+        options: &DbOptions,
+        in_flight: &HashSet<u64>,
+    ) -> Option<CompactionTask>;
+
+    /// A compaction that relieves L0. Repeated until `None`, this empties
+    /// L0 for styles that move data out of it; for those, `None` while L0
+    /// is non-empty means its files are claimed by an in-flight compaction.
+    fn pick_l0_drain(
+        &self,
+        version: &Version,
+        options: &DbOptions,
+        in_flight: &HashSet<u64>,
+    ) -> Option<CompactionTask>;
+
+    /// A compaction across levels run by a full compaction after L0 is
+    /// drained, before each level is merged in place.
+    fn pick_full_compaction(
+        &self,
+        version: &Version,
+        in_flight: &HashSet<u64>,
+    ) -> Option<CompactionTask>;
+
+    /// A compaction for a read-hot level.
+    fn pick_compaction_for_hint(
+        &self,
+        version: &Version,
+        hint: &CompactionHint,
+        in_flight: &HashSet<u64>,
+    ) -> Option<CompactionTask>;
+
+    /// A compaction of the files overlapping `[begin, end)`.
+    fn pick_compaction_for_range(
+        &self,
+        version: &Version,
+        begin: Option<&[u8]>,
+        end: Option<&[u8]>,
+    ) -> Option<CompactionTask>;
+
+    /// Estimated bytes compaction must rewrite to bring the layout back
+    /// within its limits. By default, the input size of the compaction
+    /// picked next.
+    fn estimate_pending_compaction_bytes(&self, version: &Version, options: &DbOptions) -> u64 {
+        self.pick_compaction(version, options, &HashSet::new())
+            .filter(|task| !task.delete_only)
+            .map_or(0, |task| {
+                task.inputs().map(|(_, tf)| tf.meta.file_size).sum()
+            })
+    }
+}
+
+/// The picker for `style`.
+pub(crate) fn picker(style: CompactionStyle) -> &'static dyn CompactionPicker {
+    match style {
+        CompactionStyle::Leveled => &LeveledCompaction,
+        CompactionStyle::Universal => &UniversalCompaction,
+        CompactionStyle::Fifo => &FifoCompaction,
+    }
+}
+
+impl CompactionPicker for LeveledCompaction {
+    fn pick_compaction(
+        &self,
+        version: &Version,
+        options: &DbOptions,
+        in_flight: &HashSet<u64>,
+    ) -> Option<CompactionTask> {
+        LeveledCompaction::pick_compaction(version, options, in_flight)
+    }
+
+    fn pick_l0_drain(
+        &self,
+        version: &Version,
+        options: &DbOptions,
+        in_flight: &HashSet<u64>,
+    ) -> Option<CompactionTask> {
+        // With a trigger of 1, any L0 file is compacted. Level size
+        // overflows are still picked, as they would be by the background
+        // thread.
+        let force_opts = DbOptions {
+            l0_compaction_trigger: 1,
+            ..options.clone()
+        };
+        LeveledCompaction::pick_compaction(version, &force_opts, in_flight)
+    }
+
+    fn pick_full_compaction(
+        &self,
+        _version: &Version,
+        _in_flight: &HashSet<u64>,
+    ) -> Option<CompactionTask> {
+        // Levels are merged in place; nothing spans levels.
+        None
+    }
+
+    fn pick_compaction_for_hint(
+        &self,
+        version: &Version,
+        hint: &CompactionHint,
+        in_flight: &HashSet<u64>,
+    ) -> Option<CompactionTask> {
+        LeveledCompaction::pick_compaction_for_hint(version, hint, in_flight)
+    }
+
+    fn pick_compaction_for_range(
+        &self,
+        version: &Version,
+        begin: Option<&[u8]>,
+        end: Option<&[u8]>,
+    ) -> Option<CompactionTask> {
+        LeveledCompaction::pick_compaction_for_range(version, begin, end)
+    }
+
+    fn estimate_pending_compaction_bytes(&self, version: &Version, options: &DbOptions) -> u64 {
+        LeveledCompaction::estimate_pending_compaction_bytes(version, options)
+    }
+}
+
+impl CompactionPicker for UniversalCompaction {
+    fn pick_compaction(
+        &self,
+        version: &Version,
+        options: &DbOptions,
+        in_flight: &HashSet<u64>,
+    ) -> Option<CompactionTask> {
+        UniversalCompaction::pick_compaction(version, options, in_flight)
+    }
+
+    fn pick_l0_drain(
+        &self,
+        version: &Version,
+        options: &DbOptions,
+        in_flight: &HashSet<u64>,
+    ) -> Option<CompactionTask> {
+        UniversalCompaction::pick_l0_drain(version, options, in_flight)
+    }
+
+    fn pick_full_compaction(
+        &self,
+        version: &Version,
+        in_flight: &HashSet<u64>,
+    ) -> Option<CompactionTask> {
+        UniversalCompaction::pick_full_compaction(version, in_flight)
+    }
+
+    fn pick_compaction_for_hint(
+        &self,
+        version: &Version,
+        hint: &CompactionHint,
+        in_flight: &HashSet<u64>,
+    ) -> Option<CompactionTask> {
+        UniversalCompaction::pick_compaction_for_hint(version, hint, in_flight)
+    }
+
+    fn pick_compaction_for_range(
+        &self,
+        version: &Version,
+        begin: Option<&[u8]>,
+        end: Option<&[u8]>,
+    ) -> Option<CompactionTask> {
+        UniversalCompaction::pick_compaction_for_range(version, begin, end)
+    }
+}
+
+impl CompactionPicker for FifoCompaction {
+    fn pick_compaction(
+        &self,
+        version: &Version,
+        options: &DbOptions,
+        in_flight: &HashSet<u64>,
+    ) -> Option<CompactionTask> {
+        FifoCompaction::pick_compaction(version, options, in_flight)
+    }
+
+    fn pick_l0_drain(
+        &self,
+        version: &Version,
+        options: &DbOptions,
+        in_flight: &HashSet<u64>,
+    ) -> Option<CompactionTask> {
+        // Data never leaves L0; enforce the budget, TTL and file count.
+        FifoCompaction::pick_compaction(version, options, in_flight)
+    }
+
+    fn pick_full_compaction(
+        &self,
+        _version: &Version,
+        _in_flight: &HashSet<u64>,
+    ) -> Option<CompactionTask> {
+        None
+    }
+
+    fn pick_compaction_for_hint(
+        &self,
+        version: &Version,
+        hint: &CompactionHint,
+        in_flight: &HashSet<u64>,
+    ) -> Option<CompactionTask> {
+        FifoCompaction::pick_compaction_for_hint(version, hint, in_flight)
+    }
+
+    fn pick_compaction_for_range(
+        &self,
+        _version: &Version,
+        _begin: Option<&[u8]>,
+        _end: Option<&[u8]>,
+    ) -> Option<CompactionTask> {
+        // Files are only ever deleted whole, never merged by key range.
+        None
+    }
+}
@@ -0,0 +1,380 @@
+//! Universal (size-tiered) compaction strategy.
+//!
+//! Every L0 file and every non-empty level L1+ is a sorted run, ordered
+//! newest first. Compaction merges a prefix of that order — the newest runs —
+//! into one run, written to the deepest level that keeps it above every run
+//! it did not include. Runs are merged when:
+//!
+//! 1. the runs above the oldest one add up to more than
+//!    `max_size_amplification_percent` of it (merge everything), or
+//! 2. a prefix of runs have similar sizes (`size_ratio`), or
+//! 3. there are at least `l0_compaction_trigger` runs (merge the newest
+//!    ones until the count is back under the trigger).
+//!
+//! Picked tasks are executed by the shared `LeveledCompaction` machinery.
+
+use std::collections::HashSet;
+
+use crate::compaction::leveled::{CompactionHint, CompactionTask, file_overlaps_compact_bounds};
+use crate::listener::CompactionReason;
+use crate::manifest::version::{TableFile, Version};
+use crate::options::DbOptions;
+
+pub struct UniversalCompaction;
+
+/// One sorted run: a single L0 file or a whole level.
+struct SortedRun<'a> {
+    level: usize,
+    files: &'a [TableFile],
+    size: u64,
+}
+
+fn sorted_runs(version: &Version) -> Vec<SortedRun<'_>> {
+    let mut runs: Vec<SortedRun<'_>> = version
+        .level_files(0)
+        .iter()
+        .map(|tf| SortedRun {
+            level: 0,
+            files: std::slice::from_ref(tf),
+            size: tf.meta.file_size,
+        })
+        .collect();
+    for level in 1..version.num_levels {
+        let files = version.level_files(level);
+        if !files.is_empty() {
+            runs.push(SortedRun {
+                level,
+                files,
+                size: files.iter().map(|tf| tf.meta.file_size).sum(),
+            });
+        }
+    }
+    runs
+}
+
+impl UniversalCompaction {
+    /// Pick a compaction if the run layout calls for one. `in_flight` has
+    /// the same meaning as for `LeveledCompaction::pick_compaction`; since
+    /// every pick starts at the newest run, a second concurrent pick only
+    /// succeeds when it stays clear of the first one's inputs.
+    pub fn pick_compaction(
+        version: &Version,
+        options: &DbOptions,
+        in_flight: &HashSet<u64>,
+    ) -> Option<CompactionTask> {
+        let runs = sorted_runs(version);
+        if runs.len() < 2 || runs.len() < options.l0_compaction_trigger {
+            return None;
+        }
+        let universal = &options.universal_compaction;
+
+        // Size amplification: everything newer than the oldest run is
+        // space that a full merge would reclaim.
+        let oldest = runs[runs.len() - 1].size;
+        let newer: u64 = runs[..runs.len() - 1].iter().map(|r| r.size).sum();
+        if newer.saturating_mul(100)
+            > oldest.saturating_mul(universal.max_size_amplification_percent)
+        {
+            return Self::build_task(
+                version,
+                &runs,
+                runs.len(),
+                in_flight,
+                CompactionReason::UniversalSizeAmplification,
+            );
+        }
+
+        // Size ratio: extend from the newest run while the next one is not
+        // much larger than everything picked so far.
+        let mut picked = runs[0].size;
+        let mut end = 1;
+        while end < runs.len()
+            && end < universal.max_merge_width
+            && runs[end].size.saturating_mul(100)
+                <= picked.saturating_mul(100 + u64::from(universal.size_ratio))
+        {
+            picked = picked.saturating_add(runs[end].size);
+            end += 1;
+        }
+        if end >= universal.min_merge_width {
+            return Self::build_task(
+                version,
+                &runs,
+                end,
+                in_flight,
+                CompactionReason::UniversalSizeRatio,
+            );
+        }
+
+        // Run count: merge just enough of the newest runs to get back
+        // under the trigger.
+        let end = (runs.len() + 1)
+            .saturating_sub(options.l0_compaction_trigger)
+            .max(2)
+            .min(runs.len());
+        Self::build_task(
+            version,
+            &runs,
+            end,
+            in_flight,
+            CompactionReason::UniversalSortedRunCount,
+        )
+    }
+
+    /// Like `pick_compaction`, but falls back to merging every L0 file so
+    /// that L0 can be drained completely. `None` once L0 is empty or when
+    /// its files are claimed by an in-flight compaction.
+    pub fn pick_l0_drain(
+        version: &Version,
+        options: &DbOptions,
+        in_flight: &HashSet<u64>,
+    ) -> Option<CompactionTask> {
+        let l0_count = version.l0_file_count();
+        if l0_count == 0 {
+            return None;
+        }
+        Self::pick_compaction(version, options, in_flight).or_else(|| {
+            Self::build_task(
+                version,
+                &sorted_runs(version),
+                l0_count,
+                in_flight,
+                CompactionReason::UniversalSortedRunCount,
+            )
+        })
+    }
+
+    /// Merge every run into one. `None` when there is at most one run
+    /// (already fully compacted) or an input is claimed.
+    pub fn pick_full_compaction(
+        version: &Version,
+        in_flight: &HashSet<u64>,
+    ) -> Option<CompactionTask> {
+        let runs = sorted_runs(version);
+        if runs.len() < 2 {
+            return None;
+        }
+        Self::build_task(
+            version,
+            &runs,
+            runs.len(),
+            in_flight,
+            CompactionReason::Manual,
+        )
+    }
+
+    /// Read-triggered compaction has no universal equivalent: runs are
+    /// merged by size, not by which level reads land in.
+    pub fn pick_compaction_for_hint(
+        _version: &Version,
+        _hint: &CompactionHint,
+        _in_flight: &HashSet<u64>,
+    ) -> Option<CompactionTask> {
+        None
+    }
+
+    /// Runs span the whole key space, so a range compaction merges every
+    /// run once any file overlaps `[begin, end)`.
+    pub fn pick_compaction_for_range(
+        version: &Version,
+        begin: Option<&[u8]>,
+        end: Option<&[u8]>,
+    ) -> Option<CompactionTask> {
+        let runs = sorted_runs(version);
+        let needed = runs.len() >= 2 || version.l0_file_count() > 0;
+        let overlaps = runs
+            .iter()
+            .flat_map(|r| r.files)
+            .any(|tf| file_overlaps_compact_bounds(tf, begin, end));
+        if !needed || !overlaps {
+            return None;
+        }
+        Self::build_task(
+            version,
+            &runs,
+            runs.len(),
+            &HashSet::new(),
+            CompactionReason::Manual,
+        )
+    }
+
+    /// Build a task merging `runs[..end]`, widened so the output stays a
+    /// valid level layout: a window touching L0 takes all of L0 (older L0
+    /// files must not be left above newer data), and output never lands
+    /// in L0.
+    fn build_task(
+        version: &Version,
+        runs: &[SortedRun<'_>],
+        mut end: usize,
+        in_flight: &HashSet<u64>,
+        reason: CompactionReason,
+    ) -> Option<CompactionTask> {
+        if end == 0 {
+            return None;
+        }
+        let l0_count = version.l0_file_count();
+        if runs[0].level == 0 {
+            end = end.max(l0_count);
+        }
+
+        // The output goes to the oldest picked level, or — when only L0
+        // files are picked — to the deepest level above the next run.
+        let mut output_level = match runs[end - 1].level {
+            0 => runs
+                .get(end)
+                .map_or(version.num_levels - 1, |next| next.level - 1),
+            level => level,
+        };
+        if output_level == 0 {
+            // The next run is L1: merge it too.
+            end += 1;
+            output_level = 1;
+        }
+        let window = &runs[..end];
+        if window.len() == 1 && window[0].level != 0 {
+            return None;
+        }
+        if window
+            .iter()
+            .flat_map(|r| r.files)
+            .any(|tf| in_flight.contains(&tf.meta.number))
+        {
+            return None;
+        }
+
+        let level = window[0].level;
+        let mut input_files_middle = Vec::new();
+        let mut input_files_next = Vec::new();
+        for run in window.iter().filter(|r| r.level != level) {
+            if run.level == output_level {
+                input_files_next = run.files.to_vec();
+            } else {
+                input_files_middle.extend(run.files.iter().map(|tf| (run.level, tf.clone())));
+            }
+        }
+        Some(CompactionTask {
+            level,
+            input_files_level: version.level_files(level).to_vec(),
+            input_files_middle,
+            input_files_next,
+            output_level,
+            delete_only: false,
+            blob_files: version.blob_files.clone(),
+            reason,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::UniversalCompaction;
+    use crate::manifest::version_edit::{FileMetaData, VersionEdit};
+    use crate::manifest::version_set::VersionSet;
+    use crate::options::DbOptions;
+    use crate::sst::table_builder::{TableBuildOptions, TableBuilder};
+    use crate::types::{InternalKey, ValueType};
+
+    fn build_sst(dir: &std::path::Path, number: u64, value_len: usize) -> FileMetaData {
+        let path = dir.join(format!("{number:06}.sst"));
+        let mut builder = TableBuilder::new(
+            &path,
+            TableBuildOptions {
+                internal_keys: true,
+                bloom_bits_per_key: 0,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let key = InternalKey::new(b"k", number, ValueType::Value);
+        builder.add(key.as_bytes(), &vec![7u8; value_len]).unwrap();
+        let result = builder.finish().unwrap();
+        FileMetaData {
+            number,
+            file_size: result.file_size,
+            smallest_key: result.smallest_key.unwrap(),
+            largest_key: result.largest_key.unwrap(),
+            has_range_deletions: result.has_range_deletions,
+            creation_time: 0,
+        }
+    }
+
+    fn numbers(files: &[crate::manifest::version::TableFile]) -> Vec<u64> {
+        let mut numbers: Vec<u64> = files.iter().map(|tf| tf.meta.number).collect();
+        numbers.sort();
+        numbers
+    }
+
+    #[test]
+    fn test_l0_runs_merge_above_older_level() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut versions = VersionSet::create(dir.path(), 7).unwrap();
+        let mut edit = VersionEdit::new();
+        edit.add_file(5, build_sst(dir.path(), 1, 64 * 1024));
+        for number in 2..=5 {
+            edit.add_file(0, build_sst(dir.path(), number, 1024));
+        }
+        versions.log_and_apply(edit).unwrap();
+
+        let options = DbOptions::default();
+        let task =
+            UniversalCompaction::pick_compaction(&versions.current(), &options, &HashSet::new())
+                .unwrap();
+        // The similar-sized L0 files merge together; the much larger L5
+        // run is left alone and the output lands right above it.
+        assert_eq!(task.level, 0);
+        assert_eq!(numbers(&task.input_files_level), vec![2, 3, 4, 5]);
+        assert!(task.input_files_middle.is_empty());
+        assert!(task.input_files_next.is_empty());
+        assert_eq!(task.output_level, 4);
+
+        // A claimed input blocks the pick.
+        assert!(
+            UniversalCompaction::pick_compaction(
+                &versions.current(),
+                &options,
+                &HashSet::from([3]),
+            )
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn test_size_amplification_merges_every_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut versions = VersionSet::create(dir.path(), 7).unwrap();
+        let mut edit = VersionEdit::new();
+        edit.add_file(6, build_sst(dir.path(), 1, 1024));
+        edit.add_file(4, build_sst(dir.path(), 2, 16 * 1024));
+        edit.add_file(2, build_sst(dir.path(), 3, 64 * 1024));
+        versions.log_and_apply(edit).unwrap();
+
+        let version = versions.current();
+        let options = DbOptions {
+            l0_compaction_trigger: 2,
+            ..Default::default()
+        };
+        let task =
+            UniversalCompaction::pick_compaction(&version, &options, &HashSet::new()).unwrap();
+        assert_eq!(task.level, 2);
+        assert_eq!(numbers(&task.input_files_level), vec![3]);
+        let middle: Vec<(usize, u64)> = task
+            .input_files_middle
+            .iter()
+            .map(|(level, tf)| (*level, tf.meta.number))
+            .collect();
+        assert_eq!(middle, vec![(4, 2)]);
+        assert_eq!(numbers(&task.input_files_next), vec![1]);
+        assert_eq!(task.output_level, 6);
+
+        // Below the run-count trigger, nothing is picked.
+        let options = DbOptions {
+            l0_compaction_trigger: 4,
+            ..options
+        };
+        assert!(
+            UniversalCompaction::pick_compaction(&version, &options, &HashSet::new()).is_none()
+        );
+    }
+}
@@ -1,9 +1,12 @@
 //! Core DB implementation with WAL, MemTable, SST, MANIFEST, and Iterator.
 
 use std::{
-    collections::{HashSet, VecDeque},
+    cmp::Ordering as CmpOrdering,
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
     fs::{self, OpenOptions},
-    io, mem,
+    io,
+    iter::{self, Peekable},
+    mem,
     panic::{AssertUnwindSafe, catch_unwind},
     path::{Path, PathBuf},
     sync::{
@@ -11,38 +14,54 @@ use std::{
         atomic::{AtomicBool, AtomicU8, AtomicU64, AtomicUsize, Ordering},
     },
     thread::{self, JoinHandle},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use arc_swap::ArcSwap;
 use parking_lot::{Condvar, Mutex, MutexGuard, RwLock};
 
+use crate::blob::{BlobFileWriter, blob_file_path};
 use crate::cache::block_cache::BlockCache;
 use crate::cache::table_cache::TableCache;
-use crate::compaction::LeveledCompaction;
-use crate::compaction::leveled::{CompactionContext, CompactionHint};
-use crate::error::{Error, Result, ResultExt};
+use crate::column_family::{ColumnFamilyHandle, DEFAULT_COLUMN_FAMILY_NAME};
+use crate::compaction::leveled::{
+    CompactionContext, CompactionHint, CompactionTask, files_overlap_user_range,
+};
+use crate::compaction::{self, LeveledCompaction};
+use crate::error::{Error, ErrorKind, Result, ResultExt};
 use crate::iterator::db_iter::DBIterator;
 use crate::iterator::level_iter::LevelIterator;
-use crate::iterator::merge::IterSource;
-use crate::manifest::version_edit::{FileMetaData, VersionEdit};
+use crate::iterator::merge::{IterSource, SeekableIterator};
+use crate::listener::{
+    self, CompactionReason, FlushJobInfo, RecoveryProgressInfo, WriteStallCondition, WriteStallInfo,
+};
+use crate::manifest::version::{TableFile, Version};
+use crate::manifest::version_edit::{BlobFileMetaData, FileMetaData, VersionEdit};
 use crate::manifest::version_set::VersionSet;
 use crate::memtable::MemTable;
 use crate::memtable::skiplist::MemTableCursorIter;
+use crate::metrics::{FamilyMetrics, MetricsExporter};
 use crate::options::{
-    CompactionFilter, CompactionFilterDecision, DbOptions, ReadOptions, WriteOptions,
+    CompactionFilter, CompactionFilterDecision, CompactionStyle, DbOptions,
+    IngestExternalFileOptions, MergeOperator, ReadOptions, WalRecoveryMode, WriteOptions,
+    require_merge_operator,
 };
 use crate::rate_limiter::RateLimiter;
 use crate::sst::table_builder::{
     META_BLOCK_SPLIT_THRESHOLD, TableBuildOptions, TableBuildResult, TableBuilder,
 };
-use crate::sst::table_reader::{PreparedBlockPin, TableIterator};
-use crate::stats::DbStats;
+use crate::sst::table_reader::{PreparedBlockPin, TableIterator, TableReader};
+use crate::stats::{self, DbStats, HistogramKind, Statistics};
+use crate::transaction::OptimisticTransaction;
+use crate::transaction::optimistic::ConflictCheck;
 use crate::types::{
-    self, MAX_SEQUENCE_NUMBER, MAX_USER_KEY_SIZE, MAX_WRITE_ENTRY_SIZE, SequenceNumber, ValueType,
-    WriteBatch, WriteBatchWithIndex, tombstone_overlaps_bounds,
+    self, InternalKey, InternalKeyComparator, MAX_SEQUENCE_NUMBER, MAX_USER_KEY_SIZE,
+    MAX_WRITE_ENTRY_SIZE, SequenceNumber, ValueType, WriteBatch, WriteBatchWithIndex,
+    tombstone_overlaps_bounds,
+};
+use crate::wal::{
+    WalCorruption, WalReader, WalRecoverySummary, WalUpdateIterator, WalWriter, archive,
 };
-use crate::wal::{WalReader, WalWriter};
 
 /// Confirm MANIFEST durability before unlinking inputs/WALs after an apply.
 ///
@@ -100,6 +119,15 @@ const DEAD_KEY_PRUNE_PER_FLUSH: usize = 1024;
 /// (uncapped, for explicit `compact`/`compact_range`) settled list.
 const DEAD_KEY_RECONFIRM_CHUNK: usize = 64;
 
+/// Set on a WAL entry's type byte when a column family id (u32 LE) follows
+/// it. Default-family entries omit the id, keeping the original encoding.
+const WAL_COLUMN_FAMILY_FLAG: u8 = 0x80;
+
+/// Attempts a secondary's open or `try_catch_up_with_primary` makes before
+/// giving up on a primary that keeps deleting the files it reads or
+/// flushing under it.
+const SECONDARY_CATCH_UP_ATTEMPTS: usize = 8;
+
 #[derive(Clone, Copy, Eq, PartialEq)]
 #[repr(u8)]
 enum DeadKeySweepState {
@@ -230,8 +258,17 @@ struct WriteRequest {
     batch: WriteBatch,
     sync: bool,
     disable_wal: bool,
+    /// Optimistic-transaction read set, validated by the leader before the
+    /// request is assigned sequence numbers.
+    conflict_check: Option<ConflictCheck>,
     result: Option<Result<()>>,
     done: bool,
+    /// Set once a leader has drained this request into its group, so the
+    /// request never competes for leadership after a pipelined hand-off.
+    grouped: bool,
+    /// Set by the leader when this request should insert its own batch
+    /// (`allow_concurrent_memtable_write`).
+    apply: Option<ApplyTask>,
 }
 
 /// Group commit queue state. The `leader_active` flag prevents new arrivals
@@ -239,6 +276,152 @@ struct WriteRequest {
 struct WriteQueueState {
     queue: VecDeque<*mut WriteRequest>,
     leader_active: bool,
+    /// Groups whose leader handed off leadership after the WAL write and
+    /// is still applying to the memtable (`enable_pipelined_write`).
+    groups_applying: usize,
+}
+
+/// A group whose WAL records are written and whose memtable inserts are
+/// still to come.
+struct LoggedGroup {
+    /// Position in `WritePipeline` order.
+    ticket: u64,
+    /// Requests that were assigned sequence numbers, with the first
+    /// sequence number of each.
+    assigned: Vec<(*mut WriteRequest, SequenceNumber)>,
+    mems: Arc<GroupMemtables>,
+    /// Last sequence number the group makes visible.
+    last_seq: Option<SequenceNumber>,
+    /// WAL append failure that cut the group short. Requests past the
+    /// failure were not assigned and fail with it.
+    append_error: Option<String>,
+}
+
+/// The memtables a logged group inserts into, captured under `inner`
+/// together with the group's WAL write.
+struct GroupMemtables {
+    default: Arc<MemTable>,
+    /// Column families written by the group. A family dropped since
+    /// validation has no entry: its writes are logged but never applied.
+    families: HashMap<u32, (Arc<ColumnFamily>, Arc<MemTable>)>,
+}
+
+impl GroupMemtables {
+    /// Insert `batch` with sequence numbers from `first_seq`, returning the
+    /// bytes written.
+    fn apply(&self, batch: &WriteBatch, first_seq: SequenceNumber, concurrent: bool) -> u64 {
+        let mut bytes = 0u64;
+        for (i, entry) in batch.entries.iter().enumerate() {
+            let mem = if entry.cf == 0 {
+                &self.default
+            } else if let Some((_, mem)) = self.families.get(&entry.cf) {
+                mem
+            } else {
+                continue;
+            };
+            let value = entry.value.as_deref().unwrap_or(&[]);
+            let seq = first_seq + i as u64;
+            if concurrent {
+                mem.put_concurrent(&entry.key, value, seq, entry.value_type);
+            } else {
+                mem.put(&entry.key, value, seq, entry.value_type);
+            }
+            bytes += entry.key.len() as u64 + value.len() as u64;
+        }
+        bytes
+    }
+}
+
+/// A follower's share of a concurrent memtable apply.
+struct ApplyTask {
+    first_seq: SequenceNumber,
+    mems: Arc<GroupMemtables>,
+    remaining: Arc<ApplyLatch>,
+}
+
+/// Counts down the followers still inserting a group's batches.
+struct ApplyLatch {
+    remaining: Mutex<usize>,
+    cv: Condvar,
+}
+
+impl ApplyLatch {
+    fn new(count: usize) -> Self {
+        Self {
+            remaining: Mutex::new(count),
+            cv: Condvar::new(),
+        }
+    }
+
+    fn count_down(&self) {
+        let mut remaining = self.remaining.lock();
+        *remaining -= 1;
+        if *remaining == 0 {
+            self.cv.notify_all();
+        }
+    }
+
+    fn wait(&self) {
+        let mut remaining = self.remaining.lock();
+        while *remaining > 0 {
+            self.cv.wait(&mut remaining);
+        }
+    }
+}
+
+/// Orders logged groups through the memtable stage. A group takes a ticket
+/// under `inner` once its WAL records are written, and groups publish
+/// `committed_sequence` in ticket order. Without concurrent memtable
+/// writes they insert in ticket order too.
+struct WritePipeline {
+    tickets: Mutex<PipelineTickets>,
+    turn: Condvar,
+}
+
+struct PipelineTickets {
+    issued: u64,
+    published: u64,
+}
+
+impl WritePipeline {
+    fn new() -> Self {
+        Self {
+            tickets: Mutex::new(PipelineTickets {
+                issued: 0,
+                published: 0,
+            }),
+            turn: Condvar::new(),
+        }
+    }
+
+    /// Take the next ticket. Callers hold `inner`.
+    fn issue(&self) -> u64 {
+        let mut tickets = self.tickets.lock();
+        tickets.issued += 1;
+        tickets.issued - 1
+    }
+
+    /// Wait for every earlier ticket to publish, run `f`, then publish
+    /// `ticket`.
+    fn run_in_order<T>(&self, ticket: u64, f: impl FnOnce() -> T) -> T {
+        let mut tickets = self.tickets.lock();
+        while tickets.published != ticket {
+            self.turn.wait(&mut tickets);
+        }
+        let result = f();
+        tickets.published += 1;
+        self.turn.notify_all();
+        result
+    }
+
+    /// Wait until every issued ticket has published. Callers hold `inner`,
+    /// so no ticket is issued meanwhile.
+    fn wait_idle(&self) {
+        let mut tickets = self.tickets.lock();
+        while tickets.published != tickets.issued {
+            self.turn.wait(&mut tickets);
+        }
+    }
 }
 
 /// State captured during the freeze phase of a flush.
@@ -250,11 +433,300 @@ struct FrozenMemtable {
     /// split the memtable into multiple SSTs (cut at user-key boundaries)
     /// when projected single-block metadata grows large; numbers are
     /// consumed in order and unused ones are simply never materialized.
+    /// A blob file, when values are separated, takes one of these too.
     sst_numbers: Vec<u64>,
     old_wal_number: u64,
     new_wal_number: u64,
 }
 
+/// Files written by one memtable flush.
+struct FlushOutput {
+    /// The L0 SSTs, in key order.
+    tables: Vec<(u64, TableBuildResult)>,
+    /// The blob file holding values moved out by `min_blob_size`, if any.
+    blob_file: Option<BlobFileMetaData>,
+}
+
+impl FlushOutput {
+    fn sst_numbers(&self) -> Vec<u64> {
+        self.tables.iter().map(|(n, _)| *n).collect()
+    }
+
+    /// Listener info for this output, flushed from WAL `wal_number`.
+    fn job_info(&self, wal_number: u64, duration: Duration) -> FlushJobInfo {
+        FlushJobInfo {
+            file_numbers: self.sst_numbers(),
+            bytes_written: self.tables.iter().map(|(_, r)| r.file_size).sum(),
+            wal_number,
+            duration,
+        }
+    }
+
+    /// Delete the output files of a flush whose install failed.
+    fn remove_files(&self, db_path: &Path) {
+        for num in self.sst_numbers() {
+            let _ = fs::remove_file(db_path.join(format!("{:06}.sst", num)));
+        }
+        if let Some(ref blob) = self.blob_file {
+            let _ = fs::remove_file(blob_file_path(db_path, blob.number));
+        }
+    }
+}
+
+/// A non-default column family: a full engine instance in `<db>/cf-<id>/`
+/// with its own memtable, MANIFEST, SSTs, options, and compaction threads,
+/// but no WAL and no `LOCK` of its own. The owning DB logs its writes to the
+/// shared WAL, routes them into its memtable, and orchestrates its flushes.
+struct ColumnFamily {
+    handle: ColumnFamilyHandle,
+    db: DB,
+}
+
+/// State a column family shares with the DB that owns it: the sequence
+/// space, the snapshot list (so its compactions respect every snapshot),
+/// and the fail-stop background error.
+#[derive(Clone)]
+struct FamilyLink {
+    id: u32,
+    /// `Some(wal)` when the family is being created: its fresh MANIFEST
+    /// records `id` and starts replay at WAL `wal`.
+    create_at_wal: Option<u64>,
+    sequence: Arc<AtomicU64>,
+    committed_sequence: Arc<AtomicU64>,
+    snapshot_list: Arc<SnapshotList>,
+    has_bg_error: Arc<AtomicBool>,
+    bg_error: Arc<Mutex<Option<String>>>,
+    file_deletion_gate: Arc<RwLock<()>>,
+}
+
+impl FamilyLink {
+    /// Fresh shared state for a top-level DB.
+    fn root() -> Self {
+        Self {
+            id: 0,
+            create_at_wal: None,
+            sequence: Arc::new(AtomicU64::new(0)),
+            committed_sequence: Arc::new(AtomicU64::new(0)),
+            snapshot_list: Arc::new(SnapshotList::new()),
+            has_bg_error: Arc::new(AtomicBool::new(false)),
+            bg_error: Arc::new(Mutex::new(None)),
+            file_deletion_gate: Arc::new(RwLock::new(())),
+        }
+    }
+
+    fn child(&self, id: u32, create_at_wal: Option<u64>) -> Self {
+        Self {
+            id,
+            create_at_wal,
+            ..self.clone()
+        }
+    }
+}
+
+/// How an opened handle may use the store directory.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum OpenMode {
+    ReadWrite,
+    /// No writes; a shared `LOCK` keeps writers out.
+    ReadOnly,
+    /// No writes and no `LOCK`: a primary keeps writing the directory, and
+    /// the handle follows it with `try_catch_up_with_primary`.
+    Secondary,
+}
+
+/// Where a secondary instance stands in the primary's WALs. Its private
+/// memtables hold every record of the WALs at or above `log_numbers`, up
+/// to the reader's position in the newest one.
+#[derive(Default)]
+struct SecondaryTail {
+    /// Log number of each family (default family `0`) the memtables were
+    /// rebuilt for; a flush on the primary advances one and forces a rebuild.
+    log_numbers: BTreeMap<u32, u64>,
+    /// The WAL being tailed, read up to the end of its last complete record.
+    wal: Option<(u64, WalReader)>,
+    /// Highest sequence replayed into the memtables.
+    max_sequence: SequenceNumber,
+    /// A failed catch-up may have left the version sets half updated.
+    stale: bool,
+}
+
+/// Hard-link `src` to `dst`, falling back to a synced copy when linking is
+/// not possible (e.g. `dst` is on another filesystem).
+fn link_or_copy(src: &Path, dst: &Path) -> Result<()> {
+    if fs::hard_link(src, dst).is_ok() {
+        return Ok(());
+    }
+    copy_synced(src, dst)
+}
+
+fn copy_synced(src: &Path, dst: &Path) -> Result<()> {
+    fs::copy(src, dst).ctx()?;
+    fs::File::open(dst).ctx()?.sync_all().ctx()?;
+    Ok(())
+}
+
+/// Per-key progress of a [`DB::multi_get`] as it walks the sources.
+struct MultiGetState {
+    max_tomb_seq: SequenceNumber,
+    /// Merge operands collected so far, newest first.
+    operands: Vec<Vec<u8>>,
+    /// Set once the key is resolved (or failed).
+    result: Option<Result<Option<Vec<u8>>>>,
+}
+
+/// An SST file written by `SstFileWriter`, validated by a full scan and
+/// staged for [`DB::ingest_external_file`].
+struct ExternalFile {
+    path: PathBuf,
+    reader: Arc<TableReader>,
+    /// Inclusive user-key extent, range-deletion ends included.
+    smallest_user_key: Vec<u8>,
+    largest_user_key: Vec<u8>,
+    /// Smallest and largest internal keys, as `FileMetaData` records them.
+    smallest_key: Vec<u8>,
+    largest_key: Vec<u8>,
+    /// Range tombstones `[begin, end)`, sorted by begin.
+    tombstones: Vec<(Vec<u8>, Vec<u8>)>,
+    file_size: u64,
+}
+
+impl ExternalFile {
+    fn inspect(path: &Path, icmp: &InternalKeyComparator) -> Result<Self> {
+        let reader = Arc::new(TableReader::open_with_all(path, 0, None, None, icmp.clone()).ctx()?);
+        let invalid = |what: &str| {
+            Error::invalid_argument(format!(
+                "{} is not an ingestible SST file: {}",
+                path.display(),
+                what
+            ))
+        };
+        let mut bounds: Option<(Vec<u8>, Vec<u8>)> = None;
+        let mut iter = TableIterator::new(reader.clone()).with_fill_cache(false);
+        for (key, _) in &mut iter {
+            let (_, seq, vt) = types::decode_internal_key(&key).ctx()?;
+            if seq != 0 {
+                return Err(invalid("entries carry sequence numbers"));
+            }
+            if !matches!(vt, ValueType::Value | ValueType::Deletion) {
+                return Err(invalid("unsupported entry type"));
+            }
+            match &mut bounds {
+                Some((_, largest)) if icmp.compare(largest, &key) != CmpOrdering::Less => {
+                    return Err(invalid("keys are not sorted by the DB's comparator"));
+                }
+                Some((_, largest)) => *largest = key,
+                None => bounds = Some((key.clone(), key)),
+            }
+        }
+        if let Some(e) = iter.iter_error() {
+            return Err(Error::corruption(e)).ctx();
+        }
+        let mut tombstones = Vec::new();
+        for (begin, end, seq) in reader.get_range_tombstones().ctx()? {
+            if seq != 0 {
+                return Err(invalid("range deletions carry sequence numbers"));
+            }
+            if !icmp.user_lt(&begin, &end) {
+                return Err(invalid("range deletion is empty under the DB's comparator"));
+            }
+            tombstones.push((begin, end));
+        }
+        tombstones.sort_by(|a, b| icmp.compare_user(&a.0, &b.0).then_with(|| a.1.cmp(&b.1)));
+
+        let mut smallest_user_key = None::<Vec<u8>>;
+        let mut largest_user_key = None::<Vec<u8>>;
+        let mut extend = |lo: &[u8], hi: &[u8]| {
+            if smallest_user_key
+                .as_deref()
+                .is_none_or(|s| icmp.user_lt(lo, s))
+            {
+                smallest_user_key = Some(lo.to_vec());
+            }
+            if largest_user_key
+                .as_deref()
+                .is_none_or(|l| icmp.user_lt(l, hi))
+            {
+                largest_user_key = Some(hi.to_vec());
+            }
+        };
+        if let Some((smallest, largest)) = &bounds {
+            extend(types::user_key(smallest), types::user_key(largest));
+        }
+        for (begin, end) in &tombstones {
+            extend(begin, end);
+            let begin_key = InternalKey::new(begin, 0, ValueType::RangeDeletion).into_bytes();
+            match &mut bounds {
+                Some((smallest, largest)) => {
+                    if icmp.compare(&begin_key, smallest) == CmpOrdering::Less {
+                        *smallest = begin_key.clone();
+                    }
+                    if icmp.compare(&begin_key, largest) == CmpOrdering::Greater {
+                        *largest = begin_key;
+                    }
+                }
+                None => bounds = Some((begin_key.clone(), begin_key)),
+            }
+        }
+        let (Some((smallest_key, largest_key)), Some(lo), Some(hi)) =
+            (bounds, smallest_user_key, largest_user_key)
+        else {
+            return Err(invalid("no entries"));
+        };
+        Ok(Self {
+            path: path.to_path_buf(),
+            file_size: fs::metadata(path).ctx()?.len(),
+            reader,
+            smallest_user_key: lo,
+            largest_user_key: hi,
+            smallest_key,
+            largest_key,
+            tombstones,
+        })
+    }
+
+    /// Copy the file to `dst` with every entry at sequence `seq`.
+    fn rewrite(
+        &self,
+        dst: &Path,
+        seq: SequenceNumber,
+        build_opts: TableBuildOptions,
+    ) -> Result<TableBuildResult> {
+        let mut builder = TableBuilder::new(dst, build_opts).ctx()?;
+        let icmp = self.reader.comparator();
+        let mut tombstones = self.tombstones.iter().peekable();
+        let mut iter = TableIterator::new(self.reader.clone()).with_fill_cache(false);
+        for (key, value) in &mut iter {
+            let (uk, _, vt) = types::decode_internal_key(&key).ctx()?;
+            // At one user key a range deletion sorts before point entries.
+            while let Some((begin, end)) = tombstones.next_if(|(begin, _)| icmp.user_le(begin, uk))
+            {
+                let begin_key = InternalKey::new(begin, seq, ValueType::RangeDeletion);
+                builder.add(begin_key.as_bytes(), end).ctx()?;
+            }
+            builder
+                .add(InternalKey::new(uk, seq, vt).as_bytes(), &value)
+                .ctx()?;
+        }
+        if let Some(e) = iter.iter_error() {
+            return Err(Error::corruption(e)).ctx();
+        }
+        for (begin, end) in tombstones {
+            let begin_key = InternalKey::new(begin, seq, ValueType::RangeDeletion);
+            builder.add(begin_key.as_bytes(), end).ctx()?;
+        }
+        builder.finish().ctx()
+    }
+
+    fn overlaps(&self, files: &[TableFile]) -> bool {
+        files_overlap_user_range(files, &self.smallest_user_key, &self.largest_user_key)
+    }
+}
+
+/// Directory holding column family `id` inside the DB directory.
+fn family_dir(db_path: &Path, id: u32) -> PathBuf {
+    db_path.join(format!("cf-{}", id))
+}
+
 /// Atomically install a fresh SuperVersion into the given `ArcSwap`.
 /// Shared between `DB::install_super_version` and compaction threads
 /// (which only hold an `Arc<ArcSwap<…>>`, not `&DB`).
@@ -336,6 +808,8 @@ impl Drop for CompactionClaim {
 pub struct DB {
     path: PathBuf,
     options: DbOptions,
+    /// Internal key order built from `options.comparator`.
+    icmp: InternalKeyComparator,
     /// Whether this handle was opened without write capability.
     read_only: bool,
     inner: Arc<Mutex<DBInner>>,
@@ -343,9 +817,13 @@ pub struct DB {
     sequence: Arc<AtomicU64>,
     /// Last sequence number committed and visible to readers/snapshots.
     committed_sequence: Arc<AtomicU64>,
+    /// Last sequence number known to survive a crash: advanced after each
+    /// WAL fsync to the sequences logged before it.
+    durable_sequence: AtomicU64,
     /// Write queue and condvar for group commit.
     write_queue: Mutex<WriteQueueState>,
     write_cv: Condvar,
+    write_pipeline: WritePipeline,
     closed: AtomicBool,
     /// Fast-check flag for background errors (avoids Mutex lock on every read).
     has_bg_error: Arc<AtomicBool>,
@@ -367,6 +845,14 @@ pub struct DB {
     compaction_notify: Arc<(StdMutex<bool>, StdCondvar)>,
     /// Background compaction thread handles.
     compaction_handles: Mutex<Vec<JoinHandle<()>>>,
+    /// Flush pipeline shared with the background flush threads.
+    flusher: Arc<Flusher>,
+    /// Background flush thread handles.
+    flush_handles: Mutex<Vec<JoinHandle<()>>>,
+    /// Shutdown flag and wakeup for the stats dump thread.
+    stats_dump_shutdown: Arc<(StdMutex<bool>, StdCondvar)>,
+    /// The `stats_dump_period` thread, if any.
+    stats_dump_handle: Mutex<Option<JoinHandle<()>>>,
     /// File numbers currently claimed by an in-progress compaction pick
     /// (between `pick_compaction` and `install_compaction`/discard). Lets
     /// concurrent background compaction threads (multiple
@@ -381,6 +867,8 @@ pub struct DB {
     /// Cached L0 file count for fast write-throttle checks.
     /// Updated after flush/compaction. Avoids locking `inner` on every write.
     l0_file_count: Arc<AtomicUsize>,
+    /// Last `WriteStallCondition` reported to the listeners.
+    write_stall: AtomicU8,
     /// Lock-free read snapshot (SuperVersion).
     /// Readers do a single atomic load. Writers swap atomically after
     /// memtable/version changes — no RwLock contention.
@@ -391,6 +879,14 @@ pub struct DB {
     read_counter: AtomicU64,
     /// Tracks active snapshots for compaction safety.
     snapshot_list: Arc<SnapshotList>,
+    /// Mirror of the MANIFEST's `full_history_ts_low`, readable without
+    /// locking `inner`: reads below it are rejected and compaction may
+    /// collapse the versions it hides.
+    full_history_ts_low: Arc<RwLock<Option<Vec<u8>>>>,
+    /// Held shared by every deletion of SST or orphan files and exclusively
+    /// by `create_checkpoint` while it links the files it captured. Shared
+    /// with the column families.
+    file_deletion_gate: Arc<RwLock<()>>,
     /// Directory lock (`LOCK` file): on Unix, exclusive for writable handles,
     /// shared for read-only handles, and absent for a read-only immutable
     /// snapshot that does not contain `LOCK`. The file handle holds the flock;
@@ -409,6 +905,15 @@ pub struct DB {
     /// successive capped passes cover the whole set instead of repeatedly
     /// probing the same iteration prefix.
     dead_key_prune_cursor: AtomicUsize,
+    /// Column family id of this instance: 0 for a top-level DB, the
+    /// family's id for an engine owned by a parent DB.
+    family_id: u32,
+    /// Open non-default column families, by id (top-level DB only).
+    column_families: Arc<RwLock<BTreeMap<u32, Arc<ColumnFamily>>>>,
+    /// What WAL replay at open applied and skipped.
+    wal_recovery: WalRecoverySummary,
+    /// Replay position of a secondary instance (top-level DB only).
+    secondary: Option<Mutex<SecondaryTail>>,
 }
 
 // SAFETY: the raw `*mut WriteRequest` pointers held in `write_queue` reference
@@ -417,7 +922,10 @@ pub struct DB {
 // under the write_queue lock; after draining a group, the single active leader
 // holds exclusive ownership of the drained pointers — it may dereference them
 // without the lock in `write_batch_group` — until it re-acquires the lock, sets
-// `done`, and wakes the owners. No two threads ever access a request concurrently.
+// `done`, and wakes the owners. The one exception is a concurrent memtable
+// apply: the leader hands a follower its `ApplyTask` under the lock, and the
+// follower then reads only its own batch while the leader waits on the
+// group's latch. No two threads ever mutate a request concurrently.
 // SAFETY: DB's shared mutable state is behind Arc + parking_lot locks or atomics;
 // raw request pointers are stack-local to write_queue waiters and never stored in DB.
 unsafe impl Send for DB {}
@@ -427,12 +935,38 @@ unsafe impl Sync for DB {}
 
 struct DBInner {
     active_memtable: Arc<MemTable>,
+    /// Frozen memtables not yet installed as SSTs, newest first.
     immutable_memtables: Vec<Arc<MemTable>>,
+    /// Background flushes of the frozen memtables, oldest first.
+    flush_queue: VecDeque<FlushJob>,
     wal_writer: Option<WalWriter>,
     wal_number: u64,
+    /// Last sequence assigned by a group logged to the WAL; a WAL fsync
+    /// makes everything up to here durable, except the writes in
+    /// `unlogged_memtables`.
+    logged_sequence: SequenceNumber,
+    /// Memtables (of this DB or its column families) holding `disable_wal`
+    /// writes, each with the first such sequence. Until a memtable is
+    /// flushed, the durable watermark stays below that sequence.
+    unlogged_memtables: Vec<(Arc<MemTable>, SequenceNumber)>,
     versions: VersionSet,
 }
 
+/// A queued memtable flush. Jobs may build their SSTs concurrently but are
+/// installed strictly oldest first: each install advances `log_number` to
+/// the job's `new_wal_number`, which must never pass the WAL of an older
+/// memtable that is still unflushed, and L0 must stay ordered newest first.
+struct FlushJob {
+    frozen: Arc<FrozenMemtable>,
+    /// Claimed by a thread building its SSTs.
+    running: bool,
+    /// Built output waiting for every older job to install.
+    built: Option<(FlushOutput, PreparedPins, FlushJobInfo)>,
+}
+
+/// First data blocks read for L0 pinning, by file number.
+type PreparedPins = Vec<(u64, PreparedBlockPin)>;
+
 /// Snapshot of the read-visible state: memtables + current version.
 ///
 /// Models RocksDB's `SuperVersion`. Published atomically via `ArcSwap`
@@ -441,98 +975,538 @@ struct DBInner {
 struct SuperVersion {
     active_memtable: Arc<MemTable>,
     immutable_memtables: Vec<Arc<MemTable>>,
-    version: Arc<crate::manifest::version::Version>,
+    version: Arc<Version>,
 }
 
-/// Internal compaction filter that checks the dead-keys set before
-/// delegating to an optional user-provided filter.
-struct LazyDeleteFilter {
-    dead_keys: Arc<RwLock<HashSet<Vec<u8>>>>,
-    user_filter: Option<Arc<dyn CompactionFilter>>,
+/// The memtable flush pipeline: SST build, install, and WAL retirement.
+///
+/// Shared between a `DB` handle and its background flush threads, which
+/// only hold an `Arc<Flusher>`, not `&DB`. The fields mirror the `DB`
+/// fields of the same names.
+struct Flusher {
+    path: PathBuf,
+    options: DbOptions,
+    icmp: InternalKeyComparator,
+    inner: Arc<Mutex<DBInner>>,
+    committed_sequence: Arc<AtomicU64>,
+    table_cache: Arc<TableCache>,
+    stats: Arc<DbStats>,
+    snapshot_list: Arc<SnapshotList>,
+    l0_file_count: Arc<AtomicUsize>,
+    super_version: Arc<ArcSwap<SuperVersion>>,
+    has_bg_error: Arc<AtomicBool>,
+    bg_error: Arc<Mutex<Option<String>>>,
+    manifest_poisoned: Arc<AtomicBool>,
+    compaction_notify: Arc<(StdMutex<bool>, StdCondvar)>,
+    column_families: Arc<RwLock<BTreeMap<u32, Arc<ColumnFamily>>>>,
+    /// Waited on with `inner` held — by flush threads for queued jobs, by
+    /// writers and explicit flushes for the queue to drain. Notified on
+    /// every queue change, flush failure and shutdown.
+    queue_changed: Condvar,
+    /// Tells the background flush threads to exit. Queued jobs are left
+    /// behind; their WALs are replayed on the next open.
+    shutdown: AtomicBool,
 }
 
-impl CompactionFilter for LazyDeleteFilter {
-    fn filter(&self, level: usize, key: &[u8], value: &[u8]) -> CompactionFilterDecision {
-        {
-            let set = self.dead_keys.read();
-            if set.contains(key) {
-                return CompactionFilterDecision::Remove;
-            }
+impl Flusher {
+    /// Record a flush failure and wake every queue waiter. Must not be
+    /// called with `inner` held.
+    fn set_bg_error(&self, msg: String) {
+        tracing::error!("{}", msg);
+        *self.bg_error.lock() = Some(msg.clone());
+        if !self.has_bg_error.swap(true, Ordering::AcqRel) {
+            listener::notify_background_error(&self.options.listeners, Error::background(msg));
         }
-        if let Some(ref f) = self.user_filter {
-            return f.filter(level, key, value);
+        let _inner = self.inner.lock();
+        self.queue_changed.notify_all();
+    }
+
+    /// Signal background compaction threads (non-blocking).
+    fn signal_compaction(&self) {
+        let (lock, cvar) = &*self.compaction_notify;
+        if let Ok(mut has_work) = lock.lock() {
+            *has_work = true;
+            cvar.notify_one();
         }
-        CompactionFilterDecision::Keep
     }
 
-    fn is_noop(&self) -> bool {
-        // No-op only when there is no user filter to consult AND no key is
-        // currently registered for lazy deletion. A dead key registered after a
-        // trivial move is still removed by a later compaction that re-examines
-        // the relocated file, so allowing the move here is safe.
-        self.user_filter.as_ref().is_none_or(|f| f.is_noop()) && self.dead_keys.read().is_empty()
+    /// Queue a just-frozen memtable for flushing. Callers hold `inner`.
+    fn enqueue(&self, inner: &mut DBInner, frozen: FrozenMemtable) {
+        inner.flush_queue.push_back(FlushJob {
+            frozen: Arc::new(frozen),
+            running: false,
+            built: None,
+        });
+        self.queue_changed.notify_all();
     }
-}
 
-/// Append the `(begin, end, seq)` tombstones that can cover a key inside the
-/// `[lower, upper)` window to `dst`, tagged with their source `level`.
-/// Shared by every bounded iterator constructor so a future tombstone source
-/// cannot miss the bounds filter.
-fn collect_tombstones_overlapping_bounds(
-    dst: &mut Vec<(Vec<u8>, Vec<u8>, SequenceNumber, usize)>,
-    src: impl IntoIterator<Item = (Vec<u8>, Vec<u8>, SequenceNumber)>,
-    level: usize,
-    lower: Option<&[u8]>,
-    upper: Option<&[u8]>,
-) {
-    for (b, e, s) in src {
-        if tombstone_overlaps_bounds(&b, &e, lower, upper) {
-            dst.push((b, e, s, level));
-        }
+    /// Whether a queued job is waiting for a thread to build it.
+    fn has_unclaimed_job(&self, inner: &DBInner) -> bool {
+        !self.has_bg_error.load(Ordering::Acquire)
+            && inner.flush_queue.iter().any(|job| !job.running)
     }
-}
 
-// Test-only seam for deterministically racing a concurrent write against
-// `prune_settled_dead_keys`'s window between its unlocked absence probe and
-// its locked re-verify-and-remove step. Thread-local (not a `DB` field) so
-// it cannot leak across concurrently-running tests on other threads, and
-// `#[cfg(test)]`-only so it never compiles into `test-utils` or release
-// builds. Unused outside that one regression test.
-#[cfg(test)]
-thread_local! {
-    static PRUNE_SETTLED_RACE_HOOK: std::cell::RefCell<Option<Box<dyn FnOnce()>>> =
-        const { std::cell::RefCell::new(None) };
-    /// Counts `write_queue` acquisitions made by `prune_settled_dead_keys`'s
-    /// re-verify phase, so a test can prove that phase is chunked instead of
-    /// holding the writer barrier across every settled key. Thread-local for
-    /// the same cross-test-isolation reason as the hook above.
-    static PRUNE_SETTLED_LOCK_COUNT: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
-}
+    /// Body of a background flush thread.
+    fn run_background(&self) {
+        loop {
+            {
+                let mut inner = self.inner.lock();
+                while !self.shutdown.load(Ordering::Acquire) && !self.has_unclaimed_job(&inner) {
+                    self.queue_changed.wait(&mut inner);
+                }
+            }
+            if self.shutdown.load(Ordering::Acquire) {
+                return;
+            }
+            // The job claimed by a panicking build stays claimed, so fail-stop
+            // rather than leave its waiters hanging.
+            if let Err(payload) = catch_unwind(AssertUnwindSafe(|| self.run_next())) {
+                let msg = if let Some(s) = payload.downcast_ref::<String>() {
+                    format!("flush thread panicked: {}", s)
+                } else if let Some(s) = payload.downcast_ref::<&str>() {
+                    format!("flush thread panicked: {}", s)
+                } else {
+                    "flush thread panicked with unknown payload".to_string()
+                };
+                self.set_bg_error(msg);
+            }
+        }
+    }
 
-// Test-only seam for `DB::open`'s compaction-worker spawn loop: forces the
-// spawn at this 0-based worker index to be reported as failed, so a test
-// can exercise partial-startup cleanup without needing real OS thread
-// exhaustion. Thread-local for the same cross-test-isolation reason as
-// `PRUNE_SETTLED_RACE_HOOK` above.
-#[cfg(test)]
-thread_local! {
-    static FAIL_COMPACTION_SPAWN_AT: std::cell::Cell<Option<usize>> =
-        const { std::cell::Cell::new(None) };
-    /// `DB::open` deposits its `compaction_shutdown` flag here on every
-    /// call. Every worker thread — including one whose spawn is forced to
-    /// fail above, since its (detached) real thread still shares this same
-    /// `Arc` — holds a clone for its entire life, so a test can poll
-    /// `Arc::strong_count` back down to the sink's own reference to confirm
-    /// every worker actually exited instead of leaking.
-    static LAST_COMPACTION_SHUTDOWN_FLAG: std::cell::RefCell<Option<Arc<AtomicBool>>> =
-        const { std::cell::RefCell::new(None) };
-}
+    /// Build and install queued flushes until none is left unclaimed, on
+    /// the calling thread: explicit flushes help the flush threads rather
+    /// than wait idle.
+    fn run_queued(&self) -> Result<()> {
+        while self.run_next()? {}
+        Ok(())
+    }
 
-impl DB {
-    /// Open an existing database without writing to its store directory.
-    ///
-    /// Valid records in residual WAL files are recovered into memory. Reads,
-    /// iterators, snapshots, properties, [`close`](Self::close), and `Drop`
+    /// Claim and run the oldest unclaimed job. `false` when there is none.
+    /// A failure fail-stops: the failed job stays at the front of the queue,
+    /// so no newer memtable can install past its WAL.
+    fn run_next(&self) -> Result<bool> {
+        let frozen = {
+            let mut inner = self.inner.lock();
+            if self.has_bg_error.load(Ordering::Acquire) {
+                return Ok(false);
+            }
+            let Some(job) = inner.flush_queue.iter_mut().find(|job| !job.running) else {
+                return Ok(false);
+            };
+            job.running = true;
+            job.frozen.clone()
+        };
+        if let Err(e) = self.run_job(&frozen) {
+            self.set_bg_error(format!("memtable flush failed: {}", e));
+            return Err(e);
+        }
+        Ok(true)
+    }
+
+    /// Build one claimed job, then install it along with any younger jobs
+    /// that finished first.
+    fn run_job(&self, frozen: &Arc<FrozenMemtable>) -> Result<()> {
+        // Recovery only tolerates a torn tail in the newest WAL, and this
+        // memtable's WAL may have sat closed in the queue for a while.
+        let wal_path = self.path.join(format!("{:06}.wal", frozen.old_wal_number));
+        fs::File::open(&wal_path)
+            .and_then(|f| f.sync_all())
+            .with_ctx(|| format!("failed to sync closed WAL {}", wal_path.display()))?;
+
+        let started = Instant::now();
+        let output = self.flush_frozen_memtable(frozen)?;
+        let prepared_pins = self.prepare_l0_block_pins(&output.sst_numbers());
+        let info = output.job_info(frozen.old_wal_number, started.elapsed());
+        self.stats
+            .record_duration(HistogramKind::Flush, info.duration);
+
+        let mut installed = Vec::new();
+        {
+            let mut inner = self.inner.lock();
+            if let Some(job) = inner
+                .flush_queue
+                .iter_mut()
+                .find(|job| Arc::ptr_eq(&job.frozen, frozen))
+            {
+                job.built = Some((output, prepared_pins, info));
+            }
+            while let Some(job) = inner.flush_queue.front_mut()
+                && let Some((output, prepared_pins, info)) = job.built.take()
+            {
+                let frozen = job.frozen.clone();
+                if let Err(e) = self.install_flush(&mut inner, &frozen, &output, prepared_pins) {
+                    // log_and_apply did not persist the edit.
+                    output.remove_files(&self.path);
+                    return Err(e);
+                }
+                inner.flush_queue.pop_front();
+                installed.push(info);
+            }
+            self.queue_changed.notify_all();
+        }
+
+        for info in &installed {
+            self.post_flush_cleanup(info.wal_number).ctx()?;
+            for listener in &self.options.listeners {
+                listener.on_flush_completed(info);
+            }
+        }
+        if !installed.is_empty() {
+            self.signal_compaction();
+        }
+        Ok(())
+    }
+
+    /// Block until every queued flush has been installed, or a flush has
+    /// failed (the caller checks for the background error).
+    fn wait_until_flushed(&self) {
+        let mut inner = self.inner.lock();
+        while !inner.flush_queue.is_empty() && !self.has_bg_error.load(Ordering::Acquire) {
+            self.queue_changed.wait(&mut inner);
+        }
+    }
+
+    fn build_opts(&self) -> TableBuildOptions {
+        let compression = if !self.options.compression_per_level.is_empty() {
+            self.options.compression_per_level[0]
+        } else {
+            self.options.compression
+        };
+        TableBuildOptions {
+            block_size: self.options.block_size,
+            block_restart_interval: self.options.block_restart_interval,
+            bloom_bits_per_key: self.options.bloom_bits_per_key,
+            internal_keys: true,
+            compression,
+            prefix_len: self.options.prefix_len,
+            block_property_collectors: self
+                .options
+                .block_property_collectors
+                .iter()
+                .map(|f| f())
+                .collect(),
+            comparator: self.icmp.clone(),
+        }
+    }
+
+    /// Phase 2 (no lock needed, slow I/O): write SSTs from frozen memtable.
+    fn flush_frozen_memtable(&self, frozen: &FrozenMemtable) -> Result<FlushOutput> {
+        let mut numbers = frozen.sst_numbers.iter().copied();
+        let output = DB::write_memtable_ssts(
+            &frozen.old_mem,
+            &self.path,
+            &|| self.build_opts(),
+            &mut || {
+                numbers.next().ok_or_else(|| {
+                    Error::invalid_argument(
+                        "reserved flush output file numbers exhausted".to_string(),
+                    )
+                })
+            },
+            self.options.merge_operator.as_deref(),
+            &self.snapshot_list.as_sorted_vec(),
+            self.options.min_blob_size,
+        )?;
+        // Pre-warm the table cache for the new SSTs while unlocked, so
+        // install_flush's log_and_apply (which opens each new file to
+        // install its reader) hits a warm cache instead of parsing
+        // footers/indexes while db_mutex is held.
+        self.table_cache
+            .prewarm(output.tables.iter().map(|(number, _)| *number));
+        Ok(output)
+    }
+
+    /// Read, checksum, and decompress each listed file's first data block
+    /// for L0 pinning. Does not itself take `inner` — the caller controls
+    /// whether the DB lock is held. Meant to be called from the same
+    /// unlocked phase as `flush_frozen_memtable`, right before the short
+    /// locked `install_flush` call, so a legal block up to the ~64 MiB
+    /// max write-entry size is read unlocked and `install_flush` only has
+    /// to publish these already-prepared blocks, a cheap in-memory cache
+    /// insert. Both callers — queued flush jobs and
+    /// `DB::flush_and_install_frozen` — do so. Returns an empty `Vec`
+    /// (no-op) when `pin_l0_filter_and_index_blocks_in_cache` is disabled.
+    fn prepare_l0_block_pins(&self, file_numbers: &[u64]) -> Vec<(u64, PreparedBlockPin)> {
+        if !self.options.pin_l0_filter_and_index_blocks_in_cache {
+            return Vec::new();
+        }
+        file_numbers
+            .iter()
+            .filter_map(|&number| {
+                // Cache hit: `flush_frozen_memtable`'s prewarm (or an
+                // earlier iteration of this same loop's own get_reader
+                // call) already opened and cached this reader.
+                let reader = self.table_cache.get_reader(number).ok()?;
+                let prepared = reader.prepare_first_block_pin()?;
+                Some((number, prepared))
+            })
+            .collect()
+    }
+
+    /// Phase 3 (under lock, fast): install flush result into version.
+    fn install_flush(
+        &self,
+        inner: &mut DBInner,
+        frozen: &FrozenMemtable,
+        output: &FlushOutput,
+        prepared_pins: Vec<(u64, PreparedBlockPin)>,
+    ) -> Result<()> {
+        let mut edit = VersionEdit::new();
+        edit.set_log_number(frozen.new_wal_number);
+        edit.set_next_file_number(inner.versions.next_file_number());
+        edit.set_last_sequence(self.committed_sequence.load(Ordering::Acquire));
+        for (number, build_result) in &output.tables {
+            edit.add_file(
+                0, // L0
+                FileMetaData {
+                    number: *number,
+                    file_size: build_result.file_size,
+                    smallest_key: build_result.smallest_key.clone().unwrap_or_default(),
+                    largest_key: build_result.largest_key.clone().unwrap_or_default(),
+                    has_range_deletions: build_result.has_range_deletions,
+                    creation_time: self.options.clock.now_millis(),
+                },
+            );
+        }
+        if let Some(ref blob) = output.blob_file {
+            edit.add_blob_file(blob.clone());
+        }
+        inner.versions.log_and_apply(edit).ctx()?;
+        // Marked under the lock, so a flush that has returned has also
+        // released the durable watermark held by its `disable_wal` writes.
+        frozen.old_mem.mark_flushed();
+        self.stats
+            .record_flush(output.tables.iter().map(|(_, r)| r.file_size).sum());
+        inner
+            .immutable_memtables
+            .retain(|m| !Arc::ptr_eq(m, &frozen.old_mem));
+
+        // Publish each already-read/checksummed/decompressed first block
+        // (prepared by `prepare_l0_block_pins`, unlocked on all but the
+        // `close()` path — see that function's doc comment) into the
+        // shared cache — an in-memory map insert only, no I/O, so this
+        // stays cheap under the lock regardless of block size.
+        if !prepared_pins.is_empty() {
+            let version = inner.versions.current();
+            for (number, prepared) in prepared_pins {
+                if let Some(tf) = version
+                    .level_files(0)
+                    .iter()
+                    .find(|tf| tf.meta.number == number)
+                {
+                    tf.reader.publish_prepared_pin(prepared);
+                }
+            }
+        }
+
+        self.l0_file_count
+            .store(inner.versions.current().l0_file_count(), Ordering::Relaxed);
+        refresh_super_version(&self.super_version, inner);
+
+        // WAL deletion is deferred: callers must sync the manifest first,
+        // then delete the old WAL file outside the main lock.
+        Ok(())
+    }
+
+    /// Sync the manifest and delete the old WAL after install_flush.
+    /// Must be called outside the main lock.
+    ///
+    /// By the time this runs, `install_flush` has already applied the
+    /// VersionEdit and removed the memtable from `immutable_memtables`, so
+    /// the only remaining risk is manifest durability: a later successful
+    /// flush would advance `log_number` past this WAL (stale reads + lost
+    /// recovery), so a sync failure here fail-stops via `set_bg_error` —
+    /// a background flush job has no caller to propagate the failure to.
+    fn post_flush_cleanup(&self, old_wal_number: u64) -> Result<()> {
+        let manifest_handle = self.inner.lock().versions.manifest_sync_handle();
+        if let Err(e) = confirm_manifest_durable(&manifest_handle, &self.manifest_poisoned) {
+            // Poison first: a later "successful" sync could falsely
+            // report durability of the records this sync failed to
+            // persist (fsyncgate). confirm_manifest_durable emits poison
+            // for fresh sync errors; already-poisoned rotation is fail-closed.
+            self.set_bg_error(format!("post-flush manifest sync failed: {}", e));
+            return Err(e).ctx();
+        }
+        if self.column_families.read().is_empty() {
+            if let Err(e) = archive::retire_wal(&self.path, old_wal_number, &self.options) {
+                tracing::warn!("failed to retire old WAL {:06}: {}", old_wal_number, e);
+            }
+        } else {
+            // Column families may still need the old WAL.
+            self.remove_obsolete_wals();
+        }
+        archive::purge_archive(&self.path, &self.options);
+        Ok(())
+    }
+
+    /// Delete the WAL files no column family needs any more: everything
+    /// below the oldest log number of a family that still has unflushed
+    /// memtable data. Every MANIFEST is synced first, so no deletion can
+    /// outrun the flush record that made a WAL obsolete. Failures are logged;
+    /// leftover WALs are collected by the next open.
+    fn remove_obsolete_wals(&self) {
+        let families: Vec<Arc<ColumnFamily>> =
+            self.column_families.read().values().cloned().collect();
+        let mut manifests = Vec::with_capacity(families.len() + 1);
+        let floor = {
+            let inner = self.inner.lock();
+            let needs_wal =
+                |g: &DBInner| !g.active_memtable.is_empty() || !g.immutable_memtables.is_empty();
+            let mut floor = inner.wal_number;
+            if needs_wal(&inner) {
+                floor = floor.min(inner.versions.log_number());
+            }
+            manifests.push((
+                inner.versions.manifest_sync_handle(),
+                self.manifest_poisoned.clone(),
+            ));
+            for family in &families {
+                let g = family.db.inner.lock();
+                if needs_wal(&g) {
+                    floor = floor.min(g.versions.log_number());
+                }
+                manifests.push((
+                    g.versions.manifest_sync_handle(),
+                    family.db.manifest_poisoned.clone(),
+                ));
+            }
+            floor
+        };
+        for (handle, poisoned) in &manifests {
+            if let Err(e) = confirm_manifest_durable(handle, poisoned) {
+                tracing::warn!("keeping obsolete WALs: MANIFEST sync failed: {}", e);
+                return;
+            }
+        }
+        let entries = match fs::read_dir(&self.path) {
+            Ok(entries) => entries,
+            Err(e) => {
+                tracing::warn!("WAL cleanup: cannot read DB dir: {}", e);
+                return;
+            }
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name();
+            let obsolete = name
+                .to_string_lossy()
+                .strip_suffix(".wal")
+                .and_then(|s| s.parse::<u64>().ok())
+                .filter(|&num| num < floor);
+            if let Some(num) = obsolete
+                && let Err(e) = archive::retire_wal(&self.path, num, &self.options)
+            {
+                tracing::warn!("failed to retire old WAL {}: {}", entry.path().display(), e);
+            }
+        }
+    }
+}
+
+/// Internal compaction filter that checks the dead-keys set before
+/// delegating to an optional user-provided filter.
+struct LazyDeleteFilter {
+    dead_keys: Arc<RwLock<HashSet<Vec<u8>>>>,
+    user_filter: Option<Arc<dyn CompactionFilter>>,
+}
+
+impl CompactionFilter for LazyDeleteFilter {
+    fn filter(&self, level: usize, key: &[u8], value: &[u8]) -> CompactionFilterDecision {
+        {
+            let set = self.dead_keys.read();
+            if set.contains(key) {
+                return CompactionFilterDecision::Remove;
+            }
+        }
+        if let Some(ref f) = self.user_filter {
+            return f.filter(level, key, value);
+        }
+        CompactionFilterDecision::Keep
+    }
+
+    fn is_noop(&self) -> bool {
+        // No-op only when there is no user filter to consult AND no key is
+        // currently registered for lazy deletion. A dead key registered after a
+        // trivial move is still removed by a later compaction that re-examines
+        // the relocated file, so allowing the move here is safe.
+        self.user_filter.as_ref().is_none_or(|f| f.is_noop()) && self.dead_keys.read().is_empty()
+    }
+}
+
+/// Whether `batch` writes default-family `key`, directly or through a range
+/// deletion.
+fn batch_writes_key(icmp: &InternalKeyComparator, batch: &WriteBatch, key: &[u8]) -> bool {
+    batch
+        .entries
+        .iter()
+        .filter(|e| e.cf == 0)
+        .any(|e| match e.value_type {
+            ValueType::RangeDeletion => {
+                icmp.user_le(&e.key, key)
+                    && e.value.as_deref().is_some_and(|end| icmp.user_lt(key, end))
+            }
+            _ => e.key == key,
+        })
+}
+
+/// Append the `(begin, end, seq)` tombstones that can cover a key inside the
+/// `[lower, upper)` window to `dst`, tagged with their source `level`.
+/// Shared by every bounded iterator constructor so a future tombstone source
+/// cannot miss the bounds filter.
+fn collect_tombstones_overlapping_bounds(
+    icmp: &InternalKeyComparator,
+    dst: &mut Vec<(Vec<u8>, Vec<u8>, SequenceNumber, usize)>,
+    src: impl IntoIterator<Item = (Vec<u8>, Vec<u8>, SequenceNumber)>,
+    level: usize,
+    lower: Option<&[u8]>,
+    upper: Option<&[u8]>,
+) {
+    for (b, e, s) in src {
+        if tombstone_overlaps_bounds(icmp, &b, &e, lower, upper) {
+            dst.push((b, e, s, level));
+        }
+    }
+}
+
+// Test-only seam for deterministically racing a concurrent write against
+// `prune_settled_dead_keys`'s window between its unlocked absence probe and
+// its locked re-verify-and-remove step. Thread-local (not a `DB` field) so
+// it cannot leak across concurrently-running tests on other threads, and
+// `#[cfg(test)]`-only so it never compiles into `test-utils` or release
+// builds. Unused outside that one regression test.
+#[cfg(test)]
+thread_local! {
+    static PRUNE_SETTLED_RACE_HOOK: std::cell::RefCell<Option<Box<dyn FnOnce()>>> =
+        const { std::cell::RefCell::new(None) };
+    /// Counts `write_queue` acquisitions made by `prune_settled_dead_keys`'s
+    /// re-verify phase, so a test can prove that phase is chunked instead of
+    /// holding the writer barrier across every settled key. Thread-local for
+    /// the same cross-test-isolation reason as the hook above.
+    static PRUNE_SETTLED_LOCK_COUNT: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+// Test-only seam for `DB::open`'s compaction-worker spawn loop: forces the
+// spawn at this 0-based worker index to be reported as failed, so a test
+// can exercise partial-startup cleanup without needing real OS thread
+// exhaustion. Thread-local for the same cross-test-isolation reason as
+// `PRUNE_SETTLED_RACE_HOOK` above.
+#[cfg(test)]
+thread_local! {
+    static FAIL_COMPACTION_SPAWN_AT: std::cell::Cell<Option<usize>> =
+        const { std::cell::Cell::new(None) };
+    /// `DB::open` deposits its `compaction_shutdown` flag here on every
+    /// call. Every worker thread — including one whose spawn is forced to
+    /// fail above, since its (detached) real thread still shares this same
+    /// `Arc` — holds a clone for its entire life, so a test can poll
+    /// `Arc::strong_count` back down to the sink's own reference to confirm
+    /// every worker actually exited instead of leaking.
+    static LAST_COMPACTION_SHUTDOWN_FLAG: std::cell::RefCell<Option<Arc<AtomicBool>>> =
+        const { std::cell::RefCell::new(None) };
+}
+
+impl DB {
+    /// Open an existing database without writing to its store directory.
+    ///
+    /// Valid records in residual WAL files are recovered into memory. Reads,
+    /// iterators, snapshots, properties, [`close`](Self::close), and `Drop`
     /// leave the store unchanged. Fallible mutation APIs return
     /// [`ErrorKind::ReadOnly`](crate::ErrorKind::ReadOnly) before side effects;
     /// the existing infallible [`lazy_delete`](Self::lazy_delete) and
@@ -575,17 +1549,361 @@ impl DB {
     /// Recovery, supported operations, errors, and the locking/stable-snapshot
     /// contract are identical to [`open_read_only`](Self::open_read_only).
     pub fn open_read_only_with_options(options: DbOptions, path: impl AsRef<Path>) -> Result<Self> {
-        Self::open_impl(options, path, true)
+        Self::open_impl(options, path, OpenMode::ReadOnly, &[], None)
+    }
+
+    /// Open a secondary instance following the primary process that keeps
+    /// writing the database at `primary_path`.
+    ///
+    /// Unlike [`open_read_only`](Self::open_read_only), this takes no lock
+    /// on the primary's directory, so the primary may keep writing,
+    /// flushing, and compacting. The instance starts at the primary's state
+    /// as of the call and only moves forward in
+    /// [`try_catch_up_with_primary`](Self::try_catch_up_with_primary).
+    /// `secondary_path` is created if needed and holds the instance's own
+    /// exclusive `LOCK`; nothing else is written to either directory.
+    ///
+    /// Reads, iterators, and snapshots work as on a read-only handle, and
+    /// mutation APIs return [`ErrorKind::ReadOnly`](crate::ErrorKind::ReadOnly).
+    /// The column families are those that existed at open. As with
+    /// read-only handles, pass the primary's [`DbOptions::num_levels`] when
+    /// it differs from the default.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ErrorKind::InvalidArgument`](crate::ErrorKind::InvalidArgument)
+    /// if the primary's `CURRENT` is absent, if both paths name the same
+    /// directory, or if another secondary holds `secondary_path`.
+    pub fn open_as_secondary(
+        options: DbOptions,
+        primary_path: impl AsRef<Path>,
+        secondary_path: impl AsRef<Path>,
+    ) -> Result<Self> {
+        let primary_path = primary_path.as_ref();
+        let secondary_path = secondary_path.as_ref();
+        fs::create_dir_all(secondary_path).ctx()?;
+        if fs::canonicalize(primary_path).ok() == Some(fs::canonicalize(secondary_path).ctx()?) {
+            return Err(Error::invalid_argument(format!(
+                "secondary path {} is the primary's directory",
+                secondary_path.display()
+            )));
+        }
+        let lock_file = Self::lock_dir_exclusive(secondary_path)?;
+        // Recovery fails on an SST the primary deletes between the MANIFEST
+        // read and the open; the next attempt reads the newer MANIFEST.
+        let mut attempt = 1;
+        let mut db = loop {
+            match Self::open_impl(
+                options.clone(),
+                primary_path,
+                OpenMode::Secondary,
+                &[],
+                None,
+            ) {
+                Ok(db) => break db,
+                Err(e)
+                    if attempt < SECONDARY_CATCH_UP_ATTEMPTS
+                        && e.kind() == ErrorKind::Corruption =>
+                {
+                    tracing::debug!("secondary open attempt {} failed, retrying: {}", attempt, e);
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        };
+        *db.lock_file.get_mut() = Some(lock_file);
+        db.secondary = Some(Mutex::new(SecondaryTail::default()));
+        db.try_catch_up_with_primary()?;
+        Ok(db)
+    }
+
+    /// Bring a secondary instance up to date with its primary.
+    ///
+    /// Applies the MANIFEST edits the primary has written since the last
+    /// call, then tails its WALs into the instance's private memtables from
+    /// where the last call stopped. Once a primary flush has moved the log
+    /// number on, the memtables are rebuilt from the WALs it left unflushed.
+    ///
+    /// The new state is published atomically when the call returns. Reads
+    /// and iterators that started earlier keep a consistent view even after
+    /// the primary deletes the files behind it; a snapshot taken earlier may
+    /// however miss versions the primary's compactions dropped meanwhile.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ErrorKind::InvalidArgument`](crate::ErrorKind::InvalidArgument)
+    /// on a handle not opened with [`open_as_secondary`](Self::open_as_secondary),
+    /// and [`ErrorKind::Busy`](crate::ErrorKind::Busy) if the primary kept
+    /// moving its log number during every retry. Failures reading the
+    /// primary's files surface once the retries are spent. On error the
+    /// previous state stays published and the next call starts over.
+    pub fn try_catch_up_with_primary(&self) -> Result<()> {
+        self.check_usable()?;
+        let Some(tail) = self.secondary.as_ref() else {
+            return Err(Error::invalid_argument(
+                "not a secondary instance; open it with DB::open_as_secondary",
+            ));
+        };
+        let mut tail = tail.lock();
+        let families: Vec<Arc<ColumnFamily>> =
+            self.column_families.read().values().cloned().collect();
+        // The primary may delete files this call is about to read, or flush
+        // while it reads the WALs; both resolve on a fresh attempt.
+        for attempt in 1..=SECONDARY_CATCH_UP_ATTEMPTS {
+            match self.catch_up_once(&mut tail, &families) {
+                Ok(true) => return Ok(()),
+                Ok(false) => {}
+                Err(e) if attempt < SECONDARY_CATCH_UP_ATTEMPTS => {
+                    tracing::debug!("catch-up attempt {} failed, retrying: {}", attempt, e);
+                }
+                Err(e) => return Err(e).ctx(),
+            }
+        }
+        Err(Error::busy(
+            "the primary kept flushing while the secondary caught up",
+        ))
+    }
+
+    /// One catch-up attempt. `Ok(false)` when the primary moved its log
+    /// number on while the WALs were read, which may have retired WALs the
+    /// memtables needed; nothing is published then.
+    fn catch_up_once(
+        &self,
+        tail: &mut SecondaryTail,
+        families: &[Arc<ColumnFamily>],
+    ) -> Result<bool> {
+        let mut inner = self.inner.lock();
+        let mut family_inners: Vec<_> = families.iter().map(|f| f.db.inner.lock()).collect();
+        // Cleared only on success: any error below leaves state to redo.
+        let recover = mem::replace(&mut tail.stale, true);
+
+        Self::catch_up_versions(&mut inner, families, &mut family_inners, recover)?;
+        let log_numbers = Self::secondary_log_numbers(&inner, families, &family_inners);
+        if recover || log_numbers != tail.log_numbers {
+            inner.active_memtable = Arc::new(MemTable::with_comparator(self.icmp.clone()));
+            for (family, g) in families.iter().zip(family_inners.iter_mut()) {
+                g.active_memtable = Arc::new(MemTable::with_comparator(family.db.icmp.clone()));
+            }
+            tail.log_numbers = log_numbers;
+            tail.wal = None;
+            tail.max_sequence = 0;
+        }
+
+        // List first, then drain the tailed WAL: a WAL the primary has
+        // switched away from by the listing is complete.
+        let min_log = tail.log_numbers.values().copied().min().unwrap_or(0);
+        let from = tail.wal.as_ref().map_or(min_log, |(number, _)| *number);
+        let mut wal_numbers = Vec::new();
+        for entry in fs::read_dir(&self.path).ctx()? {
+            let name = entry.ctx()?.file_name();
+            if let Some(number) = name
+                .to_string_lossy()
+                .strip_suffix(".wal")
+                .and_then(|s| s.parse::<u64>().ok())
+                && number >= from
+            {
+                wal_numbers.push(number);
+            }
+        }
+        wal_numbers.sort_unstable();
+        if let Some((number, _)) = tail.wal
+            && wal_numbers.first() != Some(&number)
+        {
+            // Retired, so the log number has moved past it.
+            return Ok(false);
+        }
+
+        let memtables: Vec<(u32, u64, Arc<MemTable>)> =
+            iter::once((0, tail.log_numbers[&0], inner.active_memtable.clone()))
+                .chain(families.iter().zip(&family_inners).map(|(family, g)| {
+                    let id = family.handle.id;
+                    (id, tail.log_numbers[&id], g.active_memtable.clone())
+                }))
+                .collect();
+        for (i, &number) in wal_numbers.iter().enumerate() {
+            let newest = i + 1 == wal_numbers.len();
+            let mut reader = match tail.wal.take() {
+                Some((tailed, mut reader)) if tailed == number => {
+                    reader.seek_to(reader.last_valid_offset()).ctx()?;
+                    reader
+                }
+                _ => WalReader::new(&self.path.join(archive::wal_file_name(number))).ctx()?,
+            };
+            loop {
+                let data = match reader.read_record() {
+                    Ok(Some(data)) => data,
+                    Ok(None) => break,
+                    // The primary may still be appending this record.
+                    Err(_) if newest && reader.last_error_is_truncation() => break,
+                    Err(e) => {
+                        return Err(e).with_ctx(|| format!("failed to tail WAL {:06}", number));
+                    }
+                };
+                let route = |cf: u32| {
+                    memtables
+                        .iter()
+                        .find(|&&(id, log, _)| id == cf && number >= log)
+                        .map(|(_, _, mem)| &**mem)
+                };
+                Self::replay_wal_record(&data, route, &mut tail.max_sequence).ctx()?;
+            }
+            tail.wal = Some((number, reader));
+        }
+
+        // WALs are retired only after the MANIFEST records the flush that
+        // made them obsolete, so an unchanged log number proves none of the
+        // WALs above was missed.
+        Self::catch_up_versions(&mut inner, families, &mut family_inners, false)?;
+        if Self::secondary_log_numbers(&inner, families, &family_inners) != tail.log_numbers {
+            return Ok(false);
+        }
+
+        self.publish_secondary_state(&inner);
+        for (family, g) in families.iter().zip(&family_inners) {
+            family.db.publish_secondary_state(g);
+        }
+        let committed = family_inners
+            .iter()
+            .map(|g| g.versions.last_sequence())
+            .fold(
+                inner.versions.last_sequence().max(tail.max_sequence),
+                u64::max,
+            );
+        self.committed_sequence
+            .fetch_max(committed, Ordering::AcqRel);
+        self.sequence.fetch_max(committed + 1, Ordering::AcqRel);
+        tail.stale = false;
+        Ok(true)
+    }
+
+    /// Catch up every version set with its MANIFEST. Families the primary
+    /// has dropped keep their last state.
+    fn catch_up_versions(
+        inner: &mut DBInner,
+        families: &[Arc<ColumnFamily>],
+        family_inners: &mut [MutexGuard<'_, DBInner>],
+        recover: bool,
+    ) -> Result<()> {
+        inner.versions.catch_up(recover)?;
+        for (family, g) in families.iter().zip(family_inners.iter_mut()) {
+            if inner
+                .versions
+                .column_families()
+                .contains_key(&family.handle.id)
+            {
+                g.versions.catch_up(recover).with_ctx(|| {
+                    format!("failed to catch up column family {:?}", family.handle.name)
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The log number of the default family (`0`) and of every family.
+    fn secondary_log_numbers(
+        inner: &DBInner,
+        families: &[Arc<ColumnFamily>],
+        family_inners: &[MutexGuard<'_, DBInner>],
+    ) -> BTreeMap<u32, u64> {
+        iter::once((0, inner.versions.log_number()))
+            .chain(
+                families
+                    .iter()
+                    .zip(family_inners)
+                    .map(|(family, g)| (family.handle.id, g.versions.log_number())),
+            )
+            .collect()
+    }
+
+    /// Publish a caught-up memtable and version to readers.
+    fn publish_secondary_state(&self, inner: &DBInner) {
+        self.install_super_version(inner);
+        *self.full_history_ts_low.write() =
+            inner.versions.full_history_ts_low().map(<[u8]>::to_vec);
     }
 
     /// Open or create a database.
+    ///
+    /// Existing column families are opened with a clone of `options`; use
+    /// [`open_with_column_families`](Self::open_with_column_families) to
+    /// give them their own options.
     pub fn open(options: DbOptions, path: impl AsRef<Path>) -> Result<Self> {
-        Self::open_impl(options, path, false)
+        Self::open_impl(options, path, OpenMode::ReadWrite, &[], None)
     }
 
-    fn open_impl(options: DbOptions, path: impl AsRef<Path>, read_only: bool) -> Result<Self> {
+    /// Open or create a database, supplying per-family options.
+    ///
+    /// `options` applies to the default column family. Each listed family is
+    /// opened with its own options, and created if it does not exist yet;
+    /// existing families that are not listed inherit a clone of `options`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ErrorKind::InvalidArgument`](crate::ErrorKind::InvalidArgument)
+    /// for an empty, duplicate, or `"default"` family name.
+    pub fn open_with_column_families<N: Into<String>>(
+        options: DbOptions,
+        path: impl AsRef<Path>,
+        families: impl IntoIterator<Item = (N, DbOptions)>,
+    ) -> Result<Self> {
+        let families: Vec<(String, DbOptions)> = families
+            .into_iter()
+            .map(|(name, opts)| (name.into(), opts))
+            .collect();
+        for (i, (name, _)) in families.iter().enumerate() {
+            Self::validate_family_name(name)?;
+            if families[..i].iter().any(|(other, _)| other == name) {
+                return Err(Error::invalid_argument(format!(
+                    "column family {:?} listed twice",
+                    name
+                )));
+            }
+        }
+        Self::open_impl(options, path, OpenMode::ReadWrite, &families, None)
+    }
+
+    /// Take the exclusive `LOCK` of the directory at `path`, creating the
+    /// lock file if needed.
+    fn lock_dir_exclusive(path: &Path) -> Result<fs::File> {
+        let lock_path = path.join("LOCK");
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .read(true)
+            .write(true)
+            .open(&lock_path)
+            .ctx()?;
+        #[cfg(unix)]
+        {
+            use std::os::fd::AsRawFd;
+            // SAFETY: flock only observes the valid fd borrowed from `file`.
+            let ret = unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) };
+            if ret != 0 {
+                let err = io::Error::last_os_error();
+                return Err(Error::invalid_argument(format!(
+                    "failed to lock DB directory {}: {} (is another process using it?)",
+                    path.display(),
+                    err
+                )));
+            }
+        }
+        Ok(file)
+    }
+
+    /// Open a top-level DB (`link == None`) or, for `Some(link)`, the engine
+    /// backing one of its column families. A column family engine takes no
+    /// `LOCK`, owns no WAL, and shares `link`'s sequence counters, snapshot
+    /// list, and background error with its owner.
+    fn open_impl(
+        options: DbOptions,
+        path: impl AsRef<Path>,
+        mode: OpenMode,
+        family_options: &[(String, DbOptions)],
+        link: Option<FamilyLink>,
+    ) -> Result<Self> {
         let mut options = options;
         let path = path.as_ref().to_path_buf();
+        let read_only = mode != OpenMode::ReadWrite;
 
         // A leveled LSM needs at least L0 plus one lower level; `num_levels < 2`
         // would panic during L0 counting / L0→L1 compaction.
@@ -608,6 +1926,43 @@ impl DB {
             )));
         }
 
+        if options.max_immutable_memtables == 0 {
+            return Err(Error::invalid_argument(
+                "max_immutable_memtables must be >= 1",
+            ));
+        }
+
+        if options.stats_dump_period == Some(Duration::ZERO) {
+            return Err(Error::invalid_argument(
+                "stats_dump_period must be > 0 (use None to disable dumps)",
+            ));
+        }
+
+        if options.min_blob_size == Some(0) {
+            return Err(Error::invalid_argument(
+                "min_blob_size must be > 0 (use None to disable blob files)",
+            ));
+        }
+        if !(0.0..=1.0).contains(&options.blob_garbage_collection_threshold) {
+            return Err(Error::invalid_argument(format!(
+                "blob_garbage_collection_threshold must be in [0.0, 1.0], got {}",
+                options.blob_garbage_collection_threshold
+            )));
+        }
+        if options.compaction_style == CompactionStyle::Fifo && options.min_blob_size.is_some() {
+            // Dropping whole SSTs would leave their blob records uncounted.
+            return Err(Error::invalid_argument(
+                "FIFO compaction does not support blob files (min_blob_size)",
+            ));
+        }
+        let universal = &options.universal_compaction;
+        if universal.min_merge_width < 2 || universal.max_merge_width < universal.min_merge_width {
+            return Err(Error::invalid_argument(format!(
+                "universal compaction merge width must satisfy 2 <= min ({}) <= max ({})",
+                universal.min_merge_width, universal.max_merge_width
+            )));
+        }
+
         if read_only {
             // Read-only open always means "open an existing DB". Normalize
             // the creation/existence flags so the stored options reflect the
@@ -645,8 +2000,14 @@ impl DB {
         // Lock before reading CURRENT/MANIFEST/WAL. Writable handles create
         // the lock file and take LOCK_EX. Read-only handles open an existing
         // lock file without write intent and take LOCK_SH; immutable snapshots
-        // that do not contain LOCK proceed unlocked by design.
-        let lock_file = if read_only {
+        // that do not contain LOCK proceed unlocked by design, and a
+        // secondary never locks the directory its primary writes.
+        let lock_file = if link.is_some() {
+            // Covered by the owning DB's lock.
+            None
+        } else if mode == OpenMode::Secondary {
+            None
+        } else if read_only {
             let lock_path = path.join("LOCK");
             match OpenOptions::new().read(true).open(&lock_path) {
                 Ok(file) => {
@@ -672,29 +2033,7 @@ impl DB {
                 Err(e) => return Err(e).ctx(),
             }
         } else {
-            let lock_path = path.join("LOCK");
-            let file = OpenOptions::new()
-                .create(true)
-                .truncate(false)
-                .read(true)
-                .write(true)
-                .open(&lock_path)
-                .ctx()?;
-            #[cfg(unix)]
-            {
-                use std::os::fd::AsRawFd;
-                // SAFETY: flock only observes the valid fd borrowed from `file`.
-                let ret = unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) };
-                if ret != 0 {
-                    let err = io::Error::last_os_error();
-                    return Err(Error::invalid_argument(format!(
-                        "failed to lock DB directory {}: {} (is another process using it?)",
-                        path.display(),
-                        err
-                    )));
-                }
-            }
-            Some(file)
+            Some(Self::lock_dir_exclusive(&path)?)
         };
 
         // Create caches and infra
@@ -706,49 +2045,157 @@ impl DB {
             None => BlockCache::new(options.block_cache_capacity),
         });
         let rate_limiter = Arc::new(RateLimiter::new(options.rate_limiter_bytes_per_sec));
-        let stats = Arc::new(DbStats::new());
+        let stats = Arc::new(DbStats::new_with_statistics(options.enable_statistics));
+        let icmp = InternalKeyComparator::new(&options.comparator);
         let table_cache = Arc::new(TableCache::new_with_stats(
             &path,
             options.max_open_files,
             Some(block_cache.clone()),
             Some(stats.clone()),
+            icmp.clone(),
         ));
 
-        // Open or create VersionSet (handles MANIFEST)
-        let mut versions = if read_only {
-            VersionSet::recover_read_only_with_cache(
-                &path,
-                options.num_levels,
-                Some(table_cache.clone()),
-            )
-            .ctx()?
-        } else {
-            VersionSet::open_with_cache(&path, options.num_levels, Some(table_cache.clone()))
-                .ctx()?
-        };
-        let mut max_sequence = versions.last_sequence();
+        // Open or create VersionSet (handles MANIFEST)
+        let mut versions = if read_only {
+            VersionSet::recover_read_only_with_cache(
+                &path,
+                options.num_levels,
+                Some(table_cache.clone()),
+            )
+            .ctx()?
+        } else {
+            VersionSet::open_with_cache(&path, options.num_levels, Some(table_cache.clone()))
+                .ctx()?
+        };
+        if !read_only && options.compaction_style == CompactionStyle::Fifo {
+            let version = versions.current();
+            if let Some(level) =
+                (1..version.num_levels).find(|&l| !version.level_files(l).is_empty())
+            {
+                return Err(Error::invalid_argument(format!(
+                    "FIFO compaction keeps every file in L0, but L{level} has files"
+                )));
+            }
+        }
+
+        if let Some(ref link) = link {
+            if let Some(wal_number) = link.create_at_wal {
+                let mut edit = VersionEdit::new();
+                edit.set_column_family(link.id);
+                edit.set_log_number(wal_number);
+                versions.log_and_apply(edit).ctx()?;
+                versions.sync_manifest().ctx()?;
+            } else if versions.column_family() != link.id {
+                return Err(Error::corruption(format!(
+                    "column family directory {} belongs to family {}, expected {}",
+                    path.display(),
+                    versions.column_family(),
+                    link.id
+                )));
+            }
+        }
+        let shared = link.unwrap_or_else(FamilyLink::root);
+
+        // Open the column families before WAL replay: their entries live in
+        // the same WAL files and are routed to their memtables below.
+        let mut families = BTreeMap::new();
+        if shared.id == 0 {
+            let registered = versions.column_families().clone();
+            for (&id, name) in &registered {
+                let mut family_opts = family_options
+                    .iter()
+                    .find(|(n, _)| n == name)
+                    .map_or_else(|| options.clone(), |(_, o)| o.clone());
+                family_opts.create_if_missing = false;
+                family_opts.error_if_exists = false;
+                let db = Self::open_impl(
+                    family_opts,
+                    family_dir(&path, id),
+                    mode,
+                    &[],
+                    Some(shared.child(id, None)),
+                )
+                .with_ctx(|| format!("failed to open column family {:?}", name))?;
+                families.insert(
+                    id,
+                    Arc::new(ColumnFamily {
+                        handle: ColumnFamilyHandle::new(id, name),
+                        db,
+                    }),
+                );
+            }
+            for (name, family_opts) in family_options {
+                if registered.values().any(|n| n == name) {
+                    continue;
+                }
+                if read_only {
+                    return Err(Error::invalid_argument(format!(
+                        "column family {:?} does not exist",
+                        name
+                    )));
+                }
+                // The fresh family's log number is advanced with every other
+                // family's once recovery below has picked the new WAL.
+                let family = Self::create_family(
+                    &path,
+                    &mut versions,
+                    name,
+                    family_opts.clone(),
+                    &shared,
+                    0,
+                )?;
+                families.insert(family.handle.id, family);
+            }
+        }
+
+        let mut max_sequence = families
+            .values()
+            .map(|f| f.db.inner.lock().versions.last_sequence())
+            .fold(versions.last_sequence(), u64::max);
+
+        // Every family only needs the WALs at or above its own log number;
+        // replay starts at the oldest one any family still needs.
+        let family_logs: Vec<(u32, u64, Arc<MemTable>)> = families
+            .values()
+            .map(|f| {
+                let g = f.db.inner.lock();
+                (
+                    f.handle.id,
+                    g.versions.log_number(),
+                    g.active_memtable.clone(),
+                )
+            })
+            .collect();
+        let min_log_number = family_logs
+            .iter()
+            .map(|&(_, log, _)| log)
+            .fold(versions.log_number(), u64::min);
 
         // Recover from any WAL files not yet flushed
-        let mut active_memtable = Arc::new(MemTable::new());
+        let mut active_memtable = Arc::new(MemTable::with_comparator(icmp.clone()));
         let mut wal_numbers: Vec<u64> = Vec::new();
         let mut max_disk_file_number = 0u64;
         for entry in fs::read_dir(&path).ctx()? {
             let entry = entry.ctx()?;
             let name = entry.file_name();
             let name = name.to_string_lossy();
-            // Track the highest file number present on disk across WAL/SST files.
+            // Track the highest file number present on disk across
+            // WAL/SST/blob files.
             if let Some(num) = name
                 .strip_suffix(".wal")
                 .or_else(|| name.strip_suffix(".sst"))
+                .or_else(|| name.strip_suffix(".blob"))
                 .and_then(|s| s.parse::<u64>().ok())
             {
                 max_disk_file_number = max_disk_file_number.max(num);
             }
+            // A secondary replays the WALs in its first catch-up.
             if let Some(num_str) = name.strip_suffix(".wal")
                 && let Ok(num) = num_str.parse::<u64>()
+                && mode != OpenMode::Secondary
             {
                 // Only recover WAL files newer than what's recorded in MANIFEST
-                if num >= versions.log_number() {
+                if num >= min_log_number {
                     wal_numbers.push(num);
                 }
             }
@@ -776,60 +2223,153 @@ impl DB {
                 }
             }
         });
-        for wal_num in &wal_numbers {
+        let mut wal_recovery = WalRecoverySummary::default();
+        for (i, wal_num) in wal_numbers.iter().enumerate() {
             let wal_path = path.join(format!("{:06}.wal", wal_num));
             let mut reader = WalReader::new(&wal_path).ctx()?;
+            wal_recovery.replayed_wals.push(*wal_num);
+            let mut replay_stopped = false;
             loop {
                 match reader.read_record() {
                     Ok(Some(data)) => {
-                        Self::replay_wal_record(&data, &active_memtable, &mut max_sequence)
-                            .ctx()?;
+                        let route = |cf: u32| {
+                            if cf == 0 {
+                                (*wal_num >= versions.log_number()).then_some(&*active_memtable)
+                            } else {
+                                family_logs
+                                    .iter()
+                                    .find(|&&(id, log, _)| id == cf && *wal_num >= log)
+                                    .map(|(_, _, mem)| &**mem)
+                            }
+                        };
+                        Self::replay_wal_record(&data, route, &mut max_sequence).ctx()?;
+                        wal_recovery.records_replayed += 1;
                     }
                     Ok(None) => break,
-                    // A torn tail (crash mid-append) can surface as a corrupt
-                    // record followed only by zero padding / a zero-extended
-                    // file tail. Tolerate exactly that case for the highest
-                    // non-empty recovered WAL: it was the active append target at
-                    // crash. Empty higher WALs can be recovery-created orphans
-                    // from a crash before the new log_number reached MANIFEST.
-                    //
-                    // Corruption in any earlier WAL, or corruption followed by
-                    // non-zero data, is NOT a torn active tail. Silently
-                    // recovering the prefix would drop committed records while
-                    // still replaying newer WALs (a causal hole), and the
-                    // post-recovery cleanup would then delete the WAL. Fail the
-                    // open loudly instead and preserve the file for inspection.
+                    Err(e) if e.kind() != ErrorKind::Corruption => {
+                        return Err(e).with_ctx(|| format!("failed to read WAL {:06}", wal_num));
+                    }
                     Err(e) => {
-                        // Only structural short-reads (partial header/payload/
-                        // trailer / zero-extended tip) may be a torn active
-                        // tail. Checksum, type, and other semantic failures fail
-                        // closed: an untrusted length can already have skipped
-                        // later valid records, so zero bytes past the advanced
-                        // position are not proof of a safe prefix.
-                        if Some(*wal_num) == recoverable_tail_wal_num
-                            && reader.last_error_is_truncation()
-                            && reader.rest_is_zero_padding().unwrap_or(false)
-                        {
-                            tracing::warn!(
-                                "WAL {} has corrupt tail, stopping replay: {}",
-                                wal_num,
-                                e
-                            );
-                            break;
+                        let corruption = WalCorruption {
+                            wal_number: *wal_num,
+                            offset: reader.last_valid_offset(),
+                            message: e.to_string(),
+                        };
+                        match options.wal_recovery_mode {
+                            // A torn tail (crash mid-append) can surface as a
+                            // corrupt record followed only by zero padding / a
+                            // zero-extended file tail. Tolerate exactly that
+                            // case for the highest non-empty recovered WAL: it
+                            // was the active append target at crash. Empty
+                            // higher WALs can be recovery-created orphans from
+                            // a crash before the new log_number reached
+                            // MANIFEST.
+                            //
+                            // Corruption in any earlier WAL, or corruption
+                            // followed by non-zero data, is NOT a torn active
+                            // tail. Silently recovering the prefix would drop
+                            // committed records while still replaying newer
+                            // WALs (a causal hole), and the post-recovery
+                            // cleanup would then delete the WAL. Fail the open
+                            // loudly instead and preserve the file for
+                            // inspection.
+                            //
+                            // Only structural short-reads (partial header/
+                            // payload/trailer / zero-extended tip) may be a torn
+                            // active tail. Checksum, type, and other semantic
+                            // failures fail closed: an untrusted length can
+                            // already have skipped later valid records, so zero
+                            // bytes past the advanced position are not proof of
+                            // a safe prefix.
+                            WalRecoveryMode::TolerateCorruptedTailRecords
+                                if Some(*wal_num) == recoverable_tail_wal_num
+                                    && reader.last_error_is_truncation()
+                                    && reader.rest_is_zero_padding().unwrap_or(false) =>
+                            {
+                                tracing::warn!(
+                                    "WAL {} has corrupt tail, stopping replay: {}",
+                                    wal_num,
+                                    e
+                                );
+                                wal_recovery.corruptions.push(corruption);
+                                break;
+                            }
+                            WalRecoveryMode::TolerateCorruptedTailRecords => {
+                                return Err(e).with_ctx(|| {
+                                    format!(
+                                        "WAL {:06} is corrupt before the recoverable active tail; \
+                                         refusing prefix recovery to avoid silent data loss",
+                                        wal_num
+                                    )
+                                });
+                            }
+                            WalRecoveryMode::AbsoluteConsistency => {
+                                return Err(e).with_ctx(|| {
+                                    format!(
+                                        "WAL {:06} is corrupt at offset {}",
+                                        wal_num, corruption.offset
+                                    )
+                                });
+                            }
+                            WalRecoveryMode::PointInTimeRecovery => {
+                                tracing::warn!(
+                                    "WAL {} is corrupt at offset {}, discarding the rest of \
+                                     the log: {}",
+                                    wal_num,
+                                    corruption.offset,
+                                    e
+                                );
+                                wal_recovery.corruptions.push(corruption);
+                                wal_recovery.dropped_wals = wal_numbers[i + 1..].to_vec();
+                                replay_stopped = true;
+                                break;
+                            }
+                            WalRecoveryMode::SkipAnyCorruptedRecords => {
+                                tracing::warn!(
+                                    "WAL {} is corrupt at offset {}, skipping to the next \
+                                     block: {}",
+                                    wal_num,
+                                    corruption.offset,
+                                    e
+                                );
+                                wal_recovery.corruptions.push(corruption);
+                                reader.skip_to_next_block().ctx()?;
+                            }
                         }
-                        return Err(e).with_ctx(|| {
-                            format!(
-                                "WAL {:06} is corrupt before the recoverable active tail; \
-                             refusing prefix recovery to avoid silent data loss",
-                                wal_num
-                            )
-                        });
                     }
                 }
             }
+            let progress = RecoveryProgressInfo {
+                wal_number: *wal_num,
+                wals_replayed: i + 1,
+                wals_total: wal_numbers.len(),
+                records_replayed: wal_recovery.records_replayed,
+            };
+            for listener in &options.listeners {
+                listener.on_recovery_progress(&progress);
+            }
+            if replay_stopped {
+                break;
+            }
         }
 
-        let (wal_writer, wal_number) = if read_only {
+        if shared.id == 0 {
+            let next_sequence = max_sequence.checked_add(1).ok_or_else(|| {
+                Error::invalid_argument("sequence number space exhausted".to_string())
+            })?;
+            shared.sequence.store(next_sequence, Ordering::Release);
+            shared
+                .committed_sequence
+                .store(max_sequence, Ordering::Release);
+        }
+
+        let (wal_writer, wal_number) = if shared.id != 0 {
+            // A column family logs to its owner's WAL.
+            if !read_only {
+                Self::remove_orphan_files(&path, &options, &versions, &shared.file_deletion_gate);
+            }
+            (None, 0)
+        } else if read_only {
             // Preserve the recovered WAL boundary as descriptive state. The
             // replayed memtable remains live and is published below.
             (None, versions.log_number())
@@ -840,7 +2380,8 @@ impl DB {
             // same WALs again and create a duplicate L0 file with identical keys.
             let wal_number = versions.new_file_number();
             let wal_path = path.join(format!("{:06}.wal", wal_number));
-            let wal_writer = WalWriter::new(&wal_path).ctx()?;
+            let wal_writer =
+                WalWriter::with_compression(&wal_path, options.wal_compression).ctx()?;
 
             // If we recovered data from WALs, flush it to SST before deleting
             // the old WALs. This ensures the data persists even if we crash again
@@ -858,18 +2399,31 @@ impl DB {
                         .iter()
                         .map(|f| f())
                         .collect(),
+                    comparator: icmp.clone(),
                 };
                 let outputs = {
                     let mut alloc = || Ok(versions.new_file_number());
-                    Self::write_memtable_ssts(&active_memtable, &path, &make_opts, &mut alloc)
-                        .ctx()?
+                    // No snapshot can exist yet while the DB is being opened.
+                    Self::write_memtable_ssts(
+                        &active_memtable,
+                        &path,
+                        &make_opts,
+                        &mut alloc,
+                        options.merge_operator.as_deref(),
+                        &[],
+                        options.min_blob_size,
+                    )
+                    .ctx()?
                 };
 
                 let mut edit = VersionEdit::new();
                 edit.set_log_number(wal_number);
                 edit.set_last_sequence(max_sequence);
                 edit.set_next_file_number(versions.next_file_number());
-                for (sst_number, build_result) in outputs {
+                if let Some(blob) = outputs.blob_file {
+                    edit.add_blob_file(blob);
+                }
+                for (sst_number, build_result) in outputs.tables {
                     edit.add_file(
                         0,
                         FileMetaData {
@@ -878,6 +2432,7 @@ impl DB {
                             smallest_key: build_result.smallest_key.unwrap_or_default(),
                             largest_key: build_result.largest_key.unwrap_or_default(),
                             has_range_deletions: build_result.has_range_deletions,
+                            creation_time: options.clock.now_millis(),
                         },
                     );
                 }
@@ -885,7 +2440,7 @@ impl DB {
                 versions.sync_manifest().ctx()?;
 
                 // Reset the memtable — data is now safely in SST
-                active_memtable = Arc::new(MemTable::new());
+                active_memtable = Arc::new(MemTable::with_comparator(icmp.clone()));
             } else {
                 // No recovered data: still record the new log number so the old
                 // WALs are skipped on the next open.
@@ -897,26 +2452,34 @@ impl DB {
                 versions.sync_manifest().ctx()?;
             }
 
+            // Move every column family onto the new WAL as well, flushing
+            // whatever it recovered, so the old WALs are dead for all of them.
+            for family in families.values() {
+                family
+                    .db
+                    .install_recovered_family(wal_number)
+                    .with_ctx(|| {
+                        format!("failed to recover column family {:?}", family.handle.name)
+                    })?;
+            }
+
             // Safe to clean up obsolete files now — the new log_number is durable
             // (so old WALs will never be replayed even if we crash here) and the
             // recovered version set defines the complete live SST set.
-            Self::remove_orphan_files(&path, &versions);
+            Self::remove_orphan_files(&path, &options, &versions, &shared.file_deletion_gate);
             (Some(wal_writer), wal_number)
         };
 
-        let next_sequence = max_sequence.checked_add(1).ok_or_else(|| {
-            Error::invalid_argument("sequence number space exhausted".to_string())
-        })?;
-        let sequence_start = Arc::new(AtomicU64::new(next_sequence));
-        let committed_sequence = Arc::new(AtomicU64::new(max_sequence));
-
         let manifest_poisoned = versions.poison_flag();
 
         let inner = Arc::new(Mutex::new(DBInner {
             active_memtable,
             immutable_memtables: Vec::new(),
+            flush_queue: VecDeque::new(),
             wal_writer,
             wal_number,
+            logged_sequence: shared.committed_sequence.load(Ordering::Acquire),
+            unlogged_memtables: Vec::new(),
             versions,
         }));
 
@@ -926,8 +2489,8 @@ impl DB {
         #[cfg(test)]
         LAST_COMPACTION_SHUTDOWN_FLAG
             .with(|sink| *sink.borrow_mut() = Some(compaction_shutdown.clone()));
-        let has_bg_error = Arc::new(AtomicBool::new(false));
-        let bg_error: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
+        let has_bg_error = shared.has_bg_error.clone();
+        let bg_error = shared.bg_error.clone();
         let compacting_files = Arc::new(CompactionClaims::new());
 
         // Wrap the user's compaction filter with lazy-delete support.
@@ -941,15 +2504,18 @@ impl DB {
         }
         let dead_key_sweep = Arc::new(DeadKeySweepScheduler::new());
 
-        let (l0_file_count, super_version) = {
+        let (l0_file_count, super_version, full_history_ts_low) = {
             let g = inner.lock();
             let l0 = Arc::new(AtomicUsize::new(g.versions.current().l0_file_count()));
+            let ts_low = Arc::new(RwLock::new(
+                g.versions.full_history_ts_low().map(<[u8]>::to_vec),
+            ));
             let sv = Arc::new(ArcSwap::from_pointee(SuperVersion {
                 active_memtable: g.active_memtable.clone(),
                 immutable_memtables: g.immutable_memtables.clone(),
                 version: g.versions.current(),
             }));
-            (l0, sv)
+            (l0, sv, ts_low)
         };
         let num_compaction_threads = if read_only {
             0
@@ -959,11 +2525,12 @@ impl DB {
         let mut compaction_handles = Vec::with_capacity(num_compaction_threads);
 
         let read_compaction_hints = Arc::new(Mutex::new(Vec::<CompactionHint>::new()));
-        let snapshot_list = Arc::new(SnapshotList::new());
+        let snapshot_list = shared.snapshot_list.clone();
         for i in 0..num_compaction_threads {
             let bg_inner = Arc::clone(&inner);
             let bg_path = path.clone();
             let bg_options = options.clone();
+            let bg_picker = compaction::picker(options.compaction_style);
             let bg_table_cache = table_cache.clone();
             let bg_block_cache = block_cache.clone();
             let bg_rate_limiter = rate_limiter.clone();
@@ -974,11 +2541,13 @@ impl DB {
             let notify = compaction_notify.clone();
             let bg_hints = read_compaction_hints.clone();
             let bg_snapshot_list = snapshot_list.clone();
+            let bg_ts_low = full_history_ts_low.clone();
             let bg_has_error = has_bg_error.clone();
             let bg_error_msg = bg_error.clone();
             let bg_compacting_files = compacting_files.clone();
             let bg_dead_key_sweep = dead_key_sweep.clone();
             let bg_manifest_poisoned = manifest_poisoned.clone();
+            let bg_deletion_gate = shared.file_deletion_gate.clone();
 
             let handle = thread::Builder::new()
                 .name(format!("mmdb-compaction-{}", i))
@@ -986,8 +2555,13 @@ impl DB {
                     // Helper: set background error and log it.
                     let set_error = |msg: String| {
                         tracing::error!("{}", msg);
-                        *bg_error_msg.lock() = Some(msg);
-                        bg_has_error.store(true, Ordering::Release);
+                        *bg_error_msg.lock() = Some(msg.clone());
+                        if !bg_has_error.swap(true, Ordering::AcqRel) {
+                            listener::notify_background_error(
+                                &bg_options.listeners,
+                                Error::background(msg),
+                            );
+                        }
                     };
 
                     loop {
@@ -1019,51 +2593,49 @@ impl DB {
                                                 let mut inner = bg_inner.lock();
                                                 let version = inner.versions.current();
                                                 let mut claimed = bg_compacting_files.lock();
-                                                LeveledCompaction::pick_compaction_for_hint(
-                                                    &version, hint, &claimed,
-                                                )
-                                                .map(|task| {
-                                                    let max_out =
-                                                        LeveledCompaction::max_output_files(
-                                                            &task,
-                                                            &bg_options,
-                                                        );
-                                                    let file_start = inner
-                                                        .versions
-                                                        .reserve_file_numbers(max_out);
-                                                    let file_limit =
-                                                        file_start.saturating_add(max_out);
-                                                    let all_inputs: Vec<_> = task
-                                                        .input_files_level
-                                                        .iter()
-                                                        .chain(task.input_files_next.iter())
-                                                        .cloned()
-                                                        .collect();
-                                                    let is_bottom =
-                                                        LeveledCompaction::is_bottommost_level(
-                                                            &version,
-                                                            task.level,
-                                                            bg_options.num_levels,
-                                                            &all_inputs,
-                                                        );
-                                                    let claimed_numbers: Vec<u64> = all_inputs
-                                                        .iter()
-                                                        .map(|f| f.meta.number)
-                                                        .collect();
-                                                    claimed.extend(claimed_numbers.iter().copied());
-                                                    // Capture the snapshot list under the
-                                                    // DB lock, consistent with the inputs.
-                                                    let active_snaps =
-                                                        bg_snapshot_list.as_sorted_vec();
-                                                    (
-                                                        task,
-                                                        file_start,
-                                                        file_limit,
-                                                        is_bottom,
-                                                        active_snaps,
-                                                        claimed_numbers,
+                                                bg_picker
+                                                    .pick_compaction_for_hint(
+                                                        &version, hint, &claimed,
                                                     )
-                                                })
+                                                    .map(|task| {
+                                                        let max_out =
+                                                            LeveledCompaction::max_output_files(
+                                                                &task,
+                                                                &bg_options,
+                                                            );
+                                                        let file_start = inner
+                                                            .versions
+                                                            .reserve_file_numbers(max_out);
+                                                        let file_limit =
+                                                            file_start.saturating_add(max_out);
+                                                        let all_inputs = task.all_input_files();
+                                                        let is_bottom =
+                                                            LeveledCompaction::is_bottommost_level(
+                                                                &version,
+                                                                task.level,
+                                                                bg_options.num_levels,
+                                                                &all_inputs,
+                                                            );
+                                                        let claimed_numbers: Vec<u64> = all_inputs
+                                                            .iter()
+                                                            .map(|f| f.meta.number)
+                                                            .collect();
+                                                        claimed.extend(
+                                                            claimed_numbers.iter().copied(),
+                                                        );
+                                                        // Capture the snapshot list under the
+                                                        // DB lock, consistent with the inputs.
+                                                        let active_snaps =
+                                                            bg_snapshot_list.as_sorted_vec();
+                                                        (
+                                                            task,
+                                                            file_start,
+                                                            file_limit,
+                                                            is_bottom,
+                                                            active_snaps,
+                                                            claimed_numbers,
+                                                        )
+                                                    })
                                             }; // lock released
 
                                             if let Some((
@@ -1082,12 +2654,14 @@ impl DB {
                                                     numbers: claimed_numbers,
                                                 };
                                                 // Phase 2: I/O (no lock)
+                                                let ts_low = bg_ts_low.read().clone();
                                                 let ctx = CompactionContext {
                                                     db_path: &bg_path,
                                                     options: &bg_options,
                                                     rate_limiter: Some(&bg_rate_limiter),
                                                     stats: Some(&bg_stats),
                                                     active_snapshots: &active_snaps,
+                                                    full_history_ts_low: ts_low.as_deref(),
                                                 };
                                                 let output =
                                                     LeveledCompaction::execute_compaction_io(
@@ -1157,7 +2731,10 @@ impl DB {
                                                     ));
                                                 }
                                                 LeveledCompaction::run_post_compaction_cleanup(
-                                                    &cleanup, &bg_path,
+                                                    &cleanup,
+                                                    &bg_path,
+                                                    &bg_deletion_gate,
+                                                    &bg_options.listeners,
                                                 );
                                             }
                                         }
@@ -1171,7 +2748,7 @@ impl DB {
                                         let mut inner = bg_inner.lock();
                                         let version = inner.versions.current();
                                         let mut claimed = bg_compacting_files.lock();
-                                        match LeveledCompaction::pick_compaction(
+                                        match bg_picker.pick_compaction(
                                             &version,
                                             &bg_options,
                                             &claimed,
@@ -1192,12 +2769,7 @@ impl DB {
                                                 let file_start =
                                                     inner.versions.reserve_file_numbers(max_out);
                                                 let file_limit = file_start.saturating_add(max_out);
-                                                let all_inputs: Vec<_> = task
-                                                    .input_files_level
-                                                    .iter()
-                                                    .chain(task.input_files_next.iter())
-                                                    .cloned()
-                                                    .collect();
+                                                let all_inputs = task.all_input_files();
                                                 let is_bottom =
                                                     LeveledCompaction::is_bottommost_level(
                                                         &version,
@@ -1252,12 +2824,14 @@ impl DB {
                                     };
 
                                     // Phase 2: I/O (no lock held)
+                                    let ts_low = bg_ts_low.read().clone();
                                     let ctx = CompactionContext {
                                         db_path: &bg_path,
                                         options: &bg_options,
                                         rate_limiter: Some(&bg_rate_limiter),
                                         stats: Some(&bg_stats),
                                         active_snapshots: &active_snaps,
+                                        full_history_ts_low: ts_low.as_deref(),
                                     };
                                     let output = LeveledCompaction::execute_compaction_io(
                                         &ctx, &task, file_start, file_limit, is_bottom,
@@ -1323,7 +2897,10 @@ impl DB {
                                         return Err(format!("manifest sync error: {}", e));
                                     }
                                     LeveledCompaction::run_post_compaction_cleanup(
-                                        &cleanup, &bg_path,
+                                        &cleanup,
+                                        &bg_path,
+                                        &bg_deletion_gate,
+                                        &bg_options.listeners,
                                     );
                                 }
 
@@ -1341,31 +2918,43 @@ impl DB {
 
                                     let mut blocked_by_snapshot = false;
                                     for level in (0..bg_options.num_levels).rev() {
-                                        let mut inner = bg_inner.lock();
-                                        let active_snaps = bg_snapshot_list.as_sorted_vec();
-                                        blocked_by_snapshot |= !active_snaps.is_empty();
-                                        let ctx = CompactionContext {
-                                            db_path: &bg_path,
-                                            options: &bg_options,
-                                            rate_limiter: Some(&bg_rate_limiter),
-                                            stats: Some(&bg_stats),
-                                            active_snapshots: &active_snaps,
+                                        let cleanup = {
+                                            let mut inner = bg_inner.lock();
+                                            let active_snaps = bg_snapshot_list.as_sorted_vec();
+                                            blocked_by_snapshot |= !active_snaps.is_empty();
+                                            let ts_low = bg_ts_low.read().clone();
+                                            let ctx = CompactionContext {
+                                                db_path: &bg_path,
+                                                options: &bg_options,
+                                                rate_limiter: Some(&bg_rate_limiter),
+                                                stats: Some(&bg_stats),
+                                                active_snapshots: &active_snaps,
+                                                full_history_ts_low: ts_low.as_deref(),
+                                            };
+                                            let cleanup = LeveledCompaction::force_merge_level(
+                                                &ctx,
+                                                level,
+                                                CompactionReason::DeadKeySweep,
+                                                &mut inner.versions,
+                                                Some(&bg_table_cache),
+                                                Some(&bg_block_cache),
+                                            )
+                                            .map_err(|e| {
+                                                format!("dead-key sweep error at L{}: {}", level, e)
+                                            })?;
+                                            bg_l0_count.store(
+                                                inner.versions.current().l0_file_count(),
+                                                Ordering::Relaxed,
+                                            );
+                                            refresh_super_version(&bg_sv, &inner);
+                                            cleanup
                                         };
-                                        LeveledCompaction::force_merge_level(
-                                            &ctx,
-                                            level,
-                                            &mut inner.versions,
-                                            Some(&bg_table_cache),
-                                            Some(&bg_block_cache),
-                                        )
-                                        .map_err(|e| {
-                                            format!("dead-key sweep error at L{}: {}", level, e)
-                                        })?;
-                                        bg_l0_count.store(
-                                            inner.versions.current().l0_file_count(),
-                                            Ordering::Relaxed,
+                                        LeveledCompaction::run_post_compaction_cleanup(
+                                            &cleanup,
+                                            &bg_path,
+                                            &bg_deletion_gate,
+                                            &bg_options.listeners,
                                         );
-                                        refresh_super_version(&bg_sv, &inner);
                                     }
                                     bg_dead_key_sweep.finish(blocked_by_snapshot);
                                 }
@@ -1431,18 +3020,43 @@ impl DB {
             }
         }
 
+        let column_families = Arc::new(RwLock::new(families));
+        let flusher = Arc::new(Flusher {
+            path: path.clone(),
+            options: options.clone(),
+            icmp: icmp.clone(),
+            inner: inner.clone(),
+            committed_sequence: shared.committed_sequence.clone(),
+            table_cache: table_cache.clone(),
+            stats: stats.clone(),
+            snapshot_list: snapshot_list.clone(),
+            l0_file_count: l0_file_count.clone(),
+            super_version: super_version.clone(),
+            has_bg_error: has_bg_error.clone(),
+            bg_error: bg_error.clone(),
+            manifest_poisoned: manifest_poisoned.clone(),
+            compaction_notify: compaction_notify.clone(),
+            column_families: column_families.clone(),
+            queue_changed: Condvar::new(),
+            shutdown: AtomicBool::new(false),
+        });
+
         let db = Self {
             path,
             options,
+            icmp,
             read_only,
             inner,
-            sequence: sequence_start.clone(),
-            committed_sequence,
+            sequence: shared.sequence.clone(),
+            committed_sequence: shared.committed_sequence.clone(),
+            durable_sequence: AtomicU64::new(shared.committed_sequence.load(Ordering::Acquire)),
             write_queue: Mutex::new(WriteQueueState {
                 queue: VecDeque::new(),
                 leader_active: false,
+                groups_applying: 0,
             }),
             write_cv: Condvar::new(),
+            write_pipeline: WritePipeline::new(),
             closed: AtomicBool::new(false),
             has_bg_error,
             bg_error,
@@ -1454,18 +3068,49 @@ impl DB {
             compaction_shutdown,
             compaction_notify,
             compaction_handles: Mutex::new(compaction_handles),
+            flusher,
+            flush_handles: Mutex::new(Vec::new()),
+            stats_dump_shutdown: Arc::new((StdMutex::new(false), StdCondvar::new())),
+            stats_dump_handle: Mutex::new(None),
             compacting_files,
             l0_file_count,
+            write_stall: AtomicU8::new(WriteStallCondition::Normal as u8),
             super_version,
             read_compaction_hints,
             read_counter: AtomicU64::new(0),
             snapshot_list,
+            full_history_ts_low,
+            file_deletion_gate: shared.file_deletion_gate.clone(),
             lock_file: Mutex::new(lock_file),
             dead_keys,
             dead_key_sweep,
             dead_key_prune_cursor: AtomicUsize::new(0),
+            family_id: shared.id,
+            column_families,
+            wal_recovery,
+            secondary: None,
         };
 
+        // Column family memtables are flushed by their owner.
+        if !read_only && shared.id == 0 {
+            let mut flush_handles = db.flush_handles.lock();
+            for i in 0..db.options.max_background_flushes.max(1) {
+                let flusher = db.flusher.clone();
+                // On failure, dropping `db` stops and joins the threads
+                // already spawned.
+                let handle = thread::Builder::new()
+                    .name(format!("mmdb-flush-{}", i))
+                    .spawn(move || flusher.run_background())
+                    .with_ctx(|| "failed to spawn flush thread")?;
+                flush_handles.push(handle);
+            }
+        }
+
+        if let Some(period) = db.options.stats_dump_period {
+            let handle = db.spawn_stats_dump(period)?;
+            *db.stats_dump_handle.lock() = Some(handle);
+        }
+
         // Kick the background compaction threads once at startup. A DB
         // opened with a pre-existing L0 backlog (e.g. WAL-recovery SSTs
         // accumulated across short-lived processes) would otherwise not
@@ -1498,10 +3143,87 @@ impl DB {
         self.write_batch_inner(batch, write_options)
     }
 
+    /// Put a value that expires `ttl` from now, overriding
+    /// [`DbOptions::default_ttl`]. Once expired the key reads as deleted,
+    /// and the next compaction that rewrites the entry drops it.
+    pub fn put_with_ttl(&self, key: &[u8], value: &[u8], ttl: Duration) -> Result<()> {
+        self.check_writable().ctx()?;
+        let mut batch = WriteBatch::new();
+        batch.put_with_ttl(key, value, ttl);
+        self.write_batch_inner(batch, &WriteOptions::default())
+    }
+
     pub fn delete(&self, key: &[u8]) -> Result<()> {
         self.delete_with_options(&WriteOptions::default(), key)
     }
 
+    /// Put `key` at user-defined timestamp `ts`. Only valid when
+    /// [`DbOptions::comparator`] has a non-zero
+    /// [`timestamp_size`](crate::Comparator::timestamp_size); see
+    /// [`ReadOptions::timestamp`] for reading as of a timestamp.
+    pub fn put_with_timestamp(&self, key: &[u8], ts: &[u8], value: &[u8]) -> Result<()> {
+        self.check_writable().ctx()?;
+        let mut batch = WriteBatch::new();
+        batch.put_with_timestamp(key, ts, value);
+        self.write_batch_inner(batch, &WriteOptions::default())
+    }
+
+    /// Delete `key` at user-defined timestamp `ts`: reads at `ts` or later
+    /// see the key as absent, reads at older timestamps still see earlier
+    /// versions.
+    pub fn delete_with_timestamp(&self, key: &[u8], ts: &[u8]) -> Result<()> {
+        self.check_writable().ctx()?;
+        let mut batch = WriteBatch::new();
+        batch.delete_with_timestamp(key, ts);
+        self.write_batch_inner(batch, &WriteOptions::default())
+    }
+
+    /// Raise the timestamp below which history may be discarded. Reads at a
+    /// timestamp under it fail with
+    /// [`ErrorKind::InvalidArgument`](crate::ErrorKind), and compaction
+    /// keeps only the newest version of each key older than it (when no
+    /// snapshot still needs the rest). The watermark is persisted in the
+    /// MANIFEST and can never move backwards.
+    pub fn increase_full_history_ts_low(&self, ts: &[u8]) -> Result<()> {
+        let ts_size = self.icmp.timestamp_size();
+        if ts_size == 0 || ts.len() != ts_size {
+            return Err(Error::invalid_argument(format!(
+                "full_history_ts_low is {} bytes, comparator {} expects {ts_size}",
+                ts.len(),
+                self.icmp.name()
+            )));
+        }
+        self.check_writable().ctx()?;
+        let manifest_handle = {
+            let mut inner = self.inner.lock();
+            if let Some(current) = inner.versions.full_history_ts_low() {
+                if ts < current {
+                    return Err(Error::invalid_argument(
+                        "full_history_ts_low cannot decrease",
+                    ));
+                }
+                if ts == current {
+                    return Ok(());
+                }
+            }
+            let mut edit = VersionEdit::new();
+            edit.set_full_history_ts_low(ts.to_vec());
+            inner.versions.log_and_apply(edit).ctx()?;
+            *self.full_history_ts_low.write() = Some(ts.to_vec());
+            inner.versions.manifest_sync_handle()
+        };
+        if let Err(e) = confirm_manifest_durable(&manifest_handle, &self.manifest_poisoned) {
+            self.set_bg_error(format!("full_history_ts_low manifest sync failed: {}", e));
+            return Err(e).ctx();
+        }
+        Ok(())
+    }
+
+    /// The current `full_history_ts_low`, if one was ever set.
+    pub fn full_history_ts_low(&self) -> Option<Vec<u8>> {
+        self.full_history_ts_low.read().clone()
+    }
+
     pub fn delete_with_options(&self, write_options: &WriteOptions, key: &[u8]) -> Result<()> {
         self.check_writable().ctx()?;
         let mut batch = WriteBatch::new();
@@ -1526,6 +3248,26 @@ impl DB {
         self.write_batch_inner(batch, write_options)
     }
 
+    /// Append a merge operand for `key`. The operand is folded into the
+    /// key's value by [`DbOptions::merge_operator`] on read and compaction;
+    /// fails with [`ErrorKind::InvalidArgument`](crate::ErrorKind) when no
+    /// operator is configured.
+    pub fn merge(&self, key: &[u8], operand: &[u8]) -> Result<()> {
+        self.merge_with_options(&WriteOptions::default(), key, operand)
+    }
+
+    pub fn merge_with_options(
+        &self,
+        write_options: &WriteOptions,
+        key: &[u8],
+        operand: &[u8],
+    ) -> Result<()> {
+        self.check_writable().ctx()?;
+        let mut batch = WriteBatch::new();
+        batch.merge(key, operand);
+        self.write_batch_inner(batch, write_options)
+    }
+
     pub fn write(&self, batch: WriteBatch) -> Result<()> {
         self.write_with_options(&WriteOptions::default(), batch)
     }
@@ -1548,8 +3290,22 @@ impl DB {
 
     pub fn get_with_options(&self, options: &ReadOptions, key: &[u8]) -> Result<Option<Vec<u8>>> {
         self.check_usable().ctx()?;
+        let started = self.stats.start_timer();
+        let result = if self.icmp.timestamp_size() > 0 {
+            self.get_at_timestamp(options, key)
+        } else {
+            self.point_get(options, key)
+        };
+        self.stats.record_latency(HistogramKind::Get, started);
+        result
+    }
+
+    fn point_get(&self, options: &ReadOptions, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        // Rejects a read timestamp on a comparator without timestamps.
+        self.read_timestamp(options)?;
 
         let seq = self.resolve_read_sequence(options.snapshot);
+        let now = self.options.clock.now_millis();
 
         // Lock-free read via SuperVersion.
         let sv = self.get_super_version();
@@ -1560,27 +3316,36 @@ impl DB {
         // This is needed to compare against any point entry we find.
         let mut max_tomb_seq = self.max_covering_tombstone_seq(key, seq, active_mem, imm_mems);
 
+        // Merge operands collected newest-first across sources until a base
+        // value (or deletion) resolves the key.
+        let mut operands: Vec<Vec<u8>> = Vec::new();
+
         // 1. Active MemTable
-        if let Some((result, entry_seq)) = active_mem.get_with_seq(key, seq) {
-            // If a range tombstone with higher seq exists, it deletes this entry
-            if max_tomb_seq > entry_seq {
-                return Ok(None);
-            }
-            if let Some(ref v) = result {
-                self.stats.record_read(key.len() as u64 + v.len() as u64);
-            }
+        if let Some(result) = self.resolve_point_in_source(
+            key,
+            seq,
+            now,
+            version,
+            max_tomb_seq,
+            &mut operands,
+            |s| Ok(active_mem.get_with_seq(key, s)),
+        )? {
+            self.record_get_hit(None, key, &result);
             return Ok(result);
         }
 
         // 2. Immutable MemTables (newest first)
         for imm in imm_mems {
-            if let Some((result, entry_seq)) = imm.get_with_seq(key, seq) {
-                if max_tomb_seq > entry_seq {
-                    return Ok(None);
-                }
-                if let Some(ref v) = result {
-                    self.stats.record_read(key.len() as u64 + v.len() as u64);
-                }
+            if let Some(result) = self.resolve_point_in_source(
+                key,
+                seq,
+                now,
+                version,
+                max_tomb_seq,
+                &mut operands,
+                |s| Ok(imm.get_with_seq(key, s)),
+            )? {
+                self.record_get_hit(None, key, &result);
                 return Ok(result);
             }
         }
@@ -1588,7 +3353,7 @@ impl DB {
         // 3. If no point entry in memtables but a tombstone covers the key,
         // the key is deleted (tombstone covers entries in older SSTs too).
         if max_tomb_seq > 0 {
-            return Ok(None);
+            return self.finish_point_read(key, None, &operands);
         }
 
         // 4. L0 SST files (newest first, may overlap).
@@ -1612,89 +3377,441 @@ impl DB {
             }
         }
         for tf in l0_files {
-            if let Some((result, entry_seq)) = tf
-                .reader
-                .get_internal_with_seq(key, seq, options.fill_cache)
-                .ctx()?
+            if let Some(result) = self.resolve_point_in_source(
+                key,
+                seq,
+                now,
+                version,
+                max_tomb_seq,
+                &mut operands,
+                |s| {
+                    tf.reader
+                        .get_internal_with_seq(key, s, options.fill_cache)
+                        .ctx()
+                },
+            )? {
+                self.record_get_hit(Some(0), key, &result);
+                return Ok(result);
+            }
+        }
+
+        // 5. L1+ SST files (sorted by smallest_key, no overlap within level)
+        for level in 1..version.num_levels {
+            let files = version.level_files(level);
+            if files.is_empty() {
+                continue;
+            }
+
+            // Check range tombstones in all files at this level that have them.
+            // We cannot filter by file key bounds because range tombstones can
+            // extend past the file's largest_key (table builder bounds only
+            // reflect point keys and tombstone start keys, not end keys).
+            for tf in files {
+                if tf.meta.has_range_deletions {
+                    let file_tomb_seq = tf.reader.max_covering_tombstone_seq(key, seq).ctx()?;
+                    max_tomb_seq = max_tomb_seq.max(file_tomb_seq);
+                }
+            }
+
+            // Binary search: find the latest file whose smallest user_key <= key.
+            // Files are sorted by smallest_key (internal key), which has user_key prefix.
+            let idx = files.partition_point(|tf| {
+                let sk = &tf.meta.smallest_key;
+                let smallest_uk = if sk.len() >= 8 {
+                    &sk[..sk.len() - 8]
+                } else {
+                    sk.as_slice()
+                };
+                self.icmp.user_le(smallest_uk, key)
+            });
+
+            // The candidate file is at idx-1 (last file whose smallest_key <= key)
+            if idx == 0 {
+                continue;
+            }
+            let tf = &files[idx - 1];
+            let lk = &tf.meta.largest_key;
+            let file_largest = if lk.len() >= 8 {
+                &lk[..lk.len() - 8]
+            } else {
+                lk.as_slice()
+            };
+            if self.icmp.user_le(key, file_largest)
+                && let Some(result) = self.resolve_point_in_source(
+                    key,
+                    seq,
+                    now,
+                    version,
+                    max_tomb_seq,
+                    &mut operands,
+                    |s| {
+                        tf.reader
+                            .get_internal_with_seq(key, s, options.fill_cache)
+                            .ctx()
+                    },
+                )?
             {
-                if max_tomb_seq > entry_seq {
-                    return Ok(None);
+                // Sample reads at level >= 2 for read-triggered compaction.
+                if level >= 2 {
+                    self.stats.maybe_sample_read_level(level);
+                    self.maybe_check_read_compaction();
+                }
+                self.record_get_hit(Some(level), key, &result);
+                return Ok(result);
+            }
+        }
+
+        // No base value below the collected operands (or a range tombstone
+        // in SSTs with no point entry): the key resolves as absent.
+        self.finish_point_read(key, None, &operands)
+    }
+
+    /// Count a point read that `source` (a memtable for `None`, else an
+    /// SST level) resolved, if it found a value.
+    fn record_get_hit(&self, source: Option<usize>, key: &[u8], result: &Option<Vec<u8>>) {
+        if let Some(value) = result {
+            self.stats
+                .record_get_hit(source, (key.len() + value.len()) as u64);
+        }
+    }
+
+    /// Point read when user keys carry timestamps. Each version of `key` is
+    /// a distinct user key, so the lookup is a bounded seek that lets the
+    /// iterator pick the newest version visible at the read timestamp.
+    fn get_at_timestamp(&self, options: &ReadOptions, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        let options = ReadOptions {
+            snapshot: options.snapshot,
+            fill_cache: options.fill_cache,
+            timestamp: options.timestamp.clone(),
+            ..Default::default()
+        };
+        let mut iter = self.iter_with_range(&options, Some(key), None)?;
+        iter.seek(key);
+        if let Some(e) = iter.error() {
+            return Err(Error::corruption(e));
+        }
+        if iter.valid() && iter.key() == Some(key) {
+            return Ok(iter.value().map(<[u8]>::to_vec));
+        }
+        Ok(None)
+    }
+
+    /// Look up many keys against one consistent view.
+    ///
+    /// Same per-key semantics as [`get_with_options`](Self::get_with_options),
+    /// but a single `SuperVersion` is pinned for the whole batch and keys are
+    /// processed in sorted order, so every SST is probed once for the group
+    /// of keys routed to it and neighbouring keys share decoded data blocks.
+    /// Results are returned in the order of `keys`.
+    pub fn multi_get(&self, options: &ReadOptions, keys: &[&[u8]]) -> Vec<Result<Option<Vec<u8>>>> {
+        if let Err(e) = self.check_usable() {
+            return keys.iter().map(|_| Err(e.clone())).collect();
+        }
+        if self.icmp.timestamp_size() > 0 {
+            // Pin the sequence so every key reads the same view.
+            let mut options = options.clone();
+            options.snapshot = Some(self.resolve_read_sequence(options.snapshot));
+            return keys
+                .iter()
+                .map(|key| self.get_at_timestamp(&options, key))
+                .collect();
+        }
+        if let Err(e) = self.read_timestamp(options) {
+            return keys.iter().map(|_| Err(e.clone())).collect();
+        }
+
+        let seq = self.resolve_read_sequence(options.snapshot);
+        let now = self.options.clock.now_millis();
+        let sv = self.get_super_version();
+        let (active_mem, imm_mems, version) =
+            (&sv.active_memtable, &sv.immutable_memtables, &sv.version);
+
+        let mut order: Vec<usize> = (0..keys.len()).collect();
+        order.sort_by(|&a, &b| self.icmp.compare_user(keys[a], keys[b]));
+        let mut states: Vec<MultiGetState> = keys
+            .iter()
+            .map(|key| MultiGetState {
+                max_tomb_seq: self.max_covering_tombstone_seq(key, seq, active_mem, imm_mems),
+                operands: Vec::new(),
+                result: None,
+            })
+            .collect();
+
+        // 1-3. MemTables, then keys whose memtable tombstone hides all SSTs.
+        for &i in &order {
+            let (key, state) = (keys[i], &mut states[i]);
+            let resolved = (|| {
+                if let Some(result) = self.resolve_point_in_source(
+                    key,
+                    seq,
+                    now,
+                    version,
+                    state.max_tomb_seq,
+                    &mut state.operands,
+                    |s| Ok(active_mem.get_with_seq(key, s)),
+                )? {
+                    return Ok(Some(result));
+                }
+                for imm in imm_mems {
+                    if let Some(result) = self.resolve_point_in_source(
+                        key,
+                        seq,
+                        now,
+                        version,
+                        state.max_tomb_seq,
+                        &mut state.operands,
+                        |s| Ok(imm.get_with_seq(key, s)),
+                    )? {
+                        return Ok(Some(result));
+                    }
+                }
+                if state.max_tomb_seq > 0 {
+                    return self.finish_point_read(key, None, &state.operands).map(Some);
+                }
+                Ok(None)
+            })();
+            state.result = resolved.transpose();
+        }
+
+        // 4. L0: collect covering tombstones from every file before any point
+        // lookup (see `get_with_options`), then probe files newest first.
+        let l0_files = version.level_files(0);
+        for &i in &order {
+            let state = &mut states[i];
+            if state.result.is_some() {
+                continue;
+            }
+            for tf in l0_files.iter().filter(|tf| tf.meta.has_range_deletions) {
+                match tf.reader.max_covering_tombstone_seq(keys[i], seq) {
+                    Ok(s) => state.max_tomb_seq = state.max_tomb_seq.max(s),
+                    Err(e) => {
+                        state.result = Some(Err(e).ctx());
+                        break;
+                    }
+                }
+            }
+        }
+        for tf in l0_files {
+            let (smallest, largest) = (
+                types::user_key(&tf.meta.smallest_key),
+                types::user_key(&tf.meta.largest_key),
+            );
+            let group: Vec<usize> = order
+                .iter()
+                .copied()
+                .filter(|&i| {
+                    states[i].result.is_none()
+                        && self.icmp.user_le(smallest, keys[i])
+                        && self.icmp.user_le(keys[i], largest)
+                })
+                .collect();
+            self.multi_get_in_file(options, version, tf, seq, now, keys, &group, &mut states);
+        }
+
+        // 5. L1+: tombstones from every file at the level, then each key is
+        // routed to the one file whose range may hold it.
+        for level in 1..version.num_levels {
+            let files = version.level_files(level);
+            if files.is_empty() {
+                continue;
+            }
+            for &i in &order {
+                let state = &mut states[i];
+                if state.result.is_some() {
+                    continue;
+                }
+                for tf in files.iter().filter(|tf| tf.meta.has_range_deletions) {
+                    match tf.reader.max_covering_tombstone_seq(keys[i], seq) {
+                        Ok(s) => state.max_tomb_seq = state.max_tomb_seq.max(s),
+                        Err(e) => {
+                            state.result = Some(Err(e).ctx());
+                            break;
+                        }
+                    }
+                }
+            }
+
+            let pending: Vec<usize> = order
+                .iter()
+                .copied()
+                .filter(|&i| states[i].result.is_none())
+                .collect();
+            let mut pending = pending.into_iter().peekable();
+            while let Some(&first) = pending.peek() {
+                let idx = files.partition_point(|tf| {
+                    self.icmp
+                        .user_le(types::user_key(&tf.meta.smallest_key), keys[first])
+                });
+                let Some(tf) = idx.checked_sub(1).map(|idx| &files[idx]) else {
+                    pending.next();
+                    continue;
+                };
+                let largest = types::user_key(&tf.meta.largest_key);
+                let mut group = Vec::new();
+                while let Some(i) = pending.next_if(|&i| self.icmp.user_le(keys[i], largest)) {
+                    group.push(i);
                 }
-                if let Some(ref v) = result {
-                    self.stats.record_read(key.len() as u64 + v.len() as u64);
+                if group.is_empty() {
+                    pending.next();
+                    continue;
+                }
+                self.multi_get_in_file(options, version, tf, seq, now, keys, &group, &mut states);
+                if level >= 2 {
+                    for &i in &group {
+                        if states[i].result.is_some() {
+                            self.stats.maybe_sample_read_level(level);
+                            self.maybe_check_read_compaction();
+                        }
+                    }
                 }
-                return Ok(result);
             }
         }
 
-        // 5. L1+ SST files (sorted by smallest_key, no overlap within level)
-        for level in 1..version.num_levels {
-            let files = version.level_files(level);
-            if files.is_empty() {
-                continue;
-            }
+        keys.iter()
+            .zip(states)
+            .map(|(key, state)| match state.result {
+                Some(result) => result,
+                None => self.finish_point_read(key, None, &state.operands),
+            })
+            .collect()
+    }
 
-            // Check range tombstones in all files at this level that have them.
-            // We cannot filter by file key bounds because range tombstones can
-            // extend past the file's largest_key (table builder bounds only
-            // reflect point keys and tombstone start keys, not end keys).
-            for tf in files {
-                if tf.meta.has_range_deletions {
-                    let file_tomb_seq = tf.reader.max_covering_tombstone_seq(key, seq).ctx()?;
-                    max_tomb_seq = max_tomb_seq.max(file_tomb_seq);
+    /// Probe one SST for the sorted `group` of unresolved keys in a single
+    /// batched lookup, then resolve each key against it. Merge chains that
+    /// continue below the first entry fall back to per-key lookups.
+    #[allow(clippy::too_many_arguments)]
+    fn multi_get_in_file(
+        &self,
+        options: &ReadOptions,
+        version: &Version,
+        tf: &TableFile,
+        seq: SequenceNumber,
+        now: u64,
+        keys: &[&[u8]],
+        group: &[usize],
+        states: &mut [MultiGetState],
+    ) {
+        if group.is_empty() {
+            return;
+        }
+        let group_keys: Vec<&[u8]> = group.iter().map(|&i| keys[i]).collect();
+        let found =
+            match tf
+                .reader
+                .multi_get_internal_with_seq(&group_keys, seq, options.fill_cache)
+            {
+                Ok(found) => found,
+                Err(e) => {
+                    for &i in group {
+                        states[i].result = Some(Err(e.clone()).ctx());
+                    }
+                    return;
                 }
-            }
-
-            // Binary search: find the latest file whose smallest user_key <= key.
-            // Files are sorted by smallest_key (internal key), which has user_key prefix.
-            let idx = files.partition_point(|tf| {
-                let sk = &tf.meta.smallest_key;
-                let smallest_uk = if sk.len() >= 8 {
-                    &sk[..sk.len() - 8]
-                } else {
-                    sk.as_slice()
-                };
-                smallest_uk <= key
-            });
+            };
+        for (&i, entry) in group.iter().zip(found) {
+            let (key, state) = (keys[i], &mut states[i]);
+            let mut first = Some(entry);
+            state.result = self
+                .resolve_point_in_source(
+                    key,
+                    seq,
+                    now,
+                    version,
+                    state.max_tomb_seq,
+                    &mut state.operands,
+                    |s| match first.take() {
+                        Some(entry) => Ok(entry),
+                        None => tf
+                            .reader
+                            .get_internal_with_seq(key, s, options.fill_cache)
+                            .ctx(),
+                    },
+                )
+                .transpose();
+        }
+    }
 
-            // The candidate file is at idx-1 (last file whose smallest_key <= key)
-            if idx == 0 {
-                continue;
-            }
-            let tf = &files[idx - 1];
-            let lk = &tf.meta.largest_key;
-            let file_largest = if lk.len() >= 8 {
-                &lk[..lk.len() - 8]
-            } else {
-                lk.as_slice()
+    /// Resolve `key` against one point-lookup source (memtable or SST).
+    ///
+    /// `lookup(s)` returns the newest entry with seq <= `s`. Returns
+    /// `Ok(Some(result))` once the key is resolved, or `Ok(None)` when the
+    /// source has no (further) entry for the key and older sources must be
+    /// consulted — `Merge` operands found on the way are appended to
+    /// `operands`, newest first. An `ExpiringValue` past `now_millis` reads
+    /// as a deletion, and a `BlobIndex` is read from its blob file in
+    /// `version`.
+    #[allow(clippy::too_many_arguments)]
+    fn resolve_point_in_source(
+        &self,
+        key: &[u8],
+        seq: SequenceNumber,
+        now_millis: u64,
+        version: &Version,
+        max_tomb_seq: SequenceNumber,
+        operands: &mut Vec<Vec<u8>>,
+        mut lookup: impl FnMut(SequenceNumber) -> Result<Option<(ValueType, Vec<u8>, SequenceNumber)>>,
+    ) -> Result<Option<Option<Vec<u8>>>> {
+        let mut read_seq = seq;
+        loop {
+            let Some((vt, value, entry_seq)) = lookup(read_seq)? else {
+                return Ok(None);
             };
-            if key <= file_largest
-                && let Some((result, entry_seq)) = tf
-                    .reader
-                    .get_internal_with_seq(key, seq, options.fill_cache)
-                    .ctx()?
-            {
-                if max_tomb_seq > entry_seq {
-                    return Ok(None);
+            // If a range tombstone with higher seq exists, it deletes this entry
+            if max_tomb_seq > entry_seq {
+                return self.finish_point_read(key, None, operands).map(Some);
+            }
+            match vt {
+                ValueType::Value => {
+                    return self.finish_point_read(key, Some(value), operands).map(Some);
                 }
-                // Sample reads at level >= 2 for read-triggered compaction.
-                if level >= 2 {
-                    self.stats.maybe_sample_read_level(level);
-                    self.maybe_check_read_compaction();
+                ValueType::BlobIndex => {
+                    let value = version.blob_value(&value)?.try_into_vec().ctx()?;
+                    return self.finish_point_read(key, Some(value), operands).map(Some);
                 }
-                if let Some(ref v) = result {
-                    self.stats.record_read(key.len() as u64 + v.len() as u64);
+                ValueType::ExpiringValue => {
+                    let mut value = value;
+                    let live = types::expiring_value_len(&value, now_millis).ctx()?;
+                    let base = live.map(|len| {
+                        value.truncate(len);
+                        value
+                    });
+                    return self.finish_point_read(key, base, operands).map(Some);
+                }
+                ValueType::Deletion | ValueType::RangeDeletion => {
+                    return self.finish_point_read(key, None, operands).map(Some);
+                }
+                ValueType::Merge => {
+                    operands.push(value);
+                    if entry_seq == 0 {
+                        return Ok(None);
+                    }
+                    read_seq = entry_seq - 1;
                 }
-                return Ok(result);
             }
         }
+    }
 
-        // If a range tombstone was found in SSTs but no point entry, key is deleted
-        if max_tomb_seq > 0 {
-            return Ok(None);
+    /// Produce the final point-read result from the resolved base value and
+    /// any merge operands stacked on top of it.
+    fn finish_point_read(
+        &self,
+        key: &[u8],
+        base: Option<Vec<u8>>,
+        operands: &[Vec<u8>],
+    ) -> Result<Option<Vec<u8>>> {
+        let result = if operands.is_empty() {
+            base
+        } else {
+            let op = require_merge_operator(self.options.merge_operator.as_ref()).ctx()?;
+            Some(
+                op.full_merge_newest_first(key, base.as_deref(), operands)
+                    .ctx()?,
+            )
+        };
+        if let Some(ref v) = result {
+            self.stats.record_read(key.len() as u64 + v.len() as u64);
         }
-
-        Ok(None)
+        Ok(result)
     }
 
     /// Create a forward iterator over the entire database.
@@ -1759,6 +3876,11 @@ impl DB {
         self.check_usable().ctx()?;
 
         let seq = self.resolve_read_sequence(options.snapshot);
+        let read_ts = self.read_timestamp(options)?;
+        // File metadata holds full user keys; prune against the newest
+        // possible version of each plain-key bound.
+        let lower_version = lower_bound.map(|b| self.icmp.first_version(b));
+        let upper_version = upper_bound.map(|b| self.icmp.first_version(b));
 
         // Lock-free read: use SuperVersion instead of locking inner.
         let sv = self.get_super_version();
@@ -1799,13 +3921,15 @@ impl DB {
                 any_range_deletions = true;
             }
             // Check if this file's key range overlaps the bound range.
-            if let Some(lo) = lower_bound
-                && types::user_key(&tf.meta.largest_key) < lo
+            if let Some(lo) = lower_version.as_deref()
+                && self.icmp.user_lt(types::user_key(&tf.meta.largest_key), lo)
             {
                 continue;
             }
-            if let Some(hi) = upper_bound
-                && types::user_key(&tf.meta.smallest_key) > hi
+            if let Some(hi) = upper_version.as_deref()
+                && self
+                    .icmp
+                    .user_lt(hi, types::user_key(&tf.meta.smallest_key))
             {
                 continue;
             }
@@ -1832,8 +3956,8 @@ impl DB {
             let mut level_iter = LevelIterator::new(files.to_vec())
                 .with_fill_cache(options.fill_cache)
                 .with_range_hints(
-                    lower_bound.map(|s| s.to_vec()),
-                    upper_bound.map(|e| e.to_vec()),
+                    lower_version.as_deref().map(<[u8]>::to_vec),
+                    upper_version.as_deref().map(<[u8]>::to_vec),
                 );
             if !options.block_property_filters.is_empty() {
                 level_iter = level_iter.with_block_filters(options.block_property_filters.clone());
@@ -1841,21 +3965,34 @@ impl DB {
             sources.push(IterSource::from_level_iter(level_iter).with_level(level));
         }
 
-        let mut db_iter = DBIterator::from_sources(sources, seq);
+        let mut db_iter = DBIterator::with_comparator(sources, seq, self.icmp.clone());
+        db_iter.set_merge_operator(self.options.merge_operator.clone());
+        db_iter.set_now_millis(self.options.clock.now_millis());
+        db_iter.set_stats(self.stats.clone());
+        db_iter.set_blob_source(version.clone());
+        if let Some(ts) = read_ts {
+            db_iter.set_read_timestamp(ts);
+        }
 
         // Apply bounds: merge explicit parameters with ReadOptions bounds, using tighter of the two.
         let effective_lower = match (&options.iterate_lower_bound, lower_bound) {
-            (Some(opt_lo), Some(param_lo)) => {
-                Some(std::cmp::max(opt_lo.as_slice(), param_lo).to_vec())
-            }
+            (Some(opt_lo), Some(param_lo)) => Some(
+                std::cmp::max_by(opt_lo.as_slice(), param_lo, |a, b| {
+                    self.icmp.compare_key(a, b)
+                })
+                .to_vec(),
+            ),
             (Some(opt_lo), None) => Some(opt_lo.clone()),
             (None, Some(param_lo)) => Some(param_lo.to_vec()),
             (None, None) => None,
         };
         let effective_upper = match (&options.iterate_upper_bound, upper_bound) {
-            (Some(opt_hi), Some(param_hi)) => {
-                Some(std::cmp::min(opt_hi.as_slice(), param_hi).to_vec())
-            }
+            (Some(opt_hi), Some(param_hi)) => Some(
+                std::cmp::min_by(opt_hi.as_slice(), param_hi, |a, b| {
+                    self.icmp.compare_key(a, b)
+                })
+                .to_vec(),
+            ),
             (Some(opt_hi), None) => Some(opt_hi.clone()),
             (None, Some(param_hi)) => Some(param_hi.to_vec()),
             (None, None) => None,
@@ -1876,6 +4013,7 @@ impl DB {
             // Memtable tombstones are at level 0 (highest priority).
             if active_mem.has_range_deletions() {
                 collect_tombstones_overlapping_bounds(
+                    &self.icmp,
                     &mut all_tombstones,
                     active_mem.get_range_tombstones(),
                     0,
@@ -1886,6 +4024,7 @@ impl DB {
             for imm in imm_mems {
                 if imm.has_range_deletions() {
                     collect_tombstones_overlapping_bounds(
+                        &self.icmp,
                         &mut all_tombstones,
                         imm.get_range_tombstones(),
                         0,
@@ -1898,6 +4037,7 @@ impl DB {
             for tf in version.level_files(0) {
                 if tf.meta.has_range_deletions {
                     collect_tombstones_overlapping_bounds(
+                        &self.icmp,
                         &mut all_tombstones,
                         tf.reader.get_range_tombstones().ctx()?,
                         0,
@@ -1913,12 +4053,15 @@ impl DB {
                         // upper bound.  We cannot use largest_key < lower_bound
                         // to skip because range tombstones can extend past the
                         // file's largest_key.
-                        if let Some(hi) = upper_bound
-                            && types::user_key(&tf.meta.smallest_key) > hi
+                        if let Some(hi) = upper_version.as_deref()
+                            && self
+                                .icmp
+                                .user_lt(hi, types::user_key(&tf.meta.smallest_key))
                         {
                             continue;
                         }
                         collect_tombstones_overlapping_bounds(
+                            &self.icmp,
                             &mut all_tombstones,
                             tf.reader.get_range_tombstones().ctx()?,
                             level,
@@ -1964,6 +4107,10 @@ impl DB {
     ///
     /// Set `ReadOptions::iterate_lower_bound` / `iterate_upper_bound` to further
     /// restrict iteration to a sub-range inside the prefix.
+    ///
+    /// Fails with `InvalidArgument` unless the comparator orders keys
+    /// bytewise: under any other order the keys sharing a prefix need not
+    /// be adjacent. Use `iter()` and filter on the prefix instead.
     pub fn iter_with_prefix(&self, prefix: &[u8], options: &ReadOptions) -> Result<DBIterator> {
         let seq = self.resolve_read_sequence(options.snapshot);
         self.iter_with_prefix_inner(prefix, seq, options)
@@ -1977,6 +4124,13 @@ impl DB {
         options: &ReadOptions,
     ) -> Result<DBIterator> {
         self.check_usable().ctx()?;
+        if !self.icmp.is_bytewise() {
+            return Err(Error::invalid_argument(format!(
+                "prefix iteration needs a bytewise comparator, not {}",
+                self.icmp.name()
+            )));
+        }
+        let read_ts = self.read_timestamp(options)?;
 
         // Lock-free read via SuperVersion.
         let sv = self.get_super_version();
@@ -2023,6 +4177,11 @@ impl DB {
             }
             if carry { None } else { Some(upper) }
         };
+        // File metadata holds full user keys (see `iter_with_range`).
+        let prefix_version = self.icmp.first_version(prefix);
+        let prefix_upper_version = prefix_upper
+            .as_deref()
+            .map(|pu| self.icmp.first_version(pu).into_owned());
 
         // SST files — skip files via range pruning + prefix bloom.
         // L0: per-file filtering (files may overlap).
@@ -2034,11 +4193,16 @@ impl DB {
             if tf.meta.has_range_deletions {
                 any_range_deletions = true;
             }
-            if types::user_key(&tf.meta.largest_key) < prefix {
+            if self
+                .icmp
+                .user_lt(types::user_key(&tf.meta.largest_key), &prefix_version)
+            {
                 continue;
             }
-            if let Some(ref pu) = prefix_upper
-                && types::user_key(&tf.meta.smallest_key) >= pu.as_slice()
+            if let Some(ref pu) = prefix_upper_version
+                && !self
+                    .icmp
+                    .user_lt(types::user_key(&tf.meta.smallest_key), pu)
             {
                 continue;
             }
@@ -2069,14 +4233,26 @@ impl DB {
             let mut level_iter = LevelIterator::new(files.to_vec())
                 .with_fill_cache(options.fill_cache)
                 .with_prefix(prefix_owned.to_vec())
-                .with_range_hints(Some(prefix_owned.to_vec()), prefix_upper.clone());
+                .with_range_hints(Some(prefix_version.to_vec()), prefix_upper_version.clone());
             if !options.block_property_filters.is_empty() {
                 level_iter = level_iter.with_block_filters(options.block_property_filters.clone());
             }
             sources.push(IterSource::from_level_iter(level_iter).with_level(level));
         }
 
-        let mut iter = DBIterator::from_sources_with_prefix(sources, seq, prefix_owned.to_vec());
+        let mut iter = DBIterator::from_sources_with_prefix(
+            sources,
+            seq,
+            prefix_owned.to_vec(),
+            self.icmp.clone(),
+        );
+        iter.set_merge_operator(self.options.merge_operator.clone());
+        iter.set_now_millis(self.options.clock.now_millis());
+        iter.set_stats(self.stats.clone());
+        iter.set_blob_source(version.clone());
+        if let Some(ts) = read_ts {
+            iter.set_read_timestamp(ts);
+        }
 
         // Collect all range tombstones with level info for cross-level pruning.
         // The iterator only yields keys within [prefix, prefix_upper), so
@@ -2087,6 +4263,7 @@ impl DB {
             let mut all_tombstones: Vec<(Vec<u8>, Vec<u8>, u64, usize)> = Vec::new();
             if active_mem.has_range_deletions() {
                 collect_tombstones_overlapping_bounds(
+                    &self.icmp,
                     &mut all_tombstones,
                     active_mem.get_range_tombstones(),
                     0,
@@ -2097,6 +4274,7 @@ impl DB {
             for imm in imm_mems {
                 if imm.has_range_deletions() {
                     collect_tombstones_overlapping_bounds(
+                        &self.icmp,
                         &mut all_tombstones,
                         imm.get_range_tombstones(),
                         0,
@@ -2108,6 +4286,7 @@ impl DB {
             for tf in version.level_files(0) {
                 if tf.meta.has_range_deletions {
                     collect_tombstones_overlapping_bounds(
+                        &self.icmp,
                         &mut all_tombstones,
                         tf.reader.get_range_tombstones().ctx()?,
                         0,
@@ -2123,12 +4302,15 @@ impl DB {
                         // upper bound.  We cannot use largest_key < prefix
                         // to skip because range tombstones can extend past
                         // the file's largest_key.
-                        if let Some(ref pu) = prefix_upper
-                            && types::user_key(&tf.meta.smallest_key) >= pu.as_slice()
+                        if let Some(ref pu) = prefix_upper_version
+                            && !self
+                                .icmp
+                                .user_lt(types::user_key(&tf.meta.smallest_key), pu)
                         {
                             continue;
                         }
                         collect_tombstones_overlapping_bounds(
+                            &self.icmp,
                             &mut all_tombstones,
                             tf.reader.get_range_tombstones().ctx()?,
                             level,
@@ -2157,7 +4339,7 @@ impl DB {
 
         // Seek to the prefix start (or lower bound if tighter)
         match &options.iterate_lower_bound {
-            Some(lb) if lb.as_slice() > prefix => iter.seek(lb),
+            Some(lb) if self.icmp.key_lt(prefix, lb) => iter.seek(lb),
             _ => iter.seek(prefix),
         }
 
@@ -2193,7 +4375,7 @@ impl DB {
                 "sequence number space exhausted".to_string(),
             ));
         }
-        let batch_entries = batch.sorted_entries(batch_base_seq).ctx()?;
+        let batch_entries = batch.sorted_entries(batch_base_seq, &self.icmp).ctx()?;
 
         // Lock-free read via SuperVersion.
         let sv = self.get_super_version();
@@ -2232,84 +4414,399 @@ impl DB {
             sources.push(IterSource::from_level_iter(level_iter).with_level(level));
         }
 
-        let mut db_iter = DBIterator::from_sources(sources, seq);
-        if batch_count > 0 {
-            db_iter.set_batch_seq_floor(batch_base_seq);
-        }
+        let mut db_iter = DBIterator::with_comparator(sources, seq, self.icmp.clone());
+        db_iter.set_merge_operator(self.options.merge_operator.clone());
+        db_iter.set_now_millis(self.options.clock.now_millis());
+        db_iter.set_stats(self.stats.clone());
+        db_iter.set_blob_source(version.clone());
+        if batch_count > 0 {
+            db_iter.set_batch_seq_floor(batch_base_seq);
+        }
+
+        // Collect range tombstones (same as iter_with_range). Batch iterators
+        // lift the tombstone visibility bound to MAX (the batch overlay lives
+        // in the reserved top region of the sequence space — see
+        // `DBIterator::set_batch_seq_floor`), so DB-sourced tombstones
+        // committed after `seq` was captured must be excluded here; other
+        // iterator paths instead filter them at query time.
+        let mut all_tombstones: Vec<(Vec<u8>, Vec<u8>, u64, usize)> = Vec::new();
+        // Batch range tombstones use position-based sequences (preserving write order).
+        for &(ref b, ref e, pos) in batch.range_tombstones() {
+            all_tombstones.push((b.clone(), e.clone(), batch_base_seq + pos, 0));
+        }
+        if active_mem.has_range_deletions() {
+            for (b, e, s) in active_mem.get_range_tombstones() {
+                if s <= seq {
+                    all_tombstones.push((b, e, s, 0));
+                }
+            }
+        }
+        for imm in imm_mems.iter() {
+            if imm.has_range_deletions() {
+                for (b, e, s) in imm.get_range_tombstones() {
+                    if s <= seq {
+                        all_tombstones.push((b, e, s, 0));
+                    }
+                }
+            }
+        }
+        for tf in version.level_files(0) {
+            if tf.meta.has_range_deletions {
+                let ts = tf.reader.get_range_tombstones().ctx()?;
+                for (b, e, s) in ts {
+                    if s <= seq {
+                        all_tombstones.push((b, e, s, 0));
+                    }
+                }
+            }
+        }
+        for level in 1..version.num_levels {
+            for tf in version.level_files(level) {
+                if tf.meta.has_range_deletions {
+                    let ts = tf.reader.get_range_tombstones().ctx()?;
+                    for (b, e, s) in ts {
+                        if s <= seq {
+                            all_tombstones.push((b, e, s, level));
+                        }
+                    }
+                }
+            }
+        }
+        if !all_tombstones.is_empty() {
+            db_iter.set_range_tombstones_with_levels(all_tombstones);
+        }
+
+        Ok(db_iter)
+    }
+
+    /// An empty indexed batch ordered like this DB, for transactions.
+    pub(crate) fn new_indexed_batch(&self) -> WriteBatchWithIndex {
+        WriteBatchWithIndex::with_comparator(&self.options.comparator)
+    }
+
+    pub(crate) fn snapshot_seq(&self) -> SequenceNumber {
+        // Register the snapshot under the DB lock so that its sequence number and
+        // its registration are atomic with respect to compaction, which captures
+        // the snapshot list *after* picking its input files under the same lock.
+        // This guarantees a compaction either observes this snapshot, or picks
+        // inputs whose maximum sequence is <= this snapshot's sequence (so the
+        // snapshot can never need a version the compaction might garbage-collect).
+        let _inner = self.inner.lock();
+        let seq = self.current_sequence();
+        self.snapshot_list.acquire(seq)
+    }
+
+    /// Acquire a snapshot with RAII guard. The snapshot is automatically released
+    /// when the `Snapshot` is dropped — no manual `release_snapshot()` needed.
+    pub fn snapshot(&self) -> Snapshot<'_> {
+        let seq = self.snapshot_seq();
+        Snapshot { db: self, seq }
+    }
+
+    /// Begin an optimistic transaction reading at the current sequence.
+    /// See [`OptimisticTransaction`] for the conflict rules.
+    pub fn begin_optimistic_transaction(&self) -> OptimisticTransaction<'_> {
+        OptimisticTransaction::new(self)
+    }
+
+    // -- Column families --
+
+    /// Create a column family with its own options.
+    ///
+    /// The family gets a new, never-reused id and its own directory
+    /// (`cf-<id>`) for MANIFEST and SST files; its writes share this DB's
+    /// WAL and sequence space.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ErrorKind::InvalidArgument`](crate::ErrorKind::InvalidArgument)
+    /// for an empty or `"default"` name, or when a family with that name
+    /// already exists.
+    pub fn create_column_family(
+        &self,
+        name: &str,
+        options: DbOptions,
+    ) -> Result<ColumnFamilyHandle> {
+        Self::validate_family_name(name)?;
+        self.check_writable().ctx()?;
+        let mut wq = self.write_queue.lock();
+        self.wait_for_write_leader_idle(&mut wq);
+        self.check_writable().ctx()?;
+        if self.column_family(name).is_some() {
+            return Err(Error::invalid_argument(format!(
+                "column family {:?} already exists",
+                name
+            )));
+        }
+        let family = {
+            let mut inner = self.inner.lock();
+            let wal_number = inner.wal_number;
+            Self::create_family(
+                &self.path,
+                &mut inner.versions,
+                name,
+                options,
+                &self.family_link(),
+                wal_number,
+            )?
+        };
+        let handle = family.handle.clone();
+        self.column_families.write().insert(handle.id, family);
+        Ok(handle)
+    }
+
+    /// Drop a column family and delete its data.
+    ///
+    /// Entries of the family still present in the WAL are ignored from now
+    /// on, including during recovery.
+    pub fn drop_column_family(&self, cf: &ColumnFamilyHandle) -> Result<()> {
+        if cf.id == 0 {
+            return Err(Error::invalid_argument(
+                "the default column family cannot be dropped".to_string(),
+            ));
+        }
+        self.check_writable().ctx()?;
+        let family = {
+            let mut wq = self.write_queue.lock();
+            self.wait_for_write_leader_idle(&mut wq);
+            self.check_writable().ctx()?;
+            let family = self.family(cf)?;
+            let manifest_handle = {
+                let mut inner = self.inner.lock();
+                let mut edit = VersionEdit::new();
+                edit.drop_column_family(cf.id);
+                inner.versions.log_and_apply(edit).ctx()?;
+                inner.versions.manifest_sync_handle()
+            };
+            if let Err(e) = confirm_manifest_durable(&manifest_handle, &self.manifest_poisoned) {
+                self.set_bg_error(format!("column family drop manifest sync failed: {}", e));
+                return Err(e).ctx();
+            }
+            self.column_families.write().remove(&cf.id);
+            family
+        };
+        // The drop is durable; closing and deleting the engine is cleanup
+        // that the next open redoes if it fails here.
+        if let Err(e) = family.db.close() {
+            tracing::warn!("closing dropped column family {:?}: {}", cf.name, e);
+        }
+        let dir = family_dir(&self.path, cf.id);
+        let gate = self.file_deletion_gate.read();
+        let removed = fs::remove_dir_all(&dir);
+        drop(gate);
+        if let Err(e) = removed {
+            tracing::warn!(
+                "failed to remove column family dir {}: {}",
+                dir.display(),
+                e
+            );
+        }
+        self.flusher.remove_obsolete_wals();
+        Ok(())
+    }
+
+    /// Look up a column family by name. `"default"` always resolves.
+    pub fn column_family(&self, name: &str) -> Option<ColumnFamilyHandle> {
+        if name == DEFAULT_COLUMN_FAMILY_NAME {
+            return Some(ColumnFamilyHandle::default_family());
+        }
+        self.column_families
+            .read()
+            .values()
+            .find(|f| &*f.handle.name == name)
+            .map(|f| f.handle.clone())
+    }
+
+    /// All column families, the default family first.
+    pub fn column_families(&self) -> Vec<ColumnFamilyHandle> {
+        iter::once(ColumnFamilyHandle::default_family())
+            .chain(
+                self.column_families
+                    .read()
+                    .values()
+                    .map(|f| f.handle.clone()),
+            )
+            .collect()
+    }
+
+    pub fn put_cf(&self, cf: &ColumnFamilyHandle, key: &[u8], value: &[u8]) -> Result<()> {
+        let mut batch = WriteBatch::new();
+        batch.put_cf(cf, key, value);
+        self.write(batch)
+    }
+
+    pub fn delete_cf(&self, cf: &ColumnFamilyHandle, key: &[u8]) -> Result<()> {
+        let mut batch = WriteBatch::new();
+        batch.delete_cf(cf, key);
+        self.write(batch)
+    }
+
+    /// Delete all keys of `cf` in `[begin, end)`.
+    pub fn delete_range_cf(&self, cf: &ColumnFamilyHandle, begin: &[u8], end: &[u8]) -> Result<()> {
+        let mut batch = WriteBatch::new();
+        batch.delete_range_cf(cf, begin, end);
+        self.write(batch)
+    }
+
+    /// Add a merge operand, folded by the family's own merge operator.
+    pub fn merge_cf(&self, cf: &ColumnFamilyHandle, key: &[u8], operand: &[u8]) -> Result<()> {
+        let mut batch = WriteBatch::new();
+        batch.merge_cf(cf, key, operand);
+        self.write(batch)
+    }
+
+    pub fn get_cf(&self, cf: &ColumnFamilyHandle, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        self.get_cf_with_options(cf, &ReadOptions::default(), key)
+    }
+
+    /// Point lookup in `cf`. A snapshot from [`Self::snapshot`] is valid for
+    /// every family of this DB.
+    pub fn get_cf_with_options(
+        &self,
+        cf: &ColumnFamilyHandle,
+        options: &ReadOptions,
+        key: &[u8],
+    ) -> Result<Option<Vec<u8>>> {
+        self.with_family(cf, |db| db.get_with_options(options, key))
+    }
+
+    /// Iterate over `cf`, honoring the same `ReadOptions` as
+    /// [`Self::iter_with_options`].
+    pub fn iter_cf(&self, cf: &ColumnFamilyHandle, options: &ReadOptions) -> Result<DBIterator> {
+        self.with_family(cf, |db| db.iter_with_options(options))
+    }
 
-        // Collect range tombstones (same as iter_with_range). Batch iterators
-        // lift the tombstone visibility bound to MAX (the batch overlay lives
-        // in the reserved top region of the sequence space — see
-        // `DBIterator::set_batch_seq_floor`), so DB-sourced tombstones
-        // committed after `seq` was captured must be excluded here; other
-        // iterator paths instead filter them at query time.
-        let mut all_tombstones: Vec<(Vec<u8>, Vec<u8>, u64, usize)> = Vec::new();
-        // Batch range tombstones use position-based sequences (preserving write order).
-        for &(ref b, ref e, pos) in batch.range_tombstones() {
-            all_tombstones.push((b.clone(), e.clone(), batch_base_seq + pos, 0));
-        }
-        if active_mem.has_range_deletions() {
-            for (b, e, s) in active_mem.get_range_tombstones() {
-                if s <= seq {
-                    all_tombstones.push((b, e, s, 0));
-                }
-            }
+    /// Flush the memtable of `cf` to SST.
+    pub fn flush_cf(&self, cf: &ColumnFamilyHandle) -> Result<()> {
+        if cf.id == 0 {
+            return self.flush();
         }
-        for imm in imm_mems.iter() {
-            if imm.has_range_deletions() {
-                for (b, e, s) in imm.get_range_tombstones() {
-                    if s <= seq {
-                        all_tombstones.push((b, e, s, 0));
-                    }
-                }
-            }
+        self.check_writable().ctx()?;
+        let family = self.family(cf)?;
+        let mut wq = self.write_queue.lock();
+        self.wait_for_write_leader_idle(&mut wq);
+        self.check_writable().ctx()?;
+        self.flush_family(&family)
+    }
+
+    /// Like [`Self::compact_range`], for column family `cf`.
+    pub fn compact_range_cf(
+        &self,
+        cf: &ColumnFamilyHandle,
+        begin: Option<&[u8]>,
+        end: Option<&[u8]>,
+    ) -> Result<()> {
+        if cf.id == 0 {
+            return self.compact_range(begin, end);
         }
-        for tf in version.level_files(0) {
-            if tf.meta.has_range_deletions {
-                let ts = tf.reader.get_range_tombstones().ctx()?;
-                for (b, e, s) in ts {
-                    if s <= seq {
-                        all_tombstones.push((b, e, s, 0));
-                    }
-                }
-            }
+        self.flush_cf(cf)?;
+        self.family(cf)?.db.compact_range(begin, end)
+    }
+
+    /// Like [`Self::get_property`], for column family `cf`.
+    pub fn get_property_cf(&self, cf: &ColumnFamilyHandle, name: &str) -> Option<String> {
+        if cf.id == 0 {
+            return self.get_property(name);
         }
-        for level in 1..version.num_levels {
-            for tf in version.level_files(level) {
-                if tf.meta.has_range_deletions {
-                    let ts = tf.reader.get_range_tombstones().ctx()?;
-                    for (b, e, s) in ts {
-                        if s <= seq {
-                            all_tombstones.push((b, e, s, level));
-                        }
-                    }
-                }
-            }
+        self.family(cf).ok()?.db.get_property(name)
+    }
+
+    fn validate_family_name(name: &str) -> Result<()> {
+        if name.is_empty() {
+            return Err(Error::invalid_argument(
+                "column family name must not be empty".to_string(),
+            ));
         }
-        if !all_tombstones.is_empty() {
-            db_iter.set_range_tombstones_with_levels(all_tombstones);
+        if name == DEFAULT_COLUMN_FAMILY_NAME {
+            return Err(Error::invalid_argument(format!(
+                "column family name {:?} is reserved",
+                name
+            )));
         }
-
-        Ok(db_iter)
+        Ok(())
     }
 
-    pub(crate) fn snapshot_seq(&self) -> SequenceNumber {
-        // Register the snapshot under the DB lock so that its sequence number and
-        // its registration are atomic with respect to compaction, which captures
-        // the snapshot list *after* picking its input files under the same lock.
-        // This guarantees a compaction either observes this snapshot, or picks
-        // inputs whose maximum sequence is <= this snapshot's sequence (so the
-        // snapshot can never need a version the compaction might garbage-collect).
-        let _inner = self.inner.lock();
-        let seq = self.current_sequence();
-        self.snapshot_list.acquire(seq)
+    /// Resolve a non-default handle to its open family.
+    fn family(&self, cf: &ColumnFamilyHandle) -> Result<Arc<ColumnFamily>> {
+        self.check_usable()?;
+        self.column_families
+            .read()
+            .get(&cf.id)
+            .filter(|f| f.handle.name == cf.name)
+            .cloned()
+            .ok_or_else(|| Error::invalid_argument(format!("unknown column family {:?}", cf.name)))
     }
 
-    /// Acquire a snapshot with RAII guard. The snapshot is automatically released
-    /// when the `Snapshot` is dropped — no manual `release_snapshot()` needed.
-    pub fn snapshot(&self) -> Snapshot<'_> {
-        let seq = self.snapshot_seq();
-        Snapshot { db: self, seq }
+    /// Run `f` against the engine backing `cf`.
+    fn with_family<R>(
+        &self,
+        cf: &ColumnFamilyHandle,
+        f: impl FnOnce(&DB) -> Result<R>,
+    ) -> Result<R> {
+        if cf.id == 0 {
+            return f(self);
+        }
+        f(&self.family(cf)?.db)
+    }
+
+    /// The state this DB shares with its column families.
+    fn family_link(&self) -> FamilyLink {
+        FamilyLink {
+            id: self.family_id,
+            create_at_wal: None,
+            sequence: self.sequence.clone(),
+            committed_sequence: self.committed_sequence.clone(),
+            snapshot_list: self.snapshot_list.clone(),
+            has_bg_error: self.has_bg_error.clone(),
+            bg_error: self.bg_error.clone(),
+            file_deletion_gate: self.file_deletion_gate.clone(),
+        }
+    }
+
+    /// Create column family `name` under the next unused id: open its engine
+    /// in a fresh directory, then register it in the owner's MANIFEST. A
+    /// crash in between leaves an unregistered directory that the next open
+    /// removes.
+    fn create_family(
+        path: &Path,
+        versions: &mut VersionSet,
+        name: &str,
+        mut options: DbOptions,
+        shared: &FamilyLink,
+        wal_number: u64,
+    ) -> Result<Arc<ColumnFamily>> {
+        let id = versions
+            .max_column_family()
+            .checked_add(1)
+            .ok_or_else(|| Error::invalid_argument("column family ids exhausted".to_string()))?;
+        let dir = family_dir(path, id);
+        if dir.exists() {
+            fs::remove_dir_all(&dir).ctx()?;
+        }
+        options.create_if_missing = true;
+        options.error_if_exists = false;
+        let db = Self::open_impl(
+            options,
+            &dir,
+            OpenMode::ReadWrite,
+            &[],
+            Some(shared.child(id, Some(wal_number))),
+        )
+        .with_ctx(|| format!("failed to create column family {:?}", name))?;
+
+        let mut edit = VersionEdit::new();
+        edit.add_column_family(id, name);
+        edit.set_max_column_family(id);
+        if let Err(e) = versions.log_and_apply(edit) {
+            let _ = db.close();
+            let _ = fs::remove_dir_all(&dir);
+            return Err(e).ctx();
+        }
+        versions.sync_manifest().ctx()?;
+        Ok(Arc::new(ColumnFamily {
+            handle: ColumnFamilyHandle::new(id, name),
+            db,
+        }))
     }
 
     /// Release a previously acquired snapshot so compaction can reclaim its data.
@@ -2325,6 +4822,12 @@ impl DB {
         &self.path
     }
 
+    /// What WAL replay applied at open, and any corrupted records it
+    /// tolerated or skipped under [`DbOptions::wal_recovery_mode`].
+    pub fn wal_recovery_summary(&self) -> &WalRecoverySummary {
+        &self.wal_recovery
+    }
+
     /// Get a database property.
     ///
     /// Supported properties:
@@ -2335,6 +4838,7 @@ impl DB {
     ///   [`BlockCachePool`](crate::BlockCachePool), the count is the
     ///   **pool-wide** LRU total across every attached member (plus this
     ///   DB's own pinned entries) — not this DB's share of it.
+    /// - `"num-immutable-mem-table"` — frozen memtables not yet flushed
     /// - `"compaction-pending"` — "1" if compaction is needed, "0" otherwise
     /// - `"stats.bytes_written"` — total user bytes written
     /// - `"stats.bytes_read"` — total user bytes read
@@ -2344,7 +4848,29 @@ impl DB {
     /// - `"stats.block_cache_hits"` — block cache hit count
     /// - `"stats.block_cache_misses"` — block cache miss count
     /// - `"stats.cache_hit_rate"` — block cache hit rate (0.0 to 1.0)
+    ///
+    /// Multi-line and estimated properties, read without the DB lock:
+    /// - `"mmdb.levelstats"` — a table of each level's file count, size,
+    ///   compaction score, and bytes compaction read from and wrote into it
+    /// - `"mmdb.stats"` — a compaction stats dump: per-level bytes read from
+    ///   the level above (`Rn`) and from the level itself (`Rnp1`), written,
+    ///   and trivially moved; write amplification (bytes written into the
+    ///   level per byte that entered it, by flush for L0 or from the level
+    ///   above); space amplification (bytes down to and including the level
+    ///   per byte in it; on the `Sum` row, total bytes per estimated live
+    ///   byte); then DB-wide totals and estimates. Also logged every
+    ///   [`DbOptions::stats_dump_period`]
+    /// - `"mmdb.estimate-pending-compaction-bytes"` — bytes compaction must
+    ///   rewrite to bring every level under its target
+    /// - `"mmdb.estimate-live-data-size"` — SST bytes not shadowed by newer
+    ///   data higher up in the tree
+    /// - `"mmdb.estimate-num-keys"` — entries in the memtables and SSTs,
+    ///   counting overwritten and deleted keys; each SST is sampled from
+    ///   its first data block
     pub fn get_property(&self, name: &str) -> Option<String> {
+        if name.starts_with("mmdb.") {
+            return self.estimated_property(name);
+        }
         let inner = self.inner.lock();
 
         if let Some(level_str) = name.strip_prefix("num-files-at-level") {
@@ -2369,13 +4895,14 @@ impl DB {
                 Some(total.to_string())
             }
             "block-cache-usage" => Some(self.block_cache.entry_count().to_string()),
+            "num-immutable-mem-table" => Some(inner.immutable_memtables.len().to_string()),
             "compaction-pending" => {
                 let version = inner.versions.current();
                 // Informational only (not an actual pick+claim), so don't
                 // bother excluding in-flight files here.
-                let needed =
-                    LeveledCompaction::pick_compaction(&version, &self.options, &HashSet::new())
-                        .is_some();
+                let needed = compaction::picker(self.options.compaction_style)
+                    .pick_compaction(&version, &self.options, &HashSet::new())
+                    .is_some();
                 Some(if needed { "1" } else { "0" }.to_string())
             }
             "stats.bytes_written" => {
@@ -2426,6 +4953,200 @@ impl DB {
         }
     }
 
+    /// The `mmdb.*` properties of [`Self::get_property`], computed from the
+    /// current super version.
+    fn estimated_property(&self, name: &str) -> Option<String> {
+        let sv = self.get_super_version();
+        let version = &sv.version;
+        match name {
+            "mmdb.levelstats" => Some(stats::format_level_stats(
+                version,
+                &self.options,
+                &self.stats,
+            )),
+            "mmdb.stats" => Some(stats::format_stats_dump(
+                version,
+                &self.options,
+                &self.stats,
+            )),
+            "mmdb.estimate-pending-compaction-bytes" => Some(
+                compaction::picker(self.options.compaction_style)
+                    .estimate_pending_compaction_bytes(version, &self.options)
+                    .to_string(),
+            ),
+            "mmdb.estimate-live-data-size" => Some(version.estimate_live_data_size().to_string()),
+            "mmdb.estimate-num-keys" => {
+                let memtables: u64 = std::iter::once(&sv.active_memtable)
+                    .chain(sv.immutable_memtables.iter())
+                    .map(|m| m.num_entries() as u64)
+                    .sum();
+                let tables: u64 = version
+                    .files
+                    .iter()
+                    .flatten()
+                    .map(|tf| {
+                        tf.reader.estimate_num_entries().unwrap_or_else(|e| {
+                            tracing::warn!(
+                                "failed to estimate entries of SST {}: {}",
+                                tf.meta.number,
+                                e
+                            );
+                            0
+                        })
+                    })
+                    .sum();
+                Some((memtables + tables).to_string())
+            }
+            _ => None,
+        }
+    }
+
+    /// Snapshot of this DB's counters, tickers and latency histograms.
+    ///
+    /// The tickers and histograms are recorded only with
+    /// [`DbOptions::enable_statistics`]. Column families keep their own;
+    /// see [`Self::statistics_cf`]. WAL syncs and writes are recorded here
+    /// whichever families a batch touches.
+    pub fn statistics(&self) -> Statistics {
+        self.stats.snapshot(self.options.num_levels)
+    }
+
+    /// Like [`Self::statistics`], for column family `cf`.
+    pub fn statistics_cf(&self, cf: &ColumnFamilyHandle) -> Result<Statistics> {
+        if cf.id == 0 {
+            return Ok(self.statistics());
+        }
+        Ok(self.family(cf)?.db.statistics())
+    }
+
+    /// This DB's metrics in the OpenMetrics text format, labelled with
+    /// `db` set to its path. Use [`MetricsExporter`] to report several
+    /// DBs, or a shared [`BlockCachePool`](crate::BlockCachePool), in one
+    /// exposition.
+    pub fn metrics_openmetrics(&self) -> String {
+        let mut exporter = MetricsExporter::new();
+        exporter.add_db(self.path.display().to_string(), self);
+        exporter.render()
+    }
+
+    /// Metrics of the default column family followed by every other open
+    /// family, for [`MetricsExporter`].
+    pub(crate) fn family_metrics(&self) -> Vec<FamilyMetrics> {
+        let mut out = vec![self.own_metrics(DEFAULT_COLUMN_FAMILY_NAME)];
+        for family in self.column_families.read().values() {
+            out.push(family.db.own_metrics(family.handle.name()));
+        }
+        out
+    }
+
+    fn own_metrics(&self, cf: &str) -> FamilyMetrics {
+        let sv = self.get_super_version();
+        let version = &sv.version;
+        let level_bytes = (0..version.num_levels)
+            .map(|level| version.level_size(level))
+            .collect();
+        FamilyMetrics {
+            cf: cf.to_string(),
+            statistics: self.statistics(),
+            statistics_enabled: self.stats.enabled(),
+            level_files: (0..version.num_levels)
+                .map(|level| version.level_files(level).len())
+                .collect(),
+            level_bytes,
+            immutable_memtables: sv.immutable_memtables.len(),
+            pending_compaction_bytes: compaction::picker(self.options.compaction_style)
+                .estimate_pending_compaction_bytes(version, &self.options),
+            dead_keys: self.dead_key_count(),
+            write_stall: self.write_stall_condition(),
+            block_cache_pinned_bytes: self.block_cache.pinned_bytes(),
+            block_cache: self.block_cache.pool().clone(),
+        }
+    }
+
+    /// Last sequence number committed and visible to reads. Once a write
+    /// returns, its sequence is at most this value.
+    pub fn latest_sequence_number(&self) -> SequenceNumber {
+        self.current_sequence()
+    }
+
+    /// Last sequence number that survives a crash. Advanced by every WAL
+    /// fsync — a write with `WriteOptions::sync`, [`Self::sync_wal`], or the
+    /// sync of a retiring WAL when the memtable switches. A write made with
+    /// `disable_wal` is only durable once its memtable is flushed, and holds
+    /// this watermark below its sequence until then.
+    pub fn durable_sequence(&self) -> SequenceNumber {
+        self.durable_sequence.load(Ordering::Acquire)
+    }
+
+    /// Fsync the WAL, making every write that has returned durable.
+    pub fn sync_wal(&self) -> Result<()> {
+        self.check_writable().ctx()?;
+        let mut inner = self.inner.lock();
+        self.sync_wal_locked(&mut inner).ctx()
+    }
+
+    /// Write buffered WAL records to the OS, and fsync them when `sync` is
+    /// true (the same as [`Self::sync_wal`]). Without `sync` the records
+    /// survive a process crash but not a machine crash.
+    pub fn flush_wal(&self, sync: bool) -> Result<()> {
+        if sync {
+            return self.sync_wal();
+        }
+        self.check_writable().ctx()?;
+        let mut inner = self.inner.lock();
+        if let Some(ref mut wal) = inner.wal_writer {
+            wal.flush().ctx()?;
+        }
+        Ok(())
+    }
+
+    /// Block until `seq` is durable, fsyncing the WAL unless another sync
+    /// already covered it. Concurrent callers share one fsync, so writers
+    /// can use `sync = false` and acknowledge in batches. `seq` must have
+    /// been assigned to a write already. Fails with
+    /// [`ErrorKind::InvalidArgument`] while a `disable_wal` write at or
+    /// below `seq` is not flushed: no WAL sync can make it durable.
+    pub fn wait_for_durable(&self, seq: SequenceNumber) -> Result<()> {
+        if self.durable_sequence() >= seq {
+            return Ok(());
+        }
+        self.check_writable().ctx()?;
+        let last_assigned = self.sequence.load(Ordering::Acquire).saturating_sub(1);
+        if seq > last_assigned {
+            return Err(Error::invalid_argument(format!(
+                "sequence {} has not been written (last assigned {})",
+                seq, last_assigned
+            )));
+        }
+        let mut inner = self.inner.lock();
+        self.sync_wal_locked(&mut inner).ctx()?;
+        if self.durable_sequence() < seq {
+            // Either a `disable_wal` write holds the watermark back, or a
+            // group's WAL append failed, which is fail-stop.
+            return Err(self.fail_stop_error().unwrap_or_else(|| {
+                Error::invalid_argument(format!(
+                    "sequence {} is not durable until a write that skipped the WAL is flushed",
+                    seq
+                ))
+            }));
+        }
+        Ok(())
+    }
+
+    /// Iterate the write batches logged since `seq`, oldest first, from the
+    /// live WALs and the WAL archive (see [`DbOptions::wal_ttl_seconds`]).
+    /// Fails with [`ErrorKind::NotFound`] once the WAL holding `seq` has
+    /// been deleted; a damaged WAL surfaces as [`ErrorKind::Corruption`].
+    pub fn get_updates_since(&self, seq: SequenceNumber) -> Result<WalUpdateIterator> {
+        self.check_usable().ctx()?;
+        if self.family_id != 0 {
+            return Err(Error::invalid_argument(
+                "column family engines have no WAL of their own".to_string(),
+            ));
+        }
+        WalUpdateIterator::new(&self.path, seq, self.current_sequence()).ctx()
+    }
+
     /// Force flush the active MemTable to SST.
     ///
     /// Durability is achieved once the memtable's SSTs are installed and the
@@ -2446,15 +5167,9 @@ impl DB {
             // mid auto-flush (install still pending).
             self.wait_for_write_leader_idle(&mut wq);
             self.check_writable().ctx()?;
-            let mut inner = self.inner.lock();
-            if inner.active_memtable.is_empty() {
+            if !self.flush_active_memtable().ctx()? {
                 return Ok(());
             }
-            let frozen = self.freeze_memtable_sync(&mut inner).ctx()?;
-            drop(inner); // release lock during SST write
-            self.flush_and_install_frozen(&frozen).ctx()?;
-            let old_wal = frozen.old_wal_number;
-            self.post_flush_cleanup(old_wal).ctx()?;
             if self.l0_file_count.load(Ordering::Relaxed) >= self.options.l0_compaction_trigger
                 && let Err(e) = self.drain_l0(false)
             {
@@ -2466,16 +5181,238 @@ impl DB {
                 if fatal {
                     return Err(e).ctx();
                 }
-                // Non-fatal: the failed step was rejected wholesale before
-                // anything was persisted (log_and_apply's error contract) and
-                // its output files were already cleaned up. The flush itself —
-                // the caller's durability request — has fully succeeded.
-                tracing::warn!("post-flush L0 drain failed (will retry): {}", e);
+                // Non-fatal: the failed step was rejected wholesale before
+                // anything was persisted (log_and_apply's error contract) and
+                // its output files were already cleaned up. The flush itself —
+                // the caller's durability request — has fully succeeded.
+                tracing::warn!("post-flush L0 drain failed (will retry): {}", e);
+            }
+        }
+        // Off the writer-serializing lock: harvest dead-key registrations
+        // that compactions since the previous flush have fully applied.
+        self.prune_settled_dead_keys(Some(DEAD_KEY_PRUNE_PER_FLUSH), None, None);
+        Ok(())
+    }
+
+    /// Create a checkpoint: an openable copy of the database in `dir`, which
+    /// must not exist yet. Memtables are flushed (a read-only handle copies
+    /// its WAL files instead) and every live SST is hard-linked, or copied
+    /// when linking fails (e.g. across filesystems). A fresh MANIFEST and
+    /// CURRENT describe exactly the state at checkpoint time, column
+    /// families included.
+    ///
+    /// Writers are held only while memtables are flushed and the live file
+    /// set is captured. Obsolete-file deletion is held off until the links
+    /// exist. On failure the partial checkpoint is removed.
+    pub fn create_checkpoint(&self, dir: impl AsRef<Path>) -> Result<()> {
+        self.check_usable().ctx()?;
+        let dir = dir.as_ref();
+        if dir.exists() {
+            return Err(Error::invalid_argument(format!(
+                "checkpoint directory {} already exists",
+                dir.display()
+            )));
+        }
+        fs::create_dir_all(dir).ctx()?;
+        let result = self.write_checkpoint(dir);
+        if result.is_err() {
+            let _ = fs::remove_dir_all(dir);
+        }
+        result
+    }
+
+    /// Bulk-load SST files written by [`SstFileWriter`](crate::SstFileWriter).
+    ///
+    /// The files must not overlap each other. Each file goes to the deepest
+    /// level at which neither it nor any level above holds overlapping
+    /// keys. Files that overlap existing data (or are ingested while
+    /// snapshots exist) are assigned one fresh global sequence number,
+    /// which is written into a copy of the file; the others keep sequence
+    /// 0 and are linked or copied in as they are. Under FIFO compaction,
+    /// which keeps every file in L0, each file goes to L0 with a global
+    /// sequence number. A file overlapping the active memtable or one still
+    /// waiting to be flushed forces a flush of all of them first, unless
+    /// [`IngestExternalFileOptions::fail_if_memtable_overlap`] is set. The
+    /// change is recorded in the MANIFEST; writers wait until it is done.
+    pub fn ingest_external_file<P: AsRef<Path>>(
+        &self,
+        paths: &[P],
+        options: &IngestExternalFileOptions,
+    ) -> Result<()> {
+        self.check_writable().ctx()?;
+        let mut files = paths
+            .iter()
+            .map(|p| ExternalFile::inspect(p.as_ref(), &self.icmp))
+            .collect::<Result<Vec<_>>>()
+            .ctx()?;
+        if files.is_empty() {
+            return Ok(());
+        }
+        files.sort_by(|a, b| {
+            self.icmp
+                .compare_user(&a.smallest_user_key, &b.smallest_user_key)
+        });
+        if let Some(pair) = files.windows(2).find(|pair| {
+            !self
+                .icmp
+                .user_lt(&pair[0].largest_user_key, &pair[1].smallest_user_key)
+        }) {
+            return Err(Error::invalid_argument(format!(
+                "external files {} and {} overlap",
+                pair[0].path.display(),
+                pair[1].path.display()
+            )));
+        }
+
+        let mut wq = self.write_queue.lock();
+        self.wait_for_write_leader_idle(&mut wq);
+        self.check_writable().ctx()?;
+        // Frozen memtables still waiting for their flush count too: an
+        // older write there would hide an ingested file placed below it.
+        let memtable_overlap = {
+            let inner = self.inner.lock();
+            let memtables: Vec<&Arc<MemTable>> = iter::once(&inner.active_memtable)
+                .chain(&inner.immutable_memtables)
+                .chain(inner.flush_queue.iter().map(|job| &job.frozen.old_mem))
+                .collect();
+            files.iter().any(|f| {
+                memtables
+                    .iter()
+                    .any(|m| m.overlaps_user_range(&f.smallest_user_key, &f.largest_user_key))
+            })
+        };
+        if memtable_overlap {
+            if options.fail_if_memtable_overlap {
+                return Err(Error::invalid_argument(
+                    "external file overlaps the memtable".to_string(),
+                ));
+            }
+            self.flush_active_memtable().ctx()?;
+        }
+
+        let fifo = self.options.compaction_style == CompactionStyle::Fifo;
+        let (first_number, global_seq) = {
+            let mut inner = self.inner.lock();
+            let version = inner.versions.current();
+            let overlaps_db = files.iter().any(|f| {
+                (0..version.num_levels).any(|level| f.overlaps(version.level_files(level)))
+            });
+            // Sequence 0 sorts below every existing entry and is visible to
+            // every snapshot, so it is only right for disjoint data placed
+            // below L0.
+            let global_seq =
+                if overlaps_db || fifo || !self.snapshot_list.as_sorted_vec().is_empty() {
+                    let seq = self.sequence.load(Ordering::Acquire);
+                    if seq > MAX_SEQUENCE_NUMBER {
+                        return Err(Error::invalid_argument(
+                            "sequence number space exhausted".to_string(),
+                        ));
+                    }
+                    self.sequence.store(seq + 1, Ordering::Release);
+                    Some(seq)
+                } else {
+                    None
+                };
+            let first_number = inner.versions.reserve_file_numbers(files.len() as u64);
+            (first_number, global_seq)
+        };
+
+        // Stage the files in the DB directory (writers stay paused).
+        let sst_path = |number: u64| self.path.join(format!("{:06}.sst", number));
+        let mut staged: Vec<FileMetaData> = Vec::with_capacity(files.len());
+        let creation_time = self.options.clock.now_millis();
+        for (file, number) in files.iter().zip(first_number..) {
+            let dst = sst_path(number);
+            let meta = match global_seq {
+                Some(seq) => file
+                    .rewrite(&dst, seq, self.flusher.build_opts())
+                    .map(|built| FileMetaData {
+                        number,
+                        file_size: built.file_size,
+                        smallest_key: built.smallest_key.unwrap_or_default(),
+                        largest_key: built.largest_key.unwrap_or_default(),
+                        has_range_deletions: built.has_range_deletions,
+                        creation_time,
+                    }),
+                None => if options.move_files {
+                    link_or_copy(&file.path, &dst)
+                } else {
+                    copy_synced(&file.path, &dst)
+                }
+                .map(|()| FileMetaData {
+                    number,
+                    file_size: file.file_size,
+                    smallest_key: file.smallest_key.clone(),
+                    largest_key: file.largest_key.clone(),
+                    has_range_deletions: !file.tombstones.is_empty(),
+                    creation_time,
+                }),
+            };
+            match meta {
+                Ok(meta) => staged.push(meta),
+                Err(e) => {
+                    let _ = fs::remove_file(&dst);
+                    for meta in &staged {
+                        let _ = fs::remove_file(sst_path(meta.number));
+                    }
+                    return Err(e).ctx();
+                }
+            }
+        }
+
+        let manifest_handle = {
+            let mut inner = self.inner.lock();
+            let version = inner.versions.current();
+            let mut edit = VersionEdit::new();
+            for (file, meta) in files.iter().zip(&staged) {
+                let level = if fifo {
+                    0
+                } else {
+                    (0..version.num_levels)
+                        .take_while(|&level| !file.overlaps(version.level_files(level)))
+                        .last()
+                        .unwrap_or(0)
+                };
+                edit.add_file(level as u32, meta.clone());
+            }
+            edit.set_next_file_number(inner.versions.next_file_number());
+            if let Some(seq) = global_seq {
+                edit.set_last_sequence(seq);
+            }
+            if let Err(e) = inner.versions.log_and_apply(edit) {
+                drop(inner);
+                for meta in &staged {
+                    let _ = fs::remove_file(sst_path(meta.number));
+                }
+                return Err(e).ctx();
+            }
+            self.l0_file_count
+                .store(inner.versions.current().l0_file_count(), Ordering::Relaxed);
+            self.install_super_version(&inner);
+            if let Some(seq) = global_seq {
+                self.committed_sequence.store(seq, Ordering::Release);
+                // Nothing to log: the next WAL sync covers every older write.
+                inner.logged_sequence = seq;
+            }
+            inner.versions.manifest_sync_handle()
+        };
+        drop(wq);
+        if let Err(e) = confirm_manifest_durable(&manifest_handle, &self.manifest_poisoned) {
+            self.set_bg_error(format!("ingestion manifest sync failed: {}", e));
+            return Err(e).ctx();
+        }
+        if options.move_files {
+            for file in &files {
+                if let Err(e) = fs::remove_file(&file.path) {
+                    tracing::warn!(
+                        "failed to remove ingested file {}: {}",
+                        file.path.display(),
+                        e
+                    );
+                }
             }
         }
-        // Off the writer-serializing lock: harvest dead-key registrations
-        // that compactions since the previous flush have fully applied.
-        self.prune_settled_dead_keys(Some(DEAD_KEY_PRUNE_PER_FLUSH), None, None);
+        self.signal_compaction();
         Ok(())
     }
 
@@ -2514,16 +5451,10 @@ impl DB {
         self.wait_for_write_leader_idle(&mut wq);
         self.check_writable().ctx()?;
 
-        // First flush memtable to ensure all data is in SSTs
-        {
-            let mut inner = self.inner.lock();
-            if !inner.active_memtable.is_empty() {
-                let frozen = self.freeze_memtable_sync(&mut inner).ctx()?;
-                drop(inner);
-                self.flush_and_install_frozen(&frozen).ctx()?;
-                let old_wal = frozen.old_wal_number;
-                self.post_flush_cleanup(old_wal).ctx()?;
-            }
+        // First flush memtable to ensure all data is in SSTs. A column
+        // family's owner has already flushed it (`compact_range_cf`).
+        if self.family_id == 0 {
+            self.flush_active_memtable().ctx()?;
         }
 
         // No range specified: same full-compaction path as compact(), while
@@ -2540,7 +5471,9 @@ impl DB {
             let pick = {
                 let mut inner = self.inner.lock();
                 let version = inner.versions.current();
-                match LeveledCompaction::pick_compaction_for_range(&version, begin, end) {
+                match compaction::picker(self.options.compaction_style)
+                    .pick_compaction_for_range(&version, begin, end)
+                {
                     Some(task) => {
                         let l0_inputs: Vec<u64> = if task.level == 0 {
                             task.input_files_level
@@ -2553,12 +5486,7 @@ impl DB {
                         let max_out = LeveledCompaction::max_output_files(&task, &self.options);
                         let file_start = inner.versions.reserve_file_numbers(max_out);
                         let file_limit = file_start.saturating_add(max_out);
-                        let all_inputs: Vec<_> = task
-                            .input_files_level
-                            .iter()
-                            .chain(task.input_files_next.iter())
-                            .cloned()
-                            .collect();
+                        let all_inputs = task.all_input_files();
                         let is_bottom = LeveledCompaction::is_bottommost_level(
                             &version,
                             task.level,
@@ -2615,12 +5543,14 @@ impl DB {
                 numbers: claimed,
             };
 
+            let ts_low = self.full_history_ts_low.read().clone();
             let ctx = CompactionContext {
                 db_path: &self.path,
                 options: &self.options,
                 rate_limiter: Some(&self.rate_limiter),
                 stats: Some(&self.stats),
                 active_snapshots: &active_snaps,
+                full_history_ts_low: ts_low.as_deref(),
             };
             let output = LeveledCompaction::execute_compaction_io(
                 &ctx, &task, file_start, file_limit, is_bottom,
@@ -2687,7 +5617,12 @@ impl DB {
                 self.set_bg_error(format!("compact_range manifest sync failed: {}", e));
                 return Err(e).ctx();
             }
-            LeveledCompaction::run_post_compaction_cleanup(&cleanup, &self.path);
+            LeveledCompaction::run_post_compaction_cleanup(
+                &cleanup,
+                &self.path,
+                &self.file_deletion_gate,
+                &self.options.listeners,
+            );
         }
 
         drop(wq);
@@ -2827,8 +5762,8 @@ impl DB {
         upper: Option<&[u8]>,
     ) {
         let in_window = |key: &Vec<u8>| {
-            lower.is_none_or(|lo| key.as_slice() >= lo)
-                && upper.is_none_or(|hi| key.as_slice() < hi)
+            lower.is_none_or(|lo| self.icmp.user_le(lo, key))
+                && upper.is_none_or(|hi| self.icmp.user_lt(key, hi))
         };
         let candidates: Vec<Vec<u8>> = {
             let set = self.dead_keys.read();
@@ -2957,17 +5892,42 @@ impl DB {
             return Ok(());
         }
 
-        let mut inner = self.inner.lock();
-
         let mut first_error: Option<Error> = None;
 
+        // Column families first: their flushes switch the shared WAL, which
+        // the default family's own flush below then retires.
+        // They stay registered until all are flushed, so WAL cleanup keeps
+        // the files the not-yet-flushed ones still need.
+        let families: Vec<Arc<ColumnFamily>> =
+            self.column_families.read().values().cloned().collect();
+        for family in &families {
+            if !self.read_only
+                && let Err(e) = self.flush_family(family)
+                && first_error.is_none()
+            {
+                first_error = Some(e);
+            }
+        }
+        self.column_families.write().clear();
+        for family in &families {
+            if let Err(e) = family.db.close()
+                && first_error.is_none()
+            {
+                first_error = Some(e);
+            }
+        }
+
+        // A column family engine's memtable is flushed by its owner.
         if !self.read_only
-            && !inner.active_memtable.is_empty()
-            && let Err(e) = self.freeze_and_flush(&mut inner)
+            && self.family_id == 0
+            && let Err(e) = self.flush_active_memtable()
+            && first_error.is_none()
         {
             first_error = Some(e);
         }
 
+        let mut inner = self.inner.lock();
+
         if !self.read_only
             && let Some(ref mut wal) = inner.wal_writer
             && let Err(e) = wal.sync()
@@ -2992,13 +5952,14 @@ impl DB {
 
     // -- Internal --
 
-    /// Block until no group-commit leader is in `write_batch_group`.
+    /// Block until no group-commit leader is in `write_batch_group` and no
+    /// pipelined group is still applying to the memtable.
     ///
     /// Leaders drop `write_queue` for I/O and only keep `leader_active`;
     /// admin paths that interpret empty-active or release the directory lock
     /// must wait here while holding `write_queue`.
     fn wait_for_write_leader_idle(&self, wq: &mut MutexGuard<'_, WriteQueueState>) {
-        while wq.leader_active {
+        while wq.leader_active || wq.groups_applying > 0 {
             self.write_cv.wait(wq);
         }
     }
@@ -3016,6 +5977,15 @@ impl DB {
     }
 
     fn shutdown_background_and_release_resources(&self) {
+        self.stop_flush_threads();
+        {
+            let (lock, cvar) = &*self.stats_dump_shutdown;
+            *lock.lock().unwrap() = true;
+            cvar.notify_all();
+        }
+        if let Some(handle) = self.stats_dump_handle.lock().take() {
+            let _ = handle.join();
+        }
         {
             let (lock, cvar) = &*self.compaction_notify;
             let _guard = lock.lock().unwrap();
@@ -3029,6 +5999,47 @@ impl DB {
         drop(self.lock_file.lock().take());
     }
 
+    /// Start the thread that logs the `mmdb.stats` dump every `period`.
+    fn spawn_stats_dump(&self, period: Duration) -> Result<JoinHandle<()>> {
+        let shutdown = self.stats_dump_shutdown.clone();
+        let super_version = self.super_version.clone();
+        let options = self.options.clone();
+        let stats = self.stats.clone();
+        let path = self.path.clone();
+        thread::Builder::new()
+            .name("mmdb-stats-dump".to_string())
+            .spawn(move || {
+                let (lock, cvar) = &*shutdown;
+                let mut stop = lock.lock().unwrap();
+                let mut deadline = Instant::now() + period;
+                while !*stop {
+                    let now = Instant::now();
+                    if now < deadline {
+                        stop = cvar.wait_timeout(stop, deadline - now).unwrap().0;
+                        continue;
+                    }
+                    let dump =
+                        stats::format_stats_dump(&super_version.load().version, &options, &stats);
+                    tracing::info!("{}\n{}", path.display(), dump);
+                    deadline = now + period;
+                }
+            })
+            .with_ctx(|| "failed to spawn stats dump thread")
+    }
+
+    /// Stop and join the background flush threads. A job being built is
+    /// finished first; queued ones stay in their WALs.
+    fn stop_flush_threads(&self) {
+        {
+            let _inner = self.inner.lock();
+            self.flusher.shutdown.store(true, Ordering::Release);
+            self.flusher.queue_changed.notify_all();
+        }
+        for handle in self.flush_handles.lock().drain(..) {
+            let _ = handle.join();
+        }
+    }
+
     /// Find the highest sequence number among range tombstones covering `key`
     /// that are visible at the given read sequence.
     /// Returns 0 if no covering tombstone exists.
@@ -3056,6 +6067,100 @@ impl DB {
         max_seq
     }
 
+    /// Fail with [`ErrorKind::Busy`](crate::ErrorKind::Busy) if any key in
+    /// `check` was written after its start sequence. The caller holds
+    /// `inner`, so no commit can land between the check and its own write.
+    fn validate_conflict_check(&self, check: &ConflictCheck) -> Result<()> {
+        for key in &check.keys {
+            let latest = self.latest_key_sequence(key).ctx()?;
+            if latest > check.start_seq {
+                return Err(Error::busy(format!(
+                    "transaction conflict: key written at sequence {} after start sequence {}",
+                    latest, check.start_seq
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Newest sequence number that wrote `key` — a point entry or a range
+    /// tombstone covering it — or 0 if none is visible.
+    ///
+    /// Sources are visited newest first; once one of them holds a point
+    /// entry for the key, every older source carries lower sequences.
+    /// Versions newer than any live snapshot keep their sequence through
+    /// compaction, which is what the transaction's pinned snapshot relies on.
+    pub(crate) fn latest_key_sequence(&self, key: &[u8]) -> Result<SequenceNumber> {
+        let sv = self.get_super_version();
+        let mut latest = self.max_covering_tombstone_seq(
+            key,
+            MAX_SEQUENCE_NUMBER,
+            &sv.active_memtable,
+            &sv.immutable_memtables,
+        );
+        for mem in iter::once(&sv.active_memtable).chain(sv.immutable_memtables.iter()) {
+            if let Some((_, _, s)) = mem.get_with_seq(key, MAX_SEQUENCE_NUMBER) {
+                return Ok(latest.max(s));
+            }
+        }
+
+        let version = &sv.version;
+        // L0 files may overlap and split flushes do not order sequences by
+        // file number, so take the maximum over all of them.
+        let mut found = false;
+        for tf in version.level_files(0) {
+            if tf.meta.has_range_deletions {
+                let s = tf
+                    .reader
+                    .max_covering_tombstone_seq(key, MAX_SEQUENCE_NUMBER)
+                    .ctx()?;
+                latest = latest.max(s);
+            }
+            if let Some((_, _, s)) = tf
+                .reader
+                .get_internal_with_seq(key, MAX_SEQUENCE_NUMBER, false)
+                .ctx()?
+            {
+                latest = latest.max(s);
+                found = true;
+            }
+        }
+        if found {
+            return Ok(latest);
+        }
+
+        for level in 1..version.num_levels {
+            for tf in version.level_files(level) {
+                if tf.meta.has_range_deletions {
+                    let s = tf
+                        .reader
+                        .max_covering_tombstone_seq(key, MAX_SEQUENCE_NUMBER)
+                        .ctx()?;
+                    latest = latest.max(s);
+                }
+                let in_bounds = self
+                    .icmp
+                    .user_le(types::user_key(&tf.meta.smallest_key), key)
+                    && self
+                        .icmp
+                        .user_le(key, types::user_key(&tf.meta.largest_key));
+                if in_bounds
+                    && let Some((_, _, s)) = tf
+                        .reader
+                        .get_internal_with_seq(key, MAX_SEQUENCE_NUMBER, false)
+                        .ctx()?
+                {
+                    latest = latest.max(s);
+                    found = true;
+                }
+            }
+            if found {
+                break;
+            }
+        }
+        Ok(latest)
+    }
+
     /// Get the current SuperVersion snapshot — single atomic load, truly lock-free.
     fn get_super_version(&self) -> arc_swap::Guard<Arc<SuperVersion>> {
         self.super_version.load()
@@ -3070,8 +6175,13 @@ impl DB {
     /// Record a background error, setting the fast-path flag and the detailed message.
     /// All subsequent `check_usable()` calls will return this error.
     fn set_bg_error(&self, msg: String) {
-        *self.bg_error.lock() = Some(msg);
-        self.has_bg_error.store(true, Ordering::Release);
+        *self.bg_error.lock() = Some(msg.clone());
+        if !self.has_bg_error.swap(true, Ordering::AcqRel) {
+            listener::notify_background_error(&self.options.listeners, Error::background(msg));
+        }
+        // Best effort: callers may hold `inner`. Queue waiters are also
+        // woken by the next flush to finish.
+        self.flusher.queue_changed.notify_all();
     }
 
     fn fail_stop_error(&self) -> Option<Error> {
@@ -3152,10 +6262,77 @@ impl DB {
         snapshot.map_or(committed, |s| s.min(committed))
     }
 
+    /// Resolve the user-defined timestamp a read runs at: `None` when the
+    /// comparator carries no timestamps, otherwise `options.timestamp` or
+    /// the newest possible timestamp. Reads below `full_history_ts_low`
+    /// are rejected because compaction may already have collapsed the
+    /// versions they would observe.
+    fn read_timestamp(&self, options: &ReadOptions) -> Result<Option<Vec<u8>>> {
+        let ts_size = self.icmp.timestamp_size();
+        if ts_size == 0 {
+            if options.timestamp.is_some() {
+                return Err(Error::invalid_argument(format!(
+                    "read timestamp given but comparator {} has no timestamps",
+                    self.icmp.name()
+                )));
+            }
+            return Ok(None);
+        }
+        let Some(ts) = &options.timestamp else {
+            return Ok(Some(vec![0xFF; ts_size]));
+        };
+        if ts.len() != ts_size {
+            return Err(Error::invalid_argument(format!(
+                "read timestamp is {} bytes, comparator expects {ts_size}",
+                ts.len()
+            )));
+        }
+        if let Some(low) = self.full_history_ts_low.read().as_deref()
+            && ts.as_slice() < low
+        {
+            return Err(Error::invalid_argument(
+                "read timestamp is below full_history_ts_low",
+            ));
+        }
+        Ok(Some(ts.clone()))
+    }
+
+    /// Block while `max_immutable_memtables` frozen memtables are waiting
+    /// for a flush.
+    fn maybe_stall_on_flush_queue(&self) -> Result<()> {
+        if !self.flush_queue_full() {
+            return Ok(());
+        }
+        let started = self.stats.start_timer();
+        {
+            let mut inner = self.inner.lock();
+            while inner.flush_queue.len() >= self.options.max_immutable_memtables
+                && !self.closed.load(Ordering::Acquire)
+                && !self.has_bg_error.load(Ordering::Acquire)
+            {
+                self.flusher.queue_changed.wait(&mut inner);
+            }
+        }
+        self.stats.record_stall(started);
+        self.check_writable()
+    }
+
+    /// Lock-free check of the flush queue length, via the SuperVersion.
+    fn flush_queue_full(&self) -> bool {
+        self.get_super_version().immutable_memtables.len() >= self.options.max_immutable_memtables
+    }
+
     /// Apply write backpressure based on L0 file count.
     fn maybe_throttle_writes(&self) -> Result<()> {
+        if !self.l0_stalls_writes() {
+            return Ok(());
+        }
         // Fast path: check cached L0 count without locking inner.
         let l0_count = self.l0_file_count.load(Ordering::Relaxed);
+        if l0_count < self.options.l0_slowdown_trigger {
+            return Ok(());
+        }
+        let started = self.stats.start_timer();
 
         if l0_count >= self.options.l0_stop_trigger {
             // Hold write_queue across the entire stop-trigger drain so close
@@ -3211,21 +6388,123 @@ impl DB {
                 Ordering::Relaxed,
             );
         }
+        self.stats.record_stall(started);
         Ok(())
     }
 
-    fn write_batch_inner(&self, mut batch: WriteBatch, write_options: &WriteOptions) -> Result<()> {
+    /// Report a change of the write stall condition to the listeners.
+    /// Writers evaluate it before and after they stall, holding no lock.
+    fn update_write_stall_condition(&self) {
+        if self.options.listeners.is_empty() {
+            return;
+        }
+        let condition = self.write_stall_condition();
+        let previous = self.write_stall.swap(condition as u8, Ordering::AcqRel);
+        if previous == condition as u8 {
+            return;
+        }
+        let info = WriteStallInfo {
+            condition,
+            previous: WriteStallCondition::from_raw(previous),
+        };
+        for listener in &self.options.listeners {
+            listener.on_stall_conditions_changed(&info);
+        }
+    }
+
+    /// The condition the next write would meet, from the flush queue and
+    /// the cached L0 file count.
+    fn write_stall_condition(&self) -> WriteStallCondition {
+        let l0_count = self.l0_file_count.load(Ordering::Relaxed);
+        let l0_stalls = self.l0_stalls_writes();
+        if self.flush_queue_full() || l0_stalls && l0_count >= self.options.l0_stop_trigger {
+            WriteStallCondition::Stopped
+        } else if l0_stalls && l0_count >= self.options.l0_slowdown_trigger {
+            WriteStallCondition::Delayed
+        } else {
+            WriteStallCondition::Normal
+        }
+    }
+
+    /// FIFO compaction keeps every file in L0 by design, so the L0 file
+    /// count says nothing about compaction falling behind.
+    fn l0_stalls_writes(&self) -> bool {
+        self.options.compaction_style != CompactionStyle::Fifo
+    }
+
+    fn write_batch_inner(&self, batch: WriteBatch, write_options: &WriteOptions) -> Result<()> {
+        self.write_batch_checked(batch, write_options, None)
+    }
+
+    /// Commit an optimistic transaction's batch, failing with
+    /// [`ErrorKind::Busy`](crate::ErrorKind::Busy) if any key in `check`
+    /// was written after its start sequence.
+    pub(crate) fn write_batch_with_conflict_check(
+        &self,
+        batch: WriteBatch,
+        write_options: &WriteOptions,
+        check: ConflictCheck,
+    ) -> Result<()> {
+        self.check_writable().ctx()?;
+        self.write_batch_checked(batch, write_options, Some(check))
+    }
+
+    fn write_batch_checked(
+        &self,
+        batch: WriteBatch,
+        write_options: &WriteOptions,
+        conflict_check: Option<ConflictCheck>,
+    ) -> Result<()> {
+        let started = self.stats.start_timer();
+        let result = self.apply_write_batch(batch, write_options, conflict_check);
+        self.stats.record_latency(HistogramKind::Write, started);
+        result
+    }
+
+    fn apply_write_batch(
+        &self,
+        mut batch: WriteBatch,
+        write_options: &WriteOptions,
+        conflict_check: Option<ConflictCheck>,
+    ) -> Result<()> {
         // Elide empty/inverted range deletes before seq assignment and WAL
         // encode. MemTable::put already no-ops them for correctness, but they
         // would still occupy WAL space and never grow approximate_size, so a
-        // stream of them would never rotate the log.
+        // stream of them would never rotate the log. Each family judges its
+        // ranges by its own comparator.
+        let families = if batch.entries.iter().any(|e| e.cf != 0) {
+            Some(self.column_families.read().clone())
+        } else {
+            None
+        };
         batch.entries.retain(|e| {
-            !(e.value_type == ValueType::RangeDeletion
-                && e.value
-                    .as_ref()
-                    .is_none_or(|end| e.key.as_slice() >= end.as_slice()))
+            if e.value_type != ValueType::RangeDeletion {
+                return true;
+            }
+            let icmp = if e.cf == 0 {
+                &self.icmp
+            } else {
+                match families.as_ref().and_then(|f| f.get(&e.cf)) {
+                    Some(family) => &family.db.icmp,
+                    // Left for the validation below to reject.
+                    None => return true,
+                }
+            };
+            if icmp.timestamp_size() > 0 {
+                // Likewise: range deletes are rejected with timestamps.
+                return true;
+            }
+            e.value
+                .as_ref()
+                .is_some_and(|end| icmp.user_lt(&e.key, end))
         });
         if batch.is_empty() {
+            // Nothing to apply, but a read-only transaction still has to
+            // validate. Holding `inner` orders the check against commits.
+            if let Some(check) = conflict_check {
+                let _inner = self.inner.lock();
+                return self.validate_conflict_check(&check);
+            }
             return Ok(());
         }
 
@@ -3246,7 +6525,54 @@ impl DB {
         // a readable SST (the reader caps blocks at 64 MiB and the index
         // block stores every data block's boundary keys twice) — rejecting at
         // write time keeps the failure retryable instead of wedging flush.
-        for entry in &batch.entries {
+        for entry in &mut batch.entries {
+            let options = if entry.cf == 0 {
+                &self.options
+            } else {
+                match families.as_ref().and_then(|f| f.get(&entry.cf)) {
+                    Some(family) => &family.db.options,
+                    None => {
+                        return Err(Error::invalid_argument(format!(
+                            "unknown column family id {}",
+                            entry.cf
+                        )));
+                    }
+                }
+            };
+            let ts_size = options.comparator.timestamp_size();
+            if ts_size == 0 && entry.timestamp_len > 0 {
+                return Err(Error::invalid_argument(format!(
+                    "timestamped write but comparator {} has no timestamps",
+                    options.comparator.name()
+                )));
+            }
+            if ts_size > 0 {
+                if entry.value_type == ValueType::RangeDeletion {
+                    return Err(Error::invalid_argument(
+                        "delete_range is not supported with user-defined timestamps",
+                    ));
+                }
+                if entry.timestamp_len != ts_size {
+                    return Err(Error::invalid_argument(format!(
+                        "write timestamp is {} bytes, comparator {} expects {ts_size}",
+                        entry.timestamp_len,
+                        options.comparator.name()
+                    )));
+                }
+            }
+            if entry.value_type == ValueType::Merge && options.merge_operator.is_none() {
+                return Err(Error::invalid_argument(
+                    "merge requires DbOptions::merge_operator".to_string(),
+                ));
+            }
+            if entry.value_type == ValueType::Value
+                && let Some(ttl) = entry.ttl.take().or(options.default_ttl)
+            {
+                let ttl_millis = u64::try_from(ttl.as_millis()).unwrap_or(u64::MAX);
+                let expire_at = options.clock.now_millis().saturating_add(ttl_millis);
+                types::encode_expiring_value(entry.value.get_or_insert_default(), expire_at);
+                entry.value_type = ValueType::ExpiringValue;
+            }
             if entry.key.len() > MAX_USER_KEY_SIZE {
                 return Err(Error::invalid_argument(format!(
                     "key size {} exceeds maximum {}",
@@ -3271,21 +6597,29 @@ impl DB {
             // trigger is the first threshold at which either blocking path
             // (the slowdown delay or the stop-trigger drain) can activate.
             let l0_count = self.l0_file_count.load(Ordering::Relaxed);
-            if l0_count >= self.options.l0_slowdown_trigger {
+            if self.flush_queue_full()
+                || self.l0_stalls_writes() && l0_count >= self.options.l0_slowdown_trigger
+            {
                 return Err(Error::invalid_argument(
                     "write stalled: no_slowdown is set".to_string(),
                 ));
             }
         } else {
+            self.update_write_stall_condition();
+            self.maybe_stall_on_flush_queue().ctx()?;
             self.maybe_throttle_writes().ctx()?;
+            self.update_write_stall_condition();
         }
 
         let mut req = WriteRequest {
             batch,
             sync: write_options.sync,
             disable_wal: write_options.disable_wal,
+            conflict_check,
             result: None,
             done: false,
+            grouped: false,
+            apply: None,
         };
         let req_ptr: *mut WriteRequest = &mut req;
 
@@ -3296,25 +6630,59 @@ impl DB {
         let mut wq = self.write_queue.lock();
         wq.queue.push_back(req_ptr);
 
-        // Wait until either: our request is done (leader processed it), or
-        // no leader is active (we should become the leader).
-        while !req.done && wq.leader_active {
+        // Wait until either: our request is done (a leader processed it), our
+        // leader hands us our own memtable insert, or no leader is active and
+        // our request is still queued (we should become the leader).
+        loop {
+            if req.done {
+                return req.result.take().unwrap_or(Ok(()));
+            }
+            if let Some(task) = req.apply.take() {
+                drop(wq);
+                let bytes = task.mems.apply(&req.batch, task.first_seq, true);
+                self.stats.record_write(bytes);
+                task.remaining.count_down();
+                wq = self.write_queue.lock();
+                continue;
+            }
+            if !req.grouped && !wq.leader_active {
+                break;
+            }
             self.write_cv.wait(&mut wq);
         }
-        if req.done {
-            return req.result.take().unwrap_or(Ok(()));
-        }
 
         // Become the leader. The flag ensures only one thread reaches here.
         debug_assert!(!wq.leader_active);
         wq.leader_active = true;
 
+        let pipelined = self.options.enable_pipelined_write;
         let mut group_flushed = false;
         loop {
             let batch_group: Vec<*mut WriteRequest> = wq.queue.drain(..).collect();
+            for &rp in &batch_group {
+                // SAFETY: queued request pointers stay valid until their
+                // leader marks them done.
+                unsafe { (*rp).grouped = true };
+            }
             drop(wq); // release queue lock while doing I/O
 
-            let result = self.write_batch_group(&batch_group);
+            let result = if pipelined {
+                let logged = self.log_batch_group(&batch_group);
+                // Hand leadership to the next group so its WAL write overlaps
+                // this group's memtable inserts.
+                let mut wq_handoff = self.write_queue.lock();
+                wq_handoff.groups_applying += 1;
+                wq_handoff.leader_active = false;
+                self.write_cv.notify_all();
+                drop(wq_handoff);
+                match logged {
+                    Ok(Some(group)) => self.apply_batch_group(group, &batch_group, req_ptr),
+                    Ok(None) => Ok(false),
+                    Err(e) => Err(e),
+                }
+            } else {
+                self.write_batch_group(&batch_group, req_ptr)
+            };
             group_flushed |= matches!(result, Ok(true));
 
             // Signal everyone in this batch
@@ -3333,6 +6701,13 @@ impl DB {
                 }
                 r.done = true;
             }
+            if pipelined {
+                // Leadership already passed on; the next leader takes any
+                // stragglers.
+                wq_inner.groups_applying -= 1;
+                self.write_cv.notify_all();
+                break;
+            }
             self.write_cv.notify_all();
 
             // Check for stragglers
@@ -3356,15 +6731,71 @@ impl DB {
         req.result.take().unwrap_or(Ok(()))
     }
 
-    /// Process a batch group: assign sequences, write+flush WAL, then publish to MemTable.
-    /// Returns `true` when the group's writes triggered a memtable flush, so
-    /// the leader can harvest settled dead-key registrations after it has
-    /// woken the group's waiters.
-    fn write_batch_group(&self, batch_group: &[*mut WriteRequest]) -> Result<bool> {
+    /// Process a batch group: write its WAL records, then insert it into the
+    /// memtable. Returns `true` when the group's writes queued a memtable
+    /// flush, so the leader can harvest settled dead-key registrations after
+    /// it has woken the group's waiters.
+    fn write_batch_group(
+        &self,
+        batch_group: &[*mut WriteRequest],
+        leader: *mut WriteRequest,
+    ) -> Result<bool> {
+        match self.log_batch_group(batch_group)? {
+            Some(group) => self.apply_batch_group(group, batch_group, leader),
+            None => Ok(false),
+        }
+    }
+
+    /// WAL stage of a group (under `inner`): validate optimistic
+    /// transactions, assign sequences, write+flush the WAL, and capture the
+    /// memtables the group inserts into. Returns `None` when no request has
+    /// anything to write.
+    fn log_batch_group(&self, batch_group: &[*mut WriteRequest]) -> Result<Option<LoggedGroup>> {
         self.check_writable()?;
         let mut need_sync = false;
         let mut inner = self.inner.lock();
 
+        // Validate optimistic transactions before any sequence is assigned.
+        // A rejected request is answered here and drops out of the group;
+        // the rest of the group commits normally. Requests accepted earlier
+        // in the group are not in the memtable yet, so their writes are
+        // checked separately, and so are pipelined groups still applying.
+        if batch_group.iter().any(|&req_ptr| {
+            // SAFETY: request pointers are still owned by this leader.
+            unsafe { &*req_ptr }.conflict_check.is_some()
+        }) {
+            self.write_pipeline.wait_idle();
+        }
+        let mut accepted = Vec::with_capacity(batch_group.len());
+        let mut group_writes: Vec<&WriteBatch> = Vec::new();
+        for &req_ptr in batch_group {
+            // SAFETY: request pointers are still owned by this leader.
+            let r = unsafe { &mut *req_ptr };
+            if let Some(ref check) = r.conflict_check {
+                let written_in_group = check.keys.iter().any(|k| {
+                    group_writes
+                        .iter()
+                        .any(|b| batch_writes_key(&self.icmp, b, k))
+                });
+                let validated = if written_in_group {
+                    Err(Error::busy(
+                        "transaction conflict: key written by a concurrent commit".to_string(),
+                    ))
+                } else {
+                    self.validate_conflict_check(check)
+                };
+                if let Err(e) = validated {
+                    r.result = Some(Err(e));
+                    continue;
+                }
+            }
+            // SAFETY: as above; the batch is not mutated while the group is
+            // being committed.
+            group_writes.push(unsafe { &(*req_ptr).batch });
+            accepted.push(req_ptr);
+        }
+        let batch_group = accepted.as_slice();
+
         let total_ops: u64 = batch_group
             .iter()
             .map(|&req_ptr| {
@@ -3374,7 +6805,7 @@ impl DB {
             })
             .sum();
         if total_ops == 0 {
-            return Ok(false);
+            return Ok(None);
         }
 
         let first_group_seq = self.sequence.load(Ordering::Acquire);
@@ -3422,10 +6853,13 @@ impl DB {
         });
         let wal_sync_err = if any_wal {
             match inner.wal_writer {
-                Some(ref mut wal) => {
-                    let r = if need_sync { wal.sync() } else { wal.flush() };
+                Some(ref mut wal) if need_sync => {
+                    let started = self.stats.start_timer();
+                    let r = wal.sync();
+                    self.stats.record_latency(HistogramKind::WalSync, started);
                     r.err()
                 }
+                Some(ref mut wal) => wal.flush().err(),
                 None => None,
             }
         } else {
@@ -3441,354 +6875,478 @@ impl DB {
             return Err(e);
         }
 
-        let mut applied_last_seq = None;
+        // Column family memtables are only swapped while the owner's `inner`
+        // is held, and a WAL switch waits for logged groups to finish their
+        // inserts, so each captured memtable stays active for this group.
+        let mut families = HashMap::new();
+        for &(req_ptr, _) in &assigned {
+            // SAFETY: request pointers are still owned by this leader.
+            let r = unsafe { &*req_ptr };
+            for entry in r.batch.entries.iter().filter(|e| e.cf != 0) {
+                if families.contains_key(&entry.cf) {
+                    continue;
+                }
+                let family = self.column_families.read().get(&entry.cf).cloned();
+                if let Some(family) = family {
+                    let mem = family.db.inner.lock().active_memtable.clone();
+                    families.insert(entry.cf, (family, mem));
+                }
+            }
+        }
         for &(req_ptr, first_seq) in &assigned {
             // SAFETY: request pointers are still owned by this leader.
             let r = unsafe { &*req_ptr };
-            let mut batch_bytes = 0u64;
-            for (i, entry) in r.batch.entries.iter().enumerate() {
-                let seq = first_seq + i as u64;
-                inner.active_memtable.put(
-                    &entry.key,
-                    entry.value.as_deref().unwrap_or(&[]),
-                    seq,
-                    entry.value_type,
-                );
-                batch_bytes +=
-                    entry.key.len() as u64 + entry.value.as_ref().map_or(0, |v| v.len()) as u64;
+            if !r.disable_wal {
+                continue;
             }
-            if !r.batch.is_empty() {
-                applied_last_seq = Some(first_seq + r.batch.len() as u64 - 1);
+            for entry in &r.batch.entries {
+                let mem = match families.get(&entry.cf) {
+                    Some((_, mem)) => mem.clone(),
+                    None if entry.cf == 0 => inner.active_memtable.clone(),
+                    None => continue,
+                };
+                Self::note_unlogged(&mut inner, mem, first_seq);
             }
-            self.stats.record_write(batch_bytes);
         }
-        if let Some(last_seq) = applied_last_seq {
-            self.committed_sequence.store(last_seq, Ordering::Release);
+        let last_seq = assigned.iter().rev().find_map(|&(req_ptr, first_seq)| {
+            // SAFETY: request pointers are still owned by this leader.
+            let len = unsafe { &*req_ptr }.batch.len() as u64;
+            (len > 0).then(|| first_seq + len - 1)
+        });
+        if let Some(last_seq) = last_seq {
+            inner.logged_sequence = last_seq;
+            if need_sync && any_wal {
+                let durable = last_seq.min(Self::durable_limit(&mut inner));
+                self.durable_sequence.fetch_max(durable, Ordering::AcqRel);
+            }
         }
 
-        if let Some(msg) = append_error {
-            self.set_bg_error(msg.clone());
-            let mut assigned_iter = assigned.iter().map(|&(p, _)| p);
-            for &rp in batch_group {
-                if assigned_iter.next().is_some_and(|p| p == rp) {
-                    // SAFETY: request pointers are still owned by this leader.
-                    let rr = unsafe { &mut *rp };
-                    rr.result = Some(Ok(()));
-                } else {
-                    // SAFETY: request pointers are still owned by this leader.
-                    let rr = unsafe { &mut *rp };
-                    rr.result = Some(Err(Error::io(io::Error::other(msg.clone()))));
+        Ok(Some(LoggedGroup {
+            ticket: self.write_pipeline.issue(),
+            assigned,
+            mems: Arc::new(GroupMemtables {
+                default: inner.active_memtable.clone(),
+                families,
+            }),
+            last_seq,
+            append_error,
+        }))
+    }
+
+    /// Memtable stage of a logged group: insert every batch, publish the
+    /// group's sequence numbers in pipeline order, then queue a flush if
+    /// the memtable filled up. With `allow_concurrent_memtable_write` each
+    /// follower inserts its own batch while the leader inserts its own.
+    /// Returns `true` when the group queued a memtable flush.
+    fn apply_batch_group(
+        &self,
+        group: LoggedGroup,
+        batch_group: &[*mut WriteRequest],
+        leader: *mut WriteRequest,
+    ) -> Result<bool> {
+        let LoggedGroup {
+            ticket,
+            assigned,
+            mems,
+            last_seq,
+            append_error,
+        } = group;
+        let publish = || {
+            if let Some(last_seq) = last_seq {
+                self.committed_sequence.store(last_seq, Ordering::Release);
+            }
+        };
+        if self.options.allow_concurrent_memtable_write {
+            let followers: Vec<_> = assigned.iter().filter(|&&(p, _)| p != leader).collect();
+            let remaining = Arc::new(ApplyLatch::new(followers.len()));
+            if !followers.is_empty() {
+                let _wq = self.write_queue.lock();
+                for &&(req_ptr, first_seq) in &followers {
+                    // SAFETY: followers stay blocked on `write_cv` until
+                    // their request is done.
+                    unsafe {
+                        (*req_ptr).apply = Some(ApplyTask {
+                            first_seq,
+                            mems: mems.clone(),
+                            remaining: remaining.clone(),
+                        });
+                    }
                 }
+                self.write_cv.notify_all();
             }
-            return Ok(false);
+            if let Some(&(req_ptr, first_seq)) = assigned.iter().find(|&&(p, _)| p == leader) {
+                // SAFETY: the leader's own request.
+                let bytes = mems.apply(unsafe { &(*req_ptr).batch }, first_seq, true);
+                self.stats.record_write(bytes);
+            }
+            remaining.wait();
+            self.write_pipeline.run_in_order(ticket, publish);
+        } else {
+            self.write_pipeline.run_in_order(ticket, || {
+                for &(req_ptr, first_seq) in &assigned {
+                    // SAFETY: request pointers are still owned by this leader.
+                    let bytes = mems.apply(unsafe { &(*req_ptr).batch }, first_seq, false);
+                    self.stats.record_write(bytes);
+                }
+                publish();
+            });
         }
 
-        // Check memtable size threshold — release lock during SST I/O
-        let mut flush_wal: Option<u64> = None;
-        if inner.active_memtable.approximate_size() >= self.options.write_buffer_size {
-            match self.freeze_memtable_sync(&mut inner) {
-                Ok(frozen) => {
-                    let old_wal = frozen.old_wal_number;
-                    drop(inner); // release lock for slow SST write
-                    match self.flush_frozen_memtable(&frozen) {
-                        Ok(build_results) => {
-                            let output_numbers: Vec<u64> =
-                                build_results.iter().map(|(n, _)| *n).collect();
-                            let prepared_pins = self.prepare_l0_block_pins(&output_numbers);
-                            let mut inner = self.inner.lock(); // reacquire
-                            match self.install_flush(
-                                &mut inner,
-                                &frozen,
-                                build_results,
-                                prepared_pins,
-                            ) {
-                                Ok(()) => flush_wal = Some(old_wal),
-                                Err(e) => {
-                                    // Clean up orphan SST files: log_and_apply did not
-                                    // persist the edit, so these files must be deleted.
-                                    for num in &output_numbers {
-                                        let path = self.path.join(format!("{:06}.sst", num));
-                                        let _ = fs::remove_file(&path);
-                                    }
-                                    // The frozen memtable is still live in
-                                    // `immutable_memtables` and its WAL is intact, but a
-                                    // later successful flush would advance `log_number`
-                                    // past this WAL (stale reads + lost recovery). Fail-stop.
-                                    self.set_bg_error(format!("flush install failed: {}", e));
-                                }
-                            }
-                            drop(inner);
-                        }
-                        Err(e) => {
-                            // Same hazard as an install failure: the unflushed memtable
-                            // must not be skipped by a later flush. Fail-stop so its WAL
-                            // stays recoverable and no stale state is published.
-                            self.set_bg_error(format!("auto-flush SST write failed: {}", e));
-                        }
-                    }
-                }
-                Err(e) => {
-                    self.set_bg_error(format!("auto-flush freeze failed: {}", e));
-                    drop(inner);
+        if let Some(msg) = append_error {
+            self.set_bg_error(msg.clone());
+            for &rp in batch_group {
+                // SAFETY: request pointers are still owned by this leader.
+                let rr = unsafe { &mut *rp };
+                if rr.result.is_none() && !assigned.iter().any(|&(p, _)| p == rp) {
+                    rr.result = Some(Err(Error::io(io::Error::other(msg.clone()))));
                 }
             }
-        } else {
-            drop(inner);
+            return Ok(false);
         }
 
-        if let Some(old_wal) = flush_wal {
-            if let Err(e) = self.post_flush_cleanup(old_wal) {
-                // post_flush_cleanup already fail-stops (set_bg_error) on
-                // manifest-sync failure; just log here since this path
-                // can't propagate a Result to a caller.
-                tracing::warn!("post-flush cleanup failed for WAL {}: {}", old_wal, e);
+        // Queue a full memtable for a background flush. With the queue at
+        // `max_immutable_memtables` the memtable keeps growing instead;
+        // `maybe_stall_on_flush_queue` holds back the next writers.
+        let mut froze = false;
+        let mut inner = self.inner.lock();
+        if inner.active_memtable.approximate_size() >= self.options.write_buffer_size
+            && inner.flush_queue.len() < self.options.max_immutable_memtables
+        {
+            match self.schedule_flush(&mut inner) {
+                Ok(()) => froze = true,
+                Err(e) => self.set_bg_error(format!("auto-flush freeze failed: {}", e)),
             }
-            self.signal_compaction();
         }
+        drop(inner);
 
-        Ok(flush_wal.is_some())
-    }
-
-    /// Freeze+flush in one call (holds lock throughout).
-    /// Used by `close()` where the lock is already held
-    /// and releasing it would complicate the caller.
-    fn freeze_and_flush(&self, inner: &mut DBInner) -> Result<()> {
-        let frozen = self.freeze_memtable_sync(inner).ctx()?;
-        // After a successful freeze the immutable memtable + its WAL are live; any
-        // failure before the install + manifest sync complete must fail-stop, or a
-        // later flush could advance log_number past this WAL (stale reads + lost
-        // recovery on restart).
-        let build_results = match self.flush_frozen_memtable(&frozen) {
-            Ok(r) => r,
-            Err(e) => {
-                self.set_bg_error(format!("flush SST write failed: {}", e));
-                return Err(e);
-            }
-        };
-        let output_numbers: Vec<u64> = build_results.iter().map(|(n, _)| *n).collect();
-        let prepared_pins = self.prepare_l0_block_pins(&output_numbers);
-        if let Err(e) = self.install_flush(inner, &frozen, build_results, prepared_pins) {
-            // Clean up orphan SST files produced by the failed flush install.
-            for num in &output_numbers {
-                let path = self.path.join(format!("{:06}.sst", num));
-                let _ = fs::remove_file(&path);
+        // Column families that filled their write buffer. A failed family
+        // flush leaves its memtable and WAL in place; fail-stop like the
+        // default family's auto-flush.
+        for (family, mem) in mems.families.values() {
+            if mem.approximate_size() >= family.db.options.write_buffer_size
+                && let Err(e) = self.flush_family(family)
+            {
+                self.set_bg_error(format!(
+                    "column family {:?} auto-flush failed: {}",
+                    family.handle.name, e
+                ));
             }
-            self.set_bg_error(format!("flush install failed: {}", e));
-            return Err(e);
-        }
-        // Sync inline (close path, not performance-critical)
-        if let Err(e) = inner.versions.sync_manifest() {
-            self.set_bg_error(format!("flush manifest sync failed: {}", e));
-            return Err(e);
-        }
-        let old_wal_path = self.path.join(format!("{:06}.wal", frozen.old_wal_number));
-        if let Err(e) = fs::remove_file(&old_wal_path) {
-            tracing::warn!("failed to remove old WAL {}: {}", old_wal_path.display(), e);
         }
-        Ok(())
+
+        Ok(froze)
     }
 
-    /// Flush a frozen memtable to SST and install it, fail-stopping on any failure.
-    /// Used by the explicit `flush()` / `compact_range()` paths, which release the
-    /// DB lock during the SST write. A failure after the freeze leaves the frozen
-    /// memtable live in `immutable_memtables`; without fail-stop a later successful
-    /// flush would advance `log_number` past this memtable's WAL.
+    /// Flush a frozen column family memtable to SST and install it,
+    /// fail-stopping on any failure. Column families are flushed in place by
+    /// their owner rather than through its queue; the DB lock is released
+    /// during the SST write. A failure after the freeze leaves the frozen
+    /// memtable live in `immutable_memtables`; without fail-stop a later
+    /// successful flush would advance `log_number` past this memtable's WAL.
     fn flush_and_install_frozen(&self, frozen: &FrozenMemtable) -> Result<()> {
-        let build_results = match self.flush_frozen_memtable(frozen) {
+        let started = Instant::now();
+        let output = match self.flusher.flush_frozen_memtable(frozen) {
             Ok(r) => r,
             Err(e) => {
                 self.set_bg_error(format!("flush SST write failed: {}", e));
                 return Err(e);
             }
         };
-        let output_numbers: Vec<u64> = build_results.iter().map(|(n, _)| *n).collect();
-        let prepared_pins = self.prepare_l0_block_pins(&output_numbers);
+        let prepared_pins = self.flusher.prepare_l0_block_pins(&output.sst_numbers());
+        let duration = started.elapsed();
+        self.stats.record_duration(HistogramKind::Flush, duration);
         let mut inner = self.inner.lock();
-        if let Err(e) = self.install_flush(&mut inner, frozen, build_results, prepared_pins) {
+        if let Err(e) = self
+            .flusher
+            .install_flush(&mut inner, frozen, &output, prepared_pins)
+        {
             drop(inner);
-            // Clean up orphan SST files: log_and_apply did not persist the edit.
-            for num in &output_numbers {
-                let path = self.path.join(format!("{:06}.sst", num));
-                let _ = fs::remove_file(&path);
-            }
+            // Clean up orphan files: log_and_apply did not persist the edit.
+            output.remove_files(&self.path);
             self.set_bg_error(format!("flush install failed: {}", e));
             return Err(e);
         }
+        drop(inner);
+        let info = output.job_info(frozen.old_wal_number, duration);
+        for listener in &self.options.listeners {
+            listener.on_flush_completed(&info);
+        }
+        Ok(())
+    }
+
+    /// Phase 1 (under lock, fast): swap memtable, create WAL, reserve SST
+    /// numbers, and queue the frozen memtable for the flush threads.
+    fn schedule_flush(&self, inner: &mut DBInner) -> Result<()> {
+        let (old_wal_number, new_wal_number) = self.switch_wal(inner).ctx()?;
+        let frozen = self.freeze_active_memtable(inner, old_wal_number, new_wal_number);
+        self.flusher.enqueue(inner, frozen);
         Ok(())
     }
 
-    /// Phase 1 (under lock, fast): swap memtable, create WAL, reserve SST numbers.
-    /// Returns the FrozenMemtable for synchronous processing without queueing for background threads.
-    fn freeze_memtable_sync(&self, inner: &mut DBInner) -> Result<FrozenMemtable> {
+    /// Start a new WAL file and retire the current writer. Returns the old
+    /// and new WAL numbers. Waits first for logged groups to finish their
+    /// memtable inserts: their records are in the old WAL, so they belong
+    /// in the memtable being retired with it.
+    fn switch_wal(&self, inner: &mut DBInner) -> Result<(u64, u64)> {
+        if self.family_id != 0 {
+            return Err(Error::invalid_argument(
+                "column family memtables are flushed through their owning DB".to_string(),
+            ));
+        }
+        self.write_pipeline.wait_idle();
+        // Sync the retiring WAL so the watermark never has to track more
+        // than one unsynced log.
+        self.sync_wal_locked(inner)?;
         let new_wal_number = inner.versions.new_file_number();
         let new_wal_path = self.path.join(format!("{:06}.wal", new_wal_number));
-        let new_wal = WalWriter::new(&new_wal_path).ctx()?;
-        let old_mem = mem::replace(&mut inner.active_memtable, Arc::new(MemTable::new()));
+        let new_wal =
+            WalWriter::with_compression(&new_wal_path, self.options.wal_compression).ctx()?;
         let old_wal_number = inner.wal_number;
         inner.wal_writer = Some(new_wal);
         inner.wal_number = new_wal_number;
-        inner.immutable_memtables.push(old_mem.clone());
+        Ok((old_wal_number, new_wal_number))
+    }
+
+    /// Fsync the active WAL and advance `durable_sequence` to everything
+    /// logged so far. A failed fsync is fail-stop: the kernel may have
+    /// dropped the dirty pages, so a retry could report success falsely.
+    fn sync_wal_locked(&self, inner: &mut DBInner) -> Result<()> {
+        let logged = inner.logged_sequence.min(Self::durable_limit(inner));
+        if self.durable_sequence.load(Ordering::Acquire) >= logged {
+            return Ok(());
+        }
+        if let Some(ref mut wal) = inner.wal_writer {
+            let started = self.stats.start_timer();
+            let synced = wal.sync();
+            self.stats.record_latency(HistogramKind::WalSync, started);
+            if let Err(e) = synced {
+                self.set_bg_error(format!("WAL sync failed: {}", e));
+                return Err(e);
+            }
+        }
+        self.durable_sequence.fetch_max(logged, Ordering::AcqRel);
+        Ok(())
+    }
+
+    /// Record a `disable_wal` write starting at `seq` in `mem`. A memtable
+    /// already listed keeps its older sequence.
+    fn note_unlogged(inner: &mut DBInner, mem: Arc<MemTable>, seq: SequenceNumber) {
+        if inner
+            .unlogged_memtables
+            .iter()
+            .any(|(m, _)| Arc::ptr_eq(m, &mem))
+        {
+            return;
+        }
+        inner.unlogged_memtables.retain(|(m, _)| !m.is_flushed());
+        inner.unlogged_memtables.push((mem, seq));
+    }
+
+    /// Highest sequence the durable watermark may reach: just below the
+    /// oldest `disable_wal` write whose memtable is not flushed yet.
+    fn durable_limit(inner: &mut DBInner) -> SequenceNumber {
+        inner.unlogged_memtables.retain(|(m, _)| !m.is_flushed());
+        inner
+            .unlogged_memtables
+            .iter()
+            .map(|&(_, seq)| seq.saturating_sub(1))
+            .min()
+            .unwrap_or(MAX_SEQUENCE_NUMBER)
+    }
+
+    /// Move the active memtable to the immutable list once the WAL has been
+    /// switched to `new_wal_number`, and reserve the flush's SST numbers.
+    fn freeze_active_memtable(
+        &self,
+        inner: &mut DBInner,
+        old_wal_number: u64,
+        new_wal_number: u64,
+    ) -> FrozenMemtable {
+        let old_mem = mem::replace(
+            &mut inner.active_memtable,
+            Arc::new(MemTable::with_comparator(self.icmp.clone())),
+        );
+        inner.immutable_memtables.insert(0, old_mem.clone());
 
         // Reserve enough file numbers for the flush to split its output when
         // projected single-block metadata grows large (key-heavy data).
         // Generous over-reservation is harmless: file numbers come
         // from a monotonic u64 counter and unused ones are never reused.
+        // One extra number covers the flush's blob file.
         let reserve =
-            2 + (4 * old_mem.approximate_size() as u64) / META_BLOCK_SPLIT_THRESHOLD as u64;
+            3 + (4 * old_mem.approximate_size() as u64) / META_BLOCK_SPLIT_THRESHOLD as u64;
         let base = inner.versions.reserve_file_numbers(reserve);
         let sst_numbers: Vec<u64> = (base..base + reserve).collect();
         self.install_super_version(inner);
 
-        Ok(FrozenMemtable {
+        FrozenMemtable {
             old_mem,
             sst_numbers,
             old_wal_number,
             new_wal_number,
-        })
-    }
-
-    /// Phase 2 (no lock needed, slow I/O): write SSTs from frozen memtable.
-    fn flush_frozen_memtable(
-        &self,
-        frozen: &FrozenMemtable,
-    ) -> Result<Vec<(u64, TableBuildResult)>> {
-        let mut numbers = frozen.sst_numbers.iter().copied();
-        let results = Self::write_memtable_ssts(
-            &frozen.old_mem,
-            &self.path,
-            &|| self.flush_build_opts(),
-            &mut || {
-                numbers.next().ok_or_else(|| {
-                    Error::invalid_argument(
-                        "reserved flush output file numbers exhausted".to_string(),
-                    )
-                })
-            },
-        )?;
-        // Pre-warm the table cache for the new SSTs while unlocked, so
-        // install_flush's log_and_apply (which opens each new file to
-        // install its reader) hits a warm cache instead of parsing
-        // footers/indexes while db_mutex is held.
-        self.table_cache
-            .prewarm(results.iter().map(|(number, _)| *number));
-        Ok(results)
+        }
     }
 
-    /// Read, checksum, and decompress each listed file's first data block
-    /// for L0 pinning. Does not itself take `inner` — the caller controls
-    /// whether the DB lock is held. Meant to be called from the same
-    /// unlocked phase as `flush_frozen_memtable`, right before the short
-    /// locked `install_flush` call, so a legal block up to the ~64 MiB
-    /// max write-entry size is read unlocked and `install_flush` only has
-    /// to publish these already-prepared blocks, a cheap in-memory cache
-    /// insert. That holds for the auto-flush and `flush_and_install_frozen`
-    /// callers; `freeze_and_flush` (used only by `close()`) still calls
-    /// this while holding `inner`, per CC4's documented
-    /// `close`/`freeze_and_flush`-may-hold-lock exception — same as the
-    /// pre-split code on that path. Returns an empty `Vec` (no-op) when
-    /// `pin_l0_filter_and_index_blocks_in_cache` is disabled.
-    fn prepare_l0_block_pins(&self, file_numbers: &[u64]) -> Vec<(u64, PreparedBlockPin)> {
-        if !self.options.pin_l0_filter_and_index_blocks_in_cache {
-            return Vec::new();
+    /// Flush the active memtable and every queued one into SSTs, building
+    /// queued jobs on this thread alongside the flush threads. Returns
+    /// `false` when there was nothing to flush. Callers hold `write_queue`
+    /// with no leader active, so nothing new is queued meanwhile.
+    fn flush_active_memtable(&self) -> Result<bool> {
+        {
+            let mut inner = self.inner.lock();
+            if inner.active_memtable.is_empty() && inner.flush_queue.is_empty() {
+                return Ok(false);
+            }
+            if !inner.active_memtable.is_empty() {
+                self.schedule_flush(&mut inner).ctx()?;
+            }
+        }
+        // A failure is recorded as the background error reported below.
+        let _ = self.flusher.run_queued();
+        self.flusher.wait_until_flushed();
+        match self.fail_stop_error() {
+            Some(e) => Err(e),
+            None => Ok(true),
         }
-        file_numbers
-            .iter()
-            .filter_map(|&number| {
-                // Cache hit: `flush_frozen_memtable`'s prewarm (or an
-                // earlier iteration of this same loop's own get_reader
-                // call) already opened and cached this reader.
-                let reader = self.table_cache.get_reader(number).ok()?;
-                let prepared = reader.prepare_first_block_pin()?;
-                Some((number, prepared))
-            })
-            .collect()
     }
 
-    /// Phase 3 (under lock, fast): install flush result into version.
-    fn install_flush(
-        &self,
-        inner: &mut DBInner,
-        frozen: &FrozenMemtable,
-        build_results: Vec<(u64, TableBuildResult)>,
-        prepared_pins: Vec<(u64, PreparedBlockPin)>,
-    ) -> Result<()> {
-        let mut edit = VersionEdit::new();
-        edit.set_log_number(frozen.new_wal_number);
-        edit.set_next_file_number(inner.versions.next_file_number());
-        edit.set_last_sequence(self.current_sequence());
-        for (number, build_result) in build_results {
-            edit.add_file(
-                0, // L0
-                FileMetaData {
-                    number,
-                    file_size: build_result.file_size,
-                    smallest_key: build_result.smallest_key.unwrap_or_default(),
-                    largest_key: build_result.largest_key.unwrap_or_default(),
-                    has_range_deletions: build_result.has_range_deletions,
-                },
-            );
-        }
-        inner.versions.log_and_apply(edit).ctx()?;
-        self.stats.record_flush();
-        inner
-            .immutable_memtables
-            .retain(|m| !Arc::ptr_eq(m, &frozen.old_mem));
+    /// Body of [`Self::create_checkpoint`] once `dir` exists.
+    fn write_checkpoint(&self, dir: &Path) -> Result<()> {
+        // (engine, checkpoint dir, live version, MANIFEST number, snapshot edit)
+        let mut captured = Vec::new();
+        let mut min_log_number = u64::MAX;
+        let gate = {
+            let mut wq = self.write_queue.lock();
+            self.wait_for_write_leader_idle(&mut wq);
+            self.check_usable().ctx()?;
+            let families: Vec<Arc<ColumnFamily>> =
+                self.column_families.read().values().cloned().collect();
+            if !self.read_only {
+                self.flush_active_memtable().ctx()?;
+                for family in &families {
+                    self.flush_family(family).ctx()?;
+                }
+            }
+            // Lock order: owner `inner`, then the families'. The gate is
+            // taken while every `inner` is held, so any compaction installed
+            // after the capture has to wait before deleting its inputs.
+            let inner = self.inner.lock();
+            let family_inners: Vec<_> = families.iter().map(|f| f.db.inner.lock()).collect();
+            let mut engines = vec![(&self.path, dir.to_path_buf(), &*inner)];
+            for (family, g) in families.iter().zip(&family_inners) {
+                engines.push((&family.db.path, family_dir(dir, family.handle.id), &**g));
+            }
+            for (src, dst, g) in engines {
+                let (manifest_number, edit) = g.versions.checkpoint_edit();
+                min_log_number = min_log_number.min(g.versions.log_number());
+                captured.push((
+                    src.clone(),
+                    dst,
+                    g.versions.current(),
+                    manifest_number,
+                    edit,
+                ));
+            }
+            self.file_deletion_gate.write()
+        };
 
-        // Publish each already-read/checksummed/decompressed first block
-        // (prepared by `prepare_l0_block_pins`, unlocked on all but the
-        // `close()` path — see that function's doc comment) into the
-        // shared cache — an in-memory map insert only, no I/O, so this
-        // stays cheap under the lock regardless of block size.
-        if !prepared_pins.is_empty() {
-            let version = inner.versions.current();
-            for (number, prepared) in prepared_pins {
-                if let Some(tf) = version
-                    .level_files(0)
-                    .iter()
-                    .find(|tf| tf.meta.number == number)
-                {
-                    tf.reader.publish_prepared_pin(prepared);
+        for (src, dst, version, _, _) in &captured {
+            if dst != dir {
+                fs::create_dir(dst).ctx()?;
+            }
+            for level in 0..version.num_levels {
+                for tf in version.level_files(level) {
+                    let name = format!("{:06}.sst", tf.meta.number);
+                    link_or_copy(&src.join(&name), &dst.join(&name)).ctx()?;
                 }
             }
+            for &number in version.blob_files.keys() {
+                link_or_copy(&blob_file_path(src, number), &blob_file_path(dst, number)).ctx()?;
+            }
         }
+        drop(gate);
 
-        self.l0_file_count
-            .store(inner.versions.current().l0_file_count(), Ordering::Relaxed);
-        self.install_super_version(inner);
+        if self.read_only {
+            // Nothing was flushed: the memtables live only in the WALs, which
+            // a read-only handle never deletes.
+            for entry in fs::read_dir(&self.path).ctx()? {
+                let entry = entry.ctx()?;
+                let name = entry.file_name();
+                let needed = name
+                    .to_string_lossy()
+                    .strip_suffix(".wal")
+                    .and_then(|s| s.parse::<u64>().ok())
+                    .is_some_and(|num| num >= min_log_number);
+                if needed {
+                    let target = dir.join(&name);
+                    fs::copy(entry.path(), &target).ctx()?;
+                    fs::File::open(&target).ctx()?.sync_all().ctx()?;
+                }
+            }
+        }
 
-        // WAL deletion is deferred: callers must sync the manifest first,
-        // then delete the old WAL file outside the main lock.
+        // Families first: the owner's MANIFEST registers them, so it is
+        // written last.
+        for (_, dst, _, manifest_number, edit) in captured.iter().rev() {
+            VersionSet::write_snapshot_manifest(dst, *manifest_number, edit).ctx()?;
+        }
+        if let Some(parent) = dir.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::File::open(parent).ctx()?.sync_all().ctx()?;
+        }
         Ok(())
     }
 
-    /// Sync the manifest and delete the old WAL after install_flush.
-    /// Must be called outside the main lock.
-    ///
-    /// By the time this runs, `install_flush` has already applied the
-    /// VersionEdit and removed the memtable from `immutable_memtables`, so
-    /// the only remaining risk is manifest durability: a later successful
-    /// flush would advance `log_number` past this WAL (stale reads + lost
-    /// recovery), so a sync failure here fail-stops via `set_bg_error` for
-    /// every caller (explicit `flush()`/`compact_range()`, and the hot
-    /// auto-flush path, which cannot otherwise propagate this failure to
-    /// any caller since the enclosing write still needs to return `Ok`).
-    fn post_flush_cleanup(&self, old_wal_number: u64) -> Result<()> {
-        let manifest_handle = self.inner.lock().versions.manifest_sync_handle();
-        if let Err(e) = confirm_manifest_durable(&manifest_handle, &self.manifest_poisoned) {
-            // Poison first: a later "successful" sync could falsely
-            // report durability of the records this sync failed to
-            // persist (fsyncgate). confirm_manifest_durable emits poison
-            // for fresh sync errors; already-poisoned rotation is fail-closed.
-            self.set_bg_error(format!("post-flush manifest sync failed: {}", e));
+    /// Flush a column family's memtable. The shared WAL is switched first so
+    /// the family's next log number cleanly separates flushed entries from
+    /// later ones. Callers hold `write_queue` with no leader active (or are
+    /// finishing a group), and the switch waits out logged groups, so no
+    /// write can race it.
+    fn flush_family(&self, family: &ColumnFamily) -> Result<()> {
+        let frozen = {
+            // Lock order: owner `inner`, then the family's.
+            let mut inner = self.inner.lock();
+            let mut family_inner = family.db.inner.lock();
+            if family_inner.active_memtable.is_empty() {
+                return Ok(());
+            }
+            let (old_wal_number, new_wal_number) = self.switch_wal(&mut inner).ctx()?;
+            family
+                .db
+                .freeze_active_memtable(&mut family_inner, old_wal_number, new_wal_number)
+        };
+        family.db.flush_and_install_frozen(&frozen).ctx()?;
+        let manifest_handle = family.db.inner.lock().versions.manifest_sync_handle();
+        if let Err(e) = confirm_manifest_durable(&manifest_handle, &family.db.manifest_poisoned) {
+            self.set_bg_error(format!("column family flush manifest sync failed: {}", e));
             return Err(e).ctx();
         }
-        let old_wal_path = self.path.join(format!("{:06}.wal", old_wal_number));
-        if let Err(e) = fs::remove_file(&old_wal_path) {
-            tracing::warn!("failed to remove old WAL {}: {}", old_wal_path.display(), e);
-        }
+        self.flusher.remove_obsolete_wals();
+        family.db.signal_compaction();
         Ok(())
     }
 
+    /// Finish recovery of a column family once its owner has replayed the
+    /// shared WAL and opened `wal_number`: flush the recovered entries and
+    /// record `wal_number` as the family's log number.
+    fn install_recovered_family(&self, wal_number: u64) -> Result<()> {
+        let frozen = {
+            let mut inner = self.inner.lock();
+            if inner.active_memtable.is_empty() {
+                let mut edit = VersionEdit::new();
+                edit.set_log_number(wal_number);
+                edit.set_last_sequence(self.current_sequence());
+                inner.versions.log_and_apply(edit).ctx()?;
+                None
+            } else {
+                let old_wal_number = inner.versions.log_number();
+                Some(self.freeze_active_memtable(&mut inner, old_wal_number, wal_number))
+            }
+        };
+        if let Some(frozen) = frozen {
+            self.flush_and_install_frozen(&frozen).ctx()?;
+        }
+        self.inner.lock().versions.sync_manifest().ctx()
+    }
+
     /// Drain L0 down below its compaction trigger. Follows the same
     /// short-lock pick → unlocked I/O → short-lock install → unlocked sync
     /// pattern used by the background compaction thread pool
@@ -3806,20 +7364,47 @@ impl DB {
     /// compaction is already doing the work), while `true` waits for the
     /// claim to settle and re-picks so L0 is genuinely drained on return
     /// (required by `force_compact_all`, whose level passes would otherwise
-    /// run before L0 data reached them).
+    /// run before L0 data reached them). Being that explicit compaction, its
+    /// tasks are reported to listeners as `CompactionReason::Manual`.
     fn drain_l0(&self, wait_for_inflight: bool) -> Result<()> {
-        let force_opts = DbOptions {
-            l0_compaction_trigger: 1,
-            ..self.options.clone()
-        };
+        let picker = compaction::picker(self.options.compaction_style);
+        self.run_forced_compactions(wait_for_inflight, |version, claimed| {
+            let task = picker.pick_l0_drain(version, &self.options, claimed);
+            // A `None` pick while L0 is non-empty means every candidate was
+            // excluded by an in-flight claim — not that L0 is drained.
+            (
+                task.map(|task| CompactionTask {
+                    reason: if wait_for_inflight {
+                        CompactionReason::Manual
+                    } else {
+                        task.reason
+                    },
+                    ..task
+                }),
+                version.l0_file_count() > 0 && !claimed.is_empty(),
+            )
+        })
+    }
+
+    /// Run the compactions returned by `pick` until it returns `None`, with
+    /// the locking pattern described on `drain_l0`. `pick` sees the current
+    /// version and the claimed in-flight inputs, and also reports whether a
+    /// `None` pick is only blocked by those claims; with
+    /// `wait_for_inflight`, a blocked pick waits for the claims to settle
+    /// and retries.
+    fn run_forced_compactions(
+        &self,
+        wait_for_inflight: bool,
+        pick: impl Fn(&Version, &HashSet<u64>) -> (Option<CompactionTask>, bool),
+    ) -> Result<()> {
         loop {
             // Phase 1: pick + pre-allocate (short lock)
-            let (pick, l0_blocked) = {
+            let (pick, blocked) = {
                 let mut inner = self.inner.lock();
                 let version = inner.versions.current();
                 let mut claimed = self.compacting_files.lock();
-                match LeveledCompaction::pick_compaction(&version, &force_opts, &claimed) {
-                    Some(task) => {
+                match pick(&version, &claimed) {
+                    (Some(task), _) => {
                         let l0_inputs: Vec<u64> = if task.level == 0 {
                             task.input_files_level
                                 .iter()
@@ -3828,19 +7413,14 @@ impl DB {
                         } else {
                             Vec::new()
                         };
-                        let max_out = LeveledCompaction::max_output_files(&task, &force_opts);
+                        let max_out = LeveledCompaction::max_output_files(&task, &self.options);
                         let file_start = inner.versions.reserve_file_numbers(max_out);
                         let file_limit = file_start.saturating_add(max_out);
-                        let all_inputs: Vec<_> = task
-                            .input_files_level
-                            .iter()
-                            .chain(task.input_files_next.iter())
-                            .cloned()
-                            .collect();
+                        let all_inputs = task.all_input_files();
                         let is_bottom = LeveledCompaction::is_bottommost_level(
                             &version,
                             task.level,
-                            force_opts.num_levels,
+                            self.options.num_levels,
                             &all_inputs,
                         );
                         let claimed_numbers: Vec<u64> =
@@ -3862,10 +7442,7 @@ impl DB {
                             false,
                         )
                     }
-                    // With `l0_compaction_trigger: 1`, a `None` pick while L0
-                    // is non-empty means every candidate was excluded by an
-                    // in-flight claim — not that L0 is drained.
-                    None => (None, version.l0_file_count() > 0),
+                    (None, blocked) => (None, blocked),
                 }
             }; // lock released
             let Some((
@@ -3878,7 +7455,7 @@ impl DB {
                 claimed_numbers,
             )) = pick
             else {
-                if wait_for_inflight && l0_blocked {
+                if wait_for_inflight && blocked {
                     // Wait (without holding `inner`) for the in-flight
                     // compaction to install or discard, then re-pick.
                     // Breaking here instead would return with L0 undrained.
@@ -3895,12 +7472,14 @@ impl DB {
             };
 
             // Phase 2: I/O (no lock held)
+            let ts_low = self.full_history_ts_low.read().clone();
             let ctx = CompactionContext {
                 db_path: &self.path,
-                options: &force_opts,
+                options: &self.options,
                 rate_limiter: Some(&self.rate_limiter),
                 stats: Some(&self.stats),
                 active_snapshots: &active_snaps,
+                full_history_ts_low: ts_low.as_deref(),
             };
             let output = LeveledCompaction::execute_compaction_io(
                 &ctx, &task, file_start, file_limit, is_bottom,
@@ -3956,16 +7535,22 @@ impl DB {
                 // thread treats as fail-stop. confirm_manifest_durable
                 // enforces poison; record it so `flush()` and `check_usable`
                 // see a fatal engine state, not a retryable hiccup.
-                self.set_bg_error(format!("drain_l0 manifest sync failed: {}", e));
+                self.set_bg_error(format!("forced compaction manifest sync failed: {}", e));
                 return Err(e).ctx();
             }
-            LeveledCompaction::run_post_compaction_cleanup(&cleanup, &self.path);
+            LeveledCompaction::run_post_compaction_cleanup(
+                &cleanup,
+                &self.path,
+                &self.file_deletion_gate,
+                &self.options.listeners,
+            );
         }
         Ok(())
     }
 
-    /// Drain L0, then force-merge every level down to as few files as
-    /// possible. This is the full, deliberate compaction used by the
+    /// Drain L0, merge runs across levels if the compaction style calls for
+    /// it, then force-merge every level down to as few files as possible.
+    /// This is the full, deliberate compaction used by the
     /// explicit, user-invoked `compact()` / `compact_range(None, None)`
     /// entry points — NOT by the inline write-throttle path or `flush()`,
     /// which only need `drain_l0` to relieve L0 backpressure. The lock is
@@ -3984,52 +7569,86 @@ impl DB {
         // stale-filtered data after this full pass has returned.
         self.compacting_files.wait_until_empty();
         self.drain_l0(true)?;
+        // Universal compaction then merges the remaining runs into one.
+        let picker = compaction::picker(self.options.compaction_style);
+        self.run_forced_compactions(true, |version, claimed| {
+            (
+                picker.pick_full_compaction(version, claimed),
+                !claimed.is_empty(),
+            )
+        })?;
         let force_opts = DbOptions {
             l0_compaction_trigger: 1,
             ..self.options.clone()
         };
         for level in 1..self.options.num_levels {
-            let mut inner = self.inner.lock();
-            let active_snaps = self.snapshot_list.as_sorted_vec();
-            let ctx = CompactionContext {
-                db_path: &self.path,
-                options: &force_opts,
-                rate_limiter: Some(&self.rate_limiter),
-                stats: Some(&self.stats),
-                active_snapshots: &active_snaps,
+            let cleanup = {
+                let mut inner = self.inner.lock();
+                let active_snaps = self.snapshot_list.as_sorted_vec();
+                let ts_low = self.full_history_ts_low.read().clone();
+                let ctx = CompactionContext {
+                    db_path: &self.path,
+                    options: &force_opts,
+                    rate_limiter: Some(&self.rate_limiter),
+                    stats: Some(&self.stats),
+                    active_snapshots: &active_snaps,
+                    full_history_ts_low: ts_low.as_deref(),
+                };
+                let cleanup = LeveledCompaction::force_merge_level(
+                    &ctx,
+                    level,
+                    CompactionReason::Manual,
+                    &mut inner.versions,
+                    Some(&self.table_cache),
+                    Some(&self.block_cache),
+                )
+                .ctx()?;
+                self.l0_file_count
+                    .store(inner.versions.current().l0_file_count(), Ordering::Relaxed);
+                self.install_super_version(&inner);
+                cleanup
             };
-            LeveledCompaction::force_merge_level(
-                &ctx,
-                level,
-                &mut inner.versions,
-                Some(&self.table_cache),
-                Some(&self.block_cache),
-            )
-            .ctx()?;
-            self.l0_file_count
-                .store(inner.versions.current().l0_file_count(), Ordering::Relaxed);
-            self.install_super_version(&inner);
+            LeveledCompaction::run_post_compaction_cleanup(
+                &cleanup,
+                &self.path,
+                &self.file_deletion_gate,
+                &self.options.listeners,
+            );
         }
         Ok(())
     }
 
     fn encode_wal_record(sequence: u64, batch: &WriteBatch) -> Vec<u8> {
-        // Pre-size: 8 (seq) + 4 (count) + per-entry (1 type + 4 key_len + key + 4 val_len + val)
+        // Pre-size: 8 (seq) + 4 (count) + per-entry (1 type [+ 4 cf] + 4 key_len + key
+        // + 4 val_len + val)
         let estimated = 12
             + batch
                 .entries
                 .iter()
-                .map(|e| 1 + 4 + e.key.len() + 4 + e.value.as_ref().map_or(0, |v| v.len()))
+                .map(|e| {
+                    let cf_len = if e.cf == 0 { 0 } else { 4 };
+                    1 + cf_len + 4 + e.key.len() + 4 + e.value.as_ref().map_or(0, |v| v.len())
+                })
                 .sum::<usize>();
         let mut buf = Vec::with_capacity(estimated);
         buf.extend_from_slice(&sequence.to_le_bytes());
         buf.extend_from_slice(&(batch.len() as u32).to_le_bytes());
         for entry in &batch.entries {
-            buf.push(entry.value_type as u8);
+            if entry.cf == 0 {
+                buf.push(entry.value_type as u8);
+            } else {
+                buf.push(entry.value_type as u8 | WAL_COLUMN_FAMILY_FLAG);
+                buf.extend_from_slice(&entry.cf.to_le_bytes());
+            }
             buf.extend_from_slice(&(entry.key.len() as u32).to_le_bytes());
             buf.extend_from_slice(&entry.key);
-            if entry.value_type == ValueType::Value || entry.value_type == ValueType::RangeDeletion
-            {
+            if matches!(
+                entry.value_type,
+                ValueType::Value
+                    | ValueType::RangeDeletion
+                    | ValueType::Merge
+                    | ValueType::ExpiringValue
+            ) {
                 let val = entry.value.as_deref().unwrap_or(&[]);
                 buf.extend_from_slice(&(val.len() as u32).to_le_bytes());
                 buf.extend_from_slice(val);
@@ -4040,10 +7659,14 @@ impl DB {
 
     /// Remove files in the DB directory that recovery has proven dead:
     /// - `.wal` files numbered below the recovered `log_number` — already
-    ///   replayed (or superseded) and never read again,
+    ///   replayed (or superseded) and never read again; these are archived
+    ///   instead when WAL archiving is on, and the archive is purged,
     /// - `.sst` files absent from the recovered version — crash orphans from
     ///   an interrupted flush/compaction, or compaction inputs whose deletion
     ///   edit was durable but whose unlink never ran,
+    /// - `.blob` files absent from the recovered version, for the same
+    ///   reasons,
+    /// - `cf-<id>` directories of column families absent from the MANIFEST,
     /// - `MANIFEST-*` files other than the current one, plus `CURRENT.tmp` /
     ///   `CURRENT.tmp.<N>` — leftovers from an interrupted MANIFEST rotation
     ///   (the unsuffixed form is only produced by pre-4.1.1 versions).
@@ -4052,7 +7675,13 @@ impl DB {
     /// `log_number`) is durable in the MANIFEST, while the directory LOCK is
     /// held and before any background thread starts. Deletion failures are
     /// logged and ignored — cleanup re-runs on the next open.
-    fn remove_orphan_files(path: &Path, versions: &VersionSet) {
+    fn remove_orphan_files(
+        path: &Path,
+        options: &DbOptions,
+        versions: &VersionSet,
+        deletion_gate: &RwLock<()>,
+    ) {
+        let _gate = deletion_gate.read();
         let version = versions.current();
         let live_ssts: HashSet<u64> = (0..version.num_levels)
             .flat_map(|level| version.level_files(level))
@@ -4073,17 +7702,42 @@ impl DB {
                 .strip_suffix(".wal")
                 .and_then(|s| s.parse::<u64>().ok())
             {
-                num < versions.log_number()
+                if num < versions.log_number()
+                    && let Err(e) = archive::retire_wal(path, num, options)
+                {
+                    tracing::warn!("failed to retire orphan WAL {}: {}", name, e);
+                }
+                false
             } else if let Some(num) = name
                 .strip_suffix(".sst")
                 .and_then(|s| s.parse::<u64>().ok())
             {
-                !live_ssts.contains(&num)
+                !live_ssts.contains(&num)
+            } else if let Some(num) = name
+                .strip_suffix(".blob")
+                .and_then(|s| s.parse::<u64>().ok())
+            {
+                !version.blob_files.contains_key(&num)
             } else if let Some(num) = name
                 .strip_prefix("MANIFEST-")
                 .and_then(|s| s.parse::<u64>().ok())
             {
                 num != versions.manifest_number()
+            } else if let Some(id) = name.strip_prefix("cf-").and_then(|s| s.parse::<u32>().ok()) {
+                // Directory of a dropped column family, or of one whose
+                // creation crashed before it was registered.
+                if !versions.column_families().contains_key(&id) {
+                    let dir = entry.path();
+                    match fs::remove_dir_all(&dir) {
+                        Ok(()) => tracing::info!("removed orphan column family {}", dir.display()),
+                        Err(e) => tracing::warn!(
+                            "failed to remove orphan column family {}: {}",
+                            dir.display(),
+                            e
+                        ),
+                    }
+                }
+                false
             } else {
                 // Keep the unsuffixed literal: dirs written by pre-4.1.1
                 // versions may still hold a plain `CURRENT.tmp`.
@@ -4103,9 +7757,33 @@ impl DB {
                 }
             }
         }
+        archive::purge_archive(path, options);
+    }
+
+    /// Replay one WAL record. `route` maps a column family id to the memtable
+    /// that should receive its entries; entries of families it returns `None`
+    /// for (dropped, or already flushed past this WAL) are parsed and skipped.
+    fn replay_wal_record<'m>(
+        data: &[u8],
+        route: impl Fn(u32) -> Option<&'m MemTable>,
+        max_sequence: &mut u64,
+    ) -> Result<()> {
+        Self::decode_wal_record(data, |seq, cf, vt, key, value| {
+            *max_sequence = (*max_sequence).max(seq);
+            if let Some(mem) = route(cf) {
+                mem.put(key, value, seq, vt);
+            }
+        })?;
+        Ok(())
     }
 
-    fn replay_wal_record(data: &[u8], mem: &MemTable, max_sequence: &mut u64) -> Result<()> {
+    /// Parse one WAL record written by `encode_wal_record`, calling `visit`
+    /// with each entry's sequence, column family, type, key and value
+    /// (empty for deletions). Returns the record's first sequence.
+    pub(crate) fn decode_wal_record(
+        data: &[u8],
+        mut visit: impl FnMut(SequenceNumber, u32, ValueType, &[u8], &[u8]),
+    ) -> Result<SequenceNumber> {
         if data.len() < 12 {
             return Err(Error::corruption(format!(
                 "WAL record too short: {} bytes",
@@ -4132,10 +7810,20 @@ impl DB {
                     entry_seq, MAX_SEQUENCE_NUMBER
                 )));
             }
-            *max_sequence = (*max_sequence).max(entry_seq);
-
-            let vt = data[offset];
+            let mut vt = data[offset];
             offset += 1;
+            let mut cf = 0;
+            if vt & WAL_COLUMN_FAMILY_FLAG != 0 {
+                if offset + 4 > data.len() {
+                    return Err(Error::corruption(format!(
+                        "WAL record truncated reading column family at entry {}",
+                        i
+                    )));
+                }
+                cf = u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap());
+                offset += 4;
+                vt &= !WAL_COLUMN_FAMILY_FLAG;
+            }
             if offset + 4 > data.len() {
                 return Err(Error::corruption(format!(
                     "WAL record truncated reading key length at entry {}",
@@ -4154,7 +7842,7 @@ impl DB {
             offset += key_len;
 
             match ValueType::from_u8(vt) {
-                Some(ValueType::Value) => {
+                Some(vt @ (ValueType::Value | ValueType::Merge | ValueType::ExpiringValue)) => {
                     if offset + 4 > data.len() {
                         return Err(Error::corruption(format!(
                             "WAL record truncated reading value length at entry {}",
@@ -4172,10 +7860,10 @@ impl DB {
                     }
                     let value = &data[offset..offset + val_len];
                     offset += val_len;
-                    mem.put(key, value, entry_seq, ValueType::Value);
+                    visit(entry_seq, cf, vt, key, value);
                 }
                 Some(ValueType::Deletion) => {
-                    mem.put(key, &[], entry_seq, ValueType::Deletion);
+                    visit(entry_seq, cf, ValueType::Deletion, key, &[]);
                 }
                 Some(ValueType::RangeDeletion) => {
                     // RangeDeletion: value is the end key
@@ -4196,9 +7884,10 @@ impl DB {
                     }
                     let value = &data[offset..offset + val_len];
                     offset += val_len;
-                    mem.put(key, value, entry_seq, ValueType::RangeDeletion);
+                    visit(entry_seq, cf, ValueType::RangeDeletion, key, value);
                 }
-                None => {
+                // Blob indexes are produced by flush and never logged.
+                Some(ValueType::BlobIndex) | None => {
                     return Err(Error::corruption(format!(
                         "WAL record contains unknown value type {} at entry {}",
                         vt, i
@@ -4206,32 +7895,10 @@ impl DB {
                 }
             }
         }
-        Ok(())
+        Ok(seq)
     }
 
     /// Build options for flush outputs (always L0).
-    fn flush_build_opts(&self) -> TableBuildOptions {
-        let compression = if !self.options.compression_per_level.is_empty() {
-            self.options.compression_per_level[0]
-        } else {
-            self.options.compression
-        };
-        TableBuildOptions {
-            block_size: self.options.block_size,
-            block_restart_interval: self.options.block_restart_interval,
-            bloom_bits_per_key: self.options.bloom_bits_per_key,
-            internal_keys: true,
-            compression,
-            prefix_len: self.options.prefix_len,
-            block_property_collectors: self
-                .options
-                .block_property_collectors
-                .iter()
-                .map(|f| f())
-                .collect(),
-        }
-    }
-
     /// Write a memtable to one or more SST files, splitting at user-key
     /// boundaries whenever projected single-block metadata reaches
     /// `META_BLOCK_SPLIT_THRESHOLD` (the SST reader rejects
@@ -4240,13 +7907,20 @@ impl DB {
     /// ranges, so all versions of one user key stay in a single file — this
     /// is required for correct newest-file-first L0 point lookups.
     ///
+    /// With a merge operator, runs of merge operands are folded the way
+    /// compaction folds them (see [`Self::fold_flush_merge_run`]); `snapshots`
+    /// are the active snapshot sequences that bound each fold.
+    ///
     /// On error, all already-written output files are removed.
     fn write_memtable_ssts(
         mem: &MemTable,
         db_path: &Path,
         make_opts: &dyn Fn() -> TableBuildOptions,
         next_number: &mut dyn FnMut() -> Result<u64>,
-    ) -> Result<Vec<(u64, TableBuildResult)>> {
+        merge_operator: Option<&dyn MergeOperator>,
+        snapshots: &[SequenceNumber],
+        min_blob_size: Option<usize>,
+    ) -> Result<FlushOutput> {
         let cleanup = |results: &[(u64, TableBuildResult)], current: Option<u64>| {
             for (num, _) in results {
                 let _ = fs::remove_file(db_path.join(format!("{:06}.sst", num)));
@@ -4258,10 +7932,40 @@ impl DB {
 
         let mut results: Vec<(u64, TableBuildResult)> = Vec::new();
         let mut builder: Option<(u64, TableBuilder)> = None;
+        // Created on the first separated value; dropping it unfinished (any
+        // early return below) removes the file.
+        let mut blob_writer: Option<BlobFileWriter> = None;
         let mut pending_cut = false;
         let mut last_uk: Vec<u8> = Vec::new();
+        let mut entries = mem.iter().peekable();
+        let mut pending: VecDeque<(Vec<u8>, Vec<u8>)> = VecDeque::new();
 
-        for (key, value) in mem.iter() {
+        loop {
+            let (key, value) = if let Some(entry) = pending.pop_front() {
+                entry
+            } else if let Some((key, value)) = entries.next() {
+                match merge_operator {
+                    Some(op)
+                        if types::decode_internal_key(&key)
+                            .is_ok_and(|(_, _, vt)| vt == ValueType::Merge) =>
+                    {
+                        let mut run = Self::fold_flush_merge_run(
+                            op,
+                            mem,
+                            snapshots,
+                            key,
+                            value,
+                            &mut entries,
+                        );
+                        let newest = run.remove(0);
+                        pending.extend(run);
+                        newest
+                    }
+                    _ => (key, value),
+                }
+            } else {
+                break;
+            };
             let uk_changed = types::user_key(&key) != last_uk.as_slice();
             if uk_changed {
                 last_uk.clear();
@@ -4297,6 +8001,24 @@ impl DB {
                     }
                 }
             }
+            let (key, value) = match min_blob_size {
+                Some(min) if value.len() >= min => {
+                    match Self::separate_blob_value(
+                        db_path,
+                        next_number,
+                        &mut blob_writer,
+                        key,
+                        value,
+                    ) {
+                        Ok(entry) => entry,
+                        Err(e) => {
+                            cleanup(&results, builder.as_ref().map(|(num, _)| *num));
+                            return Err(e);
+                        }
+                    }
+                }
+                _ => (key, value),
+            };
             let (num, b) = builder.as_mut().unwrap();
             if let Err(e) = b.add(&key, &value).ctx() {
                 let num = *num;
@@ -4316,7 +8038,124 @@ impl DB {
                 }
             }
         }
-        Ok(results)
+        let blob_file = match blob_writer.map(BlobFileWriter::finish).transpose() {
+            Ok(meta) => meta,
+            Err(e) => {
+                cleanup(&results, None);
+                return Err(e).ctx();
+            }
+        };
+        Ok(FlushOutput {
+            tables: results,
+            blob_file,
+        })
+    }
+
+    /// Move a `Value` entry's value into the flush's blob file, returning the
+    /// `BlobIndex` entry that replaces it. Other entry types pass through.
+    fn separate_blob_value(
+        db_path: &Path,
+        next_number: &mut dyn FnMut() -> Result<u64>,
+        blob_writer: &mut Option<BlobFileWriter>,
+        key: Vec<u8>,
+        value: Vec<u8>,
+    ) -> Result<(Vec<u8>, Vec<u8>)> {
+        let (uk, seq, vt) = types::decode_internal_key(&key).ctx()?;
+        if vt != ValueType::Value {
+            return Ok((key, value));
+        }
+        if blob_writer.is_none() {
+            *blob_writer = Some(BlobFileWriter::new(db_path, next_number()?).ctx()?);
+        }
+        let handle = blob_writer.as_mut().unwrap().add(&value).ctx()?;
+        let ikey = InternalKey::new(uk, seq, ValueType::BlobIndex);
+        Ok((ikey.as_bytes().to_vec(), handle.encode()))
+    }
+
+    /// Fold the merge operand `(key, value)` with the older versions of its
+    /// user key that follow it in `entries` and share its snapshot stripe,
+    /// returning the entries to write (newest first).
+    ///
+    /// Mirrors compaction's run folding: a base `Value`/`Deletion`, or an
+    /// entry hidden by a memtable range tombstone, resolves the run into a
+    /// single `Value`; otherwise operands are combined with `partial_merge`
+    /// where possible. A merge failure writes the run unchanged so the flush
+    /// itself never fails on it — the error resurfaces on read.
+    fn fold_flush_merge_run(
+        op: &dyn MergeOperator,
+        mem: &MemTable,
+        snapshots: &[SequenceNumber],
+        key: Vec<u8>,
+        value: Vec<u8>,
+        entries: &mut Peekable<impl Iterator<Item = (Vec<u8>, Vec<u8>)>>,
+    ) -> Vec<(Vec<u8>, Vec<u8>)> {
+        let Ok((uk, seq, _)) = types::decode_internal_key(&key) else {
+            return vec![(key, value)];
+        };
+        let uk = uk.to_vec();
+        let stripe = |s: SequenceNumber| snapshots.partition_point(|&snap| snap < s);
+        let run_stripe = stripe(seq);
+        let mut run = vec![(key, value)];
+        // `Some(base_is_value)` once the run resolved; a consumed base entry
+        // is the last element of `run`.
+        let mut resolved: Option<bool> = None;
+        let mut consumed_base = false;
+        while let Some((next_key, _)) = entries.peek() {
+            let Ok((next_uk, next_seq, next_vt)) = types::decode_internal_key(next_key) else {
+                break;
+            };
+            // An expiring base is left in place: folding it would either
+            // drop its expiry or make the operands expire with it.
+            if next_uk != uk.as_slice()
+                || next_vt == ValueType::RangeDeletion
+                || next_vt == ValueType::ExpiringValue
+                || stripe(next_seq) != run_stripe
+            {
+                break;
+            }
+            if mem.has_range_deletions() && mem.max_covering_tombstone_seq(&uk, seq) > next_seq {
+                resolved = Some(false);
+                break;
+            }
+            run.push(entries.next().unwrap());
+            if next_vt != ValueType::Merge {
+                resolved = Some(next_vt == ValueType::Value);
+                consumed_base = true;
+                break;
+            }
+        }
+
+        let operand_count = run.len() - usize::from(consumed_base);
+        if let Some(base_is_value) = resolved {
+            let operands: Vec<&[u8]> = run[..operand_count]
+                .iter()
+                .map(|(_, v)| v.as_slice())
+                .collect();
+            let base = base_is_value.then(|| run[operand_count].1.as_slice());
+            if let Ok(merged) = op.full_merge_newest_first(&uk, base, &operands) {
+                return vec![(
+                    types::InternalKey::new(&uk, seq, ValueType::Value).into_bytes(),
+                    merged,
+                )];
+            }
+            return run;
+        }
+
+        let mut acc = run[0].1.clone();
+        let mut combined = 0;
+        for (_, older) in &run[1..] {
+            match op.partial_merge(&uk, older, &acc) {
+                Some(v) => {
+                    acc = v;
+                    combined += 1;
+                }
+                None => break,
+            }
+        }
+        let mut out = run.split_off(combined + 1);
+        let (newest_key, _) = run.swap_remove(0);
+        out.insert(0, (newest_key, acc));
+        out
     }
 }
 
@@ -4327,6 +8166,10 @@ impl DB {
     /// Useful for testing WAL recovery without zombie compaction threads.
     pub fn simulate_crash(self) {
         self.closed.store(true, Ordering::Release);
+        for family in self.column_families.read().values() {
+            family.db.closed.store(true, Ordering::Release);
+            family.db.shutdown_background_and_release_resources();
+        }
         self.shutdown_background_and_release_resources();
         mem::forget(self);
     }
@@ -4577,14 +8420,12 @@ mod tests {
         }
     }
 
-    /// Regression: on the explicit `flush()` path, L0 first-block pinning
-    /// (default on) must not read, checksum, and decompress the block
-    /// while holding `inner` (`close()`'s `freeze_and_flush` is a
-    /// documented exception — see `prepare_l0_block_pins`). Park the
-    /// flush thread inside `prepare_first_block_pin` (before it opens the
-    /// file) via `PREPARE_FIRST_BLOCK_PIN_HOOK`, and confirm a direct
-    /// `try_lock` on `inner` from another thread succeeds immediately
-    /// instead of waiting behind it.
+    /// Regression: on the flush path, L0 first-block pinning (default on)
+    /// must not read, checksum, and decompress the block while holding
+    /// `inner`. Park the flush inside `prepare_first_block_pin` (before it
+    /// opens the file) via `PREPARE_FIRST_BLOCK_PIN_HOOK`, and confirm a
+    /// direct `try_lock` on `inner` from another thread succeeds
+    /// immediately instead of waiting behind it.
     #[test]
     fn flush_first_block_pin_read_does_not_hold_inner_lock() {
         use crate::sst::table_reader::PREPARE_FIRST_BLOCK_PIN_HOOK;
@@ -4592,6 +8433,9 @@ mod tests {
         let dir = tempfile::tempdir().unwrap();
         let db = open_test_db(dir.path());
         db.put(b"k1", &[b'x'; 4096]).unwrap();
+        // The hook is thread-local: without flush threads, `flush()` builds
+        // the queued job on this thread.
+        db.stop_flush_threads();
 
         thread::scope(|scope| {
             let db_ref = &db;
@@ -4787,6 +8631,37 @@ mod tests {
         assert!(db.lock_file.lock().is_none());
     }
 
+    #[test]
+    fn test_background_error_is_reported_to_listeners_once() {
+        struct ErrorListener(std::sync::mpsc::Sender<Error>);
+
+        impl crate::listener::EventListener for ErrorListener {
+            fn on_background_error(&self, info: &crate::listener::BackgroundErrorInfo) {
+                let _ = self.0.send(info.error.clone());
+            }
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let (tx, rx) = std::sync::mpsc::channel();
+        let opts = DbOptions {
+            create_if_missing: true,
+            listeners: vec![Arc::new(ErrorListener(tx))],
+            ..Default::default()
+        };
+        let db = DB::open(opts, dir.path()).unwrap();
+        // Raised with `inner` held, as from the WAL sync paths.
+        {
+            let _inner = db.inner.lock();
+            db.set_bg_error("simulated WAL sync failure".to_string());
+        }
+        db.set_bg_error("later failure".to_string());
+
+        let err = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert_eq!(err.kind(), ErrorKind::Background);
+        assert_eq!(err.message(), "simulated WAL sync failure");
+        assert!(rx.recv_timeout(Duration::from_millis(200)).is_err());
+    }
+
     #[test]
     fn test_close_waits_out_concurrent_auto_flush() {
         // Proves close cannot release LOCK while a group-commit leader is still
@@ -5125,6 +9000,40 @@ mod tests {
         assert_eq!(db.get(b"new_key").unwrap(), Some(b"new_val".to_vec()));
     }
 
+    #[test]
+    fn test_wal_record_routes_column_family_entries() {
+        let cf = ColumnFamilyHandle::new(7, "seven");
+        let mut batch = WriteBatch::new();
+        batch.put(b"a", b"default");
+        batch.put_cf(&cf, b"a", b"seven");
+        batch.delete_range_cf(&cf, b"m", b"n");
+        batch.put_cf(&ColumnFamilyHandle::new(9, "gone"), b"z", b"dropped");
+        let record = DB::encode_wal_record(10, &batch);
+
+        let default_mem = MemTable::new();
+        let family_mem = MemTable::new();
+        let mut max_sequence = 0;
+        DB::replay_wal_record(
+            &record,
+            |id| match id {
+                0 => Some(&default_mem),
+                7 => Some(&family_mem),
+                _ => None,
+            },
+            &mut max_sequence,
+        )
+        .unwrap();
+
+        // Skipped entries still consume their sequence numbers.
+        assert_eq!(max_sequence, 13);
+        assert_eq!(default_mem.get(b"a", 13), Some(Some(b"default".to_vec())));
+        assert_eq!(family_mem.get(b"a", 13), Some(Some(b"seven".to_vec())));
+        assert_eq!(default_mem.get(b"z", 13), None);
+        assert_eq!(family_mem.get(b"z", 13), None);
+        assert_eq!(family_mem.get_range_tombstones().len(), 1);
+        assert!(!default_mem.has_range_deletions());
+    }
+
     #[test]
     fn test_wal_recovery() {
         let dir = tempfile::tempdir().unwrap();
@@ -5223,6 +9132,146 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_queued_memtables_serve_reads_and_replay_after_crash() {
+        let dir = tempfile::tempdir().unwrap();
+        let opts = DbOptions {
+            create_if_missing: true,
+            write_buffer_size: 1024,
+            max_immutable_memtables: 16,
+            ..Default::default()
+        };
+        let db = DB::open(opts.clone(), dir.path()).unwrap();
+        // Without flush threads, every frozen memtable stays queued.
+        db.stop_flush_threads();
+        for round in 0..3 {
+            for i in 0..20 {
+                let key = format!("key_{:06}", i);
+                let val = format!("value_{:060}_{}", i, round);
+                db.put(key.as_bytes(), val.as_bytes()).unwrap();
+            }
+        }
+        let queued: usize = db
+            .get_property("num-immutable-mem-table")
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(
+            queued >= 2,
+            "expected several queued memtables, got {queued}"
+        );
+        assert_eq!(db.get_property("num-files-at-level0").unwrap(), "0");
+        let wals = fs::read_dir(dir.path())
+            .unwrap()
+            .filter(|e| {
+                e.as_ref()
+                    .unwrap()
+                    .file_name()
+                    .to_string_lossy()
+                    .ends_with(".wal")
+            })
+            .count();
+        assert!(wals > queued, "each queued memtable keeps its own WAL");
+
+        let check = |db: &DB| {
+            for i in 0..20 {
+                let key = format!("key_{:06}", i);
+                let val = format!("value_{:060}_2", i);
+                assert_eq!(db.get(key.as_bytes()).unwrap(), Some(val.into_bytes()));
+            }
+        };
+        check(&db);
+        db.simulate_crash();
+
+        let db = DB::open(opts, dir.path()).unwrap();
+        check(&db);
+        db.flush().unwrap();
+        check(&db);
+    }
+
+    #[test]
+    fn test_full_flush_queue_stalls_writes() {
+        let dir = tempfile::tempdir().unwrap();
+        let opts = DbOptions {
+            create_if_missing: true,
+            write_buffer_size: 1024,
+            max_immutable_memtables: 2,
+            ..Default::default()
+        };
+        let db = DB::open(opts, dir.path()).unwrap();
+        db.stop_flush_threads();
+        let value = [b'v'; 600];
+        for i in 0..4u32 {
+            db.put(&i.to_be_bytes(), &value).unwrap();
+        }
+        assert_eq!(db.get_property("num-immutable-mem-table").unwrap(), "2");
+
+        let no_slowdown = WriteOptions {
+            no_slowdown: true,
+            ..Default::default()
+        };
+        assert!(db.put_with_options(&no_slowdown, b"late", b"v").is_err());
+
+        thread::scope(|scope| {
+            let stalled = scope.spawn(|| db.put(b"late", b"v"));
+            thread::sleep(Duration::from_millis(50));
+            assert!(
+                !stalled.is_finished(),
+                "write should wait for the flush queue"
+            );
+            db.flush().unwrap();
+            stalled.join().unwrap().unwrap();
+        });
+        assert_eq!(db.get(b"late").unwrap(), Some(b"v".to_vec()));
+        for i in 0..4u32 {
+            assert_eq!(db.get(&i.to_be_bytes()).unwrap(), Some(value.to_vec()));
+        }
+    }
+
+    #[test]
+    fn test_ingest_flushes_overlapping_queued_memtable() {
+        let dir = tempfile::tempdir().unwrap();
+        let opts = DbOptions {
+            create_if_missing: true,
+            write_buffer_size: 1024,
+            max_immutable_memtables: 4,
+            ..Default::default()
+        };
+        let db = DB::open(opts.clone(), dir.path()).unwrap();
+        db.stop_flush_threads();
+        db.put(b"k", &[b'o'; 600]).unwrap();
+        db.put(b"x", &[b'v'; 600]).unwrap();
+        db.put(b"y", b"v").unwrap();
+        assert_eq!(db.get_property("num-immutable-mem-table").unwrap(), "1");
+        assert!(
+            !db.get_super_version()
+                .active_memtable
+                .overlaps_user_range(b"k", b"k")
+        );
+
+        let sst = dir.path().join("ingest.sst");
+        let mut writer = crate::SstFileWriter::create(&opts, &sst).unwrap();
+        writer.put(b"k", b"new").unwrap();
+        writer.finish().unwrap();
+
+        let fail = crate::IngestExternalFileOptions {
+            fail_if_memtable_overlap: true,
+            ..Default::default()
+        };
+        let err = db.ingest_external_file(&[&sst], &fail).unwrap_err();
+        assert_eq!(err.kind(), crate::ErrorKind::InvalidArgument);
+
+        db.ingest_external_file(&[&sst], &Default::default())
+            .unwrap();
+        assert_eq!(db.get_property("num-immutable-mem-table").unwrap(), "0");
+        assert_eq!(db.get(b"k").unwrap(), Some(b"new".to_vec()));
+        db.close().unwrap();
+        drop(db);
+
+        let db = DB::open(opts, dir.path()).unwrap();
+        assert_eq!(db.get(b"k").unwrap(), Some(b"new".to_vec()));
+    }
+
     #[test]
     fn test_db_iterator() {
         let dir = tempfile::tempdir().unwrap();
@@ -5352,4 +9401,167 @@ mod tests {
         db.put(b"test", b"value").unwrap();
         assert_eq!(db.get(b"test").unwrap(), Some(b"value".to_vec()));
     }
+
+    /// Little-endian u64 counter: operands are added to the base value.
+    struct AddOperator;
+
+    impl MergeOperator for AddOperator {
+        fn name(&self) -> &str {
+            "add"
+        }
+
+        fn full_merge(
+            &self,
+            _key: &[u8],
+            existing: Option<&[u8]>,
+            operands: &[&[u8]],
+        ) -> Option<Vec<u8>> {
+            let mut sum = match existing {
+                Some(v) => u64::from_le_bytes(v.try_into().ok()?),
+                None => 0,
+            };
+            for op in operands {
+                sum += u64::from_le_bytes((*op).try_into().ok()?);
+            }
+            Some(sum.to_le_bytes().to_vec())
+        }
+
+        fn partial_merge(&self, key: &[u8], left: &[u8], right: &[u8]) -> Option<Vec<u8>> {
+            self.full_merge(key, Some(left), &[right])
+        }
+    }
+
+    fn open_merge_db(dir: &Path) -> DB {
+        let opts = DbOptions {
+            create_if_missing: true,
+            merge_operator: Some(Arc::new(AddOperator)),
+            ..Default::default()
+        };
+        DB::open(opts, dir).unwrap()
+    }
+
+    fn counter(db: &DB, key: &[u8]) -> Option<u64> {
+        db.get(key)
+            .unwrap()
+            .map(|v| u64::from_le_bytes(v.try_into().unwrap()))
+    }
+
+    #[test]
+    fn test_merge_requires_operator() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = open_test_db(dir.path());
+        let err = db.merge(b"k", &1u64.to_le_bytes()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidArgument);
+    }
+
+    #[test]
+    fn test_merge_get_across_memtable_and_sst() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = open_merge_db(dir.path());
+
+        db.merge(b"c", &1u64.to_le_bytes()).unwrap();
+        db.merge(b"c", &2u64.to_le_bytes()).unwrap();
+        assert_eq!(counter(&db, b"c"), Some(3));
+
+        // Base value in an SST, operands split between SST and memtable.
+        db.put(b"c", &10u64.to_le_bytes()).unwrap();
+        db.merge(b"c", &5u64.to_le_bytes()).unwrap();
+        db.flush().unwrap();
+        db.merge(b"c", &7u64.to_le_bytes()).unwrap();
+        assert_eq!(counter(&db, b"c"), Some(22));
+
+        let snap = db.snapshot();
+        db.merge(b"c", &100u64.to_le_bytes()).unwrap();
+        db.flush().unwrap();
+        db.compact().unwrap();
+        assert_eq!(counter(&db, b"c"), Some(122));
+        let at_snap = db.get_with_options(&snap.read_options(), b"c").unwrap();
+        assert_eq!(at_snap, Some(22u64.to_le_bytes().to_vec()));
+        drop(snap);
+
+        // A deletion resets the counter.
+        db.delete(b"c").unwrap();
+        db.merge(b"c", &4u64.to_le_bytes()).unwrap();
+        assert_eq!(counter(&db, b"c"), Some(4));
+        db.flush().unwrap();
+        db.compact().unwrap();
+        assert_eq!(counter(&db, b"c"), Some(4));
+    }
+
+    #[test]
+    fn test_merge_survives_wal_recovery() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let db = open_merge_db(dir.path());
+            db.put(b"k", &1u64.to_le_bytes()).unwrap();
+            db.merge(b"k", &2u64.to_le_bytes()).unwrap();
+            db.simulate_crash();
+        }
+        let db = open_merge_db(dir.path());
+        assert_eq!(counter(&db, b"k"), Some(3));
+    }
+
+    #[test]
+    fn test_merge_range_deletion_cuts_operand_chain() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = open_merge_db(dir.path());
+        db.merge(b"k", &1u64.to_le_bytes()).unwrap();
+        db.delete_range(b"a", b"z").unwrap();
+        db.merge(b"k", &2u64.to_le_bytes()).unwrap();
+        assert_eq!(counter(&db, b"k"), Some(2));
+
+        let mut iter = db.iter().unwrap();
+        assert_eq!(iter.next().unwrap().1, 2u64.to_le_bytes().to_vec());
+        db.flush().unwrap();
+        db.compact().unwrap();
+        assert_eq!(counter(&db, b"k"), Some(2));
+    }
+
+    #[test]
+    fn test_merge_iterator_forward_and_backward() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = open_merge_db(dir.path());
+        db.put(b"a", &1u64.to_le_bytes()).unwrap();
+        db.merge(b"b", &2u64.to_le_bytes()).unwrap();
+        db.put(b"c", &3u64.to_le_bytes()).unwrap();
+        db.flush().unwrap();
+        db.merge(b"a", &10u64.to_le_bytes()).unwrap();
+        db.merge(b"b", &20u64.to_le_bytes()).unwrap();
+
+        let expected: Vec<(Vec<u8>, Vec<u8>)> = vec![
+            (b"a".to_vec(), 11u64.to_le_bytes().to_vec()),
+            (b"b".to_vec(), 22u64.to_le_bytes().to_vec()),
+            (b"c".to_vec(), 3u64.to_le_bytes().to_vec()),
+        ];
+        let forward: Vec<_> = db.iter().unwrap().collect();
+        assert_eq!(forward, expected);
+
+        let mut iter = db.iter().unwrap();
+        iter.seek_to_last();
+        let mut backward = Vec::new();
+        while iter.valid() {
+            let k = iter.key().unwrap().to_vec();
+            let v = iter.value().unwrap().to_vec();
+            backward.push((k, v));
+            iter.prev();
+        }
+        backward.reverse();
+        assert_eq!(backward, expected);
+        assert!(iter.error().is_none());
+    }
+
+    #[test]
+    fn test_merge_operands_without_operator_error_on_read() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let db = open_merge_db(dir.path());
+            db.merge(b"k", &1u64.to_le_bytes()).unwrap();
+            db.close().unwrap();
+        }
+        let db = open_test_db(dir.path());
+        assert_eq!(db.get(b"k").unwrap_err().kind(), ErrorKind::InvalidArgument);
+        let mut iter = db.iter().unwrap();
+        assert!(iter.next().is_none());
+        assert!(iter.error().is_some());
+    }
 }
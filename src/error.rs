@@ -48,6 +48,15 @@ pub enum ErrorKind {
     /// A background task (flush/compaction) failed earlier; writes are
     /// rejected until the database is reopened.
     Background,
+    /// A transaction could not commit because a key it depends on was
+    /// written by someone else after the transaction started. Retrying the
+    /// transaction from scratch may succeed.
+    Busy,
+    /// An operation gave up waiting (e.g. for a transaction key lock).
+    TimedOut,
+    /// The requested data is no longer kept (e.g. WAL updates older than
+    /// every live and archived WAL).
+    NotFound,
 }
 
 impl ErrorKind {
@@ -60,6 +69,9 @@ impl ErrorKind {
             Self::DbClosed => "DB is closed",
             Self::ReadOnly => "DB is read-only",
             Self::Background => "Background error",
+            Self::Busy => "Resource busy",
+            Self::TimedOut => "Operation timed out",
+            Self::NotFound => "Not found",
         }
     }
 }
@@ -130,6 +142,24 @@ impl Error {
         Self::new(ErrorKind::Background, msg.into())
     }
 
+    /// Create an [`ErrorKind::Busy`] error.
+    #[track_caller]
+    pub fn busy(msg: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Busy, msg.into())
+    }
+
+    /// Create an [`ErrorKind::TimedOut`] error.
+    #[track_caller]
+    pub fn timed_out(msg: impl Into<String>) -> Self {
+        Self::new(ErrorKind::TimedOut, msg.into())
+    }
+
+    /// Create an [`ErrorKind::NotFound`] error.
+    #[track_caller]
+    pub fn not_found(msg: impl Into<String>) -> Self {
+        Self::new(ErrorKind::NotFound, msg.into())
+    }
+
     /// Create an [`ErrorKind::DbClosed`] error.
     #[track_caller]
     pub fn db_closed() -> Self {
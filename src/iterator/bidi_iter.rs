@@ -273,7 +273,7 @@ impl DoubleEndedIterator for BidiIterator {
                 // re-yield a key already returned by next(). Mirrors the frontier
                 // check in the LazyBackStarted branch.
                 if let Some(fk) = last_fwd_key.as_deref()
-                    && k.as_slice() <= fk
+                    && db_iter.comparator().key_le(&k, fk)
                 {
                     self.inner = BidiInner::LazyBackStarted(LazyBidiState {
                         db_iter,
@@ -329,7 +329,7 @@ impl DoubleEndedIterator for BidiIterator {
                     return None;
                 };
                 if let Some(fwd_key) = state.last_fwd_key.as_deref()
-                    && k.as_slice() <= fwd_key
+                    && state.db_iter.comparator().key_le(&k, fwd_key)
                 {
                     self.inner = BidiInner::LazyBackStarted(state);
                     return None;
@@ -353,7 +353,7 @@ impl DoubleEndedIterator for BidiIterator {
                     // Keep last_back_key unchanged — it's the correct upper bound
                     // for a subsequent next() materialization.
                     if let Some(fk) = state.last_fwd_key.as_deref()
-                        && k.as_slice() <= fk
+                        && state.db_iter.comparator().key_le(&k, fk)
                     {
                         return None;
                     }
@@ -2,15 +2,21 @@
 //! sequence numbers, and skips tombstones.
 
 use std::cmp::Ordering;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+use crate::error::Error;
 use crate::iterator::merge::{IterSource, MergingIterator};
 use crate::iterator::range_del::FragmentedRangeTombstoneList;
+use crate::manifest::version::Version;
+use crate::options::{MergeOperator, require_merge_operator};
+use crate::stats::{DbStats, HistogramKind};
 use crate::types::{
-    LazyValue, MAX_SEQUENCE_NUMBER, SequenceNumber, ValueType, compare_internal_key,
-    decode_internal_key,
+    EXPIRY_SUFFIX_LEN, InternalKeyComparator, LazyValue, MAX_SEQUENCE_NUMBER, SequenceNumber,
+    ValueType, decode_internal_key, expiring_value_len,
 };
 
-type IKeyCompareFn = fn(&[u8], &[u8]) -> Ordering;
+type IKeyCompareFn = Box<dyn Fn(&[u8], &[u8]) -> Ordering + Send + Sync>;
 
 /// A database-level iterator that presents a clean view of key-value pairs.
 ///
@@ -70,10 +76,34 @@ pub struct DBIterator {
     /// unknown type). Surfaced via [`Self::error`] so callers do not treat it
     /// as normal exhaustion.
     key_decode_error: Option<String>,
-}
-
-fn ikey_compare(a: &[u8], b: &[u8]) -> Ordering {
-    compare_internal_key(a, b)
+    /// Operator used to fold `Merge` operands into the value yielded for a key.
+    merge_operator: Option<Arc<dyn MergeOperator>>,
+    /// A merge operand could not be folded (operator missing or failed).
+    /// Surfaced via [`Self::error`] like `key_decode_error`.
+    merge_error: Option<String>,
+    /// Wall-clock time (ms since the epoch) against which `ExpiringValue`
+    /// entries are judged; fixed when the iterator is created.
+    now_millis: u64,
+    /// Key order of every source; also used for the bound checks.
+    icmp: InternalKeyComparator,
+    /// Read timestamp: versions with a newer user-defined timestamp are
+    /// invisible. Only set when the comparator carries timestamps; keys,
+    /// bounds and seek targets are then plain keys without one.
+    read_ts: Option<Vec<u8>>,
+    /// Version whose blob files `BlobIndex` entries are resolved against.
+    /// Only set when the version has blob files.
+    blob_source: Option<Arc<Version>>,
+    /// A blob handle was invalid or its value could not be read. Surfaced
+    /// via [`Self::error`] like `key_decode_error`.
+    blob_error: Option<String>,
+    /// Where seek/next latency and skipped tombstones are recorded. Only
+    /// set when statistics are enabled.
+    stats: Option<Arc<DbStats>>,
+    /// Time the merger spent in the last lazy seek. The seek is recorded
+    /// once `ensure_current` has positioned on its first visible entry.
+    seek_time: Option<Duration>,
+    /// Deleted keys stepped over since the last step was recorded.
+    tombstones_skipped: u64,
 }
 
 impl DBIterator {
@@ -81,9 +111,23 @@ impl DBIterator {
     #[cfg(test)]
     pub(crate) fn new(sources: Vec<Vec<(Vec<u8>, Vec<u8>)>>, sequence: SequenceNumber) -> Self {
         let iter_sources: Vec<IterSource> = sources.into_iter().map(IterSource::new).collect();
+        Self::from_sources(iter_sources, sequence)
+    }
+
+    /// Build a bytewise-ordered DB iterator from pre-built IterSource objects.
+    #[cfg(test)]
+    pub(crate) fn from_sources(sources: Vec<IterSource>, sequence: SequenceNumber) -> Self {
+        Self::with_comparator(sources, sequence, InternalKeyComparator::default())
+    }
 
-        let merger =
-            MergingIterator::new(iter_sources, ikey_compare as fn(&[u8], &[u8]) -> Ordering);
+    /// Build a DB iterator from pre-built IterSource objects (supports
+    /// streaming) whose entries are ordered by `icmp`.
+    pub(crate) fn with_comparator(
+        sources: Vec<IterSource>,
+        sequence: SequenceNumber,
+        icmp: InternalKeyComparator,
+    ) -> Self {
+        let merger = MergingIterator::new(sources, Box::new(icmp.as_fn()) as IKeyCompareFn);
 
         Self {
             merger,
@@ -103,31 +147,23 @@ impl DBIterator {
             last_seek_key: None,
             clean_read: true,
             key_decode_error: None,
+            merge_operator: None,
+            merge_error: None,
+            now_millis: 0,
+            icmp,
+            read_ts: None,
+            blob_source: None,
+            blob_error: None,
+            stats: None,
+            seek_time: None,
+            tombstones_skipped: 0,
         }
     }
 
-    /// Build a DB iterator from pre-built IterSource objects (supports streaming).
-    pub(crate) fn from_sources(sources: Vec<IterSource>, sequence: SequenceNumber) -> Self {
-        let merger = MergingIterator::new(sources, ikey_compare as fn(&[u8], &[u8]) -> Ordering);
-
-        Self {
-            merger,
-            sequence,
-            batch_seq_floor: None,
-            last_user_key: Vec::new(),
-            has_last_key: false,
-            current: None,
-            needs_advance: true,
-            range_tombstones: FragmentedRangeTombstoneList::empty(),
-            prefix: None,
-            iterate_upper_bound: None,
-            iterate_lower_bound: None,
-            prev_overshoot: None,
-            backward_positioned: false,
-            skip_point: None,
-            last_seek_key: None,
-            clean_read: true,
-            key_decode_error: None,
+    /// Record seek and step latency and skipped tombstones in `stats`.
+    pub(crate) fn set_stats(&mut self, stats: Arc<DbStats>) {
+        if stats.enabled() {
+            self.stats = Some(stats);
         }
     }
 
@@ -137,28 +173,16 @@ impl DBIterator {
         sources: Vec<IterSource>,
         sequence: SequenceNumber,
         prefix: Vec<u8>,
+        icmp: InternalKeyComparator,
     ) -> Self {
-        let merger = MergingIterator::new(sources, ikey_compare as fn(&[u8], &[u8]) -> Ordering);
+        let mut iter = Self::with_comparator(sources, sequence, icmp);
+        iter.prefix = Some(prefix);
+        iter
+    }
 
-        Self {
-            merger,
-            sequence,
-            batch_seq_floor: None,
-            last_user_key: Vec::new(),
-            has_last_key: false,
-            current: None,
-            needs_advance: true,
-            range_tombstones: FragmentedRangeTombstoneList::empty(),
-            prefix: Some(prefix),
-            iterate_upper_bound: None,
-            iterate_lower_bound: None,
-            prev_overshoot: None,
-            backward_positioned: false,
-            skip_point: None,
-            last_seek_key: None,
-            clean_read: true,
-            key_decode_error: None,
-        }
+    /// The order the iterator walks user keys in.
+    pub(crate) fn comparator(&self) -> &InternalKeyComparator {
+        &self.icmp
     }
 
     /// Set pre-collected range tombstones. Called by the DB layer after
@@ -179,7 +203,8 @@ impl DBIterator {
         &mut self,
         tombstones: Vec<(Vec<u8>, Vec<u8>, SequenceNumber, usize)>,
     ) {
-        self.range_tombstones = FragmentedRangeTombstoneList::new_with_levels(tombstones);
+        self.range_tombstones =
+            FragmentedRangeTombstoneList::with_comparator(tombstones, &self.icmp);
         self.clean_read = false;
     }
 
@@ -217,12 +242,12 @@ impl DBIterator {
     fn effective_forward_target(&self, target: &[u8]) -> Vec<u8> {
         let mut effective = target;
         if let Some(lb) = self.iterate_lower_bound.as_deref()
-            && effective < lb
+            && self.icmp.key_lt(effective, lb)
         {
             effective = lb;
         }
         if let Some(prefix) = self.prefix.as_deref()
-            && effective < prefix
+            && self.icmp.key_lt(effective, prefix)
         {
             effective = prefix;
         }
@@ -233,8 +258,12 @@ impl DBIterator {
     /// Iteration stops when user key >= this bound.
     /// Models RocksDB's `ReadOptions::iterate_upper_bound`.
     pub(crate) fn set_upper_bound(&mut self, bound: Vec<u8>) {
+        let lower = self
+            .iterate_lower_bound
+            .as_deref()
+            .map(|b| self.icmp.first_version(b));
         self.merger
-            .set_bounds(self.iterate_lower_bound.as_deref(), Some(&bound));
+            .set_bounds(lower.as_deref(), Some(&self.icmp.first_version(&bound)));
         self.iterate_upper_bound = Some(bound);
         // Invalidate any buffered entry — it may now be beyond the new bound.
         self.current = None;
@@ -244,7 +273,12 @@ impl DBIterator {
 
     /// Set both lower (inclusive) and upper (exclusive) bounds on user keys.
     pub(crate) fn set_bounds(&mut self, lower: Option<Vec<u8>>, upper: Option<Vec<u8>>) {
-        self.merger.set_bounds(lower.as_deref(), upper.as_deref());
+        // Sources compare full user keys: the newest possible version of a
+        // plain-key bound sorts before every stored version of that key.
+        let source_lower = lower.as_deref().map(|b| self.icmp.first_version(b));
+        let source_upper = upper.as_deref().map(|b| self.icmp.first_version(b));
+        self.merger
+            .set_bounds(source_lower.as_deref(), source_upper.as_deref());
         self.iterate_lower_bound = lower;
         self.iterate_upper_bound = upper;
         self.current = None;
@@ -259,9 +293,218 @@ impl DBIterator {
     pub fn error(&self) -> Option<String> {
         self.key_decode_error
             .clone()
+            .or_else(|| self.merge_error.clone())
+            .or_else(|| self.blob_error.clone())
             .or_else(|| self.merger.error())
     }
 
+    /// Set the time at which `ExpiringValue` entries are judged expired.
+    pub(crate) fn set_now_millis(&mut self, now_millis: u64) {
+        self.now_millis = now_millis;
+    }
+
+    /// Whether the `ExpiringValue` stored as `value` has expired, recording
+    /// a malformed value in `key_decode_error` (`None`).
+    fn is_expired(
+        key_decode_error: &mut Option<String>,
+        value: &[u8],
+        now_millis: u64,
+    ) -> Option<bool> {
+        match expiring_value_len(value, now_millis) {
+            Ok(len) => Some(len.is_none()),
+            Err(e) => {
+                *key_decode_error = Some(e.to_string());
+                None
+            }
+        }
+    }
+
+    /// Resolve `BlobIndex` entries against `version`'s blob files.
+    pub(crate) fn set_blob_source(&mut self, version: Arc<Version>) {
+        if !version.blob_files.is_empty() {
+            self.blob_source = Some(version);
+        }
+    }
+
+    /// Turn a `BlobIndex` entry's handle into its (lazily read) value,
+    /// recording an invalid handle in `blob_error` (`None`).
+    fn resolve_blob(&mut self, handle: &LazyValue) -> Option<LazyValue> {
+        let resolved = match self.blob_source {
+            Some(ref version) => version.blob_value(handle.as_slice()),
+            None => Err(Error::corruption("blob index entry without blob files")),
+        };
+        match resolved {
+            Ok(value) => Some(value),
+            Err(e) => {
+                self.blob_error = Some(e.to_string());
+                None
+            }
+        }
+    }
+
+    /// Read a blob value that is about to be folded or returned, recording
+    /// a read failure in `blob_error` (`None`).
+    fn load_value<'a>(blob_error: &mut Option<String>, value: &'a LazyValue) -> Option<&'a [u8]> {
+        match value.load() {
+            Ok(v) => Some(v),
+            Err(e) => {
+                *blob_error = Some(e.to_string());
+                None
+            }
+        }
+    }
+
+    /// Normalize an entry taken in backward order: a live `ExpiringValue`
+    /// becomes the `Value` it wraps and an expired one a `Deletion`; a
+    /// `BlobIndex` becomes the `Value` it points to.
+    fn resolve_expiry(&mut self, vt: ValueType, value: &mut LazyValue) -> Option<ValueType> {
+        if vt == ValueType::BlobIndex {
+            *value = self.resolve_blob(value)?;
+            return Some(ValueType::Value);
+        }
+        if vt != ValueType::ExpiringValue {
+            return Some(vt);
+        }
+        if Self::is_expired(
+            &mut self.key_decode_error,
+            value.as_slice(),
+            self.now_millis,
+        )? {
+            return Some(ValueType::Deletion);
+        }
+        value.truncate(value.len() - EXPIRY_SUFFIX_LEN);
+        Some(ValueType::Value)
+    }
+
+    /// Hide versions whose user-defined timestamp is newer than `ts`.
+    pub(crate) fn set_read_timestamp(&mut self, ts: Vec<u8>) {
+        self.read_ts = Some(ts);
+    }
+
+    /// Whether the version `user_key` (carrying its timestamp) is at or
+    /// below the read timestamp.
+    #[inline]
+    fn timestamp_visible(
+        icmp: &InternalKeyComparator,
+        read_ts: Option<&[u8]>,
+        user_key: &[u8],
+    ) -> bool {
+        read_ts.is_none_or(|ts| icmp.split_timestamp(user_key).1 <= ts)
+    }
+
+    /// Set the operator used to fold `Merge` operands. Without one, reaching
+    /// a merge operand ends iteration with an error.
+    pub(crate) fn set_merge_operator(&mut self, op: Option<Arc<dyn MergeOperator>>) {
+        self.merge_operator = op;
+    }
+
+    /// Whether a range tombstone hides the entry `(user_key, seq)` that came
+    /// from LSM level `level` (`usize::MAX` when unknown).
+    fn is_range_covered(&self, user_key: &[u8], seq: SequenceNumber, level: usize) -> bool {
+        if self.range_tombstones.is_empty() {
+            return false;
+        }
+        let level_filter = if level != usize::MAX {
+            Some(level)
+        } else {
+            None
+        };
+        self.range_tombstones.max_covering_tombstone_seq_for_level(
+            user_key,
+            self.tombstone_snapshot(),
+            level_filter,
+        ) > seq
+    }
+
+    /// Fold `operands` (newest first) onto `base` with the merge operator,
+    /// recording any failure in `merge_error`.
+    fn fold_merge(
+        &mut self,
+        user_key: &[u8],
+        base: Option<&[u8]>,
+        operands: &[&[u8]],
+    ) -> Option<LazyValue> {
+        let merged = require_merge_operator(self.merge_operator.as_ref())
+            .and_then(|op| op.full_merge_newest_first(user_key, base, operands));
+        match merged {
+            Ok(v) => Some(LazyValue::Inline(v)),
+            Err(e) => {
+                self.merge_error = Some(e.to_string());
+                None
+            }
+        }
+    }
+
+    /// Resolve a key whose newest visible entry is the merge operand just
+    /// taken from the merger: consume the key's older versions up to the
+    /// first base value, deletion, or range-tombstone-covered entry and fold
+    /// the collected operands. Returns `None` on a decode or merge failure.
+    fn resolve_merge_forward(
+        &mut self,
+        user_key: &[u8],
+        newest_operand: LazyValue,
+    ) -> Option<LazyValue> {
+        let snapshot = self.sequence;
+        let batch_floor = self.batch_seq_floor;
+        let now_millis = self.now_millis;
+        let mut operands = vec![newest_operand.into_vec()];
+        let mut base: Option<Vec<u8>> = None;
+        loop {
+            let (seq, vt, ts_visible) = {
+                let Some((ikey_ref, _)) = self.merger.peek_entry() else {
+                    break;
+                };
+                match decode_internal_key(ikey_ref) {
+                    Err(e) => {
+                        self.key_decode_error = Some(e.to_string());
+                        return None;
+                    }
+                    Ok((uk, seq, vt)) => {
+                        if self.icmp.strip_timestamp(uk) != user_key {
+                            break;
+                        }
+                        let ts_visible =
+                            Self::timestamp_visible(&self.icmp, self.read_ts.as_deref(), uk);
+                        (seq, vt, ts_visible)
+                    }
+                }
+            };
+            if vt == ValueType::RangeDeletion
+                || !ts_visible
+                || !(seq <= snapshot || batch_floor.is_some_and(|floor| seq >= floor))
+            {
+                self.merger.advance_entry();
+                continue;
+            }
+            let level = self.merger.peek_source_level();
+            if vt == ValueType::Deletion || self.is_range_covered(user_key, seq, level) {
+                break;
+            }
+            let (_, value) = self.merger.peek_entry()?;
+            let mut value = value.to_vec();
+            self.merger.advance_entry();
+            if vt == ValueType::Value {
+                base = Some(value);
+                break;
+            }
+            if vt == ValueType::BlobIndex {
+                let blob = self.resolve_blob(&LazyValue::Inline(value))?;
+                base = Some(Self::load_value(&mut self.blob_error, &blob)?.to_vec());
+                break;
+            }
+            if vt == ValueType::ExpiringValue {
+                if !Self::is_expired(&mut self.key_decode_error, &value, now_millis)? {
+                    value.truncate(value.len() - EXPIRY_SUFFIX_LEN);
+                    base = Some(value);
+                }
+                break;
+            }
+            operands.push(value);
+        }
+        let operand_refs: Vec<&[u8]> = operands.iter().map(Vec::as_slice).collect();
+        self.fold_merge(user_key, base.as_deref(), &operand_refs)
+    }
+
     /// Set a skip-point callback. During iteration, any user key for which
     /// the callback returns `true` is silently skipped.
     pub(crate) fn set_skip_point(&mut self, f: crate::options::SkipPointFn) {
@@ -271,8 +514,32 @@ impl DBIterator {
 
     /// Jump to the first key of the next prefix, skipping all remaining keys
     /// under the current prefix in O(log N) instead of O(keys_in_prefix).
-    /// `prefix_len` is the number of bytes that define a prefix.
+    /// `prefix_len` is the number of bytes that define a prefix. Under a
+    /// comparator that is not bytewise there is no successor to seek to, so
+    /// the keys sharing the current prefix are stepped over one by one.
     pub fn next_prefix(&mut self, prefix_len: usize) {
+        if !self.icmp.is_bytewise() {
+            if let Some(prefix) = self
+                .current
+                .as_ref()
+                .and_then(|(key, _)| key.get(..prefix_len))
+                .map(<[u8]>::to_vec)
+            {
+                loop {
+                    self.advance();
+                    if !self.ensure_current()
+                        || !self
+                            .current
+                            .as_ref()
+                            .is_some_and(|(key, _)| key.starts_with(&prefix))
+                    {
+                        return;
+                    }
+                }
+            }
+            self.needs_advance = true;
+            return;
+        }
         // Compute the successor prefix: increment the first `prefix_len` bytes.
         if let Some((ref key, _)) = self.current {
             // `current` already holds a user key (internal key was truncated
@@ -289,8 +556,11 @@ impl DBIterator {
                         succ.truncate(i + 1);
                         // Use seek_opt with try_next=true since succ > current
                         use crate::types::InternalKey;
-                        let seek_key =
-                            InternalKey::new(&succ, MAX_SEQUENCE_NUMBER, ValueType::Value);
+                        let seek_key = InternalKey::new(
+                            &self.icmp.first_version(&succ),
+                            MAX_SEQUENCE_NUMBER,
+                            ValueType::Value,
+                        );
                         self.merger.seek_opt(seek_key.as_bytes(), true);
                         self.has_last_key = false;
                         self.needs_advance = true;
@@ -322,7 +592,11 @@ impl DBIterator {
             self.prev_overshoot = None;
             if self.has_last_key {
                 use crate::types::InternalKey;
-                let seek_key = InternalKey::new(&self.last_user_key, 0, ValueType::Deletion);
+                let seek_key = InternalKey::new(
+                    &self.icmp.last_version(&self.last_user_key),
+                    0,
+                    ValueType::Deletion,
+                );
                 self.merger.seek(seek_key.as_bytes());
             }
         }
@@ -332,35 +606,47 @@ impl DBIterator {
             Skip,
             Take {
                 uk_len: usize,
+                merge: bool,
+                expiring: bool,
+                blob: bool,
             },
             /// Deferred tombstone check — need peek_source_level() after
             /// the peek_entry() borrow ends so the heap is initialized.
             TakeCheckTombstone {
                 uk_len: usize,
                 seq: SequenceNumber,
+                merge: bool,
+                expiring: bool,
+                blob: bool,
             },
         }
         // Copy visibility parameters out of self: peek_entry() holds a
         // mutable borrow of self.merger across the checks below.
         let snapshot = self.sequence;
         let batch_floor = self.batch_seq_floor;
+        let now_millis = self.now_millis;
         loop {
             let action = {
-                let (ikey_ref, _value_ref) = self.merger.peek_entry()?;
+                let (ikey_ref, value_ref) = self.merger.peek_entry()?;
                 match decode_internal_key(ikey_ref) {
                     Err(e) => {
                         self.key_decode_error = Some(e.to_string());
                         return None;
                     }
-                    Ok((_, seq, vt)) => {
-                        if !(seq <= snapshot || batch_floor.is_some_and(|floor| seq >= floor)) {
+                    Ok((uk, seq, vt)) => {
+                        if !(seq <= snapshot || batch_floor.is_some_and(|floor| seq >= floor))
+                            || !Self::timestamp_visible(&self.icmp, self.read_ts.as_deref(), uk)
+                        {
                             Action::Skip
                         } else {
-                            let uk_len = ikey_ref.len() - 8;
+                            let uk_len = self
+                                .icmp
+                                .strip_timestamp(&ikey_ref[..ikey_ref.len() - 8])
+                                .len();
 
                             // Lower bound check
                             if let Some(ref lb) = self.iterate_lower_bound
-                                && ikey_ref[..uk_len] < **lb
+                                && self.icmp.key_lt(&ikey_ref[..uk_len], lb)
                             {
                                 Action::Skip
                             }
@@ -368,7 +654,7 @@ impl DBIterator {
                             else if let Some(ref pfx) = self.prefix
                                 && !ikey_ref[..uk_len].starts_with(pfx)
                             {
-                                if &ikey_ref[..uk_len] < pfx.as_slice() {
+                                if self.icmp.key_lt(&ikey_ref[..uk_len], pfx) {
                                     Action::Skip
                                 } else {
                                     return None;
@@ -376,7 +662,7 @@ impl DBIterator {
                             }
                             // Upper bound check
                             else if let Some(ref ub) = self.iterate_upper_bound
-                                && ikey_ref[..uk_len] >= **ub
+                                && !self.icmp.key_lt(&ikey_ref[..uk_len], ub)
                             {
                                 return None;
                             } else if vt == ValueType::RangeDeletion {
@@ -397,14 +683,37 @@ impl DBIterator {
                                 self.last_user_key.extend_from_slice(&ikey_ref[..uk_len]);
                                 self.has_last_key = true;
 
+                                let merge = vt == ValueType::Merge;
+                                let expiring = vt == ValueType::ExpiringValue;
+                                let blob = vt == ValueType::BlobIndex;
                                 if vt == ValueType::Deletion {
+                                    self.tombstones_skipped += 1;
+                                    Action::Skip
+                                } else if expiring
+                                    && Self::is_expired(
+                                        &mut self.key_decode_error,
+                                        value_ref,
+                                        now_millis,
+                                    )?
+                                {
                                     Action::Skip
                                 } else if self.range_tombstones.is_empty() {
-                                    Action::Take { uk_len }
+                                    Action::Take {
+                                        uk_len,
+                                        merge,
+                                        expiring,
+                                        blob,
+                                    }
                                 } else {
                                     // Defer tombstone check until after peek_entry
                                     // borrow ends so we can call peek_source_level().
-                                    Action::TakeCheckTombstone { uk_len, seq }
+                                    Action::TakeCheckTombstone {
+                                        uk_len,
+                                        seq,
+                                        merge,
+                                        expiring,
+                                        blob,
+                                    }
                                 }
                             }
                         }
@@ -417,41 +726,63 @@ impl DBIterator {
                     self.merger.advance_entry();
                     continue;
                 }
-                Action::TakeCheckTombstone { uk_len, seq } => {
+                Action::TakeCheckTombstone {
+                    uk_len,
+                    seq,
+                    merge,
+                    expiring,
+                    blob,
+                } => {
                     // Now that peek_entry() borrow is released and the heap is
                     // initialized, peek_source_level() returns the true level.
                     let source_level = self.merger.peek_source_level();
-                    let level_filter = if source_level != usize::MAX {
-                        Some(source_level)
-                    } else {
-                        None
-                    };
-                    let covered = self.range_tombstones.max_covering_tombstone_seq_for_level(
-                        &self.last_user_key,
-                        self.tombstone_snapshot(),
-                        level_filter,
-                    ) > seq;
-                    if covered {
+                    if self.is_range_covered(&self.last_user_key, seq, source_level) {
+                        self.tombstones_skipped += 1;
                         self.merger.advance_entry();
                         continue;
                     }
-                    let (mut ikey, value) = self.merger.take_entry()?;
+                    let (mut ikey, mut value) = self.merger.take_entry()?;
                     ikey.truncate(uk_len);
+                    if expiring {
+                        value.truncate(value.len() - EXPIRY_SUFFIX_LEN);
+                    }
+                    if blob {
+                        value = self.resolve_blob(&value)?;
+                    }
                     if let Some(ref sp) = self.skip_point
                         && sp(&ikey)
                     {
                         continue;
                     }
+                    if merge {
+                        let value = self.resolve_merge_forward(&ikey, value)?;
+                        return Some((ikey, value));
+                    }
                     return Some((ikey, value));
                 }
-                Action::Take { uk_len } => {
-                    let (mut ikey, value) = self.merger.take_entry()?;
+                Action::Take {
+                    uk_len,
+                    merge,
+                    expiring,
+                    blob,
+                } => {
+                    let (mut ikey, mut value) = self.merger.take_entry()?;
                     ikey.truncate(uk_len);
+                    if expiring {
+                        value.truncate(value.len() - EXPIRY_SUFFIX_LEN);
+                    }
+                    if blob {
+                        value = self.resolve_blob(&value)?;
+                    }
                     if let Some(ref sp) = self.skip_point
                         && sp(&ikey)
                     {
                         continue;
                     }
+                    if merge {
+                        let value = self.resolve_merge_forward(&ikey, value)?;
+                        return Some((ikey, value));
+                    }
                     return Some((ikey, value));
                 }
             }
@@ -468,7 +799,11 @@ impl DBIterator {
             self.prev_overshoot = None;
             if self.has_last_key {
                 use crate::types::InternalKey;
-                let seek_key = InternalKey::new(&self.last_user_key, 0, ValueType::Deletion);
+                let seek_key = InternalKey::new(
+                    &self.icmp.last_version(&self.last_user_key),
+                    0,
+                    ValueType::Deletion,
+                );
                 self.merger.seek(seek_key.as_bytes());
             }
         }
@@ -476,25 +811,33 @@ impl DBIterator {
         // mutable borrow of self.merger across the checks below.
         let snapshot = self.sequence;
         let batch_floor = self.batch_seq_floor;
+        let now_millis = self.now_millis;
         loop {
-            let (ikey_ref, _) = self.merger.peek_entry()?;
-            let (seq, vt) = match decode_internal_key(ikey_ref) {
+            let (ikey_ref, value_ref) = self.merger.peek_entry()?;
+            let (seq, vt, ts_visible) = match decode_internal_key(ikey_ref) {
                 Err(e) => {
                     self.key_decode_error = Some(e.to_string());
                     return None;
                 }
-                Ok((_, seq, vt)) => (seq, vt),
+                Ok((uk, seq, vt)) => (
+                    seq,
+                    vt,
+                    Self::timestamp_visible(&self.icmp, self.read_ts.as_deref(), uk),
+                ),
             };
-            let uk_len = ikey_ref.len() - 8;
+            let uk_len = self
+                .icmp
+                .strip_timestamp(&ikey_ref[..ikey_ref.len() - 8])
+                .len();
 
-            if !(seq <= snapshot || batch_floor.is_some_and(|floor| seq >= floor)) {
+            if !ts_visible || !(seq <= snapshot || batch_floor.is_some_and(|floor| seq >= floor)) {
                 self.merger.advance_entry();
                 continue;
             }
 
             // Lower bound
             if let Some(ref lb) = self.iterate_lower_bound
-                && ikey_ref[..uk_len] < **lb
+                && self.icmp.key_lt(&ikey_ref[..uk_len], lb)
             {
                 self.merger.advance_entry();
                 continue;
@@ -504,7 +847,7 @@ impl DBIterator {
             if let Some(ref pfx) = self.prefix
                 && !ikey_ref[..uk_len].starts_with(pfx)
             {
-                if &ikey_ref[..uk_len] < pfx.as_slice() {
+                if self.icmp.key_lt(&ikey_ref[..uk_len], pfx) {
                     self.merger.advance_entry();
                     continue;
                 }
@@ -513,7 +856,7 @@ impl DBIterator {
 
             // Upper bound
             if let Some(ref ub) = self.iterate_upper_bound
-                && ikey_ref[..uk_len] >= **ub
+                && !self.icmp.key_lt(&ikey_ref[..uk_len], ub)
             {
                 return None;
             }
@@ -532,12 +875,29 @@ impl DBIterator {
             self.has_last_key = true;
 
             if vt == ValueType::Deletion {
+                self.tombstones_skipped += 1;
+                self.merger.advance_entry();
+                continue;
+            }
+            if vt == ValueType::ExpiringValue
+                && Self::is_expired(&mut self.key_decode_error, value_ref, now_millis)?
+            {
                 self.merger.advance_entry();
                 continue;
             }
 
-            let (mut ikey, value) = self.merger.take_entry()?;
+            let (mut ikey, mut value) = self.merger.take_entry()?;
             ikey.truncate(uk_len);
+            if vt == ValueType::ExpiringValue {
+                value.truncate(value.len() - EXPIRY_SUFFIX_LEN);
+            }
+            if vt == ValueType::BlobIndex {
+                value = self.resolve_blob(&value)?;
+            }
+            if vt == ValueType::Merge {
+                let value = self.resolve_merge_forward(&ikey, value)?;
+                return Some((ikey, value));
+            }
             return Some((ikey, value));
         }
     }
@@ -545,16 +905,33 @@ impl DBIterator {
     /// Ensure current is populated. Returns whether there's a valid entry.
     fn ensure_current(&mut self) -> bool {
         if self.needs_advance {
+            let started = self.stats.is_some().then(Instant::now);
             self.current = if self.clean_read {
                 self.next_visible_clean()
             } else {
                 self.next_visible()
             };
             self.needs_advance = false;
+            if let Some(started) = started {
+                let kind = match self.seek_time.take() {
+                    Some(seek_time) => (HistogramKind::Seek, seek_time + started.elapsed()),
+                    None => (HistogramKind::Next, started.elapsed()),
+                };
+                self.record_step(kind);
+            }
         }
         self.current.is_some()
     }
 
+    /// Record one positioning step in `stats`, with the tombstones it
+    /// skipped.
+    fn record_step(&mut self, (kind, duration): (HistogramKind, Duration)) {
+        if let Some(ref stats) = self.stats {
+            stats.record_duration(kind, duration);
+            stats.record_tombstones_skipped(std::mem::take(&mut self.tombstones_skipped));
+        }
+    }
+
     pub fn valid(&mut self) -> bool {
         self.ensure_current()
     }
@@ -564,9 +941,12 @@ impl DBIterator {
         self.current.as_ref().map(|(k, _)| k.as_slice())
     }
 
+    /// The current value. A value stored in a blob file is read here; if
+    /// that fails this returns `None` and [`Self::error`] reports why.
     pub fn value(&mut self) -> Option<&[u8]> {
         self.ensure_current();
-        self.current.as_ref().map(|(_, v)| v.as_slice())
+        let (_, v) = self.current.as_ref()?;
+        Self::load_value(&mut self.blob_error, v)
     }
 
     pub fn advance(&mut self) {
@@ -577,15 +957,20 @@ impl DBIterator {
     /// Seek to the first key >= target.
     pub fn seek(&mut self, target: &[u8]) {
         use crate::types::InternalKey;
+        let started = self.stats.is_some().then(Instant::now);
         let target = self.effective_forward_target(target);
         // Seek the merger to a synthetic internal key with max sequence
-        let seek_key = InternalKey::new(&target, MAX_SEQUENCE_NUMBER, ValueType::Value);
+        let seek_key = InternalKey::new(
+            &self.icmp.first_version(&target),
+            MAX_SEQUENCE_NUMBER,
+            ValueType::Value,
+        );
         // TrySeekUsingNext: if the new target is strictly after the last seek target, use
         // incremental advancement instead of full re-seek.
         let try_next = self
             .last_seek_key
             .as_ref()
-            .is_some_and(|prev| target.as_slice() > prev.as_slice());
+            .is_some_and(|prev| self.icmp.key_lt(prev, &target));
         self.merger.seek_opt(seek_key.as_bytes(), try_next);
         self.last_seek_key = Some(target);
         self.has_last_key = false;
@@ -593,6 +978,7 @@ impl DBIterator {
         self.current = None;
         self.prev_overshoot = None;
         self.backward_positioned = false;
+        self.seek_time = started.map(|started| started.elapsed());
     }
 
     pub fn seek_to_first(&mut self) {
@@ -600,6 +986,7 @@ impl DBIterator {
             self.seek(b"");
             return;
         }
+        let started = self.stats.is_some().then(Instant::now);
         self.merger.seek_to_first();
         self.has_last_key = false;
         self.needs_advance = true;
@@ -607,6 +994,7 @@ impl DBIterator {
         self.prev_overshoot = None;
         self.last_seek_key = None;
         self.backward_positioned = false;
+        self.seek_time = started.map(|started| started.elapsed());
     }
 
     /// The shortest key strictly greater than every key sharing this iterator's
@@ -631,21 +1019,27 @@ impl DBIterator {
     /// Uses a single backward seek + inline resolution. No redundant forward seek.
     pub fn seek_for_prev(&mut self, target: &[u8]) {
         use crate::types::InternalKey;
+        let started = self.stats.is_some().then(Instant::now);
 
         let upper_clamps_target = self
             .iterate_upper_bound
             .as_deref()
-            .is_some_and(|ub| ub <= target);
+            .is_some_and(|ub| self.icmp.key_le(ub, target));
         let (mut seek_key, mut bound) = if upper_clamps_target {
             let ub = self.iterate_upper_bound.as_deref().unwrap();
             (
-                InternalKey::new(ub, MAX_SEQUENCE_NUMBER, ValueType::Value),
-                ub.to_vec(),
+                InternalKey::new(
+                    &self.icmp.first_version(ub),
+                    MAX_SEQUENCE_NUMBER,
+                    ValueType::Value,
+                ),
+                Some(ub.to_vec()),
             )
         } else {
-            let mut bound = target.to_vec();
-            bound.push(0x00);
-            (InternalKey::new(target, 0, ValueType::Deletion), bound)
+            (
+                InternalKey::new(&self.icmp.last_version(target), 0, ValueType::Deletion),
+                None,
+            )
         };
 
         // Clamp the backward seek to the prefix range. Without this, a target
@@ -653,10 +1047,17 @@ impl DBIterator {
         // larger non-prefix key ("z1"), where the prefix guard stops the backward
         // walk and misses valid prefix keys below it ("a1").
         if let Some(succ) = self.prefix_successor()
-            && succ.as_slice() < bound.as_slice()
+            && bound.as_deref().map_or_else(
+                || self.icmp.key_le(&succ, target),
+                |bound| self.icmp.key_lt(&succ, bound),
+            )
         {
-            seek_key = InternalKey::new(&succ, MAX_SEQUENCE_NUMBER, ValueType::Value);
-            bound = succ;
+            seek_key = InternalKey::new(
+                &self.icmp.first_version(&succ),
+                MAX_SEQUENCE_NUMBER,
+                ValueType::Value,
+            );
+            bound = Some(succ);
         }
 
         // Use merger backward seek to find the last internal key below the effective bound.
@@ -664,9 +1065,14 @@ impl DBIterator {
         self.prev_overshoot = None;
         self.has_last_key = false;
 
-        // Walk backward with inline resolution.
-        // For an unclamped target, bound = target + \0 includes target itself.
-        self.resolve_prev_user_key(Some(&bound));
+        // Walk backward with inline resolution. An unclamped target is an
+        // inclusive limit: skip user keys > target but keep target itself.
+        let skip_above = if bound.is_none() { Some(target) } else { None };
+        self.resolve_prev_user_key_limited(bound.as_deref(), skip_above);
+        if let Some(started) = started {
+            self.seek_time = None;
+            self.record_step((HistogramKind::Seek, started.elapsed()));
+        }
     }
 
     /// Fetch the next backward entry from the merger along with the LSM level
@@ -692,6 +1098,16 @@ impl DBIterator {
     /// entries for the same user_key appear in seq ascending order (low→high).
     /// The LAST entry with seq <= snapshot is the newest visible version.
     fn resolve_prev_user_key(&mut self, skip_bound: Option<&[u8]>) {
+        self.resolve_prev_user_key_limited(skip_bound, None);
+    }
+
+    /// Like [`Self::resolve_prev_user_key`], additionally skipping user keys
+    /// strictly greater than `skip_above` until the first key is resolved.
+    fn resolve_prev_user_key_limited(
+        &mut self,
+        skip_bound: Option<&[u8]>,
+        skip_above: Option<&[u8]>,
+    ) {
         let mut current_bound: Option<Vec<u8>> = skip_bound.map(|b| b.to_vec());
 
         loop {
@@ -708,6 +1124,11 @@ impl DBIterator {
             // tombstone pruning), captured via peek_source_level() at the
             // time the entry was taken from the merger.
             let mut best_level: usize = usize::MAX;
+            // Visible merge operands stacked above the newest base version
+            // (seq ascending), and that base's (seq, value, level) when it
+            // is a `Value` — a deletion or absent base leaves it `None`.
+            let mut merge_operands: Vec<(SequenceNumber, LazyValue, usize)> = Vec::new();
+            let mut merge_base: Option<(SequenceNumber, LazyValue, usize)> = None;
 
             // First, consume any overshoot entry saved from a previous backward walk
             let first_entry = self
@@ -717,7 +1138,7 @@ impl DBIterator {
 
             let mut iter_entry = first_entry;
 
-            while let Some((ikey, value, level)) = iter_entry.take() {
+            while let Some((ikey, mut value, level)) = iter_entry.take() {
                 let (uk_owned, seq, vt) = match decode_internal_key(&ikey) {
                     Err(e) => {
                         self.key_decode_error = Some(e.to_string());
@@ -726,7 +1147,9 @@ impl DBIterator {
                     }
                     Ok((uk, seq, vt)) => (uk.to_vec(), seq, vt),
                 };
-                let uk = uk_owned.as_slice();
+                let ts_visible =
+                    Self::timestamp_visible(&self.icmp, self.read_ts.as_deref(), &uk_owned);
+                let uk = self.icmp.strip_timestamp(&uk_owned);
                 let uk_len = uk.len();
 
                 // Prefix guard
@@ -738,15 +1161,18 @@ impl DBIterator {
 
                 // Lower bound guard
                 if let Some(ref lb) = self.iterate_lower_bound
-                    && uk < lb.as_slice()
+                    && self.icmp.key_lt(uk, lb)
                 {
                     break;
                 }
 
-                // Skip entries >= current_bound (same or later user key)
-                if let Some(ref bound) = current_bound
-                    && uk >= bound.as_slice()
-                {
+                // Skip entries >= current_bound (same or later user key), or
+                // > skip_above while no exclusive bound has been set yet.
+                let beyond = match current_bound {
+                    Some(ref bound) => !self.icmp.key_lt(uk, bound),
+                    None => skip_above.is_some_and(|limit| self.icmp.key_lt(limit, uk)),
+                };
+                if beyond {
                     iter_entry = self.prev_entry_with_level();
                     continue;
                 }
@@ -772,12 +1198,34 @@ impl DBIterator {
                     iter_entry = self.prev_entry_with_level();
                     continue;
                 }
+                let Some(vt) = self.resolve_expiry(vt, &mut value) else {
+                    self.current = None;
+                    return;
+                };
 
                 // Track the highest-seq visible version for this user key.
                 // Backward order = seq ascending, so each new entry has higher seq.
                 // Use >= so that sequence-0 entries (produced by bottommost compaction)
-                // are correctly picked up when best_seq starts at 0.
-                if self.is_visible(seq) && seq >= best_seq {
+                // are correctly picked up when best_seq starts at 0. With
+                // timestamps, versions arrive oldest timestamp first and a
+                // newer timestamp wins whatever its seq.
+                let newest = ts_visible
+                    && self.is_visible(seq)
+                    && (seq >= best_seq || self.icmp.timestamp_size() > 0);
+                if newest && vt == ValueType::Merge {
+                    if merge_operands.is_empty() {
+                        merge_base = best_entry.take().map(|(_, v)| (best_seq, v, best_level));
+                    }
+                    merge_operands.push((seq, value, level));
+                    best_seq = seq;
+                    best_level = level;
+                    best_is_deletion = false;
+                    let mut user_key = ikey;
+                    user_key.truncate(uk_len);
+                    best_entry = Some((user_key, LazyValue::empty()));
+                } else if newest {
+                    merge_operands.clear();
+                    merge_base = None;
                     best_seq = seq;
                     best_level = level;
                     best_is_deletion = vt == ValueType::Deletion;
@@ -802,22 +1250,11 @@ impl DBIterator {
                         continue;
                     }
                     match best_entry {
-                        Some((uk, val)) => {
+                        Some((uk, mut val)) => {
                             // Check range tombstone coverage (O(log T) binary search),
                             // excluding tombstones strictly deeper than best_level —
                             // mirrors next_visible()'s Action::TakeCheckTombstone handling.
-                            let level_filter = if best_level != usize::MAX {
-                                Some(best_level)
-                            } else {
-                                None
-                            };
-                            let covered =
-                                self.range_tombstones.max_covering_tombstone_seq_for_level(
-                                    &uk,
-                                    self.tombstone_snapshot(),
-                                    level_filter,
-                                ) > best_seq;
-                            if covered {
+                            if self.is_range_covered(&uk, best_seq, best_level) {
                                 // Covered by range tombstone — skip
                                 current_bound = Some(cuk);
                                 continue;
@@ -829,6 +1266,38 @@ impl DBIterator {
                                 current_bound = Some(cuk);
                                 continue;
                             }
+                            if !merge_operands.is_empty() {
+                                // Fold newest-first, stopping at the first
+                                // operand (or base) a range tombstone hides.
+                                let mut operands: Vec<&[u8]> = Vec::new();
+                                let mut reached_base = true;
+                                for (s, v, l) in merge_operands.iter().rev() {
+                                    if self.is_range_covered(&uk, *s, *l) {
+                                        reached_base = false;
+                                        break;
+                                    }
+                                    operands.push(v.as_slice());
+                                }
+                                let base = match merge_base.as_ref().filter(|(s, _, l)| {
+                                    reached_base && !self.is_range_covered(&uk, *s, *l)
+                                }) {
+                                    Some((_, v, _)) => {
+                                        Self::load_value(&mut self.blob_error, v).map(Some)
+                                    }
+                                    None => Some(None),
+                                };
+                                let merged = match base {
+                                    Some(base) => self.fold_merge(&uk, base, &operands),
+                                    None => None,
+                                };
+                                let Some(merged) = merged else {
+                                    self.current = None;
+                                    self.needs_advance = false;
+                                    self.backward_positioned = false;
+                                    return;
+                                };
+                                val = merged;
+                            }
                             // Save user key for forward re-seek on direction change
                             self.last_user_key.clear();
                             self.last_user_key.extend_from_slice(&uk);
@@ -870,7 +1339,11 @@ impl DBIterator {
             }
         };
 
+        let started = self.stats.is_some().then(Instant::now);
         self.resolve_prev_user_key(Some(&saved_key));
+        if let Some(started) = started {
+            self.record_step((HistogramKind::Next, started.elapsed()));
+        }
     }
 
     /// Seek to the last visible key. Positions the iterator on the very last entry.
@@ -880,11 +1353,14 @@ impl DBIterator {
     /// then resolves visibility inline (no forward re-seek).
     pub fn seek_to_last(&mut self) {
         use crate::types::InternalKey;
+        let started = self.stats.is_some().then(Instant::now);
 
         // Determine the effective upper bound for backward seek.
         let mut resolve_bound = self.iterate_upper_bound.clone();
         if let Some(succ) = self.prefix_successor()
-            && resolve_bound.as_ref().is_none_or(|bound| succ < *bound)
+            && resolve_bound
+                .as_ref()
+                .is_none_or(|bound| self.icmp.key_lt(&succ, bound))
         {
             // Prefix-bounded: seek backward from the prefix successor (the exclusive
             // upper bound of the prefix range), correctly truncated so a key just
@@ -894,7 +1370,11 @@ impl DBIterator {
 
         if let Some(ref ub) = resolve_bound {
             // Upper-bound constrained: seek backward from upper bound.
-            let seek_key = InternalKey::new(ub, MAX_SEQUENCE_NUMBER, ValueType::Value);
+            let seek_key = InternalKey::new(
+                &self.icmp.first_version(ub),
+                MAX_SEQUENCE_NUMBER,
+                ValueType::Value,
+            );
             self.merger.seek_for_prev(seek_key.as_bytes());
         } else {
             self.merger.seek_to_last_merge();
@@ -905,6 +1385,10 @@ impl DBIterator {
 
         // Use inline backward resolution to find the last visible key
         self.resolve_prev_user_key(resolve_bound.as_deref());
+        if let Some(started) = started {
+            self.seek_time = None;
+            self.record_step((HistogramKind::Seek, started.elapsed()));
+        }
     }
 
     /// Return the last user key seen by next_visible(), if any.
@@ -938,7 +1422,14 @@ impl Iterator for DBIterator {
     type Item = (Vec<u8>, Vec<u8>);
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.next_lazy().map(|(k, lv)| (k, lv.into_vec()))
+        let (k, lv) = self.next_lazy()?;
+        match lv.try_into_vec() {
+            Ok(v) => Some((k, v)),
+            Err(e) => {
+                self.blob_error = Some(e.to_string());
+                None
+            }
+        }
     }
 }
 
@@ -1341,6 +1832,7 @@ mod tests {
             vec![IterSource::new(source)],
             10,
             b"foo".to_vec(),
+            InternalKeyComparator::default(),
         );
 
         iter.seek_to_first();
@@ -10,7 +10,7 @@ use crate::iterator::merge::SeekableIterator;
 use crate::manifest::version::TableFile;
 use crate::options::BlockPropertyFilter;
 use crate::sst::table_reader::TableIterator;
-use crate::types::{LazyValue, compare_internal_key, user_key as user_key_from_internal};
+use crate::types::{InternalKeyComparator, LazyValue, user_key as user_key_from_internal};
 
 /// A lazy iterator over a sorted, non-overlapping set of SST files (L1+).
 ///
@@ -40,12 +40,19 @@ pub struct LevelIterator {
     error: Option<String>,
     /// Whether cache misses populate the block cache (default: true).
     fill_cache: bool,
+    /// Key ordering of the level, shared by all of its tables.
+    icmp: InternalKeyComparator,
 }
 
 impl LevelIterator {
     /// Create a new LevelIterator over non-overlapping files sorted by key range.
     pub fn new(files: Vec<TableFile>) -> Self {
+        let icmp = files
+            .first()
+            .map(|tf| tf.reader.comparator().clone())
+            .unwrap_or_default();
         Self {
+            icmp,
             files,
             file_index: 0,
             current_iter: None,
@@ -90,14 +97,14 @@ impl LevelIterator {
         // Range filter: file's largest user key must be >= start_hint
         if let Some(ref start) = self.start_hint {
             let largest_uk = user_key_from_internal(&tf.meta.largest_key);
-            if largest_uk < start.as_slice() {
+            if self.icmp.user_lt(largest_uk, start) {
                 return false;
             }
         }
         // Range filter: file's smallest user key must be < end_hint
         if let Some(ref end) = self.end_hint {
             let smallest_uk = user_key_from_internal(&tf.meta.smallest_key);
-            if smallest_uk >= end.as_slice() {
+            if !self.icmp.user_lt(smallest_uk, end) {
                 return false;
             }
         }
@@ -116,7 +123,7 @@ impl LevelIterator {
         // Binary search: find first file whose largest_key >= target.
         // partition_point returns first index where predicate is false.
         let idx = self.files.partition_point(|tf| {
-            compare_internal_key(&tf.meta.largest_key, target) == Ordering::Less
+            self.icmp.compare(&tf.meta.largest_key, target) == Ordering::Less
         });
         self.file_index = idx;
         self.current_iter = None;
@@ -134,7 +141,7 @@ impl LevelIterator {
             // Skip files whose smallest user key >= upper_bound
             if let Some(ref ub) = self.upper_bound {
                 let smallest_uk = user_key_from_internal(&tf.meta.smallest_key);
-                if smallest_uk >= ub.as_slice() {
+                if !self.icmp.user_lt(smallest_uk, ub) {
                     self.file_index = self.files.len();
                     self.current_iter = None;
                     return;
@@ -267,7 +274,7 @@ impl super::merge::SeekableIterator for LevelIterator {
         self.error = None;
         // Binary search: find last file whose smallest_key <= target.
         let idx = self.files.partition_point(|tf| {
-            compare_internal_key(&tf.meta.smallest_key, target) != Ordering::Greater
+            self.icmp.compare(&tf.meta.smallest_key, target) != Ordering::Greater
         });
         if idx == 0 {
             self.file_index = 0;
@@ -487,6 +494,7 @@ mod tests {
                 smallest_key: smallest,
                 largest_key: largest,
                 has_range_deletions: false,
+                creation_time: 0,
             },
             reader,
         }
@@ -536,6 +544,7 @@ mod tests {
                 smallest_key: smallest,
                 largest_key: largest,
                 has_range_deletions: false,
+                creation_time: 0,
             },
             reader,
         }
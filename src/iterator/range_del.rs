@@ -4,7 +4,7 @@
 //! tracks which tombstones currently cover the iteration position. For forward iteration
 //! with monotonically increasing keys, the amortized cost per key check is O(1).
 
-use crate::types::SequenceNumber;
+use crate::types::{InternalKeyComparator, SequenceNumber};
 
 /// A range tombstone: keys in [begin, end) at sequence `seq` are deleted.
 pub(crate) struct RangeTombstone {
@@ -23,15 +23,23 @@ pub(crate) struct RangeTombstoneTracker {
     next_idx: usize,
     /// Indices of currently active tombstones (begin <= current key).
     active: Vec<usize>,
+    /// Order of user keys.
+    icmp: InternalKeyComparator,
 }
 
 impl RangeTombstoneTracker {
+    #[cfg(test)]
     pub fn new() -> Self {
+        Self::with_comparator(InternalKeyComparator::default())
+    }
+
+    pub fn with_comparator(icmp: InternalKeyComparator) -> Self {
         Self {
             tombstones: Vec::new(),
             sorted: false,
             next_idx: 0,
             active: Vec::new(),
+            icmp,
         }
     }
 
@@ -45,7 +53,9 @@ impl RangeTombstoneTracker {
     pub fn reset(&mut self) {
         if !self.sorted {
             if self.tombstones.len() > 1 {
-                self.tombstones.sort_by(|a, b| a.begin.cmp(&b.begin));
+                let icmp = &self.icmp;
+                self.tombstones
+                    .sort_by(|a, b| icmp.compare_user(&a.begin, &b.begin));
             }
             self.sorted = true;
         }
@@ -75,7 +85,10 @@ impl RangeTombstoneTracker {
 
         // Activate new tombstones whose begin <= user_key
         while self.next_idx < self.tombstones.len() {
-            if self.tombstones[self.next_idx].begin.as_slice() <= user_key {
+            if self
+                .icmp
+                .user_le(&self.tombstones[self.next_idx].begin, user_key)
+            {
                 self.active.push(self.next_idx);
                 self.next_idx += 1;
             } else {
@@ -85,8 +98,9 @@ impl RangeTombstoneTracker {
 
         // Prune expired tombstones (end <= user_key) and check remaining
         let tombstones = &self.tombstones;
+        let icmp = &self.icmp;
         self.active
-            .retain(|&idx| tombstones[idx].end.as_slice() > user_key);
+            .retain(|&idx| icmp.user_lt(user_key, &tombstones[idx].end));
 
         for &idx in &self.active {
             let rt = &self.tombstones[idx];
@@ -102,9 +116,9 @@ impl RangeTombstoneTracker {
     fn linear_check(&self, user_key: &[u8], seq: SequenceNumber, snapshot: SequenceNumber) -> bool {
         for rt in &self.tombstones {
             if rt.seq <= snapshot
-                && user_key >= rt.begin.as_slice()
-                && user_key < rt.end.as_slice()
                 && rt.seq > seq
+                && self.icmp.user_le(&rt.begin, user_key)
+                && self.icmp.user_lt(user_key, &rt.end)
             {
                 return true;
             }
@@ -268,6 +282,8 @@ pub(crate) struct FragmentedRangeTombstoneList {
     /// Query index (empty when `raw` is empty).
     bounds: Bounds,
     tree: Tree,
+    /// Order of user keys; `bounds` is sorted by it.
+    icmp: InternalKeyComparator,
 }
 
 impl FragmentedRangeTombstoneList {
@@ -277,18 +293,40 @@ impl FragmentedRangeTombstoneList {
             raw: Vec::new(),
             bounds: Vec::new(),
             tree: Vec::new(),
+            icmp: InternalKeyComparator::default(),
         }
     }
 
-    /// Build from raw tombstones: `(begin, end, seq)` triples.
-    /// All tombstones are assigned level 0 (no cross-level pruning).
+    /// Build from raw tombstones in bytewise order: `(begin, end, seq)`
+    /// triples. All tombstones are assigned level 0 (no cross-level pruning).
+    #[cfg(test)]
     pub fn new(raw: Vec<(Vec<u8>, Vec<u8>, SequenceNumber)>) -> Self {
-        let with_levels: Vec<_> = raw.into_iter().map(|(b, e, s)| (b, e, s, 0usize)).collect();
-        Self::new_with_levels(with_levels)
+        Self::from_triples(raw, &InternalKeyComparator::default())
     }
 
-    /// Build from raw tombstones with level info: `(begin, end, seq, level)`.
+    /// Build from raw tombstones in bytewise order, with level info:
+    /// `(begin, end, seq, level)`.
+    #[cfg(test)]
     pub fn new_with_levels(raw: Vec<(Vec<u8>, Vec<u8>, SequenceNumber, usize)>) -> Self {
+        Self::with_comparator(raw, &InternalKeyComparator::default())
+    }
+
+    /// Build from `(begin, end, seq)` triples ordered by `icmp`. All
+    /// tombstones are assigned level 0 (no cross-level pruning).
+    pub fn from_triples(
+        raw: Vec<(Vec<u8>, Vec<u8>, SequenceNumber)>,
+        icmp: &InternalKeyComparator,
+    ) -> Self {
+        let with_levels: Vec<_> = raw.into_iter().map(|(b, e, s)| (b, e, s, 0usize)).collect();
+        Self::with_comparator(with_levels, icmp)
+    }
+
+    /// Build from raw tombstones with level info, `(begin, end, seq, level)`,
+    /// ordered by `icmp`.
+    pub fn with_comparator(
+        raw: Vec<(Vec<u8>, Vec<u8>, SequenceNumber, usize)>,
+        icmp: &InternalKeyComparator,
+    ) -> Self {
         if raw.is_empty() {
             return Self::empty();
         }
@@ -299,23 +337,23 @@ impl FragmentedRangeTombstoneList {
             bounds.push(begin.clone());
             bounds.push(end.clone());
         }
-        bounds.sort();
+        bounds.sort_by(|a, b| icmp.compare_user(a, b));
         bounds.dedup();
 
         let num_intervals = bounds.len() - 1;
         let mut tree: Tree = vec![Vec::new(); tree_node_capacity(num_intervals)];
 
         for (begin, end, seq, level) in &raw {
-            if begin >= end {
+            if !icmp.user_lt(begin, end) {
                 continue; // empty/invalid range contributes no coverage
             }
             // `bounds` contains this tombstone's begin/end exactly (both were
             // pushed above), so these binary searches always hit.
             let lo = bounds
-                .binary_search(begin)
+                .binary_search_by(|b| icmp.compare_user(b, begin))
                 .expect("begin was pushed into bounds above");
             let hi = bounds
-                .binary_search(end)
+                .binary_search_by(|b| icmp.compare_user(b, end))
                 .expect("end was pushed into bounds above");
             tree_insert(&mut tree, num_intervals, lo, hi, (*seq, *level));
         }
@@ -329,7 +367,12 @@ impl FragmentedRangeTombstoneList {
             node.sort_unstable_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
         }
 
-        Self { raw, bounds, tree }
+        Self {
+            raw,
+            bounds,
+            tree,
+            icmp: icmp.clone(),
+        }
     }
 
     /// Find the highest-seq tombstone covering `user_key` visible at `snapshot`.
@@ -367,14 +410,14 @@ impl FragmentedRangeTombstoneList {
         };
 
         // Binary search: find the last interval whose begin <= user_key.
-        let idx = self.bounds[..num_intervals].partition_point(|b| b.as_slice() <= user_key);
+        let idx = self.bounds[..num_intervals].partition_point(|b| self.icmp.user_le(b, user_key));
         if idx == 0 {
             return 0;
         }
         let leaf = idx - 1;
 
         // Check user_key is within [begin, end).
-        if user_key >= self.bounds[leaf + 1].as_slice() {
+        if !self.icmp.user_lt(user_key, &self.bounds[leaf + 1]) {
             return 0;
         }
 
@@ -23,6 +23,9 @@ pub struct IterSource {
     pub(crate) level: usize,
 }
 
+// Sources are few and long-lived per iterator; boxing the larger arms would
+// add an indirection to every step.
+#[allow(clippy::large_enum_variant)]
 enum IterSourceInner {
     Vec {
         entries: Vec<(Vec<u8>, Vec<u8>)>,
@@ -38,31 +38,55 @@
 //! missing `LOCK` (and every platform without Unix `flock`) must be treated as
 //! an unlocked immutable snapshot: keep the directory stable for the handle's
 //! entire lifetime and do not use it alongside a live writer.
+//!
+//! To read a store that a live writer keeps changing, open a secondary
+//! instance with [`DB::open_as_secondary`] and advance it with
+//! [`DB::try_catch_up_with_primary`].
 
+mod backup;
+mod blob;
 mod cache;
+mod column_family;
 mod compaction;
 mod db;
 mod error;
 mod iterator;
+mod listener;
 mod manifest;
 mod memtable;
+mod metrics;
 mod options;
 mod rate_limiter;
 mod sst;
 mod stats;
+mod transaction;
 mod types;
 mod wal;
 
 // ---- Primary API ----
+pub use backup::{BackupEngine, BackupInfo};
 pub use cache::block_cache::{BlockCache, BlockCachePool};
+pub use column_family::{ColumnFamilyHandle, DEFAULT_COLUMN_FAMILY_NAME};
 pub use db::{DB, Snapshot};
 pub use error::{Error, ErrorKind, Result, ResultExt};
 pub use iterator::{BidiIterator, DBIterator};
+pub use listener::{
+    BackgroundErrorInfo, CompactionJobInfo, CompactionReason, EventListener, FlushJobInfo,
+    RecoveryProgressInfo, TableFileDeletionInfo, WriteStallCondition, WriteStallInfo,
+};
+pub use metrics::MetricsExporter;
 pub use options::{
-    BlockPropertyCollector, BlockPropertyFilter, CompactionFilter, CompactionFilterDecision,
-    DbOptions, ReadOptions, SkipPointFn, WriteOptions,
+    BlockPropertyCollector, BlockPropertyFilter, BytewiseComparator, BytewiseComparatorWithU64Ts,
+    Clock, CompactionFilter, CompactionFilterDecision, CompactionStyle, Comparator, DbOptions,
+    FifoCompactionOptions, IngestExternalFileOptions, MergeOperator, ReadOptions, SkipPointFn,
+    SystemClock, TransactionDbOptions, UniversalCompactionOptions, WalRecoveryMode, WriteOptions,
 };
+pub use sst::file_writer::{ExternalSstFileInfo, SstFileWriter};
 pub use sst::format::CompressionType;
+pub use stats::{HistogramSnapshot, Statistics};
+pub use transaction::{OptimisticTransaction, Transaction, TransactionDB};
 pub use types::{
-    MAX_USER_KEY_SIZE, MAX_WRITE_ENTRY_SIZE, SequenceNumber, WriteBatch, WriteBatchWithIndex,
+    MAX_USER_KEY_SIZE, MAX_WRITE_ENTRY_SIZE, SequenceNumber, WriteBatch, WriteBatchOp,
+    WriteBatchWithIndex,
 };
+pub use wal::{WalCorruption, WalRecoverySummary, WalUpdateIterator};
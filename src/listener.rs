@@ -0,0 +1,200 @@
+//! Event listeners: callbacks for flush, compaction, write stall,
+//! background error, file deletion and recovery events.
+//!
+//! Listeners are registered through [`DbOptions::listeners`](crate::DbOptions::listeners)
+//! and invoked on the thread that did the work, after the DB lock has been
+//! released. Background errors are the exception: they can be raised while
+//! the lock is held, so they are delivered from a short-lived thread of
+//! their own.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::error::{Error, Result};
+
+/// Receives engine events. Every method has a no-op default, so an
+/// implementation only overrides what it needs.
+///
+/// Callbacks run on engine threads: keep them short and do not call back
+/// into the DB that raised them from [`on_background_error`], which may
+/// race a shutdown. Events of background work can arrive after the call
+/// that waited for that work, such as [`DB::flush`](crate::DB::flush),
+/// has returned.
+///
+/// [`on_background_error`]: EventListener::on_background_error
+pub trait EventListener: Send + Sync {
+    /// A memtable was flushed and its SSTs installed.
+    fn on_flush_completed(&self, _info: &FlushJobInfo) {}
+
+    /// A compaction is about to read its inputs. Not called for the
+    /// whole-level merges of [`DB::compact`](crate::DB::compact) and the
+    /// dead-key sweep, which run under the DB lock; those report only
+    /// completion.
+    fn on_compaction_begin(&self, _info: &CompactionJobInfo) {}
+
+    /// A compaction's output was installed and its manifest edit synced.
+    fn on_compaction_completed(&self, _info: &CompactionJobInfo) {}
+
+    /// The write path moved between normal, delayed and stopped.
+    fn on_stall_conditions_changed(&self, _info: &WriteStallInfo) {}
+
+    /// A background failure put the DB into fail-stop: every later write
+    /// fails with [`ErrorKind::Background`](crate::ErrorKind::Background).
+    /// Reported once, for the failure that tripped it.
+    fn on_background_error(&self, _info: &BackgroundErrorInfo) {}
+
+    /// An SST made obsolete by compaction was deleted (or the attempt
+    /// failed; see [`TableFileDeletionInfo::result`]).
+    fn on_table_file_deleted(&self, _info: &TableFileDeletionInfo) {}
+
+    /// A WAL was replayed while opening the DB.
+    fn on_recovery_progress(&self, _info: &RecoveryProgressInfo) {}
+}
+
+/// A completed memtable flush.
+#[derive(Debug, Clone)]
+pub struct FlushJobInfo {
+    /// The L0 SSTs written, in key order.
+    pub file_numbers: Vec<u64>,
+    /// Total size of those SSTs.
+    pub bytes_written: u64,
+    /// The WAL holding the flushed memtable's writes.
+    pub wal_number: u64,
+    /// Time spent writing the SSTs.
+    pub duration: Duration,
+}
+
+/// Why a compaction was picked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CompactionReason {
+    /// L0 reached `l0_compaction_trigger` files (leveled).
+    L0FileCount,
+    /// A level outgrew its target size (leveled).
+    LevelMaxBytes,
+    /// The runs above the oldest one exceeded
+    /// `max_size_amplification_percent` of it (universal).
+    UniversalSizeAmplification,
+    /// The newest runs have similar sizes (universal).
+    UniversalSizeRatio,
+    /// There are too many sorted runs (universal).
+    UniversalSortedRunCount,
+    /// The files exceed `max_table_files_size` (FIFO).
+    FifoMaxSize,
+    /// The oldest files are past their `ttl` (FIFO).
+    FifoTtl,
+    /// The newest small L0 files are merged (FIFO `allow_compaction`).
+    FifoIntraL0,
+    /// Reads kept landing in a deep level.
+    ReadTriggered,
+    /// [`DB::compact`](crate::DB::compact) or
+    /// [`DB::compact_range`](crate::DB::compact_range).
+    Manual,
+    /// A sweep removing keys registered by
+    /// [`DB::lazy_delete_batch`](crate::DB::lazy_delete_batch).
+    DeadKeySweep,
+}
+
+/// A compaction, as it begins or once it has completed. At begin, the
+/// output fields are empty and `duration` is zero.
+#[derive(Debug, Clone)]
+pub struct CompactionJobInfo {
+    pub reason: CompactionReason,
+    /// Source level of the inputs.
+    pub level: usize,
+    /// Level the output is written to.
+    pub output_level: usize,
+    /// Input SSTs, source level first.
+    pub input_files: Vec<u64>,
+    /// Output SSTs. A trivial move lists its input, relabeled to
+    /// `output_level`; a FIFO deletion writes none.
+    pub output_files: Vec<u64>,
+    /// Total size of the input SSTs.
+    pub bytes_read: u64,
+    /// Total size of the SSTs written; 0 for a trivial move.
+    pub bytes_written: u64,
+    /// Time spent merging and writing the output.
+    pub duration: Duration,
+}
+
+/// State of the write path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WriteStallCondition {
+    /// Writes proceed without delay.
+    #[default]
+    Normal,
+    /// Writes are delayed: L0 reached `l0_slowdown_trigger`.
+    Delayed,
+    /// Writes wait for compaction or flush: L0 reached `l0_stop_trigger`,
+    /// or `max_immutable_memtables` are queued for flush.
+    Stopped,
+}
+
+impl WriteStallCondition {
+    pub(crate) fn from_raw(condition: u8) -> Self {
+        match condition {
+            value if value == Self::Normal as u8 => Self::Normal,
+            value if value == Self::Delayed as u8 => Self::Delayed,
+            value if value == Self::Stopped as u8 => Self::Stopped,
+            _ => unreachable!("invalid write stall condition"),
+        }
+    }
+}
+
+/// A change of [`WriteStallCondition`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WriteStallInfo {
+    pub condition: WriteStallCondition,
+    pub previous: WriteStallCondition,
+}
+
+/// The failure that put the DB into fail-stop.
+#[derive(Debug, Clone)]
+pub struct BackgroundErrorInfo {
+    /// The error later operations report.
+    pub error: Error,
+}
+
+/// Deletion of an obsolete SST.
+#[derive(Debug, Clone)]
+pub struct TableFileDeletionInfo {
+    pub file_number: u64,
+    pub path: PathBuf,
+    /// The outcome of the removal. A failed removal leaves the file to be
+    /// collected on the next open.
+    pub result: Result<()>,
+}
+
+/// One WAL replayed at open.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecoveryProgressInfo {
+    pub wal_number: u64,
+    /// WALs replayed so far, including this one.
+    pub wals_replayed: usize,
+    /// WALs to replay in total.
+    pub wals_total: usize,
+    /// Records applied from every WAL replayed so far.
+    pub records_replayed: u64,
+}
+
+/// Deliver a background error to `listeners` from a new thread: the caller
+/// may hold the DB lock.
+pub(crate) fn notify_background_error(listeners: &[Arc<dyn EventListener>], error: Error) {
+    if listeners.is_empty() {
+        return;
+    }
+    let listeners = listeners.to_vec();
+    let spawned = thread::Builder::new()
+        .name("mmdb-event".to_string())
+        .spawn(move || {
+            let info = BackgroundErrorInfo { error };
+            for listener in &listeners {
+                listener.on_background_error(&info);
+            }
+        });
+    if let Err(e) = spawned {
+        tracing::warn!("failed to deliver background error to listeners: {}", e);
+    }
+}
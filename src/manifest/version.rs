@@ -1,9 +1,12 @@
 //! Version: an immutable snapshot of which SST files exist at each level.
 
-use std::{fmt, sync::Arc};
+use std::{collections::BTreeMap, fmt, sync::Arc};
 
-use crate::manifest::version_edit::FileMetaData;
+use crate::blob::{BlobFileReader, BlobHandle, BlobValue};
+use crate::error::{Error, Result};
+use crate::manifest::version_edit::{BlobFileMetaData, FileMetaData};
 use crate::sst::table_reader::TableReader;
+use crate::types::{LazyValue, user_key};
 
 /// An open SST file with its metadata.
 #[derive(Clone)]
@@ -12,6 +15,26 @@ pub struct TableFile {
     pub reader: Arc<TableReader>,
 }
 
+/// An open blob file with its metadata and garbage accounting.
+#[derive(Clone)]
+pub struct BlobFile {
+    pub meta: BlobFileMetaData,
+    /// Records no longer referenced by any SST.
+    pub garbage_count: u64,
+    pub garbage_bytes: u64,
+    pub reader: Arc<BlobFileReader>,
+}
+
+impl BlobFile {
+    /// Fraction of the file's record bytes that are garbage.
+    pub fn garbage_ratio(&self) -> f64 {
+        if self.meta.blob_bytes == 0 {
+            return 1.0;
+        }
+        self.garbage_bytes as f64 / self.meta.blob_bytes as f64
+    }
+}
+
 /// An immutable snapshot of the database's SST file set.
 ///
 /// Each Version contains, for every level, the list of SST files.
@@ -24,6 +47,8 @@ pub struct Version {
     pub files: Vec<Vec<TableFile>>,
     /// Number of levels.
     pub num_levels: usize,
+    /// Live blob files, by number.
+    pub blob_files: BTreeMap<u64, BlobFile>,
 }
 
 impl Version {
@@ -32,9 +57,26 @@ impl Version {
         Self {
             files: vec![Vec::new(); num_levels],
             num_levels,
+            blob_files: BTreeMap::new(),
         }
     }
 
+    /// Resolve an encoded [`BlobHandle`] (a `BlobIndex` entry's value) into
+    /// a lazily loaded value.
+    pub(crate) fn blob_value(&self, handle: &[u8]) -> Result<LazyValue> {
+        let handle = BlobHandle::decode(handle)?;
+        let file = self.blob_files.get(&handle.file_number).ok_or_else(|| {
+            Error::corruption(format!(
+                "blob handle references missing blob file {}",
+                handle.file_number
+            ))
+        })?;
+        Ok(LazyValue::Blob(Arc::new(BlobValue::new(
+            file.reader.clone(),
+            handle,
+        ))))
+    }
+
     /// Get files at a specific level.
     pub fn level_files(&self, level: usize) -> &[TableFile] {
         &self.files[level]
@@ -50,6 +92,66 @@ impl Version {
     pub fn l0_file_count(&self) -> usize {
         self.files[0].len()
     }
+
+    /// Total SST bytes at `level`.
+    pub(crate) fn level_size(&self, level: usize) -> u64 {
+        self.files[level].iter().map(|f| f.meta.file_size).sum()
+    }
+
+    /// Estimated SST bytes holding live data. Walks up from the last
+    /// level, counting each file unless its key range lies within data
+    /// already counted below it, which it is assumed to overwrite.
+    pub(crate) fn estimate_live_data_size(&self) -> u64 {
+        let Some(icmp) = self
+            .files
+            .iter()
+            .flatten()
+            .next()
+            .map(|tf| tf.reader.comparator())
+        else {
+            return 0;
+        };
+        // Disjoint user-key ranges counted so far, in key order.
+        let mut counted: Vec<(&[u8], &[u8])> = Vec::new();
+        let mut size = 0;
+        for level in (0..self.num_levels).rev() {
+            let mut added = Vec::new();
+            for tf in &self.files[level] {
+                if tf.meta.smallest_key.is_empty() || tf.meta.largest_key.is_empty() {
+                    size += tf.meta.file_size;
+                    continue;
+                }
+                let smallest = user_key(&tf.meta.smallest_key);
+                let largest = user_key(&tf.meta.largest_key);
+                let i = counted.partition_point(|&(_, end)| icmp.user_lt(end, smallest));
+                let covered = counted.get(i).is_some_and(|&(start, end)| {
+                    icmp.user_le(start, smallest) && icmp.user_le(largest, end)
+                });
+                if !covered {
+                    size += tf.meta.file_size;
+                    added.push((smallest, largest));
+                }
+            }
+            if added.is_empty() {
+                continue;
+            }
+            counted.extend(added);
+            counted.sort_by(|a, b| icmp.compare_user(a.0, b.0));
+            let mut merged: Vec<(&[u8], &[u8])> = Vec::with_capacity(counted.len());
+            for (start, end) in counted {
+                match merged.last_mut() {
+                    Some(last) if icmp.user_le(start, last.1) => {
+                        if icmp.user_lt(last.1, end) {
+                            last.1 = end;
+                        }
+                    }
+                    _ => merged.push((start, end)),
+                }
+            }
+            counted = merged;
+        }
+        size
+    }
 }
 
 impl fmt::Debug for Version {
@@ -60,6 +162,10 @@ impl fmt::Debug for Version {
                 write!(f, "L{}: {} files, ", i, level.len())?;
             }
         }
-        write!(f, "] }}")
+        write!(f, "]")?;
+        if !self.blob_files.is_empty() {
+            write!(f, ", blob_files: {}", self.blob_files.len())?;
+        }
+        write!(f, " }}")
     }
 }
@@ -12,6 +12,22 @@ pub struct FileMetaData {
     pub largest_key: Vec<u8>,
     /// Whether this SST file contains any range deletion entries.
     pub has_range_deletions: bool,
+    /// When the newest data in the file was written, in milliseconds since
+    /// the Unix epoch per [`DbOptions::clock`](crate::DbOptions::clock).
+    /// Compaction outputs inherit the newest time of their inputs. 0 if
+    /// unknown (files recorded before this field existed).
+    pub creation_time: u64,
+}
+
+/// Metadata for a blob file (see [`crate::blob`]).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlobFileMetaData {
+    pub number: u64,
+    pub file_size: u64,
+    /// Number of records written to the file.
+    pub blob_count: u64,
+    /// Total record bytes (values plus checksums), excluding the header.
+    pub blob_bytes: u64,
 }
 
 /// A VersionEdit describes a set of changes to apply to a Version.
@@ -27,6 +43,24 @@ pub struct VersionEdit {
     pub new_files: Vec<(u32, FileMetaData)>,
     /// Files to delete: (level, file_number).
     pub deleted_files: Vec<(u32, u64)>,
+    /// Column family this MANIFEST belongs to (absent for the default family).
+    pub column_family: Option<u32>,
+    /// Highest column family id ever allocated; ids are never reused.
+    pub max_column_family: Option<u32>,
+    /// Column families created: (id, name).
+    pub added_column_families: Vec<(u32, String)>,
+    /// Column families dropped, by id.
+    pub dropped_column_families: Vec<u32>,
+    /// Name of the user key comparator the database was created with.
+    pub comparator: Option<String>,
+    /// User-defined timestamp below which compaction may collapse versions.
+    pub full_history_ts_low: Option<Vec<u8>>,
+    /// Blob files to add.
+    pub new_blob_files: Vec<BlobFileMetaData>,
+    /// Blob records that became unreferenced: (file_number, count, bytes).
+    pub blob_garbage: Vec<(u64, u64, u64)>,
+    /// Blob files to delete, by number.
+    pub deleted_blob_files: Vec<u64>,
 }
 
 impl VersionEdit {
@@ -54,6 +88,42 @@ impl VersionEdit {
         self.deleted_files.push((level, file_number));
     }
 
+    pub fn set_column_family(&mut self, id: u32) {
+        self.column_family = Some(id);
+    }
+
+    pub fn set_max_column_family(&mut self, id: u32) {
+        self.max_column_family = Some(id);
+    }
+
+    pub fn add_column_family(&mut self, id: u32, name: impl Into<String>) {
+        self.added_column_families.push((id, name.into()));
+    }
+
+    pub fn drop_column_family(&mut self, id: u32) {
+        self.dropped_column_families.push(id);
+    }
+
+    pub fn set_comparator(&mut self, name: impl Into<String>) {
+        self.comparator = Some(name.into());
+    }
+
+    pub fn set_full_history_ts_low(&mut self, ts: Vec<u8>) {
+        self.full_history_ts_low = Some(ts);
+    }
+
+    pub fn add_blob_file(&mut self, meta: BlobFileMetaData) {
+        self.new_blob_files.push(meta);
+    }
+
+    pub fn add_blob_garbage(&mut self, file_number: u64, count: u64, bytes: u64) {
+        self.blob_garbage.push((file_number, count, bytes));
+    }
+
+    pub fn delete_blob_file(&mut self, file_number: u64) {
+        self.deleted_blob_files.push(file_number);
+    }
+
     /// Encode to bytes for MANIFEST file storage.
     ///
     /// Format (tag-length-value):
@@ -66,6 +136,17 @@ impl VersionEdit {
     ///       + largest_key_len(u32 LE) + largest_key
     ///   5 = deleted_file: level(u32 LE) + number(u64 LE)
     ///   6 = new_file_v2: same as 4 + has_range_deletions(u8)
+    ///   7 = column_family(u32 LE)
+    ///   8 = max_column_family(u32 LE)
+    ///   9 = added_column_family: id(u32 LE) + name_len(u32 LE) + name
+    ///  10 = dropped_column_family: id(u32 LE)
+    ///  11 = comparator: name_len(u32 LE) + name
+    ///  12 = full_history_ts_low: ts_len(u32 LE) + ts
+    ///  13 = new_blob_file: number + file_size + blob_count + blob_bytes
+    ///       (u64 LE each)
+    ///  14 = blob_garbage: file_number + count + bytes (u64 LE each)
+    ///  15 = deleted_blob_file: number(u64 LE)
+    ///  16 = new_file_v3: same as 6 + creation_time(u64 LE)
     pub fn encode(&self) -> Vec<u8> {
         let mut buf = Vec::new();
 
@@ -82,7 +163,8 @@ impl VersionEdit {
             buf.extend_from_slice(&s.to_le_bytes());
         }
         for (level, meta) in &self.new_files {
-            buf.push(6); // v2 format with has_range_deletions
+            // v2 format with has_range_deletions; v3 adds the creation time
+            buf.push(if meta.creation_time == 0 { 6 } else { 16 });
             buf.extend_from_slice(&level.to_le_bytes());
             buf.extend_from_slice(&meta.number.to_le_bytes());
             buf.extend_from_slice(&meta.file_size.to_le_bytes());
@@ -91,12 +173,60 @@ impl VersionEdit {
             buf.extend_from_slice(&(meta.largest_key.len() as u32).to_le_bytes());
             buf.extend_from_slice(&meta.largest_key);
             buf.push(meta.has_range_deletions as u8);
+            if meta.creation_time != 0 {
+                buf.extend_from_slice(&meta.creation_time.to_le_bytes());
+            }
         }
         for (level, number) in &self.deleted_files {
             buf.push(5);
             buf.extend_from_slice(&level.to_le_bytes());
             buf.extend_from_slice(&number.to_le_bytes());
         }
+        if let Some(id) = self.column_family {
+            buf.push(7);
+            buf.extend_from_slice(&id.to_le_bytes());
+        }
+        if let Some(id) = self.max_column_family {
+            buf.push(8);
+            buf.extend_from_slice(&id.to_le_bytes());
+        }
+        for (id, name) in &self.added_column_families {
+            buf.push(9);
+            buf.extend_from_slice(&id.to_le_bytes());
+            buf.extend_from_slice(&(name.len() as u32).to_le_bytes());
+            buf.extend_from_slice(name.as_bytes());
+        }
+        for id in &self.dropped_column_families {
+            buf.push(10);
+            buf.extend_from_slice(&id.to_le_bytes());
+        }
+        if let Some(ref name) = self.comparator {
+            buf.push(11);
+            buf.extend_from_slice(&(name.len() as u32).to_le_bytes());
+            buf.extend_from_slice(name.as_bytes());
+        }
+        if let Some(ref ts) = self.full_history_ts_low {
+            buf.push(12);
+            buf.extend_from_slice(&(ts.len() as u32).to_le_bytes());
+            buf.extend_from_slice(ts);
+        }
+        for meta in &self.new_blob_files {
+            buf.push(13);
+            buf.extend_from_slice(&meta.number.to_le_bytes());
+            buf.extend_from_slice(&meta.file_size.to_le_bytes());
+            buf.extend_from_slice(&meta.blob_count.to_le_bytes());
+            buf.extend_from_slice(&meta.blob_bytes.to_le_bytes());
+        }
+        for (number, count, bytes) in &self.blob_garbage {
+            buf.push(14);
+            buf.extend_from_slice(&number.to_le_bytes());
+            buf.extend_from_slice(&count.to_le_bytes());
+            buf.extend_from_slice(&bytes.to_le_bytes());
+        }
+        for number in &self.deleted_blob_files {
+            buf.push(15);
+            buf.extend_from_slice(&number.to_le_bytes());
+        }
 
         buf
     }
@@ -135,8 +265,9 @@ impl VersionEdit {
                         Some(u64::from_le_bytes(data[pos..pos + 8].try_into().unwrap()));
                     pos += 8;
                 }
-                4 | 6 => {
-                    // new_file (tag 4 = legacy, tag 6 = v2 with has_range_deletions)
+                4 | 6 | 16 => {
+                    // new_file (tag 4 = legacy, tag 6 = v2 with has_range_deletions,
+                    // tag 16 = v3 with creation_time)
                     if pos + 4 + 8 + 8 > data.len() {
                         return Err(Error::corruption("truncated new_file header"));
                     }
@@ -171,7 +302,7 @@ impl VersionEdit {
                     let largest_key = data[pos..pos + lk_len].to_vec();
                     pos += lk_len;
 
-                    let has_range_deletions = if tag == 6 {
+                    let has_range_deletions = if tag != 4 {
                         if pos >= data.len() {
                             return Err(Error::corruption("truncated has_range_deletions"));
                         }
@@ -181,6 +312,16 @@ impl VersionEdit {
                     } else {
                         false
                     };
+                    let creation_time = if tag == 16 {
+                        if pos + 8 > data.len() {
+                            return Err(Error::corruption("truncated creation_time"));
+                        }
+                        let v = u64::from_le_bytes(data[pos..pos + 8].try_into().unwrap());
+                        pos += 8;
+                        v
+                    } else {
+                        0
+                    };
 
                     edit.new_files.push((
                         level,
@@ -190,6 +331,7 @@ impl VersionEdit {
                             smallest_key,
                             largest_key,
                             has_range_deletions,
+                            creation_time,
                         },
                     ));
                 }
@@ -204,6 +346,91 @@ impl VersionEdit {
                     pos += 8;
                     edit.deleted_files.push((level, number));
                 }
+                7 | 8 | 10 => {
+                    if pos + 4 > data.len() {
+                        return Err(Error::corruption("truncated column family id"));
+                    }
+                    let id = u32::from_le_bytes(data[pos..pos + 4].try_into().unwrap());
+                    pos += 4;
+                    match tag {
+                        7 => edit.column_family = Some(id),
+                        8 => edit.max_column_family = Some(id),
+                        _ => edit.dropped_column_families.push(id),
+                    }
+                }
+                9 => {
+                    if pos + 8 > data.len() {
+                        return Err(Error::corruption("truncated added_column_family"));
+                    }
+                    let id = u32::from_le_bytes(data[pos..pos + 4].try_into().unwrap());
+                    pos += 4;
+                    let name_len =
+                        u32::from_le_bytes(data[pos..pos + 4].try_into().unwrap()) as usize;
+                    pos += 4;
+                    if pos + name_len > data.len() {
+                        return Err(Error::corruption("truncated column family name"));
+                    }
+                    let name = String::from_utf8(data[pos..pos + name_len].to_vec())
+                        .map_err(|_| Error::corruption("column family name is not UTF-8"))?;
+                    pos += name_len;
+                    edit.added_column_families.push((id, name));
+                }
+                11 => {
+                    if pos + 4 > data.len() {
+                        return Err(Error::corruption("truncated comparator name_len"));
+                    }
+                    let name_len =
+                        u32::from_le_bytes(data[pos..pos + 4].try_into().unwrap()) as usize;
+                    pos += 4;
+                    if pos + name_len > data.len() {
+                        return Err(Error::corruption("truncated comparator name"));
+                    }
+                    let name = String::from_utf8(data[pos..pos + name_len].to_vec())
+                        .map_err(|_| Error::corruption("comparator name is not UTF-8"))?;
+                    pos += name_len;
+                    edit.comparator = Some(name);
+                }
+                12 => {
+                    if pos + 4 > data.len() {
+                        return Err(Error::corruption("truncated full_history_ts_low len"));
+                    }
+                    let ts_len =
+                        u32::from_le_bytes(data[pos..pos + 4].try_into().unwrap()) as usize;
+                    pos += 4;
+                    if pos + ts_len > data.len() {
+                        return Err(Error::corruption("truncated full_history_ts_low"));
+                    }
+                    edit.full_history_ts_low = Some(data[pos..pos + ts_len].to_vec());
+                    pos += ts_len;
+                }
+                13 | 14 => {
+                    let len = if tag == 13 { 32 } else { 24 };
+                    if pos + len > data.len() {
+                        return Err(Error::corruption("truncated blob file record"));
+                    }
+                    let u64_at = |i: usize| {
+                        u64::from_le_bytes(data[pos + 8 * i..pos + 8 * i + 8].try_into().unwrap())
+                    };
+                    if tag == 13 {
+                        edit.new_blob_files.push(BlobFileMetaData {
+                            number: u64_at(0),
+                            file_size: u64_at(1),
+                            blob_count: u64_at(2),
+                            blob_bytes: u64_at(3),
+                        });
+                    } else {
+                        edit.blob_garbage.push((u64_at(0), u64_at(1), u64_at(2)));
+                    }
+                    pos += len;
+                }
+                15 => {
+                    if pos + 8 > data.len() {
+                        return Err(Error::corruption("truncated deleted_blob_file"));
+                    }
+                    edit.deleted_blob_files
+                        .push(u64::from_le_bytes(data[pos..pos + 8].try_into().unwrap()));
+                    pos += 8;
+                }
                 _ => {
                     return Err(Error::corruption(format!("unknown tag: {}", tag)));
                 }
@@ -233,6 +460,12 @@ impl VersionEdit {
                 edit.add_file(level as u32, tf.meta.clone());
             }
         }
+        for bf in version.blob_files.values() {
+            edit.add_blob_file(bf.meta.clone());
+            if bf.garbage_count > 0 {
+                edit.add_blob_garbage(bf.meta.number, bf.garbage_count, bf.garbage_bytes);
+            }
+        }
 
         edit
     }
@@ -268,6 +501,7 @@ mod tests {
                 smallest_key: b"aaa".to_vec(),
                 largest_key: b"zzz".to_vec(),
                 has_range_deletions: false,
+                creation_time: 0,
             },
         );
         edit.add_file(
@@ -278,6 +512,7 @@ mod tests {
                 smallest_key: b"bbb".to_vec(),
                 largest_key: b"yyy".to_vec(),
                 has_range_deletions: false,
+                creation_time: 0,
             },
         );
         edit.delete_file(0, 5);
@@ -317,6 +552,7 @@ mod tests {
                 smallest_key: vec![],         // empty key
                 largest_key: vec![0xFF; 256], // binary key
                 has_range_deletions: true,
+                creation_time: 0,
             },
         );
         edit.add_file(
@@ -327,6 +563,7 @@ mod tests {
                 smallest_key: b"start".to_vec(),
                 largest_key: b"stop".to_vec(),
                 has_range_deletions: false,
+                creation_time: 0,
             },
         );
 
@@ -383,6 +620,8 @@ mod tests {
                     smallest_key: format!("s_{:04}", i).into_bytes(),
                     largest_key: format!("l_{:04}", i).into_bytes(),
                     has_range_deletions: i % 3 == 0,
+                    // Mix v2 (unknown time) and v3 records.
+                    creation_time: i as u64 * 1000,
                 },
             );
         }
@@ -411,6 +650,8 @@ mod tests {
             assert_eq!(meta.file_size, (i as u64 + 1) * 4096);
             assert_eq!(meta.smallest_key, format!("s_{:04}", i).into_bytes());
             assert_eq!(meta.largest_key, format!("l_{:04}", i).into_bytes());
+            assert_eq!(meta.has_range_deletions, i % 3 == 0);
+            assert_eq!(meta.creation_time, i as u64 * 1000);
         }
         for i in 0..20 {
             assert_eq!(decoded.deleted_files[i], ((i % 7) as u32, i as u64));
@@ -420,4 +661,80 @@ mod tests {
         let re_encoded = decoded.encode();
         assert_eq!(encoded, re_encoded);
     }
+
+    #[test]
+    fn test_column_family_tags_roundtrip() {
+        let mut edit = VersionEdit::new();
+        edit.set_column_family(3);
+        edit.set_max_column_family(7);
+        edit.add_column_family(6, "users");
+        edit.add_column_family(7, "");
+        edit.drop_column_family(2);
+
+        let encoded = edit.encode();
+        let decoded = VersionEdit::decode(&encoded).unwrap();
+        assert_eq!(decoded.column_family, Some(3));
+        assert_eq!(decoded.max_column_family, Some(7));
+        assert_eq!(
+            decoded.added_column_families,
+            vec![(6, "users".to_string()), (7, String::new())]
+        );
+        assert_eq!(decoded.dropped_column_families, vec![2]);
+
+        // A truncated name must be rejected rather than silently shortened.
+        let mut edit = VersionEdit::new();
+        edit.add_column_family(1, "logs");
+        let encoded = edit.encode();
+        assert!(VersionEdit::decode(&encoded[..encoded.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_comparator_tag_roundtrip() {
+        let mut edit = VersionEdit::new();
+        edit.set_last_sequence(9);
+        edit.set_comparator("app.ReverseComparator");
+
+        let encoded = edit.encode();
+        let decoded = VersionEdit::decode(&encoded).unwrap();
+        assert_eq!(decoded.comparator.as_deref(), Some("app.ReverseComparator"));
+        assert_eq!(decoded.last_sequence, Some(9));
+        assert!(VersionEdit::decode(&encoded[..encoded.len() - 2]).is_err());
+        assert_eq!(VersionEdit::new().comparator, None);
+    }
+
+    #[test]
+    fn test_full_history_ts_low_tag_roundtrip() {
+        let mut edit = VersionEdit::new();
+        edit.set_full_history_ts_low(42u64.to_be_bytes().to_vec());
+
+        let encoded = edit.encode();
+        let decoded = VersionEdit::decode(&encoded).unwrap();
+        assert_eq!(
+            decoded.full_history_ts_low,
+            Some(42u64.to_be_bytes().to_vec())
+        );
+        assert!(VersionEdit::decode(&encoded[..encoded.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_blob_file_tags_roundtrip() {
+        let meta = BlobFileMetaData {
+            number: 12,
+            file_size: 1 << 20,
+            blob_count: 64,
+            blob_bytes: (1 << 20) - 8,
+        };
+        let mut edit = VersionEdit::new();
+        edit.add_blob_file(meta.clone());
+        edit.add_blob_garbage(9, 3, 3000);
+        edit.delete_blob_file(7);
+
+        let encoded = edit.encode();
+        let decoded = VersionEdit::decode(&encoded).unwrap();
+        assert_eq!(decoded.new_blob_files, vec![meta]);
+        assert_eq!(decoded.blob_garbage, vec![(9, 3, 3000)]);
+        assert_eq!(decoded.deleted_blob_files, vec![7]);
+        assert!(VersionEdit::decode(&encoded[..encoded.len() - 1]).is_err());
+        assert!(VersionEdit::decode(&encoded[..encoded.len() - 10]).is_err());
+    }
 }
@@ -1,7 +1,7 @@
 //! VersionSet: manages the MANIFEST file and the chain of Versions.
 
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     fs,
     io::{BufWriter, Write},
     path::{Path, PathBuf},
@@ -11,12 +11,14 @@ use std::{
     },
 };
 
+use crate::blob::BlobFileReader;
 use crate::cache::table_cache::TableCache;
 use crate::error::{Error, Result, ResultExt};
-use crate::manifest::version::{TableFile, Version};
-use crate::manifest::version_edit::{FileMetaData, VersionEdit};
+use crate::manifest::version::{BlobFile, TableFile, Version};
+use crate::manifest::version_edit::{BlobFileMetaData, FileMetaData, VersionEdit};
+use crate::options::BytewiseComparator;
 use crate::sst::table_reader::TableReader;
-use crate::types::{MAX_SEQUENCE_NUMBER, SequenceNumber, compare_internal_key, user_key};
+use crate::types::{InternalKeyComparator, MAX_SEQUENCE_NUMBER, SequenceNumber, user_key};
 use crate::wal::{WalReader, WalWriter};
 use parking_lot::Mutex;
 
@@ -36,11 +38,16 @@ pub struct VersionSet {
     last_sequence: SequenceNumber,
     /// Current MANIFEST file number.
     manifest_number: u64,
+    /// End of the last MANIFEST record applied by a read-only set; where
+    /// [`Self::catch_up`] resumes reading.
+    manifest_offset: u64,
     /// MANIFEST writer, behind its own lock so `sync` can be called
     /// without holding the main DB mutex.
     manifest_writer: Arc<Mutex<Option<WalWriter>>>,
     /// Table cache for opening SST readers.
     table_cache: Option<Arc<TableCache>>,
+    /// Key ordering of every level; taken from the table cache.
+    icmp: InternalKeyComparator,
     /// Count of edits since last MANIFEST snapshot (for Phase I compaction).
     edits_since_snapshot: u64,
     /// Set when the MANIFEST writer is in an unrecoverable state: a record
@@ -56,9 +63,27 @@ pub struct VersionSet {
     /// `check_usable` and poison it from `manifest_sync_handle` sync sites
     /// that bypass [`Self::sync_manifest`].
     poisoned: Arc<AtomicBool>,
+    /// Column family this MANIFEST describes (0 = the default family).
+    column_family: u32,
+    /// Live non-default column families, by id (default family only).
+    column_families: BTreeMap<u32, String>,
+    /// Highest column family id ever allocated (default family only).
+    max_column_family: u32,
+    /// User-defined timestamp below which compaction may collapse versions.
+    full_history_ts_low: Option<Vec<u8>>,
+    /// Blob files dropped from the version whose files have not been
+    /// deleted yet; drained by [`Self::take_obsolete_blob_files`].
+    obsolete_blob_files: Vec<u64>,
 }
 
 impl VersionSet {
+    /// Number of the MANIFEST named by `CURRENT`.
+    fn read_current_file(db_path: &Path) -> Result<u64> {
+        let manifest_name = fs::read_to_string(db_path.join("CURRENT"))
+            .map_err(|e| Error::corruption(format!("cannot read CURRENT: {}", e)))?;
+        Self::parse_manifest_number(manifest_name.trim_end_matches(['\r', '\n']))
+    }
+
     fn parse_manifest_number(manifest_name: &str) -> Result<u64> {
         let digits = manifest_name
             .strip_prefix("MANIFEST-")
@@ -88,7 +113,7 @@ impl VersionSet {
     /// user-key boundaries, so two live L1+ files sharing a user key can only
     /// come from a malformed MANIFEST or an internal logic bug — installing
     /// them would make binary-search reads silently miss live keys.
-    fn validate_level_disjointness(version: &Version) -> Result<()> {
+    fn validate_level_disjointness(version: &Version, icmp: &InternalKeyComparator) -> Result<()> {
         for level in 1..version.num_levels {
             let files = version.level_files(level);
             for pair in files.windows(2) {
@@ -97,7 +122,7 @@ impl VersionSet {
                 if prev.largest_key.is_empty() || next.smallest_key.is_empty() {
                     continue;
                 }
-                if user_key(&prev.largest_key) >= user_key(&next.smallest_key) {
+                if !icmp.user_lt(user_key(&prev.largest_key), user_key(&next.smallest_key)) {
                     return Err(Error::corruption(format!(
                         "SST {} and SST {} have overlapping key ranges at level {}",
                         prev.number, next.number, level
@@ -108,6 +133,14 @@ impl VersionSet {
         Ok(())
     }
 
+    /// The ordering the table cache opens readers with; bytewise without one.
+    fn comparator_of(table_cache: &Option<Arc<TableCache>>) -> InternalKeyComparator {
+        table_cache
+            .as_ref()
+            .map(|tc| tc.comparator().clone())
+            .unwrap_or_default()
+    }
+
     /// Create a new VersionSet (for a fresh database). Test-only wrapper.
     #[cfg(test)]
     pub(crate) fn create(db_path: &Path, num_levels: usize) -> Result<Self> {
@@ -127,6 +160,7 @@ impl VersionSet {
         // Write CURRENT file
         Self::set_current_file(db_path, manifest_number).ctx()?;
 
+        let icmp = Self::comparator_of(&table_cache);
         let mut vs = Self {
             db_path: db_path.to_path_buf(),
             num_levels,
@@ -135,16 +169,24 @@ impl VersionSet {
             log_number: 0,
             last_sequence: 0,
             manifest_number,
+            manifest_offset: 0,
             manifest_writer: Arc::new(Mutex::new(Some(manifest_writer))),
             table_cache,
+            icmp,
             edits_since_snapshot: 0,
             poisoned: Arc::new(AtomicBool::new(false)),
+            column_family: 0,
+            column_families: BTreeMap::new(),
+            max_column_family: 0,
+            full_history_ts_low: None,
+            obsolete_blob_files: Vec::new(),
         };
 
         // Write initial snapshot edit
         let mut edit = VersionEdit::new();
         edit.set_next_file_number(vs.next_file_number);
         edit.set_last_sequence(vs.last_sequence);
+        edit.set_comparator(vs.icmp.name());
         vs.log_and_apply(edit).ctx()?;
         vs.sync_manifest().ctx()?;
 
@@ -182,13 +224,8 @@ impl VersionSet {
         table_cache: Option<Arc<TableCache>>,
         read_only: bool,
     ) -> Result<Self> {
-        // Read CURRENT to find manifest file
-        let current_path = db_path.join("CURRENT");
-        let manifest_name = fs::read_to_string(&current_path)
-            .map_err(|e| Error::corruption(format!("cannot read CURRENT: {}", e)))
-            .ctx()?;
-        let manifest_name = manifest_name.trim_end_matches(['\r', '\n']);
-        let manifest_number = Self::parse_manifest_number(manifest_name).ctx()?;
+        let manifest_number = Self::read_current_file(db_path).ctx()?;
+        let manifest_name = format!("MANIFEST-{manifest_number:06}");
         let manifest_path = db_path.join(format!("MANIFEST-{manifest_number:06}"));
 
         // Replay MANIFEST records in two passes:
@@ -200,9 +237,16 @@ impl VersionSet {
         let mut next_file_number = 2u64;
         let mut log_number = 0u64;
         let mut last_sequence = 0u64;
+        let mut column_family = 0u32;
+        let mut column_families = BTreeMap::new();
+        let mut max_column_family = 0u32;
+        let mut comparator: Option<String> = None;
+        let mut full_history_ts_low: Option<Vec<u8>> = None;
 
         // (level, meta) pairs for files that are still live after all edits.
         let mut live_files: HashMap<u64, (usize, FileMetaData)> = HashMap::new();
+        // Live blob files with their accumulated (garbage_count, garbage_bytes).
+        let mut live_blob_files: BTreeMap<u64, (BlobFileMetaData, u64, u64)> = BTreeMap::new();
         // Number of edits replayed — seeds `edits_since_snapshot` so that
         // restart-heavy workloads still trigger MANIFEST compaction instead
         // of growing the file without bound across process lifetimes.
@@ -228,6 +272,12 @@ impl VersionSet {
             };
             let edit = VersionEdit::decode(&data).ctx()?;
             edits_replayed += 1;
+            if edit.comparator.is_some() {
+                comparator = edit.comparator.clone();
+            }
+            if edit.full_history_ts_low.is_some() {
+                full_history_ts_low = edit.full_history_ts_low.clone();
+            }
 
             // Forward-only, mirroring `log_and_apply`'s bookkeeping: replayed
             // edits are chronological, so a lower value is necessarily stale
@@ -252,6 +302,13 @@ impl VersionSet {
                 }
                 last_sequence = last_sequence.max(s);
             }
+            Self::apply_column_family_records(
+                &edit,
+                &mut column_family,
+                &mut column_families,
+                &mut max_column_family,
+            )
+            .ctx()?;
 
             // Track deletions FIRST — a trivial-move edit deletes from the
             // old level and adds to the new level with the same file number.
@@ -308,6 +365,50 @@ impl VersionSet {
                     )));
                 }
             }
+
+            // Blob records replay in the order `log_and_apply` applies them.
+            for meta in &edit.new_blob_files {
+                if live_blob_files
+                    .insert(meta.number, (meta.clone(), 0, 0))
+                    .is_some()
+                {
+                    return Err(Error::corruption(format!(
+                        "MANIFEST adds blob file {} but it is already live",
+                        meta.number
+                    )));
+                }
+            }
+            for &(number, count, bytes) in &edit.blob_garbage {
+                let Some((meta, garbage_count, garbage_bytes)) = live_blob_files.get_mut(&number)
+                else {
+                    return Err(Error::corruption(format!(
+                        "MANIFEST records garbage for blob file {} but it is not live",
+                        number
+                    )));
+                };
+                *garbage_count += count;
+                *garbage_bytes += bytes;
+                Self::check_blob_garbage(meta, *garbage_count, *garbage_bytes).ctx()?;
+            }
+            for number in &edit.deleted_blob_files {
+                if live_blob_files.remove(number).is_none() {
+                    return Err(Error::corruption(format!(
+                        "MANIFEST deletes blob file {} but it is not live",
+                        number
+                    )));
+                }
+            }
+        }
+
+        // MANIFESTs written before the comparator was recorded are bytewise.
+        let icmp = Self::comparator_of(&table_cache);
+        let recorded = comparator.as_deref().unwrap_or(BytewiseComparator::NAME);
+        if recorded != icmp.name() {
+            return Err(Error::invalid_argument(format!(
+                "database was created with comparator {:?} but opened with {:?}",
+                recorded,
+                icmp.name()
+            )));
         }
 
         // Pass 2: Open only live files
@@ -317,7 +418,8 @@ impl VersionSet {
                 tc.get_reader(meta.number)
             } else {
                 let sst_path = db_path.join(format!("{:06}.sst", meta.number));
-                TableReader::open(&sst_path).map(Arc::new)
+                TableReader::open_with_all(&sst_path, meta.number, None, None, icmp.clone())
+                    .map(Arc::new)
             };
             match reader_result {
                 Ok(reader) => {
@@ -335,16 +437,34 @@ impl VersionSet {
             }
         }
 
+        for (number, (meta, garbage_count, garbage_bytes)) in live_blob_files {
+            let reader = BlobFileReader::open(db_path, number).map_err(|e| {
+                Error::corruption(format!(
+                    "cannot open blob file {} during recovery: {}",
+                    number, e
+                ))
+            })?;
+            version.blob_files.insert(
+                number,
+                BlobFile {
+                    meta,
+                    garbage_count,
+                    garbage_bytes,
+                    reader: Arc::new(reader),
+                },
+            );
+        }
+
         // Sort L0 by file number descending (newest first)
         version.files[0].sort_by_key(|file| std::cmp::Reverse(file.meta.number));
         // Sort L1+ by smallest key
         for level in 1..num_levels {
             version.files[level]
-                .sort_by(|a, b| compare_internal_key(&a.meta.smallest_key, &b.meta.smallest_key));
+                .sort_by(|a, b| icmp.compare(&a.meta.smallest_key, &b.meta.smallest_key));
         }
         // The replayed record stream passed per-record checksums, but the
         // recovered file set must still satisfy the read-path invariant.
-        Self::validate_level_disjointness(&version).ctx()?;
+        Self::validate_level_disjointness(&version, &icmp).ctx()?;
 
         // Writable recovery repairs a torn tail and reopens the MANIFEST for
         // append. Read-only recovery must leave the file byte-for-byte intact.
@@ -363,10 +483,17 @@ impl VersionSet {
             log_number,
             last_sequence,
             manifest_number,
+            manifest_offset: reader.last_valid_offset(),
             manifest_writer: Arc::new(Mutex::new(manifest_writer)),
             table_cache,
+            icmp,
             edits_since_snapshot: edits_replayed,
             poisoned: Arc::new(AtomicBool::new(false)),
+            column_family,
+            column_families,
+            max_column_family,
+            full_history_ts_low,
+            obsolete_blob_files: Vec::new(),
         })
     }
 
@@ -384,26 +511,10 @@ impl VersionSet {
         }
     }
 
-    /// Apply a VersionEdit: write to MANIFEST and install a new Version.
-    ///
-    /// Error contract: `Err` means the edit was **not** applied — neither in
-    /// memory nor durably. Callers rely on this to safely delete output SSTs
-    /// referenced by a failed edit.
-    pub fn log_and_apply(&mut self, edit: VersionEdit) -> Result<()> {
-        if self.manifest_writer.lock().is_none() {
-            return Err(Error::read_only());
-        }
-        if self.is_poisoned() {
-            return Err(Error::corruption(
-                "MANIFEST writer poisoned by an earlier write failure; \
-                 reopen the database to recover"
-                    .to_string(),
-            ));
-        }
-
-        // Build new version from current + edit FIRST, before persisting.
-        // This ensures that if an SST fails to open, the MANIFEST is not
-        // polluted with an edit referencing a broken file.
+    /// The current version with `edit`'s file additions and deletions
+    /// applied, opening a reader for every added file. Nothing is changed
+    /// on error.
+    fn apply_edit(&self, edit: &VersionEdit) -> Result<Version> {
         let mut new_version = (*self.current).clone();
 
         for (level, meta) in &edit.new_files {
@@ -422,7 +533,8 @@ impl VersionSet {
                 tc.get_reader(meta.number)
             } else {
                 let sst_path = self.db_path.join(format!("{:06}.sst", meta.number));
-                TableReader::open(&sst_path).map(Arc::new)
+                TableReader::open_with_all(&sst_path, meta.number, None, None, self.icmp.clone())
+                    .map(Arc::new)
             };
             match reader {
                 Ok(reader) => {
@@ -442,7 +554,8 @@ impl VersionSet {
                         });
                         // Keep levels 1+ sorted by smallest key (using logical ordering)
                         new_version.files[level].sort_by(|a, b| {
-                            compare_internal_key(&a.meta.smallest_key, &b.meta.smallest_key)
+                            self.icmp
+                                .compare(&a.meta.smallest_key, &b.meta.smallest_key)
                         });
                     }
                 }
@@ -490,7 +603,103 @@ impl VersionSet {
         // the edit wholesale before anything is persisted (same contract as
         // the level/deletion checks above); recovery enforces the identical
         // invariant, so a persisted violation would also fail the next open.
-        Self::validate_level_disjointness(&new_version).ctx()?;
+        Self::validate_level_disjointness(&new_version, &self.icmp).ctx()?;
+
+        // Blob files follow the same contract: additions must open, and
+        // garbage and deletions must name a live file.
+        for meta in &edit.new_blob_files {
+            if new_version.blob_files.contains_key(&meta.number) {
+                return Err(Error::corruption(format!(
+                    "VersionEdit adds blob file {} but it is already live",
+                    meta.number
+                )));
+            }
+            let reader = BlobFileReader::open(&self.db_path, meta.number).map_err(|e| {
+                Error::corruption(format!(
+                    "failed to open new blob file {}: {}",
+                    meta.number, e
+                ))
+            })?;
+            new_version.blob_files.insert(
+                meta.number,
+                BlobFile {
+                    meta: meta.clone(),
+                    garbage_count: 0,
+                    garbage_bytes: 0,
+                    reader: Arc::new(reader),
+                },
+            );
+        }
+        for &(number, count, bytes) in &edit.blob_garbage {
+            let Some(file) = new_version.blob_files.get_mut(&number) else {
+                return Err(Error::corruption(format!(
+                    "VersionEdit records garbage for blob file {} but it is not live",
+                    number
+                )));
+            };
+            file.garbage_count += count;
+            file.garbage_bytes += bytes;
+            Self::check_blob_garbage(&file.meta, file.garbage_count, file.garbage_bytes).ctx()?;
+        }
+        for number in &edit.deleted_blob_files {
+            if new_version.blob_files.remove(number).is_none() {
+                return Err(Error::corruption(format!(
+                    "VersionEdit deletes blob file {} but it is not live",
+                    number
+                )));
+            }
+        }
+        Ok(new_version)
+    }
+
+    /// Apply a VersionEdit: write to MANIFEST and install a new Version.
+    ///
+    /// Error contract: `Err` means the edit was **not** applied — neither in
+    /// memory nor durably. Callers rely on this to safely delete output SSTs
+    /// referenced by a failed edit.
+    ///
+    /// Blob files whose records have all become garbage are dropped from the
+    /// new version (and recorded as deleted in the persisted edit); their
+    /// numbers are queued for [`Self::take_obsolete_blob_files`].
+    pub fn log_and_apply(&mut self, mut edit: VersionEdit) -> Result<()> {
+        if self.manifest_writer.lock().is_none() {
+            return Err(Error::read_only());
+        }
+        if self.is_poisoned() {
+            return Err(Error::corruption(
+                "MANIFEST writer poisoned by an earlier write failure; \
+                 reopen the database to recover"
+                    .to_string(),
+            ));
+        }
+
+        // Validate column family records against a scratch copy so a bad
+        // edit is rejected before anything is written.
+        let mut column_family = self.column_family;
+        let mut column_families = self.column_families.clone();
+        let mut max_column_family = self.max_column_family;
+        Self::apply_column_family_records(
+            &edit,
+            &mut column_family,
+            &mut column_families,
+            &mut max_column_family,
+        )
+        .ctx()?;
+
+        // Build new version from current + edit FIRST, before persisting.
+        // This ensures that if an SST fails to open, the MANIFEST is not
+        // polluted with an edit referencing a broken file.
+        let mut new_version = self.apply_edit(&edit)?;
+        let exhausted: Vec<u64> = new_version
+            .blob_files
+            .values()
+            .filter(|f| f.garbage_count == f.meta.blob_count)
+            .map(|f| f.meta.number)
+            .collect();
+        for number in &exhausted {
+            new_version.blob_files.remove(number);
+            edit.delete_blob_file(*number);
+        }
 
         // Before any MANIFEST record referencing the new SST files can become
         // durable — either via a later `sync_manifest()`/`manifest_sync_handle`
@@ -499,7 +708,7 @@ impl VersionSet {
         // (The file *contents* are already fsynced by `TableBuilder::finish`.)
         // Otherwise a crash could leave the MANIFEST referencing an SST whose
         // directory entry was lost.
-        if !edit.new_files.is_empty()
+        if (!edit.new_files.is_empty() || !edit.new_blob_files.is_empty())
             && let Err(e) = Self::fsync_directory(&self.db_path)
         {
             // A failed directory fsync means these files' directory entries
@@ -550,6 +759,15 @@ impl VersionSet {
             self.last_sequence = self.last_sequence.max(s);
         }
 
+        self.column_family = column_family;
+        self.column_families = column_families;
+        self.max_column_family = max_column_family;
+        if edit.full_history_ts_low.is_some() {
+            self.full_history_ts_low = edit.full_history_ts_low;
+        }
+
+        self.obsolete_blob_files.extend(edit.deleted_blob_files);
+
         self.current = Arc::new(new_version);
         self.edits_since_snapshot += 1;
 
@@ -563,6 +781,108 @@ impl VersionSet {
         Ok(())
     }
 
+    /// Bring a read-only set up to date with the MANIFEST another process
+    /// keeps appending to, applying the edits written since recovery or the
+    /// last catch-up.
+    ///
+    /// Recovers from scratch instead when `recover` is set, when `CURRENT`
+    /// names a new MANIFEST (the writer rotated it), or when an edit cannot
+    /// be applied — typically because it adds a file that a later edit has
+    /// already deleted from disk. A record the writer is still appending is
+    /// left for the next call.
+    pub fn catch_up(&mut self, recover: bool) -> Result<()> {
+        if self.manifest_writer.lock().is_some() {
+            return Err(Error::invalid_argument(
+                "only a read-only VersionSet can catch up with its MANIFEST",
+            ));
+        }
+        if !recover && Self::read_current_file(&self.db_path).ctx()? == self.manifest_number {
+            match self.apply_manifest_tail() {
+                Ok(()) => return Ok(()),
+                Err(e) => tracing::debug!("MANIFEST catch-up falls back to recovery: {}", e),
+            }
+        }
+        *self = Self::recover_read_only_with_cache(
+            &self.db_path,
+            self.num_levels,
+            self.table_cache.clone(),
+        )?;
+        Ok(())
+    }
+
+    /// Apply the complete records past `manifest_offset`, one edit at a time.
+    fn apply_manifest_tail(&mut self) -> Result<()> {
+        let path = self
+            .db_path
+            .join(format!("MANIFEST-{:06}", self.manifest_number));
+        let mut reader = WalReader::new(&path).ctx()?;
+        reader.seek_to(self.manifest_offset).ctx()?;
+        loop {
+            let data = match reader.read_record() {
+                Ok(Some(data)) => data,
+                Ok(None) => return Ok(()),
+                Err(_) if reader.last_error_is_truncation() => return Ok(()),
+                Err(e) => return Err(e),
+            };
+            let edit = VersionEdit::decode(&data).ctx()?;
+            if let Some(s) = edit.last_sequence
+                && s > MAX_SEQUENCE_NUMBER
+            {
+                return Err(Error::corruption(format!(
+                    "MANIFEST last_sequence {} exceeds maximum {}",
+                    s, MAX_SEQUENCE_NUMBER
+                )));
+            }
+            let mut column_family = self.column_family;
+            let mut column_families = self.column_families.clone();
+            let mut max_column_family = self.max_column_family;
+            Self::apply_column_family_records(
+                &edit,
+                &mut column_family,
+                &mut column_families,
+                &mut max_column_family,
+            )
+            .ctx()?;
+            let new_version = self.apply_edit(&edit)?;
+
+            if let Some(n) = edit.next_file_number {
+                self.next_file_number = self.next_file_number.max(n);
+            }
+            if let Some(n) = edit.log_number {
+                self.log_number = n;
+            }
+            if let Some(s) = edit.last_sequence {
+                self.last_sequence = self.last_sequence.max(s);
+            }
+            self.column_family = column_family;
+            self.column_families = column_families;
+            self.max_column_family = max_column_family;
+            if edit.full_history_ts_low.is_some() {
+                self.full_history_ts_low = edit.full_history_ts_low;
+            }
+            self.current = Arc::new(new_version);
+            self.manifest_offset = reader.last_valid_offset();
+        }
+    }
+
+    /// Garbage recorded against a blob file can never exceed what it holds.
+    fn check_blob_garbage(meta: &BlobFileMetaData, count: u64, bytes: u64) -> Result<()> {
+        if count > meta.blob_count || bytes > meta.blob_bytes {
+            return Err(Error::corruption(format!(
+                "blob file {} garbage ({} records, {} bytes) exceeds its contents \
+                 ({} records, {} bytes)",
+                meta.number, count, bytes, meta.blob_count, meta.blob_bytes
+            )));
+        }
+        Ok(())
+    }
+
+    /// Take the blob files dropped by applied edits since the last call.
+    /// The caller deletes them once no compaction can still be reading them.
+    pub fn take_obsolete_blob_files(&mut self) -> Vec<u64> {
+        std::mem::take(&mut self.obsolete_blob_files)
+    }
+
     /// Sync the MANIFEST writer. Only acquires the internal manifest lock,
     /// so callers can invoke this without holding the main DB mutex.
     ///
@@ -670,6 +990,61 @@ impl VersionSet {
         self.last_sequence
     }
 
+    /// Timestamp below which compaction may collapse versions, if set.
+    pub fn full_history_ts_low(&self) -> Option<&[u8]> {
+        self.full_history_ts_low.as_deref()
+    }
+
+    /// Column family id recorded in this MANIFEST (0 for the default family).
+    pub fn column_family(&self) -> u32 {
+        self.column_family
+    }
+
+    /// Live non-default column families registered in this MANIFEST.
+    pub fn column_families(&self) -> &BTreeMap<u32, String> {
+        &self.column_families
+    }
+
+    /// Highest column family id ever allocated.
+    pub fn max_column_family(&self) -> u32 {
+        self.max_column_family
+    }
+
+    /// Apply the column family records of `edit` to the given registry,
+    /// rejecting edits that would reuse an id or drop an unknown family.
+    fn apply_column_family_records(
+        edit: &VersionEdit,
+        column_family: &mut u32,
+        column_families: &mut BTreeMap<u32, String>,
+        max_column_family: &mut u32,
+    ) -> Result<()> {
+        if let Some(id) = edit.column_family {
+            *column_family = id;
+        }
+        if let Some(id) = edit.max_column_family {
+            *max_column_family = (*max_column_family).max(id);
+        }
+        for (id, name) in &edit.added_column_families {
+            if *id == 0 || column_families.contains_key(id) {
+                return Err(Error::corruption(format!(
+                    "MANIFEST adds column family {} ({:?}) but the id is already in use",
+                    id, name
+                )));
+            }
+            column_families.insert(*id, name.clone());
+            *max_column_family = (*max_column_family).max(*id);
+        }
+        for id in &edit.dropped_column_families {
+            if column_families.remove(id).is_none() {
+                return Err(Error::corruption(format!(
+                    "MANIFEST drops column family {} but it is not live",
+                    id
+                )));
+            }
+        }
+        Ok(())
+    }
+
     /// Rewrite the MANIFEST with a full snapshot of the current version.
     /// This prevents unbounded MANIFEST growth.
     ///
@@ -695,12 +1070,7 @@ impl VersionSet {
         let new_manifest_number = self.next_file_number;
         self.next_file_number += 1;
 
-        let snapshot_edit = VersionEdit::from_version_snapshot(
-            &self.current,
-            self.log_number,
-            self.next_file_number,
-            self.last_sequence,
-        );
+        let snapshot_edit = self.snapshot_edit(self.next_file_number);
 
         let new_manifest_path = self
             .db_path
@@ -818,6 +1188,55 @@ impl VersionSet {
         }
     }
 
+    /// A single edit describing the whole current state: live files, log
+    /// number, sequence and the column family records.
+    fn snapshot_edit(&self, next_file_number: u64) -> VersionEdit {
+        let mut edit = VersionEdit::from_version_snapshot(
+            &self.current,
+            self.log_number,
+            next_file_number,
+            self.last_sequence,
+        );
+        if self.column_family != 0 {
+            edit.set_column_family(self.column_family);
+        }
+        if self.max_column_family != 0 {
+            edit.set_max_column_family(self.max_column_family);
+        }
+        for (id, name) in &self.column_families {
+            edit.add_column_family(*id, name.clone());
+        }
+        edit.set_comparator(self.icmp.name());
+        if let Some(ref ts) = self.full_history_ts_low {
+            edit.set_full_history_ts_low(ts.clone());
+        }
+        edit
+    }
+
+    /// Capture the current state for a checkpoint: the MANIFEST number to
+    /// use in the checkpoint directory and a snapshot edit whose
+    /// `next_file_number` lies past it. No file number is consumed here.
+    pub fn checkpoint_edit(&self) -> (u64, VersionEdit) {
+        let manifest_number = self.next_file_number;
+        (manifest_number, self.snapshot_edit(manifest_number + 1))
+    }
+
+    /// Write `edit` as the only record of a fresh `MANIFEST-<N>` in `dir`
+    /// and point `CURRENT` at it. Everything is fsynced, including `dir`.
+    pub fn write_snapshot_manifest(
+        dir: &Path,
+        manifest_number: u64,
+        edit: &VersionEdit,
+    ) -> Result<()> {
+        let manifest_path = dir.join(format!("MANIFEST-{:06}", manifest_number));
+        let mut writer = WalWriter::new(&manifest_path).ctx()?;
+        writer.add_record(&edit.encode()).ctx()?;
+        writer.sync().ctx()?;
+        drop(writer);
+        Self::set_current_file(dir, manifest_number).ctx()?;
+        Ok(())
+    }
+
     fn set_current_file(db_path: &Path, manifest_number: u64) -> Result<()> {
         Self::write_current_file_tmp(db_path, manifest_number).ctx()?;
         Self::rename_current_file(db_path, manifest_number).ctx()?;
@@ -893,6 +1312,41 @@ mod tests {
         assert_eq!(fs::read(&manifest_path).unwrap(), before);
     }
 
+    #[test]
+    fn test_catch_up_follows_appended_edits_and_rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path();
+        let mut writer = VersionSet::create(path, 7).unwrap();
+        let mut follower = VersionSet::recover_read_only_with_cache(path, 7, None).unwrap();
+
+        let mut edit = VersionEdit::new();
+        edit.set_log_number(5);
+        edit.set_last_sequence(10);
+        writer.log_and_apply(edit).unwrap();
+        writer.sync_manifest().unwrap();
+        follower.catch_up(false).unwrap();
+        assert_eq!(follower.log_number(), 5);
+        assert_eq!(follower.last_sequence(), 10);
+        assert_eq!(follower.manifest_number(), 1);
+
+        // Enough edits to make the writer rotate its MANIFEST.
+        for n in 6..1100 {
+            let mut edit = VersionEdit::new();
+            edit.set_log_number(n);
+            writer.log_and_apply(edit).unwrap();
+        }
+        writer.sync_manifest().unwrap();
+        assert_ne!(writer.manifest_number(), 1);
+        follower.catch_up(false).unwrap();
+        assert_eq!(follower.manifest_number(), writer.manifest_number());
+        assert_eq!(follower.log_number(), 1099);
+
+        assert_eq!(
+            writer.catch_up(false).unwrap_err().kind(),
+            crate::ErrorKind::InvalidArgument
+        );
+    }
+
     #[test]
     fn test_create_and_recover() {
         let dir = tempfile::tempdir().unwrap();
@@ -965,6 +1419,7 @@ mod tests {
                     smallest_key: b"aaa".to_vec(),
                     largest_key: b"zzz".to_vec(),
                     has_range_deletions: false,
+                    creation_time: 0,
                 },
             );
             let encoded = edit.encode();
@@ -1005,6 +1460,7 @@ mod tests {
                 smallest_key: b"aaa".to_vec(),
                 largest_key: b"zzz".to_vec(),
                 has_range_deletions: false,
+                creation_time: 0,
             },
         );
         let err = vs.log_and_apply(edit).unwrap_err();
@@ -1172,6 +1628,7 @@ mod tests {
                 smallest_key: result.smallest_key.unwrap(),
                 largest_key: result.largest_key.unwrap(),
                 has_range_deletions: false,
+                creation_time: 0,
             }
         }
 
@@ -1219,4 +1676,47 @@ mod tests {
             "unexpected recovery error: {err}"
         );
     }
+
+    #[test]
+    fn test_column_family_registry_recovery_and_snapshot() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path();
+
+        let mut vs = VersionSet::create(path, 7).unwrap();
+        let mut edit = VersionEdit::new();
+        edit.add_column_family(1, "a");
+        edit.add_column_family(2, "b");
+        vs.log_and_apply(edit).unwrap();
+        let mut edit = VersionEdit::new();
+        edit.drop_column_family(1);
+        vs.log_and_apply(edit).unwrap();
+
+        // Reusing a live id or dropping an unknown family is rejected
+        // without touching the registry.
+        let mut reuse = VersionEdit::new();
+        reuse.add_column_family(2, "again");
+        assert!(vs.log_and_apply(reuse).is_err());
+        let mut unknown = VersionEdit::new();
+        unknown.drop_column_family(9);
+        assert!(vs.log_and_apply(unknown).is_err());
+        assert_eq!(vs.column_families().len(), 1);
+        vs.sync_manifest().unwrap();
+        drop(vs);
+
+        let mut vs = VersionSet::recover(path, 7).unwrap();
+        assert_eq!(vs.max_column_family(), 2);
+        assert_eq!(
+            vs.column_families().iter().collect::<Vec<_>>(),
+            vec![(&2, &"b".to_string())]
+        );
+
+        // Force a MANIFEST snapshot; the registry must survive the rewrite.
+        vs.edits_since_snapshot = 1000;
+        vs.log_and_apply(VersionEdit::new()).unwrap();
+        vs.sync_manifest().unwrap();
+        drop(vs);
+        let vs = VersionSet::recover(path, 7).unwrap();
+        assert_eq!(vs.max_column_family(), 2);
+        assert_eq!(vs.column_families().get(&2).map(String::as_str), Some("b"));
+    }
 }
@@ -7,7 +7,10 @@ pub use skiplist::SkipListMemTable;
 
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
-use crate::types::{InternalKey, SequenceNumber, ValueType};
+use crate::types::{
+    InternalKey, InternalKeyComparator, MAX_SEQUENCE_NUMBER, SequenceNumber, VALUE_TYPE_FOR_SEEK,
+    ValueType, user_key,
+};
 
 /// A cached range tombstone from a memtable.
 pub struct MemRangeTombstone {
@@ -19,42 +22,79 @@ pub struct MemRangeTombstone {
 /// A MemTable stores recent writes in memory before they are flushed to SST.
 ///
 /// Keys are InternalKey-encoded (user_key + seq + type), values are raw bytes.
-/// The skiplist sorts by `compare_internal_key` ordering.
+/// The skiplist sorts by the DB's internal key order.
 pub struct MemTable {
     inner: SkipListMemTable,
+    icmp: InternalKeyComparator,
     /// Approximate memory usage in bytes.
     approximate_size: AtomicUsize,
     /// Whether this memtable contains any RangeDeletion entries.
     /// Used to skip the expensive O(N) range tombstone scan in get().
     has_range_deletions: AtomicBool,
     /// Dedicated collection of range tombstones for O(T) coverage checks
-    /// instead of O(N) full memtable scan. Protected by Mutex since writes
-    /// are single-writer (group commit model).
+    /// instead of O(N) full memtable scan. Protected by Mutex so concurrent
+    /// inserts (`put_concurrent`) can append to it too.
     range_tombstones: parking_lot::Mutex<Vec<MemRangeTombstone>>,
+    /// Set once the SSTs flushed from this memtable are installed, so it no
+    /// longer holds the only copy of writes that skipped the WAL.
+    flushed: AtomicBool,
 }
 
 impl MemTable {
     pub fn new() -> Self {
+        Self::with_comparator(InternalKeyComparator::default())
+    }
+
+    /// An empty memtable ordered by `icmp`.
+    pub fn with_comparator(icmp: InternalKeyComparator) -> Self {
         Self {
-            inner: SkipListMemTable::new(),
+            inner: SkipListMemTable::with_comparator(&icmp),
+            icmp,
             approximate_size: AtomicUsize::new(0),
             has_range_deletions: AtomicBool::new(false),
             range_tombstones: parking_lot::Mutex::new(Vec::new()),
+            flushed: AtomicBool::new(false),
         }
     }
 
     /// Insert an entry. `key` is the user key; it will be encoded as an InternalKey.
     pub fn put(&self, key: &[u8], value: &[u8], sequence: SequenceNumber, value_type: ValueType) {
+        self.add(key, value, sequence, value_type, false);
+    }
+
+    /// Like `put`, but safe to call from several threads at once. Must not
+    /// run concurrently with `put` on the same memtable.
+    pub fn put_concurrent(
+        &self,
+        key: &[u8],
+        value: &[u8],
+        sequence: SequenceNumber,
+        value_type: ValueType,
+    ) {
+        self.add(key, value, sequence, value_type, true);
+    }
+
+    fn add(
+        &self,
+        key: &[u8],
+        value: &[u8],
+        sequence: SequenceNumber,
+        value_type: ValueType,
+        concurrent: bool,
+    ) {
         // An empty or inverted range deletion `[begin, end)` with `begin >= end`
         // covers no keys. Storing it would leave a RangeDeletion entry keyed at
         // `begin` that a point lookup misreads as a deletion of `begin`, so treat
         // it as a no-op. (`value` holds the range's end key for RangeDeletion.)
-        if value_type == ValueType::RangeDeletion && key >= value {
+        if value_type == ValueType::RangeDeletion && !self.icmp.user_lt(key, value) {
             return;
         }
         let ikey = InternalKey::new(key, sequence, value_type);
         let val = match value_type {
-            ValueType::Value => value.to_vec(),
+            ValueType::Value
+            | ValueType::Merge
+            | ValueType::ExpiringValue
+            | ValueType::BlobIndex => value.to_vec(),
             ValueType::Deletion => Vec::new(),
             ValueType::RangeDeletion => {
                 // Add to dedicated range tombstone collection for O(T) lookup
@@ -74,7 +114,11 @@ impl MemTable {
             // Account for duplicated data in range_tombstones Vec
             entry_size += key.len() + value.len() + std::mem::size_of::<MemRangeTombstone>();
         }
-        self.inner.insert(ikey.into_bytes(), val);
+        if concurrent {
+            self.inner.insert_concurrent(ikey.into_bytes(), val);
+        } else {
+            self.inner.insert(ikey.into_bytes(), val);
+        }
         self.approximate_size
             .fetch_add(entry_size, Ordering::Relaxed);
     }
@@ -84,16 +128,19 @@ impl MemTable {
     /// or `None` if the key is not in this MemTable.
     #[cfg(test)]
     pub fn get(&self, key: &[u8], sequence: SequenceNumber) -> Option<Option<Vec<u8>>> {
-        self.get_with_seq(key, sequence).map(|(result, _)| result)
+        self.get_with_seq(key, sequence)
+            .map(|(vt, value, _)| (vt == ValueType::Value).then_some(value))
     }
 
-    /// Like `get`, but also returns the sequence number of the found entry.
+    /// Find the newest entry for `key` with seq <= `sequence`, returning its
+    /// type, value and sequence number. Callers resolve `Merge` operands by
+    /// repeating the lookup just below the returned sequence.
     pub fn get_with_seq(
         &self,
         key: &[u8],
         sequence: SequenceNumber,
-    ) -> Option<(Option<Vec<u8>>, SequenceNumber)> {
-        let search_key = InternalKey::new(key, sequence, ValueType::Value);
+    ) -> Option<(ValueType, Vec<u8>, SequenceNumber)> {
+        let search_key = InternalKey::new(key, sequence, VALUE_TYPE_FOR_SEEK);
         self.inner.get_with_seq(search_key.as_bytes(), key)
     }
 
@@ -102,6 +149,11 @@ impl MemTable {
         self.approximate_size.load(Ordering::Relaxed)
     }
 
+    /// Number of entries, counting every version and tombstone.
+    pub fn num_entries(&self) -> usize {
+        self.inner.len()
+    }
+
     /// Return an iterator over all entries in order.
     /// Each item is (encoded_internal_key, value).
     pub fn iter(&self) -> impl Iterator<Item = (Vec<u8>, Vec<u8>)> {
@@ -140,7 +192,10 @@ impl MemTable {
             if rt.seq > read_seq {
                 continue;
             }
-            if user_key >= rt.begin.as_slice() && user_key < rt.end.as_slice() && rt.seq > max_seq {
+            if rt.seq > max_seq
+                && self.icmp.user_le(&rt.begin, user_key)
+                && self.icmp.user_lt(user_key, &rt.end)
+            {
                 max_seq = rt.seq;
             }
         }
@@ -160,10 +215,34 @@ impl MemTable {
             .collect()
     }
 
+    /// Whether any entry or range tombstone touches a user key in
+    /// `[smallest, largest]`.
+    pub fn overlaps_user_range(&self, smallest: &[u8], largest: &[u8]) -> bool {
+        let seek = InternalKey::new(smallest, MAX_SEQUENCE_NUMBER, VALUE_TYPE_FOR_SEEK);
+        if let Some(key) = self.inner.lower_bound_key(seek.as_bytes())
+            && self.icmp.user_le(user_key(&key), largest)
+        {
+            return true;
+        }
+        self.get_range_tombstones().iter().any(|(begin, end, _)| {
+            self.icmp.user_le(begin, largest) && self.icmp.user_lt(smallest, end)
+        })
+    }
+
     /// Return true if empty.
     pub fn is_empty(&self) -> bool {
         self.approximate_size.load(Ordering::Relaxed) == 0
     }
+
+    /// Record that this memtable's flush is installed.
+    pub fn mark_flushed(&self) {
+        self.flushed.store(true, Ordering::Release);
+    }
+
+    /// Whether this memtable's flush is installed.
+    pub fn is_flushed(&self) -> bool {
+        self.flushed.load(Ordering::Acquire)
+    }
 }
 
 impl Default for MemTable {
@@ -202,6 +281,18 @@ mod tests {
         assert_eq!(mt.get(b"key1", 1), Some(Some(b"value1".to_vec())));
     }
 
+    #[test]
+    fn test_memtable_overlaps_user_range() {
+        let mt = MemTable::new();
+        mt.put(b"d", b"1", 1, ValueType::Value);
+        mt.put(b"m", b"p", 2, ValueType::RangeDeletion);
+        assert!(mt.overlaps_user_range(b"a", b"d"));
+        assert!(mt.overlaps_user_range(b"d", b"d"));
+        assert!(!mt.overlaps_user_range(b"e", b"l"));
+        assert!(mt.overlaps_user_range(b"n", b"n"));
+        assert!(!mt.overlaps_user_range(b"p", b"z"));
+    }
+
     #[test]
     fn test_memtable_overwrite() {
         let mt = MemTable::new();
@@ -293,6 +384,49 @@ mod tests {
         assert_eq!(total, num_entries);
     }
 
+    #[test]
+    fn test_memtable_concurrent_writers() {
+        use std::sync::Arc;
+        use std::thread;
+
+        let mt = Arc::new(MemTable::new());
+        let handles: Vec<_> = (0..8u64)
+            .map(|t| {
+                let mt = Arc::clone(&mt);
+                thread::spawn(move || {
+                    for i in 0..500u64 {
+                        let key = format!("k{:05}", i);
+                        let seq = i * 8 + t + 1;
+                        mt.put_concurrent(
+                            key.as_bytes(),
+                            &seq.to_be_bytes(),
+                            seq,
+                            ValueType::Value,
+                        );
+                    }
+                })
+            })
+            .collect();
+        for h in handles {
+            h.join().unwrap();
+        }
+
+        assert_eq!(mt.iter().count(), 4000);
+        for i in 0..500u64 {
+            let key = format!("k{:05}", i);
+            // The newest version is the one written by thread 7.
+            let newest = i * 8 + 8;
+            assert_eq!(
+                mt.get(key.as_bytes(), MAX_SEQUENCE_NUMBER),
+                Some(Some(newest.to_be_bytes().to_vec()))
+            );
+            assert_eq!(
+                mt.get(key.as_bytes(), newest - 1),
+                Some(Some((newest - 1).to_be_bytes().to_vec()))
+            );
+        }
+    }
+
     #[test]
     fn test_memtable_prefix_similar_keys() {
         let mt = MemTable::new();
@@ -4,11 +4,15 @@ use std::{cmp::Ordering, sync::Arc};
 
 use super::skiplist_impl::ConcurrentSkipList;
 use crate::iterator::merge::SeekableIterator;
-use crate::types::{InternalKeyRef, LazyValue, ValueType, compare_internal_key};
-
-/// Newtype wrapper for internal keys that implements `Ord` using `compare_internal_key`.
-/// This ensures the skip list maintains logical internal key order directly,
-/// eliminating the need for O(N log N) re-sorting on iteration.
+use crate::types::{
+    InternalKeyComparator, InternalKeyRef, LazyValue, ValueType, compare_internal_key,
+};
+
+/// Newtype wrapper for the internal keys stored in the memtable's skip list.
+/// The list orders them with the DB's [`InternalKeyComparator`]: by user key
+/// under the user comparator, then by sequence number descending. It thus
+/// keeps logical internal key order directly, eliminating the need for
+/// O(N log N) re-sorting on iteration.
 #[derive(Clone, Debug)]
 pub struct OrdInternalKey(Vec<u8>);
 
@@ -32,6 +36,8 @@ impl PartialOrd for OrdInternalKey {
     }
 }
 
+// Only satisfies the skip list's `Ord` bound; the list compares keys with
+// the comparator it was built with.
 impl Ord for OrdInternalKey {
     fn cmp(&self, other: &Self) -> Ordering {
         compare_internal_key(&self.0, &other.0)
@@ -41,7 +47,7 @@ impl Ord for OrdInternalKey {
 /// Lock-free concurrent skiplist MemTable.
 ///
 /// Keys are encoded InternalKeys (user_key + 8-byte trailer).
-/// The skiplist is ordered by `compare_internal_key` via the `OrdInternalKey` newtype.
+/// The skiplist is ordered by an [`InternalKeyComparator`] over `OrdInternalKey`s.
 pub struct SkipListMemTable {
     map: ConcurrentSkipList<OrdInternalKey, Vec<u8>>,
 }
@@ -54,8 +60,15 @@ impl Default for SkipListMemTable {
 
 impl SkipListMemTable {
     pub fn new() -> Self {
+        Self::with_comparator(&InternalKeyComparator::default())
+    }
+
+    pub fn with_comparator(icmp: &InternalKeyComparator) -> Self {
+        let icmp = icmp.clone();
         Self {
-            map: ConcurrentSkipList::new(),
+            map: ConcurrentSkipList::with_comparator(Box::new(
+                move |a: &OrdInternalKey, b: &OrdInternalKey| icmp.compare(&a.0, &b.0),
+            )),
         }
     }
 
@@ -64,11 +77,23 @@ impl SkipListMemTable {
         &self.map as *const _
     }
 
+    /// Number of entries.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
     /// Insert an encoded internal key and value.
     pub fn insert(&self, encoded_key: Vec<u8>, value: Vec<u8>) {
         self.map.insert(OrdInternalKey(encoded_key), value);
     }
 
+    /// Insert an encoded internal key and value alongside other
+    /// `insert_concurrent` callers.
+    pub fn insert_concurrent(&self, encoded_key: Vec<u8>, value: Vec<u8>) {
+        self.map
+            .insert_concurrent(OrdInternalKey(encoded_key), value);
+    }
+
     /// Look up a user key. `search_key` is an encoded InternalKey used to seek.
     /// `user_key` is the raw user key for matching.
     ///
@@ -79,16 +104,17 @@ impl SkipListMemTable {
     #[cfg(test)]
     pub fn get(&self, search_key: &[u8], user_key: &[u8]) -> Option<Option<Vec<u8>>> {
         self.get_with_seq(search_key, user_key)
-            .map(|(result, _seq)| result)
+            .map(|(vt, value, _seq)| (vt == ValueType::Value).then_some(value))
     }
 
-    /// Like `get`, but also returns the sequence number of the found entry.
-    /// Needed for range tombstone vs point entry sequence comparison.
+    /// Like `get`, but returns the raw entry type and sequence number.
+    /// Needed for range tombstone vs point entry sequence comparison and for
+    /// merge operand resolution.
     pub fn get_with_seq(
         &self,
         search_key: &[u8],
         user_key: &[u8],
-    ) -> Option<(Option<Vec<u8>>, crate::types::SequenceNumber)> {
+    ) -> Option<(ValueType, Vec<u8>, crate::types::SequenceNumber)> {
         let search = OrdInternalKey(search_key.to_vec());
         let (k, v) = self.map.lower_bound(&search)?;
         let kb = k.as_bytes();
@@ -98,18 +124,17 @@ impl SkipListMemTable {
         let entry_uk = &kb[..kb.len() - 8];
         if entry_uk == user_key {
             let entry_ref = InternalKeyRef::new(kb);
-            let seq = entry_ref.sequence();
-            return Some((
-                match entry_ref.value_type() {
-                    ValueType::Value => Some(v),
-                    ValueType::Deletion | ValueType::RangeDeletion => None,
-                },
-                seq,
-            ));
+            return Some((entry_ref.value_type(), v, entry_ref.sequence()));
         }
         None
     }
 
+    /// The first encoded key at or after `search_key` in internal key order.
+    pub fn lower_bound_key(&self, search_key: &[u8]) -> Option<Vec<u8>> {
+        let search = OrdInternalKey(search_key.to_vec());
+        self.map.lower_bound(&search).map(|(k, _)| k.0)
+    }
+
     /// Iterate over all entries in internal key order (user_key ASC, seq DESC).
     /// With `OrdInternalKey`, the skip list is already in the correct order —
     /// no sorting needed.
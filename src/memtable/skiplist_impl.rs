@@ -1,24 +1,29 @@
-//! A write-serialized, read-concurrent skip list with arena allocation.
+//! A read-concurrent skip list with arena allocation.
 //!
-//! The DB's group commit model guarantees a single leader writes to the memtable
-//! at any time (under write_queue lock), while reads happen concurrently via
-//! shared references.  This means:
+//! By default the DB's group commit model guarantees a single leader writes to
+//! the memtable at any time (under write_queue lock), while reads happen
+//! concurrently via shared references.  This means:
 //! - **Single-writer**: `&self` insert, serialized externally by the DB write_queue lock
 //! - **Concurrent readers**: `&self` iter/get/range, lock-free via atomic pointers
 //!
+//! With `allow_concurrent_memtable_write`, group members insert their own
+//! batches through `insert_concurrent` instead, which links nodes with CAS
+//! and only serializes the arena bump allocation.
+//!
 //! Nodes are arena-allocated in contiguous blocks for cache-friendly level-0
 //! traversal. Each node carries an inline `[AtomicPtr; MAX_HEIGHT]` array,
 //! eliminating a separate heap allocation for next-pointers.
 //!
 //! Max height 12, probability p = 0.25.
 
+#[cfg(test)]
+use std::ops::RangeBounds;
 use std::{
     cell::UnsafeCell,
+    cmp::Ordering as CmpOrdering,
     ptr,
     sync::atomic::{AtomicPtr, AtomicUsize, Ordering},
 };
-#[cfg(test)]
-use std::{cmp::Ordering as CmpOrdering, ops::RangeBounds};
 
 /// Maximum height of the skip list.
 const MAX_HEIGHT: usize = 12;
@@ -35,8 +40,9 @@ const ARENA_MAX_BLOCK: usize = 1 << 20; // 1MB
 
 /// Bump-pointer arena that allocates from doubling blocks (4KB → 1MB cap).
 ///
-/// Only mutated during `insert()` (single-writer); readers follow published
-/// pointers. No per-allocation free — memory is released when the Arena drops.
+/// Only mutated during `insert()` (single-writer) or under the list's
+/// `alloc_lock` (`insert_concurrent`); readers follow published pointers.
+/// No per-allocation free — memory is released when the Arena drops.
 struct Arena {
     blocks: UnsafeCell<Vec<Vec<u8>>>,
     current_offset: UnsafeCell<usize>,
@@ -130,8 +136,11 @@ struct Node<K, V> {
 
 /// A concurrent skip list with single-writer / multi-reader semantics.
 ///
-/// Insert is `&self` (externally serialized by the caller).
+/// Insert is `&self` (externally serialized by the caller);
+/// `insert_concurrent` may run from many threads at once.
 /// Get / iter / range are `&self` (lock-free).
+///
+/// Ordered by `K`'s `Ord` unless built with [`Self::with_comparator`].
 pub struct ConcurrentSkipList<K: Ord + Clone, V: Clone> {
     /// Head pointers for each level.
     head: [AtomicPtr<Node<K, V>>; MAX_HEIGHT],
@@ -146,10 +155,17 @@ pub struct ConcurrentSkipList<K: Ord + Clone, V: Clone> {
     all_nodes: UnsafeCell<Vec<*mut Node<K, V>>>,
     /// Arena backing store for all nodes.
     arena: Arena,
+    /// Serializes arena allocation and `all_nodes` among concurrent inserts.
+    alloc_lock: parking_lot::Mutex<()>,
+    /// Key order.
+    compare: KeyCompareFn<K>,
 }
 
+type KeyCompareFn<K> = Box<dyn Fn(&K, &K) -> CmpOrdering + Send + Sync>;
+
 // SAFETY: Node pointers are stable (arena-allocated, never moved).
-// Single-writer guarantee ensures no concurrent mutations. Readers only follow
+// Single-writer guarantee (or `alloc_lock` plus CAS linking for
+// `insert_concurrent`) ensures no racing mutations. Readers only follow
 // AtomicPtr chains that are fully initialized before publication (Release/Acquire).
 unsafe impl<K: Ord + Clone + Send, V: Clone + Send> Send for ConcurrentSkipList<K, V> {}
 unsafe impl<K: Ord + Clone + Send + Sync, V: Clone + Send + Sync> Sync
@@ -158,7 +174,15 @@ unsafe impl<K: Ord + Clone + Send + Sync, V: Clone + Send + Sync> Sync
 }
 
 impl<K: Ord + Clone, V: Clone> ConcurrentSkipList<K, V> {
-    pub fn new() -> Self {
+    pub fn new() -> Self
+    where
+        K: 'static,
+    {
+        Self::with_comparator(Box::new(K::cmp))
+    }
+
+    /// An empty list ordered by `compare` instead of `K`'s `Ord`.
+    pub fn with_comparator(compare: KeyCompareFn<K>) -> Self {
         Self {
             head: std::array::from_fn(|_| AtomicPtr::new(ptr::null_mut())),
             tail: AtomicPtr::new(ptr::null_mut()),
@@ -166,6 +190,8 @@ impl<K: Ord + Clone, V: Clone> ConcurrentSkipList<K, V> {
             max_height: AtomicUsize::new(1),
             all_nodes: UnsafeCell::new(Vec::new()),
             arena: Arena::new(),
+            alloc_lock: parking_lot::Mutex::new(()),
+            compare,
         }
     }
 
@@ -196,7 +222,7 @@ impl<K: Ord + Clone, V: Clone> ConcurrentSkipList<K, V> {
                 while !next.is_null() {
                     // SAFETY: next is a valid node published via Release.
                     let node = unsafe { &*next };
-                    if node.key >= key {
+                    if (self.compare)(&node.key, &key) != CmpOrdering::Less {
                         break;
                     }
                     current = next;
@@ -210,7 +236,7 @@ impl<K: Ord + Clone, V: Clone> ConcurrentSkipList<K, V> {
                 while !next.is_null() {
                     // SAFETY: next is a valid node published via Release.
                     let node = unsafe { &*next };
-                    if node.key >= key {
+                    if (self.compare)(&node.key, &key) != CmpOrdering::Less {
                         break;
                     }
                     current = next;
@@ -269,13 +295,125 @@ impl<K: Ord + Clone, V: Clone> ConcurrentSkipList<K, V> {
         self.len.fetch_add(1, Ordering::Relaxed);
     }
 
+    /// Insert a key-value pair alongside other `insert_concurrent` calls.
+    /// Each level is linked with a CAS; a level whose CAS loses to a
+    /// neighbouring insert re-searches its splice from the same predecessor.
+    /// Must not run concurrently with `insert`.
+    pub fn insert_concurrent(&self, key: K, value: V) {
+        let height = random_height();
+        self.max_height.fetch_max(height, Ordering::Relaxed);
+
+        let new_node: *mut Node<K, V> = {
+            let _alloc = self.alloc_lock.lock();
+            // SAFETY: `alloc_lock` serializes concurrent inserters, satisfying
+            // the arena allocator contract.
+            let node = unsafe { self.arena.alloc_node() };
+            // SAFETY: as above — no concurrent mutation of all_nodes.
+            unsafe { (*self.all_nodes.get()).push(node) };
+            node
+        };
+        // SAFETY: new_node points to uninitialized arena storage sized/aligned
+        // for Node, and is not reachable until linked below.
+        unsafe {
+            ptr::write(
+                new_node,
+                Node {
+                    key,
+                    value,
+                    height: height as u8,
+                    next: std::array::from_fn(|_| AtomicPtr::new(ptr::null_mut())),
+                },
+            );
+        }
+        // SAFETY: new_node was initialized above and remains arena-owned until drop.
+        let new_ref = unsafe { &*new_node };
+
+        let top = self.max_height.load(Ordering::Relaxed).max(height);
+        let mut prev: [*mut Node<K, V>; MAX_HEIGHT] = [ptr::null_mut(); MAX_HEIGHT];
+        let mut next: [*mut Node<K, V>; MAX_HEIGHT] = [ptr::null_mut(); MAX_HEIGHT];
+        let mut start = ptr::null_mut();
+        for level in (0..top).rev() {
+            (prev[level], next[level]) = self.find_splice(&new_ref.key, start, level);
+            start = prev[level];
+        }
+
+        // Link bottom-up, so a node reachable at some level is already
+        // reachable at every level below it.
+        for level in 0..height {
+            loop {
+                new_ref.next[level].store(next[level], Ordering::Relaxed);
+                let slot = self.next_slot(prev[level], level);
+                if slot
+                    .compare_exchange(next[level], new_node, Ordering::AcqRel, Ordering::Acquire)
+                    .is_ok()
+                {
+                    break;
+                }
+                (prev[level], next[level]) = self.find_splice(&new_ref.key, prev[level], level);
+            }
+        }
+
+        // The greatest node inserted so far is the tail. A node with a
+        // level-0 successor leaves the update to that successor.
+        if new_ref.next[0].load(Ordering::Acquire).is_null() {
+            let mut tail = self.tail.load(Ordering::Acquire);
+            loop {
+                // SAFETY: a non-null tail is a published node.
+                if !tail.is_null()
+                    && (self.compare)(unsafe { &(*tail).key }, &new_ref.key) == CmpOrdering::Greater
+                {
+                    break;
+                }
+                match self.tail.compare_exchange_weak(
+                    tail,
+                    new_node,
+                    Ordering::AcqRel,
+                    Ordering::Acquire,
+                ) {
+                    Ok(_) => break,
+                    Err(current) => tail = current,
+                }
+            }
+        }
+
+        self.len.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The link slot after `node` at `level`; null means the head.
+    fn next_slot(&self, node: *mut Node<K, V>, level: usize) -> &AtomicPtr<Node<K, V>> {
+        if node.is_null() {
+            &self.head[level]
+        } else {
+            // SAFETY: non-null predecessors are published nodes.
+            unsafe { &(*node).next[level] }
+        }
+    }
+
+    /// The last node before `key` at `level` and the node after it, walking
+    /// from `start` (null means the head), which must precede `key`.
+    fn find_splice(
+        &self,
+        key: &K,
+        start: *mut Node<K, V>,
+        level: usize,
+    ) -> (*mut Node<K, V>, *mut Node<K, V>) {
+        let mut prev = start;
+        let mut next = self.next_slot(prev, level).load(Ordering::Acquire);
+        while !next.is_null() {
+            // SAFETY: next is a valid node published via Release.
+            let node = unsafe { &*next };
+            if (self.compare)(&node.key, key) != CmpOrdering::Less {
+                break;
+            }
+            prev = next;
+            next = node.next[level].load(Ordering::Acquire);
+        }
+        (prev, next)
+    }
+
     /// Look up a key. Lock-free.
     #[cfg(test)]
-    pub fn get<Q>(&self, key: &Q) -> Option<V>
-    where
-        K: std::borrow::Borrow<Q>,
-        Q: Ord + ?Sized,
-    {
+    pub fn get(&self, key: &K) -> Option<V> {
         let max_h = self.max_height.load(Ordering::Acquire);
         let mut current: *const Node<K, V> = ptr::null();
 
@@ -290,7 +428,7 @@ impl<K: Ord + Clone, V: Clone> ConcurrentSkipList<K, V> {
             while !next.is_null() {
                 // SAFETY: next is a valid node published via Release.
                 let n = unsafe { &*next };
-                match n.key.borrow().cmp(key) {
+                match (self.compare)(&n.key, key) {
                     CmpOrdering::Less => {
                         current = next;
                         next = n.next[level].load(Ordering::Acquire);
@@ -320,7 +458,7 @@ impl<K: Ord + Clone, V: Clone> ConcurrentSkipList<K, V> {
             while !next.is_null() {
                 // SAFETY: next is a valid node published via Release.
                 let n = unsafe { &*next };
-                if n.key < *target {
+                if (self.compare)(&n.key, target) == CmpOrdering::Less {
                     current = next;
                     next = n.next[level].load(Ordering::Acquire);
                 } else {
@@ -343,7 +481,7 @@ impl<K: Ord + Clone, V: Clone> ConcurrentSkipList<K, V> {
         while !ptr.is_null() {
             // SAFETY: ptr is reached through acquired level-0 links.
             let n = unsafe { &*ptr };
-            if n.key >= *target {
+            if (self.compare)(&n.key, target) != CmpOrdering::Less {
                 return Some((n.key.clone(), n.value.clone()));
             }
             ptr = n.next[0].load(Ordering::Acquire);
@@ -425,7 +563,7 @@ impl<K: Ord + Clone, V: Clone> ConcurrentSkipList<K, V> {
             while !next.is_null() {
                 // SAFETY: next is a valid node published via Release.
                 let n = unsafe { &*next };
-                if n.key < *target {
+                if (self.compare)(&n.key, target) == CmpOrdering::Less {
                     current = next;
                     next = n.next[level].load(Ordering::Acquire);
                 } else {
@@ -446,7 +584,7 @@ impl<K: Ord + Clone, V: Clone> ConcurrentSkipList<K, V> {
         while !ptr.is_null() {
             // SAFETY: ptr is reached through acquired level-0 links.
             let n = unsafe { &*ptr };
-            if n.key >= *target {
+            if (self.compare)(&n.key, target) != CmpOrdering::Less {
                 return ptr as *const ();
             }
             ptr = n.next[0].load(Ordering::Acquire);
@@ -478,7 +616,7 @@ impl<K: Ord + Clone, V: Clone> ConcurrentSkipList<K, V> {
             while !next.is_null() {
                 // SAFETY: next is a valid node published via Release.
                 let n = unsafe { &*next };
-                if n.key < *target {
+                if (self.compare)(&n.key, target) == CmpOrdering::Less {
                     current = next;
                     next = n.next[level].load(Ordering::Acquire);
                 } else {
@@ -503,7 +641,7 @@ impl<K: Ord + Clone, V: Clone> ConcurrentSkipList<K, V> {
         if !ge_ptr.is_null() {
             // SAFETY: ge_ptr was returned by this skiplist's seek_ge_raw.
             let (k, _) = unsafe { self.node_kv(ge_ptr) };
-            if k == target {
+            if (self.compare)(k, target) == CmpOrdering::Equal {
                 return ge_ptr;
             }
         }
@@ -519,7 +657,6 @@ impl<K: Ord + Clone, V: Clone> ConcurrentSkipList<K, V> {
     }
 
     /// Number of entries.
-    #[cfg(test)]
     pub fn len(&self) -> usize {
         self.len.load(Ordering::Relaxed)
     }
@@ -545,7 +682,7 @@ impl<K: Ord + Clone, V: Clone> ConcurrentSkipList<K, V> {
     }
 }
 
-impl<K: Ord + Clone, V: Clone> Default for ConcurrentSkipList<K, V> {
+impl<K: Ord + Clone + 'static, V: Clone> Default for ConcurrentSkipList<K, V> {
     fn default() -> Self {
         Self::new()
     }
@@ -786,6 +923,38 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_concurrent_inserts() {
+        use std::sync::Arc;
+        use std::thread;
+
+        let sl = Arc::new(ConcurrentSkipList::new());
+        let handles: Vec<_> = (0..8u32)
+            .map(|t| {
+                let sl = Arc::clone(&sl);
+                thread::spawn(move || {
+                    // Interleaved keys so threads contend for the same splices.
+                    for i in 0..2000u32 {
+                        sl.insert_concurrent(i * 8 + t, t);
+                    }
+                })
+            })
+            .collect();
+        for h in handles {
+            h.join().unwrap();
+        }
+
+        assert_eq!(sl.len(), 16000);
+        let keys: Vec<u32> = sl.iter().map(|(k, _)| k).collect();
+        assert_eq!(keys, (0..16000).collect::<Vec<_>>());
+        for k in (0..16000).step_by(97) {
+            assert_eq!(sl.get(&k), Some(k % 8));
+        }
+        // SAFETY: tail_ptr returns a node of this list.
+        let (last, _) = unsafe { sl.node_kv(sl.tail_ptr()) };
+        assert_eq!(*last, 15999);
+    }
+
     #[test]
     fn test_vec_u8_keys() {
         let sl = ConcurrentSkipList::new();
@@ -799,7 +968,24 @@ mod tests {
         assert_eq!(items[1].0, b"banana");
         assert_eq!(items[2].0, b"cherry");
 
-        assert_eq!(sl.get(b"banana".as_slice()), Some(b"yellow".to_vec()));
+        assert_eq!(sl.get(&b"banana".to_vec()), Some(b"yellow".to_vec()));
+    }
+
+    #[test]
+    fn test_custom_comparator() {
+        let sl = ConcurrentSkipList::with_comparator(Box::new(|a: &u32, b: &u32| b.cmp(a)));
+        for k in [3, 1, 4, 5, 9, 2, 6] {
+            sl.insert(k, k * 10);
+        }
+        let keys: Vec<_> = sl.iter().map(|(k, _)| k).collect();
+        assert_eq!(keys, vec![9, 6, 5, 4, 3, 2, 1]);
+        assert_eq!(sl.get(&4), Some(40));
+        assert_eq!(sl.lower_bound(&7), Some((6, 60)));
+        // SAFETY: pointers returned by the seeks are nodes of `sl`.
+        unsafe {
+            assert_eq!(*sl.node_kv(sl.seek_lt_raw(&4)).0, 5);
+            assert_eq!(*sl.node_kv(sl.seek_le_raw(&4)).0, 4);
+        }
     }
 
     #[test]
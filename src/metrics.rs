@@ -0,0 +1,532 @@
+//! OpenMetrics text exposition of engine metrics, for Prometheus scrapes.
+//!
+//! Every sample of a DB carries `db` and `cf` labels; per-level metrics
+//! add `level`. Block cache pools are reported once each under a `cache`
+//! label, however many DBs share them, and the `mmdb_block_cache_info`
+//! metric maps each DB and column family to its pool.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io;
+use std::sync::Arc;
+
+use crate::cache::block_cache::BlockCachePool;
+use crate::db::DB;
+use crate::listener::WriteStallCondition;
+use crate::stats::{HistogramSnapshot, Statistics};
+
+/// Quantiles reported for each latency summary.
+const QUANTILES: [(f64, &str); 3] = [(50.0, "0.5"), (99.0, "0.99"), (99.9, "0.999")];
+
+/// State of one column family, gathered by [`DB::family_metrics`].
+pub(crate) struct FamilyMetrics {
+    pub cf: String,
+    pub statistics: Statistics,
+    /// Whether `statistics` holds the tickers and histograms.
+    pub statistics_enabled: bool,
+    pub level_files: Vec<usize>,
+    pub level_bytes: Vec<u64>,
+    pub immutable_memtables: usize,
+    pub pending_compaction_bytes: u64,
+    pub dead_keys: usize,
+    pub write_stall: WriteStallCondition,
+    pub block_cache_pinned_bytes: u64,
+    pub block_cache: Arc<BlockCachePool>,
+}
+
+/// Renders the metrics of one or more DBs in the OpenMetrics text format.
+///
+/// Metric names and labels are stable. DBs sharing a
+/// [`BlockCachePool`] report its usage once; register the pool with
+/// [`add_block_cache_pool`](Self::add_block_cache_pool) to name it,
+/// otherwise it is named after the first DB using it. For a single DB,
+/// [`DB::metrics_openmetrics`] is a shorthand.
+///
+/// ```no_run
+/// use std::sync::Arc;
+/// use mmdb::{BlockCachePool, DB, DbOptions, MetricsExporter};
+///
+/// # fn main() -> mmdb::Result<()> {
+/// let pool = Arc::new(BlockCachePool::new(256 << 20));
+/// let options = DbOptions {
+///     block_cache: Some(pool.clone()),
+///     ..Default::default()
+/// };
+/// let orders = DB::open(options.clone(), "orders")?;
+/// let users = DB::open(options, "users")?;
+///
+/// let mut exporter = MetricsExporter::new();
+/// exporter
+///     .add_db("orders", &orders)
+///     .add_db("users", &users)
+///     .add_block_cache_pool("shared", &pool);
+/// let body = exporter.render();
+/// # drop(body);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Default)]
+pub struct MetricsExporter<'a> {
+    dbs: Vec<(String, &'a DB)>,
+    pools: Vec<(String, &'a Arc<BlockCachePool>)>,
+}
+
+impl<'a> MetricsExporter<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Report `db` and its column families under the label `db="name"`.
+    pub fn add_db(&mut self, name: impl Into<String>, db: &'a DB) -> &mut Self {
+        self.dbs.push((name.into(), db));
+        self
+    }
+
+    /// Report `pool` under the label `cache="name"`, whether or not an
+    /// added DB uses it.
+    pub fn add_block_cache_pool(
+        &mut self,
+        name: impl Into<String>,
+        pool: &'a Arc<BlockCachePool>,
+    ) -> &mut Self {
+        self.pools.push((name.into(), pool));
+        self
+    }
+
+    /// The exposition, terminated by `# EOF`.
+    pub fn render(&self) -> String {
+        let families: Vec<(&str, Vec<FamilyMetrics>)> = self
+            .dbs
+            .iter()
+            .map(|(name, db)| (name.as_str(), db.family_metrics()))
+            .collect();
+
+        // Name each distinct pool once: registered names first, then the
+        // first DB using an unregistered one.
+        let mut pools: Vec<(String, Arc<BlockCachePool>)> = Vec::new();
+        let mut pool_names: HashMap<*const BlockCachePool, usize> = HashMap::new();
+        let registered = self
+            .pools
+            .iter()
+            .map(|(name, pool)| (name.clone(), Arc::clone(pool)));
+        let used = families.iter().flat_map(|(db, cfs)| {
+            cfs.iter()
+                .map(move |cf| (db.to_string(), cf.block_cache.clone()))
+        });
+        for (name, pool) in registered.chain(used) {
+            pool_names.entry(Arc::as_ptr(&pool)).or_insert_with(|| {
+                pools.push((name, pool));
+                pools.len() - 1
+            });
+        }
+
+        let samples = |value: &dyn Fn(&FamilyMetrics) -> Option<String>| -> Vec<Sample> {
+            families
+                .iter()
+                .flat_map(|(db, cfs)| cfs.iter().map(move |cf| (db, cf)))
+                .filter_map(|(db, cf)| {
+                    value(cf).map(|value| Sample::new(db_labels(db, &cf.cf, &[]), value))
+                })
+                .collect()
+        };
+        let per_level = |value: &dyn Fn(&FamilyMetrics) -> Option<Vec<String>>| -> Vec<Sample> {
+            let mut out = Vec::new();
+            for (db, cfs) in &families {
+                for cf in cfs {
+                    for (level, value) in value(cf).into_iter().flatten().enumerate() {
+                        let labels = db_labels(db, &cf.cf, &[("level", &level.to_string())]);
+                        out.push(Sample::new(labels, value));
+                    }
+                }
+            }
+            out
+        };
+        // Extended tickers only exist with `enable_statistics`.
+        let ticker = |value: fn(&Statistics) -> u64| {
+            samples(&|cf: &FamilyMetrics| {
+                cf.statistics_enabled
+                    .then(|| value(&cf.statistics).to_string())
+            })
+        };
+        let counter = |value: fn(&Statistics) -> u64| {
+            samples(&|cf: &FamilyMetrics| Some(value(&cf.statistics).to_string()))
+        };
+
+        let mut out = String::new();
+        let mut family = |name: &str, kind: Kind, help: &str, samples: Vec<Sample>| {
+            write_family(&mut out, name, kind, help, &samples);
+        };
+
+        family(
+            "mmdb_bytes_written",
+            Kind::Counter,
+            "Bytes written by the user.",
+            counter(|s| s.bytes_written),
+        );
+        family(
+            "mmdb_bytes_read",
+            Kind::Counter,
+            "Bytes read by the user.",
+            counter(|s| s.bytes_read),
+        );
+        family(
+            "mmdb_compactions",
+            Kind::Counter,
+            "Compactions completed.",
+            counter(|s| s.compactions_completed),
+        );
+        family(
+            "mmdb_compaction_bytes_written",
+            Kind::Counter,
+            "Bytes written by compactions.",
+            counter(|s| s.compaction_bytes_written),
+        );
+        family(
+            "mmdb_flushes",
+            Kind::Counter,
+            "Memtable flushes completed.",
+            counter(|s| s.flushes_completed),
+        );
+        family(
+            "mmdb_block_cache_hits",
+            Kind::Counter,
+            "Block cache hits.",
+            counter(|s| s.block_cache_hits),
+        );
+        family(
+            "mmdb_block_cache_misses",
+            Kind::Counter,
+            "Block cache misses.",
+            counter(|s| s.block_cache_misses),
+        );
+        family(
+            "mmdb_bloom_filter_checked",
+            Kind::Counter,
+            "SST bloom filter probes by point lookups.",
+            ticker(|s| s.bloom_filter_checked),
+        );
+        family(
+            "mmdb_bloom_filter_useful",
+            Kind::Counter,
+            "Bloom filter probes that ruled the key out.",
+            ticker(|s| s.bloom_filter_useful),
+        );
+        family(
+            "mmdb_bloom_filter_false_positives",
+            Kind::Counter,
+            "Bloom filter probes passed for a key the SST did not hold.",
+            ticker(|s| s.bloom_filter_false_positive),
+        );
+        family(
+            "mmdb_memtable_get_hits",
+            Kind::Counter,
+            "Point lookups answered by a memtable.",
+            ticker(|s| s.get_hits_memtable),
+        );
+        family(
+            "mmdb_get_hits",
+            Kind::Counter,
+            "Point lookups answered by an SST, by level.",
+            per_level(&|cf| {
+                cf.statistics_enabled
+                    .then(|| to_strings(&cf.statistics.get_hits_per_level))
+            }),
+        );
+        family(
+            "mmdb_get_read_bytes",
+            Kind::Counter,
+            "Key and value bytes returned by point lookups from SSTs, by level.",
+            per_level(&|cf| {
+                cf.statistics_enabled
+                    .then(|| to_strings(&cf.statistics.bytes_read_per_level))
+            }),
+        );
+        family(
+            "mmdb_iter_tombstones_skipped",
+            Kind::Counter,
+            "Deleted keys iterators stepped over.",
+            ticker(|s| s.iter_tombstones_skipped),
+        );
+        family(
+            "mmdb_write_stall_seconds",
+            Kind::Counter,
+            "Time writers spent delayed or stopped by write stalls.",
+            samples(&|cf| {
+                cf.statistics_enabled
+                    .then(|| seconds(cf.statistics.stall_micros))
+            }),
+        );
+        family(
+            "mmdb_operation_latency_seconds",
+            Kind::Summary,
+            "Latency of engine operations.",
+            families
+                .iter()
+                .flat_map(|(db, cfs)| cfs.iter().map(move |cf| (db, cf)))
+                .filter(|(_, cf)| cf.statistics_enabled)
+                .flat_map(|(db, cf)| latency_samples(db, cf))
+                .collect(),
+        );
+        family(
+            "mmdb_level_files",
+            Kind::Gauge,
+            "SST files per level.",
+            per_level(&|cf| Some(to_strings(&cf.level_files))),
+        );
+        family(
+            "mmdb_level_bytes",
+            Kind::Gauge,
+            "Total SST size per level.",
+            per_level(&|cf| Some(to_strings(&cf.level_bytes))),
+        );
+        family(
+            "mmdb_immutable_memtables",
+            Kind::Gauge,
+            "Frozen memtables waiting for a flush.",
+            samples(&|cf| Some(cf.immutable_memtables.to_string())),
+        );
+        family(
+            "mmdb_pending_compaction_bytes",
+            Kind::Gauge,
+            "Estimated bytes compaction must rewrite to bring the levels within their limits.",
+            samples(&|cf| Some(cf.pending_compaction_bytes.to_string())),
+        );
+        family(
+            "mmdb_dead_keys",
+            Kind::Gauge,
+            "Keys registered by lazy_delete and not yet compacted away.",
+            samples(&|cf| Some(cf.dead_keys.to_string())),
+        );
+        family(
+            "mmdb_write_stall",
+            Kind::StateSet,
+            "Write stall condition.",
+            families
+                .iter()
+                .flat_map(|(db, cfs)| cfs.iter().map(move |cf| (db, cf)))
+                .flat_map(|(db, cf)| {
+                    [
+                        (WriteStallCondition::Normal, "normal"),
+                        (WriteStallCondition::Delayed, "delayed"),
+                        (WriteStallCondition::Stopped, "stopped"),
+                    ]
+                    .into_iter()
+                    .map(move |(condition, state)| {
+                        let value = u8::from(cf.write_stall == condition);
+                        Sample::new(
+                            db_labels(db, &cf.cf, &[("mmdb_write_stall", state)]),
+                            value.to_string(),
+                        )
+                    })
+                })
+                .collect(),
+        );
+        family(
+            "mmdb_block_cache_pinned_bytes",
+            Kind::Gauge,
+            "Bytes of L0 blocks pinned in the block cache.",
+            samples(&|cf| Some(cf.block_cache_pinned_bytes.to_string())),
+        );
+        family(
+            "mmdb_block_cache",
+            Kind::Info,
+            "Block cache pool used by each DB and column family.",
+            families
+                .iter()
+                .flat_map(|(db, cfs)| cfs.iter().map(move |cf| (db, cf)))
+                .map(|(db, cf)| {
+                    let pool = &pools[pool_names[&Arc::as_ptr(&cf.block_cache)]].0;
+                    Sample::new(db_labels(db, &cf.cf, &[("cache", pool)]), "1".to_string())
+                })
+                .collect(),
+        );
+        let pool_gauge = |value: fn(&BlockCachePool) -> u64| -> Vec<Sample> {
+            pools
+                .iter()
+                .map(|(name, pool)| {
+                    Sample::new(labels(&[("cache", name)]), value(pool).to_string())
+                })
+                .collect()
+        };
+        family(
+            "mmdb_block_cache_capacity_bytes",
+            Kind::Gauge,
+            "Block cache pool capacity.",
+            pool_gauge(BlockCachePool::capacity_bytes),
+        );
+        family(
+            "mmdb_block_cache_usage_bytes",
+            Kind::Gauge,
+            "Bytes held by the block cache pool, excluding pinned blocks.",
+            pool_gauge(BlockCachePool::usage_bytes),
+        );
+        family(
+            "mmdb_block_cache_entries",
+            Kind::Gauge,
+            "Blocks held by the block cache pool, excluding pinned blocks.",
+            pool_gauge(BlockCachePool::entry_count),
+        );
+        out.push_str("# EOF\n");
+        out
+    }
+
+    /// Write [`Self::render`]'s exposition to `writer`.
+    pub fn write_to(&self, mut writer: impl io::Write) -> io::Result<()> {
+        writer.write_all(self.render().as_bytes())
+    }
+}
+
+/// One sample line: the name suffix (`_sum`, `_count`; empty for the
+/// kind's default), the rendered label set and the value.
+struct Sample {
+    suffix: &'static str,
+    labels: String,
+    value: String,
+}
+
+impl Sample {
+    fn new(labels: String, value: String) -> Self {
+        Self {
+            suffix: "",
+            labels,
+            value,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Counter,
+    Gauge,
+    Summary,
+    StateSet,
+    Info,
+}
+
+fn write_family(out: &mut String, name: &str, kind: Kind, help: &str, samples: &[Sample]) {
+    if samples.is_empty() {
+        return;
+    }
+    let (type_name, default_suffix) = match kind {
+        Kind::Counter => ("counter", "_total"),
+        Kind::Gauge => ("gauge", ""),
+        Kind::Summary => ("summary", ""),
+        Kind::StateSet => ("stateset", ""),
+        Kind::Info => ("info", "_info"),
+    };
+    let _ = writeln!(out, "# TYPE {name} {type_name}");
+    let _ = writeln!(out, "# HELP {name} {help}");
+    for sample in samples {
+        let suffix = if sample.suffix.is_empty() {
+            default_suffix
+        } else {
+            sample.suffix
+        };
+        let _ = writeln!(out, "{name}{suffix}{} {}", sample.labels, sample.value);
+    }
+}
+
+/// Quantile, sum and count samples of every histogram of `cf`.
+fn latency_samples(db: &str, cf: &FamilyMetrics) -> Vec<Sample> {
+    let s = &cf.statistics;
+    let histograms: [(&str, &HistogramSnapshot); 7] = [
+        ("get", &s.get_micros),
+        ("write", &s.write_micros),
+        ("seek", &s.seek_micros),
+        ("next", &s.next_micros),
+        ("flush", &s.flush_micros),
+        ("compaction", &s.compaction_micros),
+        ("wal_sync", &s.wal_sync_micros),
+    ];
+    let mut out = Vec::new();
+    for (operation, histogram) in histograms {
+        let op = [("operation", operation)];
+        for (percentile, quantile) in QUANTILES {
+            out.push(Sample::new(
+                db_labels(
+                    db,
+                    &cf.cf,
+                    &[("operation", operation), ("quantile", quantile)],
+                ),
+                seconds(histogram.percentile(percentile)),
+            ));
+        }
+        out.push(Sample {
+            suffix: "_sum",
+            labels: db_labels(db, &cf.cf, &op),
+            value: seconds(histogram.sum),
+        });
+        out.push(Sample {
+            suffix: "_count",
+            labels: db_labels(db, &cf.cf, &op),
+            value: histogram.count.to_string(),
+        });
+    }
+    out
+}
+
+fn db_labels(db: &str, cf: &str, extra: &[(&str, &str)]) -> String {
+    let mut all = vec![("db", db), ("cf", cf)];
+    all.extend_from_slice(extra);
+    labels(&all)
+}
+
+fn labels(pairs: &[(&str, &str)]) -> String {
+    let mut out = String::from("{");
+    for (i, (name, value)) in pairs.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        out.push_str(name);
+        out.push_str("=\"");
+        for c in value.chars() {
+            match c {
+                '\\' => out.push_str("\\\\"),
+                '"' => out.push_str("\\\""),
+                '\n' => out.push_str("\\n"),
+                c => out.push(c),
+            }
+        }
+        out.push('"');
+    }
+    out.push('}');
+    out
+}
+
+fn to_strings<T: ToString>(values: &[T]) -> Vec<String> {
+    values.iter().map(T::to_string).collect()
+}
+
+fn seconds(micros: u64) -> String {
+    (micros as f64 / 1_000_000.0).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_label_values_are_escaped() {
+        assert_eq!(
+            labels(&[("db", "a\"b\\c\nd"), ("cf", "default")]),
+            r#"{db="a\"b\\c\nd",cf="default"}"#
+        );
+    }
+
+    #[test]
+    fn test_family_suffixes() {
+        let mut out = String::new();
+        write_family(
+            &mut out,
+            "mmdb_flushes",
+            Kind::Counter,
+            "Flushes.",
+            &[Sample::new(labels(&[("db", "a")]), "3".to_string())],
+        );
+        write_family(&mut out, "mmdb_empty", Kind::Gauge, "Nothing.", &[]);
+        assert_eq!(
+            out,
+            "# TYPE mmdb_flushes counter\n# HELP mmdb_flushes Flushes.\nmmdb_flushes_total{db=\"a\"} 3\n"
+        );
+    }
+}
@@ -1,7 +1,14 @@
 //! Configuration options for MMDB.
 
-use std::{fmt, sync::Arc};
+use std::{
+    cmp::Ordering,
+    fmt,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
+use crate::error::{Error, Result};
+use crate::listener::EventListener;
 use crate::sst::format::CompressionType;
 use crate::types::SequenceNumber;
 
@@ -21,14 +28,22 @@ pub struct DbOptions {
     pub error_if_exists: bool,
     /// Size of a single MemTable in bytes before it is frozen.
     pub write_buffer_size: usize,
-    /// Currently unused compatibility option.
-    ///
-    /// MemTable freezes are flushed synchronously by the write path, so there
-    /// is no immutable-MemTable queue for this value to bound. The field remains
-    /// accepted for configuration compatibility and may be implemented or
-    /// removed in a future major release.
+    /// Maximum number of frozen MemTables waiting for a background flush.
+    /// A full MemTable is frozen and queued, and the writer moves on to a
+    /// fresh one; writes stall only while this many are already queued.
+    /// Must be >= 1 (validated at [`crate::DB::open`]).
     pub max_immutable_memtables: usize,
-    /// Number of L0 files that triggers compaction.
+    /// Let group commit followers insert their own batches into the
+    /// MemTable in parallel once the leader has written the group's WAL
+    /// records, instead of the leader inserting every batch in turn.
+    pub allow_concurrent_memtable_write: bool,
+    /// Pipeline group commit: once a group's WAL records are written, the
+    /// next group starts its WAL write while this one is still applying to
+    /// the MemTable. Writes still become visible in sequence order.
+    pub enable_pipelined_write: bool,
+    /// Number of L0 files that triggers compaction. Under
+    /// [`CompactionStyle::Universal`], the number of sorted runs (each L0
+    /// file plus each non-empty level) that triggers compaction.
     pub l0_compaction_trigger: usize,
     /// Target size for SST files in bytes.
     pub target_file_size_base: u64,
@@ -69,6 +84,10 @@ pub struct DbOptions {
     /// Optional compaction filter. Wrapped in `Arc` so it survives Clone
     /// and is shared with background compaction threads.
     pub compaction_filter: Option<Arc<dyn CompactionFilter>>,
+    /// Optional merge operator. Required for [`crate::DB::merge`]; a DB that
+    /// already holds merge operands must be reopened with an operator of the
+    /// same [`name`](MergeOperator::name) to read them.
+    pub merge_operator: Option<Arc<dyn MergeOperator>>,
 
     // ---- Compaction parallelism (RocksDB: increase_parallelism) ----
     /// Maximum number of background compaction threads. Default: 1.
@@ -80,6 +99,10 @@ pub struct DbOptions {
     /// using target-level file boundaries as split points. Effective only when the
     /// target level has enough files to split on.
     pub max_subcompactions: usize,
+    /// Number of background threads flushing frozen MemTables. Default: 1.
+    /// Flushes may build their SSTs in parallel but are installed oldest
+    /// first. RocksDB equivalent: `max_background_flushes`.
+    pub max_background_flushes: usize,
 
     // ---- Cache behavior ----
     /// Eagerly warm the index-entry cache and pin each newly-flushed L0
@@ -118,6 +141,74 @@ pub struct DbOptions {
     /// **`block_cache_capacity` is ignored** — capacity belongs to the
     /// pool.
     pub block_cache: Option<Arc<crate::cache::block_cache::BlockCachePool>>,
+    /// Time-to-live applied to every put that does not carry its own (see
+    /// [`DB::put_with_ttl`](crate::DB::put_with_ttl)). Expired entries are
+    /// hidden from reads and dropped by compaction. `None` (the default)
+    /// keeps values until they are overwritten or deleted.
+    pub default_ttl: Option<Duration>,
+    /// Time source for TTL expiry. Default: [`SystemClock`].
+    pub clock: Arc<dyn Clock>,
+    /// Order of user keys. Default: [`BytewiseComparator`]. Its
+    /// [`name`](Comparator::name) is recorded in the MANIFEST; reopening
+    /// with a comparator of a different name fails with
+    /// [`ErrorKind::InvalidArgument`](crate::ErrorKind::InvalidArgument).
+    pub comparator: Arc<dyn Comparator>,
+
+    // ---- Key-value separation ----
+    /// Values of at least this many bytes are written to blob files at
+    /// flush time, and SSTs store a small handle in their place, so
+    /// compaction rewrites the handle instead of the value. `None` (the
+    /// default) keeps every value inline. Values with a TTL always stay
+    /// inline.
+    pub min_blob_size: Option<usize>,
+    /// Compaction relocates the live values of a blob file whose garbage
+    /// ratio (unreferenced bytes / total bytes) exceeds this, so the file
+    /// can be deleted. Files that are entirely garbage are deleted
+    /// regardless. Must be in `[0.0, 1.0]`; `1.0` disables relocation.
+    /// Default: 0.5.
+    pub blob_garbage_collection_threshold: f64,
+
+    // ---- Compaction style ----
+    /// How compaction picks its inputs. Default: [`CompactionStyle::Leveled`].
+    /// Not persisted: a DB written under one style can be reopened under
+    /// the other.
+    pub compaction_style: CompactionStyle,
+    /// Tuning for [`CompactionStyle::Universal`]; ignored otherwise.
+    pub universal_compaction: UniversalCompactionOptions,
+    /// Tuning for [`CompactionStyle::Fifo`]; ignored otherwise.
+    pub fifo_compaction: FifoCompactionOptions,
+    /// How WAL replay at open handles corrupted records. Default:
+    /// [`WalRecoveryMode::TolerateCorruptedTailRecords`]. Whatever is
+    /// skipped is reported by [`crate::DB::wal_recovery_summary`].
+    pub wal_recovery_mode: WalRecoveryMode,
+    /// Compression applied to each WAL record (write batch) before it is
+    /// written. Recorded at the start of every new WAL, so logs written
+    /// under any setting stay readable after it changes. Default: `None`.
+    pub wal_compression: CompressionType,
+    /// Archive WALs no longer needed for recovery instead of deleting them,
+    /// keeping each for this many seconds so [`crate::DB::get_updates_since`]
+    /// can still read it. 0 (the default) sets no age limit.
+    pub wal_ttl_seconds: u64,
+    /// Archive obsolete WALs, deleting the oldest once the archive exceeds
+    /// this many bytes. 0 (the default) sets no size limit. With both this
+    /// and `wal_ttl_seconds` at 0, obsolete WALs are deleted.
+    pub wal_size_limit: u64,
+
+    // ---- Events ----
+    /// Receive flush, compaction, write stall, background error, file
+    /// deletion and recovery events, in registration order. Each column
+    /// family reports to the listeners in its own options. Default: none.
+    pub listeners: Vec<Arc<dyn EventListener>>,
+
+    // ---- Statistics ----
+    /// Record the latency histograms and extended tickers reported by
+    /// [`crate::DB::statistics`]. Off by default: timed operations then
+    /// skip their clock reads, and the tickers their atomic updates.
+    pub enable_statistics: bool,
+    /// Log the `mmdb.stats` dump (see [`crate::DB::get_property`]) at
+    /// `info` level through `tracing` this often. Each column family
+    /// dumps on its own period. Default: None (never).
+    pub stats_dump_period: Option<Duration>,
 }
 
 impl Default for DbOptions {
@@ -127,6 +218,8 @@ impl Default for DbOptions {
             error_if_exists: false,
             write_buffer_size: 64 * 1024 * 1024, // 64 MB
             max_immutable_memtables: 4,
+            allow_concurrent_memtable_write: false,
+            enable_pipelined_write: false,
             l0_compaction_trigger: 4,
             target_file_size_base: 64 * 1024 * 1024, // 64 MB
             max_bytes_for_level_base: 256 * 1024 * 1024, // 256 MB
@@ -144,12 +237,29 @@ impl Default for DbOptions {
             prefix_len: 0,
             compression_per_level: Vec::new(),
             compaction_filter: None,
+            merge_operator: None,
             max_background_compactions: 1,
             max_subcompactions: 1,
+            max_background_flushes: 1,
             pin_l0_filter_and_index_blocks_in_cache: true,
             block_property_collectors: Vec::new(),
             lazy_delete_compaction_threshold: 0,
             block_cache: None,
+            default_ttl: None,
+            clock: Arc::new(SystemClock),
+            comparator: Arc::new(BytewiseComparator),
+            min_blob_size: None,
+            blob_garbage_collection_threshold: 0.5,
+            compaction_style: CompactionStyle::default(),
+            universal_compaction: UniversalCompactionOptions::default(),
+            fifo_compaction: FifoCompactionOptions::default(),
+            wal_recovery_mode: WalRecoveryMode::default(),
+            wal_compression: CompressionType::None,
+            wal_ttl_seconds: 0,
+            wal_size_limit: 0,
+            listeners: Vec::new(),
+            enable_statistics: false,
+            stats_dump_period: None,
         }
     }
 }
@@ -161,6 +271,11 @@ impl fmt::Debug for DbOptions {
             .field("error_if_exists", &self.error_if_exists)
             .field("write_buffer_size", &self.write_buffer_size)
             .field("max_immutable_memtables", &self.max_immutable_memtables)
+            .field(
+                "allow_concurrent_memtable_write",
+                &self.allow_concurrent_memtable_write,
+            )
+            .field("enable_pipelined_write", &self.enable_pipelined_write)
             .field("l0_compaction_trigger", &self.l0_compaction_trigger)
             .field("target_file_size_base", &self.target_file_size_base)
             .field("max_bytes_for_level_base", &self.max_bytes_for_level_base)
@@ -187,11 +302,16 @@ impl fmt::Debug for DbOptions {
                 "compaction_filter",
                 &self.compaction_filter.as_ref().map(|_| ".."),
             )
+            .field(
+                "merge_operator",
+                &self.merge_operator.as_ref().map(|m| m.name().to_string()),
+            )
             .field(
                 "max_background_compactions",
                 &self.max_background_compactions,
             )
             .field("max_subcompactions", &self.max_subcompactions)
+            .field("max_background_flushes", &self.max_background_flushes)
             .field(
                 "pin_l0_filter_and_index_blocks_in_cache",
                 &self.pin_l0_filter_and_index_blocks_in_cache,
@@ -205,10 +325,121 @@ impl fmt::Debug for DbOptions {
                 &self.lazy_delete_compaction_threshold,
             )
             .field("block_cache", &self.block_cache.as_ref().map(|_| ".."))
+            .field("default_ttl", &self.default_ttl)
+            .field("comparator", &self.comparator.name())
+            .field("min_blob_size", &self.min_blob_size)
+            .field(
+                "blob_garbage_collection_threshold",
+                &self.blob_garbage_collection_threshold,
+            )
+            .field("compaction_style", &self.compaction_style)
+            .field("universal_compaction", &self.universal_compaction)
+            .field("fifo_compaction", &self.fifo_compaction)
+            .field("wal_recovery_mode", &self.wal_recovery_mode)
+            .field("wal_compression", &self.wal_compression)
+            .field("wal_ttl_seconds", &self.wal_ttl_seconds)
+            .field("wal_size_limit", &self.wal_size_limit)
+            .field("listeners", &self.listeners.len())
+            .field("enable_statistics", &self.enable_statistics)
+            .field("stats_dump_period", &self.stats_dump_period)
             .finish()
     }
 }
 
+/// Compaction strategy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CompactionStyle {
+    /// Each level L1+ is one sorted run, `max_bytes_for_level_multiplier`
+    /// times larger than the one above; data moves down one level at a
+    /// time. Lower space and read amplification.
+    #[default]
+    Leveled,
+    /// Each L0 file and each non-empty level is a sorted run, and
+    /// compaction merges adjacent runs of similar size (see
+    /// [`UniversalCompactionOptions`]). Lower write amplification, at the
+    /// cost of more runs to read and more temporary space.
+    Universal,
+    /// Every file stays in L0 and the oldest files are deleted whole once
+    /// the store outgrows its size budget or its TTL (see
+    /// [`FifoCompactionOptions`]). For data that is only ever appended and
+    /// expires by age, such as logs. L0 file count does not stall writes.
+    Fifo,
+}
+
+/// How WAL replay at open handles a record that fails its integrity checks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WalRecoveryMode {
+    /// Accept a torn record at the end of the newest WAL — the shape a
+    /// crash mid-append leaves behind — and fail on any other corruption.
+    #[default]
+    TolerateCorruptedTailRecords,
+    /// Fail on any corruption, including a torn tail.
+    AbsoluteConsistency,
+    /// Stop replay at the first corruption and discard everything after
+    /// it, including later WAL files. Recovers a consistent prefix of the
+    /// write history.
+    PointInTimeRecovery,
+    /// Drop the rest of the WAL block holding a corrupted record and keep
+    /// replaying. Recovers as much as possible, with no ordering guarantee
+    /// for what is lost.
+    SkipAnyCorruptedRecords,
+}
+
+/// Options for [`CompactionStyle::Universal`].
+#[derive(Debug, Clone)]
+pub struct UniversalCompactionOptions {
+    /// Percentage of slack when comparing run sizes: the next older run
+    /// joins a merge if its size is at most `(100 + size_ratio)%` of the
+    /// runs already picked. Default: 1.
+    pub size_ratio: u32,
+    /// Minimum number of runs merged by a size-ratio compaction. Must be
+    /// >= 2. Default: 2.
+    pub min_merge_width: usize,
+    /// Maximum number of runs merged by a size-ratio compaction. Must be
+    /// >= `min_merge_width`. Default: unlimited.
+    pub max_merge_width: usize,
+    /// When the size of all runs but the oldest exceeds this percentage of
+    /// the oldest run, every run is merged into one. Default: 200.
+    pub max_size_amplification_percent: u64,
+}
+
+impl Default for UniversalCompactionOptions {
+    fn default() -> Self {
+        Self {
+            size_ratio: 1,
+            min_merge_width: 2,
+            max_merge_width: usize::MAX,
+            max_size_amplification_percent: 200,
+        }
+    }
+}
+
+/// Options for [`CompactionStyle::Fifo`].
+#[derive(Debug, Clone)]
+pub struct FifoCompactionOptions {
+    /// Once the SST files add up to more than this many bytes, the oldest
+    /// are deleted until they fit. Default: 1 GiB.
+    pub max_table_files_size: u64,
+    /// Files whose newest data was written longer ago than this are
+    /// deleted, along with every older file. Ages are measured with
+    /// [`DbOptions::clock`]. Default: `None` (no age limit).
+    pub ttl: Option<Duration>,
+    /// Merge the newest L0 files smaller than `target_file_size_base` once
+    /// `l0_compaction_trigger` of them accumulate, to bound the file count.
+    /// Merged data stays in L0. Default: false.
+    pub allow_compaction: bool,
+}
+
+impl Default for FifoCompactionOptions {
+    fn default() -> Self {
+        Self {
+            max_table_files_size: 1024 * 1024 * 1024,
+            ttl: None,
+            allow_compaction: false,
+        }
+    }
+}
+
 /// Preset profiles for common workloads.
 impl DbOptions {
     /// Balanced profile — good for mixed read/write workloads.
@@ -273,6 +504,12 @@ pub struct ReadOptions {
     /// Enforced by DBIterator — no manual bound checking needed.
     /// RocksDB equivalent: `iterate_upper_bound`.
     pub iterate_upper_bound: Option<Vec<u8>>,
+    /// Read as of this user-defined timestamp: only versions with a
+    /// timestamp `<=` it are visible. Must be exactly
+    /// [`Comparator::timestamp_size`] bytes; `None` reads the newest
+    /// version. Only valid when the comparator carries timestamps, and
+    /// rejected below the DB's `full_history_ts_low`.
+    pub timestamp: Option<Vec<u8>>,
 }
 
 impl Default for ReadOptions {
@@ -284,6 +521,7 @@ impl Default for ReadOptions {
             block_property_filters: Vec::new(),
             iterate_lower_bound: None,
             iterate_upper_bound: None,
+            timestamp: None,
         }
     }
 }
@@ -297,6 +535,7 @@ impl fmt::Debug for ReadOptions {
             .field("block_property_filters", &self.block_property_filters.len())
             .field("iterate_lower_bound", &self.iterate_lower_bound)
             .field("iterate_upper_bound", &self.iterate_upper_bound)
+            .field("timestamp", &self.timestamp)
             .finish()
     }
 }
@@ -312,6 +551,140 @@ pub struct WriteOptions {
     pub no_slowdown: bool,
 }
 
+/// Options for [`DB::ingest_external_file`](crate::DB::ingest_external_file).
+#[derive(Debug, Clone, Default)]
+pub struct IngestExternalFileOptions {
+    /// Hard-link the files into the DB and remove the originals instead of
+    /// copying them. Falls back to a copy when linking fails (e.g. across
+    /// filesystems). Default: false.
+    pub move_files: bool,
+    /// Fail with [`ErrorKind::InvalidArgument`](crate::ErrorKind::InvalidArgument)
+    /// when a file overlaps keys in the active memtable or one waiting to
+    /// be flushed, instead of flushing them first. Default: false.
+    pub fail_if_memtable_overlap: bool,
+}
+
+/// Wall-clock source for TTL expiry. Implement it to control time in tests.
+pub trait Clock: Send + Sync {
+    /// Milliseconds since the Unix epoch.
+    fn now_millis(&self) -> u64;
+}
+
+/// The system wall clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_millis(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as u64)
+    }
+}
+
+/// Total order over user keys.
+///
+/// Two keys may compare [`Equal`](Ordering::Equal) only if they are
+/// byte-for-byte identical: point lookups, bloom filters and key
+/// deduplication all match keys by their bytes.
+pub trait Comparator: Send + Sync {
+    /// Stable identifier, persisted in the MANIFEST. Change it whenever the
+    /// order changes.
+    fn name(&self) -> &str;
+
+    /// Three-way comparison of two user keys.
+    fn compare(&self, a: &[u8], b: &[u8]) -> Ordering;
+
+    /// Width in bytes of the user-defined timestamp carried after every
+    /// key, or 0 (the default) for none.
+    ///
+    /// With timestamps, [`compare`](Self::compare) only ever sees keys with
+    /// the timestamp stripped. Timestamps themselves are compared bytewise
+    /// (encode integers big-endian), larger meaning newer, and the versions
+    /// of one key are ordered newest first. The width is part of the
+    /// on-disk format: give comparators of different widths different
+    /// [`name`](Self::name)s.
+    fn timestamp_size(&self) -> usize {
+        0
+    }
+}
+
+impl fmt::Debug for dyn Comparator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Comparator({})", self.name())
+    }
+}
+
+/// Lexicographic byte order, the default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BytewiseComparator;
+
+impl BytewiseComparator {
+    /// The name recorded for the default order, also assumed for stores
+    /// whose MANIFEST predates comparator names.
+    pub const NAME: &'static str = "mmdb.BytewiseComparator";
+}
+
+impl Comparator for BytewiseComparator {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    fn compare(&self, a: &[u8], b: &[u8]) -> Ordering {
+        a.cmp(b)
+    }
+}
+
+/// [`BytewiseComparator`] with an 8-byte timestamp, a big-endian `u64`
+/// (see [`DB::put_with_timestamp`](crate::DB::put_with_timestamp)).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BytewiseComparatorWithU64Ts;
+
+impl BytewiseComparatorWithU64Ts {
+    pub const NAME: &'static str = "mmdb.BytewiseComparator.u64ts";
+}
+
+impl Comparator for BytewiseComparatorWithU64Ts {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    fn compare(&self, a: &[u8], b: &[u8]) -> Ordering {
+        a.cmp(b)
+    }
+
+    fn timestamp_size(&self) -> usize {
+        8
+    }
+}
+
+/// Options for [`TransactionDB`](crate::TransactionDB).
+#[derive(Debug, Clone)]
+pub struct TransactionDbOptions {
+    /// How long a transaction waits for a key lock held by another
+    /// transaction before failing with [`ErrorKind::TimedOut`](crate::ErrorKind::TimedOut).
+    /// Default: 1 second.
+    pub lock_timeout: Duration,
+    /// Number of independently locked stripes in the key lock table.
+    /// More stripes reduce contention between unrelated keys. Default: 16.
+    pub num_stripes: usize,
+    /// Walk the wait-for graph before blocking on a lock and fail with
+    /// [`ErrorKind::Busy`](crate::ErrorKind::Busy) if waiting would
+    /// deadlock. When disabled, deadlocked transactions time out instead.
+    /// Default: true.
+    pub deadlock_detect: bool,
+}
+
+impl Default for TransactionDbOptions {
+    fn default() -> Self {
+        Self {
+            lock_timeout: Duration::from_secs(1),
+            num_stripes: 16,
+            deadlock_detect: true,
+        }
+    }
+}
+
 /// Decision returned by a compaction filter.
 #[derive(Debug)]
 pub enum CompactionFilterDecision {
@@ -341,8 +714,9 @@ pub enum CompactionFilterDecision {
 ///   released. Ordinary DB iterators pin a SuperVersion for their own
 ///   read sequence and are **not** registered in the snapshot list; they
 ///   do not by themselves pause filtering.
-/// - The entry is a `Value`. `Deletion` and `RangeDeletion` entries are never
-///   passed to the filter.
+/// - The entry is a `Value`. `Deletion`, `RangeDeletion` and `Merge` entries
+///   are never passed to the filter (a merge chain collapsed into a `Value`
+///   by the same compaction is filtered as that `Value`).
 pub trait CompactionFilter: Send + Sync {
     fn filter(&self, level: usize, key: &[u8], value: &[u8]) -> CompactionFilterDecision;
 
@@ -364,6 +738,79 @@ impl fmt::Debug for dyn CompactionFilter {
     }
 }
 
+/// Associative read-modify-write operator for [`crate::DB::merge`].
+///
+/// Merge operands are stored as-is and folded lazily: point reads and
+/// iterators fold every visible operand on top of the newest base value,
+/// and flush/compaction collapse operand chains whenever the base value is
+/// known (or the output is the bottommost level, where no older version can
+/// exist). Above the bottommost level, [`partial_merge`](Self::partial_merge)
+/// may combine adjacent operands without a base value.
+///
+/// Both methods must be deterministic: the same inputs may be folded more
+/// than once, at different times, by different threads.
+pub trait MergeOperator: Send + Sync {
+    /// Stable identifier for this operator.
+    fn name(&self) -> &str;
+
+    /// Fold `operands` (oldest first) onto `existing`, the base value, or
+    /// `None` if the key had no value (never written, or deleted).
+    ///
+    /// Returning `None` signals a merge failure: reads surface it as a
+    /// corruption error, flush writes the operands unchanged, and compaction
+    /// fails the job (its inputs are kept).
+    fn full_merge(
+        &self,
+        key: &[u8],
+        existing: Option<&[u8]>,
+        operands: &[&[u8]],
+    ) -> Option<Vec<u8>>;
+
+    /// Combine two adjacent operands (`left` older than `right`) into one
+    /// operand without knowing the base value. Returning `None` (the
+    /// default) keeps both operands unchanged.
+    fn partial_merge(&self, _key: &[u8], _left: &[u8], _right: &[u8]) -> Option<Vec<u8>> {
+        None
+    }
+}
+
+impl fmt::Debug for dyn MergeOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "MergeOperator({})", self.name())
+    }
+}
+
+impl dyn MergeOperator + '_ {
+    /// Fold operands collected newest-first (the order every read path
+    /// discovers them in) onto `existing`, mapping an operator failure to a
+    /// corruption error.
+    pub(crate) fn full_merge_newest_first<T: AsRef<[u8]>>(
+        &self,
+        key: &[u8],
+        existing: Option<&[u8]>,
+        operands: &[T],
+    ) -> Result<Vec<u8>> {
+        let ordered: Vec<&[u8]> = operands.iter().rev().map(AsRef::as_ref).collect();
+        self.full_merge(key, existing, &ordered).ok_or_else(|| {
+            Error::corruption(format!(
+                "merge operator '{}' failed for key {:?}",
+                self.name(),
+                String::from_utf8_lossy(key)
+            ))
+        })
+    }
+}
+
+/// Resolve the merge operator for a read or compaction that encountered a
+/// `Merge` entry, failing when the DB was opened without one.
+pub(crate) fn require_merge_operator(
+    operator: Option<&Arc<dyn MergeOperator>>,
+) -> Result<&Arc<dyn MergeOperator>> {
+    operator.ok_or_else(|| {
+        Error::invalid_argument("merge operand found but no merge operator is configured")
+    })
+}
+
 /// Collects properties from key-value pairs during SST building.
 /// One instance per property type per SST file build.
 pub trait BlockPropertyCollector: Send + Sync {
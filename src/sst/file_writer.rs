@@ -0,0 +1,234 @@
+//! Public writer for SST files meant for [`DB::ingest_external_file`].
+//!
+//! Entries are written as internal keys with sequence number 0; ingestion
+//! decides whether the file can keep it or needs a global sequence number.
+//!
+//! [`DB::ingest_external_file`]: crate::DB::ingest_external_file
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error, Result, ResultExt};
+use crate::options::DbOptions;
+use crate::sst::table_builder::{TableBuildOptions, TableBuilder};
+use crate::types::{
+    InternalKey, InternalKeyComparator, MAX_USER_KEY_SIZE, MAX_WRITE_ENTRY_SIZE, ValueType,
+    user_key,
+};
+
+/// Summary of a finished external SST file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExternalSstFileInfo {
+    pub file_path: PathBuf,
+    /// Smallest user key written (point key or range-deletion begin).
+    pub smallest_key: Vec<u8>,
+    /// Largest user key written (point key or range-deletion begin).
+    pub largest_key: Vec<u8>,
+    /// Number of puts, deletes and range deletions written.
+    pub num_entries: u64,
+    pub file_size: u64,
+}
+
+/// Builds an SST file from sorted entries for bulk loading with
+/// [`DB::ingest_external_file`](crate::DB::ingest_external_file).
+///
+/// Entries must arrive in ascending user-key order under the options'
+/// [`comparator`](DbOptions::comparator), a range deletion being
+/// ordered by its begin key; a point key may repeat the begin key of the
+/// range deletion just before it. Range deletions apply to data already in
+/// the DB, not to keys in the same file. Block, bloom, prefix and
+/// compression settings are taken from the [`DbOptions`] given to
+/// [`create`](Self::create), which should match the target DB's.
+pub struct SstFileWriter {
+    path: PathBuf,
+    builder: TableBuilder,
+    last_point: Option<Vec<u8>>,
+    last_range_begin: Option<Vec<u8>>,
+    num_entries: u64,
+    icmp: InternalKeyComparator,
+}
+
+impl SstFileWriter {
+    /// Create (truncating) the SST file at `path`.
+    pub fn create(options: &DbOptions, path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let icmp = InternalKeyComparator::new(&options.comparator);
+        let build_opts = TableBuildOptions {
+            block_size: options.block_size,
+            block_restart_interval: options.block_restart_interval,
+            bloom_bits_per_key: options.bloom_bits_per_key,
+            internal_keys: true,
+            compression: options.compression,
+            prefix_len: options.prefix_len,
+            block_property_collectors: options
+                .block_property_collectors
+                .iter()
+                .map(|f| f())
+                .collect(),
+            comparator: icmp.clone(),
+        };
+        let builder = TableBuilder::new(&path, build_opts).ctx()?;
+        Ok(Self {
+            path,
+            builder,
+            last_point: None,
+            last_range_begin: None,
+            num_entries: 0,
+            icmp,
+        })
+    }
+
+    pub fn put(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
+        self.add_point(key, value, ValueType::Value)
+    }
+
+    pub fn delete(&mut self, key: &[u8]) -> Result<()> {
+        self.add_point(key, b"", ValueType::Deletion)
+    }
+
+    /// Delete every key in `[begin, end)`.
+    pub fn delete_range(&mut self, begin: &[u8], end: &[u8]) -> Result<()> {
+        if !self.icmp.user_lt(begin, end) {
+            return Err(Error::invalid_argument(
+                "range deletion begin must be below its end".to_string(),
+            ));
+        }
+        Self::check_size(begin, end)?;
+        let after_point = self
+            .last_point
+            .as_deref()
+            .is_none_or(|p| self.icmp.user_lt(p, begin));
+        let after_range = self
+            .last_range_begin
+            .as_deref()
+            .is_none_or(|b| self.icmp.user_lt(b, begin));
+        if !after_point || !after_range {
+            return Err(Error::invalid_argument(
+                "range deletion begin key is out of order".to_string(),
+            ));
+        }
+        let ikey = InternalKey::new(begin, 0, ValueType::RangeDeletion);
+        self.builder.add(ikey.as_bytes(), end).ctx()?;
+        self.last_range_begin = Some(begin.to_vec());
+        self.num_entries += 1;
+        Ok(())
+    }
+
+    /// Finish and sync the file. A file without entries is removed and
+    /// rejected.
+    pub fn finish(self) -> Result<ExternalSstFileInfo> {
+        if self.num_entries == 0 {
+            drop(self.builder);
+            let _ = fs::remove_file(&self.path);
+            return Err(Error::invalid_argument(
+                "cannot finish an SST file without entries".to_string(),
+            ));
+        }
+        let result = self.builder.finish().ctx()?;
+        Ok(ExternalSstFileInfo {
+            file_path: self.path,
+            smallest_key: user_key(&result.smallest_key.unwrap_or_default()).to_vec(),
+            largest_key: user_key(&result.largest_key.unwrap_or_default()).to_vec(),
+            num_entries: self.num_entries,
+            file_size: result.file_size,
+        })
+    }
+
+    fn add_point(&mut self, key: &[u8], value: &[u8], vt: ValueType) -> Result<()> {
+        Self::check_size(key, value)?;
+        let after_point = self
+            .last_point
+            .as_deref()
+            .is_none_or(|p| self.icmp.user_lt(p, key));
+        let after_range = self
+            .last_range_begin
+            .as_deref()
+            .is_none_or(|b| self.icmp.user_le(b, key));
+        if !after_point || !after_range {
+            return Err(Error::invalid_argument(
+                "keys must be added in ascending order".to_string(),
+            ));
+        }
+        let ikey = InternalKey::new(key, 0, vt);
+        self.builder.add(ikey.as_bytes(), value).ctx()?;
+        self.last_point = Some(key.to_vec());
+        self.num_entries += 1;
+        Ok(())
+    }
+
+    fn check_size(key: &[u8], value: &[u8]) -> Result<()> {
+        if key.len() > MAX_USER_KEY_SIZE {
+            return Err(Error::invalid_argument(format!(
+                "key size {} exceeds maximum {}",
+                key.len(),
+                MAX_USER_KEY_SIZE
+            )));
+        }
+        if key.len().saturating_add(value.len()) > MAX_WRITE_ENTRY_SIZE {
+            return Err(Error::invalid_argument(format!(
+                "entry size {} (key + value) exceeds maximum {}",
+                key.len() + value.len(),
+                MAX_WRITE_ENTRY_SIZE
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::sst::table_reader::TableReader;
+    use crate::types::decode_internal_key;
+
+    #[test]
+    fn test_writes_sorted_entries_with_sequence_zero() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bulk.sst");
+        let mut writer = SstFileWriter::create(&DbOptions::default(), &path).unwrap();
+        writer.put(b"a", b"1").unwrap();
+        writer.delete_range(b"b", b"d").unwrap();
+        writer.put(b"b", b"2").unwrap();
+        writer.delete(b"c").unwrap();
+        assert_eq!(
+            writer.put(b"c", b"3").unwrap_err().kind(),
+            ErrorKind::InvalidArgument
+        );
+        assert_eq!(
+            writer.delete_range(b"c", b"e").unwrap_err().kind(),
+            ErrorKind::InvalidArgument
+        );
+        assert_eq!(
+            writer.delete_range(b"z", b"y").unwrap_err().kind(),
+            ErrorKind::InvalidArgument
+        );
+        let info = writer.finish().unwrap();
+        assert_eq!(info.smallest_key, b"a");
+        assert_eq!(info.largest_key, b"c");
+        assert_eq!(info.num_entries, 4);
+
+        let reader = TableReader::open(&path).unwrap();
+        let entries = reader.iter().unwrap();
+        assert_eq!(entries.len(), 3);
+        for (key, _) in &entries {
+            assert_eq!(decode_internal_key(key).unwrap().1, 0);
+        }
+        assert_eq!(
+            reader.get_range_tombstones().unwrap(),
+            vec![(b"b".to_vec(), b"d".to_vec(), 0)]
+        );
+    }
+
+    #[test]
+    fn test_empty_file_is_rejected_and_removed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("empty.sst");
+        let writer = SstFileWriter::create(&DbOptions::default(), &path).unwrap();
+        assert_eq!(
+            writer.finish().unwrap_err().kind(),
+            ErrorKind::InvalidArgument
+        );
+        assert!(!path.exists());
+    }
+}
@@ -9,6 +9,7 @@
 
 pub mod block;
 pub mod block_builder;
+pub mod file_writer;
 pub mod filter;
 pub mod format;
 pub mod table_builder;
@@ -24,7 +24,7 @@ use crate::sst::{
     format::*,
     table_reader::MAX_DECOMPRESSED_BLOCK_SIZE,
 };
-use crate::types::{ValueType, compare_internal_key, decode_internal_key, user_key};
+use crate::types::{InternalKeyComparator, ValueType, decode_internal_key, user_key};
 
 /// Soft threshold at which callers that can split their output across
 /// multiple SST files (flush, compaction) should cut the current file, so
@@ -60,6 +60,8 @@ pub struct TableBuildOptions {
     pub prefix_len: usize,
     /// Block property collectors to attach per-block metadata to the index.
     pub block_property_collectors: Vec<Box<dyn crate::options::BlockPropertyCollector>>,
+    /// Order that internal keys must be added in.
+    pub comparator: InternalKeyComparator,
 }
 
 impl Clone for TableBuildOptions {
@@ -73,6 +75,7 @@ impl Clone for TableBuildOptions {
             prefix_len: self.prefix_len,
             // Collectors are per-build; a clone starts with empty collectors
             block_property_collectors: Vec::new(),
+            comparator: self.comparator.clone(),
         }
     }
 }
@@ -87,6 +90,7 @@ impl Default for TableBuildOptions {
             compression: CompressionType::None,
             prefix_len: 0,
             block_property_collectors: Vec::new(),
+            comparator: InternalKeyComparator::default(),
         }
     }
 }
@@ -222,7 +226,7 @@ impl TableBuilder {
             let (_, _, vt) = decode_internal_key(key).ctx()?;
             assert!(
                 self.last_key.is_empty()
-                    || compare_internal_key(key, &self.last_key) == Ordering::Greater,
+                    || self.options.comparator.compare(key, &self.last_key) == Ordering::Greater,
                 "keys must be added in order"
             );
             Some(vt)
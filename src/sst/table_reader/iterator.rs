@@ -3,7 +3,7 @@ use std::{cmp::Ordering, sync::Arc};
 use crate::error::Result;
 use crate::sst::block::{Block, decode_entry_reuse};
 use crate::sst::format::BlockHandle;
-use crate::types::{LazyValue, compare_internal_key, user_key};
+use crate::types::{InternalKeyComparator, LazyValue, user_key};
 
 pub use crate::sst::format::BLOCK_TRAILER_SIZE;
 
@@ -82,11 +82,15 @@ pub struct TableIterator {
     /// Whether cache misses populate the block cache. False for scans that
     /// must not evict hot blocks (compaction, `ReadOptions::fill_cache=false`).
     fill_cache: bool,
+
+    /// The table's key ordering, cloned from the reader.
+    icmp: InternalKeyComparator,
 }
 
 impl TableIterator {
     pub fn new(reader: Arc<TableReader>) -> Self {
         Self {
+            icmp: reader.comparator().clone(),
             reader,
             index_entries: None,
             index_pos: 0,
@@ -184,7 +188,7 @@ impl TableIterator {
             entry
                 .first_key
                 .as_ref()
-                .is_some_and(|fk| user_key(fk) >= ub.as_slice())
+                .is_some_and(|fk| !self.icmp.user_lt(user_key(fk), ub))
         })
     }
 
@@ -233,7 +237,7 @@ impl TableIterator {
         };
         // Check upper bound before returning entry
         if let Some(ref ub) = self.upper_bound
-            && user_key(&self.block_cursor_key) >= ub.as_slice()
+            && !self.icmp.user_lt(user_key(&self.block_cursor_key), ub)
         {
             return None;
         }
@@ -254,13 +258,14 @@ impl TableIterator {
 
     /// Seek to the first entry >= target using the index block for O(log N) lookup.
     pub fn seek(&mut self, target: &[u8]) {
+        let icmp = self.icmp.clone();
         self.ensure_index();
         self.reset_positioning_state();
         let index_entries = self.index_entries.as_ref().unwrap();
 
         // Quick check: if target > file's largest key, mark exhausted.
         if let Some(last) = index_entries.last()
-            && compare_internal_key(target, &last.separator_key) == Ordering::Greater
+            && self.icmp.compare(target, &last.separator_key) == Ordering::Greater
         {
             self.index_pos = index_entries.len();
             return;
@@ -268,7 +273,7 @@ impl TableIterator {
 
         // Binary search index entries to find the first block that may contain target
         let idx = index_entries.partition_point(|entry| {
-            compare_internal_key(&entry.separator_key, target) == Ordering::Less
+            self.icmp.compare(&entry.separator_key, target) == Ordering::Less
         });
 
         self.index_pos = idx;
@@ -287,7 +292,7 @@ impl TableIterator {
 
             // Deferred block read: if target <= first_key, position without I/O
             if let Some(ref first_key) = entry.first_key
-                && compare_internal_key(target, first_key) != Ordering::Greater
+                && self.icmp.compare(target, first_key) != Ordering::Greater
             {
                 self.at_first_key_from_index = true;
                 self.deferred_index_pos = self.index_pos - 1;
@@ -303,7 +308,7 @@ impl TableIterator {
             {
                 Ok(data) => match Block::new(data) {
                     Ok(block) => {
-                        self.seek_within_block(block, target, compare_internal_key);
+                        self.seek_within_block(block, target, |a, b| icmp.compare(a, b));
                     }
                     Err(e) => {
                         self.err = Some(format!("block decode error in seek: {e}"));
@@ -403,19 +408,20 @@ impl TableIterator {
         self.current_block = Some(block);
     }
 
-    /// Seek to the last entry <= target using compare_internal_key ordering.
+    /// Seek to the last entry <= target in the table's internal key order.
     /// After this call, the iterator is positioned on the found entry (or exhausted
     /// if no entry <= target exists).
     ///
     /// Uses windowed segment decoding: only decodes the restart segment containing
     /// the target entry, not the entire block.
     pub fn seek_for_prev(&mut self, target: &[u8]) {
+        let icmp = self.icmp.clone();
         self.ensure_index();
         self.reset_positioning_state();
         let index_entries = self.index_entries.as_ref().unwrap();
 
         let idx = index_entries.partition_point(|entry| {
-            compare_internal_key(&entry.separator_key, target) == Ordering::Less
+            self.icmp.compare(&entry.separator_key, target) == Ordering::Less
         });
 
         // Try the block at `idx` first, then fall back to previous blocks.
@@ -450,25 +456,25 @@ impl TableIterator {
                     self.err = Some(format!("block read error in seek_for_prev: {e}"));
                     break;
                 }
-                Ok(block) => match block.seek_for_prev_by(target, compare_internal_key) {
+                Ok(block) => match block.seek_for_prev_by(target, |a, b| icmp.compare(a, b)) {
                     Ok(Some((found_key, _found_val))) => {
                         // Determine which restart segment this entry belongs to
                         // and decode all entries from that segment to end of block.
                         // Using iter_from_restart (not iter_restart_segment) ensures
                         // that a subsequent next() will see all remaining entries in
                         // this block before advancing to the next block.
-                        let restart_idx = match self.find_restart_for_key(
-                            &block,
-                            &found_key,
-                            compare_internal_key,
-                        ) {
-                            Ok(idx) => idx,
-                            Err(e) => {
-                                self.err =
-                                    Some(format!("block entry decode error in seek_for_prev: {e}"));
-                                break;
-                            }
-                        };
+                        let restart_idx =
+                            match self
+                                .find_restart_for_key(&block, &found_key, |a, b| icmp.compare(a, b))
+                            {
+                                Ok(idx) => idx,
+                                Err(e) => {
+                                    self.err = Some(format!(
+                                        "block entry decode error in seek_for_prev: {e}"
+                                    ));
+                                    break;
+                                }
+                            };
                         let entries_from_restart = match block.iter_from_restart(restart_idx) {
                             Ok(entries) => entries,
                             Err(e) => {
@@ -480,9 +486,7 @@ impl TableIterator {
                         // Find position of found entry within the decoded range
                         let pos_in_entries = entries_from_restart
                             .iter()
-                            .rposition(|(k, _)| {
-                                compare_internal_key(k, target) != Ordering::Greater
-                            })
+                            .rposition(|(k, _)| self.icmp.compare(k, target) != Ordering::Greater)
                             .unwrap_or(0);
 
                         self.index_pos = try_idx + 1;
@@ -881,7 +885,7 @@ impl crate::iterator::merge::SeekableIterator for TableIterator {
                     Ok((vs, vl, next)) => {
                         // Check upper bound before returning entry
                         if let Some(ref ub) = self.upper_bound
-                            && user_key(&self.block_cursor_key) >= ub.as_slice()
+                            && !self.icmp.user_lt(user_key(&self.block_cursor_key), ub)
                         {
                             return false;
                         }
@@ -903,7 +907,7 @@ impl crate::iterator::merge::SeekableIterator for TableIterator {
                 let (ref k, ref v) = self.current_block_entries[self.block_pos];
                 // Check upper bound before returning entry
                 if let Some(ref ub) = self.upper_bound
-                    && user_key(k) >= ub.as_slice()
+                    && !self.icmp.user_lt(user_key(k), ub)
                 {
                     return false;
                 }
@@ -967,7 +971,7 @@ impl crate::iterator::merge::SeekableIterator for TableIterator {
                     Ok((value_start, value_len, next_offset)) => {
                         // Check upper bound
                         if let Some(ref ub) = self.upper_bound
-                            && user_key(&self.block_cursor_key) >= ub.as_slice()
+                            && !self.icmp.user_lt(user_key(&self.block_cursor_key), ub)
                         {
                             return None;
                         }
@@ -990,7 +994,7 @@ impl crate::iterator::merge::SeekableIterator for TableIterator {
             if self.block_pos < self.current_block_entries.len() {
                 let (ref k, ref v) = self.current_block_entries[self.block_pos];
                 if let Some(ref ub) = self.upper_bound
-                    && user_key(k) >= ub.as_slice()
+                    && !self.icmp.user_lt(user_key(k), ub)
                 {
                     return None;
                 }
@@ -1018,7 +1022,7 @@ mod tests {
     use super::*;
     use crate::options::{BlockPropertyCollector, BlockPropertyFilter};
     use crate::sst::table_builder::{TableBuildOptions, TableBuilder};
-    use crate::types::{InternalKeyRef, ValueType};
+    use crate::types::{InternalKeyRef, ValueType, compare_internal_key};
 
     fn build_test_table(dir: &Path, count: usize) -> PathBuf {
         let path = dir.join("test.sst");
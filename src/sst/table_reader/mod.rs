@@ -21,11 +21,16 @@ use crate::sst::format::{
     RANGE_DEL_BLOCK_NAME, decode_footer, decode_index_value_with_props,
 };
 use crate::stats::DbStats;
-use crate::types::{SequenceNumber, ValueType, compare_internal_key, decode_internal_key};
+use crate::types::{
+    InternalKeyComparator, SequenceNumber, VALUE_TYPE_FOR_SEEK, ValueType, decode_internal_key,
+};
 
 /// A range tombstone: (begin_key, end_key, sequence_number).
 type RangeTombstoneEntry = (Vec<u8>, Vec<u8>, SequenceNumber);
 
+/// A point entry found by lookup: (value_type, value, sequence_number).
+type PointEntry = (ValueType, Vec<u8>, SequenceNumber);
+
 /// Maximum allowed decompressed block size. Used by readers to reject
 /// allocation bombs and by compaction to reserve enough output file numbers
 /// when compacting compressed inputs.
@@ -92,8 +97,12 @@ pub struct TableReader {
     /// Populated once on first max_covering_tombstone_seq call, then reused.
     /// O(log T) binary search instead of O(T) linear scan.
     range_tombstone_cache: OnceLock<Arc<FragmentedRangeTombstoneList>>,
+    /// Estimated entry count, sampled once from the first data block.
+    num_entries_estimate: OnceLock<u64>,
     /// Handle to the range-deletion block (if present in metaindex).
     range_del_handle: Option<BlockHandle>,
+    /// Order of the internal keys in this file.
+    icmp: InternalKeyComparator,
 }
 
 impl TableReader {
@@ -109,17 +118,20 @@ impl TableReader {
         Ok(())
     }
 
-    /// Open an SST file for reading.
+    /// Open an SST file in bytewise key order for reading.
+    #[cfg(test)]
     pub fn open(path: &Path) -> Result<Self> {
-        Self::open_with_all(path, 0, None, None)
+        Self::open_with_all(path, 0, None, None, InternalKeyComparator::default())
     }
 
-    /// Open with file number, optional block cache, and optional stats.
+    /// Open with file number, optional block cache, optional stats, and the
+    /// key order the file was written in.
     pub fn open_with_all(
         path: &Path,
         file_number: u64,
         block_cache: Option<Arc<BlockCache>>,
         stats: Option<Arc<DbStats>>,
+        icmp: InternalKeyComparator,
     ) -> Result<Self> {
         let mut file = File::open(path).ctx()?;
         let file_size = file.metadata().ctx()?.len();
@@ -156,7 +168,9 @@ impl TableReader {
             stats,
             index_entry_cache: OnceLock::new(),
             range_tombstone_cache: OnceLock::new(),
+            num_entries_estimate: OnceLock::new(),
             range_del_handle: meta.range_del_handle,
+            icmp,
         };
 
         // Eagerly warm the range-tombstone cache at open time for files using
@@ -179,6 +193,11 @@ impl TableReader {
         Ok(reader)
     }
 
+    /// The key order of this file.
+    pub fn comparator(&self) -> &InternalKeyComparator {
+        &self.icmp
+    }
+
     /// Get cached index entries (shared across all TableIterators for this file).
     /// Populated once on first access, then reused via Arc.
     /// Parses extended index values (BlockHandle + optional first_key).
@@ -192,6 +211,30 @@ impl TableReader {
         Ok(entries)
     }
 
+    /// Estimated number of entries: the first data block's entry count
+    /// times the number of data blocks. Sampled on first call, without
+    /// filling the block cache.
+    pub fn estimate_num_entries(&self) -> Result<u64> {
+        if let Some(&estimate) = self.num_entries_estimate.get() {
+            return Ok(estimate);
+        }
+        let index = self.cached_index_entries()?;
+        let estimate = match index.first() {
+            None => 0,
+            Some(first) => {
+                let block = Block::new(self.read_block_cached_opt(&first.handle, false)?)?;
+                let mut iter = block.iter();
+                let per_block = (&mut iter).count() as u64;
+                if let Some(e) = iter.error() {
+                    return Err(e.clone()).ctx();
+                }
+                per_block * index.len() as u64
+            }
+        };
+        let _ = self.num_entries_estimate.set(estimate);
+        Ok(estimate)
+    }
+
     /// Parse index entries from the index block, propagating decode errors.
     fn parse_index_entries(index_block: &Block) -> Result<Vec<IndexEntry>> {
         let mut iter = index_block.iter();
@@ -268,12 +311,12 @@ impl TableReader {
         // Construct a seek key: (user_key, sequence, Value).
         // With inverted-BE encoding, lex order = logical order, so seeking to this
         // key in the index block finds the right data block via binary search.
-        let seek_key = InternalKey::new(user_key, sequence, ValueType::Value);
+        let seek_key = InternalKey::new(user_key, sequence, VALUE_TYPE_FOR_SEEK);
 
         // Use index block seek to find the data block that may contain our key.
         let handle = match self
             .index_block
-            .seek_by(seek_key.as_bytes(), compare_internal_key)
+            .seek_by(seek_key.as_bytes(), |a, b| self.icmp.compare(a, b))
             .ctx()?
         {
             Some((_idx_key, handle_bytes)) => BlockHandle::decode(&handle_bytes).ctx()?,
@@ -286,7 +329,7 @@ impl TableReader {
         // Seek within the data block. The first entry >= seek_key with matching user_key
         // is our answer (because entries are sorted user_key ASC, seq DESC).
         match block
-            .seek_by(seek_key.as_bytes(), compare_internal_key)
+            .seek_by(seek_key.as_bytes(), |a, b| self.icmp.compare(a, b))
             .ctx()?
         {
             Some((encoded_ikey, value)) => {
@@ -294,7 +337,11 @@ impl TableReader {
                 if uk == user_key {
                     return Ok(Some(match vt {
                         ValueType::Value => Some(value),
-                        ValueType::Deletion | ValueType::RangeDeletion => None,
+                        ValueType::Deletion
+                        | ValueType::RangeDeletion
+                        | ValueType::Merge
+                        | ValueType::ExpiringValue
+                        | ValueType::BlobIndex => None,
                     }));
                 }
                 Ok(None)
@@ -303,29 +350,40 @@ impl TableReader {
         }
     }
 
-    /// Like `get_internal` but also returns the sequence number of the found entry.
-    /// Returns `Some((result, entry_seq))` if found, `None` if key not in this table.
-    /// When `fill_cache` is false, a cache miss does not populate the block cache.
+    /// Like `get_internal` but returns the raw entry type and sequence number.
+    /// Returns `Some((value_type, value, entry_seq))` if found, `None` if key
+    /// not in this table. When `fill_cache` is false, a cache miss does not
+    /// populate the block cache.
     pub fn get_internal_with_seq(
         &self,
         user_key: &[u8],
         sequence: SequenceNumber,
         fill_cache: bool,
-    ) -> Result<Option<(Option<Vec<u8>>, SequenceNumber)>> {
-        use crate::types::InternalKey;
-
+    ) -> Result<Option<(ValueType, Vec<u8>, SequenceNumber)>> {
         // Check bloom filter with user key
-        if let Some(ref filter) = self.filter_data
-            && !BloomFilter::key_may_match(user_key, filter)
-        {
+        if !self.bloom_may_match(user_key) {
             return Ok(None);
         }
+        let found = self.seek_internal_with_seq(user_key, sequence, fill_cache)?;
+        if found.is_none() {
+            self.record_bloom_false_positive();
+        }
+        Ok(found)
+    }
 
-        let seek_key = InternalKey::new(user_key, sequence, ValueType::Value);
+    fn seek_internal_with_seq(
+        &self,
+        user_key: &[u8],
+        sequence: SequenceNumber,
+        fill_cache: bool,
+    ) -> Result<Option<(ValueType, Vec<u8>, SequenceNumber)>> {
+        use crate::types::InternalKey;
+
+        let seek_key = InternalKey::new(user_key, sequence, VALUE_TYPE_FOR_SEEK);
 
         let handle = match self
             .index_block
-            .seek_by(seek_key.as_bytes(), compare_internal_key)
+            .seek_by(seek_key.as_bytes(), |a, b| self.icmp.compare(a, b))
             .ctx()?
         {
             Some((_idx_key, handle_bytes)) => BlockHandle::decode(&handle_bytes).ctx()?,
@@ -336,19 +394,13 @@ impl TableReader {
         let block = Block::new(block_data).ctx()?;
 
         match block
-            .seek_by(seek_key.as_bytes(), compare_internal_key)
+            .seek_by(seek_key.as_bytes(), |a, b| self.icmp.compare(a, b))
             .ctx()?
         {
             Some((encoded_ikey, value)) => {
                 let (uk, entry_seq, vt) = decode_internal_key(&encoded_ikey).ctx()?;
                 if uk == user_key {
-                    return Ok(Some((
-                        match vt {
-                            ValueType::Value => Some(value),
-                            ValueType::Deletion | ValueType::RangeDeletion => None,
-                        },
-                        entry_seq,
-                    )));
+                    return Ok(Some((vt, value, entry_seq)));
                 }
                 Ok(None)
             }
@@ -356,6 +408,88 @@ impl TableReader {
         }
     }
 
+    /// Batched `get_internal_with_seq` for ascending `user_keys`: each key
+    /// is bloom-checked and located through the index, and consecutive keys
+    /// landing in the same data block share one block read and decode.
+    pub fn multi_get_internal_with_seq(
+        &self,
+        user_keys: &[&[u8]],
+        sequence: SequenceNumber,
+        fill_cache: bool,
+    ) -> Result<Vec<Option<PointEntry>>> {
+        use crate::types::InternalKey;
+
+        let mut results = Vec::with_capacity(user_keys.len());
+        let mut current: Option<(u64, Block)> = None;
+        for &user_key in user_keys {
+            if !self.bloom_may_match(user_key) {
+                results.push(None);
+                continue;
+            }
+
+            let seek_key = InternalKey::new(user_key, sequence, VALUE_TYPE_FOR_SEEK);
+            let handle = match self
+                .index_block
+                .seek_by(seek_key.as_bytes(), |a, b| self.icmp.compare(a, b))
+                .ctx()?
+            {
+                Some((_idx_key, handle_bytes)) => BlockHandle::decode(&handle_bytes).ctx()?,
+                None => {
+                    self.record_bloom_false_positive();
+                    results.push(None);
+                    continue;
+                }
+            };
+            let block = match current {
+                Some((offset, ref block)) if offset == handle.offset => block,
+                _ => {
+                    let block_data = self.read_block_cached_opt(&handle, fill_cache).ctx()?;
+                    &current
+                        .insert((handle.offset, Block::new(block_data).ctx()?))
+                        .1
+                }
+            };
+
+            let found = match block
+                .seek_by(seek_key.as_bytes(), |a, b| self.icmp.compare(a, b))
+                .ctx()?
+            {
+                Some((encoded_ikey, value)) => {
+                    let (uk, entry_seq, vt) = decode_internal_key(&encoded_ikey).ctx()?;
+                    (uk == user_key).then_some((vt, value, entry_seq))
+                }
+                None => None,
+            };
+            if found.is_none() {
+                self.record_bloom_false_positive();
+            }
+            results.push(found);
+        }
+        Ok(results)
+    }
+
+    /// Probe the whole-key bloom filter, recording the probe in the stats.
+    /// `true` when the table has no filter.
+    fn bloom_may_match(&self, user_key: &[u8]) -> bool {
+        let Some(ref filter) = self.filter_data else {
+            return true;
+        };
+        let may_match = BloomFilter::key_may_match(user_key, filter);
+        if let Some(ref s) = self.stats {
+            s.record_bloom_check(may_match);
+        }
+        may_match
+    }
+
+    /// Record a lookup that passed the bloom filter but found nothing.
+    fn record_bloom_false_positive(&self) {
+        if self.filter_data.is_some()
+            && let Some(ref s) = self.stats
+        {
+            s.record_bloom_false_positive();
+        }
+    }
+
     /// Find the highest-seq range tombstone covering `user_key` with seq <= `read_seq`.
     /// Returns 0 if none found. Only meaningful for SSTs that contain range deletions.
     ///
@@ -430,7 +564,9 @@ impl TableReader {
             }
         }
 
-        let cached = Arc::new(FragmentedRangeTombstoneList::new(triples));
+        let cached = Arc::new(FragmentedRangeTombstoneList::from_triples(
+            triples, &self.icmp,
+        ));
         // Race is benign — worst case we build twice
         let _ = self.range_tombstone_cache.set(cached.clone());
         Ok(cached)
@@ -1,10 +1,255 @@
 //! Database properties and statistics.
 
+use std::fmt::Write;
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::compaction::{self, leveled::LeveledCompaction};
+use crate::manifest::version::Version;
+use crate::options::DbOptions;
 
 /// Maximum number of levels supported for read-level sampling.
 pub(crate) const MAX_LEVELS: usize = 32;
 
+/// Values below 8 get a bucket each; larger ones get four buckets per
+/// power of two, so a bucket is at most a quarter as wide as its lower
+/// bound. Covers the whole `u64` range.
+const HISTOGRAM_BUCKETS: usize = 8 + 61 * 4;
+
+fn bucket_index(value: u64) -> usize {
+    if value < 8 {
+        return value as usize;
+    }
+    let msb = 63 - value.leading_zeros() as usize;
+    8 + (msb - 3) * 4 + ((value >> (msb - 2)) & 3) as usize
+}
+
+/// Inclusive value range of bucket `index`.
+fn bucket_bounds(index: usize) -> (u64, u64) {
+    if index < 8 {
+        return (index as u64, index as u64);
+    }
+    let shift = (index - 8) / 4 + 1;
+    let lower = (4 + (index - 8) as u64 % 4) << shift;
+    (lower, lower + ((1u64 << shift) - 1))
+}
+
+/// Lock-free histogram of `u64` samples. Recording is a relaxed atomic
+/// add to the sample's bucket and to the sum, plus a min/max update.
+pub(crate) struct Histogram {
+    buckets: [AtomicU64; HISTOGRAM_BUCKETS],
+    sum: AtomicU64,
+    min: AtomicU64,
+    max: AtomicU64,
+}
+
+impl Histogram {
+    fn new() -> Self {
+        Self {
+            buckets: std::array::from_fn(|_| AtomicU64::new(0)),
+            sum: AtomicU64::new(0),
+            min: AtomicU64::new(u64::MAX),
+            max: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn record(&self, value: u64) {
+        self.buckets[bucket_index(value)].fetch_add(1, Ordering::Relaxed);
+        self.sum.fetch_add(value, Ordering::Relaxed);
+        self.min.fetch_min(value, Ordering::Relaxed);
+        self.max.fetch_max(value, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> HistogramSnapshot {
+        let buckets: Vec<u64> = self
+            .buckets
+            .iter()
+            .map(|b| b.load(Ordering::Relaxed))
+            .collect();
+        let count = buckets.iter().sum();
+        if count == 0 {
+            return HistogramSnapshot::default();
+        }
+        HistogramSnapshot {
+            count,
+            sum: self.sum.load(Ordering::Relaxed),
+            min: self.min.load(Ordering::Relaxed),
+            max: self.max.load(Ordering::Relaxed),
+            buckets,
+        }
+    }
+}
+
+/// A copy of one latency histogram, in microseconds.
+///
+/// Percentiles are interpolated within power-of-two sub-buckets, so they
+/// are accurate to about 25% of the value (exact below 8).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HistogramSnapshot {
+    /// Number of samples.
+    pub count: u64,
+    /// Sum of all samples.
+    pub sum: u64,
+    /// Smallest sample; 0 when empty.
+    pub min: u64,
+    /// Largest sample; 0 when empty.
+    pub max: u64,
+    buckets: Vec<u64>,
+}
+
+impl HistogramSnapshot {
+    /// Mean sample; 0.0 when empty.
+    pub fn mean(&self) -> f64 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        self.sum as f64 / self.count as f64
+    }
+
+    /// Estimated value below which `percentile` percent of the samples
+    /// fall, for `percentile` in `0.0..=100.0`. 0 when empty.
+    pub fn percentile(&self, percentile: f64) -> u64 {
+        if self.count == 0 {
+            return 0;
+        }
+        let rank = ((percentile.clamp(0.0, 100.0) / 100.0 * self.count as f64).ceil() as u64)
+            .clamp(1, self.count);
+        let mut seen = 0;
+        for (index, &in_bucket) in self.buckets.iter().enumerate() {
+            if seen + in_bucket < rank {
+                seen += in_bucket;
+                continue;
+            }
+            let (lower, upper) = bucket_bounds(index);
+            let fraction = (rank - seen) as f64 / in_bucket as f64;
+            let value = lower as f64 + (upper - lower) as f64 * fraction;
+            return (value as u64).clamp(self.min, self.max);
+        }
+        self.max
+    }
+
+    pub fn p50(&self) -> u64 {
+        self.percentile(50.0)
+    }
+
+    pub fn p99(&self) -> u64 {
+        self.percentile(99.0)
+    }
+
+    pub fn p999(&self) -> u64 {
+        self.percentile(99.9)
+    }
+}
+
+/// Operations timed by [`DbStats`] when statistics are enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum HistogramKind {
+    Get,
+    Write,
+    Seek,
+    Next,
+    Flush,
+    Compaction,
+    WalSync,
+}
+
+impl HistogramKind {
+    const COUNT: usize = 7;
+}
+
+/// A point-in-time copy of a DB's statistics, returned by
+/// [`DB::statistics`](crate::DB::statistics).
+///
+/// The counters up to `block_cache_misses` are always kept. The tickers
+/// and latency histograms after them are recorded only with
+/// [`DbOptions::enable_statistics`](crate::DbOptions::enable_statistics)
+/// and stay zero otherwise.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Statistics {
+    /// Total bytes written by the user.
+    pub bytes_written: u64,
+    /// Total bytes read by the user.
+    pub bytes_read: u64,
+    /// Number of compactions completed.
+    pub compactions_completed: u64,
+    /// Total bytes written during compaction.
+    pub compaction_bytes_written: u64,
+    /// Number of flushes completed.
+    pub flushes_completed: u64,
+    /// Number of block cache hits.
+    pub block_cache_hits: u64,
+    /// Number of block cache misses.
+    pub block_cache_misses: u64,
+
+    /// SST bloom filter probes by point lookups.
+    pub bloom_filter_checked: u64,
+    /// Probes where the filter ruled the key out, saving a block read.
+    pub bloom_filter_useful: u64,
+    /// Probes the filter passed for a key the SST did not hold.
+    pub bloom_filter_false_positive: u64,
+    /// Point lookups answered by a memtable.
+    pub get_hits_memtable: u64,
+    /// Point lookups answered by an SST, by level.
+    pub get_hits_per_level: Vec<u64>,
+    /// Key and value bytes returned by point lookups from SSTs, by level.
+    pub bytes_read_per_level: Vec<u64>,
+    /// Deleted keys iterators stepped over: point tombstones and keys
+    /// covered by a range tombstone.
+    pub iter_tombstones_skipped: u64,
+    /// Time writers spent delayed or stopped by write stalls.
+    pub stall_micros: u64,
+
+    /// `get` latency.
+    pub get_micros: HistogramSnapshot,
+    /// Write latency, from the batch entering the write path to its
+    /// memtable insert; includes any stall.
+    pub write_micros: HistogramSnapshot,
+    /// Iterator seek latency, including positioning on the first visible
+    /// entry.
+    pub seek_micros: HistogramSnapshot,
+    /// Iterator `next` and `prev` step latency.
+    pub next_micros: HistogramSnapshot,
+    /// Time to write a flush's SSTs.
+    pub flush_micros: HistogramSnapshot,
+    /// Time to merge and write a compaction's output.
+    pub compaction_micros: HistogramSnapshot,
+    /// WAL fsync latency.
+    pub wal_sync_micros: HistogramSnapshot,
+}
+
+/// Compaction I/O into one level, as reported by the `mmdb.stats`
+/// property. Flushes count as writes into L0.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct LevelIo {
+    /// Compactions that wrote into this level.
+    pub compactions: u64,
+    /// Bytes read from the level above (RocksDB's `Rn`).
+    pub read_upper: u64,
+    /// Bytes read from this level itself (RocksDB's `Rnp1`).
+    pub read_output: u64,
+    /// Bytes of SSTs written into this level.
+    pub written: u64,
+    /// Bytes moved into this level without a rewrite.
+    pub moved: u64,
+}
+
+impl LevelIo {
+    pub fn read(&self) -> u64 {
+        self.read_upper + self.read_output
+    }
+}
+
+/// Atomic counterpart of [`LevelIo`].
+#[derive(Default)]
+struct LevelIoCounters {
+    compactions: AtomicU64,
+    read_upper: AtomicU64,
+    read_output: AtomicU64,
+    written: AtomicU64,
+    moved: AtomicU64,
+}
+
 /// Tracks database statistics for monitoring and diagnostics.
 pub struct DbStats {
     /// Total bytes written by the user.
@@ -25,10 +270,35 @@ pub struct DbStats {
     pub read_level_samples: [AtomicU64; MAX_LEVELS],
     /// Counter for sampling reads (only sample every Nth read).
     pub read_sample_counter: AtomicU64,
+    /// Record the tickers and histograms below. When false every
+    /// `record_*` for them returns without touching an atomic, and
+    /// [`Self::start_timer`] skips the clock read.
+    enabled: bool,
+    /// Tickers; see the [`Statistics`] fields of the same names.
+    pub bloom_filter_checked: AtomicU64,
+    pub bloom_filter_useful: AtomicU64,
+    pub bloom_filter_false_positive: AtomicU64,
+    pub get_hits_memtable: AtomicU64,
+    pub get_hits_per_level: [AtomicU64; MAX_LEVELS],
+    pub bytes_read_per_level: [AtomicU64; MAX_LEVELS],
+    pub iter_tombstones_skipped: AtomicU64,
+    pub stall_micros: AtomicU64,
+    /// Latency histograms, indexed by [`HistogramKind`].
+    histograms: [Histogram; HistogramKind::COUNT],
+    /// Bytes written by flushes: the data ingested into the LSM tree.
+    pub flush_bytes_written: AtomicU64,
+    /// Flush and compaction I/O by output level.
+    level_io: [LevelIoCounters; MAX_LEVELS],
 }
 
 impl DbStats {
     pub fn new() -> Self {
+        Self::new_with_statistics(false)
+    }
+
+    /// Create stats that also record the tickers and latency histograms
+    /// when `enabled`.
+    pub fn new_with_statistics(enabled: bool) -> Self {
         Self {
             bytes_written: AtomicU64::new(0),
             bytes_read: AtomicU64::new(0),
@@ -39,9 +309,25 @@ impl DbStats {
             block_cache_misses: AtomicU64::new(0),
             read_level_samples: std::array::from_fn(|_| AtomicU64::new(0)),
             read_sample_counter: AtomicU64::new(0),
+            enabled,
+            bloom_filter_checked: AtomicU64::new(0),
+            bloom_filter_useful: AtomicU64::new(0),
+            bloom_filter_false_positive: AtomicU64::new(0),
+            get_hits_memtable: AtomicU64::new(0),
+            get_hits_per_level: std::array::from_fn(|_| AtomicU64::new(0)),
+            bytes_read_per_level: std::array::from_fn(|_| AtomicU64::new(0)),
+            iter_tombstones_skipped: AtomicU64::new(0),
+            stall_micros: AtomicU64::new(0),
+            histograms: std::array::from_fn(|_| Histogram::new()),
+            flush_bytes_written: AtomicU64::new(0),
+            level_io: std::array::from_fn(|_| LevelIoCounters::default()),
         }
     }
 
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
     pub fn record_write(&self, bytes: u64) {
         self.bytes_written.fetch_add(bytes, Ordering::Relaxed);
     }
@@ -55,12 +341,55 @@ impl DbStats {
             .fetch_add(bytes_written, Ordering::Relaxed);
     }
 
-    pub fn record_compaction_completed(&self) {
+    /// Count a completed compaction, recording how long it took to merge
+    /// and write its output when known.
+    pub fn record_compaction_completed(&self, duration: Option<Duration>) {
         self.compactions_completed.fetch_add(1, Ordering::Relaxed);
+        if let Some(duration) = duration {
+            self.record_duration(HistogramKind::Compaction, duration);
+        }
     }
 
-    pub fn record_flush(&self) {
+    /// Count a completed flush that wrote `bytes_written` into L0.
+    pub fn record_flush(&self, bytes_written: u64) {
         self.flushes_completed.fetch_add(1, Ordering::Relaxed);
+        self.flush_bytes_written
+            .fetch_add(bytes_written, Ordering::Relaxed);
+        self.level_io[0]
+            .written
+            .fetch_add(bytes_written, Ordering::Relaxed);
+    }
+
+    /// Record an installed compaction's I/O into `output_level`.
+    pub fn record_level_io(&self, output_level: usize, io: &LevelIo) {
+        let Some(counters) = self.level_io.get(output_level) else {
+            return;
+        };
+        counters
+            .compactions
+            .fetch_add(io.compactions, Ordering::Relaxed);
+        counters
+            .read_upper
+            .fetch_add(io.read_upper, Ordering::Relaxed);
+        counters
+            .read_output
+            .fetch_add(io.read_output, Ordering::Relaxed);
+        counters.written.fetch_add(io.written, Ordering::Relaxed);
+        counters.moved.fetch_add(io.moved, Ordering::Relaxed);
+    }
+
+    /// Flush and compaction I/O of the first `num_levels` levels.
+    pub fn level_io(&self, num_levels: usize) -> Vec<LevelIo> {
+        self.level_io[..num_levels.min(MAX_LEVELS)]
+            .iter()
+            .map(|c| LevelIo {
+                compactions: c.compactions.load(Ordering::Relaxed),
+                read_upper: c.read_upper.load(Ordering::Relaxed),
+                read_output: c.read_output.load(Ordering::Relaxed),
+                written: c.written.load(Ordering::Relaxed),
+                moved: c.moved.load(Ordering::Relaxed),
+            })
+            .collect()
     }
 
     pub fn record_cache_hit(&self) {
@@ -89,6 +418,249 @@ impl DbStats {
         }
         result
     }
+
+    /// Start timing an operation for [`Self::record_latency`]. `None`
+    /// when statistics are disabled, so no clock is read.
+    #[inline]
+    pub fn start_timer(&self) -> Option<Instant> {
+        self.enabled.then(Instant::now)
+    }
+
+    /// Record the time since `started` in `kind`'s histogram.
+    #[inline]
+    pub fn record_latency(&self, kind: HistogramKind, started: Option<Instant>) {
+        if let Some(started) = started {
+            self.histograms[kind as usize].record(micros(started.elapsed()));
+        }
+    }
+
+    /// Record an already measured `duration` in `kind`'s histogram.
+    pub fn record_duration(&self, kind: HistogramKind, duration: Duration) {
+        if self.enabled {
+            self.histograms[kind as usize].record(micros(duration));
+        }
+    }
+
+    /// Record a bloom filter probe; `may_match` is the filter's answer.
+    #[inline]
+    pub fn record_bloom_check(&self, may_match: bool) {
+        if self.enabled {
+            self.bloom_filter_checked.fetch_add(1, Ordering::Relaxed);
+            if !may_match {
+                self.bloom_filter_useful.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Record that a key which passed the bloom filter was not in the SST.
+    #[inline]
+    pub fn record_bloom_false_positive(&self) {
+        if self.enabled {
+            self.bloom_filter_false_positive
+                .fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Record a point lookup that found a value, in a memtable (`level` is
+    /// `None`) or in an SST at `level`, returning `bytes` of key and value.
+    pub fn record_get_hit(&self, level: Option<usize>, bytes: u64) {
+        if !self.enabled {
+            return;
+        }
+        match level {
+            None => {
+                self.get_hits_memtable.fetch_add(1, Ordering::Relaxed);
+            }
+            Some(level) if level < MAX_LEVELS => {
+                self.get_hits_per_level[level].fetch_add(1, Ordering::Relaxed);
+                self.bytes_read_per_level[level].fetch_add(bytes, Ordering::Relaxed);
+            }
+            Some(_) => {}
+        }
+    }
+
+    pub fn record_tombstones_skipped(&self, count: u64) {
+        if self.enabled && count > 0 {
+            self.iter_tombstones_skipped
+                .fetch_add(count, Ordering::Relaxed);
+        }
+    }
+
+    /// Record the time since `started` as write stall time.
+    pub fn record_stall(&self, started: Option<Instant>) {
+        if let Some(started) = started {
+            self.stall_micros
+                .fetch_add(micros(started.elapsed()), Ordering::Relaxed);
+        }
+    }
+
+    /// Copy every counter, truncating the per-level ones to `num_levels`.
+    pub fn snapshot(&self, num_levels: usize) -> Statistics {
+        let load = |c: &AtomicU64| c.load(Ordering::Relaxed);
+        let per_level = |counters: &[AtomicU64; MAX_LEVELS]| {
+            counters[..num_levels.min(MAX_LEVELS)]
+                .iter()
+                .map(load)
+                .collect()
+        };
+        let histogram = |kind: HistogramKind| self.histograms[kind as usize].snapshot();
+        Statistics {
+            bytes_written: load(&self.bytes_written),
+            bytes_read: load(&self.bytes_read),
+            compactions_completed: load(&self.compactions_completed),
+            compaction_bytes_written: load(&self.compaction_bytes_written),
+            flushes_completed: load(&self.flushes_completed),
+            block_cache_hits: load(&self.block_cache_hits),
+            block_cache_misses: load(&self.block_cache_misses),
+            bloom_filter_checked: load(&self.bloom_filter_checked),
+            bloom_filter_useful: load(&self.bloom_filter_useful),
+            bloom_filter_false_positive: load(&self.bloom_filter_false_positive),
+            get_hits_memtable: load(&self.get_hits_memtable),
+            get_hits_per_level: per_level(&self.get_hits_per_level),
+            bytes_read_per_level: per_level(&self.bytes_read_per_level),
+            iter_tombstones_skipped: load(&self.iter_tombstones_skipped),
+            stall_micros: load(&self.stall_micros),
+            get_micros: histogram(HistogramKind::Get),
+            write_micros: histogram(HistogramKind::Write),
+            seek_micros: histogram(HistogramKind::Seek),
+            next_micros: histogram(HistogramKind::Next),
+            flush_micros: histogram(HistogramKind::Flush),
+            compaction_micros: histogram(HistogramKind::Compaction),
+            wal_sync_micros: histogram(HistogramKind::WalSync),
+        }
+    }
+}
+
+const MB: f64 = (1 << 20) as f64;
+
+fn ratio(numerator: u64, denominator: u64) -> f64 {
+    if denominator == 0 {
+        return 0.0;
+    }
+    numerator as f64 / denominator as f64
+}
+
+/// The `mmdb.levelstats` property: files, size, score and compaction
+/// read/write bytes of every level.
+pub(crate) fn format_level_stats(
+    version: &Version,
+    options: &DbOptions,
+    stats: &DbStats,
+) -> String {
+    let io = stats.level_io(version.num_levels);
+    let mut out = String::new();
+    let header = "Level  Files  Size(MB)  Score  Read(MB)  Write(MB)";
+    let _ = writeln!(out, "{header}\n{}", "-".repeat(header.len()));
+    for (level, io) in io.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "{:>5}  {:>5}  {:>8.2}  {:>5.2}  {:>8.2}  {:>9.2}",
+            format!("L{level}"),
+            version.level_files(level).len(),
+            version.level_size(level) as f64 / MB,
+            LeveledCompaction::level_score(version, options, level),
+            io.read() as f64 / MB,
+            io.written as f64 / MB,
+        );
+    }
+    out
+}
+
+/// The `mmdb.stats` dump: per-level compaction I/O with write and space
+/// amplification, followed by DB-wide totals and estimates.
+pub(crate) fn format_stats_dump(version: &Version, options: &DbOptions, stats: &DbStats) -> String {
+    let io = stats.level_io(version.num_levels);
+    let ingest = stats.flush_bytes_written.load(Ordering::Relaxed);
+    let mut out = String::from("** Compaction Stats **\n");
+    let header = "Level  Files  Size(MB)  Score  Read(MB)  Rn(MB)  Rnp1(MB)  Write(MB)  \
+                  Moved(MB)  W-Amp  S-Amp  Comp(cnt)";
+    let _ = writeln!(out, "{header}\n{}", "-".repeat(header.len()));
+    let mut row =
+        |name: String, files: usize, size: u64, score: f64, io: &LevelIo, w_amp, s_amp| {
+            let _ = writeln!(
+                out,
+                "{name:>5}  {files:>5}  {:>8.2}  {score:>5.2}  {:>8.2}  {:>6.2}  {:>8.2}  {:>9.2}  \
+             {:>9.2}  {w_amp:>5.2}  {s_amp:>5.2}  {:>9}",
+                size as f64 / MB,
+                io.read() as f64 / MB,
+                io.read_upper as f64 / MB,
+                io.read_output as f64 / MB,
+                io.written as f64 / MB,
+                io.moved as f64 / MB,
+                io.compactions,
+            );
+        };
+    let mut total = LevelIo::default();
+    let mut total_size = 0;
+    let mut total_files = 0;
+    for (level, level_io) in io.iter().enumerate() {
+        let files = version.level_files(level).len();
+        let size = version.level_size(level);
+        total_size += size;
+        total_files += files;
+        total.compactions += level_io.compactions;
+        total.read_upper += level_io.read_upper;
+        total.read_output += level_io.read_output;
+        total.written += level_io.written;
+        total.moved += level_io.moved;
+        if files == 0 && *level_io == LevelIo::default() {
+            continue;
+        }
+        // Bytes written into the level per byte that entered it: flushed
+        // bytes for L0, bytes compacted down from the level above otherwise.
+        let entered = if level == 0 {
+            ingest
+        } else {
+            level_io.read_upper
+        };
+        row(
+            format!("L{level}"),
+            files,
+            size,
+            LeveledCompaction::level_score(version, options, level),
+            level_io,
+            ratio(level_io.written, entered),
+            ratio(total_size, size),
+        );
+    }
+    let live = version.estimate_live_data_size();
+    row(
+        "Sum".to_string(),
+        total_files,
+        total_size,
+        0.0,
+        &total,
+        ratio(total.written, ingest),
+        ratio(total_size, live),
+    );
+    let pending = compaction::picker(options.compaction_style)
+        .estimate_pending_compaction_bytes(version, options);
+    let _ = writeln!(
+        out,
+        "Flushes: {}, ingest(MB): {:.2}, compactions: {}, compaction write(MB): {:.2}",
+        stats.flushes_completed.load(Ordering::Relaxed),
+        ingest as f64 / MB,
+        stats.compactions_completed.load(Ordering::Relaxed),
+        stats.compaction_bytes_written.load(Ordering::Relaxed) as f64 / MB,
+    );
+    let _ = writeln!(
+        out,
+        "Estimated pending compaction(MB): {:.2}, estimated live data(MB): {:.2}",
+        pending as f64 / MB,
+        live as f64 / MB,
+    );
+    let _ = writeln!(
+        out,
+        "Block cache hits: {}, misses: {}, write stall(secs): {:.3}",
+        stats.block_cache_hits.load(Ordering::Relaxed),
+        stats.block_cache_misses.load(Ordering::Relaxed),
+        stats.stall_micros.load(Ordering::Relaxed) as f64 / 1e6,
+    );
+    out
+}
+
+fn micros(duration: Duration) -> u64 {
+    u64::try_from(duration.as_micros()).unwrap_or(u64::MAX)
 }
 
 impl Default for DbStats {
@@ -109,8 +681,96 @@ mod tests {
         assert_eq!(stats.bytes_written.load(Ordering::Relaxed), 300);
 
         stats.record_compaction_bytes(1000);
-        stats.record_compaction_completed();
+        stats.record_compaction_completed(None);
         assert_eq!(stats.compactions_completed.load(Ordering::Relaxed), 1);
         assert_eq!(stats.compaction_bytes_written.load(Ordering::Relaxed), 1000);
     }
+
+    #[test]
+    fn test_level_io() {
+        let stats = DbStats::new();
+        stats.record_flush(100);
+        stats.record_flush(50);
+        let io = LevelIo {
+            compactions: 1,
+            read_upper: 150,
+            read_output: 40,
+            written: 170,
+            moved: 0,
+        };
+        stats.record_level_io(1, &io);
+        stats.record_level_io(MAX_LEVELS, &io);
+        let levels = stats.level_io(3);
+        assert_eq!(levels.len(), 3);
+        assert_eq!(levels[0].written, 150);
+        assert_eq!(levels[0].compactions, 0);
+        assert_eq!(levels[1], io);
+        assert_eq!(levels[1].read(), 190);
+        assert_eq!(levels[2], LevelIo::default());
+        assert_eq!(stats.flush_bytes_written.load(Ordering::Relaxed), 150);
+    }
+
+    #[test]
+    fn test_histogram_buckets_cover_u64() {
+        let mut expected_lower = 0;
+        for index in 0..HISTOGRAM_BUCKETS {
+            let (lower, upper) = bucket_bounds(index);
+            assert_eq!(lower, expected_lower, "bucket {index}");
+            assert!(upper >= lower);
+            assert_eq!(bucket_index(lower), index);
+            assert_eq!(bucket_index(upper), index);
+            expected_lower = upper.wrapping_add(1);
+        }
+        assert_eq!(expected_lower, 0, "last bucket ends at u64::MAX");
+    }
+
+    #[test]
+    fn test_histogram_percentiles() {
+        let histogram = Histogram::new();
+        assert_eq!(histogram.snapshot(), HistogramSnapshot::default());
+        for value in 1..=1000 {
+            histogram.record(value);
+        }
+        let snapshot = histogram.snapshot();
+        assert_eq!(snapshot.count, 1000);
+        assert_eq!((snapshot.min, snapshot.max), (1, 1000));
+        assert_eq!(snapshot.mean(), 500.5);
+        for (percentile, exact) in [(50.0, 500.0), (99.0, 990.0), (99.9, 999.0)] {
+            let estimate = snapshot.percentile(percentile) as f64;
+            assert!(
+                (estimate - exact).abs() <= exact * 0.25,
+                "p{percentile}: {estimate} vs {exact}"
+            );
+        }
+        assert_eq!(snapshot.percentile(100.0), 1000);
+        assert_eq!(snapshot.percentile(0.0), 1);
+    }
+
+    #[test]
+    fn test_disabled_statistics_record_nothing() {
+        let stats = DbStats::new();
+        assert!(stats.start_timer().is_none());
+        stats.record_bloom_check(false);
+        stats.record_get_hit(Some(1), 10);
+        stats.record_duration(HistogramKind::Flush, Duration::from_millis(1));
+        let snapshot = stats.snapshot(7);
+        assert_eq!(snapshot.bloom_filter_checked, 0);
+        assert_eq!(snapshot.get_hits_per_level, vec![0; 7]);
+        assert_eq!(snapshot.flush_micros.count, 0);
+
+        let stats = DbStats::new_with_statistics(true);
+        stats.record_bloom_check(false);
+        stats.record_bloom_check(true);
+        stats.record_get_hit(Some(1), 10);
+        stats.record_duration(HistogramKind::Flush, Duration::from_millis(1));
+        let snapshot = stats.snapshot(7);
+        assert_eq!(
+            (snapshot.bloom_filter_checked, snapshot.bloom_filter_useful),
+            (2, 1)
+        );
+        assert_eq!(snapshot.get_hits_per_level[1], 1);
+        assert_eq!(snapshot.bytes_read_per_level[1], 10);
+        assert_eq!(snapshot.flush_micros.count, 1);
+        assert_eq!(snapshot.flush_micros.p50(), 1000);
+    }
 }
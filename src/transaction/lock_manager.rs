@@ -0,0 +1,177 @@
+//! Striped exclusive key locks with wait-for-graph deadlock detection.
+//!
+//! Each key hashes to one stripe; a stripe is a map from locked key to the
+//! owning transaction, guarded by a mutex and paired with a condvar that
+//! waiters sleep on. A transaction blocks on at most one key at a time, so
+//! the wait-for graph is a map from waiter to the current lock owner, and a
+//! deadlock is a path from the owner back to the waiter.
+//!
+//! Lock order: a stripe mutex may be held while taking the wait-for graph
+//! mutex, never the reverse.
+
+use std::collections::HashMap;
+use std::hash::{BuildHasher, RandomState};
+use std::time::{Duration, Instant};
+
+use parking_lot::{Condvar, Mutex};
+
+use crate::error::{Error, Result};
+
+/// Identifies one transaction within a [`LockManager`].
+pub(crate) type TxnId = u64;
+
+struct LockStripe {
+    owners: Mutex<HashMap<Vec<u8>, TxnId>>,
+    released: Condvar,
+}
+
+pub(crate) struct LockManager {
+    stripes: Vec<LockStripe>,
+    hasher: RandomState,
+    /// waiter -> transaction currently holding the key it waits for.
+    wait_for: Mutex<HashMap<TxnId, TxnId>>,
+    deadlock_detect: bool,
+}
+
+impl LockManager {
+    pub(crate) fn new(num_stripes: usize, deadlock_detect: bool) -> Self {
+        Self {
+            stripes: (0..num_stripes.max(1))
+                .map(|_| LockStripe {
+                    owners: Mutex::new(HashMap::new()),
+                    released: Condvar::new(),
+                })
+                .collect(),
+            hasher: RandomState::new(),
+            wait_for: Mutex::new(HashMap::new()),
+            deadlock_detect,
+        }
+    }
+
+    fn stripe(&self, key: &[u8]) -> &LockStripe {
+        let idx = self.hasher.hash_one(key) as usize % self.stripes.len();
+        &self.stripes[idx]
+    }
+
+    /// Acquire the exclusive lock on `key` for `txn`. Re-acquiring a lock
+    /// the transaction already holds succeeds immediately.
+    ///
+    /// Returns `Ok(true)` if the lock was newly acquired, `Ok(false)` if
+    /// `txn` already held it. Fails with [`ErrorKind::Busy`] if waiting would
+    /// deadlock, or [`ErrorKind::TimedOut`] once `timeout` elapses.
+    ///
+    /// [`ErrorKind::Busy`]: crate::ErrorKind::Busy
+    /// [`ErrorKind::TimedOut`]: crate::ErrorKind::TimedOut
+    pub(crate) fn lock(&self, txn: TxnId, key: &[u8], timeout: Duration) -> Result<bool> {
+        let stripe = self.stripe(key);
+        let deadline = Instant::now() + timeout;
+        let mut owners = stripe.owners.lock();
+        let result = loop {
+            let owner = match owners.get(key) {
+                None => {
+                    owners.insert(key.to_vec(), txn);
+                    break Ok(true);
+                }
+                Some(&owner) if owner == txn => break Ok(false),
+                Some(&owner) => owner,
+            };
+            if self.deadlock_detect && self.would_deadlock(txn, owner) {
+                break Err(Error::busy(format!(
+                    "deadlock detected: transaction {} waits for {}",
+                    txn, owner
+                )));
+            }
+            if Instant::now() >= deadline {
+                break Err(Error::timed_out(format!(
+                    "transaction {} timed out after {:?} waiting for a key lock held by {}",
+                    txn, timeout, owner
+                )));
+            }
+            stripe.released.wait_until(&mut owners, deadline);
+        };
+        if self.deadlock_detect {
+            self.wait_for.lock().remove(&txn);
+        }
+        result
+    }
+
+    /// Record that `waiter` is about to block on `owner`, unless `owner`
+    /// (transitively) already waits for `waiter`.
+    fn would_deadlock(&self, waiter: TxnId, owner: TxnId) -> bool {
+        let mut graph = self.wait_for.lock();
+        let mut cur = owner;
+        // Every transaction waits on at most one other, so a walk longer
+        // than the graph must have entered a cycle not involving `waiter`.
+        for _ in 0..=graph.len() {
+            if cur == waiter {
+                return true;
+            }
+            match graph.get(&cur) {
+                Some(&next) => cur = next,
+                None => break,
+            }
+        }
+        graph.insert(waiter, owner);
+        false
+    }
+
+    /// Release every lock in `keys` held by `txn` and wake their waiters.
+    pub(crate) fn unlock_all<'k>(&self, txn: TxnId, keys: impl IntoIterator<Item = &'k [u8]>) {
+        for key in keys {
+            let stripe = self.stripe(key);
+            let mut owners = stripe.owners.lock();
+            if owners.get(key) == Some(&txn) {
+                owners.remove(key);
+                stripe.released.notify_all();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn reentrant_and_exclusive() {
+        let lm = LockManager::new(4, true);
+        assert!(lm.lock(1, b"k", Duration::from_millis(10)).unwrap());
+        assert!(!lm.lock(1, b"k", Duration::from_millis(10)).unwrap());
+        let err = lm.lock(2, b"k", Duration::from_millis(20)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::TimedOut);
+        lm.unlock_all(1, [b"k".as_slice()]);
+        assert!(lm.lock(2, b"k", Duration::from_millis(10)).unwrap());
+    }
+
+    #[test]
+    fn waiter_wakes_on_release() {
+        let lm = Arc::new(LockManager::new(1, true));
+        lm.lock(1, b"k", Duration::ZERO).unwrap();
+        let lm2 = Arc::clone(&lm);
+        let waiter = thread::spawn(move || lm2.lock(2, b"k", Duration::from_secs(10)));
+        thread::sleep(Duration::from_millis(20));
+        lm.unlock_all(1, [b"k".as_slice()]);
+        assert!(waiter.join().unwrap().unwrap());
+    }
+
+    #[test]
+    fn detects_two_party_deadlock() {
+        let lm = Arc::new(LockManager::new(8, true));
+        lm.lock(1, b"a", Duration::ZERO).unwrap();
+        lm.lock(2, b"b", Duration::ZERO).unwrap();
+        let lm2 = Arc::clone(&lm);
+        // 1 waits for 2 ...
+        let waiter = thread::spawn(move || lm2.lock(1, b"b", Duration::from_secs(10)));
+        while !lm.wait_for.lock().contains_key(&1) {
+            thread::yield_now();
+        }
+        // ... so 2 waiting for 1 would close the cycle.
+        let err = lm.lock(2, b"a", Duration::from_secs(10)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Busy);
+        lm.unlock_all(2, [b"b".as_slice()]);
+        assert!(waiter.join().unwrap().unwrap());
+    }
+}
@@ -0,0 +1,8 @@
+//! Transactions layered on top of the group-commit write path.
+
+pub(crate) mod lock_manager;
+pub mod optimistic;
+pub mod pessimistic;
+
+pub use optimistic::OptimisticTransaction;
+pub use pessimistic::{Transaction, TransactionDB};
@@ -0,0 +1,216 @@
+//! Optimistic transactions: buffer writes in a [`WriteBatchWithIndex`],
+//! remember every key read, and validate at commit time that none of those
+//! keys changed since the transaction started.
+//!
+//! No locks are taken while the transaction runs. Validation happens in the
+//! group-commit leader under the DB lock, immediately before sequence
+//! assignment, so no other write can slip in between the check and the
+//! commit. A failed check rejects the whole batch with [`ErrorKind::Busy`];
+//! nothing is written.
+//!
+//! [`ErrorKind::Busy`]: crate::ErrorKind::Busy
+
+use std::collections::BTreeSet;
+
+use crate::db::{DB, Snapshot};
+use crate::error::{Result, ResultExt};
+use crate::options::{ReadOptions, WriteOptions};
+use crate::types::{SequenceNumber, WriteBatchWithIndex};
+
+/// Keys a transaction depends on, and the sequence they were read at.
+/// Carried by the write request to the group-commit leader.
+pub(crate) struct ConflictCheck {
+    pub(crate) start_seq: SequenceNumber,
+    pub(crate) keys: Vec<Vec<u8>>,
+}
+
+/// A transaction that detects conflicts at commit instead of locking.
+///
+/// Reads see the DB as of the transaction's start, overlaid with the
+/// transaction's own uncommitted writes. Every key read from the DB is
+/// tracked; [`commit`](Self::commit) fails with [`ErrorKind::Busy`] if any
+/// of them was written (put, delete, merge, or covered by a range deletion)
+/// by another writer after the start sequence.
+///
+/// Dropping the transaction without committing discards its writes.
+///
+/// [`ErrorKind::Busy`]: crate::ErrorKind::Busy
+pub struct OptimisticTransaction<'a> {
+    db: &'a DB,
+    /// Pins the start sequence so compaction keeps every version newer
+    /// than it distinguishable for the commit-time check.
+    snapshot: Snapshot<'a>,
+    batch: WriteBatchWithIndex,
+    read_keys: BTreeSet<Vec<u8>>,
+}
+
+impl<'a> OptimisticTransaction<'a> {
+    pub(crate) fn new(db: &'a DB) -> Self {
+        Self {
+            db,
+            snapshot: db.snapshot(),
+            batch: db.new_indexed_batch(),
+            read_keys: BTreeSet::new(),
+        }
+    }
+
+    /// The sequence number the transaction reads at and validates against.
+    pub fn start_sequence(&self) -> SequenceNumber {
+        self.snapshot.sequence()
+    }
+
+    /// Read `key`, preferring the transaction's own writes. Keys resolved
+    /// from the DB are recorded for the commit-time conflict check.
+    pub fn get(&mut self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        if let Some(own) = self.batch.get_from_batch(key) {
+            return Ok(own);
+        }
+        self.read_keys.insert(key.to_vec());
+        let read_options = ReadOptions {
+            snapshot: Some(self.snapshot.sequence()),
+            ..ReadOptions::default()
+        };
+        self.db.get_with_options(&read_options, key).ctx()
+    }
+
+    pub fn put(&mut self, key: &[u8], value: &[u8]) {
+        self.batch.put(key, value);
+    }
+
+    pub fn delete(&mut self, key: &[u8]) {
+        self.batch.delete(key);
+    }
+
+    pub fn delete_range(&mut self, begin: &[u8], end: &[u8]) {
+        self.batch.delete_range(begin, end);
+    }
+
+    /// The transaction's pending writes.
+    pub fn write_batch(&self) -> &WriteBatchWithIndex {
+        &self.batch
+    }
+
+    /// Commit with default write options.
+    pub fn commit(self) -> Result<()> {
+        self.commit_with_options(&WriteOptions::default())
+    }
+
+    /// Validate the read set and, if no key changed since the start
+    /// sequence, apply the pending writes atomically. On conflict returns
+    /// [`ErrorKind::Busy`](crate::ErrorKind::Busy) and applies nothing.
+    pub fn commit_with_options(self, write_options: &WriteOptions) -> Result<()> {
+        let check = ConflictCheck {
+            start_seq: self.snapshot.sequence(),
+            keys: self.read_keys.into_iter().collect(),
+        };
+        self.db
+            .write_batch_with_conflict_check(self.batch.into_batch(), write_options, check)
+            .ctx()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::options::DbOptions;
+
+    fn open_db(dir: &tempfile::TempDir) -> DB {
+        DB::open(DbOptions::default(), dir.path()).unwrap()
+    }
+
+    #[test]
+    fn reads_see_own_writes_and_start_snapshot() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = open_db(&dir);
+        db.put(b"a", b"1").unwrap();
+
+        let mut txn = db.begin_optimistic_transaction();
+        db.put(b"b", b"outside").unwrap();
+        assert_eq!(txn.get(b"b").unwrap(), None);
+
+        txn.put(b"a", b"2");
+        assert_eq!(txn.get(b"a").unwrap(), Some(b"2".to_vec()));
+        txn.delete_range(b"a", b"b");
+        assert_eq!(txn.get(b"a").unwrap(), None);
+        txn.put(b"a", b"3");
+        assert_eq!(txn.get(b"a").unwrap(), Some(b"3".to_vec()));
+
+        // `b` was read and changed after the start sequence.
+        let err = txn.commit().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Busy);
+        assert_eq!(db.get(b"a").unwrap(), Some(b"1".to_vec()));
+    }
+
+    #[test]
+    fn conflicting_commit_applies_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = open_db(&dir);
+        db.put(b"counter", b"0").unwrap();
+
+        let mut t1 = db.begin_optimistic_transaction();
+        let mut t2 = db.begin_optimistic_transaction();
+        assert_eq!(t1.get(b"counter").unwrap(), Some(b"0".to_vec()));
+        assert_eq!(t2.get(b"counter").unwrap(), Some(b"0".to_vec()));
+        t1.put(b"counter", b"1");
+        t2.put(b"counter", b"2");
+        t2.put(b"other", b"x");
+
+        t1.commit().unwrap();
+        let err = t2.commit().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Busy);
+        assert_eq!(db.get(b"counter").unwrap(), Some(b"1".to_vec()));
+        assert_eq!(db.get(b"other").unwrap(), None);
+    }
+
+    #[test]
+    fn blind_writes_and_unrelated_keys_do_not_conflict() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = open_db(&dir);
+
+        let mut txn = db.begin_optimistic_transaction();
+        assert_eq!(txn.get(b"x").unwrap(), None);
+        txn.put(b"y", b"1");
+        db.put(b"y", b"outside").unwrap();
+        db.put(b"z", b"outside").unwrap();
+        txn.commit().unwrap();
+        assert_eq!(db.get(b"y").unwrap(), Some(b"1".to_vec()));
+    }
+
+    #[test]
+    fn conflict_detected_after_flush_and_range_delete() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = open_db(&dir);
+        db.put(b"k1", b"v").unwrap();
+        db.put(b"k2", b"v").unwrap();
+
+        // The competing write reaches an SST before the commit.
+        let mut txn = db.begin_optimistic_transaction();
+        txn.get(b"k1").unwrap();
+        db.put(b"k1", b"changed").unwrap();
+        db.flush().unwrap();
+        db.compact().unwrap();
+        assert_eq!(txn.commit().unwrap_err().kind(), ErrorKind::Busy);
+
+        // A range deletion covering a read key is a conflict too.
+        let mut txn = db.begin_optimistic_transaction();
+        txn.get(b"k2").unwrap();
+        db.delete_range(b"k2", b"k3").unwrap();
+        assert_eq!(txn.commit().unwrap_err().kind(), ErrorKind::Busy);
+    }
+
+    #[test]
+    fn read_only_transaction_validates() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = open_db(&dir);
+
+        let mut txn = db.begin_optimistic_transaction();
+        txn.get(b"k").unwrap();
+        txn.commit().unwrap();
+
+        let mut txn = db.begin_optimistic_transaction();
+        txn.get(b"k").unwrap();
+        db.put(b"k", b"v").unwrap();
+        assert_eq!(txn.commit().unwrap_err().kind(), ErrorKind::Busy);
+    }
+}
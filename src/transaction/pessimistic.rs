@@ -0,0 +1,317 @@
+//! Pessimistic transactions: every key a transaction writes, or reads with
+//! [`Transaction::get_for_update`], is locked exclusively until the
+//! transaction commits or is dropped.
+//!
+//! Locks come from a striped [`LockManager`]; waits are bounded by
+//! [`TransactionDbOptions::lock_timeout`] and, when enabled, checked for
+//! deadlocks before blocking. Each transaction reads at a snapshot taken
+//! from [`DB::snapshot`] when it begins. Locking a key additionally checks
+//! that nobody wrote it after that snapshot, so a locked key's snapshot
+//! value is also its latest value and the commit — a plain
+//! [`DB::write_with_options`] through group commit — cannot lose an update.
+
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::db::{DB, Snapshot};
+use crate::error::{Error, Result, ResultExt};
+use crate::options::{DbOptions, ReadOptions, TransactionDbOptions, WriteOptions};
+use crate::transaction::lock_manager::{LockManager, TxnId};
+use crate::types::{SequenceNumber, WriteBatchWithIndex};
+
+/// A [`DB`] whose writes go through locking [`Transaction`]s.
+///
+/// Writes made directly through [`db`](Self::db) bypass the lock table and
+/// are not isolated from running transactions; use the `TransactionDB`
+/// write methods (or a transaction) for keys that transactions touch.
+pub struct TransactionDB {
+    db: DB,
+    locks: LockManager,
+    options: TransactionDbOptions,
+    next_txn_id: AtomicU64,
+}
+
+impl TransactionDB {
+    /// Open (or create) a database with transaction support.
+    pub fn open(
+        options: DbOptions,
+        txn_options: TransactionDbOptions,
+        path: impl AsRef<Path>,
+    ) -> Result<Self> {
+        if txn_options.num_stripes == 0 {
+            return Err(Error::invalid_argument(
+                "num_stripes must be >= 1".to_string(),
+            ));
+        }
+        let db = DB::open(options, path).ctx()?;
+        Ok(Self {
+            db,
+            locks: LockManager::new(txn_options.num_stripes, txn_options.deadlock_detect),
+            options: txn_options,
+            next_txn_id: AtomicU64::new(1),
+        })
+    }
+
+    /// The underlying database, for reads, iteration, and maintenance.
+    pub fn db(&self) -> &DB {
+        &self.db
+    }
+
+    /// Begin a transaction reading at the current sequence.
+    pub fn begin_transaction(&self) -> Transaction<'_> {
+        Transaction {
+            txn_db: self,
+            id: self.next_txn_id.fetch_add(1, Ordering::Relaxed),
+            snapshot: self.db.snapshot(),
+            batch: self.db.new_indexed_batch(),
+            locked: BTreeSet::new(),
+        }
+    }
+
+    pub fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        self.db.get(key)
+    }
+
+    /// Put `key` as a single-operation transaction, waiting for its lock.
+    pub fn put(&self, key: &[u8], value: &[u8]) -> Result<()> {
+        let mut txn = self.begin_transaction();
+        txn.put(key, value).ctx()?;
+        txn.commit()
+    }
+
+    /// Delete `key` as a single-operation transaction, waiting for its lock.
+    pub fn delete(&self, key: &[u8]) -> Result<()> {
+        let mut txn = self.begin_transaction();
+        txn.delete(key).ctx()?;
+        txn.commit()
+    }
+
+    pub fn close(&self) -> Result<()> {
+        self.db.close()
+    }
+}
+
+/// A transaction holding exclusive locks on the keys it writes or reads
+/// for update. Locks are released on commit, rollback, or drop.
+pub struct Transaction<'a> {
+    txn_db: &'a TransactionDB,
+    id: TxnId,
+    snapshot: Snapshot<'a>,
+    batch: WriteBatchWithIndex,
+    locked: BTreeSet<Vec<u8>>,
+}
+
+impl Transaction<'_> {
+    /// The sequence number the transaction reads at.
+    pub fn start_sequence(&self) -> SequenceNumber {
+        self.snapshot.sequence()
+    }
+
+    /// Read `key` at the transaction's snapshot, preferring its own writes.
+    /// Takes no lock.
+    pub fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        if let Some(own) = self.batch.get_from_batch(key) {
+            return Ok(own);
+        }
+        let read_options = ReadOptions {
+            snapshot: Some(self.snapshot.sequence()),
+            ..ReadOptions::default()
+        };
+        self.txn_db.db.get_with_options(&read_options, key).ctx()
+    }
+
+    /// Lock `key`, then read it. Once this returns, no other transaction can
+    /// write the key until this one finishes.
+    pub fn get_for_update(&mut self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        self.lock_key(key).ctx()?;
+        self.get(key)
+    }
+
+    /// Lock `key` and stage a write of `value`. Nothing is visible to
+    /// other readers until the transaction commits.
+    pub fn put(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
+        self.lock_key(key).ctx()?;
+        self.batch.put(key, value);
+        Ok(())
+    }
+
+    /// Lock `key` and stage its deletion. Nothing is visible to other
+    /// readers until the transaction commits.
+    pub fn delete(&mut self, key: &[u8]) -> Result<()> {
+        self.lock_key(key).ctx()?;
+        self.batch.delete(key);
+        Ok(())
+    }
+
+    /// Apply the pending writes with default write options.
+    pub fn commit(self) -> Result<()> {
+        self.commit_with_options(&WriteOptions::default())
+    }
+
+    /// Apply the pending writes atomically, then release all locks.
+    pub fn commit_with_options(mut self, write_options: &WriteOptions) -> Result<()> {
+        let batch = std::mem::take(&mut self.batch).into_batch();
+        self.txn_db
+            .db
+            .write_with_options(write_options, batch)
+            .ctx()
+    }
+
+    /// Discard the pending writes and release all locks.
+    pub fn rollback(self) {}
+
+    /// Lock `key` for this transaction and verify nobody wrote it after the
+    /// snapshot. On a conflict this returns
+    /// [`ErrorKind::Busy`](crate::ErrorKind::Busy) and releases the lock, so
+    /// a retry checks the key again instead of finding it already locked.
+    fn lock_key(&mut self, key: &[u8]) -> Result<()> {
+        let newly_locked = self
+            .txn_db
+            .locks
+            .lock(self.id, key, self.txn_db.options.lock_timeout)
+            .ctx()?;
+        if !newly_locked {
+            return Ok(());
+        }
+        let snapshot = self.snapshot.sequence();
+        let validated = self
+            .txn_db
+            .db
+            .latest_key_sequence(key)
+            .ctx()
+            .and_then(|latest| {
+                if latest > snapshot {
+                    return Err(Error::busy(format!(
+                        "transaction conflict: key written at sequence {} after snapshot {}",
+                        latest, snapshot
+                    )));
+                }
+                Ok(())
+            });
+        match validated {
+            Ok(()) => {
+                self.locked.insert(key.to_vec());
+            }
+            Err(_) => self.txn_db.locks.unlock_all(self.id, [key]),
+        }
+        validated
+    }
+}
+
+impl Drop for Transaction<'_> {
+    fn drop(&mut self) {
+        self.txn_db
+            .locks
+            .unlock_all(self.id, self.locked.iter().map(Vec::as_slice));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use std::time::Duration;
+
+    fn open_txn_db(dir: &tempfile::TempDir, lock_timeout: Duration) -> TransactionDB {
+        TransactionDB::open(
+            DbOptions::default(),
+            TransactionDbOptions {
+                lock_timeout,
+                ..TransactionDbOptions::default()
+            },
+            dir.path(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn locked_key_blocks_other_writers_until_commit() {
+        let dir = tempfile::tempdir().unwrap();
+        let tdb = open_txn_db(&dir, Duration::from_millis(20));
+        tdb.put(b"k", b"0").unwrap();
+
+        let mut t1 = tdb.begin_transaction();
+        assert_eq!(t1.get_for_update(b"k").unwrap(), Some(b"0".to_vec()));
+        t1.put(b"k", b"1").unwrap();
+
+        let mut t2 = tdb.begin_transaction();
+        assert_eq!(t2.put(b"k", b"2").unwrap_err().kind(), ErrorKind::TimedOut);
+        assert_eq!(tdb.put(b"k", b"x").unwrap_err().kind(), ErrorKind::TimedOut);
+        // Plain reads never block.
+        assert_eq!(t2.get(b"k").unwrap(), Some(b"0".to_vec()));
+        drop(t2);
+
+        t1.commit().unwrap();
+        assert_eq!(tdb.get(b"k").unwrap(), Some(b"1".to_vec()));
+        tdb.put(b"k", b"2").unwrap();
+        assert_eq!(tdb.get(b"k").unwrap(), Some(b"2".to_vec()));
+    }
+
+    #[test]
+    fn rollback_discards_writes_and_releases_locks() {
+        let dir = tempfile::tempdir().unwrap();
+        let tdb = open_txn_db(&dir, Duration::from_millis(20));
+
+        let mut t1 = tdb.begin_transaction();
+        t1.put(b"a", b"1").unwrap();
+        t1.delete(b"b").unwrap();
+        assert_eq!(t1.get(b"a").unwrap(), Some(b"1".to_vec()));
+        t1.rollback();
+
+        assert_eq!(tdb.get(b"a").unwrap(), None);
+        let mut t2 = tdb.begin_transaction();
+        t2.put(b"a", b"2").unwrap();
+        t2.put(b"b", b"2").unwrap();
+        t2.commit().unwrap();
+        assert_eq!(tdb.get(b"a").unwrap(), Some(b"2".to_vec()));
+    }
+
+    #[test]
+    fn key_written_after_snapshot_is_a_conflict() {
+        let dir = tempfile::tempdir().unwrap();
+        let tdb = open_txn_db(&dir, Duration::from_millis(20));
+
+        let mut t1 = tdb.begin_transaction();
+        tdb.put(b"k", b"newer").unwrap();
+        let err = t1.get_for_update(b"k").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Busy);
+        drop(t1);
+
+        // A fresh transaction sees the newer value and may lock it.
+        let mut t2 = tdb.begin_transaction();
+        assert_eq!(t2.get_for_update(b"k").unwrap(), Some(b"newer".to_vec()));
+    }
+
+    #[test]
+    fn retry_after_conflict_is_still_a_conflict() {
+        let dir = tempfile::tempdir().unwrap();
+        let tdb = open_txn_db(&dir, Duration::from_millis(20));
+
+        let mut t1 = tdb.begin_transaction();
+        tdb.put(b"k", b"newer").unwrap();
+        assert_eq!(t1.put(b"k", b"1").unwrap_err().kind(), ErrorKind::Busy);
+        assert_eq!(t1.put(b"k", b"1").unwrap_err().kind(), ErrorKind::Busy);
+        assert_eq!(t1.get_for_update(b"k").unwrap_err().kind(), ErrorKind::Busy);
+        // The failed attempts hold no lock on the key.
+        tdb.put(b"k", b"newest").unwrap();
+        t1.commit().unwrap();
+        assert_eq!(tdb.get(b"k").unwrap(), Some(b"newest".to_vec()));
+    }
+
+    #[test]
+    fn rejects_zero_stripes() {
+        let dir = tempfile::tempdir().unwrap();
+        let err = TransactionDB::open(
+            DbOptions::default(),
+            TransactionDbOptions {
+                num_stripes: 0,
+                ..TransactionDbOptions::default()
+            },
+            dir.path(),
+        )
+        .err()
+        .unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidArgument);
+    }
+}
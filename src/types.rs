@@ -5,12 +5,17 @@
 //!   The trailer is bit-inverted so a higher seq yields smaller bytes.
 //!   Sort order: user_key ASC, sequence DESC, value_type DESC
 
+use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::collections::BTreeMap;
 use std::fmt;
 use std::sync::Arc;
+use std::time::Duration;
 
+use crate::blob::BlobValue;
+use crate::column_family::ColumnFamilyHandle;
 use crate::error::{Error, Result};
+use crate::options::{BytewiseComparator, BytewiseComparatorWithU64Ts, Comparator};
 
 /// Global monotonically increasing sequence number.
 pub type SequenceNumber = u64;
@@ -40,6 +45,17 @@ pub enum ValueType {
     Deletion = 0,
     Value = 1,
     RangeDeletion = 2,
+    /// Merge operand, folded into the base value by the configured
+    /// [`MergeOperator`](crate::MergeOperator) at read and compaction time.
+    Merge = 3,
+    /// A `Value` with an expiry time: the stored value carries an
+    /// [`EXPIRY_SUFFIX_LEN`]-byte suffix (see [`expiring_value_len`]). Reads
+    /// see it as the wrapped value until it expires and as a `Deletion`
+    /// afterwards.
+    ExpiringValue = 4,
+    /// A `Value` stored out of line: the stored value is an encoded blob
+    /// handle pointing into a blob file (see [`crate::blob`]).
+    BlobIndex = 5,
 }
 
 impl ValueType {
@@ -48,11 +64,41 @@ impl ValueType {
             0 => Some(Self::Deletion),
             1 => Some(Self::Value),
             2 => Some(Self::RangeDeletion),
+            3 => Some(Self::Merge),
+            4 => Some(Self::ExpiringValue),
+            5 => Some(Self::BlobIndex),
             _ => None,
         }
     }
 }
 
+/// Value type used when building point-lookup seek keys. Within one sequence
+/// number entries sort by type DESC, so seeking with the largest type lands on
+/// the first entry at or below the target sequence regardless of its type.
+pub const VALUE_TYPE_FOR_SEEK: ValueType = ValueType::BlobIndex;
+
+/// Length of the expiry suffix on an `ExpiringValue`'s stored value: the
+/// expiry time in milliseconds since the Unix epoch, little-endian.
+pub const EXPIRY_SUFFIX_LEN: usize = 8;
+
+/// Append the expiry suffix to `value`.
+pub(crate) fn encode_expiring_value(value: &mut Vec<u8>, expire_at_millis: u64) {
+    value.extend_from_slice(&expire_at_millis.to_le_bytes());
+}
+
+/// Length of the value wrapped in an `ExpiringValue`'s stored bytes, or
+/// `None` when it has expired at `now_millis`.
+pub(crate) fn expiring_value_len(stored: &[u8], now_millis: u64) -> Result<Option<usize>> {
+    let Some(len) = stored.len().checked_sub(EXPIRY_SUFFIX_LEN) else {
+        return Err(Error::corruption(format!(
+            "expiring value too short: {} bytes",
+            stored.len()
+        )));
+    };
+    let expire_at = u64::from_le_bytes(stored[len..].try_into().unwrap());
+    Ok((now_millis < expire_at).then_some(len))
+}
+
 /// Pack sequence number and value type into a single u64.
 /// Layout: `(sequence << 8) | value_type`
 #[inline]
@@ -243,6 +289,163 @@ pub fn compare_internal_key(a: &[u8], b: &[u8]) -> Ordering {
     }
 }
 
+/// [`compare_internal_key`] generalized to a user [`Comparator`]: user keys
+/// ascend in the comparator's order, then trailers as before. The default
+/// bytewise order skips the dynamic dispatch.
+///
+/// With user-defined timestamps, every user key seen by the storage layers
+/// is `key ++ timestamp`; the versions of one key sort newest timestamp
+/// first. Methods named `*_key` take plain keys without the timestamp.
+#[derive(Clone, Default)]
+pub struct InternalKeyComparator {
+    /// `None` for [`BytewiseComparator`].
+    user: Option<Arc<dyn Comparator>>,
+    /// Width of the timestamp suffix of user keys; 0 for none.
+    ts_size: usize,
+}
+
+impl InternalKeyComparator {
+    pub fn new(user: &Arc<dyn Comparator>) -> Self {
+        let ts_size = user.timestamp_size();
+        Self {
+            user: (user.name() != BytewiseComparator::NAME || ts_size != 0).then(|| user.clone()),
+            ts_size,
+        }
+    }
+
+    /// Name of the user comparator, as recorded in the MANIFEST.
+    pub fn name(&self) -> &str {
+        self.user
+            .as_ref()
+            .map_or(BytewiseComparator::NAME, |c| c.name())
+    }
+
+    /// Whether user keys, timestamps aside, are in plain byte order. Only
+    /// then do the keys sharing a prefix form one range, bounded by the
+    /// byte-incremented prefix.
+    pub fn is_bytewise(&self) -> bool {
+        matches!(
+            self.name(),
+            BytewiseComparator::NAME | BytewiseComparatorWithU64Ts::NAME
+        )
+    }
+
+    /// Width of the user-defined timestamp; 0 when disabled.
+    #[inline]
+    pub fn timestamp_size(&self) -> usize {
+        self.ts_size
+    }
+
+    /// Split a user key into the plain key and its timestamp. A key shorter
+    /// than the timestamp width is all key.
+    #[inline]
+    pub fn split_timestamp<'a>(&self, user_key: &'a [u8]) -> (&'a [u8], &'a [u8]) {
+        if user_key.len() < self.ts_size {
+            return (user_key, &[]);
+        }
+        user_key.split_at(user_key.len() - self.ts_size)
+    }
+
+    /// The user key without its timestamp.
+    #[inline]
+    pub fn strip_timestamp<'a>(&self, user_key: &'a [u8]) -> &'a [u8] {
+        self.split_timestamp(user_key).0
+    }
+
+    /// The newest possible version of `key`: the first user key it can
+    /// appear as.
+    pub fn first_version<'a>(&self, key: &'a [u8]) -> Cow<'a, [u8]> {
+        self.with_timestamp(key, 0xFF)
+    }
+
+    /// The oldest possible version of `key`: the last user key it can
+    /// appear as.
+    pub fn last_version<'a>(&self, key: &'a [u8]) -> Cow<'a, [u8]> {
+        self.with_timestamp(key, 0x00)
+    }
+
+    fn with_timestamp<'a>(&self, key: &'a [u8], fill: u8) -> Cow<'a, [u8]> {
+        if self.ts_size == 0 {
+            return Cow::Borrowed(key);
+        }
+        let mut out = Vec::with_capacity(key.len() + self.ts_size);
+        out.extend_from_slice(key);
+        out.resize(key.len() + self.ts_size, fill);
+        Cow::Owned(out)
+    }
+
+    /// Compare plain keys, without timestamps.
+    #[inline]
+    pub fn compare_key(&self, a: &[u8], b: &[u8]) -> Ordering {
+        match &self.user {
+            None => a.cmp(b),
+            Some(c) => c.compare(a, b),
+        }
+    }
+
+    /// `a < b` for plain keys.
+    #[inline]
+    pub fn key_lt(&self, a: &[u8], b: &[u8]) -> bool {
+        self.compare_key(a, b) == Ordering::Less
+    }
+
+    /// `a <= b` for plain keys.
+    #[inline]
+    pub fn key_le(&self, a: &[u8], b: &[u8]) -> bool {
+        self.compare_key(a, b) != Ordering::Greater
+    }
+
+    #[inline]
+    pub fn compare_user(&self, a: &[u8], b: &[u8]) -> Ordering {
+        if self.ts_size == 0 {
+            return self.compare_key(a, b);
+        }
+        let (a_key, a_ts) = self.split_timestamp(a);
+        let (b_key, b_ts) = self.split_timestamp(b);
+        self.compare_key(a_key, b_key).then_with(|| b_ts.cmp(a_ts))
+    }
+
+    #[inline]
+    pub fn compare(&self, a: &[u8], b: &[u8]) -> Ordering {
+        if self.user.is_none() {
+            return compare_internal_key(a, b);
+        }
+        match (a.len() >= 8, b.len() >= 8) {
+            (true, true) => self
+                .compare_user(&a[..a.len() - 8], &b[..b.len() - 8])
+                .then_with(|| a[a.len() - 8..].cmp(&b[b.len() - 8..])),
+            (false, false) => a.cmp(b),
+            (false, true) => Ordering::Less,
+            (true, false) => Ordering::Greater,
+        }
+    }
+
+    /// `a < b` in user-key order.
+    #[inline]
+    pub fn user_lt(&self, a: &[u8], b: &[u8]) -> bool {
+        self.compare_user(a, b) == Ordering::Less
+    }
+
+    /// `a <= b` in user-key order.
+    #[inline]
+    pub fn user_le(&self, a: &[u8], b: &[u8]) -> bool {
+        self.compare_user(a, b) != Ordering::Greater
+    }
+
+    /// An owned internal-key comparison function, for iterators and sorts
+    /// that take one.
+    pub fn as_fn(&self) -> impl Fn(&[u8], &[u8]) -> Ordering + Clone + Send + Sync + 'static {
+        let icmp = self.clone();
+        move |a: &[u8], b: &[u8]| icmp.compare(a, b)
+    }
+}
+
+impl fmt::Debug for InternalKeyComparator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "InternalKeyComparator({})", self.name())
+    }
+}
+
 /// Extract the user key from an internal key by stripping the 8-byte trailer.
 /// Returns the full slice if the key is shorter than 8 bytes.
 #[inline]
@@ -266,12 +469,13 @@ pub fn user_key(internal_key: &[u8]) -> &[u8] {
 /// extent.
 #[inline]
 pub fn tombstone_overlaps_bounds(
+    icmp: &InternalKeyComparator,
     begin: &[u8],
     end: &[u8],
     lower: Option<&[u8]>,
     upper: Option<&[u8]>,
 ) -> bool {
-    lower.is_none_or(|lo| end > lo) && upper.is_none_or(|hi| begin < hi)
+    lower.is_none_or(|lo| icmp.user_lt(lo, end)) && upper.is_none_or(|hi| icmp.user_lt(begin, hi))
 }
 
 /// A write batch groups multiple mutations to be applied atomically.
@@ -279,11 +483,54 @@ pub struct WriteBatch {
     pub(crate) entries: Vec<WriteBatchEntry>,
 }
 
+/// One mutation of a [`WriteBatch`], as yielded by [`WriteBatch::iter`].
+/// `cf` is the column family id ([`ColumnFamilyHandle::id`]), 0 for the
+/// default family.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteBatchOp<'a> {
+    Put {
+        cf: u32,
+        key: &'a [u8],
+        value: &'a [u8],
+    },
+    /// A put whose TTL has been resolved to an absolute expiry, in
+    /// milliseconds since the Unix epoch. Batches read back from the WAL
+    /// carry these; a batch not yet written reports its TTL puts as `Put`.
+    PutWithExpiry {
+        cf: u32,
+        key: &'a [u8],
+        value: &'a [u8],
+        expire_at_millis: u64,
+    },
+    Delete {
+        cf: u32,
+        key: &'a [u8],
+    },
+    DeleteRange {
+        cf: u32,
+        begin: &'a [u8],
+        end: &'a [u8],
+    },
+    Merge {
+        cf: u32,
+        key: &'a [u8],
+        operand: &'a [u8],
+    },
+}
+
 /// A single entry in a WriteBatch.
 pub(crate) struct WriteBatchEntry {
+    /// Column family id; 0 is the default family.
+    pub cf: u32,
     pub value_type: ValueType,
     pub key: Vec<u8>,
     pub value: Option<Vec<u8>>,
+    /// Time-to-live of a put, turned into an `ExpiringValue` with an
+    /// absolute expiry when the batch is written.
+    pub ttl: Option<Duration>,
+    /// Length of the user-defined timestamp suffixed to `key`, 0 if none.
+    /// Checked against the comparator's width when the batch is written.
+    pub timestamp_len: usize,
 }
 
 impl WriteBatch {
@@ -294,34 +541,142 @@ impl WriteBatch {
     }
 
     pub fn put(&mut self, key: &[u8], value: &[u8]) {
-        self.entries.push(WriteBatchEntry {
-            value_type: ValueType::Value,
-            key: key.to_vec(),
-            value: Some(value.to_vec()),
-        });
+        self.push(0, ValueType::Value, key, Some(value));
+    }
+
+    /// Like [`Self::put`], but the value expires `ttl` after the batch is
+    /// written. Overrides [`DbOptions::default_ttl`](crate::DbOptions::default_ttl).
+    pub fn put_with_ttl(&mut self, key: &[u8], value: &[u8], ttl: Duration) {
+        self.push(0, ValueType::Value, key, Some(value));
+        self.entries.last_mut().unwrap().ttl = Some(ttl);
     }
 
     pub fn delete(&mut self, key: &[u8]) {
-        self.entries.push(WriteBatchEntry {
-            value_type: ValueType::Deletion,
-            key: key.to_vec(),
-            value: None,
-        });
+        self.push(0, ValueType::Deletion, key, None);
+    }
+
+    /// Put `key` at user-defined timestamp `ts`. Requires a comparator with
+    /// a [`timestamp_size`](crate::Comparator::timestamp_size) equal to
+    /// `ts.len()`; such a DB accepts only timestamped puts and deletes.
+    pub fn put_with_timestamp(&mut self, key: &[u8], ts: &[u8], value: &[u8]) {
+        self.push_timestamped(ValueType::Value, key, ts, Some(value));
+    }
+
+    /// Delete the version of `key` at user-defined timestamp `ts`, hiding
+    /// all older versions from reads at or after `ts`.
+    pub fn delete_with_timestamp(&mut self, key: &[u8], ts: &[u8]) {
+        self.push_timestamped(ValueType::Deletion, key, ts, None);
     }
 
     /// Add a range deletion. Deletes all keys in [begin, end).
     /// Stored as: InternalKey(begin, seq, RangeDeletion) → end.
     ///
-    /// Empty or inverted ranges (`begin >= end`) are no-ops and are not
-    /// appended, so they do not consume WAL space or sequences.
+    /// Empty or inverted ranges (`begin >= end` in the
+    /// [`Comparator`](crate::Comparator) order) are no-ops: they are dropped
+    /// when the batch is written, so they do not consume WAL space or
+    /// sequences.
     pub fn delete_range(&mut self, begin: &[u8], end: &[u8]) {
-        if begin >= end {
+        if begin == end {
             return;
         }
+        self.push(0, ValueType::RangeDeletion, begin, Some(end));
+    }
+
+    /// Add a merge operand for `key`. Operands are folded into the key's
+    /// existing value by the DB's configured merge operator.
+    pub fn merge(&mut self, key: &[u8], operand: &[u8]) {
+        self.push(0, ValueType::Merge, key, Some(operand));
+    }
+
+    /// Like [`Self::put`], scoped to column family `cf`.
+    pub fn put_cf(&mut self, cf: &ColumnFamilyHandle, key: &[u8], value: &[u8]) {
+        self.push(cf.id, ValueType::Value, key, Some(value));
+    }
+
+    /// Like [`Self::delete`], scoped to column family `cf`.
+    pub fn delete_cf(&mut self, cf: &ColumnFamilyHandle, key: &[u8]) {
+        self.push(cf.id, ValueType::Deletion, key, None);
+    }
+
+    /// Like [`Self::delete_range`], scoped to column family `cf`.
+    pub fn delete_range_cf(&mut self, cf: &ColumnFamilyHandle, begin: &[u8], end: &[u8]) {
+        if begin == end {
+            return;
+        }
+        self.push(cf.id, ValueType::RangeDeletion, begin, Some(end));
+    }
+
+    /// Like [`Self::merge`], scoped to column family `cf`; the family's own
+    /// merge operator folds the operand.
+    pub fn merge_cf(&mut self, cf: &ColumnFamilyHandle, key: &[u8], operand: &[u8]) {
+        self.push(cf.id, ValueType::Merge, key, Some(operand));
+    }
+
+    /// The batch's mutations in insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = WriteBatchOp<'_>> {
+        self.entries.iter().map(|e| {
+            let (cf, key) = (e.cf, e.key.as_slice());
+            let value = e.value.as_deref().unwrap_or(&[]);
+            match e.value_type {
+                ValueType::Deletion => WriteBatchOp::Delete { cf, key },
+                ValueType::RangeDeletion => WriteBatchOp::DeleteRange {
+                    cf,
+                    begin: key,
+                    end: value,
+                },
+                ValueType::Merge => WriteBatchOp::Merge {
+                    cf,
+                    key,
+                    operand: value,
+                },
+                ValueType::ExpiringValue if value.len() >= EXPIRY_SUFFIX_LEN => {
+                    let (value, expiry) = value.split_at(value.len() - EXPIRY_SUFFIX_LEN);
+                    WriteBatchOp::PutWithExpiry {
+                        cf,
+                        key,
+                        value,
+                        expire_at_millis: u64::from_le_bytes(expiry.try_into().unwrap()),
+                    }
+                }
+                _ => WriteBatchOp::Put { cf, key, value },
+            }
+        })
+    }
+
+    pub(crate) fn push(
+        &mut self,
+        cf: u32,
+        value_type: ValueType,
+        key: &[u8],
+        value: Option<&[u8]>,
+    ) {
         self.entries.push(WriteBatchEntry {
-            value_type: ValueType::RangeDeletion,
-            key: begin.to_vec(),
-            value: Some(end.to_vec()),
+            cf,
+            value_type,
+            key: key.to_vec(),
+            value: value.map(<[u8]>::to_vec),
+            ttl: None,
+            timestamp_len: 0,
+        });
+    }
+
+    fn push_timestamped(
+        &mut self,
+        value_type: ValueType,
+        key: &[u8],
+        ts: &[u8],
+        value: Option<&[u8]>,
+    ) {
+        let mut full_key = Vec::with_capacity(key.len() + ts.len());
+        full_key.extend_from_slice(key);
+        full_key.extend_from_slice(ts);
+        self.entries.push(WriteBatchEntry {
+            cf: 0,
+            value_type,
+            key: full_key,
+            value: value.map(<[u8]>::to_vec),
+            ttl: None,
+            timestamp_len: ts.len(),
         });
     }
 
@@ -358,15 +713,24 @@ pub struct WriteBatchWithIndex {
     range_del_entries: Vec<(Vec<u8>, Vec<u8>, u64)>,
     /// Monotonic counter: each put/delete/delete_range increments this.
     next_pos: u64,
+    /// Order of user keys, for range deletions.
+    comparator: InternalKeyComparator,
 }
 
 impl WriteBatchWithIndex {
     pub fn new() -> Self {
+        Self::with_comparator(&(Arc::new(BytewiseComparator) as Arc<dyn Comparator>))
+    }
+
+    /// An empty batch whose range deletions follow `comparator`, which
+    /// should be the target DB's [`DbOptions::comparator`](crate::DbOptions::comparator).
+    pub fn with_comparator(comparator: &Arc<dyn Comparator>) -> Self {
         Self {
             batch: WriteBatch::new(),
             index: BTreeMap::new(),
             range_del_entries: Vec::new(),
             next_pos: 0,
+            comparator: InternalKeyComparator::new(comparator),
         }
     }
 
@@ -387,7 +751,7 @@ impl WriteBatchWithIndex {
     }
 
     pub fn delete_range(&mut self, begin: &[u8], end: &[u8]) {
-        if begin >= end {
+        if !self.comparator.user_lt(begin, end) {
             return;
         }
         let pos = self.next_pos;
@@ -415,14 +779,39 @@ impl WriteBatchWithIndex {
         self.next_pos
     }
 
+    /// Look up `key` among the batch's own writes.
+    ///
+    /// Returns `None` when the batch never touched the key, `Some(None)` when
+    /// its latest batch write is a deletion (point or covering range), and
+    /// `Some(Some(value))` when it is a put.
+    pub fn get_from_batch(&self, key: &[u8]) -> Option<Option<Vec<u8>>> {
+        let point = self.index.get(key);
+        let range_pos = self
+            .range_del_entries
+            .iter()
+            .filter(|(b, e, _)| self.comparator.user_le(b, key) && self.comparator.user_lt(key, e))
+            .map(|&(_, _, pos)| pos)
+            .max();
+        if let Some(&(idx, pos)) = point
+            && range_pos.is_none_or(|r| r < pos)
+        {
+            let entry = &self.batch.entries[idx];
+            return match entry.value_type {
+                ValueType::Value => Some(entry.value.clone()),
+                _ => Some(None),
+            };
+        }
+        range_pos.map(|_| None)
+    }
+
     /// Return the range tombstones with their write positions.
     pub fn range_tombstones(&self) -> &[(Vec<u8>, Vec<u8>, u64)] {
         &self.range_del_entries
     }
 
-    /// Iterate the BTreeMap in order, producing (InternalKey, value) pairs
-    /// for point operations only. Range tombstones are handled separately
-    /// by `range_tombstones()` and injected into `DBIterator` via
+    /// Produce (InternalKey, value) pairs for point operations only, sorted
+    /// by `icmp`. Range tombstones are handled separately by
+    /// `range_tombstones()` and injected into `DBIterator` via
     /// `set_range_tombstones_with_levels()`.
     ///
     /// Sequence numbers are assigned as `base_seq + write_position`,
@@ -430,6 +819,7 @@ impl WriteBatchWithIndex {
     pub(crate) fn sorted_entries(
         &self,
         base_seq: SequenceNumber,
+        icmp: &InternalKeyComparator,
     ) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
         let mut result = Vec::with_capacity(self.index.len());
         for (user_key, &(entry_idx, pos)) in &self.index {
@@ -446,6 +836,9 @@ impl WriteBatchWithIndex {
             let value = entry.value.clone().unwrap_or_default();
             result.push((ikey.into_bytes(), value));
         }
+        // The index is in bytewise order; user keys are distinct, so this is
+        // a no-op for the default comparator.
+        result.sort_by(|(a, _), (b, _)| icmp.compare(a, b));
         Ok(result)
     }
 }
@@ -477,10 +870,31 @@ pub enum LazyValue {
         offset: u32,
         len: u32,
     },
+    /// A value in a blob file, read from disk on first access.
+    Blob(Arc<BlobValue>),
 }
 
 impl LazyValue {
+    /// Shorten the value to its first `len` bytes.
+    ///
+    /// A blob value is materialized first (an unreadable blob becomes empty).
+    #[inline]
+    pub fn truncate(&mut self, new_len: usize) {
+        match self {
+            LazyValue::Inline(v) => v.truncate(new_len),
+            LazyValue::BlockRef { len, .. } => *len = (*len).min(new_len as u32),
+            LazyValue::Blob(b) => {
+                let mut v = b.load().map(<[u8]>::to_vec).unwrap_or_default();
+                v.truncate(new_len);
+                *self = LazyValue::Inline(v);
+            }
+        }
+    }
+
     /// View the value bytes without copying.
+    ///
+    /// An unreadable blob yields an empty slice; callers that must surface
+    /// the error use [`load`](Self::load).
     #[inline]
     pub fn as_slice(&self) -> &[u8] {
         match self {
@@ -488,25 +902,45 @@ impl LazyValue {
             LazyValue::BlockRef { data, offset, len } => {
                 &data[*offset as usize..(*offset as usize) + (*len as usize)]
             }
+            LazyValue::Blob(b) => b.load().unwrap_or_default(),
+        }
+    }
+
+    /// View the value bytes, reading a blob value from disk if needed.
+    #[inline]
+    pub(crate) fn load(&self) -> Result<&[u8]> {
+        match self {
+            LazyValue::Blob(b) => b.load(),
+            _ => Ok(self.as_slice()),
         }
     }
 
     /// Consume self and produce owned bytes.
     #[inline]
     pub fn into_vec(self) -> Vec<u8> {
+        self.try_into_vec().unwrap_or_default()
+    }
+
+    /// Consume self and produce owned bytes, surfacing blob read errors.
+    #[inline]
+    pub(crate) fn try_into_vec(self) -> Result<Vec<u8>> {
         match self {
-            LazyValue::Inline(v) => v,
+            LazyValue::Inline(v) => Ok(v),
             LazyValue::BlockRef { data, offset, len } => {
-                data[offset as usize..(offset as usize) + (len as usize)].to_vec()
+                Ok(data[offset as usize..(offset as usize) + (len as usize)].to_vec())
             }
+            LazyValue::Blob(b) => b.load().map(<[u8]>::to_vec),
         }
     }
 
+    /// Value length. For a blob value this is the size recorded in its
+    /// handle and does not touch the blob file.
     #[inline]
     pub fn len(&self) -> usize {
         match self {
             LazyValue::Inline(v) => v.len(),
             LazyValue::BlockRef { len, .. } => *len as usize,
+            LazyValue::Blob(b) => b.handle().size as usize,
         }
     }
 
@@ -522,6 +956,7 @@ impl fmt::Debug for LazyValue {
         match self {
             LazyValue::Inline(v) => write!(f, "Inline({}B)", v.len()),
             LazyValue::BlockRef { len, .. } => write!(f, "BlockRef({}B)", len),
+            LazyValue::Blob(b) => write!(f, "Blob({}B)", b.handle().size),
         }
     }
 }
@@ -630,6 +1065,35 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_timestamp_ordering() {
+        let cmp: Arc<dyn Comparator> = Arc::new(crate::options::BytewiseComparatorWithU64Ts);
+        let icmp = InternalKeyComparator::new(&cmp);
+        let v = |k: &[u8], ts: u64| [k, &ts.to_be_bytes()].concat();
+
+        // Plain key ascending, then newest timestamp first.
+        assert!(icmp.user_lt(&v(b"a", 5), &v(b"a", 1)));
+        assert!(icmp.user_lt(&v(b"a", 1), &v(b"ab", 9)));
+        assert_eq!(
+            icmp.split_timestamp(&v(b"ab", 7)),
+            (&b"ab"[..], &7u64.to_be_bytes()[..])
+        );
+
+        // The version sentinels bracket every stored version of the key.
+        let newest = icmp.first_version(b"a");
+        let oldest = icmp.last_version(b"a");
+        assert!(icmp.user_le(&newest, &v(b"a", u64::MAX)));
+        assert!(icmp.user_le(&v(b"a", 0), &oldest));
+        assert!(icmp.user_lt(&oldest, &v(b"ab", u64::MAX)));
+
+        // Higher sequence still sorts first within one user key.
+        let a = InternalKey::new(&v(b"a", 5), 20, ValueType::Value);
+        let b = InternalKey::new(&v(b"a", 5), 10, ValueType::Value);
+        let c = InternalKey::new(&v(b"a", 4), 30, ValueType::Value);
+        assert_eq!(icmp.compare(a.as_bytes(), b.as_bytes()), Ordering::Less);
+        assert_eq!(icmp.compare(b.as_bytes(), c.as_bytes()), Ordering::Less);
+    }
+
     #[test]
     fn test_compare_internal_key_short_keys() {
         // Short keys (< 8 bytes) must not panic
@@ -757,6 +1221,40 @@ mod tests {
         assert_eq!(wb.entries[3].key, b"a");
         assert_eq!(wb.entries[3].value, Some(b"z".to_vec()));
     }
+
+    #[test]
+    fn test_batch_with_index_get_from_batch() {
+        let mut wbwi = WriteBatchWithIndex::new();
+        assert_eq!(wbwi.get_from_batch(b"a"), None);
+        wbwi.put(b"a", b"1");
+        wbwi.put(b"c", b"3");
+        assert_eq!(wbwi.get_from_batch(b"a"), Some(Some(b"1".to_vec())));
+        wbwi.delete_range(b"a", b"c");
+        assert_eq!(wbwi.get_from_batch(b"a"), Some(None));
+        assert_eq!(wbwi.get_from_batch(b"b"), Some(None));
+        assert_eq!(wbwi.get_from_batch(b"c"), Some(Some(b"3".to_vec())));
+        wbwi.put(b"a", b"2");
+        assert_eq!(wbwi.get_from_batch(b"a"), Some(Some(b"2".to_vec())));
+        wbwi.delete(b"c");
+        assert_eq!(wbwi.get_from_batch(b"c"), Some(None));
+    }
+
+    #[test]
+    fn test_merge_entry_and_seek_type() {
+        let mut wb = WriteBatch::new();
+        wb.merge(b"counter", b"+1");
+        assert_eq!(wb.entries[0].value_type, ValueType::Merge);
+        assert_eq!(wb.entries[0].value, Some(b"+1".to_vec()));
+        assert_eq!(ValueType::from_u8(3), Some(ValueType::Merge));
+
+        // A seek key at seq 5 must not skip a Merge written at exactly seq 5.
+        let merge = InternalKey::new(b"k", 5, ValueType::Merge);
+        let seek = InternalKey::new(b"k", 5, VALUE_TYPE_FOR_SEEK);
+        assert_ne!(
+            compare_internal_key(seek.as_bytes(), merge.as_bytes()),
+            Ordering::Greater
+        );
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,123 @@
+//! WAL archiving: obsolete WALs kept around for `DB::get_updates_since`.
+//!
+//! With `wal_ttl_seconds` or `wal_size_limit` set, a WAL no longer needed
+//! for recovery is moved into `<db>/archive/` instead of being deleted.
+//! The archive is purged of files archived longer ago than the TTL (by the
+//! DB clock, kept as the file's mtime), then of the oldest files until it
+//! fits the size limit. With both unset, WALs are deleted and any archive
+//! left from an earlier configuration is emptied.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+use crate::options::DbOptions;
+
+/// Name of the archive directory inside the DB directory.
+pub const ARCHIVE_DIR: &str = "archive";
+
+pub fn archive_dir(db_path: &Path) -> PathBuf {
+    db_path.join(ARCHIVE_DIR)
+}
+
+pub fn wal_file_name(number: u64) -> String {
+    format!("{:06}.wal", number)
+}
+
+fn archiving_enabled(options: &DbOptions) -> bool {
+    options.wal_ttl_seconds > 0 || options.wal_size_limit > 0
+}
+
+/// Retire WAL `number`: archive it when archiving is enabled, delete it
+/// otherwise. A missing file is not an error.
+pub fn retire_wal(db_path: &Path, number: u64, options: &DbOptions) -> io::Result<()> {
+    let path = db_path.join(wal_file_name(number));
+    let result = if archiving_enabled(options) {
+        let archived = archive_dir(db_path).join(wal_file_name(number));
+        // The TTL runs from archiving, on the DB's clock.
+        let archived_at = UNIX_EPOCH + Duration::from_millis(options.clock.now_millis());
+        fs::create_dir_all(archive_dir(db_path))
+            .and_then(|()| fs::rename(&path, &archived))
+            .and_then(|()| fs::File::options().write(true).open(&archived))
+            .and_then(|file| file.set_modified(archived_at))
+    } else {
+        fs::remove_file(&path)
+    };
+    match result {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+/// Archived WAL numbers, oldest first.
+pub fn archived_wals(db_path: &Path) -> io::Result<Vec<u64>> {
+    let mut numbers = Vec::new();
+    let entries = match fs::read_dir(archive_dir(db_path)) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(numbers),
+        Err(e) => return Err(e),
+    };
+    for entry in entries {
+        let name = entry?.file_name();
+        if let Some(number) = name
+            .to_string_lossy()
+            .strip_suffix(".wal")
+            .and_then(|s| s.parse::<u64>().ok())
+        {
+            numbers.push(number);
+        }
+    }
+    numbers.sort_unstable();
+    Ok(numbers)
+}
+
+/// Delete archived WALs past the TTL or beyond the size limit, oldest
+/// first. Failures are logged; the next purge retries.
+pub fn purge_archive(db_path: &Path, options: &DbOptions) {
+    let numbers = match archived_wals(db_path) {
+        Ok(numbers) => numbers,
+        Err(e) => {
+            tracing::warn!("WAL archive purge: cannot read archive: {}", e);
+            return;
+        }
+    };
+    let dir = archive_dir(db_path);
+    let now = options.clock.now_millis();
+    let ttl_millis = options.wal_ttl_seconds.saturating_mul(1000);
+    let mut files: Vec<(u64, u64)> = Vec::with_capacity(numbers.len());
+    for number in numbers {
+        let Ok(meta) = fs::metadata(dir.join(wal_file_name(number))) else {
+            continue;
+        };
+        let modified = meta
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_millis() as u64);
+        let expired = !archiving_enabled(options)
+            || (options.wal_ttl_seconds > 0 && modified.saturating_add(ttl_millis) <= now);
+        if expired {
+            remove_archived(&dir, number);
+        } else {
+            files.push((number, meta.len()));
+        }
+    }
+    if options.wal_size_limit > 0 {
+        let mut total: u64 = files.iter().map(|&(_, size)| size).sum();
+        for &(number, size) in &files {
+            if total <= options.wal_size_limit {
+                break;
+            }
+            remove_archived(&dir, number);
+            total -= size;
+        }
+    }
+}
+
+fn remove_archived(dir: &Path, number: u64) {
+    let path = dir.join(wal_file_name(number));
+    if let Err(e) = fs::remove_file(&path) {
+        tracing::warn!("failed to remove archived WAL {}: {}", path.display(), e);
+    }
+}
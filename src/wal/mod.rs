@@ -3,9 +3,14 @@
 //! Each WAL file corresponds to one MemTable lifecycle.
 //! Records are appended sequentially, each with a CRC32 checksum.
 
+pub mod archive;
 pub mod reader;
 pub mod record;
+pub mod recovery;
+pub mod updates;
 pub mod writer;
 
 pub use reader::WalReader;
+pub use recovery::{WalCorruption, WalRecoverySummary};
+pub use updates::WalUpdateIterator;
 pub use writer::WalWriter;
@@ -1,10 +1,11 @@
 //! WAL reader: reads and reassembles records from a WAL file.
 
 use std::fs::File;
-use std::io::{BufReader, ErrorKind, Read, Seek};
+use std::io::{BufReader, ErrorKind, Read, Seek, SeekFrom};
 use std::path::Path;
 
 use crate::error::{Error, Result, ResultExt};
+use crate::sst::format::CompressionType;
 use crate::wal::record::*;
 
 /// WAL reader. Reads records from a WAL file, handling fragmentation.
@@ -24,6 +25,11 @@ pub struct WalReader {
     /// be treated as torn tails even if the remaining file bytes are zero,
     /// because an untrusted length can already have consumed later records.
     last_error_is_truncation: bool,
+    /// Set by `skip_to_next_block`: fragments continuing a record that
+    /// began before the skip are dropped until the next record start.
+    resyncing: bool,
+    /// Compression named by the file's `SetCompression` record, if any.
+    compression: CompressionType,
 }
 
 impl WalReader {
@@ -36,6 +42,8 @@ impl WalReader {
             eof: false,
             last_valid_offset: 0,
             last_error_is_truncation: false,
+            resyncing: false,
+            compression: CompressionType::None,
         })
     }
 
@@ -84,6 +92,37 @@ impl WalReader {
         }
     }
 
+    /// Resume reading at the next block boundary after a failed
+    /// `read_record`, dropping the rest of the block that held the bad
+    /// record. A failure that consumed exactly up to a boundary resumes
+    /// right there. Middle and last fragments met before the next record
+    /// start belong to a record cut by the skip and are dropped silently.
+    pub fn skip_to_next_block(&mut self) -> Result<()> {
+        let pos = self.reader.stream_position().ctx()?;
+        let block = BLOCK_SIZE as u64;
+        self.reader
+            .seek(SeekFrom::Start(pos.div_ceil(block) * block))
+            .ctx()?;
+        self.block_offset = 0;
+        self.eof = false;
+        self.resyncing = true;
+        Ok(())
+    }
+
+    /// Resume reading at `offset`, the end of a record read earlier (see
+    /// [`Self::last_valid_offset`]), after EOF or a torn tail. Lets a reader
+    /// tail a file that another process is still appending to; the buffered
+    /// bytes are dropped so the new ones are read from the file.
+    pub fn seek_to(&mut self, offset: u64) -> Result<()> {
+        self.reader.seek(SeekFrom::Start(offset)).ctx()?;
+        self.block_offset = (offset % BLOCK_SIZE as u64) as usize;
+        self.last_valid_offset = offset;
+        self.eof = false;
+        self.resyncing = false;
+        self.last_error_is_truncation = false;
+        Ok(())
+    }
+
     fn fail_truncation<T>(&mut self, msg: impl Into<String>) -> Result<T> {
         self.last_error_is_truncation = true;
         Err(Error::corruption(msg.into()))
@@ -121,15 +160,17 @@ impl WalReader {
                     }
                     return Ok(None);
                 }
+                Some((RecordType::Middle | RecordType::Last, _)) if self.resyncing => {}
                 Some((record_type, data)) => match record_type {
                     RecordType::Full => {
+                        self.resyncing = false;
                         if in_fragmented_record {
                             return self.fail_corruption("full record inside fragment");
                         }
-                        self.last_valid_offset = self.reader.stream_position().ctx()?;
-                        return Ok(Some(data));
+                        return self.finish_record(data);
                     }
                     RecordType::First => {
+                        self.resyncing = false;
                         if in_fragmented_record {
                             return self.fail_corruption("first record inside fragment");
                         }
@@ -147,8 +188,26 @@ impl WalReader {
                             return self.fail_corruption("last record without first");
                         }
                         result.extend_from_slice(&data);
+                        return self.finish_record(result);
+                    }
+                    RecordType::SetCompression => {
+                        self.resyncing = false;
+                        if in_fragmented_record {
+                            return self.fail_corruption("compression record inside fragment");
+                        }
+                        self.compression = match data[..] {
+                            [byte] => match CompressionType::from_u8(byte) {
+                                Some(compression) => compression,
+                                None => {
+                                    return self.fail_corruption(format!(
+                                        "unknown WAL compression type: {}",
+                                        byte
+                                    ));
+                                }
+                            },
+                            _ => return self.fail_corruption("malformed WAL compression record"),
+                        };
                         self.last_valid_offset = self.reader.stream_position().ctx()?;
-                        return Ok(Some(result));
                     }
                     RecordType::Zero => unreachable!("zero records are handled as padding"),
                 },
@@ -156,6 +215,16 @@ impl WalReader {
         }
     }
 
+    /// Decompress a reassembled record and mark it as the last valid one.
+    fn finish_record(&mut self, data: Vec<u8>) -> Result<Option<Vec<u8>>> {
+        let data = match decompress_record(self.compression, data) {
+            Ok(data) => data,
+            Err(e) => return self.fail_corruption(e.to_string()),
+        };
+        self.last_valid_offset = self.reader.stream_position().ctx()?;
+        Ok(Some(data))
+    }
+
     /// Read a single physical record (fragment).
     /// Returns None at EOF.
     fn read_physical_record(&mut self) -> Result<Option<(RecordType, Vec<u8>)>> {
@@ -344,6 +413,35 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_skip_to_next_block_drops_cut_record_fragments() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("skip.wal");
+
+        // `spanning` starts in block 0 and ends in block 1.
+        let spanning = vec![0x42_u8; BLOCK_SIZE];
+        {
+            let mut writer = WalWriter::new(&path).unwrap();
+            writer.add_record(b"first").unwrap();
+            writer.add_record(&spanning).unwrap();
+            writer.add_record(b"after").unwrap();
+            writer.sync().unwrap();
+        }
+        {
+            use std::io::Write;
+            let mut file = OpenOptions::new().write(true).open(&path).unwrap();
+            file.seek(SeekFrom::Start(HEADER_SIZE as u64)).unwrap();
+            file.write_all(b"XX").unwrap();
+        }
+
+        let mut reader = WalReader::new(&path).unwrap();
+        assert!(reader.read_record().is_err());
+        reader.skip_to_next_block().unwrap();
+        // The tail of `spanning` opens block 1 and is dropped with it.
+        assert_eq!(reader.read_record().unwrap(), Some(b"after".to_vec()));
+        assert!(reader.read_record().unwrap().is_none());
+    }
+
     #[test]
     fn test_block_trailer_truncation() {
         let dir = tempfile::tempdir().unwrap();
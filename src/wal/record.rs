@@ -11,6 +11,14 @@
 //! The WAL is divided into fixed-size blocks (default 32KB).
 //! A record that doesn't fit in the remaining block space is split
 //! across blocks using record types: Full, First, Middle, Last.
+//!
+//! A WAL written with compression opens with a single `SetCompression`
+//! record whose one-byte payload is the [`CompressionType`]; every logical
+//! record after it is compressed before fragmentation. Files without that
+//! record are read uncompressed.
+
+use crate::error::{Error, Result, ResultExt};
+use crate::sst::format::CompressionType;
 
 /// WAL block size: 32 KB.
 pub const BLOCK_SIZE: usize = 32 * 1024;
@@ -32,6 +40,8 @@ pub enum RecordType {
     Middle = 3,
     /// Last fragment of a split record.
     Last = 4,
+    /// Header naming the compression of the records that follow.
+    SetCompression = 5,
 }
 
 impl RecordType {
@@ -42,6 +52,7 @@ impl RecordType {
             2 => Some(Self::First),
             3 => Some(Self::Middle),
             4 => Some(Self::Last),
+            5 => Some(Self::SetCompression),
             _ => None,
         }
     }
@@ -68,3 +79,30 @@ pub fn decode_header(buf: &[u8; HEADER_SIZE]) -> (u32, u16, Option<RecordType>)
     let record_type = RecordType::from_u8(buf[6]);
     (checksum, length, record_type)
 }
+
+/// Compress one logical record for a WAL written with `compression`.
+pub fn compress_record(compression: CompressionType, payload: &[u8]) -> Result<Vec<u8>> {
+    match compression {
+        CompressionType::None => Ok(payload.to_vec()),
+        CompressionType::Lz4 => Ok(lz4_flex::compress_prepend_size(payload)),
+        CompressionType::Zstd => zstd::bulk::compress(payload, 3).ctx(),
+    }
+}
+
+/// Reverse [`compress_record`]. Malformed input is reported as corruption.
+pub fn decompress_record(compression: CompressionType, data: Vec<u8>) -> Result<Vec<u8>> {
+    match compression {
+        CompressionType::None => Ok(data),
+        CompressionType::Lz4 => lz4_flex::decompress_size_prepended(&data)
+            .map_err(|e| Error::corruption(format!("WAL LZ4 decompression error: {}", e))),
+        CompressionType::Zstd => {
+            // `compress_record` always embeds the content size.
+            let capacity = match zstd::zstd_safe::get_frame_content_size(&data) {
+                Ok(Some(size)) => size as usize,
+                _ => return Err(Error::corruption("malformed WAL Zstd frame header")),
+            };
+            zstd::bulk::decompress(&data, capacity)
+                .map_err(|e| Error::corruption(format!("WAL Zstd decompression error: {}", e)))
+        }
+    }
+}
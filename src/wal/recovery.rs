@@ -0,0 +1,34 @@
+//! Report of what WAL replay skipped during `DB::open`.
+
+/// A corrupted WAL record met during recovery.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WalCorruption {
+    /// Number of the WAL file (`<number>.wal`).
+    pub wal_number: u64,
+    /// Offset just past the last good record before the corruption.
+    pub offset: u64,
+    /// What the reader found wrong.
+    pub message: String,
+}
+
+/// Outcome of WAL replay, available from
+/// [`DB::wal_recovery_summary`](crate::DB::wal_recovery_summary).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WalRecoverySummary {
+    /// WAL files replayed, oldest first.
+    pub replayed_wals: Vec<u64>,
+    /// Records applied to memtables.
+    pub records_replayed: u64,
+    /// Corruptions tolerated or skipped, in the order they were met.
+    pub corruptions: Vec<WalCorruption>,
+    /// WAL files left unread because replay stopped at an earlier
+    /// corruption (`PointInTimeRecovery`).
+    pub dropped_wals: Vec<u64>,
+}
+
+impl WalRecoverySummary {
+    /// True when replay read every WAL record intact.
+    pub fn is_clean(&self) -> bool {
+        self.corruptions.is_empty() && self.dropped_wals.is_empty()
+    }
+}
@@ -0,0 +1,183 @@
+//! Tailing the WAL: committed write batches in sequence order, for
+//! replication and change data capture.
+
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::db::DB;
+use crate::error::{Error, ErrorKind, Result, ResultExt};
+use crate::types::{SequenceNumber, ValueType, WriteBatch};
+use crate::wal::archive::{archive_dir, archived_wals, wal_file_name};
+use crate::wal::reader::WalReader;
+
+/// Iterator over `(first sequence, batch)` pairs read from the live and
+/// archived WALs, returned by [`DB::get_updates_since`].
+///
+/// The first batch is the one holding the requested sequence; iteration
+/// ends at the last batch committed when the iterator was created. Call
+/// `get_updates_since` again with the next sequence to keep tailing.
+/// Writes made with `disable_wal` and ingested files never appear, so
+/// sequences may skip.
+pub struct WalUpdateIterator {
+    db_path: PathBuf,
+    /// WALs still to read, oldest first.
+    wals: VecDeque<u64>,
+    reader: Option<WalReader>,
+    since: SequenceNumber,
+    last: SequenceNumber,
+    done: bool,
+}
+
+impl WalUpdateIterator {
+    pub(crate) fn new(db_path: &Path, since: SequenceNumber, last: SequenceNumber) -> Result<Self> {
+        let since = since.max(1);
+        let mut iter = Self {
+            db_path: db_path.to_path_buf(),
+            wals: VecDeque::new(),
+            reader: None,
+            since,
+            last,
+            done: since > last,
+        };
+        if iter.done {
+            return Ok(iter);
+        }
+
+        let mut numbers = archived_wals(db_path).ctx()?;
+        for entry in fs::read_dir(db_path).ctx()? {
+            let name = entry.ctx()?.file_name();
+            if let Some(number) = name
+                .to_string_lossy()
+                .strip_suffix(".wal")
+                .and_then(|s| s.parse::<u64>().ok())
+            {
+                numbers.push(number);
+            }
+        }
+        numbers.sort_unstable();
+        numbers.dedup();
+
+        // Start at the newest WAL whose first record is at or before
+        // `since`; every later WAL only holds later sequences.
+        let mut oldest = None;
+        let mut start = None;
+        for (i, &number) in numbers.iter().enumerate() {
+            let Some(first) = iter.first_sequence(number)? else {
+                continue;
+            };
+            oldest.get_or_insert(first);
+            if first > since {
+                break;
+            }
+            start = Some(i);
+        }
+        let Some(start) = start else {
+            let oldest = oldest.map_or(String::new(), |first| format!(" (oldest kept is {first})"));
+            return Err(Error::not_found(format!(
+                "WAL updates since sequence {} are no longer available{}",
+                since, oldest
+            )));
+        };
+        iter.wals = numbers.into_iter().skip(start).collect();
+        Ok(iter)
+    }
+
+    /// Open WAL `number`, wherever it is now: a live WAL may be archived
+    /// between listing and opening it. `None` when it is gone from both.
+    fn open(&self, number: u64) -> Result<Option<WalReader>> {
+        let name = wal_file_name(number);
+        for path in [
+            self.db_path.join(&name),
+            archive_dir(&self.db_path).join(&name),
+        ] {
+            match fs::File::open(&path) {
+                Ok(_) => return WalReader::new(&path).map(Some),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e).ctx(),
+            }
+        }
+        Ok(None)
+    }
+
+    /// First sequence logged in WAL `number`; `None` if it holds no
+    /// records or has been purged.
+    fn first_sequence(&self, number: u64) -> Result<Option<SequenceNumber>> {
+        let Some(mut reader) = self.open(number)? else {
+            return Ok(None);
+        };
+        match reader.read_record() {
+            Ok(Some(data)) => DB::decode_wal_record(&data, |_, _, _, _, _| {}).map(Some),
+            Ok(None) => Ok(None),
+            // A torn first record: only possible in the live WAL.
+            Err(e) if e.kind() == ErrorKind::Corruption && reader.last_error_is_truncation() => {
+                Ok(None)
+            }
+            Err(e) => Err(e).with_ctx(|| format!("failed to read WAL {}", number)),
+        }
+    }
+
+    fn next_batch(&mut self) -> Result<Option<(SequenceNumber, WriteBatch)>> {
+        loop {
+            let Some(reader) = self.reader.as_mut() else {
+                let Some(number) = self.wals.pop_front() else {
+                    return Ok(None);
+                };
+                match self.open(number)? {
+                    Some(reader) => self.reader = Some(reader),
+                    None => {
+                        return Err(Error::not_found(format!(
+                            "WAL {} was purged while being read",
+                            number
+                        )));
+                    }
+                }
+                continue;
+            };
+            let data = match reader.read_record() {
+                Ok(Some(data)) => data,
+                Ok(None) => {
+                    self.reader = None;
+                    continue;
+                }
+                // The live WAL may end in a record still being appended.
+                Err(e)
+                    if e.kind() == ErrorKind::Corruption
+                        && reader.last_error_is_truncation()
+                        && self.wals.is_empty() =>
+                {
+                    return Ok(None);
+                }
+                Err(e) => return Err(e),
+            };
+            let mut batch = WriteBatch::new();
+            let first = DB::decode_wal_record(&data, |_, cf, vt, key, value| {
+                batch.push(cf, vt, key, (vt != ValueType::Deletion).then_some(value));
+            })?;
+            if first > self.last {
+                return Ok(None);
+            }
+            let end = first + batch.len().saturating_sub(1) as u64;
+            if batch.is_empty() || end < self.since {
+                continue;
+            }
+            return Ok(Some((first, batch)));
+        }
+    }
+}
+
+impl Iterator for WalUpdateIterator {
+    type Item = Result<(SequenceNumber, WriteBatch)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let next = self.next_batch().transpose();
+        if !matches!(next, Some(Ok(_))) {
+            self.done = true;
+        }
+        next
+    }
+}
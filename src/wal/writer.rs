@@ -5,6 +5,7 @@ use std::io::{BufWriter, Seek, SeekFrom, Write};
 use std::path::Path;
 
 use crate::error::{Result, ResultExt};
+use crate::sst::format::CompressionType;
 use crate::wal::record::*;
 
 /// WAL writer. Appends records to a file, splitting across block boundaries.
@@ -12,11 +13,21 @@ pub struct WalWriter {
     writer: BufWriter<File>,
     /// Current offset within the current block.
     block_offset: usize,
+    /// Compression applied to each record before fragmentation.
+    compression: CompressionType,
 }
 
 impl WalWriter {
     /// Create a new WAL writer for the given file path.
     pub fn new(path: &Path) -> Result<Self> {
+        Self::with_compression(path, CompressionType::None)
+    }
+
+    /// Create a new WAL writer that compresses every record.
+    ///
+    /// Unless `compression` is `None`, a `SetCompression` record is written
+    /// first so `WalReader` knows to decompress what follows.
+    pub fn with_compression(path: &Path, compression: CompressionType) -> Result<Self> {
         let file = OpenOptions::new()
             .create(true)
             .truncate(true)
@@ -24,10 +35,15 @@ impl WalWriter {
             .open(path)
             .ctx()?;
         Self::sync_parent_dir(path).ctx()?;
-        Ok(Self {
+        let mut writer = Self {
             writer: BufWriter::new(file),
             block_offset: 0,
-        })
+            compression,
+        };
+        if compression != CompressionType::None {
+            writer.emit_fragment(RecordType::SetCompression, &[compression as u8])?;
+        }
+        Ok(writer)
     }
 
     /// Reopen a WAL file for appending, truncating it to `valid_len` first.
@@ -35,6 +51,7 @@ impl WalWriter {
     /// After a crash, the file may contain a corrupt partial record at the tail.
     /// Use `WalReader::last_valid_offset()` to determine the safe truncation
     /// point, then call this to discard the corrupt tail before appending.
+    /// Records are appended uncompressed.
     pub fn open_append_truncated(path: &Path, valid_len: u64) -> Result<Self> {
         let mut file = OpenOptions::new().write(true).open(path).ctx()?;
         file.set_len(valid_len).ctx()?;
@@ -43,6 +60,7 @@ impl WalWriter {
         Ok(Self {
             writer: BufWriter::new(file),
             block_offset,
+            compression: CompressionType::None,
         })
     }
 
@@ -50,6 +68,14 @@ impl WalWriter {
     ///
     /// The record may be split into multiple fragments across block boundaries.
     pub fn add_record(&mut self, payload: &[u8]) -> Result<()> {
+        if self.compression == CompressionType::None {
+            return self.add_fragments(payload);
+        }
+        let compressed = compress_record(self.compression, payload)?;
+        self.add_fragments(&compressed)
+    }
+
+    fn add_fragments(&mut self, payload: &[u8]) -> Result<()> {
         let mut left = payload;
         let mut is_first = true;
 
@@ -275,4 +301,31 @@ mod tests {
         assert_eq!(records[3], b"batch3_rec1");
         assert_eq!(records[4], b"batch3_rec2");
     }
+
+    #[test]
+    fn test_compressed_records_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let large = b"compressible ".repeat(BLOCK_SIZE / 4);
+
+        for compression in [CompressionType::Lz4, CompressionType::Zstd] {
+            let path = dir.path().join(format!("{:?}.wal", compression));
+            {
+                let mut writer = WalWriter::with_compression(&path, compression).unwrap();
+                writer.add_record(b"").unwrap();
+                writer.add_record(b"hello world").unwrap();
+                writer.add_record(&large).unwrap();
+                writer.sync().unwrap();
+            }
+            let file_len = std::fs::metadata(&path).unwrap().len();
+            assert!(file_len < (large.len() / 4) as u64, "{compression:?}");
+
+            let mut reader = WalReader::new(&path).unwrap();
+            let records: Vec<Vec<u8>> = reader.iter().collect::<StdResult<Vec<_>, _>>().unwrap();
+            assert_eq!(
+                records,
+                vec![b"".to_vec(), b"hello world".to_vec(), large.clone()]
+            );
+            assert_eq!(reader.last_valid_offset(), file_len);
+        }
+    }
 }
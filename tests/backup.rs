@@ -0,0 +1,156 @@
+//! Backup engine tests: incremental backups, purge, verify and restore.
+
+use std::fs;
+use std::path::Path;
+
+use mmdb::{BackupEngine, DB, DbOptions, ErrorKind};
+
+fn make_opts() -> DbOptions {
+    DbOptions {
+        create_if_missing: true,
+        ..Default::default()
+    }
+}
+
+fn count_files(dir: &Path) -> usize {
+    fs::read_dir(dir).unwrap().count()
+}
+
+#[test]
+fn test_backups_share_ssts_and_restore() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = DB::open(make_opts(), dir.path().join("db")).unwrap();
+    let meta = db.create_column_family("meta", make_opts()).unwrap();
+    let backup_dir = dir.path().join("backups");
+    let mut engine = BackupEngine::open(&backup_dir).unwrap();
+
+    for i in 0..100u32 {
+        db.put(format!("k{i:03}").as_bytes(), b"v1").unwrap();
+    }
+    db.put_cf(&meta, b"schema", b"1").unwrap();
+    let first = engine.create_new_backup(&db).unwrap();
+    let shared_after_first = count_files(&backup_dir.join("shared"));
+    assert_eq!(shared_after_first, 2);
+
+    // Only the SSTs created since the first backup are stored again.
+    db.put(b"k000", b"v2").unwrap();
+    let second = engine.create_new_backup(&db).unwrap();
+    assert!(second > first);
+    assert_eq!(
+        count_files(&backup_dir.join("shared")),
+        shared_after_first + 1
+    );
+
+    let info = engine.get_backup_info();
+    assert_eq!(
+        info.iter().map(|b| b.backup_id).collect::<Vec<_>>(),
+        [first, second]
+    );
+    assert!(info[1].num_files > info[0].num_files);
+    assert!(info[0].size > 0);
+    engine.verify_backup(first).unwrap();
+    engine.verify_backup(second).unwrap();
+
+    // Backups survive reopening the engine; each restores its own state.
+    drop(engine);
+    let engine = BackupEngine::open(&backup_dir).unwrap();
+    for (id, expected) in [(first, b"v1"), (second, b"v2")] {
+        let target = dir.path().join(format!("restore-{id}"));
+        engine.restore_db_from_backup(id, &target).unwrap();
+        let restored = DB::open(make_opts(), &target).unwrap();
+        assert_eq!(restored.get(b"k000").unwrap(), Some(expected.to_vec()));
+        assert_eq!(restored.get(b"k099").unwrap(), Some(b"v1".to_vec()));
+        let meta = restored.column_family("meta").unwrap();
+        assert_eq!(
+            restored.get_cf(&meta, b"schema").unwrap(),
+            Some(b"1".to_vec())
+        );
+        restored.close().unwrap();
+    }
+    assert_eq!(
+        engine
+            .restore_db_from_backup(first, dir.path().join(format!("restore-{first}")))
+            .unwrap_err()
+            .kind(),
+        ErrorKind::InvalidArgument
+    );
+    db.close().unwrap();
+}
+
+#[test]
+fn test_purge_old_backups_collects_unshared_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = DB::open(make_opts(), dir.path().join("db")).unwrap();
+    let backup_dir = dir.path().join("backups");
+    let mut engine = BackupEngine::open(&backup_dir).unwrap();
+
+    let mut ids = Vec::new();
+    for round in 0..3u32 {
+        db.put(format!("round{round}").as_bytes(), b"x").unwrap();
+        ids.push(engine.create_new_backup(&db).unwrap());
+    }
+    // Compaction merges every round into one new SST.
+    db.compact_range(None, None).unwrap();
+    ids.push(engine.create_new_backup(&db).unwrap());
+    assert_eq!(count_files(&backup_dir.join("shared")), 4);
+
+    engine.purge_old_backups(1).unwrap();
+    assert_eq!(
+        engine
+            .get_backup_info()
+            .iter()
+            .map(|b| b.backup_id)
+            .collect::<Vec<_>>(),
+        [ids[3]]
+    );
+    assert_eq!(count_files(&backup_dir.join("shared")), 1);
+    assert_eq!(count_files(&backup_dir.join("private")), 1);
+    assert_eq!(
+        engine.verify_backup(ids[0]).unwrap_err().kind(),
+        ErrorKind::InvalidArgument
+    );
+
+    let target = dir.path().join("restore");
+    engine.restore_db_from_backup(ids[3], &target).unwrap();
+    let restored = DB::open_read_only(&target).unwrap();
+    for round in 0..3u32 {
+        assert_eq!(
+            restored.get(format!("round{round}").as_bytes()).unwrap(),
+            Some(b"x".to_vec())
+        );
+    }
+}
+
+#[test]
+fn test_verify_and_restore_detect_corruption() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = DB::open(make_opts(), dir.path().join("db")).unwrap();
+    db.put(b"key", b"value").unwrap();
+    let backup_dir = dir.path().join("backups");
+    let mut engine = BackupEngine::open(&backup_dir).unwrap();
+    let id = engine.create_new_backup(&db).unwrap();
+    db.close().unwrap();
+
+    // Corrupt the stored SST in place, keeping its size.
+    let sst = fs::read_dir(backup_dir.join("shared"))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap()
+        .path();
+    let mut bytes = fs::read(&sst).unwrap();
+    bytes[0] ^= 0xFF;
+    fs::write(&sst, bytes).unwrap();
+
+    assert_eq!(
+        engine.verify_backup(id).unwrap_err().kind(),
+        ErrorKind::Corruption
+    );
+    assert_eq!(
+        engine
+            .restore_db_from_backup(id, dir.path().join("restore"))
+            .unwrap_err()
+            .kind(),
+        ErrorKind::Corruption
+    );
+}
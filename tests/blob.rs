@@ -0,0 +1,205 @@
+//! Key-value separation: large values in blob files, garbage collection.
+
+use std::path::Path;
+
+use mmdb::{DB, DbOptions, ErrorKind, ReadOptions};
+
+fn blob_opts(threshold: f64) -> DbOptions {
+    DbOptions {
+        create_if_missing: true,
+        min_blob_size: Some(1024),
+        blob_garbage_collection_threshold: threshold,
+        ..Default::default()
+    }
+}
+
+/// Incompressible filler of `len` bytes.
+fn noise(seed: u64, len: usize) -> Vec<u8> {
+    let mut x = seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1;
+    (0..len)
+        .map(|_| {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            x as u8
+        })
+        .collect()
+}
+
+fn key(i: u64) -> Vec<u8> {
+    format!("key{:03}", i).into_bytes()
+}
+
+/// Large values for even keys, small (inline) ones for odd keys.
+fn value(i: u64, round: u64) -> Vec<u8> {
+    let len = if i.is_multiple_of(2) { 8192 } else { 100 };
+    noise(i * 1000 + round, len)
+}
+
+fn blob_files(dir: &Path) -> Vec<u64> {
+    let mut numbers: Vec<u64> = std::fs::read_dir(dir)
+        .unwrap()
+        .filter_map(|e| {
+            let name = e.unwrap().file_name().to_string_lossy().into_owned();
+            name.strip_suffix(".blob")?.parse().ok()
+        })
+        .collect();
+    numbers.sort();
+    numbers
+}
+
+fn check_contents(db: &DB, keys: u64, round: impl Fn(u64) -> u64) {
+    for i in 0..keys {
+        assert_eq!(
+            db.get(&key(i)).unwrap(),
+            Some(value(i, round(i))),
+            "key {i}"
+        );
+    }
+    let scanned: Vec<(Vec<u8>, Vec<u8>)> = db.iter().unwrap().collect();
+    let expected: Vec<(Vec<u8>, Vec<u8>)> =
+        (0..keys).map(|i| (key(i), value(i, round(i)))).collect();
+    assert_eq!(scanned, expected);
+
+    let mut iter = db.iter().unwrap();
+    iter.seek_to_last();
+    for i in (0..keys).rev() {
+        assert!(iter.valid());
+        assert_eq!(iter.key().unwrap(), key(i));
+        assert_eq!(iter.value().unwrap(), value(i, round(i)));
+        iter.prev();
+    }
+    assert!(!iter.valid());
+
+    let wanted: Vec<Vec<u8>> = (0..keys).map(key).collect();
+    let wanted: Vec<&[u8]> = wanted.iter().map(|k| k.as_slice()).collect();
+    for (i, result) in db
+        .multi_get(&ReadOptions::default(), &wanted)
+        .into_iter()
+        .enumerate()
+    {
+        let i = i as u64;
+        assert_eq!(result.unwrap(), Some(value(i, round(i))));
+    }
+}
+
+#[test]
+fn test_large_values_roundtrip_through_blob_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = DB::open(blob_opts(0.5), dir.path()).unwrap();
+    for i in 0..40 {
+        db.put(&key(i), &value(i, 0)).unwrap();
+    }
+    check_contents(&db, 40, |_| 0);
+    assert!(blob_files(dir.path()).is_empty());
+
+    db.flush().unwrap();
+    assert_eq!(blob_files(dir.path()).len(), 1);
+    check_contents(&db, 40, |_| 0);
+
+    // The SSTs hold handles, not the 160 KiB of large values.
+    let sst_size: u64 = db.get_property("total-sst-size").unwrap().parse().unwrap();
+    assert!(
+        sst_size < 40 * 1024,
+        "large values were stored inline: {sst_size}"
+    );
+
+    db.compact_range(None, None).unwrap();
+    check_contents(&db, 40, |_| 0);
+    db.close().unwrap();
+
+    let db = DB::open(blob_opts(0.5), dir.path()).unwrap();
+    check_contents(&db, 40, |_| 0);
+    assert_eq!(blob_files(dir.path()).len(), 1);
+}
+
+#[test]
+fn test_fully_overwritten_blob_file_is_deleted() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = DB::open(blob_opts(1.0), dir.path()).unwrap();
+    for i in 0..20 {
+        db.put(&key(i), &value(i, 0)).unwrap();
+    }
+    db.flush().unwrap();
+    let first = blob_files(dir.path());
+    assert_eq!(first.len(), 1);
+
+    for i in 0..20 {
+        db.put(&key(i), &value(i, 1)).unwrap();
+    }
+    db.flush().unwrap();
+    assert_eq!(blob_files(dir.path()).len(), 2);
+
+    db.compact_range(None, None).unwrap();
+    let remaining = blob_files(dir.path());
+    assert_eq!(remaining.len(), 1);
+    assert!(!remaining.contains(&first[0]));
+    check_contents(&db, 20, |_| 1);
+
+    db.close().unwrap();
+    let db = DB::open(blob_opts(1.0), dir.path()).unwrap();
+    check_contents(&db, 20, |_| 1);
+}
+
+#[test]
+fn test_garbage_collection_relocates_live_values() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = DB::open(blob_opts(0.25), dir.path()).unwrap();
+    for i in 0..20 {
+        db.put(&key(i), &value(i, 0)).unwrap();
+    }
+    db.flush().unwrap();
+    let first = blob_files(dir.path())[0];
+
+    // Overwrite half of the large values: the first file is then 50% garbage.
+    let round = |i: u64| u64::from(i.is_multiple_of(4));
+    for i in (0..20).step_by(4) {
+        db.put(&key(i), &value(i, 1)).unwrap();
+    }
+    db.flush().unwrap();
+    db.compact_range(None, None).unwrap();
+
+    // Its live values were moved out and the file deleted.
+    assert!(!blob_files(dir.path()).contains(&first));
+    check_contents(&db, 20, round);
+
+    db.close().unwrap();
+    let db = DB::open(blob_opts(0.25), dir.path()).unwrap();
+    check_contents(&db, 20, round);
+}
+
+#[test]
+fn test_threshold_one_keeps_partially_live_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = DB::open(blob_opts(1.0), dir.path()).unwrap();
+    for i in 0..20 {
+        db.put(&key(i), &value(i, 0)).unwrap();
+    }
+    db.flush().unwrap();
+    let first = blob_files(dir.path())[0];
+    db.put(&key(0), &value(0, 1)).unwrap();
+    db.flush().unwrap();
+    db.compact_range(None, None).unwrap();
+    db.compact_range(None, None).unwrap();
+    assert!(blob_files(dir.path()).contains(&first));
+    check_contents(&db, 20, |i| u64::from(i == 0));
+}
+
+#[test]
+fn test_invalid_blob_options_are_rejected() {
+    for opts in [
+        DbOptions {
+            min_blob_size: Some(0),
+            ..blob_opts(0.5)
+        },
+        blob_opts(-0.1),
+        blob_opts(1.5),
+        blob_opts(f64::NAN),
+    ] {
+        let dir = tempfile::tempdir().unwrap();
+        let Err(err) = DB::open(opts, dir.path()) else {
+            panic!("open with invalid blob options succeeded");
+        };
+        assert_eq!(err.kind(), ErrorKind::InvalidArgument, "{err}");
+    }
+}
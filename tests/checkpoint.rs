@@ -0,0 +1,136 @@
+//! Checkpoint tests: hard-linked, openable copies of a live database.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+
+use mmdb::{DB, DbOptions, ErrorKind, WriteBatch};
+
+fn make_opts() -> DbOptions {
+    DbOptions {
+        create_if_missing: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_checkpoint_during_concurrent_writes_is_consistent() {
+    let dir = tempfile::tempdir().unwrap();
+    let opts = DbOptions {
+        write_buffer_size: 8 * 1024,
+        l0_compaction_trigger: 2,
+        ..make_opts()
+    };
+    let db = Arc::new(DB::open(opts, dir.path().join("db")).unwrap());
+    for i in 0..200u32 {
+        db.put(format!("pre{i:04}").as_bytes(), &[b'p'; 100])
+            .unwrap();
+    }
+
+    // Each batch writes a matching pair; a consistent checkpoint holds
+    // both halves of a prefix of the batches and nothing else.
+    let stop = Arc::new(AtomicBool::new(false));
+    let writer = {
+        let db = db.clone();
+        let stop = stop.clone();
+        thread::spawn(move || {
+            let mut i = 0u32;
+            while !stop.load(Ordering::Relaxed) {
+                let mut batch = WriteBatch::new();
+                batch.put(format!("a{i:06}").as_bytes(), &[b'a'; 100]);
+                batch.put(format!("b{i:06}").as_bytes(), &[b'b'; 100]);
+                db.write(batch).unwrap();
+                i += 1;
+            }
+            i
+        })
+    };
+    while db.get(b"a000500").unwrap().is_none() {
+        thread::yield_now();
+    }
+    let cp1 = dir.path().join("cp1");
+    let cp2 = dir.path().join("cp2");
+    db.create_checkpoint(&cp1).unwrap();
+    db.create_checkpoint(&cp2).unwrap();
+    stop.store(true, Ordering::Relaxed);
+    let written = writer.join().unwrap();
+    db.compact_range(None, None).unwrap();
+
+    let check = |cp: &DB| {
+        let entries: Vec<_> = cp.iter().unwrap().collect();
+        let a = entries.iter().filter(|(k, _)| k[0] == b'a').count();
+        let b = entries.iter().filter(|(k, _)| k[0] == b'b').count();
+        assert_eq!(a, b);
+        assert!(a > 500 && a as u32 <= written);
+        assert_eq!(entries.len(), a + b + 200);
+        assert_eq!(
+            cp.get(format!("a{:06}", a - 1).as_bytes()).unwrap(),
+            Some(vec![b'a'; 100])
+        );
+        a
+    };
+
+    let read_only = DB::open_read_only(&cp1).unwrap();
+    let count = check(&read_only);
+    drop(read_only);
+    let reopened = DB::open(make_opts(), &cp1).unwrap();
+    assert_eq!(check(&reopened), count);
+    // The checkpoint is an independent database.
+    reopened.put(b"only-in-checkpoint", b"1").unwrap();
+    assert_eq!(db.get(b"only-in-checkpoint").unwrap(), None);
+    reopened.close().unwrap();
+
+    let second = DB::open_read_only(&cp2).unwrap();
+    assert!(check(&second) >= count);
+}
+
+#[test]
+fn test_checkpoint_includes_column_families() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = DB::open(make_opts(), dir.path().join("db")).unwrap();
+    let meta = db.create_column_family("meta", make_opts()).unwrap();
+    db.put(b"k", b"default").unwrap();
+    db.put_cf(&meta, b"k", b"meta").unwrap();
+    db.flush_cf(&meta).unwrap();
+    db.put_cf(&meta, b"unflushed", b"1").unwrap();
+
+    let cp = dir.path().join("cp");
+    db.create_checkpoint(&cp).unwrap();
+    db.put_cf(&meta, b"after", b"1").unwrap();
+
+    let copy = DB::open(make_opts(), &cp).unwrap();
+    let meta = copy.column_family("meta").unwrap();
+    assert_eq!(copy.get(b"k").unwrap(), Some(b"default".to_vec()));
+    assert_eq!(copy.get_cf(&meta, b"k").unwrap(), Some(b"meta".to_vec()));
+    assert_eq!(
+        copy.get_cf(&meta, b"unflushed").unwrap(),
+        Some(b"1".to_vec())
+    );
+    assert_eq!(copy.get_cf(&meta, b"after").unwrap(), None);
+    copy.close().unwrap();
+}
+
+#[test]
+fn test_checkpoint_of_read_only_handle_copies_wal() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db");
+    {
+        let db = DB::open(make_opts(), &path).unwrap();
+        db.put(b"flushed", b"1").unwrap();
+        db.flush().unwrap();
+        db.put(b"in-wal", b"2").unwrap();
+        db.simulate_crash();
+    }
+
+    let db = DB::open_read_only(&path).unwrap();
+    let cp = dir.path().join("cp");
+    db.create_checkpoint(&cp).unwrap();
+    assert_eq!(
+        db.create_checkpoint(&cp).unwrap_err().kind(),
+        ErrorKind::InvalidArgument
+    );
+
+    let copy = DB::open_read_only(&cp).unwrap();
+    assert_eq!(copy.get(b"flushed").unwrap(), Some(b"1".to_vec()));
+    assert_eq!(copy.get(b"in-wal").unwrap(), Some(b"2".to_vec()));
+}
@@ -0,0 +1,302 @@
+//! Column family tests: independent keyspaces sharing one WAL.
+
+use std::sync::Arc;
+
+use mmdb::{
+    CompactionFilter, CompactionFilterDecision, DB, DbOptions, ErrorKind, MergeOperator,
+    ReadOptions, WriteBatch,
+};
+
+fn make_opts() -> DbOptions {
+    DbOptions {
+        create_if_missing: true,
+        ..Default::default()
+    }
+}
+
+fn collect(db: &DB, cf: &mmdb::ColumnFamilyHandle) -> Vec<(Vec<u8>, Vec<u8>)> {
+    db.iter_cf(cf, &ReadOptions::default()).unwrap().collect()
+}
+
+struct AppendOperator;
+
+impl MergeOperator for AppendOperator {
+    fn name(&self) -> &str {
+        "append"
+    }
+
+    fn full_merge(
+        &self,
+        _key: &[u8],
+        existing: Option<&[u8]>,
+        operands: &[&[u8]],
+    ) -> Option<Vec<u8>> {
+        let mut out = existing.map(<[u8]>::to_vec).unwrap_or_default();
+        for op in operands {
+            out.extend_from_slice(op);
+        }
+        Some(out)
+    }
+}
+
+/// Drops every value that starts with `tmp:`.
+struct DropTemp;
+
+impl CompactionFilter for DropTemp {
+    fn filter(&self, _level: usize, _key: &[u8], value: &[u8]) -> CompactionFilterDecision {
+        if value.starts_with(b"tmp:") {
+            CompactionFilterDecision::Remove
+        } else {
+            CompactionFilterDecision::Keep
+        }
+    }
+}
+
+#[test]
+fn test_families_are_isolated_and_batches_span_them() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = DB::open(make_opts(), dir.path()).unwrap();
+    let users = db.create_column_family("users", make_opts()).unwrap();
+    let index = db.create_column_family("index", make_opts()).unwrap();
+    assert_ne!(users.id(), index.id());
+
+    let mut batch = WriteBatch::new();
+    batch.put(b"k", b"default");
+    batch.put_cf(&users, b"k", b"user");
+    batch.put_cf(&index, b"k", b"index");
+    batch.put_cf(&index, b"z", b"zz");
+    db.write(batch).unwrap();
+
+    assert_eq!(db.get(b"k").unwrap(), Some(b"default".to_vec()));
+    assert_eq!(db.get_cf(&users, b"k").unwrap(), Some(b"user".to_vec()));
+    assert_eq!(db.get_cf(&index, b"k").unwrap(), Some(b"index".to_vec()));
+    assert_eq!(db.get(b"z").unwrap(), None);
+    assert_eq!(collect(&db, &index).len(), 2);
+
+    // A snapshot taken on the DB pins every family.
+    let snap = db.snapshot();
+    let mut batch = WriteBatch::new();
+    batch.delete_cf(&users, b"k");
+    batch.delete_range_cf(&index, b"a", b"zz");
+    db.write(batch).unwrap();
+    assert_eq!(db.get_cf(&users, b"k").unwrap(), None);
+    assert!(collect(&db, &index).is_empty());
+    assert_eq!(
+        db.get_cf_with_options(&users, &snap.read_options(), b"k")
+            .unwrap(),
+        Some(b"user".to_vec())
+    );
+    assert_eq!(db.iter_cf(&index, &snap.read_options()).unwrap().count(), 2);
+    drop(snap);
+
+    let names: Vec<String> = db
+        .column_families()
+        .iter()
+        .map(|cf| cf.name().to_string())
+        .collect();
+    assert_eq!(names, ["default", "users", "index"]);
+    assert_eq!(db.column_family("users"), Some(users));
+    assert_eq!(
+        db.create_column_family("index", make_opts())
+            .unwrap_err()
+            .kind(),
+        ErrorKind::InvalidArgument
+    );
+    db.close().unwrap();
+}
+
+#[test]
+fn test_batch_with_unknown_family_is_rejected_atomically() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = DB::open(make_opts(), dir.path()).unwrap();
+    let cf = db.create_column_family("cf", make_opts()).unwrap();
+    db.drop_column_family(&cf).unwrap();
+
+    let mut batch = WriteBatch::new();
+    batch.put(b"a", b"1");
+    batch.put_cf(&cf, b"b", b"2");
+    assert_eq!(
+        db.write(batch).unwrap_err().kind(),
+        ErrorKind::InvalidArgument
+    );
+    assert_eq!(db.get(b"a").unwrap(), None);
+    assert_eq!(
+        db.get_cf(&cf, b"b").unwrap_err().kind(),
+        ErrorKind::InvalidArgument
+    );
+}
+
+#[test]
+fn test_families_recover_from_shared_wal_after_crash() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path();
+    let small = DbOptions {
+        write_buffer_size: 4096,
+        ..make_opts()
+    };
+    {
+        let db = DB::open(small.clone(), path).unwrap();
+        let hot = db.create_column_family("hot", small.clone()).unwrap();
+        let cold = db.create_column_family("cold", make_opts()).unwrap();
+        // `cold` writes first and never fills its buffer, so its entries
+        // must survive every WAL switch driven by `hot` and the default
+        // family.
+        for i in 0..20u32 {
+            db.put_cf(&cold, format!("c{i:03}").as_bytes(), b"cold")
+                .unwrap();
+        }
+        for i in 0..500u32 {
+            let key = format!("k{i:04}");
+            let mut batch = WriteBatch::new();
+            batch.put(key.as_bytes(), &[b'd'; 64]);
+            batch.put_cf(&hot, key.as_bytes(), &[b'h'; 64]);
+            db.write(batch).unwrap();
+        }
+        assert!(
+            db.get_property_cf(&hot, "num-files-at-level0")
+                .unwrap()
+                .parse::<usize>()
+                .unwrap()
+                + db.get_property_cf(&hot, "num-files-at-level1")
+                    .unwrap()
+                    .parse::<usize>()
+                    .unwrap()
+                > 0,
+            "hot family should have flushed"
+        );
+        db.simulate_crash();
+    }
+
+    let db = DB::open(small, path).unwrap();
+    let hot = db.column_family("hot").unwrap();
+    let cold = db.column_family("cold").unwrap();
+    for i in 0..20u32 {
+        assert_eq!(
+            db.get_cf(&cold, format!("c{i:03}").as_bytes()).unwrap(),
+            Some(b"cold".to_vec())
+        );
+    }
+    for i in 0..500u32 {
+        let key = format!("k{i:04}");
+        assert_eq!(db.get(key.as_bytes()).unwrap(), Some(vec![b'd'; 64]));
+        assert_eq!(
+            db.get_cf(&hot, key.as_bytes()).unwrap(),
+            Some(vec![b'h'; 64])
+        );
+    }
+    // Recovery flushed everything and retired the old WALs.
+    let wals = std::fs::read_dir(path)
+        .unwrap()
+        .filter(|e| {
+            e.as_ref()
+                .unwrap()
+                .file_name()
+                .to_string_lossy()
+                .ends_with(".wal")
+        })
+        .count();
+    assert_eq!(wals, 1);
+    db.close().unwrap();
+}
+
+#[test]
+fn test_close_and_reopen_preserves_families() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path();
+    {
+        let db = DB::open(make_opts(), path).unwrap();
+        let cf = db.create_column_family("meta", make_opts()).unwrap();
+        db.put_cf(&cf, b"version", b"3").unwrap();
+        db.put(b"version", b"1").unwrap();
+        db.close().unwrap();
+    }
+    let db = DB::open_read_only(path).unwrap();
+    let cf = db.column_family("meta").unwrap();
+    assert_eq!(db.get_cf(&cf, b"version").unwrap(), Some(b"3".to_vec()));
+    assert_eq!(db.get(b"version").unwrap(), Some(b"1".to_vec()));
+    assert_eq!(
+        db.put_cf(&cf, b"x", b"y").unwrap_err().kind(),
+        ErrorKind::ReadOnly
+    );
+}
+
+#[test]
+fn test_drop_column_family_removes_data_and_never_reuses_id() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path();
+    let old_id;
+    {
+        let db = DB::open(make_opts(), path).unwrap();
+        let cf = db.create_column_family("tmp", make_opts()).unwrap();
+        old_id = cf.id();
+        db.put_cf(&cf, b"a", b"1").unwrap();
+        db.flush_cf(&cf).unwrap();
+        db.put_cf(&cf, b"b", b"2").unwrap();
+        assert!(path.join(format!("cf-{old_id}")).exists());
+
+        db.drop_column_family(&cf).unwrap();
+        assert!(!path.join(format!("cf-{old_id}")).exists());
+        assert_eq!(db.column_family("tmp"), None);
+        assert_eq!(
+            db.drop_column_family(&mmdb::ColumnFamilyHandle::default_family())
+                .unwrap_err()
+                .kind(),
+            ErrorKind::InvalidArgument
+        );
+        db.simulate_crash();
+    }
+
+    // The dropped family's WAL entries are skipped on recovery, and a new
+    // family with the same name starts empty under a fresh id.
+    let db = DB::open(make_opts(), path).unwrap();
+    assert_eq!(db.column_family("tmp"), None);
+    let cf = db.create_column_family("tmp", make_opts()).unwrap();
+    assert!(cf.id() > old_id);
+    assert_eq!(db.get_cf(&cf, b"a").unwrap(), None);
+    assert_eq!(db.get_cf(&cf, b"b").unwrap(), None);
+    db.close().unwrap();
+}
+
+#[test]
+fn test_families_use_their_own_options() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path();
+    let counters_opts = DbOptions {
+        merge_operator: Some(Arc::new(AppendOperator)),
+        compaction_filter: Some(Arc::new(DropTemp)),
+        ..make_opts()
+    };
+    {
+        let db =
+            DB::open_with_column_families(make_opts(), path, [("counters", counters_opts.clone())])
+                .unwrap();
+        let counters = db.column_family("counters").unwrap();
+
+        db.merge_cf(&counters, b"log", b"a").unwrap();
+        db.merge_cf(&counters, b"log", b"b").unwrap();
+        assert_eq!(db.get_cf(&counters, b"log").unwrap(), Some(b"ab".to_vec()));
+        // The default family has no merge operator.
+        assert_eq!(
+            db.merge(b"log", b"a").unwrap_err().kind(),
+            ErrorKind::InvalidArgument
+        );
+
+        db.put_cf(&counters, b"scratch", b"tmp:1").unwrap();
+        db.put(b"scratch", b"tmp:1").unwrap();
+        db.compact_range_cf(&counters, None, None).unwrap();
+        db.compact_range(None, None).unwrap();
+        assert_eq!(db.get_cf(&counters, b"scratch").unwrap(), None);
+        assert_eq!(db.get(b"scratch").unwrap(), Some(b"tmp:1".to_vec()));
+        db.close().unwrap();
+    }
+
+    // Listed options are applied again on reopen.
+    let db =
+        DB::open_with_column_families(make_opts(), path, [("counters", counters_opts)]).unwrap();
+    let counters = db.column_family("counters").unwrap();
+    db.merge_cf(&counters, b"log", b"c").unwrap();
+    assert_eq!(db.get_cf(&counters, b"log").unwrap(), Some(b"abc".to_vec()));
+    let reserved =
+        DB::open_with_column_families(make_opts(), path.join("other"), [("default", make_opts())]);
+    assert!(matches!(reserved, Err(e) if e.kind() == ErrorKind::InvalidArgument));
+}
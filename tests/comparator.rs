@@ -0,0 +1,237 @@
+//! User-defined key order, exercised with a comparator that reverses
+//! bytewise order.
+
+use std::cmp::Ordering;
+use std::sync::Arc;
+
+use mmdb::{
+    Comparator, DB, DbOptions, ErrorKind, IngestExternalFileOptions, ReadOptions, SstFileWriter,
+};
+
+struct ReverseComparator;
+
+impl Comparator for ReverseComparator {
+    fn name(&self) -> &str {
+        "test.ReverseComparator"
+    }
+
+    fn compare(&self, a: &[u8], b: &[u8]) -> Ordering {
+        b.cmp(a)
+    }
+}
+
+fn reverse_opts() -> DbOptions {
+    DbOptions {
+        create_if_missing: true,
+        comparator: Arc::new(ReverseComparator),
+        ..Default::default()
+    }
+}
+
+fn keys(db: &DB) -> Vec<Vec<u8>> {
+    db.iter().unwrap().map(|(k, _)| k).collect()
+}
+
+fn keys_reverse(db: &DB) -> Vec<Vec<u8>> {
+    let mut iter = db.iter().unwrap();
+    iter.seek_to_last();
+    let mut out = Vec::new();
+    while iter.valid() {
+        out.push(iter.key().unwrap().to_vec());
+        iter.prev();
+    }
+    out
+}
+
+fn k(i: u32) -> Vec<u8> {
+    format!("key{i:03}").into_bytes()
+}
+
+#[test]
+fn test_reverse_order_across_memtable_sst_and_reopen() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = DB::open(reverse_opts(), dir.path()).unwrap();
+    for i in 0..50 {
+        db.put(&k(i), format!("v{i}").as_bytes()).unwrap();
+    }
+    db.flush().unwrap();
+    for i in 50..100 {
+        db.put(&k(i), format!("v{i}").as_bytes()).unwrap();
+    }
+
+    let expected: Vec<Vec<u8>> = (0..100).rev().map(k).collect();
+    assert_eq!(keys(&db), expected);
+    let mut reversed = expected.clone();
+    reversed.reverse();
+    assert_eq!(keys_reverse(&db), reversed);
+
+    // "key070" sorts before "key069" here, so seek lands on the next
+    // smaller bytewise key and seek_for_prev on the next larger one.
+    let mut iter = db.iter().unwrap();
+    iter.seek(b"key069x");
+    assert_eq!(iter.key().unwrap(), b"key069");
+    iter.seek_for_prev(b"key069x");
+    assert_eq!(iter.key().unwrap(), b"key070");
+
+    // delete_range(begin, end) covers keys in [begin, end) under the
+    // comparator: "key020" down to, but excluding, "key010".
+    db.delete_range(&k(20), &k(10)).unwrap();
+    assert_eq!(db.get(&k(20)).unwrap(), None);
+    assert_eq!(db.get(&k(11)).unwrap(), None);
+    assert_eq!(db.get(&k(10)).unwrap(), Some(b"v10".to_vec()));
+    assert_eq!(db.get(&k(21)).unwrap(), Some(b"v21".to_vec()));
+
+    db.flush().unwrap();
+    db.compact_range(None, None).unwrap();
+    let expected: Vec<Vec<u8>> = (0..100)
+        .rev()
+        .filter(|i| !(11..=20).contains(i))
+        .map(k)
+        .collect();
+    assert_eq!(keys(&db), expected);
+
+    // Bounds are interpreted by the comparator as well.
+    let opts = ReadOptions {
+        iterate_lower_bound: Some(k(60)),
+        iterate_upper_bound: Some(k(55)),
+        ..Default::default()
+    };
+    let bounded: Vec<Vec<u8>> = db
+        .iter_with_options(&opts)
+        .unwrap()
+        .map(|(k, _)| k)
+        .collect();
+    assert_eq!(bounded, (56..=60).rev().map(k).collect::<Vec<_>>());
+
+    let results = db.multi_get(&ReadOptions::default(), &[&k(5), &k(15), &k(95)]);
+    assert_eq!(results[0].as_ref().unwrap(), &Some(b"v5".to_vec()));
+    assert_eq!(results[1].as_ref().unwrap(), &None);
+    assert_eq!(results[2].as_ref().unwrap(), &Some(b"v95".to_vec()));
+
+    db.close().unwrap();
+    let db = DB::open(reverse_opts(), dir.path()).unwrap();
+    assert_eq!(keys(&db), expected);
+}
+
+#[test]
+fn test_reopen_with_different_comparator_fails() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = DB::open(reverse_opts(), dir.path()).unwrap();
+    db.put(b"a", b"1").unwrap();
+    db.close().unwrap();
+
+    let bytewise = DbOptions {
+        create_if_missing: true,
+        ..Default::default()
+    };
+    let Err(err) = DB::open(bytewise, dir.path()) else {
+        panic!("bytewise open of a reverse-ordered store succeeded");
+    };
+    assert_eq!(
+        err.kind(),
+        ErrorKind::InvalidArgument,
+        "unexpected error: {err}"
+    );
+
+    // A store created with the default order rejects the reverse one.
+    let dir = tempfile::tempdir().unwrap();
+    let db = DB::open(
+        DbOptions {
+            create_if_missing: true,
+            ..Default::default()
+        },
+        dir.path(),
+    )
+    .unwrap();
+    db.close().unwrap();
+    let Err(err) = DB::open(reverse_opts(), dir.path()) else {
+        panic!("reverse open of a bytewise store succeeded");
+    };
+    assert_eq!(
+        err.kind(),
+        ErrorKind::InvalidArgument,
+        "unexpected error: {err}"
+    );
+}
+
+#[test]
+fn test_ingest_requires_comparator_order() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = DB::open(reverse_opts(), dir.path()).unwrap();
+
+    let sorted = dir.path().join("sorted.sst");
+    let mut writer = SstFileWriter::create(&reverse_opts(), &sorted).unwrap();
+    writer.put(b"z", b"1").unwrap();
+    writer.put(b"m", b"2").unwrap();
+    writer.put(b"a", b"3").unwrap();
+    writer.finish().unwrap();
+    db.ingest_external_file(&[&sorted], &IngestExternalFileOptions::default())
+        .unwrap();
+    assert_eq!(keys(&db), vec![b"z".to_vec(), b"m".to_vec(), b"a".to_vec()]);
+
+    // The writer enforces the comparator's order, not bytewise order.
+    let mut writer = SstFileWriter::create(&reverse_opts(), dir.path().join("bad.sst")).unwrap();
+    writer.put(b"a", b"1").unwrap();
+    let err = writer.put(b"b", b"2").unwrap_err();
+    assert_eq!(
+        err.kind(),
+        ErrorKind::InvalidArgument,
+        "unexpected error: {err}"
+    );
+}
+
+#[test]
+fn test_optimistic_conflict_detected_in_compacted_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = DB::open(reverse_opts(), dir.path()).unwrap();
+    for key in [&b"a"[..], b"k1", b"z"] {
+        db.put(key, b"v0").unwrap();
+    }
+    db.flush().unwrap();
+    db.compact().unwrap();
+
+    let mut txn = db.begin_optimistic_transaction();
+    assert_eq!(txn.get(b"k1").unwrap(), Some(b"v0".to_vec()));
+    // A concurrent write that ends up in an L1+ file whose bounds are
+    // `z` (smallest) to `a` (largest) in reverse order.
+    db.put(b"k1", b"v1").unwrap();
+    db.put(b"a", b"v1").unwrap();
+    db.put(b"z", b"v1").unwrap();
+    db.flush().unwrap();
+    db.compact().unwrap();
+    assert_eq!(db.get_property("num-files-at-level0").as_deref(), Some("0"));
+
+    txn.put(b"k1", b"v2");
+    let err = txn.commit().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Busy);
+    assert_eq!(db.get(b"k1").unwrap(), Some(b"v1".to_vec()));
+}
+
+#[test]
+fn test_prefix_iteration_needs_bytewise_order() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = DB::open(reverse_opts(), dir.path()).unwrap();
+    for key in [b"a1", b"k1", b"k2", b"z1"] {
+        db.put(key, b"v").unwrap();
+    }
+    db.flush().unwrap();
+    db.compact_range(None, None).unwrap();
+    db.put(b"k3", b"v").unwrap();
+
+    let Err(err) = db.iter_with_prefix(b"k", &ReadOptions::default()) else {
+        panic!("prefix iteration under a reverse comparator succeeded");
+    };
+    assert_eq!(err.kind(), ErrorKind::InvalidArgument, "{err}");
+
+    // next_prefix steps over the keys sharing a prefix instead of seeking
+    // past a byte-incremented bound, which sorts before them here.
+    let mut iter = db.iter().unwrap();
+    iter.seek_to_first();
+    assert_eq!(iter.key().unwrap(), b"z1");
+    iter.next_prefix(1);
+    assert_eq!(iter.key().unwrap(), b"k3");
+    iter.next_prefix(1);
+    assert_eq!(iter.key().unwrap(), b"a1");
+    iter.next_prefix(1);
+    assert!(!iter.valid());
+}
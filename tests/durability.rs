@@ -0,0 +1,164 @@
+//! Explicit WAL sync and the durability watermark.
+
+use std::thread;
+
+use mmdb::{DB, DbOptions, ErrorKind, WriteOptions};
+
+fn opts() -> DbOptions {
+    DbOptions {
+        create_if_missing: true,
+        ..Default::default()
+    }
+}
+
+fn key(i: usize) -> Vec<u8> {
+    format!("key{:05}", i).into_bytes()
+}
+
+#[test]
+fn test_sync_wal_advances_durable_sequence() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = DB::open(opts(), dir.path()).unwrap();
+    assert_eq!(db.durable_sequence(), db.latest_sequence_number());
+
+    for i in 0..10 {
+        db.put(&key(i), b"v").unwrap();
+    }
+    assert!(db.durable_sequence() < db.latest_sequence_number());
+    db.flush_wal(false).unwrap();
+    assert!(db.durable_sequence() < db.latest_sequence_number());
+    db.sync_wal().unwrap();
+    assert_eq!(db.durable_sequence(), db.latest_sequence_number());
+
+    db.put(b"unsynced", b"v").unwrap();
+    let sync = WriteOptions {
+        sync: true,
+        ..Default::default()
+    };
+    db.put_with_options(&sync, b"synced", b"v").unwrap();
+    assert_eq!(db.durable_sequence(), db.latest_sequence_number());
+
+    let durable = db.durable_sequence();
+    db.simulate_crash();
+    let db = DB::open(opts(), dir.path()).unwrap();
+    assert!(db.latest_sequence_number() >= durable);
+    for i in 0..10 {
+        assert_eq!(db.get(&key(i)).unwrap(), Some(b"v".to_vec()));
+    }
+    assert_eq!(db.get(b"synced").unwrap(), Some(b"v".to_vec()));
+}
+
+#[test]
+fn test_memtable_switch_syncs_retiring_wal() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = DB::open(
+        DbOptions {
+            write_buffer_size: 16 * 1024,
+            ..opts()
+        },
+        dir.path(),
+    )
+    .unwrap();
+    for i in 0..500 {
+        db.put(&key(i), &[b'x'; 100]).unwrap();
+    }
+    // At least one WAL was retired; everything logged before it is durable.
+    assert!(db.durable_sequence() > 0);
+    assert!(db.durable_sequence() <= db.latest_sequence_number());
+}
+
+#[test]
+fn test_wait_for_durable_from_concurrent_writers() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = DB::open(opts(), dir.path()).unwrap();
+
+    thread::scope(|scope| {
+        for w in 0..8 {
+            let db = &db;
+            scope.spawn(move || {
+                for i in 0..100 {
+                    db.put(&key(w * 1000 + i), b"v").unwrap();
+                    let seq = db.latest_sequence_number();
+                    if i % 10 == 9 {
+                        db.wait_for_durable(seq).unwrap();
+                        assert!(db.durable_sequence() >= seq);
+                    }
+                }
+            });
+        }
+    });
+    db.wait_for_durable(db.latest_sequence_number()).unwrap();
+    assert_eq!(db.durable_sequence(), db.latest_sequence_number());
+
+    let err = db
+        .wait_for_durable(db.latest_sequence_number() + 1)
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidArgument, "{err}");
+}
+
+#[test]
+fn test_unlogged_write_holds_durable_sequence_until_flushed() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = DB::open(opts(), dir.path()).unwrap();
+    db.put(b"logged", b"v").unwrap();
+    let before = db.latest_sequence_number();
+    let unlogged = WriteOptions {
+        disable_wal: true,
+        ..Default::default()
+    };
+    db.put_with_options(&unlogged, b"unlogged", b"v").unwrap();
+    db.put(b"after", b"v").unwrap();
+
+    db.sync_wal().unwrap();
+    assert_eq!(db.durable_sequence(), before);
+    let err = db
+        .wait_for_durable(db.latest_sequence_number())
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidArgument, "{err}");
+    db.wait_for_durable(before).unwrap();
+
+    db.flush().unwrap();
+    db.wait_for_durable(db.latest_sequence_number()).unwrap();
+    assert_eq!(db.durable_sequence(), db.latest_sequence_number());
+}
+
+#[test]
+fn test_mixed_group_keeps_durable_sequence_below_unlogged_write() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = DB::open(opts(), dir.path()).unwrap();
+
+    // Synced and `disable_wal` writers race, so groups mix both kinds.
+    thread::scope(|scope| {
+        for w in 0..8 {
+            let db = &db;
+            scope.spawn(move || {
+                let options = WriteOptions {
+                    sync: w % 2 == 0,
+                    disable_wal: w % 2 == 1,
+                    ..Default::default()
+                };
+                for i in 0..100 {
+                    db.put_with_options(&options, &key(w * 1000 + i), b"v")
+                        .unwrap();
+                }
+            });
+        }
+    });
+    db.sync_wal().unwrap();
+
+    // The first sequence missing from the WAL belongs to an unlogged write.
+    let mut next = 1;
+    for update in db.get_updates_since(1).unwrap() {
+        let (seq, batch) = update.unwrap();
+        if seq != next {
+            break;
+        }
+        next = seq + batch.len() as u64;
+    }
+    assert!(next <= db.latest_sequence_number());
+    assert!(
+        db.durable_sequence() < next,
+        "durable {} passed unlogged sequence {next}",
+        db.durable_sequence()
+    );
+}
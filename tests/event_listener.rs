@@ -0,0 +1,199 @@
+//! Event listeners registered through `DbOptions::listeners`.
+
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use mmdb::{
+    CompactionJobInfo, CompactionReason, DB, DbOptions, EventListener, FlushJobInfo,
+    RecoveryProgressInfo, TableFileDeletionInfo, WriteStallCondition, WriteStallInfo,
+};
+
+#[derive(Default)]
+struct Recorder {
+    flushes: Mutex<Vec<FlushJobInfo>>,
+    compactions_begun: Mutex<Vec<CompactionJobInfo>>,
+    compactions: Mutex<Vec<CompactionJobInfo>>,
+    stalls: Mutex<Vec<WriteStallInfo>>,
+    deletions: Mutex<Vec<TableFileDeletionInfo>>,
+    recovery: Mutex<Vec<RecoveryProgressInfo>>,
+}
+
+impl EventListener for Recorder {
+    fn on_flush_completed(&self, info: &FlushJobInfo) {
+        self.flushes.lock().unwrap().push(info.clone());
+    }
+
+    fn on_compaction_begin(&self, info: &CompactionJobInfo) {
+        self.compactions_begun.lock().unwrap().push(info.clone());
+    }
+
+    fn on_compaction_completed(&self, info: &CompactionJobInfo) {
+        self.compactions.lock().unwrap().push(info.clone());
+    }
+
+    fn on_stall_conditions_changed(&self, info: &WriteStallInfo) {
+        self.stalls.lock().unwrap().push(*info);
+    }
+
+    fn on_table_file_deleted(&self, info: &TableFileDeletionInfo) {
+        self.deletions.lock().unwrap().push(info.clone());
+    }
+
+    fn on_recovery_progress(&self, info: &RecoveryProgressInfo) {
+        self.recovery.lock().unwrap().push(info.clone());
+    }
+}
+
+fn opts(recorder: &Arc<Recorder>) -> DbOptions {
+    DbOptions {
+        create_if_missing: true,
+        listeners: vec![recorder.clone() as Arc<dyn EventListener>],
+        ..Default::default()
+    }
+}
+
+/// Events are delivered by the thread that did the work, which may be a
+/// background thread finishing after the triggering call returned.
+fn wait_for(condition: impl Fn() -> bool) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !condition() {
+        assert!(Instant::now() < deadline, "event not delivered");
+        thread::sleep(Duration::from_millis(5));
+    }
+}
+
+fn key(i: u64) -> Vec<u8> {
+    format!("key{:05}", i).into_bytes()
+}
+
+#[test]
+fn flush_and_compaction_events_describe_the_jobs() {
+    let dir = tempfile::tempdir().unwrap();
+    let recorder = Arc::new(Recorder::default());
+    let options = DbOptions {
+        l0_compaction_trigger: 100,
+        ..opts(&recorder)
+    };
+    let db = DB::open(options, dir.path()).unwrap();
+    for round in 0..3 {
+        for i in 0..100 {
+            db.put(&key(i), format!("v{round}").as_bytes()).unwrap();
+        }
+        db.flush().unwrap();
+    }
+
+    wait_for(|| recorder.flushes.lock().unwrap().len() == 3);
+    let flushes = recorder.flushes.lock().unwrap().clone();
+    let flushed: Vec<u64> = flushes
+        .iter()
+        .flat_map(|f| f.file_numbers.clone())
+        .collect();
+    assert!(flushes.iter().all(|f| f.bytes_written > 0));
+    assert!(recorder.compactions.lock().unwrap().is_empty());
+
+    db.compact().unwrap();
+    let begun = recorder.compactions_begun.lock().unwrap().clone();
+    let completed = recorder.compactions.lock().unwrap().clone();
+    assert!(!begun.is_empty());
+    assert!(completed.len() >= begun.len());
+    let first = &completed[0];
+    assert_eq!(first.reason, CompactionReason::Manual);
+    assert_eq!((first.level, first.output_level), (0, 1));
+    assert_eq!(first.input_files, begun[0].input_files);
+    assert!(first.input_files.iter().all(|n| flushed.contains(n)));
+    assert!(!first.output_files.is_empty());
+    assert!(first.bytes_read > 0 && first.bytes_written > 0);
+
+    // Every flushed SST was compacted away and reported deleted.
+    let deletions = recorder.deletions.lock().unwrap();
+    for number in &flushed {
+        let deletion = deletions.iter().find(|d| d.file_number == *number).unwrap();
+        assert!(deletion.result.is_ok());
+        assert!(!deletion.path.exists());
+    }
+    assert_eq!(db.get(&key(0)).unwrap(), Some(b"v2".to_vec()));
+}
+
+#[test]
+fn background_compactions_report_their_trigger() {
+    let dir = tempfile::tempdir().unwrap();
+    let recorder = Arc::new(Recorder::default());
+    let options = DbOptions {
+        l0_compaction_trigger: 2,
+        ..opts(&recorder)
+    };
+    let db = DB::open(options, dir.path()).unwrap();
+    for round in 0..2 {
+        for i in 0..100 {
+            db.put(&key(i), format!("v{round}").as_bytes()).unwrap();
+        }
+        db.flush().unwrap();
+    }
+    wait_for(|| !recorder.compactions.lock().unwrap().is_empty());
+    let completed = recorder.compactions.lock().unwrap()[0].clone();
+    assert_eq!(completed.reason, CompactionReason::L0FileCount);
+    assert_eq!(completed.input_files.len(), 2);
+}
+
+#[test]
+fn write_stalls_are_reported_on_each_change() {
+    let dir = tempfile::tempdir().unwrap();
+    let recorder = Arc::new(Recorder::default());
+    let options = DbOptions {
+        l0_compaction_trigger: 100,
+        l0_slowdown_trigger: 2,
+        l0_stop_trigger: 100,
+        ..opts(&recorder)
+    };
+    let db = DB::open(options, dir.path()).unwrap();
+    db.put(b"a", b"1").unwrap();
+    db.flush().unwrap();
+    db.put(b"b", b"1").unwrap();
+    db.flush().unwrap();
+    assert!(recorder.stalls.lock().unwrap().is_empty());
+
+    db.put(b"c", b"1").unwrap();
+    db.put(b"d", b"1").unwrap();
+    db.compact().unwrap();
+    db.put(b"e", b"1").unwrap();
+    let stalls = recorder.stalls.lock().unwrap().clone();
+    assert_eq!(
+        stalls,
+        vec![
+            WriteStallInfo {
+                condition: WriteStallCondition::Delayed,
+                previous: WriteStallCondition::Normal,
+            },
+            WriteStallInfo {
+                condition: WriteStallCondition::Normal,
+                previous: WriteStallCondition::Delayed,
+            },
+        ]
+    );
+}
+
+#[test]
+fn recovery_progress_is_reported_per_wal() {
+    let dir = tempfile::tempdir().unwrap();
+    {
+        let db = DB::open(DbOptions::default(), dir.path()).unwrap();
+        for i in 0..10 {
+            db.put(&key(i), b"v").unwrap();
+        }
+    }
+
+    let recorder = Arc::new(Recorder::default());
+    let db = DB::open(opts(&recorder), dir.path()).unwrap();
+    let progress = recorder.recovery.lock().unwrap().clone();
+    let summary = db.wal_recovery_summary();
+    assert_eq!(progress.len(), summary.replayed_wals.len());
+    let last = progress.last().unwrap();
+    assert_eq!(last.wals_replayed, last.wals_total);
+    assert_eq!(last.records_replayed, summary.records_replayed);
+    assert_eq!(
+        progress.iter().map(|p| p.wal_number).collect::<Vec<_>>(),
+        summary.replayed_wals
+    );
+    assert_eq!(db.get(&key(9)).unwrap(), Some(b"v".to_vec()));
+}
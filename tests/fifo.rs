@@ -0,0 +1,233 @@
+//! FIFO compaction: every file in L0, oldest files dropped by size and age.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use mmdb::{
+    Clock, CompactionStyle, DB, DbOptions, ErrorKind, FifoCompactionOptions,
+    IngestExternalFileOptions, SstFileWriter, WriteOptions,
+};
+
+struct ManualClock(AtomicU64);
+
+impl Clock for ManualClock {
+    fn now_millis(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+fn fifo_opts(fifo: FifoCompactionOptions) -> DbOptions {
+    DbOptions {
+        create_if_missing: true,
+        compaction_style: CompactionStyle::Fifo,
+        fifo_compaction: fifo,
+        ..Default::default()
+    }
+}
+
+fn key(batch: u64, i: u64) -> Vec<u8> {
+    format!("event{:04}-{:04}", batch, i).into_bytes()
+}
+
+/// Write one batch of 100 events and flush it into its own L0 file.
+fn write_batch(db: &DB, batch: u64) {
+    for i in 0..100 {
+        db.put(&key(batch, i), &[b'x'; 200]).unwrap();
+    }
+    db.flush().unwrap();
+}
+
+fn has_batch(db: &DB, batch: u64) -> bool {
+    let present = (0..100)
+        .filter(|&i| db.get(&key(batch, i)).unwrap().is_some())
+        .count();
+    assert!(
+        present == 0 || present == 100,
+        "batch {batch} partially dropped"
+    );
+    present == 100
+}
+
+fn property(db: &DB, name: &str) -> u64 {
+    db.get_property(name).unwrap().parse().unwrap()
+}
+
+fn files_below_l0(db: &DB) -> u64 {
+    (1..DbOptions::default().num_levels)
+        .map(|level| property(db, &format!("num-files-at-level{level}")))
+        .sum()
+}
+
+#[test]
+fn test_oldest_files_dropped_over_size_budget() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = DB::open(fifo_opts(FifoCompactionOptions::default()), dir.path()).unwrap();
+    write_batch(&db, 0);
+    let file_size = property(&db, "total-sst-size");
+    db.close().unwrap();
+
+    let budget = file_size * 5 + file_size / 2;
+    let options = fifo_opts(FifoCompactionOptions {
+        max_table_files_size: budget,
+        ..Default::default()
+    });
+    let db = DB::open(options.clone(), dir.path()).unwrap();
+    for batch in 1..20 {
+        write_batch(&db, batch);
+    }
+    db.compact_range(None, None).unwrap();
+
+    assert!(property(&db, "total-sst-size") <= budget);
+    assert_eq!(files_below_l0(&db), 0);
+    // A contiguous tail of the newest batches survives.
+    let kept: Vec<u64> = (0..20).filter(|&b| has_batch(&db, b)).collect();
+    assert_eq!(kept, (15..20).collect::<Vec<_>>());
+    assert!(property(&db, "stats.compaction_bytes_written") == 0);
+
+    db.close().unwrap();
+    let db = DB::open(options, dir.path()).unwrap();
+    let kept: Vec<u64> = (0..20).filter(|&b| has_batch(&db, b)).collect();
+    assert_eq!(kept, (15..20).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_expired_files_dropped_by_ttl() {
+    let dir = tempfile::tempdir().unwrap();
+    let clock = Arc::new(ManualClock(AtomicU64::new(1_000_000)));
+    let options = DbOptions {
+        clock: clock.clone(),
+        ..fifo_opts(FifoCompactionOptions {
+            ttl: Some(Duration::from_secs(60)),
+            ..Default::default()
+        })
+    };
+    let db = DB::open(options.clone(), dir.path()).unwrap();
+    write_batch(&db, 0);
+    clock.0.fetch_add(30_000, Ordering::Relaxed);
+    write_batch(&db, 1);
+
+    db.compact_range(None, None).unwrap();
+    assert!(has_batch(&db, 0) && has_batch(&db, 1));
+
+    // Batch 0 is now 60s old; batch 1 only 30s.
+    clock.0.fetch_add(30_000, Ordering::Relaxed);
+    db.compact_range(None, None).unwrap();
+    assert!(!has_batch(&db, 0));
+    assert!(has_batch(&db, 1));
+    db.close().unwrap();
+
+    // Creation times survive a reopen.
+    let db = DB::open(options, dir.path()).unwrap();
+    clock.0.fetch_add(30_000, Ordering::Relaxed);
+    db.compact_range(None, None).unwrap();
+    assert!(!has_batch(&db, 1));
+    assert_eq!(property(&db, "num-files-at-level0"), 0);
+}
+
+#[test]
+fn test_intra_l0_compaction_bounds_file_count() {
+    let dir = tempfile::tempdir().unwrap();
+    let options = DbOptions {
+        l0_compaction_trigger: 4,
+        ..fifo_opts(FifoCompactionOptions {
+            allow_compaction: true,
+            ..Default::default()
+        })
+    };
+    let db = DB::open(options, dir.path()).unwrap();
+    for batch in 0..20 {
+        write_batch(&db, batch);
+    }
+    let deadline = Instant::now() + Duration::from_secs(30);
+    while db.get_property("compaction-pending").unwrap() != "0" {
+        assert!(Instant::now() < deadline, "compaction never settled");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert!(property(&db, "num-files-at-level0") < 4);
+    assert_eq!(files_below_l0(&db), 0);
+    assert!(property(&db, "stats.compaction_bytes_written") > 0);
+    for batch in 0..20 {
+        assert!(has_batch(&db, batch));
+    }
+}
+
+#[test]
+fn test_l0_file_count_does_not_stall_writes() {
+    let dir = tempfile::tempdir().unwrap();
+    let options = DbOptions {
+        l0_slowdown_trigger: 2,
+        l0_stop_trigger: 3,
+        ..fifo_opts(FifoCompactionOptions::default())
+    };
+    let db = DB::open(options, dir.path()).unwrap();
+    for batch in 0..10 {
+        write_batch(&db, batch);
+    }
+    assert_eq!(property(&db, "num-files-at-level0"), 10);
+    let no_slowdown = WriteOptions {
+        no_slowdown: true,
+        ..Default::default()
+    };
+    db.put_with_options(&no_slowdown, b"late", b"write")
+        .unwrap();
+}
+
+#[test]
+fn test_fifo_rejects_leveled_layout_and_blob_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = DB::open(
+        DbOptions {
+            create_if_missing: true,
+            ..Default::default()
+        },
+        dir.path(),
+    )
+    .unwrap();
+    write_batch(&db, 0);
+    db.compact_range(None, None).unwrap();
+    assert!(files_below_l0(&db) > 0);
+    db.close().unwrap();
+
+    let fresh = tempfile::tempdir().unwrap();
+    for (path, options) in [
+        (dir.path(), fifo_opts(FifoCompactionOptions::default())),
+        (
+            fresh.path(),
+            DbOptions {
+                min_blob_size: Some(1024),
+                ..fifo_opts(FifoCompactionOptions::default())
+            },
+        ),
+    ] {
+        let Err(err) = DB::open(options, path) else {
+            panic!("open with an unsupported FIFO configuration succeeded");
+        };
+        assert_eq!(err.kind(), ErrorKind::InvalidArgument, "{err}");
+    }
+}
+
+#[test]
+fn test_ingested_file_stays_in_l0() {
+    let dir = tempfile::tempdir().unwrap();
+    let options = fifo_opts(FifoCompactionOptions::default());
+    let sst = dir.path().join("bulk.sst");
+    let mut writer = SstFileWriter::create(&options, &sst).unwrap();
+    for i in 0..100 {
+        writer.put(&key(1, i), b"ingested").unwrap();
+    }
+    writer.finish().unwrap();
+
+    let db = DB::open(options.clone(), dir.path().join("db")).unwrap();
+    write_batch(&db, 0);
+    db.ingest_external_file(&[&sst], &IngestExternalFileOptions::default())
+        .unwrap();
+    assert_eq!(files_below_l0(&db), 0);
+    assert_eq!(property(&db, "num-files-at-level0"), 2);
+    db.close().unwrap();
+
+    let db = DB::open(options, dir.path().join("db")).unwrap();
+    assert!(has_batch(&db, 0));
+    assert!(has_batch(&db, 1));
+    assert_eq!(db.get(&key(1, 0)).unwrap(), Some(b"ingested".to_vec()));
+}
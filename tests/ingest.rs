@@ -0,0 +1,206 @@
+//! External SST ingestion tests: SstFileWriter output loaded into a live DB.
+
+use std::path::{Path, PathBuf};
+
+use mmdb::{DB, DbOptions, ErrorKind, IngestExternalFileOptions, ReadOptions, SstFileWriter};
+
+fn make_opts() -> DbOptions {
+    DbOptions {
+        create_if_missing: true,
+        ..Default::default()
+    }
+}
+
+fn write_sst(path: &Path, entries: &[(&[u8], Option<&[u8]>)]) -> PathBuf {
+    let mut writer = SstFileWriter::create(&make_opts(), path).unwrap();
+    for (key, value) in entries {
+        match value {
+            Some(value) => writer.put(key, value).unwrap(),
+            None => writer.delete(key).unwrap(),
+        }
+    }
+    writer.finish().unwrap().file_path
+}
+
+fn files_at_level(db: &DB, level: usize) -> usize {
+    db.get_property(&format!("num-files-at-level{level}"))
+        .unwrap()
+        .parse()
+        .unwrap()
+}
+
+#[test]
+fn test_ingest_into_empty_db_goes_to_bottom_level() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = DB::open(make_opts(), dir.path().join("db")).unwrap();
+    let mut writer = SstFileWriter::create(&make_opts(), dir.path().join("bulk.sst")).unwrap();
+    for i in 0..1000u32 {
+        writer
+            .put(format!("key{i:05}").as_bytes(), format!("v{i}").as_bytes())
+            .unwrap();
+    }
+    let info = writer.finish().unwrap();
+    assert_eq!(info.num_entries, 1000);
+    assert_eq!(info.smallest_key, b"key00000");
+    assert_eq!(info.largest_key, b"key00999");
+
+    db.ingest_external_file(&[&info.file_path], &IngestExternalFileOptions::default())
+        .unwrap();
+    // A copy was ingested; the source stays.
+    assert!(info.file_path.exists());
+    assert_eq!(files_at_level(&db, 6), 1);
+    assert_eq!(db.get(b"key00042").unwrap(), Some(b"v42".to_vec()));
+    assert_eq!(db.iter().unwrap().count(), 1000);
+
+    // Later writes shadow ingested data.
+    db.put(b"key00042", b"new").unwrap();
+    assert_eq!(db.get(b"key00042").unwrap(), Some(b"new".to_vec()));
+    db.close().unwrap();
+
+    let db = DB::open(make_opts(), dir.path().join("db")).unwrap();
+    assert_eq!(db.get(b"key00042").unwrap(), Some(b"new".to_vec()));
+    assert_eq!(db.get(b"key00999").unwrap(), Some(b"v999".to_vec()));
+}
+
+#[test]
+fn test_ingest_overlapping_data_gets_global_sequence() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = DB::open(make_opts(), dir.path().join("db")).unwrap();
+    db.put(b"a", b"old").unwrap();
+    db.put(b"b", b"old").unwrap();
+    db.put(b"c", b"old").unwrap();
+    db.flush().unwrap();
+    let snap = db.snapshot();
+
+    let sst = write_sst(
+        &dir.path().join("update.sst"),
+        &[(b"a", Some(b"new")), (b"b", None)],
+    );
+    let opts = IngestExternalFileOptions {
+        move_files: true,
+        ..Default::default()
+    };
+    db.ingest_external_file(&[&sst], &opts).unwrap();
+    assert!(!sst.exists());
+
+    assert_eq!(db.get(b"a").unwrap(), Some(b"new".to_vec()));
+    assert_eq!(db.get(b"b").unwrap(), None);
+    assert_eq!(db.get(b"c").unwrap(), Some(b"old".to_vec()));
+    let at_snap = ReadOptions {
+        snapshot: Some(snap.sequence()),
+        ..Default::default()
+    };
+    assert_eq!(
+        db.get_with_options(&at_snap, b"a").unwrap(),
+        Some(b"old".to_vec())
+    );
+    assert_eq!(
+        db.get_with_options(&at_snap, b"b").unwrap(),
+        Some(b"old".to_vec())
+    );
+    drop(snap);
+
+    // The assigned sequence survives compaction and reopen.
+    db.compact_range(None, None).unwrap();
+    db.close().unwrap();
+    let db = DB::open(make_opts(), dir.path().join("db")).unwrap();
+    assert_eq!(db.get(b"a").unwrap(), Some(b"new".to_vec()));
+    assert_eq!(db.get(b"b").unwrap(), None);
+    db.put(b"a", b"newest").unwrap();
+    assert_eq!(db.get(b"a").unwrap(), Some(b"newest".to_vec()));
+}
+
+#[test]
+fn test_ingested_range_deletion_removes_existing_keys() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = DB::open(make_opts(), dir.path().join("db")).unwrap();
+    for key in [b"k1", b"k2", b"k3", b"k4"] {
+        db.put(key, b"old").unwrap();
+    }
+    db.flush().unwrap();
+
+    let path = dir.path().join("range.sst");
+    let mut writer = SstFileWriter::create(&make_opts(), &path).unwrap();
+    writer.delete_range(b"k2", b"k4").unwrap();
+    writer.put(b"k2", b"ingested").unwrap();
+    writer.finish().unwrap();
+    db.ingest_external_file(&[&path], &IngestExternalFileOptions::default())
+        .unwrap();
+
+    let entries: Vec<_> = db.iter().unwrap().collect();
+    assert_eq!(
+        entries,
+        vec![
+            (b"k1".to_vec(), b"old".to_vec()),
+            (b"k2".to_vec(), b"ingested".to_vec()),
+            (b"k4".to_vec(), b"old".to_vec()),
+        ]
+    );
+}
+
+#[test]
+fn test_ingest_memtable_overlap_flushes_or_fails() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = DB::open(make_opts(), dir.path().join("db")).unwrap();
+    db.put(b"m", b"memtable").unwrap();
+    let sst = write_sst(&dir.path().join("m.sst"), &[(b"m", Some(b"ingested"))]);
+
+    let strict = IngestExternalFileOptions {
+        fail_if_memtable_overlap: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        db.ingest_external_file(&[&sst], &strict)
+            .unwrap_err()
+            .kind(),
+        ErrorKind::InvalidArgument
+    );
+    assert_eq!(db.get(b"m").unwrap(), Some(b"memtable".to_vec()));
+
+    db.ingest_external_file(&[&sst], &IngestExternalFileOptions::default())
+        .unwrap();
+    assert_eq!(db.get(b"m").unwrap(), Some(b"ingested".to_vec()));
+    assert_eq!(files_at_level(&db, 0), 2);
+}
+
+#[test]
+fn test_ingest_rejects_invalid_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = DB::open(make_opts(), dir.path().join("db")).unwrap();
+    let first = write_sst(
+        &dir.path().join("1.sst"),
+        &[(b"a", Some(b"1")), (b"m", Some(b"1"))],
+    );
+    let second = write_sst(
+        &dir.path().join("2.sst"),
+        &[(b"k", Some(b"2")), (b"z", Some(b"2"))],
+    );
+    let opts = IngestExternalFileOptions::default();
+    assert_eq!(
+        db.ingest_external_file(&[&first, &second], &opts)
+            .unwrap_err()
+            .kind(),
+        ErrorKind::InvalidArgument
+    );
+
+    // A DB-produced SST carries real sequence numbers.
+    db.put(b"x", b"1").unwrap();
+    db.flush().unwrap();
+    let db_sst = std::fs::read_dir(dir.path().join("db"))
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .find(|p| p.extension().is_some_and(|e| e == "sst"))
+        .unwrap();
+    assert_eq!(
+        db.ingest_external_file(&[&db_sst], &opts)
+            .unwrap_err()
+            .kind(),
+        ErrorKind::InvalidArgument
+    );
+    assert_eq!(db.iter().unwrap().count(), 1);
+
+    // Disjoint files are ingested together.
+    let third = write_sst(&dir.path().join("3.sst"), &[(b"n", Some(b"3"))]);
+    db.ingest_external_file(&[&third, &first], &opts).unwrap();
+    assert_eq!(db.iter().unwrap().count(), 4);
+}
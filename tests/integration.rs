@@ -2,8 +2,12 @@
 
 use std::sync::Arc;
 use std::thread;
+use std::time::Duration;
 
-use mmdb::{DB, DbOptions, ErrorKind, ReadOptions, WriteBatch, WriteOptions};
+use mmdb::{
+    DB, DbOptions, ErrorKind, ReadOptions, TransactionDB, TransactionDbOptions, WriteBatch,
+    WriteOptions,
+};
 
 fn make_db(dir: &std::path::Path) -> DB {
     DB::open(
@@ -2405,3 +2409,223 @@ fn test_startup_drains_inherited_l0_backlog() {
         );
     }
 }
+
+/// Appends operands with `,` separators; no partial merge, so unresolved
+/// operand chains above the bottommost level stay as separate entries.
+struct AppendOperator;
+
+impl mmdb::MergeOperator for AppendOperator {
+    fn name(&self) -> &str {
+        "append"
+    }
+
+    fn full_merge(
+        &self,
+        _key: &[u8],
+        existing: Option<&[u8]>,
+        operands: &[&[u8]],
+    ) -> Option<Vec<u8>> {
+        let mut out = existing.map(<[u8]>::to_vec).unwrap_or_default();
+        for op in operands {
+            if !out.is_empty() {
+                out.push(b',');
+            }
+            out.extend_from_slice(op);
+        }
+        Some(out)
+    }
+}
+
+#[test]
+fn test_merge_operands_through_flush_and_compaction() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = Arc::new(
+        DB::open(
+            DbOptions {
+                create_if_missing: true,
+                write_buffer_size: 4096,
+                l0_compaction_trigger: 2,
+                merge_operator: Some(Arc::new(AppendOperator)),
+                ..Default::default()
+            },
+            dir.path(),
+        )
+        .unwrap(),
+    );
+
+    let handles: Vec<_> = (0..4)
+        .map(|t| {
+            let db = Arc::clone(&db);
+            thread::spawn(move || {
+                for i in 0..200 {
+                    let key = format!("list_{:02}", i % 20);
+                    db.merge(key.as_bytes(), format!("t{}-{}", t, i).as_bytes())
+                        .unwrap();
+                }
+            })
+        })
+        .collect();
+    for h in handles {
+        h.join().unwrap();
+    }
+    db.flush().unwrap();
+    db.compact().unwrap();
+
+    for k in 0..20 {
+        let key = format!("list_{:02}", k);
+        let value = db.get(key.as_bytes()).unwrap().unwrap();
+        let items: Vec<&[u8]> = value.split(|&b| b == b',').collect();
+        // 4 writers x 10 operands per key, each writer's operands in order.
+        assert_eq!(items.len(), 40, "key {}", key);
+        for t in 0..4 {
+            let mine: Vec<String> = items
+                .iter()
+                .map(|i| String::from_utf8(i.to_vec()).unwrap())
+                .filter(|i| i.starts_with(&format!("t{}-", t)))
+                .collect();
+            let expected: Vec<String> = (0..200)
+                .filter(|i| i % 20 == k)
+                .map(|i| format!("t{}-{}", t, i))
+                .collect();
+            assert_eq!(mine, expected);
+        }
+    }
+
+    let scanned: Vec<_> = db.iter().unwrap().collect();
+    assert_eq!(scanned.len(), 20);
+    for (key, value) in scanned {
+        assert_eq!(db.get(&key).unwrap(), Some(value));
+    }
+}
+
+#[test]
+fn test_optimistic_transactions_serialize_concurrent_increments() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = Arc::new(
+        DB::open(
+            DbOptions {
+                create_if_missing: true,
+                write_buffer_size: 4096,
+                ..Default::default()
+            },
+            dir.path(),
+        )
+        .unwrap(),
+    );
+    db.put(b"counter", &0u64.to_le_bytes()).unwrap();
+
+    let handles: Vec<_> = (0..4)
+        .map(|t| {
+            let db = Arc::clone(&db);
+            thread::spawn(move || {
+                for i in 0..50 {
+                    loop {
+                        let mut txn = db.begin_optimistic_transaction();
+                        let cur = txn.get(b"counter").unwrap().unwrap();
+                        let n = u64::from_le_bytes(cur.try_into().unwrap());
+                        txn.put(b"counter", &(n + 1).to_le_bytes());
+                        txn.put(format!("log_{}_{:02}", t, i).as_bytes(), b"x");
+                        match txn.commit() {
+                            Ok(()) => break,
+                            Err(e) if e.kind() == ErrorKind::Busy => continue,
+                            Err(e) => panic!("unexpected commit error: {e}"),
+                        }
+                    }
+                }
+            })
+        })
+        .collect();
+    for h in handles {
+        h.join().unwrap();
+    }
+
+    let n = u64::from_le_bytes(db.get(b"counter").unwrap().unwrap().try_into().unwrap());
+    assert_eq!(n, 200);
+    let logs = db
+        .iter_with_prefix(b"log_", &ReadOptions::default())
+        .unwrap()
+        .count();
+    assert_eq!(logs, 200);
+}
+
+#[test]
+fn test_transaction_db_ledger_transfers_and_deadlock() {
+    let dir = tempfile::tempdir().unwrap();
+    let tdb = Arc::new(
+        TransactionDB::open(
+            DbOptions {
+                create_if_missing: true,
+                write_buffer_size: 4096,
+                ..Default::default()
+            },
+            TransactionDbOptions {
+                lock_timeout: Duration::from_secs(10),
+                ..Default::default()
+            },
+            dir.path(),
+        )
+        .unwrap(),
+    );
+    const ACCOUNTS: u64 = 8;
+    for a in 0..ACCOUNTS {
+        tdb.put(format!("acct_{}", a).as_bytes(), &100u64.to_le_bytes())
+            .unwrap();
+    }
+    let balance = |txn: &mut mmdb::Transaction<'_>, a: u64| {
+        let v = txn.get_for_update(format!("acct_{}", a).as_bytes())?;
+        Ok::<_, mmdb::Error>(u64::from_le_bytes(v.unwrap().try_into().unwrap()))
+    };
+
+    // Transfers in both directions between the same accounts: lock-order
+    // inversions must surface as deadlock errors, never hang or lose money.
+    let handles: Vec<_> = (0..4u64)
+        .map(|t| {
+            let tdb = Arc::clone(&tdb);
+            thread::spawn(move || {
+                let mut done = 0;
+                let mut i = 0u64;
+                while done < 50 {
+                    i += 1;
+                    let from = (t + i) % ACCOUNTS;
+                    let to = (t + i * 3 + 1) % ACCOUNTS;
+                    if from == to {
+                        continue;
+                    }
+                    let mut txn = tdb.begin_transaction();
+                    let res = (|| {
+                        let a = balance(&mut txn, from)?;
+                        let b = balance(&mut txn, to)?;
+                        let amount = a.min(5);
+                        txn.put(
+                            format!("acct_{}", from).as_bytes(),
+                            &(a - amount).to_le_bytes(),
+                        )?;
+                        txn.put(
+                            format!("acct_{}", to).as_bytes(),
+                            &(b + amount).to_le_bytes(),
+                        )
+                    })();
+                    match res {
+                        Ok(()) => {
+                            txn.commit().unwrap();
+                            done += 1;
+                        }
+                        Err(e) if e.kind() == ErrorKind::Busy => txn.rollback(),
+                        Err(e) => panic!("unexpected transaction error: {e}"),
+                    }
+                }
+            })
+        })
+        .collect();
+    for h in handles {
+        h.join().unwrap();
+    }
+
+    let total: u64 = (0..ACCOUNTS)
+        .map(|a| {
+            let v = tdb.get(format!("acct_{}", a).as_bytes()).unwrap().unwrap();
+            u64::from_le_bytes(v.try_into().unwrap())
+        })
+        .sum();
+    assert_eq!(total, 100 * ACCOUNTS);
+}
@@ -135,6 +135,13 @@ fn lazy_delete_batch_auto_triggers_compaction() {
     for i in 0u32..20 {
         db.put(&i.to_be_bytes(), &[i as u8; 64]).unwrap();
     }
+    // Auto-flushes run in the background: wait until the first memtable
+    // has reached L0, where the sweep can see its keys.
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+    while db.get_property("num-immutable-mem-table").unwrap() != "0" {
+        assert!(std::time::Instant::now() < deadline, "flush never finished");
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
 
     // Register 6 keys — crosses the threshold of 5, should signal compaction.
     let dead: Vec<Vec<u8>> = (0u32..6).map(|i| i.to_be_bytes().to_vec()).collect();
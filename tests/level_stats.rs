@@ -0,0 +1,156 @@
+//! `mmdb.*` properties: level stats, the stats dump and size/key estimates.
+
+use std::time::{Duration, Instant};
+
+use mmdb::{DB, DbOptions};
+
+fn opts() -> DbOptions {
+    DbOptions {
+        create_if_missing: true,
+        l0_compaction_trigger: 100,
+        ..Default::default()
+    }
+}
+
+fn key(i: u32) -> Vec<u8> {
+    format!("key{:06}", i).into_bytes()
+}
+
+/// Write keys `0..count` with 1 KiB values and flush them to one L0 file.
+fn fill(db: &DB, count: u32) {
+    for i in 0..count {
+        db.put(&key(i), &[b'v'; 1024]).unwrap();
+    }
+    db.flush().unwrap();
+}
+
+fn property(db: &DB, name: &str) -> u64 {
+    db.get_property(name).unwrap().parse().unwrap()
+}
+
+/// The row of `table` whose first column is `name`, split into columns.
+fn row<'a>(table: &'a str, name: &str) -> Vec<&'a str> {
+    table
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>())
+        .find(|columns| columns.first() == Some(&name))
+        .unwrap_or_else(|| panic!("no {name} row in\n{table}"))
+}
+
+#[test]
+fn levelstats_report_every_level() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = DB::open(opts(), dir.path()).unwrap();
+    fill(&db, 2000);
+    fill(&db, 2000);
+
+    let stats = db.get_property("mmdb.levelstats").unwrap();
+    assert_eq!(stats.lines().count(), 2 + opts().num_levels);
+    assert!(stats.starts_with("Level  Files  Size(MB)  Score  Read(MB)  Write(MB)\n"));
+    let l0 = row(&stats, "L0");
+    assert_eq!(l0[1], "2");
+    assert_eq!(l0[3], "0.02", "two files against a trigger of 100");
+    assert_eq!(l0[4], "0.00");
+    let written: f64 = l0[5].parse().unwrap();
+    assert!(written > 3.5, "two flushes of ~2 MiB, got {written}");
+
+    db.compact().unwrap();
+    let stats = db.get_property("mmdb.levelstats").unwrap();
+    assert_eq!(row(&stats, "L0")[1], "0");
+    let compacted: f64 = stats
+        .lines()
+        .skip(3)
+        .map(|line| {
+            line.split_whitespace()
+                .nth(4)
+                .unwrap()
+                .parse::<f64>()
+                .unwrap()
+        })
+        .sum();
+    assert!(compacted > 3.5, "compaction read both L0 files:\n{stats}");
+}
+
+#[test]
+fn stats_dump_reports_amplification() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = DB::open(opts(), dir.path()).unwrap();
+    fill(&db, 2000);
+    fill(&db, 2000);
+    db.compact().unwrap();
+
+    let dump = db.get_property("mmdb.stats").unwrap();
+    assert!(dump.starts_with("** Compaction Stats **\n"));
+    let l0 = row(&dump, "L0");
+    assert_eq!(l0[9], "1.00", "L0 writes exactly what is flushed");
+    let sum = row(&dump, "Sum");
+    let w_amp: f64 = sum[9].parse().unwrap();
+    assert!(
+        (1.2..=2.0).contains(&w_amp),
+        "flushes plus one rewrite of half the data, got {w_amp}"
+    );
+    assert_eq!(
+        sum[10], "1.00",
+        "a fully compacted tree holds only live data"
+    );
+    let compactions: u64 = sum[11].parse().unwrap();
+    assert_eq!(compactions, db.statistics().compactions_completed);
+    assert!(dump.contains("Flushes: 2,"));
+    assert!(dump.contains("Estimated pending compaction(MB): 0.00"));
+}
+
+#[test]
+fn size_and_key_estimates() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = DB::open(opts(), dir.path()).unwrap();
+    assert_eq!(property(&db, "mmdb.estimate-num-keys"), 0);
+    assert_eq!(property(&db, "mmdb.estimate-live-data-size"), 0);
+
+    fill(&db, 2000);
+    db.compact().unwrap();
+    let compacted = property(&db, "total-sst-size");
+    assert_eq!(property(&db, "mmdb.estimate-live-data-size"), compacted);
+    let keys = property(&db, "mmdb.estimate-num-keys");
+    assert!((1600..=2400).contains(&keys), "estimated {keys} keys");
+
+    // Newer versions of the same keys in L0 shadow the compacted data.
+    fill(&db, 1000);
+    for i in 0..10 {
+        db.put(&key(i), b"in memtable").unwrap();
+    }
+    assert!(property(&db, "total-sst-size") > compacted);
+    assert_eq!(property(&db, "mmdb.estimate-live-data-size"), compacted);
+    let keys = property(&db, "mmdb.estimate-num-keys");
+    assert!((2410..=3610).contains(&keys), "estimated {keys} keys");
+    assert_eq!(property(&db, "mmdb.estimate-pending-compaction-bytes"), 0);
+    assert_eq!(db.get_property("mmdb.unknown"), None);
+}
+
+#[test]
+fn stats_dump_period() {
+    let dir = tempfile::tempdir().unwrap();
+    let options = DbOptions {
+        stats_dump_period: Some(Duration::ZERO),
+        ..opts()
+    };
+    assert!(DB::open(options, dir.path()).is_err());
+
+    let options = DbOptions {
+        stats_dump_period: Some(Duration::from_millis(10)),
+        ..opts()
+    };
+    let db = DB::open(options, dir.path()).unwrap();
+    fill(&db, 100);
+    std::thread::sleep(Duration::from_millis(50));
+    db.close().unwrap();
+
+    // A long period does not hold up closing the DB.
+    let options = DbOptions {
+        stats_dump_period: Some(Duration::from_secs(3600)),
+        ..opts()
+    };
+    let db = DB::open(options, dir.path()).unwrap();
+    let started = Instant::now();
+    drop(db);
+    assert!(started.elapsed() < Duration::from_secs(10));
+}
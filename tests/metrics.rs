@@ -0,0 +1,227 @@
+//! OpenMetrics export: `DB::metrics_openmetrics` and `MetricsExporter`.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use mmdb::{BlockCachePool, DB, DbOptions, MetricsExporter};
+
+/// Parse an exposition into `(sample name, labels, value)`, checking
+/// that every family is declared once, before its samples, and that the
+/// text ends with `# EOF`.
+fn parse(text: &str) -> Vec<(String, String, String)> {
+    let body = text.strip_suffix("# EOF\n").expect("terminated by # EOF");
+    let mut declared = HashSet::new();
+    let mut current = String::new();
+    let mut samples = Vec::new();
+    for line in body.lines() {
+        if let Some(rest) = line.strip_prefix("# TYPE ") {
+            let name = rest.split(' ').next().unwrap().to_string();
+            assert!(declared.insert(name.clone()), "{name} declared twice");
+            current = name;
+        } else if let Some(rest) = line.strip_prefix("# HELP ") {
+            assert!(rest.starts_with(&current));
+        } else {
+            let (series, value) = line.rsplit_once(' ').unwrap();
+            let (name, labels) = series.split_once('{').unwrap();
+            assert!(name.starts_with(&current), "{name} outside its family");
+            samples.push((name.to_string(), format!("{{{labels}"), value.to_string()));
+        }
+    }
+    samples
+}
+
+fn value(samples: &[(String, String, String)], name: &str, labels: &str) -> String {
+    samples
+        .iter()
+        .find(|(n, l, _)| n == name && l == labels)
+        .unwrap_or_else(|| panic!("no sample {name}{labels}"))
+        .2
+        .clone()
+}
+
+#[test]
+fn single_db_exposition() {
+    let dir = tempfile::tempdir().unwrap();
+    let options = DbOptions {
+        enable_statistics: true,
+        l0_compaction_trigger: 100,
+        ..Default::default()
+    };
+    let db = DB::open(options, dir.path()).unwrap();
+    for i in 0..10u32 {
+        db.put(&i.to_be_bytes(), b"value").unwrap();
+    }
+    db.flush().unwrap();
+    db.lazy_delete(b"gone");
+    assert!(db.get(&3u32.to_be_bytes()).unwrap().is_some());
+
+    let text = db.metrics_openmetrics();
+    let samples = parse(&text);
+    let db_label = dir.path().display().to_string();
+    let base = format!(r#"{{db="{db_label}",cf="default"}}"#);
+    assert_eq!(value(&samples, "mmdb_flushes_total", &base), "1");
+    assert_eq!(value(&samples, "mmdb_dead_keys", &base), "1");
+    assert_eq!(value(&samples, "mmdb_immutable_memtables", &base), "0");
+    let level0 = format!(r#"{{db="{db_label}",cf="default",level="0"}}"#);
+    assert_eq!(value(&samples, "mmdb_level_files", &level0), "1");
+    assert_ne!(value(&samples, "mmdb_level_bytes", &level0), "0");
+    assert_eq!(value(&samples, "mmdb_get_hits_total", &level0), "1");
+    let stall =
+        |state: &str| format!(r#"{{db="{db_label}",cf="default",mmdb_write_stall="{state}"}}"#);
+    assert_eq!(value(&samples, "mmdb_write_stall", &stall("normal")), "1");
+    assert_eq!(value(&samples, "mmdb_write_stall", &stall("stopped")), "0");
+    let writes = format!(r#"{{db="{db_label}",cf="default",operation="write"}}"#);
+    assert_eq!(
+        value(&samples, "mmdb_operation_latency_seconds_count", &writes),
+        "10"
+    );
+    assert!(text.contains("# TYPE mmdb_operation_latency_seconds summary\n"));
+    let p99 = format!(r#"{{db="{db_label}",cf="default",operation="get",quantile="0.99"}}"#);
+    assert!(
+        value(&samples, "mmdb_operation_latency_seconds", &p99)
+            .parse::<f64>()
+            .unwrap()
+            > 0.0
+    );
+
+    // A private cache is named after its DB.
+    assert_eq!(
+        value(
+            &samples,
+            "mmdb_block_cache_info",
+            &format!(r#"{{db="{db_label}",cf="default",cache="{db_label}"}}"#)
+        ),
+        "1"
+    );
+    let cache = format!(r#"{{cache="{db_label}"}}"#);
+    assert_eq!(
+        value(&samples, "mmdb_block_cache_capacity_bytes", &cache),
+        DbOptions::default().block_cache_capacity.to_string()
+    );
+}
+
+#[test]
+fn shared_pool_is_reported_once() {
+    let pool = Arc::new(BlockCachePool::new(8 * 1024 * 1024));
+    let dirs = [tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap()];
+    let dbs: Vec<DB> = dirs
+        .iter()
+        .map(|dir| {
+            let options = DbOptions {
+                block_cache: Some(pool.clone()),
+                ..Default::default()
+            };
+            DB::open(options, dir.path()).unwrap()
+        })
+        .collect();
+    for db in &dbs {
+        db.put(b"key", b"value").unwrap();
+        db.flush().unwrap();
+        db.get(b"key").unwrap();
+    }
+    let cf = dbs[1]
+        .create_column_family("events", DbOptions::default())
+        .unwrap();
+    dbs[1].put_cf(&cf, b"key", b"value").unwrap();
+
+    let mut exporter = MetricsExporter::new();
+    exporter
+        .add_db("a", &dbs[0])
+        .add_db("b", &dbs[1])
+        .add_block_cache_pool("shared", &pool);
+    let mut body = Vec::new();
+    exporter.write_to(&mut body).unwrap();
+    let samples = parse(&String::from_utf8(body).unwrap());
+
+    let capacity: Vec<_> = samples
+        .iter()
+        .filter(|(name, _, _)| name == "mmdb_block_cache_capacity_bytes")
+        .collect();
+    // The shared pool, plus the column family's private one.
+    assert_eq!(capacity.len(), 2);
+    assert_eq!(
+        value(
+            &samples,
+            "mmdb_block_cache_capacity_bytes",
+            r#"{cache="shared"}"#
+        ),
+        "8388608"
+    );
+    value(
+        &samples,
+        "mmdb_block_cache_usage_bytes",
+        r#"{cache="shared"}"#,
+    );
+    for db in ["a", "b"] {
+        let labels = format!(r#"{{db="{db}",cf="default",cache="shared"}}"#);
+        assert_eq!(value(&samples, "mmdb_block_cache_info", &labels), "1");
+        let labels = format!(r#"{{db="{db}",cf="default"}}"#);
+        assert_eq!(value(&samples, "mmdb_flushes_total", &labels), "1");
+        // Each member pins its own L0 blocks.
+        assert_ne!(
+            value(&samples, "mmdb_block_cache_pinned_bytes", &labels),
+            "0"
+        );
+    }
+    assert_eq!(
+        value(
+            &samples,
+            "mmdb_block_cache_info",
+            r#"{db="b",cf="events",cache="b"}"#
+        ),
+        "1"
+    );
+    assert_eq!(
+        value(
+            &samples,
+            "mmdb_bytes_written_total",
+            r#"{db="b",cf="events"}"#
+        ),
+        "0"
+    );
+    // Statistics are off: no extended tickers or latency summaries.
+    assert!(samples.iter().all(
+        |(name, _, _)| !name.starts_with("mmdb_operation_latency_seconds")
+            && !name.starts_with("mmdb_bloom_filter")
+    ));
+}
+
+#[test]
+fn pending_compaction_bytes_track_l0_backlog() {
+    let dir = tempfile::tempdir().unwrap();
+    {
+        let options = DbOptions {
+            l0_compaction_trigger: 100,
+            ..Default::default()
+        };
+        let db = DB::open(options, dir.path()).unwrap();
+        for round in 0..3u32 {
+            db.put(&round.to_be_bytes(), b"value").unwrap();
+            db.flush().unwrap();
+        }
+        let samples = parse(&db.metrics_openmetrics());
+        let labels = format!(r#"{{db="{}",cf="default"}}"#, dir.path().display());
+        assert_eq!(
+            value(&samples, "mmdb_pending_compaction_bytes", &labels),
+            "0"
+        );
+    }
+
+    // Read-only: the backlog stays put for a lower trigger to see.
+    let options = DbOptions {
+        l0_compaction_trigger: 2,
+        ..Default::default()
+    };
+    let db = DB::open_read_only_with_options(options, dir.path()).unwrap();
+    let samples = parse(&db.metrics_openmetrics());
+    let labels = format!(r#"{{db="{}",cf="default"}}"#, dir.path().display());
+    let level0 = format!(
+        r#"{{db="{}",cf="default",level="0"}}"#,
+        dir.path().display()
+    );
+    assert_eq!(value(&samples, "mmdb_level_files", &level0), "3");
+    assert_eq!(
+        value(&samples, "mmdb_pending_compaction_bytes", &labels),
+        value(&samples, "mmdb_level_bytes", &level0)
+    );
+}
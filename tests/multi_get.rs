@@ -0,0 +1,134 @@
+//! Batched multi_get tests: results must match per-key get_with_options.
+
+use std::sync::Arc;
+
+use mmdb::{DB, DbOptions, MergeOperator, ReadOptions};
+
+struct AppendOperator;
+
+impl MergeOperator for AppendOperator {
+    fn name(&self) -> &str {
+        "append"
+    }
+
+    fn full_merge(
+        &self,
+        _key: &[u8],
+        existing: Option<&[u8]>,
+        operands: &[&[u8]],
+    ) -> Option<Vec<u8>> {
+        let mut out = existing.map(<[u8]>::to_vec).unwrap_or_default();
+        for op in operands {
+            out.extend_from_slice(op);
+        }
+        Some(out)
+    }
+}
+
+fn make_opts() -> DbOptions {
+    DbOptions {
+        create_if_missing: true,
+        merge_operator: Some(Arc::new(AppendOperator)),
+        ..Default::default()
+    }
+}
+
+fn key(i: u32) -> Vec<u8> {
+    format!("key{i:04}").into_bytes()
+}
+
+fn assert_matches_get(db: &DB, options: &ReadOptions, keys: &[Vec<u8>]) {
+    let refs: Vec<&[u8]> = keys.iter().map(Vec::as_slice).collect();
+    let results = db.multi_get(options, &refs);
+    assert_eq!(results.len(), keys.len());
+    for (key, result) in keys.iter().zip(results) {
+        assert_eq!(
+            result.unwrap(),
+            db.get_with_options(options, key).unwrap(),
+            "key {}",
+            String::from_utf8_lossy(key)
+        );
+    }
+}
+
+#[test]
+fn test_multi_get_matches_get_across_levels() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = DB::open(make_opts(), dir.path()).unwrap();
+
+    // Bottom level: every key.
+    for i in 0..400 {
+        db.put(&key(i), b"base").unwrap();
+    }
+    db.compact_range(None, None).unwrap();
+    // L0: overwrites, merges, a point delete and a range delete.
+    for i in (0..400).step_by(7) {
+        db.put(&key(i), b"l0").unwrap();
+    }
+    for i in (0..400).step_by(11) {
+        db.merge(&key(i), b"+m").unwrap();
+    }
+    db.delete(&key(5)).unwrap();
+    db.delete_range(&key(100), &key(150)).unwrap();
+    db.flush().unwrap();
+    let snap = db.snapshot();
+    // Memtable: more merges, a write inside the deleted range, a range delete.
+    for i in (0..400).step_by(13) {
+        db.merge(&key(i), b"+mem").unwrap();
+    }
+    db.put(&key(120), b"revived").unwrap();
+    db.delete_range(&key(300), &key(320)).unwrap();
+
+    // Unsorted, with duplicates and missing keys.
+    let mut keys: Vec<Vec<u8>> = (0..420).rev().map(key).collect();
+    keys.extend([
+        key(120),
+        key(5),
+        b"absent".to_vec(),
+        b"zzz".to_vec(),
+        key(0),
+    ]);
+    assert_matches_get(&db, &ReadOptions::default(), &keys);
+    let at_snap = ReadOptions {
+        snapshot: Some(snap.sequence()),
+        ..Default::default()
+    };
+    assert_matches_get(&db, &at_snap, &keys);
+
+    let refs: Vec<&[u8]> = keys.iter().map(Vec::as_slice).collect();
+    let results = db.multi_get(&ReadOptions::default(), &refs);
+    assert_eq!(results[0].as_ref().unwrap(), &None);
+    assert_eq!(
+        results[420 - 1].as_ref().unwrap(),
+        &Some(b"l0+m+mem".to_vec())
+    );
+    assert_eq!(
+        results[420 - 1 - 120].as_ref().unwrap(),
+        &Some(b"revived".to_vec())
+    );
+    assert_eq!(results[420 - 1 - 310].as_ref().unwrap(), &None);
+    assert!(db.multi_get(&ReadOptions::default(), &[]).is_empty());
+}
+
+#[test]
+fn test_multi_get_over_many_l1_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let opts = DbOptions {
+        write_buffer_size: 16 * 1024,
+        target_file_size_base: 16 * 1024,
+        ..make_opts()
+    };
+    let db = DB::open(opts, dir.path()).unwrap();
+    for i in 0..3000 {
+        db.put(&key(i), &[b'v'; 64]).unwrap();
+    }
+    db.delete_range(&key(1000), &key(2000)).unwrap();
+    db.compact_range(None, None).unwrap();
+    for i in (0..3000).step_by(3) {
+        db.put(&key(i), b"new").unwrap();
+    }
+    db.flush().unwrap();
+
+    let keys: Vec<Vec<u8>> = (0..3100).step_by(2).map(key).collect();
+    assert_matches_get(&db, &ReadOptions::default(), &keys);
+}
@@ -0,0 +1,211 @@
+//! Secondary instances following a live primary with
+//! `open_as_secondary` and `try_catch_up_with_primary`.
+
+use std::path::Path;
+
+use mmdb::{DB, DbOptions, ErrorKind};
+
+fn opts() -> DbOptions {
+    DbOptions {
+        create_if_missing: true,
+        ..Default::default()
+    }
+}
+
+fn key(i: u64) -> Vec<u8> {
+    format!("key{:05}", i).into_bytes()
+}
+
+fn put_range(db: &DB, from: u64, to: u64, value: &str) {
+    for i in from..to {
+        db.put(&key(i), value.as_bytes()).unwrap();
+    }
+}
+
+fn count_files(path: &Path, suffix: &str) -> usize {
+    std::fs::read_dir(path)
+        .unwrap()
+        .filter(|e| {
+            e.as_ref()
+                .unwrap()
+                .file_name()
+                .to_string_lossy()
+                .ends_with(suffix)
+        })
+        .count()
+}
+
+#[test]
+fn secondary_follows_writes_flushes_and_compactions() {
+    let dir = tempfile::tempdir().unwrap();
+    let primary_path = dir.path().join("primary");
+    let secondary_path = dir.path().join("secondary");
+    let primary = DB::open(opts(), &primary_path).unwrap();
+    put_range(&primary, 0, 100, "v1");
+    primary.flush().unwrap();
+    put_range(&primary, 100, 150, "v1");
+
+    // Opening sees both the flushed SST and the live WAL.
+    let secondary = DB::open_as_secondary(opts(), &primary_path, &secondary_path).unwrap();
+    assert_eq!(secondary.get(&key(0)).unwrap(), Some(b"v1".to_vec()));
+    assert_eq!(secondary.get(&key(149)).unwrap(), Some(b"v1".to_vec()));
+
+    // Tailing the same WAL: nothing moves until the catch-up.
+    put_range(&primary, 150, 200, "v1");
+    primary.delete(&key(0)).unwrap();
+    assert_eq!(secondary.get(&key(150)).unwrap(), None);
+    secondary.try_catch_up_with_primary().unwrap();
+    assert_eq!(secondary.get(&key(199)).unwrap(), Some(b"v1".to_vec()));
+    assert_eq!(secondary.get(&key(0)).unwrap(), None);
+    assert_eq!(
+        secondary.latest_sequence_number(),
+        primary.latest_sequence_number()
+    );
+
+    // A flush retires the WAL being tailed, and compaction deletes SSTs.
+    put_range(&primary, 0, 200, "v2");
+    primary.flush().unwrap();
+    put_range(&primary, 200, 250, "v2");
+    primary.compact().unwrap();
+    put_range(&primary, 250, 300, "v2");
+    secondary.try_catch_up_with_primary().unwrap();
+    let entries: Vec<_> = secondary.iter().unwrap().collect();
+    assert_eq!(entries.len(), 300);
+    assert!(entries.iter().all(|(_, v)| v == b"v2"));
+
+    // Catching up with no new writes is a no-op.
+    secondary.try_catch_up_with_primary().unwrap();
+    assert_eq!(secondary.iter().unwrap().count(), 300);
+}
+
+#[test]
+fn secondary_readers_keep_a_consistent_view_while_primary_deletes_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let primary_path = dir.path().join("primary");
+    let secondary_path = dir.path().join("secondary");
+    let options = DbOptions {
+        write_buffer_size: 4 * 1024,
+        ..opts()
+    };
+    let primary = DB::open(options.clone(), &primary_path).unwrap();
+    put_range(&primary, 0, 500, "old");
+    primary.flush().unwrap();
+
+    let secondary = DB::open_as_secondary(options, &primary_path, &secondary_path).unwrap();
+    let mut old_iter = secondary.iter().unwrap();
+    let first = old_iter.next().unwrap();
+    assert_eq!(first, (key(0), b"old".to_vec()));
+
+    // Rewrite everything and compact: every SST the iterator reads is gone.
+    let old_ssts = count_files(&primary_path, ".sst");
+    assert!(old_ssts > 0);
+    put_range(&primary, 0, 500, "new");
+    primary.compact().unwrap();
+    secondary.try_catch_up_with_primary().unwrap();
+
+    let rest: Vec<_> = old_iter.collect();
+    assert_eq!(rest.len(), 499);
+    assert!(rest.iter().all(|(_, v)| v == b"old"));
+    assert_eq!(secondary.get(&key(0)).unwrap(), Some(b"new".to_vec()));
+    let entries: Vec<_> = secondary.iter().unwrap().collect();
+    assert_eq!(entries.len(), 500);
+    assert!(entries.iter().all(|(_, v)| v == b"new"));
+}
+
+#[test]
+fn secondary_catches_up_after_primary_reopens() {
+    let dir = tempfile::tempdir().unwrap();
+    let primary_path = dir.path().join("primary");
+    let secondary_path = dir.path().join("secondary");
+    let primary = DB::open(opts(), &primary_path).unwrap();
+    put_range(&primary, 0, 10, "a");
+    let secondary = DB::open_as_secondary(opts(), &primary_path, &secondary_path).unwrap();
+
+    // Reopening the primary switches to a new WAL and log number before
+    // the secondary has caught up once.
+    primary.close().unwrap();
+    drop(primary);
+    let primary = DB::open(opts(), &primary_path).unwrap();
+    for round in 0..40 {
+        put_range(&primary, round * 10, round * 10 + 10, "b");
+        primary.flush().unwrap();
+    }
+    put_range(&primary, 400, 410, "b");
+    secondary.try_catch_up_with_primary().unwrap();
+    assert_eq!(secondary.iter().unwrap().count(), 410);
+    assert_eq!(secondary.get(&key(0)).unwrap(), Some(b"b".to_vec()));
+    assert_eq!(secondary.get(&key(409)).unwrap(), Some(b"b".to_vec()));
+}
+
+#[test]
+fn secondary_column_families_follow_the_primary() {
+    let dir = tempfile::tempdir().unwrap();
+    let primary_path = dir.path().join("primary");
+    let secondary_path = dir.path().join("secondary");
+    let primary = DB::open(opts(), &primary_path).unwrap();
+    let cf = primary.create_column_family("meta", opts()).unwrap();
+    primary.put_cf(&cf, b"k", b"1").unwrap();
+
+    let secondary = DB::open_as_secondary(opts(), &primary_path, &secondary_path).unwrap();
+    let secondary_cf = secondary.column_family("meta").unwrap();
+    assert_eq!(
+        secondary.get_cf(&secondary_cf, b"k").unwrap(),
+        Some(b"1".to_vec())
+    );
+
+    primary.put_cf(&cf, b"k", b"2").unwrap();
+    primary.flush_cf(&cf).unwrap();
+    primary.put_cf(&cf, b"j", b"3").unwrap();
+    secondary.try_catch_up_with_primary().unwrap();
+    assert_eq!(
+        secondary.get_cf(&secondary_cf, b"k").unwrap(),
+        Some(b"2".to_vec())
+    );
+    assert_eq!(
+        secondary.get_cf(&secondary_cf, b"j").unwrap(),
+        Some(b"3".to_vec())
+    );
+    assert_eq!(secondary.get(b"k").unwrap(), None);
+}
+
+#[test]
+fn secondary_rejects_writes_and_misuse() {
+    let dir = tempfile::tempdir().unwrap();
+    let primary_path = dir.path().join("primary");
+    let secondary_path = dir.path().join("secondary");
+    let primary = DB::open(opts(), &primary_path).unwrap();
+    primary.put(b"k", b"v").unwrap();
+
+    let secondary = DB::open_as_secondary(opts(), &primary_path, &secondary_path).unwrap();
+    assert_eq!(
+        secondary.put(b"k", b"w").unwrap_err().kind(),
+        ErrorKind::ReadOnly
+    );
+    assert_eq!(secondary.flush().unwrap_err().kind(), ErrorKind::ReadOnly);
+
+    // The secondary directory is locked by the first instance.
+    let err = DB::open_as_secondary(opts(), &primary_path, &secondary_path)
+        .err()
+        .unwrap();
+    assert_eq!(err.kind(), ErrorKind::InvalidArgument);
+    let err = DB::open_as_secondary(opts(), &primary_path, &primary_path)
+        .err()
+        .unwrap();
+    assert_eq!(err.kind(), ErrorKind::InvalidArgument);
+    let other_path = dir.path().join("other");
+    let err = DB::open_as_secondary(opts(), dir.path().join("missing"), &other_path)
+        .err()
+        .unwrap();
+    assert_eq!(err.kind(), ErrorKind::InvalidArgument);
+
+    assert_eq!(
+        primary.try_catch_up_with_primary().unwrap_err().kind(),
+        ErrorKind::InvalidArgument
+    );
+
+    secondary.close().unwrap();
+    assert_eq!(
+        secondary.try_catch_up_with_primary().unwrap_err().kind(),
+        ErrorKind::DbClosed
+    );
+}
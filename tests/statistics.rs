@@ -0,0 +1,127 @@
+//! `DB::statistics`: tickers and latency histograms.
+
+use mmdb::{DB, DbOptions, WriteOptions};
+
+fn opts() -> DbOptions {
+    DbOptions {
+        create_if_missing: true,
+        enable_statistics: true,
+        bloom_bits_per_key: 10,
+        l0_compaction_trigger: 100,
+        ..Default::default()
+    }
+}
+
+fn key(i: u64) -> Vec<u8> {
+    format!("key{:05}", i).into_bytes()
+}
+
+#[test]
+fn histograms_time_each_operation() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = DB::open(opts(), dir.path()).unwrap();
+    let sync = WriteOptions {
+        sync: true,
+        ..Default::default()
+    };
+    for i in 0..100 {
+        db.put_with_options(&sync, &key(i), b"value").unwrap();
+    }
+    db.flush().unwrap();
+    for i in 0..100 {
+        db.put(&key(i), b"newer").unwrap();
+    }
+    db.flush().unwrap();
+    db.compact().unwrap();
+    for i in 0..50 {
+        assert!(db.get(&key(i)).unwrap().is_some());
+    }
+    let mut iter = db.iter().unwrap();
+    iter.seek(&key(10));
+    for _ in 0..20 {
+        assert!(iter.valid());
+        iter.advance();
+    }
+    iter.valid();
+    drop(iter);
+
+    let stats = db.statistics();
+    assert_eq!(stats.write_micros.count, 200);
+    // Plus any sync of a retiring WAL at a flush.
+    assert!(stats.wal_sync_micros.count >= 100);
+    assert_eq!(stats.get_micros.count, 50);
+    assert_eq!(stats.seek_micros.count, 1);
+    assert_eq!(stats.next_micros.count, 20);
+    assert_eq!(stats.flush_micros.count, 2);
+    assert_eq!(stats.compaction_micros.count, stats.compactions_completed);
+    assert!(stats.compactions_completed > 0);
+    let writes = &stats.write_micros;
+    assert!(writes.min <= writes.p50() && writes.p50() <= writes.p99());
+    assert!(writes.p99() <= writes.p999() && writes.p999() <= writes.max);
+}
+
+#[test]
+fn tickers_count_reads_by_source() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = DB::open(opts(), dir.path()).unwrap();
+    for i in (0..100).step_by(2) {
+        db.put(&key(i), b"value").unwrap();
+    }
+    db.flush().unwrap();
+    db.compact().unwrap();
+    db.put(&key(0), b"in memtable").unwrap();
+    db.delete(&key(2)).unwrap();
+
+    assert_eq!(db.get(&key(0)).unwrap(), Some(b"in memtable".to_vec()));
+    assert_eq!(db.get(&key(4)).unwrap(), Some(b"value".to_vec()));
+    for i in (1..100).step_by(2) {
+        assert_eq!(db.get(&key(i)).unwrap(), None);
+    }
+    let stats = db.statistics();
+    assert_eq!(stats.get_hits_memtable, 1);
+    let level = stats
+        .get_hits_per_level
+        .iter()
+        .position(|&hits| hits > 0)
+        .unwrap();
+    assert!(level > 0);
+    assert_eq!(stats.get_hits_per_level[level], 1);
+    assert_eq!(
+        stats.bytes_read_per_level[level],
+        (key(4).len() + b"value".len()) as u64
+    );
+    assert_eq!(stats.get_hits_per_level.len(), opts().num_levels);
+    // Every absent key inside the SST's range reached its filter.
+    assert_eq!(stats.bloom_filter_checked, 50);
+    assert!(stats.bloom_filter_useful >= 40);
+    assert_eq!(
+        stats.bloom_filter_checked - stats.bloom_filter_useful,
+        stats.bloom_filter_false_positive + 1
+    );
+
+    let live: Vec<_> = db.iter().unwrap().map(|(k, _)| k).collect();
+    assert_eq!(live.len(), 49);
+    assert_eq!(db.statistics().iter_tombstones_skipped, 1);
+}
+
+#[test]
+fn disabled_statistics_keep_only_the_base_counters() {
+    let dir = tempfile::tempdir().unwrap();
+    let options = DbOptions {
+        enable_statistics: false,
+        ..opts()
+    };
+    let db = DB::open(options, dir.path()).unwrap();
+    db.put(b"a", b"1").unwrap();
+    db.flush().unwrap();
+    assert_eq!(db.get(b"a").unwrap(), Some(b"1".to_vec()));
+    assert_eq!(db.get(b"b").unwrap(), None);
+
+    let stats = db.statistics();
+    assert!(stats.bytes_written > 0);
+    assert_eq!(stats.flushes_completed, 1);
+    assert_eq!(stats.write_micros.count, 0);
+    assert_eq!(stats.get_micros.count, 0);
+    assert_eq!(stats.bloom_filter_checked, 0);
+    assert!(stats.get_hits_per_level.iter().all(|&hits| hits == 0));
+}
@@ -0,0 +1,206 @@
+//! User-defined timestamps: versioned writes and reads as of a timestamp.
+
+use std::sync::Arc;
+
+use mmdb::{BytewiseComparatorWithU64Ts, DB, DbOptions, ErrorKind, ReadOptions};
+
+fn ts_opts() -> DbOptions {
+    DbOptions {
+        create_if_missing: true,
+        comparator: Arc::new(BytewiseComparatorWithU64Ts),
+        ..Default::default()
+    }
+}
+
+fn ts(t: u64) -> [u8; 8] {
+    t.to_be_bytes()
+}
+
+fn at(t: u64) -> ReadOptions {
+    ReadOptions {
+        timestamp: Some(ts(t).to_vec()),
+        ..Default::default()
+    }
+}
+
+fn get_at(db: &DB, key: &[u8], t: u64) -> Option<Vec<u8>> {
+    db.get_with_options(&at(t), key).unwrap()
+}
+
+fn scan_at(db: &DB, t: u64) -> Vec<(Vec<u8>, Vec<u8>)> {
+    db.iter_with_options(&at(t)).unwrap().collect()
+}
+
+fn scan_reverse_at(db: &DB, t: u64) -> Vec<Vec<u8>> {
+    let mut iter = db.iter_with_options(&at(t)).unwrap();
+    iter.seek_to_last();
+    let mut out = Vec::new();
+    while iter.valid() {
+        out.push(iter.key().unwrap().to_vec());
+        iter.prev();
+    }
+    out
+}
+
+fn kv(k: &str, v: &str) -> (Vec<u8>, Vec<u8>) {
+    (k.as_bytes().to_vec(), v.as_bytes().to_vec())
+}
+
+/// Versions written across the memtable and SSTs, read at several
+/// timestamps before and after compaction and reopen.
+#[test]
+fn test_reads_as_of_timestamp() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = DB::open(ts_opts(), dir.path()).unwrap();
+    db.put_with_timestamp(b"a", &ts(10), b"a10").unwrap();
+    db.put_with_timestamp(b"b", &ts(10), b"b10").unwrap();
+    db.flush().unwrap();
+    db.put_with_timestamp(b"a", &ts(20), b"a20").unwrap();
+    db.delete_with_timestamp(b"b", &ts(30)).unwrap();
+    db.put_with_timestamp(b"c", &ts(25), b"c25").unwrap();
+
+    let check = |db: &DB| {
+        assert_eq!(get_at(db, b"a", 5), None);
+        assert_eq!(get_at(db, b"a", 10), Some(b"a10".to_vec()));
+        assert_eq!(get_at(db, b"a", 19), Some(b"a10".to_vec()));
+        assert_eq!(get_at(db, b"a", 20), Some(b"a20".to_vec()));
+        assert_eq!(get_at(db, b"b", 29), Some(b"b10".to_vec()));
+        assert_eq!(get_at(db, b"b", 30), None);
+        assert_eq!(db.get(b"a").unwrap(), Some(b"a20".to_vec()));
+        assert_eq!(db.get(b"b").unwrap(), None);
+
+        assert_eq!(scan_at(db, 10), vec![kv("a", "a10"), kv("b", "b10")]);
+        assert_eq!(
+            scan_at(db, 25),
+            vec![kv("a", "a20"), kv("b", "b10"), kv("c", "c25")]
+        );
+        assert_eq!(
+            db.iter().unwrap().collect::<Vec<_>>(),
+            vec![kv("a", "a20"), kv("c", "c25")]
+        );
+        assert_eq!(
+            scan_reverse_at(db, 25),
+            vec![b"c".to_vec(), b"b".to_vec(), b"a".to_vec()]
+        );
+        assert_eq!(scan_reverse_at(db, 15), vec![b"b".to_vec(), b"a".to_vec()]);
+
+        let mut iter = db.iter_with_options(&at(15)).unwrap();
+        iter.seek(b"b");
+        assert_eq!(iter.key().unwrap(), b"b");
+        iter.seek_for_prev(b"bb");
+        assert_eq!(iter.key().unwrap(), b"b");
+
+        let results = db.multi_get(&at(20), &[b"a", b"b", b"c"]);
+        assert_eq!(results[0].as_ref().unwrap(), &Some(b"a20".to_vec()));
+        assert_eq!(results[1].as_ref().unwrap(), &Some(b"b10".to_vec()));
+        assert_eq!(results[2].as_ref().unwrap(), &None);
+    };
+
+    check(&db);
+    db.flush().unwrap();
+    db.compact_range(None, None).unwrap();
+    check(&db);
+    db.close().unwrap();
+    let db = DB::open(ts_opts(), dir.path()).unwrap();
+    check(&db);
+}
+
+/// Incompressible filler, so SST size tracks the number of versions kept.
+fn noise(seed: u64) -> Vec<u8> {
+    let mut x = seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1;
+    (0..4096)
+        .map(|_| {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            x as u8
+        })
+        .collect()
+}
+
+fn sst_size(db: &DB) -> u64 {
+    db.get_property("total-sst-size").unwrap().parse().unwrap()
+}
+
+#[test]
+fn test_full_history_ts_low_collapses_old_versions() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = DB::open(ts_opts(), dir.path()).unwrap();
+    for t in 1..=5 {
+        db.put_with_timestamp(b"k", &ts(t * 10), &noise(t)).unwrap();
+    }
+    db.put_with_timestamp(b"gone", &ts(10), &noise(9)).unwrap();
+    db.delete_with_timestamp(b"gone", &ts(20)).unwrap();
+    db.flush().unwrap();
+    db.compact_range(None, None).unwrap();
+    // Without a watermark every version survives compaction.
+    assert_eq!(get_at(&db, b"k", 10), Some(noise(1)));
+    assert_eq!(get_at(&db, b"gone", 15), Some(noise(9)));
+    let before = sst_size(&db);
+
+    db.increase_full_history_ts_low(&ts(35)).unwrap();
+    assert_eq!(db.full_history_ts_low(), Some(ts(35).to_vec()));
+    let err = db.increase_full_history_ts_low(&ts(30)).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidArgument, "{err}");
+    let err = db.get_with_options(&at(34), b"k").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidArgument, "{err}");
+
+    db.compact_range(None, None).unwrap();
+    // The newest version below the watermark still answers reads at it;
+    // the three values it shadows (k@10, k@20, gone@10) are dropped.
+    assert_eq!(get_at(&db, b"k", 35), Some(noise(3)));
+    assert_eq!(get_at(&db, b"k", 40), Some(noise(4)));
+    assert_eq!(get_at(&db, b"k", 50), Some(noise(5)));
+    assert_eq!(get_at(&db, b"gone", 35), None);
+    let after = sst_size(&db);
+    assert!(
+        after + 3 * 4096 <= before,
+        "history below the watermark was kept: {before} -> {after} bytes"
+    );
+
+    db.close().unwrap();
+    let db = DB::open(ts_opts(), dir.path()).unwrap();
+    assert_eq!(db.full_history_ts_low(), Some(ts(35).to_vec()));
+    let err = db.get_with_options(&at(20), b"k").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidArgument, "{err}");
+    assert_eq!(get_at(&db, b"k", 35), Some(noise(3)));
+}
+
+#[test]
+fn test_timestamp_misuse_is_rejected() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = DB::open(ts_opts(), dir.path()).unwrap();
+    for err in [
+        db.put(b"k", b"v").unwrap_err(),
+        db.delete(b"k").unwrap_err(),
+        db.delete_range(b"a", b"z").unwrap_err(),
+        db.put_with_timestamp(b"k", &[1, 2, 3], b"v").unwrap_err(),
+        db.get_with_options(
+            &ReadOptions {
+                timestamp: Some(vec![0; 4]),
+                ..Default::default()
+            },
+            b"k",
+        )
+        .unwrap_err(),
+    ] {
+        assert_eq!(err.kind(), ErrorKind::InvalidArgument, "{err}");
+    }
+
+    let dir = tempfile::tempdir().unwrap();
+    let plain = DB::open(
+        DbOptions {
+            create_if_missing: true,
+            ..Default::default()
+        },
+        dir.path(),
+    )
+    .unwrap();
+    for err in [
+        plain.put_with_timestamp(b"k", &ts(1), b"v").unwrap_err(),
+        plain.get_with_options(&at(1), b"k").unwrap_err(),
+        plain.increase_full_history_ts_low(&ts(1)).unwrap_err(),
+    ] {
+        assert_eq!(err.kind(), ErrorKind::InvalidArgument, "{err}");
+    }
+}
@@ -0,0 +1,223 @@
+//! Per-key TTL tests driven by a manual clock.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use mmdb::{Clock, DB, DbOptions, MergeOperator, ReadOptions, WriteBatch};
+
+struct ManualClock(AtomicU64);
+
+impl ManualClock {
+    fn advance(&self, ms: u64) {
+        self.0.fetch_add(ms, Ordering::SeqCst);
+    }
+
+    fn set(&self, ms: u64) {
+        self.0.store(ms, Ordering::SeqCst);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now_millis(&self) -> u64 {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+struct AppendOperator;
+
+impl MergeOperator for AppendOperator {
+    fn name(&self) -> &str {
+        "append"
+    }
+
+    fn full_merge(
+        &self,
+        _key: &[u8],
+        existing: Option<&[u8]>,
+        operands: &[&[u8]],
+    ) -> Option<Vec<u8>> {
+        let mut out = existing.map(<[u8]>::to_vec).unwrap_or_default();
+        for op in operands {
+            out.extend_from_slice(op);
+        }
+        Some(out)
+    }
+}
+
+const START: u64 = 1_000_000;
+
+fn make_opts(clock: &Arc<ManualClock>) -> DbOptions {
+    DbOptions {
+        create_if_missing: true,
+        merge_operator: Some(Arc::new(AppendOperator)),
+        clock: clock.clone(),
+        ..Default::default()
+    }
+}
+
+fn new_clock() -> Arc<ManualClock> {
+    Arc::new(ManualClock(AtomicU64::new(START)))
+}
+
+fn scan(db: &DB) -> Vec<(Vec<u8>, Vec<u8>)> {
+    db.iter().unwrap().collect()
+}
+
+fn scan_reverse(db: &DB) -> Vec<Vec<u8>> {
+    let mut iter = db.iter().unwrap();
+    iter.seek_to_last();
+    let mut keys = Vec::new();
+    while iter.valid() {
+        keys.push(iter.key().unwrap().to_vec());
+        iter.prev();
+    }
+    keys
+}
+
+#[test]
+fn test_expired_entries_are_hidden_from_reads() {
+    let dir = tempfile::tempdir().unwrap();
+    let clock = new_clock();
+    let db = DB::open(make_opts(&clock), dir.path()).unwrap();
+    db.put(b"a", b"plain").unwrap();
+    db.put_with_ttl(b"b", b"short", Duration::from_secs(10))
+        .unwrap();
+    db.put_with_ttl(b"c", b"long", Duration::from_secs(100))
+        .unwrap();
+    let snap = db.snapshot();
+
+    assert_eq!(db.get(b"b").unwrap(), Some(b"short".to_vec()));
+    assert_eq!(scan(&db).len(), 3);
+
+    clock.advance(10_000);
+    assert_eq!(db.get(b"b").unwrap(), None);
+    assert_eq!(db.get(b"c").unwrap(), Some(b"long".to_vec()));
+    assert_eq!(
+        scan(&db),
+        vec![
+            (b"a".to_vec(), b"plain".to_vec()),
+            (b"c".to_vec(), b"long".to_vec()),
+        ]
+    );
+    assert_eq!(scan_reverse(&db), vec![b"c".to_vec(), b"a".to_vec()]);
+    let results = db.multi_get(&ReadOptions::default(), &[b"a", b"b", b"c"]);
+    assert_eq!(results[1].as_ref().unwrap(), &None);
+    assert_eq!(results[2].as_ref().unwrap(), &Some(b"long".to_vec()));
+    // Expiry is absolute: snapshots do not keep expired data alive.
+    let at_snap = ReadOptions {
+        snapshot: Some(snap.sequence()),
+        ..Default::default()
+    };
+    assert_eq!(db.get_with_options(&at_snap, b"b").unwrap(), None);
+
+    // Same answers once the data lives in SSTs, and after reopen.
+    db.flush().unwrap();
+    assert_eq!(db.get(b"b").unwrap(), None);
+    assert_eq!(db.get(b"c").unwrap(), Some(b"long".to_vec()));
+    drop(snap);
+    db.close().unwrap();
+    let db = DB::open(make_opts(&clock), dir.path()).unwrap();
+    assert_eq!(db.get(b"b").unwrap(), None);
+    assert_eq!(db.get(b"c").unwrap(), Some(b"long".to_vec()));
+    clock.advance(90_000);
+    assert_eq!(scan(&db), vec![(b"a".to_vec(), b"plain".to_vec())]);
+}
+
+#[test]
+fn test_expired_value_does_not_resurrect_older_version() {
+    let dir = tempfile::tempdir().unwrap();
+    let clock = new_clock();
+    let db = DB::open(make_opts(&clock), dir.path()).unwrap();
+    db.put(b"k", b"old").unwrap();
+    db.flush().unwrap();
+    db.put_with_ttl(b"k", b"new", Duration::from_secs(1))
+        .unwrap();
+    clock.advance(1_000);
+
+    assert_eq!(db.get(b"k").unwrap(), None);
+    assert!(scan(&db).is_empty());
+    assert!(scan_reverse(&db).is_empty());
+    db.flush().unwrap();
+    db.compact_range(None, None).unwrap();
+    assert_eq!(db.get(b"k").unwrap(), None);
+}
+
+#[test]
+fn test_default_ttl_and_batch_ttl() {
+    let dir = tempfile::tempdir().unwrap();
+    let clock = new_clock();
+    let opts = DbOptions {
+        default_ttl: Some(Duration::from_secs(5)),
+        ..make_opts(&clock)
+    };
+    let db = DB::open(opts, dir.path()).unwrap();
+    db.put(b"default", b"v").unwrap();
+    let mut batch = WriteBatch::new();
+    batch.put_with_ttl(b"explicit", b"v", Duration::from_secs(60));
+    batch.put(b"batched", b"v");
+    db.write(batch).unwrap();
+
+    clock.advance(5_000);
+    assert_eq!(db.get(b"default").unwrap(), None);
+    assert_eq!(db.get(b"batched").unwrap(), None);
+    assert_eq!(db.get(b"explicit").unwrap(), Some(b"v".to_vec()));
+}
+
+#[test]
+fn test_merge_onto_expiring_base() {
+    let dir = tempfile::tempdir().unwrap();
+    let clock = new_clock();
+    let db = DB::open(make_opts(&clock), dir.path()).unwrap();
+    db.put_with_ttl(b"k", b"base", Duration::from_secs(10))
+        .unwrap();
+    db.merge(b"k", b"+op").unwrap();
+    assert_eq!(db.get(b"k").unwrap(), Some(b"base+op".to_vec()));
+    // Flushing and compacting must not fold the operand into the base,
+    // or the operand would expire with it.
+    db.flush().unwrap();
+    db.compact_range(None, None).unwrap();
+    assert_eq!(db.get(b"k").unwrap(), Some(b"base+op".to_vec()));
+    assert_eq!(scan(&db), vec![(b"k".to_vec(), b"base+op".to_vec())]);
+
+    clock.advance(10_000);
+    assert_eq!(db.get(b"k").unwrap(), Some(b"+op".to_vec()));
+    assert_eq!(scan(&db), vec![(b"k".to_vec(), b"+op".to_vec())]);
+    db.compact_range(None, None).unwrap();
+    assert_eq!(db.get(b"k").unwrap(), Some(b"+op".to_vec()));
+}
+
+#[test]
+fn test_compaction_drops_expired_entries() {
+    let dir = tempfile::tempdir().unwrap();
+    let clock = new_clock();
+    let db = DB::open(make_opts(&clock), dir.path()).unwrap();
+    // Two L0 files, so compaction really rewrites them.
+    for i in 0..200u32 {
+        let key = format!("key{i:04}");
+        if i % 2 == 0 {
+            db.put_with_ttl(key.as_bytes(), b"temp", Duration::from_secs(1))
+                .unwrap();
+        } else {
+            db.put(key.as_bytes(), b"keep").unwrap();
+        }
+        if i == 100 {
+            db.flush().unwrap();
+        }
+    }
+    db.flush().unwrap();
+
+    // A snapshot taken before expiry still cannot read the expired data,
+    // so it does not hold compaction back.
+    let snap = db.snapshot();
+    clock.advance(1_000);
+    db.compact_range(None, None).unwrap();
+    drop(snap);
+    db.compact_range(None, None).unwrap();
+
+    // Turning the clock back would expose anything compaction kept.
+    clock.set(START);
+    assert_eq!(db.get(b"key0000").unwrap(), None);
+    assert_eq!(db.get(b"key0001").unwrap(), Some(b"keep".to_vec()));
+    assert_eq!(scan(&db).len(), 100);
+}
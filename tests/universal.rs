@@ -0,0 +1,168 @@
+//! Universal compaction: sorted runs merged by size instead of level by level.
+
+use std::time::{Duration, Instant};
+
+use mmdb::{CompactionStyle, DB, DbOptions, ErrorKind, UniversalCompactionOptions};
+
+fn opts(style: CompactionStyle) -> DbOptions {
+    DbOptions {
+        create_if_missing: true,
+        write_buffer_size: 16 * 1024,
+        target_file_size_base: 32 * 1024,
+        max_bytes_for_level_base: 64 * 1024,
+        compaction_style: style,
+        ..Default::default()
+    }
+}
+
+fn key(i: u64) -> Vec<u8> {
+    format!("key{:06}", i).into_bytes()
+}
+
+fn value(i: u64, round: u64) -> Vec<u8> {
+    format!("value-{i}-{round}-{}", "x".repeat(100)).into_bytes()
+}
+
+/// Overwrite `keys` keys `rounds` times, flushing after each round.
+fn load(db: &DB, keys: u64, rounds: u64) {
+    for round in 0..rounds {
+        for i in 0..keys {
+            db.put(
+                &key((i * 7919 + round) % keys),
+                &value((i * 7919 + round) % keys, round),
+            )
+            .unwrap();
+        }
+        db.flush().unwrap();
+    }
+}
+
+fn check(db: &DB, keys: u64, rounds: u64) {
+    // The last round writes every key.
+    for i in 0..keys {
+        assert_eq!(
+            db.get(&key(i)).unwrap(),
+            Some(value(i, rounds - 1)),
+            "key {i}"
+        );
+    }
+    assert_eq!(db.iter().unwrap().count() as u64, keys);
+}
+
+/// Number of sorted runs: each L0 file plus each non-empty level.
+fn sorted_runs(db: &DB, num_levels: usize) -> usize {
+    (0..num_levels)
+        .map(|level| {
+            let files: usize = db
+                .get_property(&format!("num-files-at-level{level}"))
+                .unwrap()
+                .parse()
+                .unwrap();
+            if level == 0 {
+                files
+            } else {
+                usize::from(files > 0)
+            }
+        })
+        .sum()
+}
+
+fn compaction_bytes_written(db: &DB) -> u64 {
+    db.get_property("stats.compaction_bytes_written")
+        .unwrap()
+        .parse()
+        .unwrap()
+}
+
+#[test]
+fn test_universal_keeps_data_and_bounds_runs() {
+    let dir = tempfile::tempdir().unwrap();
+    let options = opts(CompactionStyle::Universal);
+    let db = DB::open(options.clone(), dir.path()).unwrap();
+    load(&db, 2000, 8);
+    check(&db, 2000, 8);
+    // Background compaction keeps the run count under the trigger.
+    let deadline = Instant::now() + Duration::from_secs(30);
+    while db.get_property("compaction-pending").unwrap() != "0" {
+        assert!(Instant::now() < deadline, "compaction never settled");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert!(sorted_runs(&db, options.num_levels) < options.l0_compaction_trigger);
+    db.close().unwrap();
+
+    let db = DB::open(options.clone(), dir.path()).unwrap();
+    check(&db, 2000, 8);
+    db.compact_range(None, None).unwrap();
+    check(&db, 2000, 8);
+    assert_eq!(sorted_runs(&db, options.num_levels), 1);
+}
+
+#[test]
+fn test_universal_writes_less_than_leveled() {
+    let mut written = Vec::new();
+    for style in [CompactionStyle::Leveled, CompactionStyle::Universal] {
+        let dir = tempfile::tempdir().unwrap();
+        let db = DB::open(opts(style), dir.path()).unwrap();
+        // Fresh keys in scattered order: every level of a leveled tree
+        // overlaps every new file.
+        let keys = 20_000;
+        for i in 0..keys {
+            let k = (i * 7919) % keys;
+            db.put(&key(k), &value(k, 0)).unwrap();
+        }
+        db.flush().unwrap();
+        check(&db, keys, 1);
+        written.push(compaction_bytes_written(&db));
+    }
+    assert!(
+        written[1] < written[0],
+        "universal wrote {} compaction bytes, leveled {}",
+        written[1],
+        written[0]
+    );
+}
+
+#[test]
+fn test_switching_styles_keeps_data() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = DB::open(opts(CompactionStyle::Leveled), dir.path()).unwrap();
+    load(&db, 1000, 4);
+    db.close().unwrap();
+
+    let db = DB::open(opts(CompactionStyle::Universal), dir.path()).unwrap();
+    check(&db, 1000, 4);
+    load(&db, 1000, 6);
+    check(&db, 1000, 6);
+    db.close().unwrap();
+
+    let db = DB::open(opts(CompactionStyle::Leveled), dir.path()).unwrap();
+    check(&db, 1000, 6);
+    load(&db, 1000, 2);
+    db.compact_range(None, None).unwrap();
+    check(&db, 1000, 2);
+}
+
+#[test]
+fn test_invalid_universal_options_are_rejected() {
+    for universal in [
+        UniversalCompactionOptions {
+            min_merge_width: 1,
+            ..Default::default()
+        },
+        UniversalCompactionOptions {
+            min_merge_width: 4,
+            max_merge_width: 3,
+            ..Default::default()
+        },
+    ] {
+        let dir = tempfile::tempdir().unwrap();
+        let options = DbOptions {
+            universal_compaction: universal,
+            ..opts(CompactionStyle::Universal)
+        };
+        let Err(err) = DB::open(options, dir.path()) else {
+            panic!("open with invalid universal options succeeded");
+        };
+        assert_eq!(err.kind(), ErrorKind::InvalidArgument, "{err}");
+    }
+}
@@ -0,0 +1,99 @@
+//! WAL compression: records compressed per `wal_compression`, readable
+//! whatever the setting at replay.
+
+use std::fs;
+use std::path::Path;
+
+use mmdb::{CompressionType, DB, DbOptions};
+
+const RECORDS: usize = 200;
+
+fn opts(compression: CompressionType) -> DbOptions {
+    DbOptions {
+        create_if_missing: true,
+        wal_compression: compression,
+        ..Default::default()
+    }
+}
+
+fn key(round: usize, i: usize) -> Vec<u8> {
+    format!("round{}-key{:04}", round, i).into_bytes()
+}
+
+fn value(round: usize, i: usize) -> Vec<u8> {
+    format!("value {} {} {}", round, i, "text-like payload ".repeat(20)).into_bytes()
+}
+
+fn wal_bytes(dir: &Path) -> u64 {
+    fs::read_dir(dir)
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "wal"))
+        .map(|p| fs::metadata(p).unwrap().len())
+        .sum()
+}
+
+/// Write one round of records and crash, leaving them only in the WAL.
+fn write_round_and_crash(dir: &Path, compression: CompressionType, round: usize) -> u64 {
+    let db = DB::open(opts(compression), dir).unwrap();
+    for i in 0..RECORDS {
+        db.put(&key(round, i), &value(round, i)).unwrap();
+    }
+    db.simulate_crash();
+    wal_bytes(dir)
+}
+
+#[test]
+fn test_compressed_wal_is_smaller() {
+    let sizes: Vec<u64> = [
+        CompressionType::None,
+        CompressionType::Lz4,
+        CompressionType::Zstd,
+    ]
+    .into_iter()
+    .map(|compression| {
+        let dir = tempfile::tempdir().unwrap();
+        let size = write_round_and_crash(dir.path(), compression, 0);
+        let db = DB::open(opts(compression), dir.path()).unwrap();
+        for i in 0..RECORDS {
+            assert_eq!(db.get(&key(0, i)).unwrap(), Some(value(0, i)));
+        }
+        size
+    })
+    .collect();
+    assert!(
+        sizes[1] * 2 < sizes[0],
+        "lz4 {} vs none {}",
+        sizes[1],
+        sizes[0]
+    );
+    assert!(
+        sizes[2] * 2 < sizes[0],
+        "zstd {} vs none {}",
+        sizes[2],
+        sizes[0]
+    );
+}
+
+#[test]
+fn test_replay_across_compression_changes() {
+    let settings = [
+        CompressionType::None,
+        CompressionType::Lz4,
+        CompressionType::Zstd,
+        CompressionType::None,
+    ];
+    let dir = tempfile::tempdir().unwrap();
+    for (round, &compression) in settings.iter().enumerate() {
+        // Each open replays the WAL left by the previous setting.
+        write_round_and_crash(dir.path(), compression, round);
+    }
+
+    let db = DB::open(opts(CompressionType::Lz4), dir.path()).unwrap();
+    assert!(db.wal_recovery_summary().is_clean());
+    for round in 0..settings.len() {
+        for i in 0..RECORDS {
+            assert_eq!(db.get(&key(round, i)).unwrap(), Some(value(round, i)));
+        }
+    }
+}
@@ -0,0 +1,182 @@
+//! WAL recovery modes: what open does with corrupted WAL records.
+
+use std::fs;
+use std::io::{Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use mmdb::{DB, DbOptions, ErrorKind, WalRecoveryMode, WriteOptions};
+
+const RECORDS: usize = 100;
+
+fn opts(mode: WalRecoveryMode) -> DbOptions {
+    DbOptions {
+        create_if_missing: true,
+        wal_recovery_mode: mode,
+        ..Default::default()
+    }
+}
+
+fn key(i: usize) -> Vec<u8> {
+    format!("key{:04}", i).into_bytes()
+}
+
+fn wal_files(dir: &Path) -> Vec<PathBuf> {
+    let mut wals: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "wal"))
+        .collect();
+    wals.sort();
+    wals
+}
+
+fn wal_number(path: &Path) -> u64 {
+    path.file_stem().unwrap().to_str().unwrap().parse().unwrap()
+}
+
+/// Write `RECORDS` 1 KiB records into a single WAL (spanning several
+/// 32 KiB blocks), crash, and return the WAL's path.
+fn write_and_crash(dir: &Path) -> PathBuf {
+    let db = DB::open(opts(WalRecoveryMode::default()), dir).unwrap();
+    for i in 0..RECORDS {
+        db.put(&key(i), &[b'v'; 1000]).unwrap();
+    }
+    db.simulate_crash();
+    let wals = wal_files(dir);
+    assert_eq!(wals.len(), 1);
+    wals[0].clone()
+}
+
+fn overwrite(path: &Path, offset: u64, bytes: &[u8]) {
+    let mut file = fs::OpenOptions::new().write(true).open(path).unwrap();
+    file.seek(SeekFrom::Start(offset)).unwrap();
+    file.write_all(bytes).unwrap();
+}
+
+fn copy_dir(from: &Path, to: &Path) {
+    for entry in fs::read_dir(from).unwrap() {
+        let entry = entry.unwrap();
+        if entry.file_type().unwrap().is_file() {
+            fs::copy(entry.path(), to.join(entry.file_name())).unwrap();
+        }
+    }
+}
+
+fn present(db: &DB) -> Vec<usize> {
+    (0..RECORDS)
+        .filter(|&i| db.get(&key(i)).unwrap().is_some())
+        .collect()
+}
+
+#[test]
+fn test_clean_recovery_summary() {
+    let dir = tempfile::tempdir().unwrap();
+    let wal = write_and_crash(dir.path());
+    let db = DB::open(opts(WalRecoveryMode::default()), dir.path()).unwrap();
+    let summary = db.wal_recovery_summary();
+    assert!(summary.is_clean());
+    assert_eq!(summary.replayed_wals, vec![wal_number(&wal)]);
+    assert_eq!(summary.records_replayed, RECORDS as u64);
+    assert_eq!(present(&db).len(), RECORDS);
+}
+
+#[test]
+fn test_midlog_corruption_by_mode() {
+    let dir = tempfile::tempdir().unwrap();
+    let wal = write_and_crash(dir.path());
+    // Somewhere inside the second 32 KiB block.
+    overwrite(&wal, 40_000, b"XXXXXXXX");
+
+    for mode in [
+        WalRecoveryMode::TolerateCorruptedTailRecords,
+        WalRecoveryMode::AbsoluteConsistency,
+    ] {
+        let Err(err) = DB::open(opts(mode), dir.path()) else {
+            panic!("{mode:?} opened a WAL with mid-log corruption");
+        };
+        assert_eq!(err.kind(), ErrorKind::Corruption, "{err}");
+    }
+
+    // Point-in-time: a prefix survives and nothing after the corruption.
+    let pit = tempfile::tempdir().unwrap();
+    copy_dir(dir.path(), pit.path());
+    let db = DB::open(opts(WalRecoveryMode::PointInTimeRecovery), pit.path()).unwrap();
+    let kept = present(&db);
+    assert!(!kept.is_empty() && kept.len() < RECORDS);
+    assert_eq!(kept, (0..kept.len()).collect::<Vec<_>>());
+    let summary = db.wal_recovery_summary();
+    assert_eq!(summary.records_replayed, kept.len() as u64);
+    assert_eq!(summary.corruptions.len(), 1);
+    assert_eq!(summary.corruptions[0].wal_number, wal_number(&wal));
+    assert!(summary.corruptions[0].offset <= 40_000);
+    assert!(summary.dropped_wals.is_empty());
+
+    // Skip-any: everything but the damaged block, including later records.
+    let skip = tempfile::tempdir().unwrap();
+    copy_dir(dir.path(), skip.path());
+    let db = DB::open(opts(WalRecoveryMode::SkipAnyCorruptedRecords), skip.path()).unwrap();
+    let kept = present(&db);
+    assert!(kept.len() < RECORDS && kept.contains(&(RECORDS - 1)));
+    let summary = db.wal_recovery_summary();
+    assert_eq!(summary.records_replayed, kept.len() as u64);
+    assert!(!summary.corruptions.is_empty());
+    drop(db);
+
+    // The recovered data was flushed; a clean reopen keeps it.
+    let db = DB::open(opts(WalRecoveryMode::AbsoluteConsistency), skip.path()).unwrap();
+    assert_eq!(present(&db), kept);
+    assert!(db.wal_recovery_summary().is_clean());
+}
+
+#[test]
+fn test_torn_tail_rejected_only_by_absolute_consistency() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path();
+    {
+        let db = DB::open(opts(WalRecoveryMode::default()), path).unwrap();
+        let sync = WriteOptions {
+            sync: true,
+            ..Default::default()
+        };
+        for i in 0..3 {
+            db.put_with_options(&sync, &key(i), b"value").unwrap();
+        }
+        db.simulate_crash();
+    }
+    let wal = wal_files(path).pop().unwrap();
+    let len = fs::metadata(&wal).unwrap().len();
+    fs::OpenOptions::new()
+        .write(true)
+        .open(&wal)
+        .unwrap()
+        .set_len(len - 3)
+        .unwrap();
+
+    let Err(err) = DB::open(opts(WalRecoveryMode::AbsoluteConsistency), path) else {
+        panic!("absolute consistency accepted a torn tail");
+    };
+    assert_eq!(err.kind(), ErrorKind::Corruption, "{err}");
+
+    let db = DB::open(opts(WalRecoveryMode::TolerateCorruptedTailRecords), path).unwrap();
+    assert_eq!(present(&db), vec![0, 1]);
+    let summary = db.wal_recovery_summary();
+    assert_eq!(summary.records_replayed, 2);
+    assert_eq!(summary.corruptions.len(), 1);
+}
+
+#[test]
+fn test_point_in_time_recovery_drops_later_wals() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path();
+    let earlier = write_and_crash(path);
+    let newer = path.join(format!("{:06}.wal", wal_number(&earlier) + 1));
+    fs::copy(&earlier, &newer).unwrap();
+    overwrite(&earlier, 40_000, b"XXXXXXXX");
+
+    let db = DB::open(opts(WalRecoveryMode::PointInTimeRecovery), path).unwrap();
+    let summary = db.wal_recovery_summary().clone();
+    assert_eq!(summary.replayed_wals, vec![wal_number(&earlier)]);
+    assert_eq!(summary.dropped_wals, vec![wal_number(&newer)]);
+    assert_eq!(present(&db).len() as u64, summary.records_replayed);
+    assert!(summary.records_replayed < RECORDS as u64);
+}
@@ -0,0 +1,233 @@
+//! Tailing the WAL with `get_updates_since`, and WAL archiving.
+
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use mmdb::{Clock, DB, DbOptions, ErrorKind, WriteBatch, WriteBatchOp};
+
+struct ManualClock(AtomicU64);
+
+impl Clock for ManualClock {
+    fn now_millis(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+fn opts() -> DbOptions {
+    DbOptions {
+        create_if_missing: true,
+        ..Default::default()
+    }
+}
+
+fn archiving(ttl_seconds: u64, size_limit: u64) -> DbOptions {
+    DbOptions {
+        wal_ttl_seconds: ttl_seconds,
+        wal_size_limit: size_limit,
+        ..opts()
+    }
+}
+
+fn key(i: u64) -> Vec<u8> {
+    format!("key{:05}", i).into_bytes()
+}
+
+/// Write `count` three-entry batches: put, merge-free put, delete.
+fn write_batches(db: &DB, from: u64, count: u64) {
+    for i in from..from + count {
+        let mut batch = WriteBatch::new();
+        batch.put(&key(i), b"value");
+        batch.put(&key(i + 100_000), b"other");
+        batch.delete(&key(i + 100_000));
+        db.write(batch).unwrap();
+    }
+}
+
+fn collect(db: &DB, since: u64) -> Vec<(u64, WriteBatch)> {
+    db.get_updates_since(since)
+        .unwrap()
+        .collect::<mmdb::Result<Vec<_>>>()
+        .unwrap()
+}
+
+fn archived(dir: &Path) -> usize {
+    fs::read_dir(dir.join("archive")).map_or(0, |e| e.count())
+}
+
+/// Flushed WALs are retired after `flush` returns, possibly on a flush
+/// thread; wait for the archive to reach `count` files.
+fn wait_archived(dir: &Path, count: usize) {
+    let deadline = Instant::now() + Duration::from_secs(30);
+    while archived(dir) != count {
+        assert!(
+            Instant::now() < deadline,
+            "archive never reached {count} files"
+        );
+        std::thread::sleep(Duration::from_millis(5));
+    }
+}
+
+#[test]
+fn test_updates_replay_onto_follower() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = DB::open(opts(), dir.path()).unwrap();
+    write_batches(&db, 0, 50);
+
+    let updates = collect(&db, 0);
+    assert_eq!(updates.len(), 50);
+    let mut expected_seq = updates[0].0;
+    for (seq, batch) in &updates {
+        assert_eq!(*seq, expected_seq);
+        expected_seq += batch.len() as u64;
+    }
+    let ops: Vec<WriteBatchOp<'_>> = updates[0].1.iter().collect();
+    assert_eq!(
+        ops,
+        vec![
+            WriteBatchOp::Put {
+                cf: 0,
+                key: &key(0),
+                value: b"value"
+            },
+            WriteBatchOp::Put {
+                cf: 0,
+                key: &key(100_000),
+                value: b"other"
+            },
+            WriteBatchOp::Delete {
+                cf: 0,
+                key: &key(100_000)
+            },
+        ]
+    );
+
+    // A sequence inside a batch starts at that batch.
+    let mid = updates[10].0 + 1;
+    assert_eq!(collect(&db, mid)[0].0, updates[10].0);
+
+    // Tail: only what was committed after the last batch seen.
+    write_batches(&db, 50, 5);
+    let next = expected_seq;
+    let tail = collect(&db, next);
+    assert_eq!(tail.len(), 5);
+    assert_eq!(tail[0].0, next);
+    assert!(collect(&db, db.latest_sequence_number() + 1).is_empty());
+
+    let follower_dir = tempfile::tempdir().unwrap();
+    let follower = DB::open(opts(), follower_dir.path()).unwrap();
+    for (_, batch) in updates.into_iter().chain(tail) {
+        follower.write(batch).unwrap();
+    }
+    for i in 0..55 {
+        assert_eq!(follower.get(&key(i)).unwrap(), Some(b"value".to_vec()));
+        assert_eq!(follower.get(&key(i + 100_000)).unwrap(), None);
+    }
+}
+
+#[test]
+fn test_flushed_updates_need_archiving() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = DB::open(opts(), dir.path()).unwrap();
+    write_batches(&db, 0, 20);
+    db.flush().unwrap();
+    write_batches(&db, 20, 20);
+    db.close().unwrap();
+    let db = DB::open(opts(), dir.path()).unwrap();
+    let Err(err) = db.get_updates_since(1) else {
+        panic!("updates from a deleted WAL were returned");
+    };
+    assert_eq!(err.kind(), ErrorKind::NotFound, "{err}");
+    assert_eq!(archived(dir.path()), 0);
+    drop(db);
+
+    let dir = tempfile::tempdir().unwrap();
+    let options = archiving(3600, 0);
+    let db = DB::open(options.clone(), dir.path()).unwrap();
+    write_batches(&db, 0, 20);
+    db.flush().unwrap();
+    write_batches(&db, 20, 20);
+    wait_archived(dir.path(), 1);
+    assert_eq!(collect(&db, 1).len(), 40);
+    db.close().unwrap();
+
+    // Close flushes the rest; the WAL it retired is archived too.
+    let db = DB::open(options, dir.path()).unwrap();
+    assert!(archived(dir.path()) >= 2);
+    assert_eq!(collect(&db, 1).len(), 40);
+}
+
+#[test]
+fn test_archive_purged_by_size_and_ttl() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = DB::open(archiving(0, 1), dir.path()).unwrap();
+    for round in 0..3 {
+        write_batches(&db, round * 10, 10);
+        db.flush().unwrap();
+    }
+    db.close().unwrap();
+    let db = DB::open(archiving(0, 1), dir.path()).unwrap();
+    // A one-byte budget keeps no WAL with any records in it.
+    let Err(err) = db.get_updates_since(1) else {
+        panic!("updates beyond the archive size limit were returned");
+    };
+    assert_eq!(err.kind(), ErrorKind::NotFound, "{err}");
+    db.close().unwrap();
+
+    let now = || {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64
+    };
+    let clock = Arc::new(ManualClock(AtomicU64::new(now())));
+    let options = DbOptions {
+        clock: clock.clone(),
+        ..archiving(60, 0)
+    };
+    let db = DB::open(options, dir.path()).unwrap();
+    let first = db.latest_sequence_number() + 1;
+    write_batches(&db, 30, 10);
+    db.flush().unwrap();
+    assert_eq!(collect(&db, first).len(), 10);
+
+    clock.0.store(now() + 61_000, Ordering::Relaxed);
+    write_batches(&db, 40, 10);
+    db.flush().unwrap();
+    // Every older WAL expired; the one just archived is fresh.
+    wait_archived(dir.path(), 1);
+    let Err(err) = db.get_updates_since(first) else {
+        panic!("updates from a purged WAL were returned");
+    };
+    assert_eq!(err.kind(), ErrorKind::NotFound, "{err}");
+}
+
+#[test]
+fn test_corrupted_archived_wal_is_reported_as_corruption() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = DB::open(archiving(3600, 0), dir.path()).unwrap();
+    write_batches(&db, 0, 20);
+    db.flush().unwrap();
+    write_batches(&db, 20, 20);
+    wait_archived(dir.path(), 1);
+
+    let wal = fs::read_dir(dir.path().join("archive"))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap()
+        .path();
+    let mut bytes = fs::read(&wal).unwrap();
+    let middle = bytes.len() / 2;
+    bytes[middle] ^= 0xff;
+    fs::write(&wal, bytes).unwrap();
+
+    let results: Vec<_> = db.get_updates_since(1).unwrap().collect();
+    let Some(Err(err)) = results.last() else {
+        panic!("corrupted WAL read without error");
+    };
+    assert_eq!(err.kind(), ErrorKind::Corruption, "{err}");
+    assert!(results.len() < 20);
+}